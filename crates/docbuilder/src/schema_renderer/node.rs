@@ -339,6 +339,10 @@ impl TryFrom<SchemaObject> for SchemaNodeModel {
                                     // Schemars uses `oneOf` with a list of objects to represent an enum.
                                     NodeKind::Enum
                                 }
+                            } else if subschemas.any_of.as_ref().is_some_and(|l| !l.is_empty()) {
+                                // Schemars uses `anyOf` with a list of objects to represent an
+                                // untagged enum.
+                                NodeKind::Enum
                             } else {
                                 // If we don't know what it is, we can't render it.
                                 bail!("Unsupported subschema type:\n{:#?}", subschemas);
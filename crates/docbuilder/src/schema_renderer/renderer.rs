@@ -237,12 +237,14 @@ impl NodeRenderer {
                 .context(format!("Failed to get characteristics for '{id}'",))?,
         );
 
-        let variants = node
+        let subschemas = node
             .object
             .subschemas
-            .context("Node does not contain subschemas")?
+            .context("Node does not contain subschemas")?;
+        let variants = subschemas
             .one_of
-            .context("Node does not contain 'oneOf'")?
+            .or(subschemas.any_of)
+            .context("Node does not contain 'oneOf' or 'anyOf'")?
             .into_iter()
             .enumerate()
             .map(|(index, schema)| {
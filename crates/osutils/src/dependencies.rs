@@ -1,10 +1,12 @@
 use std::{
     borrow::Cow,
+    collections::BTreeMap,
     ffi::{OsStr, OsString},
     io,
     os::unix::process::ExitStatusExt,
     path::PathBuf,
     process::{Command as StdCommand, Output},
+    sync::Mutex,
 };
 
 use log::trace;
@@ -14,6 +16,23 @@ use trident_api::error::{
     ExecutionEnvironmentMisconfigurationError, ServicingError, TridentError, TridentResultExt,
 };
 
+/// Dependencies executed by this process, with the path they were executed from.
+static INVOKED: Mutex<BTreeMap<&'static str, PathBuf>> = Mutex::new(BTreeMap::new());
+
+/// Returns the names of the dependencies executed by this process so far, with the path they were
+/// executed from, sorted by name.
+pub fn invoked() -> Vec<(&'static str, PathBuf)> {
+    INVOKED
+        .lock()
+        .map(|invoked| {
+            invoked
+                .iter()
+                .map(|(name, path)| (*name, path.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum DependencyError {
     #[error("Failed to find dependency '{dependency}': {source}")]
@@ -89,42 +108,67 @@ impl<T> DependencyResultExt<T> for Result<T, Box<DependencyError>> {
 #[strum(serialize_all = "lowercase")]
 pub enum Dependency {
     Blkid,
+    Crictl,
     Cryptsetup,
     Dd,
     Df,
+    Dmesg,
+    Dmsetup,
+    Dnf,
     Dracut,
     E2fsck,
     Efivar,
     Efibootmgr,
     Eject,
     Findmnt,
+    Git,
     Iptables,
     Losetup,
     Lsblk,
     Lsof,
+    Lvcreate,
+    Lvs,
     Mdadm,
+    Memtester,
     Mkdir,
     Mkfs,
+    #[strum(serialize = "mkfs.erofs")]
+    MkfsErofs,
     Mkinitrd,
     Mkswap,
+    Modprobe,
     Mount,
     Mountpoint,
     Netplan,
     Partx,
+    Ping,
+    Podman,
+    Pvcreate,
+    Pvs,
     Resize2fs,
+    Rpm,
+    Semodule,
     Setfiles,
     Sfdisk,
+    Smartctl,
     Swapoff,
     Swapon,
     Systemctl,
+    #[strum(serialize = "systemd-confext")]
+    SystemdConfext,
     #[strum(serialize = "systemd-cryptenroll")]
     SystemdCryptenroll,
+    #[strum(serialize = "systemd-dissect")]
+    SystemdDissect,
     #[strum(serialize = "systemd-firstboot")]
     SystemdFirstboot,
     #[strum(serialize = "systemd-pcrlock")]
     SystemdPcrlock,
     #[strum(serialize = "systemd-repart")]
     SystemdRepart,
+    #[strum(serialize = "systemd-sysext")]
+    SystemdSysext,
+    Tdnf,
     Touch,
     #[strum(serialize = "tpm2_clear")]
     Tpm2Clear,
@@ -135,6 +179,9 @@ pub enum Dependency {
     Umount,
     Uname,
     Veritysetup,
+    Vgchange,
+    Vgcreate,
+    Visudo,
     Wipefs,
     // Test dependencies
     #[cfg(test)]
@@ -283,7 +330,11 @@ impl Command {
     }
 
     pub fn output(&self) -> Result<CommandOutput, Box<DependencyError>> {
-        let mut cmd = StdCommand::new(self.dependency.path()?);
+        let path = self.dependency.path()?;
+        if let Ok(mut invoked) = INVOKED.lock() {
+            invoked.insert(self.dependency.name(), path.clone());
+        }
+        let mut cmd = StdCommand::new(path);
         cmd.args(&self.args);
         cmd.envs(self.envs.clone());
         let rendered_command = self.render_command();
@@ -445,6 +496,25 @@ mod tests {
         assert_eq!(arg_output, "Hello, world\n");
     }
 
+    #[test]
+    fn test_invoked() {
+        Dependency::Echo
+            .cmd()
+            .arg("Hello, world")
+            .run_and_check()
+            .unwrap();
+        Dependency::DoesNotExist.cmd().output().unwrap_err();
+
+        let invoked = invoked();
+        let (_, path) = invoked
+            .iter()
+            .find(|(name, _)| *name == "echo")
+            .expect("echo was invoked");
+        assert_eq!(path, &Dependency::Echo.path().unwrap());
+        // Dependencies that are not found are never executed.
+        assert!(!invoked.iter().any(|(name, _)| *name == "doesnotexist"));
+    }
+
     #[test]
     fn test_nonexistent_dep() {
         let output = Dependency::DoesNotExist.cmd().output().unwrap_err();
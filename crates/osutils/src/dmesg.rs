@@ -0,0 +1,12 @@
+use anyhow::{Context, Error};
+
+use crate::dependencies::Dependency;
+
+/// Returns the messages of the kernel log, one per line, without timestamps.
+pub fn read() -> Result<String, Error> {
+    Dependency::Dmesg
+        .cmd()
+        .arg("--notime")
+        .output_and_check()
+        .context("Failed to read kernel log")
+}
@@ -28,8 +28,15 @@ pub struct GrubMkConfigScript {
     /// GRUB_CMDLINE_LINUX variable.
     new_params: Vec<(OsString, Option<OsString>)>,
 
+    /// Kernel command line parameters that should be removed from the GRUB_CMDLINE_LINUX and
+    /// GRUB_CMDLINE_LINUX_DEFAULT variables.
+    removed_params: Vec<OsString>,
+
     /// Boot device to use for the GRUB_DEVICE variable.
     boot_device: Option<OsString>,
+
+    /// Other variables to set, e.g. GRUB_TIMEOUT.
+    variables: Vec<(String, OsString)>,
 }
 
 impl GrubMkConfigScript {
@@ -89,11 +96,24 @@ impl GrubMkConfigScript {
         self.new_params.push((key.into(), Some(value.into())));
     }
 
+    /// Removes a parameter from the GRUB_CMDLINE_LINUX and GRUB_CMDLINE_LINUX_DEFAULT variables.
+    /// A parameter without a value, e.g. `quiet`, also removes the parameters with the same key
+    /// and any value, e.g. `quiet=1`. The parameter is used as a shell pattern, so it must not
+    /// contain any character that is special to the shell.
+    pub fn remove_param(&mut self, param: impl Into<OsString>) {
+        self.removed_params.push(param.into());
+    }
+
     /// Sets the boot device to use for the GRUB_DEVICE variable.
     pub fn set_boot_device(&mut self, boot_device: impl Into<OsString>) {
         self.boot_device = Some(boot_device.into());
     }
 
+    /// Sets a variable read by grub-mkconfig, e.g. GRUB_TIMEOUT.
+    pub fn set_variable(&mut self, name: impl Into<String>, value: impl Into<OsString>) {
+        self.variables.push((name.into(), value.into()));
+    }
+
     /// Writes the grub-mkconfig script to the filesystem.
     pub fn write(&self) -> Result<(), Error> {
         self.write_inner(&self.file_path())
@@ -138,6 +158,35 @@ impl GrubMkConfigScript {
     fn render(&self) -> OsString {
         let mut conf = OsString::new();
 
+        // Filter the removed parameters out of the command line variables, before appending the
+        // new parameters.
+        if !self.removed_params.is_empty() {
+            let mut patterns = OsString::new();
+            for (i, param) in self.removed_params.iter().enumerate() {
+                if i > 0 {
+                    patterns.push("|");
+                }
+                patterns.push(param);
+                if !param.as_bytes().contains(&b'=') {
+                    patterns.push("|");
+                    patterns.push(param);
+                    patterns.push("=*");
+                }
+            }
+
+            for variable in ["GRUB_CMDLINE_LINUX", "GRUB_CMDLINE_LINUX_DEFAULT"] {
+                conf.push("_trident_cmdline=\n");
+                conf.push(format!("for _trident_param in ${variable}; do\n"));
+                conf.push("    case \"$_trident_param\" in\n        ");
+                conf.push(&patterns);
+                conf.push(") ;;\n");
+                conf.push("        *) _trident_cmdline=\"$_trident_cmdline $_trident_param\" ;;\n");
+                conf.push("    esac\ndone\n");
+                conf.push(format!("{variable}=\"${{_trident_cmdline# }}\"\n"));
+            }
+            conf.push("unset _trident_cmdline _trident_param\n");
+        }
+
         // Append the new parameters to the GRUB_CMDLINE_LINUX variable.
         if !self.new_params.is_empty() {
             conf.push("GRUB_CMDLINE_LINUX=\"$GRUB_CMDLINE_LINUX ");
@@ -171,6 +220,14 @@ impl GrubMkConfigScript {
             conf.push("\"\n");
         }
 
+        // Set the other variables.
+        for (name, value) in &self.variables {
+            conf.push(name);
+            conf.push("=\"");
+            conf.push(value);
+            conf.push("\"\n");
+        }
+
         conf
     }
 }
@@ -231,6 +288,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_variables() {
+        let mut script = GrubMkConfigScript::new("50_my_script");
+        script.set_boot_device("/dev/sda");
+        script.set_variable("GRUB_TIMEOUT", "0");
+        script.set_variable("GRUB_TIMEOUT_STYLE", "hidden");
+
+        let content = script.render();
+        assert_eq!(
+            content,
+            OsString::from(indoc::indoc! {
+                r#"
+                GRUB_DEVICE="/dev/sda"
+                GRUB_TIMEOUT="0"
+                GRUB_TIMEOUT_STYLE="hidden"
+                "#
+            })
+        );
+    }
+
+    #[test]
+    fn test_removed_params() {
+        let mut script = GrubMkConfigScript::new("50_my_script");
+        script.remove_param("quiet");
+        script.remove_param("console=ttyS0");
+        script.add_simple_param("my_param");
+
+        let content = script.render();
+        assert_eq!(
+            content,
+            OsString::from(indoc::indoc! {
+                r#"
+                _trident_cmdline=
+                for _trident_param in $GRUB_CMDLINE_LINUX; do
+                    case "$_trident_param" in
+                        quiet|quiet=*|console=ttyS0) ;;
+                        *) _trident_cmdline="$_trident_cmdline $_trident_param" ;;
+                    esac
+                done
+                GRUB_CMDLINE_LINUX="${_trident_cmdline# }"
+                _trident_cmdline=
+                for _trident_param in $GRUB_CMDLINE_LINUX_DEFAULT; do
+                    case "$_trident_param" in
+                        quiet|quiet=*|console=ttyS0) ;;
+                        *) _trident_cmdline="$_trident_cmdline $_trident_param" ;;
+                    esac
+                done
+                GRUB_CMDLINE_LINUX_DEFAULT="${_trident_cmdline# }"
+                unset _trident_cmdline _trident_param
+                GRUB_CMDLINE_LINUX="$GRUB_CMDLINE_LINUX my_param"
+                "#
+            })
+        );
+    }
+
     #[test]
     fn test_no_params() {
         let script = GrubMkConfigScript::new("50_my_script");
@@ -35,6 +35,7 @@ pub fn detect_boot_type() -> Result<BootType, TridentError> {
 }
 
 /// Ejects the installation media by using the eject command.
+#[cfg_attr(feature = "minimal-dependencies", allow(dead_code))]
 fn eject_media() -> Result<(), Error> {
     info!("Attempting to eject installation media");
     Dependency::Eject
@@ -48,6 +49,10 @@ fn eject_media() -> Result<(), Error> {
 /// Ejects for RAM disk, shows message for live media, and does nothing for persistent storage.
 pub fn handle_installation_media() -> Result<(), TridentError> {
     match detect_boot_type()? {
+        // eject is not used with minimal dependencies.
+        BootType::RamDisk | BootType::LiveMedia if cfg!(feature = "minimal-dependencies") => {
+            info!("Please remove the installation media when the system reboots");
+        }
         BootType::RamDisk => {
             if let Err(e) = eject_media() {
                 warn!("Failed to eject installation media. Please remove the installation media when the system reboots. Ejection error: {e:?}");
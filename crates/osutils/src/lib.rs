@@ -5,6 +5,7 @@ pub mod chroot;
 pub mod container;
 pub mod dependencies;
 pub mod df;
+pub mod dmesg;
 pub mod e2fsck;
 pub mod efibootmgr;
 pub mod efivar;
@@ -19,8 +20,10 @@ pub mod hostname;
 pub mod installation_media;
 pub mod lsblk;
 pub mod lsof;
+pub mod lvm;
 pub mod machine_id;
 pub mod mdadm;
+pub mod memtester;
 pub mod mkfs;
 pub mod mkinitrd;
 pub mod mount;
@@ -29,13 +32,18 @@ pub mod netplan;
 pub mod osmodifier;
 pub mod osrelease;
 pub mod overlay;
+pub mod packages;
 pub mod path;
 pub mod pcrlock;
+pub mod podman;
+pub mod read_only;
 pub mod repart;
 pub mod resize2fs;
 pub mod scripts;
 pub mod sfdisk;
+pub mod smartctl;
 pub mod swap;
+pub mod sysext;
 pub mod systemd;
 pub mod tabfile;
 pub mod tune2fs;
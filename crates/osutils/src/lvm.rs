@@ -0,0 +1,250 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Error};
+use log::trace;
+use serde::Deserialize;
+
+use crate::dependencies::Dependency;
+
+/// Output of an LVM reporting command run with `--reportformat json`.
+#[derive(Deserialize, Debug)]
+struct Report<T> {
+    report: Vec<T>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PvReport {
+    pv: Vec<PhysicalVolume>,
+}
+
+#[derive(Deserialize, Debug)]
+struct LvReport {
+    lv: Vec<LogicalVolume>,
+}
+
+/// Physical volume as returned by `pvs`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PhysicalVolume {
+    /// Path of the device backing the physical volume.
+    pub pv_name: PathBuf,
+
+    /// Name of the volume group the physical volume belongs to, empty if none.
+    pub vg_name: String,
+}
+
+/// Logical volume as returned by `lvs`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LogicalVolume {
+    /// Name of the logical volume.
+    pub lv_name: String,
+
+    /// Name of the volume group the logical volume belongs to.
+    pub vg_name: String,
+}
+
+/// Size of a logical volume to create.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogicalVolumeSize {
+    /// Fixed size, in bytes.
+    Bytes(u64),
+
+    /// All the free space left in the volume group.
+    Free,
+}
+
+/// Kind of logical volume to create.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogicalVolumeKind<'a> {
+    /// Linear logical volume, allocated directly from the volume group.
+    Linear,
+
+    /// Thin pool, from which thin logical volumes are allocated.
+    ThinPool,
+
+    /// Thin logical volume, allocated from the given thin pool.
+    Thin { thin_pool: &'a str },
+}
+
+/// Lists the physical volumes known to LVM.
+pub fn list_physical_volumes() -> Result<Vec<PhysicalVolume>, Error> {
+    let output = Dependency::Pvs
+        .cmd()
+        .arg("--reportformat")
+        .arg("json")
+        .arg("--options")
+        .arg("pv_name,vg_name")
+        .output_and_check()
+        .context("Failed to execute pvs")?;
+
+    let parsed: Report<PvReport> =
+        serde_json::from_str(&output).context("Failed to parse pvs output")?;
+    Ok(parsed.report.into_iter().flat_map(|r| r.pv).collect())
+}
+
+/// Lists the logical volumes of the volume group `vg_name`.
+pub fn list_logical_volumes(vg_name: &str) -> Result<Vec<LogicalVolume>, Error> {
+    let output = Dependency::Lvs
+        .cmd()
+        .arg("--reportformat")
+        .arg("json")
+        .arg("--options")
+        .arg("lv_name,vg_name")
+        .arg(vg_name)
+        .output_and_check()
+        .context("Failed to execute lvs")?;
+
+    let parsed: Report<LvReport> =
+        serde_json::from_str(&output).context("Failed to parse lvs output")?;
+    Ok(parsed.report.into_iter().flat_map(|r| r.lv).collect())
+}
+
+/// Initializes `devices` as physical volumes, wiping any previous signature.
+pub fn create_physical_volumes(devices: &[PathBuf]) -> Result<(), Error> {
+    trace!("Creating physical volumes on {devices:?}");
+    Dependency::Pvcreate
+        .cmd()
+        .arg("--force")
+        .arg("--yes")
+        .args(devices)
+        .run_and_check()
+        .context("Failed to run pvcreate")
+}
+
+/// Creates the volume group `vg_name` on the physical volumes `devices`.
+pub fn create_volume_group(vg_name: &str, devices: &[PathBuf]) -> Result<(), Error> {
+    trace!("Creating volume group '{vg_name}' on {devices:?}");
+    Dependency::Vgcreate
+        .cmd()
+        .arg(vg_name)
+        .args(devices)
+        .run_and_check()
+        .context("Failed to run vgcreate")
+}
+
+/// Activates or deactivates all the logical volumes of the volume group `vg_name`.
+pub fn set_volume_group_active(vg_name: &str, active: bool) -> Result<(), Error> {
+    trace!(
+        "{} volume group '{vg_name}'",
+        if active { "Activating" } else { "Deactivating" }
+    );
+    Dependency::Vgchange
+        .cmd()
+        .arg("--activate")
+        .arg(if active { "y" } else { "n" })
+        .arg(vg_name)
+        .run_and_check()
+        .context("Failed to run vgchange")
+}
+
+/// Creates the logical volume `lv_name` of the given kind and size in the volume group
+/// `vg_name`.
+pub fn create_logical_volume(
+    vg_name: &str,
+    lv_name: &str,
+    kind: LogicalVolumeKind,
+    size: LogicalVolumeSize,
+) -> Result<(), Error> {
+    trace!("Creating logical volume '{lv_name}' in volume group '{vg_name}'");
+    let mut cmd = Dependency::Lvcreate.cmd();
+    cmd.arg("--yes")
+        .arg("--wipesignatures")
+        .arg("y")
+        .arg("--name")
+        .arg(lv_name);
+
+    match kind {
+        LogicalVolumeKind::Linear => {}
+        LogicalVolumeKind::ThinPool => {
+            cmd.arg("--type").arg("thin-pool");
+        }
+        LogicalVolumeKind::Thin { thin_pool } => {
+            cmd.arg("--type")
+                .arg("thin")
+                .arg("--thinpool")
+                .arg(thin_pool);
+        }
+    }
+
+    match (kind, size) {
+        (LogicalVolumeKind::Thin { .. }, LogicalVolumeSize::Bytes(bytes)) => {
+            cmd.arg("--virtualsize").arg(format!("{bytes}b"));
+        }
+        (LogicalVolumeKind::Thin { .. }, LogicalVolumeSize::Free) => {
+            bail!("Thin logical volume '{lv_name}' must have a fixed size")
+        }
+        (_, LogicalVolumeSize::Bytes(bytes)) => {
+            cmd.arg("--size").arg(format!("{bytes}b"));
+        }
+        (_, LogicalVolumeSize::Free) => {
+            cmd.arg("--extents").arg("100%FREE");
+        }
+    }
+
+    cmd.arg(vg_name)
+        .run_and_check()
+        .context("Failed to run lvcreate")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pvs_output() {
+        let output = indoc::indoc!(
+            r#"
+            {
+                "report": [
+                    {
+                        "pv": [
+                            {"pv_name":"/dev/sda3", "vg_name":"data"},
+                            {"pv_name":"/dev/sdb1", "vg_name":""}
+                        ]
+                    }
+                ]
+            }
+            "#
+        );
+        let parsed: Report<PvReport> = serde_json::from_str(output).unwrap();
+        let pvs: Vec<_> = parsed.report.into_iter().flat_map(|r| r.pv).collect();
+        assert_eq!(
+            pvs,
+            vec![
+                PhysicalVolume {
+                    pv_name: PathBuf::from("/dev/sda3"),
+                    vg_name: "data".into(),
+                },
+                PhysicalVolume {
+                    pv_name: PathBuf::from("/dev/sdb1"),
+                    vg_name: "".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_lvs_output() {
+        let output = indoc::indoc!(
+            r#"
+            {
+                "report": [
+                    {
+                        "lv": [
+                            {"lv_name":"pool", "vg_name":"data"},
+                            {"lv_name":"var", "vg_name":"data"}
+                        ]
+                    }
+                ]
+            }
+            "#
+        );
+        let parsed: Report<LvReport> = serde_json::from_str(output).unwrap();
+        let names: Vec<_> = parsed
+            .report
+            .into_iter()
+            .flat_map(|r| r.lv)
+            .map(|lv| lv.lv_name)
+            .collect();
+        assert_eq!(names, vec!["pool", "var"]);
+    }
+}
@@ -0,0 +1,15 @@
+use anyhow::{Context, Error};
+use log::debug;
+
+use crate::dependencies::Dependency;
+
+/// Tests the given amount of memory, in MiB, with a single pass of `memtester`.
+pub fn test(size_mib: u64) -> Result<(), Error> {
+    debug!("Testing {size_mib} MiB of memory");
+    Dependency::Memtester
+        .cmd()
+        .arg(format!("{size_mib}M"))
+        .arg("1")
+        .run_and_check()
+        .with_context(|| format!("Memory test of {size_mib} MiB failed"))
+}
@@ -14,6 +14,27 @@ pub fn run(device_path: &Path, filesystem: MkfsFileSystemType) -> Result<(), Err
         .context("Failed to execute mkfs")
 }
 
+/// Builds an uncompressed EROFS image at `image_path` from the directory tree at `source_dir`.
+/// The image is reproducible: all files are owned by root, and all timestamps and the UUID of the
+/// filesystem are fixed, so the same tree always results in the same image.
+pub fn erofs_image(source_dir: &Path, image_path: &Path) -> Result<(), Error> {
+    Dependency::MkfsErofs
+        .cmd()
+        .arg("--all-root")
+        .arg("-T0")
+        .arg("-U")
+        .arg("00000000-0000-0000-0000-000000000000")
+        .arg(image_path)
+        .arg(source_dir)
+        .run_and_check()
+        .with_context(|| {
+            format!(
+                "Failed to build EROFS image from '{}'",
+                source_dir.display()
+            )
+        })
+}
+
 #[cfg(feature = "functional-test")]
 #[cfg_attr(not(test), allow(unused_imports, dead_code))]
 /// Helper function to create a filesystem that is smaller than the full device size
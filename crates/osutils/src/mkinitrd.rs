@@ -1,6 +1,7 @@
 use std::{io::Write, os::unix::fs::PermissionsExt, path::Path};
 
 use anyhow::{Context, Error};
+use log::{info, warn};
 use tempfile::NamedTempFile;
 
 use trident_api::error::{ReportError, ServicingError, TridentError};
@@ -40,23 +41,56 @@ EOF
 fi
 "#;
 
+/// Returns whether the initrd of the running OS is regenerated with mkinitrd rather than dracut.
+/// To be called in the target OS, e.g. in its chroot.
+pub fn uses_mkinitrd() -> bool {
+    Path::new("/usr/bin/mkinitrd").exists()
+}
+
 /// Generate a new initrd image using either mkinitrd or dracut.
 ///
 /// If mkinitrd is available, it will be used. Azl 3.0 doesn't have mkinitrd anymore, so dracut is
 /// used instead.
-pub fn execute(debug: bool) -> Result<(), TridentError> {
-    if Path::new("/usr/bin/mkinitrd").exists() {
+///
+/// `includes` are directories copied into the initrd at the same path, and `modules` are dracut
+/// modules added to the initrd. They are only supported with dracut.
+///
+/// mkinitrd picks the modules of the initrd from the configuration of the image, as it did before
+/// Trident added any, so images using it must ship the modules they need, e.g. for encrypted
+/// volumes or LVM volume groups.
+pub fn execute(debug: bool, includes: &[&Path], modules: &[&str]) -> Result<(), TridentError> {
+    if uses_mkinitrd() {
+        if !modules.is_empty() {
+            info!(
+                "Relying on the mkinitrd configuration of the image for dracut modules {}",
+                modules
+                    .iter()
+                    .map(|module| format!("'{module}'"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        if !includes.is_empty() {
+            warn!(
+                "Not including {} in the initrd, as mkinitrd does not support it",
+                includes
+                    .iter()
+                    .map(|path| format!("'{}'", path.display()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
         Dependency::Mkinitrd
             .cmd()
             .run_and_check()
             .structured(ServicingError::RegenerateInitrd)
     } else {
-        run_dracut(debug).structured(ServicingError::RegenerateInitrd)
+        run_dracut(debug, includes, modules).structured(ServicingError::RegenerateInitrd)
     }
 }
 
 /// Wrapper around dracut to regenerate the initrd with specific options
-fn run_dracut(debug: bool) -> Result<(), Error> {
+fn run_dracut(debug: bool, includes: &[&Path], modules: &[&str]) -> Result<(), Error> {
     // Create a temp file
     let mut script = NamedTempFile::new().context("Failed to create temporary file")?;
     // Write the worakround script to the temp file
@@ -77,6 +111,14 @@ fn run_dracut(debug: bool) -> Result<(), Error> {
         cmd.arg("--debug").arg("-L").arg("6");
     }
 
+    for include in includes {
+        cmd.arg("--include").arg(include).arg(include);
+    }
+
+    if !modules.is_empty() {
+        cmd.arg("--add").arg(modules.join(" "));
+    }
+
     cmd.arg("--regenerate-all")
         .arg("--zstd")
         .arg("--include")
@@ -112,7 +154,7 @@ mod functional_test {
             std::fs::remove_file(initrd_path.as_ref().unwrap()).unwrap();
         }
 
-        execute(false).unwrap();
+        execute(false, &[], &[]).unwrap();
 
         // Some initrd should have been created
         let initrd_path = glob::glob(pattern).unwrap().next();
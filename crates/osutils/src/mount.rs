@@ -51,12 +51,12 @@ pub fn umount(mount_dir: impl AsRef<Path>, recursive: bool) -> Result<(), Error>
     // Try to unmount the directory
     if let Err(e) = cmd.arg(mount_dir.as_ref()).run_and_check() {
         // If umount returns an error, do best effort to log open files while ignoring failures,
-        // such as missing external dependency
-        let opened_process_files = lsof::run(mount_dir.as_ref());
-
-        if let Ok(opened_process_files) = opened_process_files {
-            if !opened_process_files.is_empty() {
-                error!("Open files: {:?}", opened_process_files);
+        // such as missing external dependency. lsof is not used with minimal dependencies.
+        if !cfg!(feature = "minimal-dependencies") {
+            if let Ok(opened_process_files) = lsof::run(mount_dir.as_ref()) {
+                if !opened_process_files.is_empty() {
+                    error!("Open files: {:?}", opened_process_files);
+                }
             }
         }
 
@@ -28,6 +28,8 @@ pub struct OsRelease {
     pub version: Option<String>,
     pub version_id: Option<String>,
     pub pretty_name: Option<String>,
+    pub sysext_level: Option<String>,
+    pub confext_level: Option<String>,
 }
 
 impl OsRelease {
@@ -100,6 +102,8 @@ impl OsRelease {
                 "VERSION" => os_release.version = value(),
                 "VERSION_ID" => os_release.version_id = value(),
                 "PRETTY_NAME" => os_release.pretty_name = value(),
+                "SYSEXT_LEVEL" => os_release.sysext_level = value(),
+                "CONFEXT_LEVEL" => os_release.confext_level = value(),
                 _ => {}
             }
         }
@@ -123,6 +127,12 @@ impl<'de> Deserialize<'de> for OsRelease {
 pub struct ExtensionRelease {
     pub sysext_id: Option<String>,
     pub confext_id: Option<String>,
+    pub architecture: Option<String>,
+    /// Version of the extension itself, from SYSEXT_VERSION_ID or CONFEXT_VERSION_ID.
+    pub version_id: Option<String>,
+    /// Kinds of systems the extension is meant for, from SYSEXT_SCOPE or CONFEXT_SCOPE, e.g.
+    /// `system portable`.
+    pub scope: Option<String>,
     pub os_release: OsRelease,
 }
 
@@ -138,6 +148,9 @@ impl ExtensionRelease {
     fn parse(data: &str) -> Self {
         let mut sysext_id = None;
         let mut confext_id = None;
+        let mut architecture = None;
+        let mut version_id = None;
+        let mut scope = None;
 
         for line in data.lines() {
             if line.is_empty() || line.trim_start().starts_with('#') {
@@ -163,6 +176,9 @@ impl ExtensionRelease {
             match key {
                 "SYSEXT_ID" => sysext_id = value(),
                 "CONFEXT_ID" => confext_id = value(),
+                "ARCHITECTURE" => architecture = value(),
+                "SYSEXT_VERSION_ID" | "CONFEXT_VERSION_ID" => version_id = value(),
+                "SYSEXT_SCOPE" | "CONFEXT_SCOPE" => scope = value(),
                 _ => {}
             }
         }
@@ -170,6 +186,9 @@ impl ExtensionRelease {
         Self {
             sysext_id,
             confext_id,
+            architecture,
+            version_id,
+            scope,
             os_release: OsRelease::parse(data),
         }
     }
@@ -281,6 +300,8 @@ mod tests {
             ID=_any
             SYSEXT_ID=docker
             SYSEXT_VERSION_ID=28.0.4
+            SYSEXT_LEVEL=1.0
+            SYSEXT_SCOPE="system portable"
             ARCHITECTURE=x86-64
             "#,
         };
@@ -289,6 +310,13 @@ mod tests {
 
         assert_eq!(extension_release.sysext_id, Some("docker".to_string()));
         assert_eq!(extension_release.confext_id, None);
+        assert_eq!(extension_release.architecture, Some("x86-64".to_string()));
+        assert_eq!(extension_release.version_id, Some("28.0.4".to_string()));
+        assert_eq!(extension_release.scope, Some("system portable".to_string()));
         assert_eq!(extension_release.os_release.id, Some("_any".to_string()));
+        assert_eq!(
+            extension_release.os_release.sysext_level,
+            Some("1.0".to_string())
+        );
     }
 }
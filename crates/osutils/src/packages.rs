@@ -0,0 +1,89 @@
+use std::path::Path;
+
+use anyhow::{Context, Error};
+use log::trace;
+
+use crate::dependencies::Dependency;
+
+/// ID of the repository created from a local path.
+const LOCAL_REPOSITORY_ID: &str = "trident-local";
+
+/// Package manager of an RPM-based OS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageManager {
+    Tdnf,
+    Dnf,
+}
+
+impl PackageManager {
+    /// Returns the package manager installed on the system, preferring tdnf over dnf.
+    pub fn detect() -> Option<Self> {
+        [Self::Tdnf, Self::Dnf]
+            .into_iter()
+            .find(|package_manager| package_manager.dependency().exists())
+    }
+
+    fn dependency(self) -> Dependency {
+        match self {
+            Self::Tdnf => Dependency::Tdnf,
+            Self::Dnf => Dependency::Dnf,
+        }
+    }
+
+    /// Installs `packages`. When `repository` is set, packages are only installed from the local
+    /// repository at that path.
+    pub fn install(self, packages: &[String], repository: Option<&Path>) -> Result<(), Error> {
+        trace!(
+            "Installing packages {packages:?} with {}",
+            self.dependency()
+        );
+        self.dependency()
+            .cmd()
+            .args(install_args(packages, repository))
+            .run_and_check()
+            .with_context(|| format!("Failed to run {}", self.dependency()))
+    }
+}
+
+/// Returns the arguments of the install command of tdnf and dnf, which share the same syntax.
+fn install_args(packages: &[String], repository: Option<&Path>) -> Vec<String> {
+    let mut args = vec!["install".to_string(), "--assumeyes".to_string()];
+    if let Some(repository) = repository {
+        args.extend([
+            "--disablerepo=*".to_string(),
+            format!(
+                "--repofrompath={LOCAL_REPOSITORY_ID},{}",
+                repository.display()
+            ),
+            format!("--enablerepo={LOCAL_REPOSITORY_ID}"),
+        ]);
+    }
+    args.extend(packages.iter().cloned());
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_args() {
+        let packages = vec!["tmux".to_string(), "vim-enhanced".to_string()];
+        assert_eq!(
+            install_args(&packages, None),
+            vec!["install", "--assumeyes", "tmux", "vim-enhanced"]
+        );
+        assert_eq!(
+            install_args(&packages, Some(Path::new("/run/repo"))),
+            vec![
+                "install",
+                "--assumeyes",
+                "--disablerepo=*",
+                "--repofrompath=trident-local,/run/repo",
+                "--enablerepo=trident-local",
+                "tmux",
+                "vim-enhanced"
+            ]
+        );
+    }
+}
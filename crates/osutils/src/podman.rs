@@ -0,0 +1,110 @@
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{Context, Error};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::trace;
+use serde_json::json;
+
+use crate::dependencies::{Command, Dependency};
+
+/// Returns the podman command, with events disabled as podman may run without systemd-journald,
+/// e.g. in the chroot of the target OS.
+fn podman() -> Command {
+    Dependency::Podman.cmd().with_arg("--events-backend=none")
+}
+
+/// Returns whether the image `image` exists in the containers-storage.
+pub fn image_exists(image: &str) -> Result<bool, Error> {
+    let output = podman()
+        .args(["image", "exists", image])
+        .output()
+        .context("Failed to run podman")?;
+    // `podman image exists` exits with 1 when the image does not exist.
+    if output.code() == Some(1) {
+        return Ok(false);
+    }
+    output
+        .check()
+        .with_context(|| format!("Failed to check whether image '{image}' exists"))?;
+    Ok(true)
+}
+
+/// Pulls the image `image` from its registry, authenticating with the credentials of the
+/// `auth_file` if any.
+pub fn pull_image(image: &str, auth_file: Option<&Path>) -> Result<(), Error> {
+    trace!("Pulling image '{image}'");
+    let mut cmd = podman();
+    cmd.args(["pull", "--quiet"]);
+    if let Some(auth_file) = auth_file {
+        cmd.arg("--authfile").arg(auth_file);
+    }
+    cmd.arg(image)
+        .run_and_check()
+        .with_context(|| format!("Failed to pull image '{image}'"))
+}
+
+/// Returns the registry of the image `image`, following the rules of containers-registries:
+/// the first component of the reference is a registry if it contains a '.' or a ':', or is
+/// `localhost`, and the image is otherwise on Docker Hub.
+pub fn registry_of(image: &str) -> &str {
+    match image.split_once('/') {
+        Some((first, _)) if first.contains(['.', ':']) || first == "localhost" => first,
+        _ => "docker.io",
+    }
+}
+
+/// Renders an auth file in the format of containers-auth.json, holding the username and password
+/// to use for each registry of `credentials`.
+///
+/// Credentials are passed to podman through a file, as command lines end up in the logs.
+pub fn render_auth_file<'a>(
+    credentials: impl IntoIterator<Item = (&'a str, &'a str, &'a str)>,
+) -> String {
+    let auths = credentials
+        .into_iter()
+        .map(|(registry, username, password)| {
+            (
+                registry,
+                json!({ "auth": STANDARD.encode(format!("{username}:{password}")) }),
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+    json!({ "auths": auths }).to_string()
+}
+
+/// Loads the images of the docker-archive or oci-archive at `archive`.
+pub fn load_images(archive: &Path) -> Result<(), Error> {
+    trace!("Loading images from '{}'", archive.display());
+    podman()
+        .args(["load", "--quiet", "--input"])
+        .arg(archive)
+        .run_and_check()
+        .with_context(|| format!("Failed to load images from '{}'", archive.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_of() {
+        assert_eq!(
+            registry_of("registry.example.com/app:1.0"),
+            "registry.example.com"
+        );
+        assert_eq!(registry_of("localhost:5000/app"), "localhost:5000");
+        assert_eq!(registry_of("localhost/app"), "localhost");
+        assert_eq!(registry_of("library/nginx"), "docker.io");
+        assert_eq!(registry_of("nginx:latest"), "docker.io");
+    }
+
+    #[test]
+    fn test_render_auth_file() {
+        let rendered = render_auth_file([("registry.example.com", "robot", "token")]);
+        let parsed: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(
+            parsed,
+            json!({ "auths": { "registry.example.com": { "auth": "cm9ib3Q6dG9rZW4=" } } })
+        );
+    }
+}
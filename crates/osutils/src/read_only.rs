@@ -0,0 +1,16 @@
+//! Subset of osutils that only queries the host and never modifies it.
+//!
+//! Callers that must not change the system, such as `trident inspect`, import osutils through
+//! this module only. A function is only re-exported here if it cannot modify the host. This is a
+//! convention rather than a guarantee: such callers can still reach the rest of osutils, so
+//! importing anything else from them must be caught in review.
+
+/// Read-only queries of block devices.
+pub mod lsblk {
+    pub use crate::lsblk::{list, BlockDevice};
+}
+
+/// Read-only queries of UEFI boot entries.
+pub mod efibootmgr {
+    pub use crate::efibootmgr::list_and_parse_bootmgr_entries;
+}
@@ -3,11 +3,18 @@ use std::{
     ffi::OsStr,
     fs::File,
     io::{Read, Seek, SeekFrom, Write},
+    os::unix::process::CommandExt,
     path::{Path, PathBuf},
-    process::{Command, ExitStatus},
+    process::{Child, Command, ExitStatus, Stdio},
+    thread,
+    time::{Duration, Instant},
 };
 
-use anyhow::{Context, Error};
+use anyhow::{bail, Context, Error};
+use nix::{
+    sys::signal::{self, Signal},
+    unistd::Pid,
+};
 use tempfile::NamedTempFile;
 
 use crate::{crate_private::Sealed, exe::OutputChecker};
@@ -23,6 +30,9 @@ pub fn run_bash_script(script: &str) -> Result<(), Error> {
         .check()
 }
 
+/// Interval at which a script with a timeout is polled for its exit.
+const TIMEOUT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 /// Output of a script
 #[derive(Debug)]
 pub struct ScriptResult {
@@ -74,6 +84,9 @@ pub struct ScriptRunner<'a> {
     pub env_vars: HashMap<&'a OsStr, &'a OsStr>,
     /// Arguments to pass to the script
     pub args: Vec<&'a OsStr>,
+    /// Maximum time the script may run, after which it is killed along with all the processes it
+    /// started
+    pub timeout: Option<Duration>,
 }
 
 impl<'a> ScriptRunner<'a> {
@@ -84,6 +97,7 @@ impl<'a> ScriptRunner<'a> {
             script,
             env_vars: HashMap::new(),
             args: Vec::new(),
+            timeout: None,
         }
     }
 
@@ -112,6 +126,11 @@ impl<'a> ScriptRunner<'a> {
         self
     }
 
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
     fn run_internal(&mut self) -> Result<ScriptResult, Error> {
         // TODO: consider changing internal implementation to use duct crate
 
@@ -139,10 +158,20 @@ impl<'a> ScriptRunner<'a> {
         let mut file = tempfile::tempfile().context("Failed to create log file")?;
 
         // Set the command to output to the logfile
+        cmd.stdin(Stdio::null());
         cmd.stdout(file.try_clone().context("Failed to redirect stdout")?);
         cmd.stderr(file.try_clone().context("Failed to redirect stderr")?);
 
-        let cmd_output = cmd.output().context("Failed to start script")?;
+        // A script with a timeout runs in its own process group, so that the processes it started
+        // are killed along with it.
+        if self.timeout.is_some() {
+            cmd.process_group(0);
+        }
+        let mut child = cmd.spawn().context("Failed to start script")?;
+        let status = match self.timeout {
+            Some(timeout) => wait_with_timeout(&mut child, timeout)?,
+            None => Some(child.wait().context("Failed to wait for script")?),
+        };
 
         file.flush().context("Failed to flush logfile")?;
         file.seek(SeekFrom::Start(0))
@@ -150,12 +179,15 @@ impl<'a> ScriptRunner<'a> {
         let mut buf = Vec::new();
         file.read_to_end(&mut buf)
             .context("Failed to read logfile")?;
-        let output = String::from_utf8_lossy(&buf).into();
-
-        Ok(ScriptResult {
-            status: cmd_output.status,
-            output,
-        })
+        let output: String = String::from_utf8_lossy(&buf).into();
+
+        let Some(status) = status else {
+            bail!(
+                "Script timed out after {} seconds and was killed, output:\n{output}",
+                self.timeout.unwrap_or_default().as_secs()
+            );
+        };
+        Ok(ScriptResult { status, output })
     }
 
     /// Run the script and get the output
@@ -181,6 +213,24 @@ impl<'a> ScriptRunner<'a> {
     }
 }
 
+/// Waits for `child` to exit for at most `timeout`. Returns `None` if the timeout is reached, in
+/// which case the process group of `child` is killed.
+fn wait_with_timeout(child: &mut Child, timeout: Duration) -> Result<Option<ExitStatus>, Error> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait().context("Failed to wait for script")? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            signal::killpg(Pid::from_raw(child.id() as i32), Signal::SIGKILL)
+                .context("Failed to kill script")?;
+            child.wait().context("Failed to wait for killed script")?;
+            return Ok(None);
+        }
+        thread::sleep(TIMEOUT_POLL_INTERVAL);
+    }
+}
+
 /// Writes a script to a temporary UNNAMED file and returns the File.
 fn write_script_to_file(script_body: &str) -> Result<File, Error> {
     let mut script_file =
@@ -205,3 +255,28 @@ fn write_script_to_named_file(script_body: &[u8]) -> Result<NamedTempFile, Error
 
     Ok(script_file)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeout() {
+        ScriptRunner::new_bash(b"exit 0")
+            .with_timeout(Duration::from_secs(10))
+            .run_check()
+            .unwrap();
+
+        // The script and the processes it started are killed once the timeout is reached.
+        let start = Instant::now();
+        let error = ScriptRunner::new_bash(b"echo started; sleep 30 & wait")
+            .with_timeout(Duration::from_millis(200))
+            .run()
+            .unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(
+            error.to_string(),
+            "Script timed out after 0 seconds and was killed, output:\nstarted\n"
+        );
+    }
+}
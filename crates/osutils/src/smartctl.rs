@@ -0,0 +1,180 @@
+use std::path::Path;
+
+use anyhow::{bail, Context, Error};
+use log::{debug, warn};
+
+use crate::dependencies::Dependency;
+
+/// Bit of the exit status of `smartctl` reporting that its command line could not be parsed.
+const EXIT_INVALID_COMMAND_LINE: i32 = 1;
+
+/// Bits of the exit status of `smartctl` reporting that the disk could not be queried: the device
+/// could not be opened, or a SMART command failed, e.g. because the disk does not support SMART,
+/// like virtio disks.
+const EXIT_QUERY_FAILED: i32 = 0b110;
+
+/// Bit of the exit status of `smartctl` reporting that the overall self-assessment of the disk
+/// failed.
+const EXIT_DISK_FAILING: i32 = 1 << 3;
+
+/// Bit of the exit status of `smartctl` reporting prefail attributes at or below their threshold.
+const EXIT_PREFAIL_ATTRIBUTES: i32 = 1 << 4;
+
+/// Bit of the exit status of `smartctl` reporting attributes that were at or below their
+/// threshold in the past.
+const EXIT_PAST_FAILURES: i32 = 1 << 5;
+
+/// Health of a disk, as reported by SMART.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmartHealth {
+    /// Whether the disk is failing: its overall self-assessment failed, or prefail attributes are
+    /// at or below their threshold.
+    pub failing: bool,
+
+    /// Whether attributes were at or below their threshold in the past, though they no longer
+    /// are.
+    pub past_failures: bool,
+
+    /// Attributes that are or were at or below their threshold, along with when they failed,
+    /// e.g. `Reallocated_Sector_Ct (FAILING_NOW)`.
+    pub failed_attributes: Vec<String>,
+}
+
+/// Runs a SMART short self-test on the given disk and checks its overall health. Fails only if
+/// the disk is failing. Returns whether the disk was tested: disks that cannot be queried, e.g.
+/// because they do not support SMART, are skipped.
+///
+/// The self-test runs in captive mode, so this blocks until the test completes, which usually
+/// takes a couple of minutes.
+pub fn short_self_test(device: impl AsRef<Path>) -> Result<bool, Error> {
+    let device = device.as_ref();
+
+    debug!("Running SMART short self-test on '{}'", device.display());
+    if run(device, &["--test=short", "--captive"])
+        .with_context(|| format!("SMART short self-test failed on '{}'", device.display()))?
+        .is_none()
+    {
+        return Ok(false);
+    }
+
+    let Some((code, output)) = run(device, &["--health"])
+        .with_context(|| format!("SMART health check failed on '{}'", device.display()))?
+    else {
+        return Ok(false);
+    };
+    if code & (EXIT_DISK_FAILING | EXIT_PREFAIL_ATTRIBUTES) != 0 {
+        bail!(
+            "Disk '{}' is failing according to SMART:\n{output}",
+            device.display()
+        );
+    }
+    Ok(true)
+}
+
+/// Runs `smartctl` with `args` on `device`. Returns its exit status and output, or `None` if the
+/// disk could not be queried. The other bits of the exit status are left to the caller.
+fn run(device: &Path, args: &[&str]) -> Result<Option<(i32, String)>, Error> {
+    let output = Dependency::Smartctl
+        .cmd()
+        .args(args)
+        .arg(device)
+        .output()
+        .context("Failed to run smartctl")?;
+    let Some(code) = output.code() else {
+        bail!("smartctl was terminated by a signal");
+    };
+    if code & EXIT_INVALID_COMMAND_LINE != 0 {
+        bail!(
+            "smartctl rejected its command line:\n{}",
+            output.output_report()
+        );
+    }
+    if code & EXIT_QUERY_FAILED != 0 {
+        warn!(
+            "Disk '{}' cannot be queried with SMART, exit status {code}:\n{}",
+            device.display(),
+            output.output_report()
+        );
+        return Ok(None);
+    }
+    Ok(Some((code, output.output())))
+}
+
+/// Queries the overall health and the attributes of the given disk. Returns `None` if the disk
+/// cannot be queried, e.g. because it does not support SMART.
+pub fn health(device: impl AsRef<Path>) -> Result<Option<SmartHealth>, Error> {
+    let device = device.as_ref();
+
+    debug!("Querying SMART health of '{}'", device.display());
+    Ok(run(device, &["--health", "--attributes"])
+        .with_context(|| format!("Failed to query SMART health of '{}'", device.display()))?
+        .map(|(code, output)| assess_health(code, &output)))
+}
+
+/// Returns the health of a disk given `code`, the exit status of `smartctl --health
+/// --attributes`, and `output`, its output.
+fn assess_health(code: i32, output: &str) -> SmartHealth {
+    // ID# ATTRIBUTE_NAME FLAG VALUE WORST THRESH TYPE UPDATED WHEN_FAILED RAW_VALUE
+    let failed_attributes = output
+        .lines()
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            fields.first()?.parse::<u8>().ok()?;
+            if !fields.get(2)?.starts_with("0x") {
+                return None;
+            }
+            let when_failed = *fields.get(8)?;
+            (when_failed != "-").then(|| format!("{} ({when_failed})", fields[1]))
+        })
+        .collect();
+
+    SmartHealth {
+        failing: code & (EXIT_DISK_FAILING | EXIT_PREFAIL_ATTRIBUTES) != 0,
+        past_failures: code & EXIT_PAST_FAILURES != 0,
+        failed_attributes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ATTRIBUTES: &str = "\
+=== START OF READ SMART DATA SECTION ===
+SMART overall-health self-assessment test result: PASSED
+
+SMART Attributes Data Structure revision number: 16
+Vendor Specific SMART Attributes with Thresholds:
+ID# ATTRIBUTE_NAME          FLAG     VALUE WORST THRESH TYPE      UPDATED  WHEN_FAILED RAW_VALUE
+  1 Raw_Read_Error_Rate     0x002f   200   200   051    Pre-fail  Always       -       0
+  5 Reallocated_Sector_Ct   0x0033   005   005   010    Pre-fail  Always   FAILING_NOW 1950
+196 Reallocated_Event_Count 0x0032   100   100   000    Old_age   Always   In_the_past 12
+";
+
+    #[test]
+    fn test_assess_health() {
+        assert_eq!(
+            assess_health(
+                0,
+                "SMART overall-health self-assessment test result: PASSED\n"
+            ),
+            SmartHealth {
+                failing: false,
+                past_failures: false,
+                failed_attributes: vec![],
+            }
+        );
+        assert_eq!(
+            assess_health(EXIT_PREFAIL_ATTRIBUTES | EXIT_PAST_FAILURES, ATTRIBUTES),
+            SmartHealth {
+                failing: true,
+                past_failures: true,
+                failed_attributes: vec![
+                    "Reallocated_Sector_Ct (FAILING_NOW)".into(),
+                    "Reallocated_Event_Count (In_the_past)".into(),
+                ],
+            }
+        );
+        assert!(assess_health(EXIT_DISK_FAILING, "").failing);
+    }
+}
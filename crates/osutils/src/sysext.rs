@@ -0,0 +1,372 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error};
+use log::{debug, error, warn};
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+
+use trident_api::{
+    config::ExtensionMutability,
+    error::{ExtensionRefreshError, ReportError, ServicingError, TridentError, TridentResultExt},
+};
+
+use crate::dependencies::Dependency;
+
+/// Image policy requiring the root or /usr partition of an image to be protected by a signed
+/// dm-verity root hash.
+const SIGNED_IMAGE_POLICY: &str = "root=signed+absent:usr=signed+absent";
+
+/// Kind of extension images merged by systemd.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExtensionKind {
+    Sysext,
+    Confext,
+}
+
+impl Display for ExtensionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sysext => write!(f, "sysext"),
+            Self::Confext => write!(f, "confext"),
+        }
+    }
+}
+
+impl ExtensionKind {
+    fn dependency(&self) -> Dependency {
+        match self {
+            Self::Sysext => Dependency::SystemdSysext,
+            Self::Confext => Dependency::SystemdConfext,
+        }
+    }
+}
+
+/// Extension image found by systemd-sysext or systemd-confext, as reported by `list`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct ListedImage {
+    /// Name of the image, i.e. its file name without the `.raw` suffix.
+    pub name: String,
+
+    /// Type of the image, e.g. `raw` or `directory`.
+    #[serde(rename = "type")]
+    pub image_type: String,
+
+    /// Path of the image.
+    pub path: PathBuf,
+}
+
+/// Hierarchy that extension images are merged into, as reported by `status`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct MergedHierarchy {
+    /// Path of the hierarchy, e.g. `/usr`.
+    pub hierarchy: PathBuf,
+
+    /// Names of the extension images merged into the hierarchy.
+    #[serde(deserialize_with = "deserialize_merged_extensions")]
+    pub extensions: Vec<String>,
+}
+
+/// systemd reports the string "none" instead of an empty list when a hierarchy has no extensions
+/// merged into it.
+fn deserialize_merged_extensions<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum MergedExtensions {
+        List(Vec<String>),
+        None(String),
+    }
+
+    Ok(match MergedExtensions::deserialize(deserializer)? {
+        MergedExtensions::List(extensions) => extensions,
+        MergedExtensions::None(_) => Vec::new(),
+    })
+}
+
+/// Executes `systemd-sysext list` or `systemd-confext list`, returning the extension images found
+/// on the running OS, whether they are merged or not.
+pub fn list(kind: ExtensionKind) -> Result<Vec<ListedImage>, Error> {
+    let output = kind
+        .dependency()
+        .cmd()
+        .arg("list")
+        .arg("--json=short")
+        .output_and_check()
+        .with_context(|| format!("Failed to list {kind} images"))?;
+    serde_json::from_str(&output)
+        .with_context(|| format!("Failed to parse output of '{} list'", kind.dependency()))
+}
+
+/// Executes `systemd-sysext status` or `systemd-confext status`, returning the hierarchies of the
+/// running OS and the extension images merged into them.
+pub fn status(kind: ExtensionKind) -> Result<Vec<MergedHierarchy>, Error> {
+    let output = kind
+        .dependency()
+        .cmd()
+        .arg("status")
+        .arg("--json=short")
+        .output_and_check()
+        .with_context(|| format!("Failed to get {kind} status"))?;
+    serde_json::from_str(&output)
+        .with_context(|| format!("Failed to parse output of '{} status'", kind.dependency()))
+}
+
+/// Executes `systemd-sysext refresh` or `systemd-confext refresh`, to merge the current set of
+/// extension images into the running OS. `mutable` is passed as `--mutable`, unless the merged
+/// hierarchies are read-only, which is the default of systemd.
+///
+/// On failure, the error output is parsed to report the cause of the failure and the extension
+/// image that caused it.
+pub fn refresh(kind: ExtensionKind, mutable: ExtensionMutability) -> Result<(), TridentError> {
+    debug!("Refreshing {kind} images");
+    let error = |inner| ServicingError::RefreshExtensionImages {
+        extension_type: kind.to_string(),
+        inner,
+    };
+
+    let mut cmd = kind.dependency().cmd();
+    cmd.arg("refresh");
+    if mutable != ExtensionMutability::default() {
+        cmd.arg(format!("--mutable={}", mutable.as_arg()));
+    }
+    let output = cmd
+        .output()
+        .structured(error(ExtensionRefreshError::Unrecognized))?;
+    if output.success() {
+        return Ok(());
+    }
+
+    let cause = parse_refresh_failure(&output.error_output());
+    if cause == ExtensionRefreshError::Unrecognized {
+        warn!(
+            "Failed to determine why {} refresh failed:\n{}",
+            kind.dependency(),
+            output.output_report()
+        );
+    }
+
+    output
+        .check()
+        .structured(error(cause))
+        .message(format!("Failed to run '{} refresh'", kind.dependency()))
+}
+
+/// Checks that the extension image at `path` is protected by dm-verity with a root hash signed by
+/// a key trusted by the running kernel.
+pub fn verify_signature(path: &Path) -> Result<(), Error> {
+    debug!(
+        "Verifying signature of extension image '{}'",
+        path.display()
+    );
+    Dependency::SystemdDissect
+        .cmd()
+        .arg("--validate")
+        .arg(format!("--image-policy={SIGNED_IMAGE_POLICY}"))
+        .arg(path)
+        .run_and_check()
+        .with_context(|| {
+            format!(
+                "Extension image '{}' is not signed by a trusted key",
+                path.display()
+            )
+        })
+}
+
+/// Extension image mounted read-only by systemd-dissect. The image is unmounted when the guard is
+/// dropped, so that its loop and dm-verity devices are released even on early returns.
+pub struct DissectedImageGuard {
+    mount_dir: PathBuf,
+    mounted: bool,
+}
+
+impl DissectedImageGuard {
+    /// Unmounts the image, reporting failures instead of only logging them as on drop.
+    pub fn unmount(mut self) -> Result<(), Error> {
+        self.mounted = false;
+        unmount_dissected(&self.mount_dir)
+    }
+}
+
+impl Drop for DissectedImageGuard {
+    fn drop(&mut self) {
+        if !self.mounted {
+            return;
+        }
+        if let Err(e) = unmount_dissected(&self.mount_dir) {
+            error!(
+                "Failed to unmount extension image at '{}': {e:?}",
+                self.mount_dir.display()
+            );
+        }
+    }
+}
+
+/// Mounts the extension image at `path` read-only at `mount_dir`, using systemd-dissect.
+///
+/// systemd-dissect sets up the loop device, as well as the dm-verity devices of verity-protected
+/// images, and attaches them with auto-clear, so they are released when the image is unmounted.
+pub fn mount_read_only(path: &Path, mount_dir: &Path) -> Result<DissectedImageGuard, Error> {
+    debug!(
+        "Mounting extension image '{}' at '{}'",
+        path.display(),
+        mount_dir.display()
+    );
+    Dependency::SystemdDissect
+        .cmd()
+        .arg("--mount")
+        .arg("--read-only")
+        .arg(path)
+        .arg(mount_dir)
+        .run_and_check()
+        .with_context(|| format!("Failed to mount extension image '{}'", path.display()))?;
+
+    Ok(DissectedImageGuard {
+        mount_dir: mount_dir.to_path_buf(),
+        mounted: true,
+    })
+}
+
+/// Unmounts an image mounted by systemd-dissect, releasing its devices.
+fn unmount_dissected(mount_dir: &Path) -> Result<(), Error> {
+    Dependency::SystemdDissect
+        .cmd()
+        .arg("--umount")
+        .arg(mount_dir)
+        .run_and_check()
+        .with_context(|| {
+            format!(
+                "Failed to unmount extension image at '{}'",
+                mount_dir.display()
+            )
+        })
+}
+
+/// Determines the cause of a failed refresh from the error output of systemd-sysext or
+/// systemd-confext.
+fn parse_refresh_failure(stderr: &str) -> ExtensionRefreshError {
+    // Names may or may not be quoted, depending on the systemd version.
+    let verification_regex = Regex::new(
+        r#"(?i)(?:extension|image)\s+(?:image\s+)?['"]?([^'"\s:,]+)['"]?.*(?:verity|signature|verif|required key not available)"#,
+    )
+    .unwrap();
+    let incompatible_regex = Regex::new(
+        r#"(?i)(?:extension|image)\s+(?:image\s+)?['"]?([^'"\s:,]+)['"]?.*(?:not compatible|incompatible|is for os|is for architecture)"#,
+    )
+    .unwrap();
+    let busy_regex =
+        Regex::new(r#"(?i)['"]?(/[^'"\s:,]*)['"]?.*(?:device or resource busy|\bbusy\b)"#).unwrap();
+
+    for line in stderr.lines() {
+        if let Some(captures) = verification_regex.captures(line) {
+            return ExtensionRefreshError::ImageVerification {
+                extension: captures[1].to_string(),
+            };
+        }
+        if let Some(captures) = incompatible_regex.captures(line) {
+            return ExtensionRefreshError::IncompatibleExtension {
+                extension: captures[1].to_string(),
+            };
+        }
+        if let Some(captures) = busy_regex.captures(line) {
+            return ExtensionRefreshError::OverlayBusy {
+                hierarchy: captures[1].to_string(),
+            };
+        }
+    }
+
+    ExtensionRefreshError::Unrecognized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_list_and_status() {
+        let images: Vec<ListedImage> = serde_json::from_str(
+            r#"[{"type":"raw","name":"docker","path":"/var/lib/extensions/docker.raw","time":0},
+                {"type":"directory","name":"tools","path":"/run/extensions/tools","time":0}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            images,
+            vec![
+                ListedImage {
+                    name: "docker".into(),
+                    image_type: "raw".into(),
+                    path: "/var/lib/extensions/docker.raw".into(),
+                },
+                ListedImage {
+                    name: "tools".into(),
+                    image_type: "directory".into(),
+                    path: "/run/extensions/tools".into(),
+                },
+            ]
+        );
+
+        let hierarchies: Vec<MergedHierarchy> = serde_json::from_str(
+            r#"[{"hierarchy":"/usr","extensions":["docker","tools"],"since":1700000000000000},
+                {"hierarchy":"/opt","extensions":"none"}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            hierarchies,
+            vec![
+                MergedHierarchy {
+                    hierarchy: "/usr".into(),
+                    extensions: vec!["docker".into(), "tools".into()],
+                },
+                MergedHierarchy {
+                    hierarchy: "/opt".into(),
+                    extensions: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_refresh_failure() {
+        assert_eq!(
+            parse_refresh_failure(
+                "Using extensions 'base', 'tools'.\n\
+                 Failed to dissect image 'tools': Required key not available\n"
+            ),
+            ExtensionRefreshError::ImageVerification {
+                extension: "tools".into()
+            }
+        );
+        assert_eq!(
+            parse_refresh_failure(
+                "Failed to open image /var/lib/extensions/tools.raw: verity signature check failed"
+            ),
+            ExtensionRefreshError::ImageVerification {
+                extension: "/var/lib/extensions/tools.raw".into()
+            }
+        );
+        assert_eq!(
+            parse_refresh_failure("Extension 'tools' is not compatible with host, refusing."),
+            ExtensionRefreshError::IncompatibleExtension {
+                extension: "tools".into()
+            }
+        );
+        assert_eq!(
+            parse_refresh_failure("Failed to unmount '/usr': Device or resource busy"),
+            ExtensionRefreshError::OverlayBusy {
+                hierarchy: "/usr".into()
+            }
+        );
+        assert_eq!(
+            parse_refresh_failure("Something unexpected happened."),
+            ExtensionRefreshError::Unrecognized
+        );
+        assert_eq!(
+            parse_refresh_failure(""),
+            ExtensionRefreshError::Unrecognized
+        );
+    }
+}
@@ -1,8 +1,14 @@
 use anyhow::{Context, Error};
 
+#[cfg(not(feature = "minimal-dependencies"))]
 use crate::dependencies::Dependency;
 
+/// File holding the release of the running kernel, as printed by `uname -r`.
+#[cfg(feature = "minimal-dependencies")]
+const KERNEL_RELEASE_PATH: &str = "/proc/sys/kernel/osrelease";
+
 // Grab the kernel version using the `uname` command
+#[cfg(not(feature = "minimal-dependencies"))]
 pub fn kernel_release() -> Result<String, Error> {
     Dependency::Uname
         .cmd()
@@ -11,6 +17,13 @@ pub fn kernel_release() -> Result<String, Error> {
         .context("Failed to run uname -r")
 }
 
+// Grab the kernel version from procfs, without running `uname`
+#[cfg(feature = "minimal-dependencies")]
+pub fn kernel_release() -> Result<String, Error> {
+    std::fs::read_to_string(KERNEL_RELEASE_PATH)
+        .with_context(|| format!("Failed to read '{KERNEL_RELEASE_PATH}'"))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::uname;
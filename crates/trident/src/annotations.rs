@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+
+use trident_api::error::{InvalidInputError, TridentError};
+
+/// Maximum length of the key of an annotation.
+const MAX_KEY_LENGTH: usize = 63;
+
+/// Maximum length of the value of an annotation.
+const MAX_VALUE_LENGTH: usize = 256;
+
+/// Maximum number of annotations on the Host Status, to keep it and its history small.
+const MAX_ANNOTATIONS: usize = 64;
+
+/// Parses an annotation given as `KEY=VALUE`.
+pub(crate) fn parse(annotation: &str) -> Result<(String, String), TridentError> {
+    let (key, value) = annotation
+        .split_once('=')
+        .ok_or_else(|| invalid(annotation, "expected KEY=VALUE"))?;
+    validate_key(key)?;
+    if value.len() > MAX_VALUE_LENGTH {
+        return Err(invalid(
+            annotation,
+            &format!("values are limited to {MAX_VALUE_LENGTH} bytes"),
+        ));
+    }
+    Ok((key.into(), value.into()))
+}
+
+/// Checks that `key` only contains ASCII letters, digits, `.`, `_`, `-` and `/`.
+pub(crate) fn validate_key(key: &str) -> Result<(), TridentError> {
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+        return Err(invalid(
+            key,
+            &format!("keys must be between 1 and {MAX_KEY_LENGTH} characters long"),
+        ));
+    }
+    if !key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '/'))
+    {
+        return Err(invalid(
+            key,
+            "keys may only contain ASCII letters, digits, '.', '_', '-' and '/'",
+        ));
+    }
+    Ok(())
+}
+
+/// Sets the annotations of `set` and removes those of `remove`, which do not need to exist.
+pub(crate) fn update(
+    annotations: &mut BTreeMap<String, String>,
+    set: Vec<(String, String)>,
+    remove: &[String],
+) -> Result<(), TridentError> {
+    for key in remove {
+        annotations.remove(key);
+    }
+    for (key, value) in set {
+        if !annotations.contains_key(&key) && annotations.len() >= MAX_ANNOTATIONS {
+            return Err(invalid(
+                &key,
+                &format!("the Host Status is limited to {MAX_ANNOTATIONS} annotations"),
+            ));
+        }
+        annotations.insert(key, value);
+    }
+    Ok(())
+}
+
+fn invalid(annotation: &str, explanation: &str) -> TridentError {
+    TridentError::new(InvalidInputError::InvalidAnnotation {
+        annotation: annotation.into(),
+        explanation: explanation.into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("ticket=CHG-1234").unwrap(),
+            ("ticket".into(), "CHG-1234".into())
+        );
+        assert_eq!(
+            parse("example.com/drained=").unwrap(),
+            ("example.com/drained".into(), "".into())
+        );
+        assert_eq!(parse("a=b=c").unwrap(), ("a".into(), "b=c".into()));
+
+        parse("drained").unwrap_err();
+        parse("=true").unwrap_err();
+        parse("drained now=true").unwrap_err();
+        parse(&format!("{}=true", "k".repeat(MAX_KEY_LENGTH + 1))).unwrap_err();
+        parse(&format!("note={}", "v".repeat(MAX_VALUE_LENGTH + 1))).unwrap_err();
+    }
+
+    #[test]
+    fn test_update() {
+        let mut annotations = BTreeMap::from([("drained".into(), "false".into())]);
+        update(
+            &mut annotations,
+            vec![
+                ("drained".into(), "true".into()),
+                ("ticket".into(), "CHG-1234".into()),
+            ],
+            &["unknown".into()],
+        )
+        .unwrap();
+        assert_eq!(
+            annotations,
+            BTreeMap::from([
+                ("drained".into(), "true".into()),
+                ("ticket".into(), "CHG-1234".into()),
+            ])
+        );
+
+        update(&mut annotations, vec![], &["drained".into()]).unwrap();
+        assert_eq!(annotations.len(), 1);
+
+        // Existing annotations can be updated once the limit is reached, but not added.
+        let mut annotations = (0..MAX_ANNOTATIONS)
+            .map(|i| (format!("key{i}"), String::new()))
+            .collect();
+        update(&mut annotations, vec![("key0".into(), "v".into())], &[]).unwrap();
+        update(&mut annotations, vec![("other".into(), "v".into())], &[]).unwrap_err();
+        update(
+            &mut annotations,
+            vec![("other".into(), "v".into())],
+            &["key1".into()],
+        )
+        .unwrap();
+    }
+}
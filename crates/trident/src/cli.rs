@@ -1,12 +1,13 @@
 use std::{
     fmt::{Display, Formatter, Result as FmtResult},
+    net::SocketAddr,
     path::PathBuf,
 };
 
 use clap::{Parser, Subcommand};
 use log::LevelFilter;
 
-use trident_api::config::{Operation, Operations};
+use trident_api::config::{Operation, Operations, UpdateScope};
 
 use crate::TRIDENT_VERSION;
 
@@ -17,10 +18,35 @@ pub struct Cli {
     #[arg(global = true, short, long, default_value_t = LevelFilter::Debug)]
     pub verbosity: LevelFilter,
 
+    /// Where to send log output
+    #[arg(global = true, long, value_enum, default_value_t = LogSink::Stderr)]
+    pub log_sink: LogSink,
+
     #[clap(subcommand)]
     pub command: Commands,
 }
 
+/// The destinations that Trident can send its log output to
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LogSink {
+    /// Plain text on stderr
+    Stderr,
+    /// Structured records in the systemd journal
+    Journald,
+    /// Both stderr and the systemd journal
+    Both,
+}
+
+impl LogSink {
+    pub fn stderr(&self) -> bool {
+        matches!(self, LogSink::Stderr | LogSink::Both)
+    }
+
+    pub fn journald(&self) -> bool {
+        matches!(self, LogSink::Journald | LogSink::Both)
+    }
+}
+
 /// The operations that Trident is allowed to perform
 #[derive(clap::ValueEnum, Clone, Debug, Eq, PartialEq)]
 pub enum AllowedOperation {
@@ -28,6 +54,32 @@ pub enum AllowedOperation {
     Finalize,
 }
 
+/// The configuration domains that an update can be restricted to
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OnlySection {
+    Sysexts,
+    Scripts,
+    Osconfig,
+    Storage,
+    Network,
+}
+
+pub fn to_update_scopes(sections: &[OnlySection]) -> Vec<UpdateScope> {
+    let mut scopes = sections
+        .iter()
+        .map(|section| match section {
+            OnlySection::Sysexts => UpdateScope::Sysexts,
+            OnlySection::Scripts => UpdateScope::Scripts,
+            OnlySection::Osconfig => UpdateScope::Osconfig,
+            OnlySection::Storage => UpdateScope::Storage,
+            OnlySection::Network => UpdateScope::Network,
+        })
+        .collect::<Vec<_>>();
+    scopes.sort();
+    scopes.dedup();
+    scopes
+}
+
 pub fn to_operations(allowed_operations: &[AllowedOperation]) -> Operations {
     let mut ops = Operations::empty();
     for op in allowed_operations {
@@ -62,6 +114,14 @@ pub enum Commands {
         /// Allow Trident to perform a multiboot install
         #[clap(long)]
         multiboot: bool,
+
+        /// Path to save a report of the binaries, kernel modules and disk space used by the run
+        ///
+        /// The report is written when the run ends, before Trident reboots the host. The path
+        /// must be on storage that persists across the reboot, or finalizing must not be allowed,
+        /// e.g. with `--allowed-operations stage`.
+        #[clap(long)]
+        footprint: Option<PathBuf>,
     },
 
     /// Start or continue an A/B update from an existing install
@@ -70,10 +130,37 @@ pub enum Commands {
         #[clap(index = 1, default_value = "/etc/trident/config.yaml")]
         config: PathBuf,
 
+        /// URL of a Git repository to load the new configuration from, instead of a local file
+        #[clap(long, conflicts_with = "config")]
+        git_url: Option<String>,
+
+        /// Branch or tag of the Git repository to load the new configuration from
+        #[clap(long, default_value = "main", requires = "git_url")]
+        git_ref: String,
+
+        /// Path of the configuration file, relative to the root of the Git repository
+        #[clap(long, default_value = "host-config.yaml", requires = "git_url")]
+        git_path: PathBuf,
+
         /// Comma-separated list of operations that Trident will be allowed to perform
         #[clap(long, value_delimiter = ',', num_args = 0.., default_value = "stage,finalize")]
         allowed_operations: Vec<AllowedOperation>,
 
+        /// Comma-separated list of configuration sections to restrict the update to
+        ///
+        /// Only the selected sections of the new configuration are applied, directly to the
+        /// running OS; all other sections keep their current values. Storage changes are only
+        /// validated.
+        #[clap(long, value_delimiter = ',', conflicts_with = "allowed_operations")]
+        only: Vec<OnlySection>,
+
+        /// Seconds to wait for a hot-applied network change to be confirmed before reverting it
+        ///
+        /// Only used with `--only network`. Confirm the change with `trident confirm-network` or
+        /// through the gRPC API.
+        #[clap(long, requires = "only")]
+        confirm_timeout: Option<u64>,
+
         /// Path to save the resulting Host Status
         #[clap(short, long)]
         status: Option<PathBuf>,
@@ -81,6 +168,14 @@ pub enum Commands {
         /// Path to save an eventual fatal error
         #[clap(short, long)]
         error: Option<PathBuf>,
+
+        /// Path to save a report of the binaries, kernel modules and disk space used by the run
+        ///
+        /// The report is written when the run ends, before Trident reboots the host. The path
+        /// must be on storage that persists across the reboot, or finalizing must not be allowed,
+        /// e.g. with `--allowed-operations stage`.
+        #[clap(long)]
+        footprint: Option<PathBuf>,
     },
 
     /// Detect whether an install or update succeeded, and update the boot order accordingly
@@ -94,6 +189,37 @@ pub enum Commands {
         error: Option<PathBuf>,
     },
 
+    /// Watch a Git repository and update the host whenever the tracked branch or tag advances
+    Watch {
+        /// URL of the Git repository to load the configuration from
+        #[clap(long)]
+        git_url: String,
+
+        /// Branch or tag of the Git repository to track
+        #[clap(long, default_value = "main")]
+        git_ref: String,
+
+        /// Path of the configuration file, relative to the root of the Git repository
+        #[clap(long, default_value = "host-config.yaml")]
+        git_path: PathBuf,
+
+        /// Number of seconds to wait between checks of the Git repository, at least 1
+        #[clap(long, default_value_t = 300, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+
+        /// Comma-separated list of operations that Trident will be allowed to perform
+        #[clap(long, value_delimiter = ',', num_args = 0.., default_value = "stage,finalize")]
+        allowed_operations: Vec<AllowedOperation>,
+
+        /// Path to save the resulting Host Status
+        #[clap(short, long)]
+        status: Option<PathBuf>,
+
+        /// Path to save an eventual fatal error
+        #[clap(short, long)]
+        error: Option<PathBuf>,
+    },
+
     #[clap(hide(true))]
     Listen {
         /// Path to save the resulting Host Status
@@ -139,6 +265,127 @@ pub enum Commands {
         /// Path to save the resulting output
         #[clap(short, long)]
         outfile: Option<PathBuf>,
+
+        /// Print the data as JSON instead of YAML
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Attach key-value annotations to the Host Status, or remove them
+    ///
+    /// Annotations are kept across servicing operations and recorded in the history of the Host
+    /// Status, so that orchestrators and hook scripts can correlate servicing with their own
+    /// workflows, e.g. `trident annotate drained=true ticket=CHG-1234`. Annotations set while
+    /// Trident is servicing the host may be overwritten when the servicing completes.
+    Annotate {
+        /// Annotation to set, as KEY=VALUE
+        #[clap(index = 1)]
+        annotations: Vec<String>,
+
+        /// Key of an annotation to remove; may be repeated
+        #[clap(long)]
+        remove: Vec<String>,
+    },
+
+    /// Report the state of the host without modifying it
+    ///
+    /// Only reads block devices, boot entries, extension images and the datastore, so it is safe
+    /// to run under strict change control.
+    #[clap(name = "inspect")]
+    Inspect {
+        /// Section of the host to inspect; may be repeated. All sections are inspected by default
+        #[clap(long, value_enum)]
+        section: Vec<InspectSection>,
+
+        /// Path to save the resulting report
+        #[clap(short, long)]
+        outfile: Option<PathBuf>,
+    },
+
+    /// Show the steps that servicing the host with a Host Configuration would take
+    ///
+    /// Nothing is modified. An A/B update is planned if the host was already provisioned by
+    /// Trident, and a clean install otherwise. The text format also lists which extension images
+    /// would be merged, unmerged or kept, and why.
+    #[clap(name = "plan")]
+    Plan {
+        /// Path to a Host Configuration file
+        #[clap(index = 1, default_value = "/etc/trident/config.yaml")]
+        config: PathBuf,
+
+        /// Output format of the plan
+        #[clap(long, value_enum, default_value_t = PlanFormat::Text)]
+        format: PlanFormat,
+
+        /// Path to save the resulting plan
+        #[clap(short, long)]
+        outfile: Option<PathBuf>,
+    },
+
+    /// Compare a Host Configuration with the one currently applied to the host
+    ///
+    /// Nothing is modified. Lists the fields that differ from the Host Configuration recorded in
+    /// the datastore, and the servicing each change requires: none, a hot-apply with `update
+    /// --only`, an A/B update or a clean install. All changes require a clean install if the
+    /// host was not provisioned by Trident.
+    #[clap(name = "diff")]
+    Diff {
+        /// Path to the proposed Host Configuration file
+        #[clap(index = 1, default_value = "/etc/trident/config.yaml")]
+        config: PathBuf,
+
+        /// Output format of the diff
+        #[clap(long, value_enum, default_value_t = DiffFormat::Text)]
+        format: DiffFormat,
+
+        /// Path to save the resulting diff
+        #[clap(short, long)]
+        outfile: Option<PathBuf>,
+    },
+
+    /// Run or inspect the health checks from the Host Configuration
+    #[clap(name = "health")]
+    Health {
+        #[clap(subcommand)]
+        command: HealthCommands,
+    },
+
+    /// List the extension images of the running OS and where they are merged
+    #[clap(name = "sysext")]
+    Sysext {
+        #[clap(subcommand)]
+        command: SysextCommands,
+    },
+
+    /// Serve the OS image and extension images of this host to peer installers over HTTP
+    ///
+    /// Artifacts are taken from the Host Configuration last applied to the host, or from the
+    /// provided one. Only the OS image, when its URL is a local file, and the extension images
+    /// present on the host are served. The list of artifacts is served at `/`.
+    #[clap(name = "serve")]
+    Serve {
+        /// Serve the cached OS image and extension images
+        #[clap(long, required = true)]
+        artifacts: bool,
+
+        /// Address to listen on
+        #[clap(long, default_value = "0.0.0.0:8080")]
+        listen: SocketAddr,
+
+        /// Path to a Host Configuration file listing the artifacts to serve
+        #[clap(short, long)]
+        config: Option<PathBuf>,
+    },
+
+    /// Confirm a hot-applied network configuration change, so that it is not reverted
+    ConfirmNetwork,
+
+    #[cfg(feature = "grpc-dangerous")]
+    /// Service several hosts listed in a manifest over the gRPC API of Trident
+    #[clap(name = "fleet")]
+    Fleet {
+        #[clap(subcommand)]
+        command: FleetCommands,
     },
 
     /// Validate the provided Host Configuration
@@ -149,6 +396,50 @@ pub enum Commands {
         /// Path to a Host Configuration file
         #[clap(index = 1, default_value = "/etc/trident/config.yaml")]
         config: PathBuf,
+
+        /// Print the resolved Host Configuration, with the fragments it includes merged and its
+        /// variables substituted, once validated
+        #[clap(long)]
+        print: bool,
+
+        /// Also perform the checks of dynamic validation that do not depend on the host, such as
+        /// the existence of referenced files, without requiring root or touching any device
+        #[clap(long)]
+        offline: bool,
+
+        /// Directory to look up the local files referenced by the Host Configuration in, as if it
+        /// were the root of the servicing OS
+        #[clap(long, default_value = "/", requires = "offline")]
+        root: PathBuf,
+    },
+
+    /// Upgrade a Host Configuration to the current API version
+    ///
+    /// Renames the fields and restructures the sections that changed since the API version of
+    /// the Host Configuration, and sets `apiVersion` to the current version. Comments are not
+    /// preserved, and the fragments included by the Host Configuration must be migrated
+    /// separately.
+    #[clap(name = "migrate-config")]
+    MigrateConfig {
+        /// Path to a Host Configuration file
+        #[clap(index = 1, default_value = "/etc/trident/config.yaml")]
+        config: PathBuf,
+
+        /// Path to save the migrated Host Configuration
+        #[clap(short, long)]
+        outfile: Option<PathBuf>,
+    },
+
+    #[cfg(feature = "schema")]
+    /// Print the JSON Schema of the Host Configuration
+    ///
+    /// The schema describes the Host Configuration API of this version of Trident, so that
+    /// editors and CI pipelines can validate Host Configuration files against it.
+    #[clap(name = "schema")]
+    Schema {
+        /// Path to save the schema
+        #[clap(short, long)]
+        outfile: Option<PathBuf>,
     },
 
     #[cfg(feature = "pytest-generator")]
@@ -183,11 +474,25 @@ impl Commands {
             Commands::Install { .. } => "install",
             Commands::Update { .. } => "update",
             Commands::Commit { .. } => "commit",
+            Commands::Watch { .. } => "watch",
             Commands::Listen { .. } => "listen",
             Commands::RebuildRaid { .. } => "rebuild-raid",
             Commands::StartNetwork { .. } => "start-network",
             Commands::Get { .. } => "get",
+            Commands::Annotate { .. } => "annotate",
+            Commands::Health { .. } => "health",
+            Commands::Sysext { .. } => "sysext",
+            Commands::Serve { .. } => "serve",
+            Commands::Inspect { .. } => "inspect",
+            Commands::Plan { .. } => "plan",
+            Commands::Diff { .. } => "diff",
+            Commands::ConfirmNetwork => "confirm-network",
+            #[cfg(feature = "grpc-dangerous")]
+            Commands::Fleet { .. } => "fleet",
             Commands::Validate { .. } => "validate",
+            Commands::MigrateConfig { .. } => "migrate-config",
+            #[cfg(feature = "schema")]
+            Commands::Schema { .. } => "schema",
             #[cfg(feature = "pytest-generator")]
             Commands::Pytest => "pytest",
             Commands::OfflineInitialize { .. } => "offline-initialize",
@@ -201,9 +506,200 @@ impl Display for Commands {
     }
 }
 
+#[derive(Subcommand, Debug)]
+pub enum HealthCommands {
+    /// Run the health checks against the current OS and print the resulting report
+    ///
+    /// The checks are executed outside of any servicing, so that their definitions can be
+    /// debugged before relying on them to gate rollbacks.
+    Run {
+        /// Name of a health check to run; may be repeated. All checks are run by default
+        #[clap(long)]
+        check: Vec<String>,
+
+        /// Path to save the resulting report
+        #[clap(short, long)]
+        outfile: Option<PathBuf>,
+    },
+
+    /// Validate the health checks of a Host Configuration against the target OS, without running them
+    ///
+    /// Reports the scripts and interpreters that do not exist or are not executable, the systemd
+    /// units that have no unit file, the container runtimes that are not installed and the checks
+    /// that never run, so that they can be fixed before an update is staged. Fails if any problem
+    /// is found.
+    Lint {
+        /// Path to a Host Configuration file
+        #[clap(index = 1, default_value = "/etc/trident/config.yaml")]
+        config: PathBuf,
+
+        /// Path to the root filesystem of the target OS, e.g. of its image mounted locally
+        #[clap(long, default_value = "/")]
+        root: PathBuf,
+
+        /// Path to save the resulting report
+        #[clap(short, long)]
+        outfile: Option<PathBuf>,
+    },
+}
+
+#[cfg(feature = "grpc-dangerous")]
+#[derive(Subcommand, Debug)]
+pub enum FleetCommands {
+    /// Apply Host Configurations to the hosts of a manifest, and report the outcome on each host
+    ///
+    /// Each host must run `trident listen` with the gRPC API enabled. Progress of each host is
+    /// logged as it is serviced. Fails if servicing fails on any host.
+    Apply {
+        /// Path to the manifest listing the hosts and their Host Configurations
+        #[clap(short, long)]
+        manifest: PathBuf,
+
+        /// Comma-separated list of operations that Trident will be allowed to perform
+        #[clap(long, value_delimiter = ',', num_args = 0.., default_value = "stage,finalize")]
+        allowed_operations: Vec<AllowedOperation>,
+
+        /// Maximum number of hosts serviced at once
+        #[clap(long, default_value_t = 4)]
+        concurrency: usize,
+
+        /// Print the report as JSON instead of a table
+        #[clap(long)]
+        json: bool,
+
+        /// Path to save the resulting report
+        #[clap(short, long)]
+        outfile: Option<PathBuf>,
+    },
+
+    /// Report the servicing state of the hosts of a manifest
+    Status {
+        /// Path to the manifest listing the hosts
+        #[clap(short, long)]
+        manifest: PathBuf,
+
+        /// Maximum number of hosts queried at once
+        #[clap(long, default_value_t = 4)]
+        concurrency: usize,
+
+        /// Print the report as JSON instead of a table
+        #[clap(long)]
+        json: bool,
+
+        /// Path to save the resulting report
+        #[clap(short, long)]
+        outfile: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SysextCommands {
+    /// List the sysext and confext images found by systemd, with their ID, version, scope, path
+    /// and whether they are merged
+    ///
+    /// Images of the Host Configuration last applied by Trident are matched with the images found
+    /// by systemd, and reported as missing when systemd does not find them.
+    List {
+        /// Print the list as JSON instead of a table
+        #[clap(long)]
+        json: bool,
+
+        /// Path to save the resulting list
+        #[clap(short, long)]
+        outfile: Option<PathBuf>,
+    },
+
+    /// Show the hierarchies that sysext and confext images are merged into
+    Status {
+        /// Print the status as JSON instead of a table
+        #[clap(long)]
+        json: bool,
+
+        /// Path to save the resulting status
+        #[clap(short, long)]
+        outfile: Option<PathBuf>,
+    },
+
+    /// Remove the extension images in /var/lib/extensions and /var/lib/confexts that are not part
+    /// of the Host Configuration last applied by Trident
+    ///
+    /// Images are grouped by name, ignoring a trailing version, and the most recently modified
+    /// images of each name are kept. Remaining images are merged again afterwards.
+    Prune {
+        /// Number of unmanaged images of each name to keep. Defaults to the `keepUnmanaged`
+        /// sysext policy of the Host Configuration, or 0
+        #[clap(long)]
+        keep: Option<usize>,
+
+        /// Only list the images that would be removed
+        #[clap(long)]
+        dry_run: bool,
+
+        /// Print the list of images as JSON instead of a table
+        #[clap(long)]
+        json: bool,
+
+        /// Path to save the resulting list
+        #[clap(short, long)]
+        outfile: Option<PathBuf>,
+    },
+    /// Merge the sysext and confext images again, and check that all the images of the Host
+    /// Configuration last applied by Trident are merged
+    ///
+    /// Use after running systemd-sysext or systemd-confext by hand, which can leave the merged
+    /// images out of sync with the Host Configuration.
+    RefreshCache,
+}
+
+/// The sections of the host that can be inspected
+#[derive(clap::ValueEnum, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum InspectSection {
+    Disks,
+    Boot,
+    Extensions,
+    Datastore,
+}
+
+impl Display for InspectSection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            InspectSection::Disks => write!(f, "disks"),
+            InspectSection::Boot => write!(f, "boot"),
+            InspectSection::Extensions => write!(f, "extensions"),
+            InspectSection::Datastore => write!(f, "datastore"),
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PlanFormat {
+    /// Numbered list of steps
+    Text,
+    /// Dependency graph of the steps in the Graphviz DOT language
+    Dot,
+}
+
+#[derive(clap::ValueEnum, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DiffFormat {
+    /// One line per changed field
+    Text,
+    /// Structured YAML document
+    Yaml,
+}
+
 #[derive(clap::ValueEnum, Copy, Clone, Debug, Eq, PartialEq)]
 pub enum GetKind {
     Configuration,
     Status,
     LastError,
+    /// Key-value annotations attached to the Host Status with `trident annotate`
+    Annotations,
+    /// Provenance of the artifacts currently deployed on the host
+    Artifacts,
+    /// Provenance of the artifacts deployed by each past servicing, oldest first
+    ArtifactHistory,
+    /// Outcome of each health check of the last commit of a target OS
+    HealthChecks,
+    /// Outcome of the health checks in each of the last boots in which they ran, oldest first
+    HealthCheckHistory,
 }
@@ -0,0 +1,246 @@
+//! Composition of the Host Configuration from fragments.
+//!
+//! A Host Configuration file, or any fragment it includes, can list other YAML fragments under
+//! the top-level `include` key, with paths relative to the including file. The fragments are
+//! merged in order, and the including file is merged last, so that a host configuration can be
+//! built from a base configuration, then site overrides, then host overrides:
+//!
+//! - Mappings are merged key by key, recursively.
+//! - Sequences and scalars of the overlay replace those of the base.
+//! - A key set to `null` in the overlay is removed from the base.
+//!
+//! Variables are substituted in each fragment before it is merged.
+
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{bail, Context, Error};
+use log::debug;
+use serde_yaml::Value;
+
+use crate::template;
+
+/// Key of the list of fragments included by a Host Configuration or a fragment.
+pub(crate) const INCLUDE_KEY: &str = "include";
+
+/// Merges the fragments included by `value`, the contents of the Host Configuration at `path`,
+/// into it. `load` returns the contents of the fragment at the given path.
+pub(crate) fn resolve_includes(
+    value: Value,
+    path: &Path,
+    load: &mut impl FnMut(&Path) -> Result<String, Error>,
+) -> Result<Value, Error> {
+    resolve(value, path, load, &mut vec![normalize(path)])
+}
+
+fn resolve(
+    mut value: Value,
+    path: &Path,
+    load: &mut impl FnMut(&Path) -> Result<String, Error>,
+    stack: &mut Vec<PathBuf>,
+) -> Result<Value, Error> {
+    let includes = take_includes(&mut value)
+        .with_context(|| format!("Invalid '{INCLUDE_KEY}' in '{}'", path.display()))?;
+
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let mut base = Value::Null;
+    for include in includes {
+        let include_path = normalize(&directory.join(include));
+        if stack.contains(&include_path) {
+            bail!(
+                "Fragment '{}' includes itself through {}",
+                include_path.display(),
+                stack
+                    .iter()
+                    .map(|path| format!("'{}'", path.display()))
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            );
+        }
+
+        debug!("Including fragment '{}'", include_path.display());
+        let contents = load(&include_path)
+            .with_context(|| format!("Failed to load fragment '{}'", include_path.display()))?;
+        let rendered = template::render(&contents).with_context(|| {
+            format!(
+                "Failed to substitute the variables of fragment '{}'",
+                include_path.display()
+            )
+        })?;
+        let fragment: Value = serde_yaml::from_str(&rendered)
+            .with_context(|| format!("Failed to parse fragment '{}'", include_path.display()))?;
+
+        stack.push(include_path.clone());
+        let fragment = resolve(fragment, &include_path, load, stack)?;
+        stack.pop();
+
+        base = merge(base, fragment);
+    }
+
+    Ok(merge(base, value))
+}
+
+/// Removes the list of included fragments from `value` and returns it.
+fn take_includes(value: &mut Value) -> Result<Vec<String>, Error> {
+    let Some(includes) = value
+        .as_mapping_mut()
+        .and_then(|mapping| mapping.remove(INCLUDE_KEY))
+    else {
+        return Ok(Vec::new());
+    };
+
+    match includes {
+        Value::String(include) => Ok(vec![include]),
+        Value::Sequence(includes) => includes
+            .into_iter()
+            .map(|include| match include {
+                Value::String(include) => Ok(include),
+                _ => bail!("Included fragments must be paths"),
+            })
+            .collect(),
+        _ => bail!("Included fragments must be a list of paths"),
+    }
+}
+
+/// Merges `overlay` on top of `base`.
+fn merge(base: Value, overlay: Value) -> Value {
+    match (base, overlay) {
+        (base, Value::Null) => base,
+        (Value::Mapping(mut base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                if value.is_null() {
+                    base.shift_remove(&key);
+                } else if let Some(existing) = base.get_mut(&key) {
+                    *existing = merge(std::mem::take(existing), value);
+                } else {
+                    base.insert(key, value);
+                }
+            }
+            Value::Mapping(base)
+        }
+        (_, overlay) => overlay,
+    }
+}
+
+/// Lexically normalizes `path`, resolving `.` and `..` without accessing the filesystem, as
+/// fragments may not be stored on it.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                // The parent of the root is the root itself.
+                if !normalized.pop() && !normalized.has_root() {
+                    normalized.push(component);
+                }
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    fn yaml(contents: &str) -> Value {
+        serde_yaml::from_str(contents).unwrap()
+    }
+
+    #[test]
+    fn test_merge() {
+        let base = yaml(indoc::indoc! {"
+            os:
+              hostname: base
+              users:
+                - name: admin
+              selinux:
+                mode: enforcing
+            trident:
+              logstream: https://logs.example.com
+        "});
+        let overlay = yaml(indoc::indoc! {"
+            os:
+              hostname: site
+              users:
+                - name: operator
+              selinux: null
+            scripts: {}
+        "});
+        assert_eq!(
+            merge(base, overlay),
+            yaml(indoc::indoc! {"
+                os:
+                  hostname: site
+                  users:
+                    - name: operator
+                trident:
+                  logstream: https://logs.example.com
+                scripts: {}
+            "})
+        );
+    }
+
+    #[test]
+    fn test_resolve_includes() {
+        let fragments = HashMap::from([
+            (
+                PathBuf::from("/configs/base.yaml"),
+                "os:\n  hostname: base\n  selinux:\n    mode: enforcing\n",
+            ),
+            (
+                PathBuf::from("/configs/sites/paris.yaml"),
+                "include: ../base.yaml\nos:\n  hostname: paris\n",
+            ),
+            (
+                PathBuf::from("/configs/loop.yaml"),
+                "include: [hosts/edge.yaml]\n",
+            ),
+        ]);
+        let mut load = |path: &Path| {
+            fragments
+                .get(path)
+                .map(|contents| contents.to_string())
+                .with_context(|| format!("'{}' does not exist", path.display()))
+        };
+
+        let host = yaml("include:\n  - ../sites/paris.yaml\nos:\n  hostname: edge-1\n");
+        assert_eq!(
+            resolve_includes(host, Path::new("/configs/hosts/edge.yaml"), &mut load).unwrap(),
+            yaml("os:\n  hostname: edge-1\n  selinux:\n    mode: enforcing\n")
+        );
+
+        // Without includes, the Host Configuration is left as is.
+        let host = yaml("os:\n  hostname: edge-1\n");
+        assert_eq!(
+            resolve_includes(host.clone(), Path::new("/configs/edge.yaml"), &mut load).unwrap(),
+            host
+        );
+
+        let host = yaml("include: [../loop.yaml]\n");
+        let error =
+            resolve_includes(host, Path::new("/configs/hosts/edge.yaml"), &mut load).unwrap_err();
+        assert!(error.to_string().contains("includes itself"), "{error:?}");
+
+        let host = yaml("include: [missing.yaml]\n");
+        resolve_includes(host, Path::new("/configs/edge.yaml"), &mut load).unwrap_err();
+
+        let host = yaml("include: {base: base.yaml}\n");
+        resolve_includes(host, Path::new("/configs/edge.yaml"), &mut load).unwrap_err();
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize(Path::new("/configs/hosts/../sites/./paris.yaml")),
+            PathBuf::from("/configs/sites/paris.yaml")
+        );
+        assert_eq!(
+            normalize(Path::new("../base.yaml")),
+            PathBuf::from("../base.yaml")
+        );
+    }
+}
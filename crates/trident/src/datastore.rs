@@ -39,6 +39,27 @@ impl DataStore {
                 path: path.to_string_lossy().into(),
             },
         })?;
+        Self::load(db)
+    }
+
+    /// Opens an existing datastore without allowing any modification of it. Unlike `open`, this
+    /// fails if the datastore does not exist, and any attempt to update the Host Status fails.
+    pub(crate) fn open_read_only(path: &Path) -> Result<Self, TridentError> {
+        debug!(
+            "Loading datastore from {} in read-only mode",
+            path.display()
+        );
+        let db =
+            sqlite::Connection::open_with_flags(path, sqlite::OpenFlags::new().with_read_only())
+                .structured(ServicingError::Datastore {
+                    inner: DatastoreError::LoadDatastore {
+                        path: path.to_string_lossy().into(),
+                    },
+                })?;
+        Self::load(db)
+    }
+
+    fn load(db: sqlite::Connection) -> Result<Self, TridentError> {
         let host_status_yaml: Option<serde_yaml::Value> = db
             .prepare("SELECT contents FROM hoststatus ORDER BY id DESC LIMIT 1")
             .structured(ServicingError::Datastore {
@@ -135,6 +156,34 @@ impl DataStore {
         &self.host_status
     }
 
+    /// Returns every revision of the Host Status stored in the datastore, oldest first.
+    pub(crate) fn history(&self) -> Result<Vec<HostStatus>, TridentError> {
+        let db = self
+            .db
+            .as_ref()
+            .structured(ServicingError::from(DatastoreError::WriteToClosedDatastore))?;
+
+        db.prepare("SELECT contents FROM hoststatus ORDER BY id")
+            .structured(ServicingError::Datastore {
+                inner: DatastoreError::InitializeDatastore,
+            })?
+            .into_iter()
+            .map(|row| {
+                let row = row.structured(ServicingError::Datastore {
+                    inner: DatastoreError::InitializeDatastore,
+                })?;
+                let yaml: serde_yaml::Value = serde_yaml::from_str(row.read::<&str, _>(0))
+                    .structured(ServicingError::Datastore {
+                        inner: DatastoreError::InitializeDatastore,
+                    })
+                    .message("Failed to parse Host Status as YAML")?;
+                decode_host_status(yaml).structured(ServicingError::Datastore {
+                    inner: DatastoreError::InitializeDatastore,
+                })
+            })
+            .collect()
+    }
+
     pub(crate) fn with_host_status<T, F: FnOnce(&mut HostStatus) -> T>(
         &mut self,
         f: F,
@@ -202,6 +251,51 @@ mod tests {
 
         temp_dir.close().unwrap();
     }
+
+    #[test]
+    fn test_open_read_only() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("db.sqlite");
+
+        // Read-only mode never creates the datastore
+        super::DataStore::open_read_only(&path).unwrap_err();
+        assert!(!path.exists());
+
+        let _ = super::DataStore::make_datastore(&path).unwrap();
+        let mut datastore = super::DataStore::open_read_only(&path).unwrap();
+        assert!(datastore.host_status().is_management_os);
+
+        // Updating the Host Status fails
+        datastore
+            .with_host_status(|host_status| host_status.is_management_os = false)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_history() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("db.sqlite");
+
+        let _ = super::DataStore::make_datastore(&path).unwrap();
+        let mut datastore = super::DataStore::open(&path).unwrap();
+        assert!(datastore.history().unwrap().is_empty());
+
+        datastore
+            .with_host_status(|host_status| host_status.is_management_os = false)
+            .unwrap();
+        datastore
+            .with_host_status(|host_status| host_status.config_commit = Some("abc".into()))
+            .unwrap();
+
+        let history = datastore.history().unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(!history[0].is_management_os);
+        assert_eq!(history[0].config_commit, None);
+        assert_eq!(history[1], *datastore.host_status());
+
+        datastore.close();
+        datastore.history().unwrap_err();
+    }
 }
 
 #[cfg(feature = "functional-test")]
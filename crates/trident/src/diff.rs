@@ -0,0 +1,348 @@
+//! Differences between Host Configurations.
+//!
+//! The diff compares a proposed Host Configuration with the one currently applied to the host,
+//! field by field, and tells for each change which kind of servicing applies it. It is derived
+//! from the Host Configurations alone, without touching the host.
+
+use std::fmt::{Display, Formatter, Result as FmtResult, Write};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use trident_api::{
+    config::{HostConfiguration, UpdateScope},
+    error::{InternalError, ReportError, TridentError},
+};
+
+/// Differences between the Host Configuration currently applied and a proposed one.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Diff {
+    /// Servicing required to apply all the changes.
+    pub servicing: Servicing,
+    pub changes: Vec<Change>,
+}
+
+/// Single changed field of the Host Configuration.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Change {
+    /// Path of the field, e.g. `os.users[0].name`.
+    pub path: String,
+
+    /// Value in the Host Configuration currently applied, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<Value>,
+
+    /// Value in the proposed Host Configuration, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<Value>,
+
+    /// Servicing that applies the change.
+    pub servicing: Servicing,
+
+    /// Section to pass to `trident update --only` to hot-apply the change, if it can be.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<UpdateScope>,
+}
+
+/// Servicing that applies a change, from the least to the most disruptive.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Servicing {
+    /// Nothing to apply, e.g. the field is only used during clean install.
+    NoOp,
+
+    /// Applied to the running OS by a scoped update, without a reboot.
+    HotApply,
+
+    /// Applied by staging an A/B update of the OS image.
+    AbUpdate,
+
+    /// Only applied by a clean install.
+    CleanInstall,
+}
+
+impl Display for Servicing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.pad(match self {
+            Servicing::NoOp => "no-op",
+            Servicing::HotApply => "hot-apply",
+            Servicing::AbUpdate => "ab-update",
+            Servicing::CleanInstall => "clean-install",
+        })
+    }
+}
+
+/// Fields hot-applied by scoped updates, with their update scope. Must match the sections
+/// applied by `scoped_host_config`, except for `storage`, which scoped updates never apply.
+const HOT_APPLIED_FIELDS: &[(&str, UpdateScope)] = &[
+    ("os.sysexts", UpdateScope::Sysexts),
+    ("os.confexts", UpdateScope::Sysexts),
+    ("scripts", UpdateScope::Scripts),
+    ("os.additionalFiles", UpdateScope::Scripts),
+    ("os.users", UpdateScope::Osconfig),
+    ("os.hostname", UpdateScope::Osconfig),
+    ("os.modules", UpdateScope::Osconfig),
+    ("os.services", UpdateScope::Osconfig),
+    ("os.kernelCommandLine", UpdateScope::Osconfig),
+    ("os.selinux", UpdateScope::Osconfig),
+    ("os.netplan", UpdateScope::Network),
+];
+
+/// Fields only used during clean install, which later servicings ignore.
+const CLEAN_INSTALL_ONLY_FIELDS: &[&str] = &["managementOs"];
+
+/// Fields that can only be changed by a clean install.
+const CLEAN_INSTALL_FIELDS: &[&str] = &["storage"];
+
+impl Diff {
+    /// Compares `new_host_config` with `old_host_config`, the Host Configuration currently
+    /// applied to the host, or `None` if the host has not been provisioned by Trident.
+    pub(crate) fn new(
+        new_host_config: &HostConfiguration,
+        old_host_config: Option<&HostConfiguration>,
+    ) -> Result<Self, TridentError> {
+        let to_value = |host_config: &HostConfiguration| {
+            serde_json::to_value(host_config).structured(InternalError::SerializeError)
+        };
+        let provisioned = old_host_config.is_some();
+        let old = to_value(old_host_config.unwrap_or(&HostConfiguration::default()))?;
+        let new = to_value(new_host_config)?;
+
+        let mut changes = Vec::new();
+        compare("", Some(&old), Some(&new), &mut |path, old, new| {
+            let (servicing, scope) = if provisioned {
+                classify(&path)
+            } else {
+                (Servicing::CleanInstall, None)
+            };
+            changes.push(Change {
+                path,
+                old: old.cloned(),
+                new: new.cloned(),
+                servicing,
+                scope,
+            });
+        });
+
+        Ok(Diff {
+            servicing: changes
+                .iter()
+                .map(|change| change.servicing)
+                .max()
+                .unwrap_or(Servicing::NoOp),
+            changes,
+        })
+    }
+
+    /// Renders the diff as a list of changes, with their servicing.
+    pub(crate) fn to_text(&self) -> String {
+        let mut text = format!("Servicing: {}\n", self.servicing);
+        for change in &self.changes {
+            let (marker, value) = match (&change.old, &change.new) {
+                (None, Some(new)) => ('+', new.to_string()),
+                (Some(old), None) => ('-', old.to_string()),
+                (Some(old), Some(new)) => ('~', format!("{old} -> {new}")),
+                (None, None) => continue,
+            };
+            let _ = write!(
+                text,
+                "{marker} {}: {value} [{}",
+                change.path, change.servicing
+            );
+            if let Some(scope) = change.scope {
+                let _ = write!(text, " with --only {}", scope_name(scope));
+            }
+            text.push_str("]\n");
+        }
+        text
+    }
+}
+
+/// Reports the differences between `old` and `new` at `path` to `report`. Mappings are compared
+/// key by key, a missing mapping being empty, and sequences item by item. Other values are
+/// compared as a whole.
+fn compare(
+    path: &str,
+    old: Option<&Value>,
+    new: Option<&Value>,
+    report: &mut impl FnMut(String, Option<&Value>, Option<&Value>),
+) {
+    match (old, new) {
+        (Some(Value::Object(old)), Some(Value::Object(new))) => {
+            compare_mappings(path, old, new, report)
+        }
+        (None, Some(Value::Object(new))) => compare_mappings(path, &Map::new(), new, report),
+        (Some(Value::Object(old)), None) => compare_mappings(path, old, &Map::new(), report),
+        (Some(Value::Array(old)), Some(Value::Array(new))) => {
+            for index in 0..old.len().max(new.len()) {
+                compare(
+                    &format!("{path}[{index}]"),
+                    old.get(index),
+                    new.get(index),
+                    report,
+                );
+            }
+        }
+        (old, new) if old != new => report(path.into(), old, new),
+        _ => {}
+    }
+}
+
+fn compare_mappings(
+    path: &str,
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+    report: &mut impl FnMut(String, Option<&Value>, Option<&Value>),
+) {
+    let mut keys = old.keys().chain(new.keys()).collect::<Vec<_>>();
+    keys.sort();
+    keys.dedup();
+    for key in keys {
+        let path = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{path}.{key}")
+        };
+        compare(&path, old.get(key), new.get(key), report);
+    }
+}
+
+/// Returns the servicing that applies a change to the field at `path` of the Host Configuration
+/// of a provisioned host, and the update scope to hot-apply it, if any.
+fn classify(path: &str) -> (Servicing, Option<UpdateScope>) {
+    if let Some((_, scope)) = HOT_APPLIED_FIELDS
+        .iter()
+        .find(|(field, _)| is_in_field(path, field))
+    {
+        (Servicing::HotApply, Some(*scope))
+    } else if CLEAN_INSTALL_FIELDS
+        .iter()
+        .any(|field| is_in_field(path, field))
+    {
+        (Servicing::CleanInstall, None)
+    } else if CLEAN_INSTALL_ONLY_FIELDS
+        .iter()
+        .any(|field| is_in_field(path, field))
+    {
+        (Servicing::NoOp, None)
+    } else {
+        (Servicing::AbUpdate, None)
+    }
+}
+
+/// Returns whether `path` is the path of `field` or of one of its descendants.
+fn is_in_field(path: &str, field: &str) -> bool {
+    path.strip_prefix(field)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '[']))
+}
+
+/// Returns the name of `scope` for `trident update --only`.
+fn scope_name(scope: UpdateScope) -> &'static str {
+    match scope {
+        UpdateScope::Sysexts => "sysexts",
+        UpdateScope::Scripts => "scripts",
+        UpdateScope::Osconfig => "osconfig",
+        UpdateScope::Storage => "storage",
+        UpdateScope::Network => "network",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use trident_api::config::{ImageSha384, OsImage};
+
+    #[test]
+    fn test_is_in_field() {
+        assert!(is_in_field("os.users", "os.users"));
+        assert!(is_in_field("os.users[0].name", "os.users"));
+        assert!(is_in_field("os.selinux.mode", "os.selinux"));
+        assert!(!is_in_field("os.usersExtra", "os.users"));
+        assert!(!is_in_field("os", "os.users"));
+    }
+
+    #[test]
+    fn test_diff_no_changes() {
+        let host_config = HostConfiguration::default();
+        let diff = Diff::new(&host_config, Some(&host_config)).unwrap();
+        assert_eq!(diff.servicing, Servicing::NoOp);
+        assert!(diff.changes.is_empty());
+        assert_eq!(diff.to_text(), "Servicing: no-op\n");
+    }
+
+    #[test]
+    fn test_diff_provisioned() {
+        let mut old_host_config = HostConfiguration::default();
+        old_host_config.os.hostname = Some("edge-1".into());
+
+        let mut host_config = old_host_config.clone();
+        host_config.os.hostname = Some("edge-2".into());
+        host_config.os.packages = vec!["vim".into()];
+
+        let diff = Diff::new(&host_config, Some(&old_host_config)).unwrap();
+        assert_eq!(diff.servicing, Servicing::AbUpdate);
+        assert_eq!(
+            diff.changes,
+            vec![
+                Change {
+                    path: "os.hostname".into(),
+                    old: Some("edge-1".into()),
+                    new: Some("edge-2".into()),
+                    servicing: Servicing::HotApply,
+                    scope: Some(UpdateScope::Osconfig),
+                },
+                Change {
+                    path: "os.packages".into(),
+                    old: None,
+                    new: Some(serde_json::json!(["vim"])),
+                    servicing: Servicing::AbUpdate,
+                    scope: None,
+                },
+            ]
+        );
+        assert_eq!(
+            diff.to_text(),
+            "Servicing: ab-update\n\
+            ~ os.hostname: \"edge-1\" -> \"edge-2\" [hot-apply with --only osconfig]\n\
+            + os.packages: [\"vim\"] [ab-update]\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_not_provisioned() {
+        let host_config = HostConfiguration {
+            image: Some(OsImage {
+                url: "https://example.com/os.cosi".parse().unwrap(),
+                sha384: ImageSha384::Ignored,
+            }),
+            ..Default::default()
+        };
+
+        let diff = Diff::new(&host_config, None).unwrap();
+        assert_eq!(diff.servicing, Servicing::CleanInstall);
+        assert_eq!(
+            diff.changes
+                .iter()
+                .map(|change| change.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["image.sha384", "image.url"]
+        );
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify("os.netplan.ethernets"),
+            (Servicing::HotApply, Some(UpdateScope::Network))
+        );
+        assert_eq!(
+            classify("storage.disks[0].device"),
+            (Servicing::CleanInstall, None)
+        );
+        assert_eq!(classify("managementOs.users"), (Servicing::NoOp, None));
+        assert_eq!(classify("image.url"), (Servicing::AbUpdate, None));
+    }
+}
@@ -0,0 +1,440 @@
+//! HTTP endpoints Trident reports to: the orchestrator (phonehome), the logstream and tracestream
+//! servers, and the fleet registration endpoint.
+//!
+//! Each endpoint can be configured with its own TLS settings in the Host Configuration, so that
+//! Trident can talk to management planes using a private PKI: custom root certificates, public key
+//! pinning and client certificates.
+
+use std::{fs, io::BufReader, path::Path, sync::Arc, time::Duration};
+
+use anyhow::{bail, Context, Error};
+use log::warn;
+use openssl::{base64, sha, x509::X509};
+use reqwest::{
+    blocking::{Client, ClientBuilder, RequestBuilder, Response},
+    Certificate, Identity, Url,
+};
+use rustls::{
+    client::{
+        danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        WebPkiServerVerifier,
+    },
+    crypto::ring,
+    pki_types::{CertificateDer, ServerName, UnixTime},
+    ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+
+use trident_api::config::{EndpointTls, PINNED_PUBLIC_KEY_PREFIX};
+
+/// HTTP endpoint Trident sends POST requests to, with its TLS settings applied.
+#[derive(Clone, Debug)]
+pub struct HttpEndpoint {
+    url: String,
+    client: Client,
+}
+
+impl HttpEndpoint {
+    /// Creates an endpoint for `url`, using the TLS settings in `tls` if any. `timeout` applies to
+    /// each request.
+    pub fn new(
+        url: String,
+        tls: Option<&EndpointTls>,
+        timeout: Option<Duration>,
+    ) -> Result<Self, Error> {
+        Url::parse(&url).with_context(|| format!("Failed to parse URL '{url}'"))?;
+
+        let mut builder = Client::builder();
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(tls) = tls {
+            builder = configure_tls(builder, tls)?;
+        }
+
+        Ok(Self {
+            client: builder.build().context("Failed to create HTTP client")?,
+            url,
+        })
+    }
+
+    /// Returns the URL of the endpoint.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Sends `body` to the endpoint with a POST request.
+    pub fn post(&self, body: Vec<u8>) -> Result<Response, Error> {
+        self.send(self.client.post(&self.url).body(body))
+    }
+
+    /// Sends the JSON document `body` to the endpoint with a POST request.
+    pub fn post_json(&self, body: Vec<u8>) -> Result<Response, Error> {
+        self.send(
+            self.client
+                .post(&self.url)
+                .header("Content-Type", "application/json")
+                .body(body),
+        )
+    }
+
+    fn send(&self, request: RequestBuilder) -> Result<Response, Error> {
+        request
+            .send()
+            .with_context(|| format!("Failed to send request to '{}'", self.url))
+    }
+}
+
+/// Applies the TLS settings of an endpoint to a client builder.
+fn configure_tls(builder: ClientBuilder, tls: &EndpointTls) -> Result<ClientBuilder, Error> {
+    // Pins must be checked while the connection is established, so that nothing is sent to a
+    // server presenting an unexpected key. That requires a custom certificate verifier, which only
+    // the rustls backend supports.
+    if !tls.pinned_public_keys.is_empty() {
+        return Ok(builder.use_preconfigured_tls(pinned_tls_config(tls)?));
+    }
+
+    let mut builder = builder;
+    if let Some(path) = &tls.ca_certificate_path {
+        let certificates = Certificate::from_pem_bundle(&read(path)?).with_context(|| {
+            format!("Failed to parse root certificates in '{}'", path.display())
+        })?;
+        if certificates.is_empty() {
+            bail!("No root certificates found in '{}'", path.display());
+        }
+        builder = certificates.into_iter().fold(
+            builder.tls_built_in_root_certs(false),
+            |builder, certificate| builder.add_root_certificate(certificate),
+        );
+    }
+
+    if let Some((certificate_path, key_path)) = client_identity_paths(tls)? {
+        let identity = Identity::from_pkcs8_pem(&read(certificate_path)?, &read(key_path)?)
+            .with_context(|| {
+                format!(
+                    "Failed to load client certificate '{}' with key '{}'",
+                    certificate_path.display(),
+                    key_path.display()
+                )
+            })?;
+        builder = builder.identity(identity);
+    }
+
+    Ok(builder)
+}
+
+/// Builds the rustls configuration of an endpoint with pinned public keys, applying its root and
+/// client certificates.
+fn pinned_tls_config(tls: &EndpointTls) -> Result<ClientConfig, Error> {
+    let provider = Arc::new(ring::default_provider());
+
+    let mut roots = RootCertStore::empty();
+    match &tls.ca_certificate_path {
+        Some(path) => {
+            let certificates = read_certificates(path)?;
+            if certificates.is_empty() {
+                bail!("No root certificates found in '{}'", path.display());
+            }
+            for certificate in certificates {
+                roots.add(certificate).with_context(|| {
+                    format!("Failed to add root certificate from '{}'", path.display())
+                })?;
+            }
+        }
+        None => {
+            let native = rustls_native_certs::load_native_certs();
+            for error in native.errors {
+                warn!("Failed to load system root certificate: {error}");
+            }
+            roots.add_parsable_certificates(native.certs);
+        }
+    }
+
+    let verifier = PinnedServerCertVerifier {
+        inner: WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+            .build()
+            .context("Failed to create certificate verifier")?,
+        pins: tls.pinned_public_keys.clone(),
+    };
+    let builder = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .context("Failed to select TLS protocol versions")?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier));
+
+    match client_identity_paths(tls)? {
+        Some((certificate_path, key_path)) => {
+            let key = rustls_pemfile::private_key(&mut BufReader::new(read(key_path)?.as_slice()))
+                .with_context(|| {
+                    format!("Failed to parse private key in '{}'", key_path.display())
+                })?
+                .with_context(|| format!("No private key found in '{}'", key_path.display()))?;
+            builder
+                .with_client_auth_cert(read_certificates(certificate_path)?, key)
+                .with_context(|| {
+                    format!(
+                        "Failed to load client certificate '{}' with key '{}'",
+                        certificate_path.display(),
+                        key_path.display()
+                    )
+                })
+        }
+        None => Ok(builder.with_no_client_auth()),
+    }
+}
+
+/// Returns the paths of the client certificate and key of an endpoint, if it authenticates with a
+/// client certificate. Setting only one of them is an error rather than disabling client
+/// authentication.
+fn client_identity_paths(tls: &EndpointTls) -> Result<Option<(&Path, &Path)>, Error> {
+    match (
+        tls.client_certificate_path.as_deref(),
+        tls.client_key_path.as_deref(),
+    ) {
+        (Some(certificate_path), Some(key_path)) => Ok(Some((certificate_path, key_path))),
+        (None, None) => Ok(None),
+        _ => bail!("Client certificate and client key must be set together"),
+    }
+}
+
+/// Verifies server certificates against the root certificates, then checks that the public key
+/// of the server certificate matches one of the pins.
+#[derive(Debug)]
+struct PinnedServerCertVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    pins: Vec<String>,
+}
+
+impl ServerCertVerifier for PinnedServerCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+        check_pin(end_entity, &self.pins).map_err(|e| rustls::Error::General(format!("{e:#}")))?;
+        Ok(verified)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, Error> {
+    fs::read(path).with_context(|| format!("Failed to read '{}'", path.display()))
+}
+
+/// Reads the certificates of a PEM file.
+fn read_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>, Error> {
+    rustls_pemfile::certs(&mut BufReader::new(read(path)?.as_slice()))
+        .collect::<Result<_, _>>()
+        .with_context(|| format!("Failed to parse certificates in '{}'", path.display()))
+}
+
+/// Returns the pin of the public key of a DER-encoded certificate: the base64-encoded SHA256 hash of
+/// its DER-encoded SubjectPublicKeyInfo, prefixed with `sha256//`.
+fn public_key_pin(certificate: &[u8]) -> Result<String, Error> {
+    let public_key = X509::from_der(certificate)
+        .context("Failed to parse certificate")?
+        .public_key()
+        .context("Failed to read public key of certificate")?
+        .public_key_to_der()
+        .context("Failed to encode public key of certificate")?;
+    Ok(format!(
+        "{PINNED_PUBLIC_KEY_PREFIX}{}",
+        base64::encode_block(&sha::sha256(&public_key))
+    ))
+}
+
+/// Checks that the public key of a DER-encoded certificate matches one of `pins`.
+fn check_pin(certificate: &[u8], pins: &[String]) -> Result<(), Error> {
+    let pin = public_key_pin(certificate)?;
+    if !pins.contains(&pin) {
+        bail!("Public key '{pin}' matches none of the pinned public keys");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread::{self, JoinHandle},
+    };
+
+    use openssl::{
+        asn1::Asn1Time,
+        bn::BigNum,
+        hash::MessageDigest,
+        pkey::{PKey, Private},
+        ssl::{SslAcceptor, SslMethod},
+        x509::{extension::SubjectAlternativeName, X509NameBuilder},
+    };
+    use tempfile::NamedTempFile;
+
+    /// Returns a self-signed certificate for `localhost`, its key and the pin of its public key.
+    fn self_signed() -> (X509, PKey<Private>, String) {
+        let key = PKey::generate_ed25519().unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "localhost").unwrap();
+        let name = name.build();
+
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder
+            .set_serial_number(&BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap())
+            .unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        builder.set_pubkey(&key).unwrap();
+        let san = SubjectAlternativeName::new()
+            .dns("localhost")
+            .build(&builder.x509v3_context(None, None))
+            .unwrap();
+        builder.append_extension(san).unwrap();
+        builder.sign(&key, MessageDigest::null()).unwrap();
+
+        let pin = format!(
+            "sha256//{}",
+            base64::encode_block(&sha::sha256(&key.public_key_to_der().unwrap()))
+        );
+        (builder.build(), key, pin)
+    }
+
+    /// Serves a single HTTPS connection on localhost with `certificate`, and returns its port and
+    /// a handle resolving to the bytes of the request received, if any.
+    fn serve_once(certificate: &X509, key: &PKey<Private>) -> (u16, JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+        acceptor.set_certificate(certificate).unwrap();
+        acceptor.set_private_key(key).unwrap();
+        let acceptor = acceptor.build();
+
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let Ok(mut stream) = acceptor.accept(stream) else {
+                return Vec::new();
+            };
+            let mut request = vec![0; 4096];
+            let read = stream.read(&mut request).unwrap_or(0);
+            request.truncate(read);
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+            request
+        });
+        (port, handle)
+    }
+
+    #[test]
+    fn test_check_pin() {
+        let (certificate, _, pin) = self_signed();
+        let (_, _, other_pin) = self_signed();
+        let der = certificate.to_der().unwrap();
+
+        assert_eq!(public_key_pin(&der).unwrap(), pin);
+        check_pin(&der, &[other_pin.clone(), pin]).unwrap();
+        check_pin(&der, &[other_pin]).unwrap_err();
+        check_pin(b"not a certificate", &[]).unwrap_err();
+    }
+
+    #[test]
+    fn test_new() {
+        HttpEndpoint::new("not a url".into(), None, None).unwrap_err();
+
+        let endpoint = HttpEndpoint::new("https://localhost/phonehome".into(), None, None).unwrap();
+        assert_eq!(endpoint.url(), "https://localhost/phonehome");
+
+        let (certificate, _, pin) = self_signed();
+        let ca = NamedTempFile::new().unwrap();
+        fs::write(ca.path(), certificate.to_pem().unwrap()).unwrap();
+        let mut tls = EndpointTls {
+            ca_certificate_path: Some(ca.path().into()),
+            ..Default::default()
+        };
+        HttpEndpoint::new("https://localhost/phonehome".into(), Some(&tls), None).unwrap();
+        tls.pinned_public_keys = vec![pin];
+        HttpEndpoint::new("https://localhost/phonehome".into(), Some(&tls), None).unwrap();
+
+        // Root certificates must exist and be valid PEM, with or without pinned public keys.
+        let empty = NamedTempFile::new().unwrap();
+        for pinned_public_keys in [vec![], tls.pinned_public_keys.clone()] {
+            tls.pinned_public_keys = pinned_public_keys;
+            tls.ca_certificate_path = Some(empty.path().into());
+            HttpEndpoint::new("https://localhost/phonehome".into(), Some(&tls), None).unwrap_err();
+            tls.ca_certificate_path = Some("/nonexistent/ca.pem".into());
+            HttpEndpoint::new("https://localhost/phonehome".into(), Some(&tls), None).unwrap_err();
+        }
+
+        // A client certificate without a key is rejected, with or without pinned public keys.
+        tls.ca_certificate_path = Some(ca.path().into());
+        tls.client_certificate_path = Some(ca.path().into());
+        for pinned_public_keys in [vec![], tls.pinned_public_keys.clone()] {
+            tls.pinned_public_keys = pinned_public_keys;
+            HttpEndpoint::new("https://localhost/phonehome".into(), Some(&tls), None).unwrap_err();
+        }
+    }
+
+    #[test]
+    fn test_pinned_public_keys() {
+        let (certificate, key, pin) = self_signed();
+        let (_, _, other_pin) = self_signed();
+        let ca = NamedTempFile::new().unwrap();
+        fs::write(ca.path(), certificate.to_pem().unwrap()).unwrap();
+        let mut tls = EndpointTls {
+            ca_certificate_path: Some(ca.path().into()),
+            pinned_public_keys: vec![other_pin],
+            ..Default::default()
+        };
+
+        // A server whose key matches none of the pins is rejected during the handshake, before
+        // the request is sent.
+        let (port, server) = serve_once(&certificate, &key);
+        let url = format!("https://localhost:{port}/phonehome");
+        let endpoint = HttpEndpoint::new(url, Some(&tls), None).unwrap();
+        endpoint.post(b"status".to_vec()).unwrap_err();
+        assert!(server.join().unwrap().is_empty());
+
+        tls.pinned_public_keys.push(pin);
+        let (port, server) = serve_once(&certificate, &key);
+        let url = format!("https://localhost:{port}/phonehome");
+        let endpoint = HttpEndpoint::new(url, Some(&tls), None).unwrap();
+        endpoint.post(b"status".to_vec()).unwrap();
+        assert!(server.join().unwrap().starts_with(b"POST /phonehome"));
+    }
+}
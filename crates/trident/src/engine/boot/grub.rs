@@ -1,7 +1,7 @@
 use std::{fs, io::Write, path::Path};
 
 use anyhow::{bail, Context, Error};
-use log::{debug, info, trace};
+use log::{debug, info, trace, warn};
 use tempfile::NamedTempFile;
 use uuid::Uuid;
 
@@ -13,7 +13,7 @@ use osutils::{
     osrelease::{AzureLinuxRelease, Distro, OsRelease},
 };
 use trident_api::{
-    config::Selinux,
+    config::{BootMenu, Selinux},
     constants::{
         BOOT_MOUNT_POINT_PATH, ESP_EFI_DIRECTORY, ESP_MOUNT_POINT_PATH, GRUB2_CONFIG_FILENAME,
         GRUB2_CONFIG_RELATIVE_PATH, ROOT_MOUNT_POINT_PATH, TRIDENT_OVERLAY_LOWER_RELATIVE_PATH,
@@ -92,6 +92,22 @@ pub(super) fn update_configs(ctx: &EngineContext, os_modifier_path: &Path) -> Re
     ))
 }
 
+/// Returns the grub-mkconfig script applying the boot menu settings of the Host Configuration.
+fn boot_menu_script(boot_menu: &BootMenu) -> GrubMkConfigScript {
+    info!("Configuring GRUB boot menu");
+    let mut script = GrubMkConfigScript::new("boot-menu");
+    if let Some(timeout) = boot_menu.timeout {
+        script.set_variable("GRUB_TIMEOUT", timeout.to_string());
+    }
+    if let Some(visibility) = boot_menu.visibility {
+        script.set_variable("GRUB_TIMEOUT_STYLE", visibility.to_string());
+    }
+    if let Some(console_mode) = boot_menu.console_mode {
+        warn!("Ignoring boot menu console mode '{console_mode}', it is not supported by GRUB");
+    }
+    script
+}
+
 /// Updates the GRUB config for Azure Linux 3.0 using OS modifier.
 fn update_grub_config_azl3(
     ctx: &EngineContext,
@@ -111,6 +127,27 @@ fn update_grub_config_azl3(
             .context("Failed to disable default cloud-init network config")?;
     }
 
+    let removed_params = &ctx.spec.os.kernel_command_line.removed_command_line;
+    if !removed_params.is_empty() {
+        info!(
+            "Removing kernel command line parameters: [{}]",
+            removed_params.join(", ")
+        );
+        let mut remove_kernel_params = GrubMkConfigScript::new("kernel-command-line");
+        for param in removed_params {
+            remove_kernel_params.remove_param(param);
+        }
+        remove_kernel_params
+            .write()
+            .context("Failed to remove kernel command line parameters")?;
+    }
+
+    if let Some(boot_menu) = &ctx.spec.os.boot_menu {
+        boot_menu_script(boot_menu)
+            .write()
+            .context("Failed to configure the GRUB boot menu")?;
+    }
+
     debug!("Updating GRUB config for Azure Linux 3.0 with OS modifier");
 
     // OS modifier will read values of verity, selinux, root device, and overlay from original GRUB config
@@ -133,12 +170,10 @@ fn update_grub_config_azl3(
     );
 
     // If SELinux is provided in engine context, overwrite SELinux in GRUB config
-    let selinux_config = ctx
-        .spec
-        .os
-        .selinux
-        .mode
-        .map(|mode| Selinux { mode: Some(mode) });
+    let selinux_config = ctx.spec.os.selinux.mode.map(|mode| Selinux {
+        mode: Some(mode),
+        policy_modules: vec![],
+    });
 
     // If root-verity is provided in engine context, overwrite it in GRUB config
     let root_device_id = ctx
@@ -13,6 +13,7 @@ use trident_api::error::{
     InternalError, ReportError, ServicingError, TridentError, TridentResultExt,
 };
 use trident_api::{
+    config::{BootMenu, MenuVisibility},
     constants::{ESP_EFI_DIRECTORY, ESP_MOUNT_POINT_PATH},
     status::AbVolumeSelection,
 };
@@ -23,11 +24,30 @@ use crate::engine::EngineContext;
 pub const TMP_UKI_NAME: &str = "vmlinuz-0.efi.staged";
 pub const UKI_DIRECTORY: &str = formatcp!("{ESP_EFI_DIRECTORY}/Linux");
 
+/// Path of the systemd-boot loader configuration, relative to the ESP.
+const LOADER_CONFIG_PATH: &str = "loader/loader.conf";
+
+/// First line of the loader configurations written by Trident, so that they are only removed
+/// when Trident wrote them.
+const LOADER_CONFIG_HEADER: &str = "# Boot menu settings of the Host Configuration, set by Trident";
+
 /// Returns the UKI file suffix, given the current active volume and install index.
 fn uki_suffix(ctx: &EngineContext) -> String {
     match ctx.ab_active_volume {
-        Some(AbVolumeSelection::VolumeA) => format!("azlb{}.efi", ctx.install_index),
-        None | Some(AbVolumeSelection::VolumeB) => format!("azla{}.efi", ctx.install_index),
+        Some(AbVolumeSelection::VolumeA) => {
+            volume_suffix(AbVolumeSelection::VolumeB, ctx.install_index)
+        }
+        None | Some(AbVolumeSelection::VolumeB) => {
+            volume_suffix(AbVolumeSelection::VolumeA, ctx.install_index)
+        }
+    }
+}
+
+/// Returns the suffix of the UKI file of `volume`, given the install index.
+fn volume_suffix(volume: AbVolumeSelection, install_index: usize) -> String {
+    match volume {
+        AbVolumeSelection::VolumeA => format!("azla{install_index}.efi"),
+        AbVolumeSelection::VolumeB => format!("azlb{install_index}.efi"),
     }
 }
 
@@ -76,6 +96,51 @@ pub fn prepare_esp_for_uki(root_mount_point: &Path) -> Result<(), Error> {
     Ok(())
 }
 
+/// Applies the boot menu settings of the Host Configuration to systemd-boot, by writing its loader
+/// configuration on the ESP. Without settings, a loader configuration previously written by
+/// Trident is removed, so that systemd-boot defaults apply again.
+pub fn configure_boot_menu(
+    root_mount_point: &Path,
+    boot_menu: Option<&BootMenu>,
+) -> Result<(), Error> {
+    let loader_config_path =
+        join_relative(root_mount_point, ESP_MOUNT_POINT_PATH).join(LOADER_CONFIG_PATH);
+    let Some(boot_menu) = boot_menu else {
+        if fs::read_to_string(&loader_config_path)
+            .is_ok_and(|content| content.starts_with(LOADER_CONFIG_HEADER))
+        {
+            debug!("Removing boot menu settings from the loader configuration");
+            fs::remove_file(&loader_config_path)
+                .context("Failed to remove the loader configuration")?;
+        }
+        return Ok(());
+    };
+
+    let content = render_loader_config(boot_menu);
+    trace!("Loader configuration content:\n{content}");
+    fs::write(&loader_config_path, content).context("Failed to write the loader configuration")
+}
+
+/// Renders the systemd-boot loader configuration for the boot menu settings.
+fn render_loader_config(boot_menu: &BootMenu) -> String {
+    let mut content = format!("{LOADER_CONFIG_HEADER}\n");
+    // systemd-boot has no countdown, and cannot wait for a key with a hidden menu.
+    let timeout = match (boot_menu.visibility, boot_menu.timeout) {
+        (Some(MenuVisibility::Hidden), _) => Some("menu-hidden".to_string()),
+        (Some(MenuVisibility::Menu | MenuVisibility::Countdown), None) => {
+            Some("menu-force".to_string())
+        }
+        (_, timeout) => timeout.map(|timeout| timeout.to_string()),
+    };
+    if let Some(timeout) = timeout {
+        content.push_str(&format!("timeout {timeout}\n"));
+    }
+    if let Some(console_mode) = boot_menu.console_mode {
+        content.push_str(&format!("console-mode {console_mode}\n"));
+    }
+    content
+}
+
 /// Enumerates existing UKIs in the given directory, returning their indices and suffixes.
 fn enumerate_existing_ukis(
     esp_uki_directory: &Path,
@@ -151,6 +216,27 @@ pub fn update_uki_boot_order(
     Ok(())
 }
 
+/// Sets systemd-boot's default boot entry to the UKI of `volume`, e.g. to roll back to the previous
+/// OS after the target OS was committed.
+pub fn set_default_to_volume(
+    esp_dir_path: &Path,
+    install_index: usize,
+    volume: AbVolumeSelection,
+) -> Result<(), TridentError> {
+    let suffix = volume_suffix(volume, install_index);
+    let entry_name = enumerate_existing_ukis(&esp_dir_path.join(UKI_DIRECTORY))
+        .structured(ServicingError::EnumerateUkis)?
+        .into_iter()
+        .filter(|(_, entry_suffix, _)| *entry_suffix == suffix)
+        .max_by_key(|(index, _, _)| *index)
+        .and_then(|(_, _, path)| Some(path.file_name()?.to_str()?.to_string()))
+        .structured(ServicingError::UpdateUki)
+        .message(format!("Found no UKI of {volume:?}"))?;
+
+    debug!("Setting default boot entry to '{entry_name}'");
+    efivar::set_default(&entry_name)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -175,6 +261,9 @@ mod tests {
         ctx.ab_active_volume = None;
         ctx.install_index = 3;
         assert_eq!(uki_suffix(&ctx), "azla3.efi");
+
+        assert_eq!(volume_suffix(AbVolumeSelection::VolumeA, 1), "azla1.efi");
+        assert_eq!(volume_suffix(AbVolumeSelection::VolumeB, 4), "azlb4.efi");
     }
 
     #[test]
@@ -227,6 +316,65 @@ mod tests {
         assert_eq!(content, "type1\n");
     }
 
+    #[test]
+    fn test_render_loader_config() {
+        use trident_api::config::ConsoleMode;
+
+        let render = |timeout, visibility, console_mode| {
+            render_loader_config(&BootMenu {
+                timeout,
+                visibility,
+                console_mode,
+            })
+            .strip_prefix(LOADER_CONFIG_HEADER)
+            .unwrap()
+            .to_string()
+        };
+        assert_eq!(render(None, None, None), "\n");
+        assert_eq!(
+            render(Some(3), None, Some(ConsoleMode::Max)),
+            "\ntimeout 3\nconsole-mode max\n"
+        );
+        assert_eq!(
+            render(Some(3), Some(MenuVisibility::Hidden), None),
+            "\ntimeout menu-hidden\n"
+        );
+        assert_eq!(
+            render(None, Some(MenuVisibility::Countdown), None),
+            "\ntimeout menu-force\n"
+        );
+        assert_eq!(
+            render(Some(0), Some(MenuVisibility::Menu), None),
+            "\ntimeout 0\n"
+        );
+    }
+
+    #[test]
+    fn test_configure_boot_menu() {
+        let root_mount = tempdir().unwrap();
+        prepare_esp_for_uki(root_mount.path()).unwrap();
+        let loader_config_path =
+            join_relative(root_mount.path(), ESP_MOUNT_POINT_PATH).join(LOADER_CONFIG_PATH);
+
+        // A loader configuration not written by Trident is kept.
+        fs::write(&loader_config_path, "timeout 10\n").unwrap();
+        configure_boot_menu(root_mount.path(), None).unwrap();
+        assert!(loader_config_path.exists());
+
+        let boot_menu = BootMenu {
+            timeout: Some(0),
+            ..Default::default()
+        };
+        configure_boot_menu(root_mount.path(), Some(&boot_menu)).unwrap();
+        assert_eq!(
+            fs::read_to_string(&loader_config_path).unwrap(),
+            format!("{LOADER_CONFIG_HEADER}\ntimeout 0\n")
+        );
+
+        configure_boot_menu(root_mount.path(), None).unwrap();
+        assert!(!loader_config_path.exists());
+    }
+
     #[test]
     fn test_enumerate_existing_ukis_empty_directory() {
         let dir = tempdir().unwrap();
@@ -7,7 +7,7 @@ use osutils::{
     block_devices,
     bootloaders::BOOT_EFI,
     efibootmgr::{self, EfiBootManagerOutput},
-    virt,
+    efivar, virt,
 };
 
 use trident_api::{
@@ -30,10 +30,12 @@ struct EspDeviceMetadata {
     path: PathBuf,
 }
 
-/// ESP device enum which can be either a standalone partition or a RAID device
+/// ESP device enum which can be either a standalone partition, a partition mirrored to other ESP
+/// partitions, or a RAID device
 #[derive(Debug, PartialEq, Clone)]
 enum EspDevice {
     Partition(EspDeviceMetadata),
+    Mirrored(Vec<EspDeviceMetadata>),
     Raid(Vec<EspDeviceMetadata>),
 }
 
@@ -204,6 +206,48 @@ pub fn persist_boot_order() -> Result<(), TridentError> {
         .structured(ServicingError::UpdateBootOrder)
 }
 
+/// Makes the host boot the current boot option once more on next boot, without making it the
+/// default. In UKI mode, systemd-boot is also set to boot the current entry once more.
+pub fn set_boot_next_to_current(ctx: &EngineContext) -> Result<(), TridentError> {
+    let bootmgr_output = efibootmgr::list_and_parse_bootmgr_entries()
+        .structured(ServicingError::ListAndParseBootEntries)?;
+    efibootmgr::set_boot_next(&bootmgr_output.boot_current)?;
+    debug!(
+        "Set `BootNext` to current entry '{}'",
+        bootmgr_output.boot_current
+    );
+
+    if ctx.is_uki()? {
+        efivar::set_oneshot(&efivar::read_current_var()?)?;
+    }
+    Ok(())
+}
+
+/// Makes the boot entries of `volume` the default boot option, so that the host boots `volume`
+/// from now on. In UKI mode, systemd-boot's default entry is also set to the UKI of `volume`.
+pub fn set_default_to_volume(
+    ctx: &EngineContext,
+    esp_path: &Path,
+    volume: AbVolumeSelection,
+) -> Result<(), TridentError> {
+    let [entry_label_a, entry_label_b] = get_entry_labels(ctx.install_index)?;
+    let entry_label = match volume {
+        AbVolumeSelection::VolumeA => entry_label_a,
+        AbVolumeSelection::VolumeB => entry_label_b,
+    };
+    let bootmgr_output = efibootmgr::list_and_parse_bootmgr_entries()
+        .structured(ServicingError::ListAndParseBootEntries)?;
+    let entries = bootmgr_output.get_entries_with_label(&entry_label);
+    debug!("Found boot entries with label '{entry_label}': {entries:?}");
+    update_boot_order(entries, &BootOrderPosition::First)
+        .structured(ServicingError::UpdateBootOrder)?;
+
+    if ctx.is_uki()? {
+        uki::set_default_to_volume(esp_path, ctx.install_index, volume)?;
+    }
+    Ok(())
+}
+
 /// Returns the boot entry labels of the A/B volumes.
 pub fn get_entry_labels(install_index: usize) -> Result<[String; 2], TridentError> {
     let entry_label_a = boot::make_esp_dir_name(install_index, AbVolumeSelection::VolumeA);
@@ -334,8 +378,8 @@ pub fn create_and_update_boot_variables_after_rebuilding(
 ) -> Result<(), TridentError> {
     let esp_device_info = get_esp_device_info(ctx).structured(ServicingError::GetEspDeviceInfo)?;
 
-    if let EspDevice::Partition(_) = esp_device_info {
-        // No need to create boot entries for standalone ESP partition.
+    if let EspDevice::Partition(_) | EspDevice::Mirrored(_) = esp_device_info {
+        // No need to create boot entries for ESP partitions, which are not rebuilt.
         return Ok(());
     }
     // If Esp device is on RAID1, we need to create boot entries for all the RAID1 partitions on the
@@ -355,6 +399,8 @@ pub fn create_and_update_boot_variables_after_rebuilding(
 
 /// Parses the ESP device info and returns the ESP device metadata:
 /// - If the ESP device is a standalone partition, the metadata for the partition is returned.
+/// - If the ESP device is a mirrored partition, the metadata for the partition and its mirrors is
+///   returned.
 /// - If the ESP device is on RAID1, the metadata for the RAID1 partitions is returned.
 fn parse_esp_metadata(
     ctx: &EngineContext,
@@ -362,6 +408,7 @@ fn parse_esp_metadata(
 ) -> Result<Vec<EspDeviceMetadata>, TridentError> {
     Ok(match esp_device_info {
         EspDevice::Partition(esp_device_metadata) => vec![esp_device_metadata],
+        EspDevice::Mirrored(esp_device_metadata) => esp_device_metadata,
         EspDevice::Raid(esp_device_metadata) => {
             let esp_device_id =
                 get_esp_device_id(ctx).structured(InternalError::GetEspDeviceInfo)?;
@@ -396,8 +443,8 @@ fn create_boot_entry_helper(
     entry_label_new: String,
     bootloader_path_new: PathBuf,
 ) -> Result<Vec<String>, TridentError> {
-    // Skip duplicate check for RAID1 ESP as we create boot entries with same label for
-    // all the RAID1 partitions.
+    // Skip duplicate check for RAID1 and mirrored ESPs as we create boot entries with same label
+    // for all their partitions.
     let skip_duplicate = esp_device_metadata.len() > 1;
     esp_device_metadata
         .into_iter()
@@ -501,6 +548,8 @@ fn get_esp_device_id(ctx: &EngineContext) -> Result<BlockDeviceId, Error> {
 /// TODO: https://dev.azure.com/mariner-org/ECF/_workitems/edit/9622
 /// TODO: https://dev.azure.com/mariner-org/ECF/_workitems/edit/9411
 /// If the ESP partition is on RAID1, the information for the RAID1 partitions will be returned.
+/// If the ESP partition is mirrored, the information for the ESP partition followed by its
+/// mirrors will be returned.
 ///
 /// # Arguments
 /// - `ctx` - A reference to the `EngineContext` which contains the host's configuration.
@@ -529,6 +578,14 @@ fn get_esp_device_info(ctx: &EngineContext) -> Result<EspDevice, Error> {
                 .map(|id| get_esp_metadata(id, ctx))
                 .collect::<Result<_, _>>()?,
         ))
+    } else if !ctx.spec.storage.esp_mirrors.is_empty() {
+        // ESP is a partition mirrored to other ESP partitions
+        Ok(EspDevice::Mirrored(
+            std::iter::once(&esp_device_id)
+                .chain(&ctx.spec.storage.esp_mirrors)
+                .map(|id| get_esp_metadata(id, ctx))
+                .collect::<Result<_, _>>()?,
+        ))
     } else {
         // ESP is a standalone partition, not on RAID
         Ok(EspDevice::Partition(get_esp_metadata(&esp_device_id, ctx)?))
@@ -0,0 +1,112 @@
+//! Hardware burn-in run before a clean install.
+//!
+//! All enabled tests run even when an earlier one fails, so that a single run reports every
+//! problem with the machine.
+
+use std::{fs, path::Path};
+
+use log::{error, info};
+
+use osutils::{memtester, smartctl};
+use trident_api::{
+    config::{BurnIn, HostConfiguration},
+    error::{ServicingError, TridentError},
+};
+
+use crate::subsystems::network::SYSFS_NET_DIR;
+
+/// Runs the burn-in tests configured in `health.burnIn`, if any. Returns an error listing every
+/// failed test, in which case the host must not be provisioned.
+#[tracing::instrument(skip_all)]
+pub(super) fn run(host_config: &HostConfiguration) -> Result<(), TridentError> {
+    let Some(burn_in) = host_config.health.burn_in.as_ref() else {
+        return Ok(());
+    };
+
+    info!("Running hardware burn-in");
+    let mut failures = Vec::new();
+
+    if let Some(size_mib) = burn_in.memory_test_mib {
+        if let Err(e) = memtester::test(size_mib) {
+            error!("{e:?}");
+            failures.push(format!("memory test of {size_mib} MiB failed"));
+        }
+    }
+
+    if burn_in.smart_test {
+        for disk in &host_config.storage.disks {
+            match smartctl::short_self_test(&disk.device) {
+                Ok(true) => {}
+                Ok(false) => info!(
+                    "Skipped SMART short self-test on disk '{}', which does not support SMART",
+                    disk.id
+                ),
+                Err(e) => {
+                    error!("{e:?}");
+                    failures.push(format!(
+                        "SMART short self-test failed on disk '{}'",
+                        disk.id
+                    ));
+                }
+            }
+        }
+    }
+
+    failures.extend(check_links(burn_in, SYSFS_NET_DIR));
+
+    if !failures.is_empty() {
+        return Err(TridentError::new(ServicingError::BurnInFailed {
+            failures: failures.join("; "),
+        }));
+    }
+
+    info!("Hardware burn-in passed");
+    Ok(())
+}
+
+/// Returns a failure for each interface in `linkInterfaces` that does not have a link.
+fn check_links(burn_in: &BurnIn, sysfs_net_dir: impl AsRef<Path>) -> Vec<String> {
+    burn_in
+        .link_interfaces
+        .iter()
+        .filter(|interface| {
+            // Reading the carrier of an interface that is administratively down fails, which
+            // counts as no link as well.
+            fs::read_to_string(sysfs_net_dir.as_ref().join(interface).join("carrier"))
+                .map(|carrier| carrier.trim() != "1")
+                .unwrap_or(true)
+        })
+        .map(|interface| format!("network interface '{interface}' has no link"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_without_burn_in() {
+        run(&HostConfiguration::default()).unwrap();
+    }
+
+    #[test]
+    fn test_check_links() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        for (interface, carrier) in [("eth0", "1\n"), ("eth1", "0\n")] {
+            fs::create_dir(temp_dir.path().join(interface)).unwrap();
+            fs::write(temp_dir.path().join(interface).join("carrier"), carrier).unwrap();
+        }
+
+        let burn_in = BurnIn {
+            link_interfaces: vec!["eth0".into(), "eth1".into(), "eth2".into()],
+            ..Default::default()
+        };
+        assert_eq!(
+            check_links(&burn_in, temp_dir.path()),
+            vec![
+                "network interface 'eth1' has no link",
+                "network interface 'eth2' has no link"
+            ]
+        );
+    }
+}
@@ -1,8 +1,4 @@
-use std::{
-    path::{Path, PathBuf},
-    sync::MutexGuard,
-    time::Instant,
-};
+use std::{path::PathBuf, sync::MutexGuard, time::Instant};
 
 use log::{debug, error, info, warn};
 #[cfg(feature = "grpc-dangerous")]
@@ -18,7 +14,7 @@ use trident_api::{
     config::{HostConfiguration, Operations},
     constants::{
         internal_params::{DISABLE_MEDIA_EJECTION, ENABLE_UKI_SUPPORT, NO_TRANSITION},
-        ESP_MOUNT_POINT_PATH, ROOT_MOUNT_POINT_PATH, UPDATE_ROOT_PATH,
+        ESP_MOUNT_POINT_PATH, ROOT_MOUNT_POINT_PATH,
     },
     error::{
         InitializationError, InternalError, InvalidInputError, ReportError, ServicingError,
@@ -29,7 +25,9 @@ use trident_api::{
 
 use crate::{
     datastore::DataStore,
-    engine::{self, bootentries, install_index, storage, EngineContext, SUBSYSTEMS},
+    engine::{
+        self, bootentries, burn_in, install_index, provenance, storage, EngineContext, SUBSYSTEMS,
+    },
     monitor_metrics,
     osimage::OsImage,
     subsystems::esp,
@@ -39,7 +37,7 @@ use crate::{
 #[cfg(feature = "grpc-dangerous")]
 use crate::{grpc, GrpcSender};
 
-use super::{NewrootMount, Subsystem};
+use super::{EnginePaths, NewrootMount, Subsystem};
 
 #[tracing::instrument(skip_all)]
 pub(crate) fn clean_install(
@@ -48,22 +46,27 @@ pub(crate) fn clean_install(
     allowed_operations: &Operations,
     multiboot: bool,
     image: OsImage,
+    config_commit: Option<String>,
     #[cfg(feature = "grpc-dangerous")] sender: &mut Option<GrpcSender>,
 ) -> Result<ExitKind, TridentError> {
     info!("Starting clean install");
     tracing::info!(metric_name = "clean_install_start", value = true);
     let clean_install_start_time = Instant::now();
 
-    if Path::new(UPDATE_ROOT_PATH).exists()
-        && mountpoint::check_is_mountpoint(UPDATE_ROOT_PATH).structured(
+    let paths = EnginePaths::default();
+    if paths.newroot_dir.exists()
+        && mountpoint::check_is_mountpoint(&paths.newroot_dir).structured(
             ServicingError::CheckIfMountPoint {
-                path: UPDATE_ROOT_PATH.to_string(),
+                path: paths.newroot_dir.display().to_string(),
             },
         )?
     {
         debug!("Unmounting volumes from earlier runs of Trident");
-        if let Err(e) = mount::umount(UPDATE_ROOT_PATH, true) {
-            warn!("Attempt to unmount '{UPDATE_ROOT_PATH}' returned error: {e}",);
+        if let Err(e) = mount::umount(&paths.newroot_dir, true) {
+            warn!(
+                "Attempt to unmount '{}' returned error: {e}",
+                paths.newroot_dir.display()
+            );
         }
     }
 
@@ -73,6 +76,9 @@ pub(crate) fn clean_install(
     clean_install_safety_check(host_config, multiboot)?;
     info!("Safety check passed");
 
+    // Refuse to image machines with faulty hardware, before any disk is touched.
+    burn_in::run(host_config)?;
+
     let mut subsystems = SUBSYSTEMS.lock().unwrap();
 
     // Stage clean install
@@ -81,6 +87,8 @@ pub(crate) fn clean_install(
         state,
         host_config,
         image,
+        config_commit,
+        paths,
         #[cfg(feature = "grpc-dangerous")]
         sender,
     )?;
@@ -166,10 +174,13 @@ fn clean_install_safety_check(
     ))
 }
 
-/// Stages a clean install. Takes in 4 arguments:
+/// Stages a clean install. Takes in 7 arguments:
 /// - subsystems: A mutable reference to the list of subsystems.
 /// - state: A mutable reference to the DataStore.
 /// - host_config: A reference to the HostConfiguration.
+/// - image: The OS image to install.
+/// - config_commit: Git commit the Host Configuration was loaded from, recorded once staged.
+/// - paths: Well-known paths used while servicing the host.
 /// - sender: Optional mutable reference to the gRPC sender.
 ///
 /// On success, returns a NewrootMount.
@@ -179,6 +190,8 @@ fn stage_clean_install(
     state: &mut DataStore,
     host_config: &HostConfiguration,
     image: OsImage,
+    config_commit: Option<String>,
+    paths: EnginePaths,
     #[cfg(feature = "grpc-dangerous")] sender: &mut Option<
         mpsc::UnboundedSender<Result<grpc::HostStatusState, tonic::Status>>,
     >,
@@ -207,6 +220,9 @@ fn stage_clean_install(
         image: Some(image),
         storage_graph: engine::build_storage_graph(&host_config.storage)?, // Build storage graph
         filesystems: Vec::new(), // Will be populated after dynamic validation
+        paths,
+        scoped_update: false,
+        resolved_extension_urls: Default::default(),
     };
 
     // Execute pre-servicing scripts
@@ -234,6 +250,7 @@ fn stage_clean_install(
         host_config,
         &ctx.partition_paths,
         AbVolumeSelection::VolumeA,
+        &ctx.paths,
     )?;
     ctx.install_index = install_index::next_install_index(newroot_mount.path())?;
 
@@ -260,8 +277,12 @@ fn stage_clean_install(
 
     // Update the Host Configuration with information produced and stored in the
     // subsystems. Currently, this step is used only to update the final paths
-    // of sysexts and confexts configured in the extensions subsystem.
+    // of sysexts and confexts configured in the extensions subsystem, and to
+    // record the URLs they were downloaded from.
     engine::update_host_configuration(subsystems, &mut ctx)?;
+    let artifacts = provenance::collect(&ctx, &state.host_status().artifacts);
+    let verity_root_hash = storage::verity::get_verity_root_hash(&ctx)
+        .structured(ServicingError::GetVerityRootHash)?;
 
     // At this point, clean install has been staged, so update Host Status
     debug!(
@@ -279,6 +300,19 @@ fn stage_clean_install(
             install_index: ctx.install_index,
             last_error: None,
             is_management_os: true,
+            config_commit: config_commit.or_else(|| hs.config_commit.take()),
+            artifacts,
+            reboot_boot_id: None,
+            identity: None,
+            config_drift: None,
+            annotations: std::mem::take(&mut hs.annotations),
+            extensions: std::mem::take(&mut hs.extensions),
+            health_checks: Vec::new(),
+            health_check_history: std::mem::take(&mut hs.health_check_history),
+            previous_os_version: None,
+            failed_boots: 0,
+            kernel_command_line: None,
+            verity_root_hash,
         }
     })?;
     #[cfg(feature = "grpc-dangerous")]
@@ -314,6 +348,9 @@ pub(crate) fn finalize_clean_install(
         storage_graph: engine::build_storage_graph(&state.host_status().spec.storage)?, // Build storage graph
         filesystems: Vec::new(), // Left empty since context does not have image
         is_uki: None,
+        paths: Default::default(),
+        scoped_update: false,
+        resolved_extension_urls: Default::default(),
     };
 
     let new_root = match new_root {
@@ -325,6 +362,7 @@ pub(crate) fn finalize_clean_install(
                 .structured(InternalError::Internal(
                     "No update volume despite there being a clean install in progress",
                 ))?,
+            &ctx.paths,
         )?,
     };
 
@@ -335,6 +373,7 @@ pub(crate) fn finalize_clean_install(
     // UEFI fallback, and a successful commit will finish it.
     esp::set_uefi_fallback_contents(&ctx, ServicingState::CleanInstallStaged, new_root.path())
         .structured(ServicingError::SetUpUefiFallback)?;
+    esp::sync_esp_mirrors(&ctx, &esp_path).structured(ServicingError::SyncEspMirrors)?;
 
     debug!(
         "Updating host's servicing state to '{:?}'",
@@ -346,6 +385,15 @@ pub(crate) fn finalize_clean_install(
     #[cfg(feature = "grpc-dangerous")]
     grpc::send_host_status_state(sender, state)?;
 
+    if !state
+        .host_status()
+        .spec
+        .internal_params
+        .get_flag(NO_TRANSITION)
+    {
+        engine::record_reboot(state)?;
+    }
+
     // Persist the datastore to the new root
     state.persist(&join_relative(
         new_root.path(),
@@ -0,0 +1,195 @@
+//! Capture of local changes made to /etc on the running OS.
+//!
+//! Before an A/B update is staged, Trident can record which files under /etc differ from the
+//! pristine copy shipped in the current OS image, so that operators can see what local
+//! modifications might need to be migrated to the updated OS.
+//!
+//! When the root filesystem is protected by dm-verity, /etc is an overlay, and its upper directory
+//! holds exactly the local changes. Otherwise, the files are compared with the RPM database of the
+//! OS image, which only covers files owned by packages.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    os::unix::fs::{FileTypeExt, MetadataExt},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error};
+use log::{debug, info};
+use sys_mount::{MountBuilder, MountFlags, UnmountFlags};
+
+use osutils::dependencies::Dependency;
+use trident_api::{
+    constants::{TRIDENT_OVERLAY_LOWER_RELATIVE_PATH, TRIDENT_OVERLAY_UPPER_RELATIVE_PATH},
+    status::ConfigFileChange,
+};
+
+/// Directory whose changes are captured.
+const ETC_PATH: &str = "/etc";
+
+/// Captures the files under /etc that differ from the current OS image. `overlay_dir` holds the
+/// state of the /etc overlay, if any.
+pub(super) fn capture(overlay_dir: &Path) -> Result<BTreeMap<PathBuf, ConfigFileChange>, Error> {
+    let upper_path = overlay_dir.join(TRIDENT_OVERLAY_UPPER_RELATIVE_PATH);
+    let changes = if upper_path.is_dir() {
+        debug!(
+            "Capturing changes to /etc from overlay at '{}'",
+            upper_path.display()
+        );
+        capture_from_overlay(&upper_path)?
+    } else {
+        debug!("Capturing changes to /etc from the RPM database");
+        capture_from_rpm()?
+    };
+
+    info!(
+        "Captured {} locally modified file(s) in /etc",
+        changes.len()
+    );
+    Ok(changes)
+}
+
+/// Captures the changes recorded in the upper directory of the /etc overlay.
+///
+/// The pristine /etc is hidden under the overlay, so the root filesystem is bind mounted
+/// elsewhere, without its submounts, to tell added files from modified ones.
+fn capture_from_overlay(upper_path: &Path) -> Result<BTreeMap<PathBuf, ConfigFileChange>, Error> {
+    let root_mount = tempfile::tempdir().context("Failed to create temporary directory")?;
+    let _root_mount = MountBuilder::default()
+        .flags(MountFlags::BIND)
+        .mount_autodrop("/", root_mount.path(), UnmountFlags::DETACH)
+        .context("Failed to bind mount root filesystem")?;
+    let lower_path = root_mount.path().join(TRIDENT_OVERLAY_LOWER_RELATIVE_PATH);
+
+    let mut changes = BTreeMap::new();
+    diff_overlay(upper_path, &lower_path, Path::new(ETC_PATH), &mut changes)?;
+    Ok(changes)
+}
+
+/// Recursively records the files in `upper` as changes to `target`, comparing with `lower`.
+fn diff_overlay(
+    upper: &Path,
+    lower: &Path,
+    target: &Path,
+    changes: &mut BTreeMap<PathBuf, ConfigFileChange>,
+) -> Result<(), Error> {
+    for entry in fs::read_dir(upper)
+        .with_context(|| format!("Failed to read directory '{}'", upper.display()))?
+    {
+        let entry = entry.context("Failed to read directory entry")?;
+        let metadata = entry
+            .metadata()
+            .with_context(|| format!("Failed to read metadata of '{}'", entry.path().display()))?;
+        let lower_entry = lower.join(entry.file_name());
+        let target_entry = target.join(entry.file_name());
+
+        // Overlayfs records removed files as character devices with device number 0/0.
+        if metadata.file_type().is_char_device() && metadata.rdev() == 0 {
+            changes.insert(target_entry, ConfigFileChange::Removed);
+        } else if metadata.is_dir() {
+            diff_overlay(&entry.path(), &lower_entry, &target_entry, changes)?;
+        } else if lower_entry.symlink_metadata().is_ok() {
+            changes.insert(target_entry, ConfigFileChange::Modified);
+        } else {
+            changes.insert(target_entry, ConfigFileChange::Added);
+        }
+    }
+
+    Ok(())
+}
+
+/// Captures the changes to files under /etc owned by RPM packages.
+fn capture_from_rpm() -> Result<BTreeMap<PathBuf, ConfigFileChange>, Error> {
+    let output = Dependency::Rpm
+        .cmd()
+        .args(["--verify", "--all", "--nodeps", "--noscripts", "--nomtime"])
+        .output()
+        .context("Failed to verify installed packages")?;
+
+    // rpm exits with a non-zero status whenever a file differs, so only treat it as a failure
+    // when nothing was reported.
+    if !output.success() && output.output().trim().is_empty() {
+        output
+            .check()
+            .context("Failed to verify installed packages")?;
+    }
+
+    Ok(parse_rpm_verify(&output.output()))
+}
+
+/// Parses the output of `rpm --verify`, keeping the files under /etc. Each line holds the failed
+/// checks, or `missing`, optionally followed by the file attribute, and the path of the file.
+fn parse_rpm_verify(output: &str) -> BTreeMap<PathBuf, ConfigFileChange> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let path = line.split_whitespace().last()?;
+            if !Path::new(path).starts_with(ETC_PATH) {
+                return None;
+            }
+            let change = if line.starts_with("missing") {
+                ConfigFileChange::Removed
+            } else {
+                ConfigFileChange::Modified
+            };
+            Some((PathBuf::from(path), change))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rpm_verify() {
+        let output = "S.5....T.  c /etc/ssh/sshd_config\n\
+                      missing   c /etc/motd\n\
+                      .M.......    /usr/bin/ping\n\
+                      ..5......  c /etc/sudoers\n";
+        assert_eq!(
+            parse_rpm_verify(output),
+            BTreeMap::from([
+                (PathBuf::from("/etc/motd"), ConfigFileChange::Removed),
+                (
+                    PathBuf::from("/etc/ssh/sshd_config"),
+                    ConfigFileChange::Modified
+                ),
+                (PathBuf::from("/etc/sudoers"), ConfigFileChange::Modified),
+            ])
+        );
+        assert!(parse_rpm_verify("").is_empty());
+    }
+
+    #[test]
+    fn test_diff_overlay() {
+        let upper = tempfile::tempdir().unwrap();
+        let lower = tempfile::tempdir().unwrap();
+
+        fs::create_dir_all(lower.path().join("ssh")).unwrap();
+        fs::write(lower.path().join("ssh/sshd_config"), "pristine").unwrap();
+        fs::create_dir_all(upper.path().join("ssh")).unwrap();
+        fs::write(upper.path().join("ssh/sshd_config"), "modified").unwrap();
+        fs::write(upper.path().join("custom.conf"), "added").unwrap();
+
+        let mut changes = BTreeMap::new();
+        diff_overlay(
+            upper.path(),
+            lower.path(),
+            Path::new(ETC_PATH),
+            &mut changes,
+        )
+        .unwrap();
+        assert_eq!(
+            changes,
+            BTreeMap::from([
+                (PathBuf::from("/etc/custom.conf"), ConfigFileChange::Added),
+                (
+                    PathBuf::from("/etc/ssh/sshd_config"),
+                    ConfigFileChange::Modified
+                ),
+            ])
+        );
+    }
+}
@@ -11,15 +11,20 @@ use trident_api::{
     config::{HostConfiguration, Partition, VerityDevice},
     constants::ROOT_MOUNT_POINT_PATH,
     error::{InternalError, ReportError, TridentError},
+    primitives::hash::Sha384Hash,
     status::{AbVolumeSelection, ServicingType},
     storage_graph::graph::StorageGraph,
     BlockDeviceId,
 };
+use url::Url;
 
 use crate::osimage::OsImage;
 
+pub(crate) use paths::EnginePaths;
+
 #[allow(dead_code)]
 pub mod filesystem;
+mod paths;
 
 #[cfg(test)]
 mod test_utils;
@@ -75,6 +80,18 @@ pub struct EngineContext {
 
     /// Whether the image will use a UKI or not.
     pub is_uki: Option<bool>,
+
+    /// Well-known paths on the servicing and target OSes.
+    pub paths: EnginePaths,
+
+    /// Whether the servicing is a scoped update, which applies selected sections of the Host
+    /// Configuration directly to the running OS.
+    pub scoped_update: bool,
+
+    /// URLs that the extension images downloaded by the servicing were read from, by hash, when
+    /// they differ from the URLs in the Host Configuration, e.g. the registry blob that an
+    /// `oci://` URL resolved to. Set by the extensions subsystem.
+    pub resolved_extension_urls: HashMap<Sha384Hash, Url>,
 }
 impl EngineContext {
     /// Returns the update volume selection for all A/B volume pairs. The update volume is the one
@@ -140,6 +157,10 @@ impl EngineContext {
             }
         }
 
+        if let Some(lv_path) = self.spec.storage.lvm.logical_volume_path(block_device_id) {
+            return Some(lv_path);
+        }
+
         if let Some(verity) = self
             .spec
             .storage
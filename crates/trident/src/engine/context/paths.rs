@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+use trident_api::constants::{
+    AGENT_CONFIG_PATH, TRIDENT_OVERLAY_PATH, UPDATE_ROOT_FALLBACK_PATH, UPDATE_ROOT_PATH,
+};
+
+/// Directory of the target OS in which extension images are downloaded, relative to the root of
+/// the target OS.
+const EXTENSION_STAGING_DIRECTORY: &str = "/var/lib/extensions/.staging";
+
+/// Well-known paths used while servicing the host.
+///
+/// The paths default to the standard locations. Carrying them in the engine context, rather than
+/// hardcoding them in each module, lets tests redirect them to temporary directories.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnginePaths {
+    /// Directory in which extension images are downloaded before being moved to their final
+    /// path, relative to the root of the target OS.
+    pub extension_staging_dir: PathBuf,
+
+    /// Directory holding the state of the /etc overlay, on hosts with a read-only root
+    /// filesystem.
+    pub overlay_dir: PathBuf,
+
+    /// Directory at which the root of the target OS is mounted while servicing.
+    pub newroot_dir: PathBuf,
+
+    /// Directory at which the root of the target OS is mounted when `newroot_dir` cannot be
+    /// used, e.g. because /mnt is read-only.
+    pub newroot_fallback_dir: PathBuf,
+
+    /// Path of the Trident agent configuration file.
+    pub agent_config_path: PathBuf,
+}
+
+impl Default for EnginePaths {
+    fn default() -> Self {
+        Self {
+            extension_staging_dir: EXTENSION_STAGING_DIRECTORY.into(),
+            overlay_dir: TRIDENT_OVERLAY_PATH.into(),
+            newroot_dir: UPDATE_ROOT_PATH.into(),
+            newroot_fallback_dir: UPDATE_ROOT_FALLBACK_PATH.into(),
+            agent_config_path: AGENT_CONFIG_PATH.into(),
+        }
+    }
+}
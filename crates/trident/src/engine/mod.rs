@@ -2,25 +2,26 @@ use std::{
     fs,
     path::{Path, PathBuf},
     sync::Mutex,
-    thread,
-    time::Duration,
 };
 
 use chrono::Utc;
-use log::{debug, error, info, warn};
+use log::{debug, info, warn};
 
-use osutils::{dependencies::Dependency, path::join_relative};
+use osutils::path::join_relative;
 use trident_api::{
     config::Storage,
     constants,
-    error::{InternalError, ReportError, ServicingError, TridentError, TridentResultExt},
+    error::{InternalError, ReportError, TridentError, TridentResultExt},
     status::{ServicingState, ServicingType},
     storage_graph::graph::StorageGraph,
 };
 
 use crate::{
-    engine::boot::BootSubsystem,
+    datastore::DataStore,
+    engine::{boot::BootSubsystem, space_forecast::SpaceDemand},
     subsystems::{
+        cloud_init::CloudInitSubsystem,
+        container_images::ContainerImagesSubsystem,
         esp::EspSubsystem,
         extensions::ExtensionsSubsystem,
         hooks::HooksSubsystem,
@@ -28,6 +29,7 @@ use crate::{
         management::ManagementSubsystem,
         network::NetworkSubsystem,
         osconfig::{MosConfigSubsystem, OsConfigSubsystem},
+        packages::PackagesSubsystem,
         selinux::SelinuxSubsystem,
         storage::StorageSubsystem,
     },
@@ -36,12 +38,17 @@ use crate::{
 
 // Engine functionality
 pub mod bootentries;
+mod burn_in;
 mod clean_install;
+mod config_drift;
 mod context;
 mod kexec;
 mod newroot;
+mod provenance;
 pub mod provisioning_network;
+mod reboot;
 pub mod rollback;
+pub(crate) mod space_forecast;
 mod update;
 
 // Trident Subsystems
@@ -53,9 +60,11 @@ mod etc_overlay;
 pub(crate) mod install_index;
 
 pub(crate) use clean_install::{clean_install, finalize_clean_install};
-pub(crate) use context::{filesystem, EngineContext};
+pub(crate) use context::{filesystem, EngineContext, EnginePaths};
 pub use newroot::NewrootMount;
-pub(crate) use update::{finalize_update, update};
+pub(crate) use reboot::{current_boot_id, reboot_pending, record_reboot};
+pub use reboot::{power_off, reboot};
+pub(crate) use update::{finalize_update, scoped_update, update};
 
 pub(crate) trait Subsystem: Send {
     fn name(&self) -> &'static str;
@@ -83,6 +92,12 @@ pub(crate) trait Subsystem: Send {
         Ok(())
     }
 
+    /// Estimate the free space needed on the filesystems of the servicing OS to stage the Host
+    /// Configuration in `ctx.spec`.
+    fn forecast_space(&self, _ctx: &EngineContext) -> Vec<SpaceDemand> {
+        Vec::new()
+    }
+
     /// Perform non-destructive preparations for an update.
     fn prepare(&mut self, _ctx: &EngineContext) -> Result<(), TridentError> {
         Ok(())
@@ -116,7 +131,10 @@ lazy_static::lazy_static! {
         Box::<StorageSubsystem>::default(),
         Box::<BootSubsystem>::default(),
         Box::<NetworkSubsystem>::default(),
+        Box::<PackagesSubsystem>::default(),
+        Box::<ContainerImagesSubsystem>::default(),
         Box::<OsConfigSubsystem>::default(),
+        Box::<CloudInitSubsystem>::default(),
         Box::<ManagementSubsystem>::default(),
         Box::<ExtensionsSubsystem>::default(),
         Box::<HooksSubsystem>::default(),
@@ -345,26 +363,17 @@ fn update_host_configuration(
     Ok(())
 }
 
-pub fn reboot() -> Result<(), TridentError> {
-    // Sync all writes to the filesystem.
-    info!("Syncing filesystem");
-    nix::unistd::sync();
-
-    // This trace event will be used with the trident_start event to track the
-    // total time taken for the reboot
-    tracing::info!(metric_name = "trident_system_reboot");
-    info!("Rebooting system");
-    Dependency::Systemctl
-        .cmd()
-        .env("SYSTEMD_IGNORE_CHROOT", "true")
-        .arg("reboot")
-        .run_and_check()
-        .structured(ServicingError::Reboot)?;
-
-    thread::sleep(Duration::from_secs(600));
-
-    error!("Waited for reboot for 10 minutes, but nothing happened, aborting");
-    Err(TridentError::new(ServicingError::RebootTimeout))
+/// Records the Git commit the applied Host Configuration was loaded from, if any, when the host
+/// already matches the Host Configuration and no servicing is needed.
+fn record_config_commit(
+    state: &mut DataStore,
+    config_commit: Option<String>,
+) -> Result<(), TridentError> {
+    if config_commit.is_none() || state.host_status().config_commit == config_commit {
+        return Ok(());
+    }
+
+    state.with_host_status(|host_status| host_status.config_commit = config_commit)
 }
 
 /// Builds the storage graph for the given storage configuration. Since graph v2 is still in its
@@ -14,15 +14,13 @@ use osutils::{files, filesystems::MountFileSystemType, findmnt::FindMnt, lsblk,
 use sysdefs::filesystems::{KernelFilesystemType, RealFilesystemType};
 use trident_api::{
     config::{FileSystem, HostConfiguration},
-    constants::{
-        NONE_MOUNT_POINT, ROOT_MOUNT_POINT_PATH, UPDATE_ROOT_FALLBACK_PATH, UPDATE_ROOT_PATH,
-    },
+    constants::{NONE_MOUNT_POINT, ROOT_MOUNT_POINT_PATH},
     error::{InternalError, ReportError, ServicingError, TridentError, TridentResultExt},
     status::AbVolumeSelection,
     BlockDeviceId,
 };
 
-use crate::{OS_MODIFIER_BINARY_PATH, OS_MODIFIER_NEWROOT_PATH};
+use crate::{engine::EnginePaths, OS_MODIFIER_BINARY_PATH, OS_MODIFIER_NEWROOT_PATH};
 
 /// NewrootMount represents all the necessary mounting points for newroot and
 /// the nested execmount to exit the chroot jail. It is also responsible for
@@ -61,9 +59,10 @@ impl NewrootMount {
         host_config: &HostConfiguration,
         partition_paths: &BTreeMap<BlockDeviceId, PathBuf>,
         update_volume: AbVolumeSelection,
+        paths: &EnginePaths,
     ) -> Result<Self, TridentError> {
         // Get the path where the newroot should be mounted
-        let new_root_path = get_new_root_path();
+        let new_root_path = get_new_root_path(paths);
         debug!(
             "Attempting to mount newroot at '{}'",
             new_root_path.display()
@@ -354,16 +353,16 @@ fn mount_points_map(host_config: &HostConfiguration) -> BTreeMap<&Path, &FileSys
 }
 
 /// Returns the path where the newroot should be mounted.
-fn get_new_root_path() -> PathBuf {
-    let mut new_root_path = Path::new(UPDATE_ROOT_PATH);
+fn get_new_root_path(paths: &EnginePaths) -> PathBuf {
+    let mut new_root_path = paths.newroot_dir.as_path();
     if let Err(e) = prepare_mount_directory(new_root_path, true) {
         debug!(
             "Failed to prepare new root mount directory at '{}'. Error: {}.",
             new_root_path.display(),
             e
         );
-        debug!("Falling back to '{}'", UPDATE_ROOT_FALLBACK_PATH);
-        new_root_path = Path::new(UPDATE_ROOT_FALLBACK_PATH);
+        debug!("Falling back to '{}'", paths.newroot_fallback_dir.display());
+        new_root_path = paths.newroot_fallback_dir.as_path();
     }
     new_root_path.to_owned()
 }
@@ -621,6 +620,24 @@ mod tests {
         assert_eq!(newroot_mount.path(), newroot_path, "Newroot path mismatch");
     }
 
+    #[test]
+    fn test_get_new_root_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let paths = EnginePaths {
+            newroot_dir: temp_dir.path().join("newroot"),
+            newroot_fallback_dir: temp_dir.path().join("fallback"),
+            ..Default::default()
+        };
+
+        // The preferred directory is created and used when possible
+        assert_eq!(get_new_root_path(&paths), paths.newroot_dir);
+        assert!(paths.newroot_dir.is_dir());
+
+        // A non-empty preferred directory falls back to the fallback directory
+        File::create(paths.newroot_dir.join("file")).unwrap();
+        assert_eq!(get_new_root_path(&paths), paths.newroot_fallback_dir);
+    }
+
     #[test]
     fn test_prepare_mount_directory() {
         let temp_mount_dir = TempDir::new().unwrap();
@@ -0,0 +1,209 @@
+//! Provenance of the artifacts deployed on the host.
+
+use log::warn;
+
+use trident_api::{
+    config::Extension,
+    status::{ArtifactKind, ArtifactProvenance, VerificationMethod},
+};
+
+use crate::engine::EngineContext;
+
+/// Returns the provenance of the OS image, UKI, sysexts and confexts deployed by the servicing
+/// described by `ctx`.
+///
+/// Must be called after the subsystems updated the Host Configuration, so that the paths of
+/// extension images are known. `previous` is the provenance recorded by earlier servicings.
+pub(crate) fn collect(
+    ctx: &EngineContext,
+    previous: &[ArtifactProvenance],
+) -> Vec<ArtifactProvenance> {
+    let mut artifacts = Vec::new();
+
+    if let Some(image) = &ctx.image {
+        let url = image.source().to_string();
+        let resolved_url = image.resolved_source().map(ToString::to_string);
+        let verification = if image.metadata_verified() {
+            VerificationMethod::HostConfigurationDigest
+        } else {
+            VerificationMethod::Unverified
+        };
+
+        artifacts.push(ArtifactProvenance {
+            kind: ArtifactKind::OsImage,
+            url: url.clone(),
+            resolved_url: resolved_url.clone(),
+            digests: vec![format!("sha384:{}", image.metadata_sha384())],
+            verification,
+            path: None,
+        });
+
+        // The UKI is deployed from the ESP image, whose digest is listed in the OS image metadata.
+        if ctx.is_uki == Some(true) {
+            match image.esp_filesystem() {
+                Ok(esp) => artifacts.push(ArtifactProvenance {
+                    kind: ArtifactKind::Uki,
+                    url,
+                    resolved_url,
+                    digests: vec![format!("sha384:{}", esp.image_file.sha384)],
+                    verification: match verification {
+                        VerificationMethod::HostConfigurationDigest => {
+                            VerificationMethod::OsImageMetadata
+                        }
+                        _ => VerificationMethod::Unverified,
+                    },
+                    path: None,
+                }),
+                Err(e) => warn!("Failed to record provenance of the UKI: {e:?}"),
+            }
+        }
+    }
+
+    artifacts.extend(collect_extensions(ctx, previous));
+
+    artifacts
+}
+
+/// Returns the provenance of the sysexts and confexts deployed by the servicing described by
+/// `ctx`. Images that were not downloaded by the servicing keep the resolved URL recorded in
+/// `previous`.
+pub(crate) fn collect_extensions(
+    ctx: &EngineContext,
+    previous: &[ArtifactProvenance],
+) -> Vec<ArtifactProvenance> {
+    ctx.spec
+        .os
+        .sysexts
+        .iter()
+        .map(|ext| (ArtifactKind::Sysext, ext))
+        .chain(
+            ctx.spec
+                .os
+                .confexts
+                .iter()
+                .map(|ext| (ArtifactKind::Confext, ext)),
+        )
+        .map(|(kind, ext)| {
+            let mut artifact = extension_provenance(kind, ext);
+            artifact.resolved_url = match ctx.resolved_extension_urls.get(&ext.sha384) {
+                Some(url) => Some(url.to_string()),
+                None => previous
+                    .iter()
+                    .find(|old| {
+                        old.kind == artifact.kind
+                            && old.url == artifact.url
+                            && old.digests == artifact.digests
+                    })
+                    .and_then(|old| old.resolved_url.clone()),
+            };
+            artifact
+        })
+        .collect()
+}
+
+/// Returns whether the artifact is a sysext or confext.
+pub(crate) fn is_extension(artifact: &ArtifactProvenance) -> bool {
+    matches!(artifact.kind, ArtifactKind::Sysext | ArtifactKind::Confext)
+}
+
+/// Returns the provenance of an extension image. Extension images are always checked against the
+/// digests in the Host Configuration before they are installed.
+fn extension_provenance(kind: ArtifactKind, ext: &Extension) -> ArtifactProvenance {
+    let mut digests = vec![format!("sha384:{}", ext.sha384)];
+    if let Some(sha256) = &ext.sha256 {
+        digests.push(format!("sha256:{sha256}"));
+    }
+
+    ArtifactProvenance {
+        kind,
+        url: ext.url.to_string(),
+        resolved_url: None,
+        digests,
+        verification: VerificationMethod::HostConfigurationDigest,
+        path: ext.path.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use url::Url;
+
+    use trident_api::primitives::hash::{Sha256Hash, Sha384Hash};
+
+    use crate::osimage::{mock::MockOsImage, OsImage};
+
+    #[test]
+    fn test_collect() {
+        assert!(collect(&EngineContext::default(), &[]).is_empty());
+
+        let mut ctx = EngineContext {
+            image: Some(OsImage::mock(MockOsImage::new())),
+            is_uki: Some(false),
+            ..Default::default()
+        };
+        ctx.spec.os.sysexts.push(Extension {
+            sha256: Some(Sha256Hash::from("b".repeat(64))),
+            path: Some(PathBuf::from("/var/lib/extensions/sysext.raw")),
+            ..Extension::new(
+                Url::parse("https://example.com/sysext.raw").unwrap(),
+                Sha384Hash::from("a".repeat(96)),
+            )
+        });
+
+        let artifacts = collect(&ctx, &[]);
+        assert_eq!(artifacts.len(), 2);
+
+        assert_eq!(artifacts[0].kind, ArtifactKind::OsImage);
+        assert_eq!(artifacts[0].url, MockOsImage::new().source.to_string());
+        assert_eq!(
+            artifacts[0].digests,
+            vec![format!("sha384:{}", "0".repeat(96))]
+        );
+        assert_eq!(artifacts[0].verification, VerificationMethod::Unverified);
+
+        assert_eq!(
+            artifacts[1],
+            ArtifactProvenance {
+                kind: ArtifactKind::Sysext,
+                url: "https://example.com/sysext.raw".into(),
+                resolved_url: None,
+                digests: vec![
+                    format!("sha384:{}", "a".repeat(96)),
+                    format!("sha256:{}", "b".repeat(64))
+                ],
+                verification: VerificationMethod::HostConfigurationDigest,
+                path: Some(PathBuf::from("/var/lib/extensions/sysext.raw")),
+            }
+        );
+    }
+
+    #[test]
+    fn test_collect_extensions_resolved_url() {
+        let mut ctx = EngineContext::default();
+        for (name, hash) in [("downloaded", 'a'), ("reused", 'b')] {
+            ctx.spec.os.sysexts.push(Extension::new(
+                Url::parse(&format!("oci://example.com/{name}:1.0")).unwrap(),
+                Sha384Hash::from(hash.to_string().repeat(96)),
+            ));
+        }
+        let blob = Url::parse("https://example.com/v2/downloaded/blobs/sha256:0").unwrap();
+        ctx.resolved_extension_urls
+            .insert(Sha384Hash::from("a".repeat(96)), blob.clone());
+
+        // Images that were not downloaded keep the resolved URL recorded by an earlier servicing
+        let mut previous = collect_extensions(&ctx, &[]);
+        previous[1].resolved_url = Some("https://example.com/v2/reused/blobs/sha256:1".into());
+
+        let artifacts = collect_extensions(&ctx, &previous);
+        assert_eq!(artifacts[0].resolved_url, Some(blob.to_string()));
+        assert_eq!(artifacts[1].resolved_url, previous[1].resolved_url);
+
+        // The resolved URL of a different image is not kept
+        ctx.spec.os.sysexts[1].sha384 = Sha384Hash::from("c".repeat(96));
+        assert_eq!(collect_extensions(&ctx, &previous)[1].resolved_url, None);
+    }
+}
@@ -0,0 +1,223 @@
+//! Reboot executor.
+//!
+//! Rebooting is the last step of clean install and A/B update, and a reboot that silently fails
+//! would leave the host waiting forever. Before rebooting, Trident records the current boot ID in
+//! the Host Status, so that the next run can tell that the reboot never happened, and arms a
+//! watchdog, so that the host is reset even if the reboot hangs.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    os::fd::AsRawFd,
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Error};
+use log::{debug, error, info, warn};
+use nix::sys::reboot::RebootMode;
+
+use osutils::dependencies::Dependency;
+use trident_api::error::{ReportError, ServicingError, TridentError};
+
+use crate::datastore::DataStore;
+
+/// Boot ID of the running kernel, which changes on every boot.
+const BOOT_ID_PATH: &str = "/proc/sys/kernel/random/boot_id";
+
+/// Watchdog device armed before rebooting.
+const WATCHDOG_DEVICE_PATH: &str = "/dev/watchdog";
+
+/// Time after which the watchdog resets the host if it has not rebooted yet.
+const WATCHDOG_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Time to wait for the reboot to happen before giving up.
+const REBOOT_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// `WDIOC_SETTIMEOUT` ioctl request, i.e. `_IOWR('W', 6, int)`.
+const WDIOC_SETTIMEOUT: u64 = 0xC004_5706;
+
+/// Records in the Host Status that a reboot is about to be initiated. Must be called before the
+/// datastore is closed for the last time ahead of the reboot.
+pub(crate) fn record_reboot(datastore: &mut DataStore) -> Result<(), TridentError> {
+    let boot_id = current_boot_id().structured(ServicingError::Reboot)?;
+    debug!("Recording reboot initiated from boot '{boot_id}'");
+    datastore.with_host_status(|host_status| host_status.reboot_boot_id = Some(boot_id))
+}
+
+/// Returns whether the host is still running the boot in which the last reboot was initiated,
+/// i.e. whether the reboot recorded by `record_reboot` never happened.
+pub(crate) fn reboot_pending(datastore: &DataStore) -> bool {
+    let Some(recorded) = &datastore.host_status().reboot_boot_id else {
+        return false;
+    };
+
+    match current_boot_id() {
+        Ok(boot_id) => &boot_id == recorded,
+        Err(e) => {
+            warn!("Failed to read boot ID, assuming that the host rebooted: {e:?}");
+            false
+        }
+    }
+}
+
+/// Reboots the host.
+///
+/// A watchdog is armed first, so that the host is reset if the reboot hangs. If `systemctl reboot`
+/// fails, the host is rebooted directly with reboot(2).
+pub fn reboot() -> Result<(), TridentError> {
+    // Sync all writes to the filesystem.
+    info!("Syncing filesystem");
+    nix::unistd::sync();
+
+    // Keep the watchdog open until the reboot happens. If Trident exits without rebooting, the
+    // watchdog is closed without being disarmed, so it still resets the host.
+    let _watchdog = match arm_watchdog(Path::new(WATCHDOG_DEVICE_PATH), WATCHDOG_TIMEOUT) {
+        Ok(watchdog) => Some(watchdog),
+        Err(e) => {
+            warn!("Failed to arm watchdog, rebooting without it: {e:?}");
+            None
+        }
+    };
+
+    // This trace event will be used with the trident_start event to track the
+    // total time taken for the reboot
+    tracing::info!(metric_name = "trident_system_reboot");
+    info!("Rebooting system");
+    if let Err(e) = Dependency::Systemctl
+        .cmd()
+        .env("SYSTEMD_IGNORE_CHROOT", "true")
+        .arg("reboot")
+        .run_and_check()
+    {
+        warn!("Failed to reboot with systemctl, rebooting directly: {e:?}");
+        nix::sys::reboot::reboot(RebootMode::RB_AUTOBOOT).structured(ServicingError::Reboot)?;
+    }
+
+    thread::sleep(REBOOT_TIMEOUT);
+
+    error!(
+        "Waited for reboot for {} minutes, but nothing happened, aborting",
+        REBOOT_TIMEOUT.as_secs() / 60
+    );
+    Err(TridentError::new(ServicingError::RebootTimeout))
+}
+
+/// Powers the host off.
+///
+/// If `systemctl poweroff` fails, the host is powered off directly with reboot(2). No watchdog is
+/// armed, as it would reset the host instead.
+pub fn power_off() -> Result<(), TridentError> {
+    info!("Syncing filesystem");
+    nix::unistd::sync();
+
+    info!("Powering off system");
+    if let Err(e) = Dependency::Systemctl
+        .cmd()
+        .env("SYSTEMD_IGNORE_CHROOT", "true")
+        .arg("poweroff")
+        .run_and_check()
+    {
+        warn!("Failed to power off with systemctl, powering off directly: {e:?}");
+        nix::sys::reboot::reboot(RebootMode::RB_POWER_OFF).structured(ServicingError::PowerOff)?;
+    }
+
+    thread::sleep(REBOOT_TIMEOUT);
+
+    error!(
+        "Waited for power off for {} minutes, but nothing happened, aborting",
+        REBOOT_TIMEOUT.as_secs() / 60
+    );
+    Err(TridentError::new(ServicingError::PowerOff))
+}
+
+/// Returns the boot ID of the running kernel.
+pub(crate) fn current_boot_id() -> Result<String, Error> {
+    Ok(fs::read_to_string(BOOT_ID_PATH)
+        .with_context(|| format!("Failed to read boot ID from '{BOOT_ID_PATH}'"))?
+        .trim()
+        .to_string())
+}
+
+/// Opens the watchdog device, which starts the watchdog, and sets its timeout. Loads the softdog
+/// module first if the host has no watchdog device.
+fn arm_watchdog(device: &Path, timeout: Duration) -> Result<File, Error> {
+    if !device.exists() {
+        debug!("No watchdog device found, loading softdog module");
+        Dependency::Modprobe
+            .cmd()
+            .arg("softdog")
+            .run_and_check()
+            .context("Failed to load softdog module")?;
+    }
+
+    let watchdog = OpenOptions::new()
+        .write(true)
+        .open(device)
+        .with_context(|| format!("Failed to open watchdog device '{}'", device.display()))?;
+
+    let mut timeout_secs: libc::c_int = timeout
+        .as_secs()
+        .try_into()
+        .context("Watchdog timeout is too large")?;
+    // SAFETY: WDIOC_SETTIMEOUT reads and writes a single int, which outlives the call.
+    let ret = unsafe {
+        libc::ioctl(
+            watchdog.as_raw_fd(),
+            WDIOC_SETTIMEOUT as _,
+            &mut timeout_secs,
+        )
+    };
+    if ret < 0 {
+        let err = io::Error::last_os_error();
+        // Disarm the watchdog with the magic close character, so that it does not reset the host
+        // with its default timeout.
+        if let Err(e) = (&watchdog).write_all(b"V") {
+            warn!("Failed to disarm watchdog '{}': {e}", device.display());
+        }
+        bail!(
+            "Failed to set timeout of watchdog device '{}': {err}",
+            device.display()
+        );
+    }
+
+    info!(
+        "Armed watchdog '{}' with a timeout of {timeout_secs} seconds",
+        device.display()
+    );
+    Ok(watchdog)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reboot_pending() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut datastore = DataStore::open_or_create(&temp_dir.path().join("db.sqlite")).unwrap();
+        assert!(!reboot_pending(&datastore));
+
+        record_reboot(&mut datastore).unwrap();
+        assert_eq!(
+            datastore.host_status().reboot_boot_id,
+            Some(current_boot_id().unwrap())
+        );
+        assert!(reboot_pending(&datastore));
+
+        datastore
+            .with_host_status(|host_status| {
+                host_status.reboot_boot_id = Some("previous-boot".into())
+            })
+            .unwrap();
+        assert!(!reboot_pending(&datastore));
+    }
+
+    #[test]
+    fn test_arm_watchdog_not_a_watchdog() {
+        // Regular files do not support the watchdog ioctl
+        let file = tempfile::NamedTempFile::new().unwrap();
+        arm_watchdog(file.path(), WATCHDOG_TIMEOUT).unwrap_err();
+    }
+}
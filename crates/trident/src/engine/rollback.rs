@@ -5,12 +5,18 @@ use chrono::Utc;
 use enumflags2::BitFlags;
 use log::{debug, error, info, trace, warn};
 
-use osutils::{block_devices, container, efivar, lsblk, pcrlock, veritysetup, virt};
+use osutils::{
+    block_devices, container, efivar, lsblk, path::join_relative, pcrlock, veritysetup, virt,
+};
 use trident_api::{
+    config::{HostConfiguration, RollbackPolicy, ScriptFailureAction},
     constants::internal_params::VIRTDEPLOY_BOOT_ORDER_WORKAROUND,
-    constants::ROOT_MOUNT_POINT_PATH,
+    constants::{ESP_MOUNT_POINT_PATH, ROOT_MOUNT_POINT_PATH},
     error::{InternalError, ReportError, ServicingError, TridentError, TridentResultExt},
-    status::{AbVolumeSelection, ServicingState, ServicingType},
+    status::{
+        AbVolumeSelection, HealthCheckKind, HealthCheckVerdict, HostStatus, ServicingState,
+        ServicingType,
+    },
     BlockDeviceId,
 };
 
@@ -21,16 +27,24 @@ use crate::{
         storage::{encryption, verity},
     },
     health,
-    subsystems::esp,
+    subsystems::{esp, extensions},
     DataStore,
 };
 
+/// Kernel command line of the running OS.
+const KERNEL_COMMAND_LINE_PATH: &str = "/proc/cmdline";
+
 #[must_use]
 pub enum BootValidationResult {
     /// Target OS booted successfully, and the health checks succeeded
     ValidBootProvisioned,
     /// Target OS booted successfully, and the health checks failed
     ValidBootHealthCheckFailed(TridentError),
+    /// Target OS booted successfully, the health checks failed, and the rollback policy requires
+    /// the host to be powered off rather than rebooted
+    ValidBootHealthCheckFailedPowerOff(TridentError),
+    /// The reboot initiated after finalizing never happened, so the host must be rebooted again
+    RebootPending,
 }
 
 /// Validates that the firmware did not perform a rollback, i.e. correctly booted from the updated
@@ -42,6 +56,13 @@ pub enum BootValidationResult {
 pub fn validate_boot(datastore: &mut DataStore) -> Result<BootValidationResult, TridentError> {
     info!("Validating whether host correctly booted from target OS image");
 
+    if engine::reboot_pending(datastore) {
+        warn!("Host is still running the boot in which the reboot was initiated");
+        return Ok(BootValidationResult::RebootPending);
+    }
+    // The host rebooted, so the recorded reboot is no longer needed.
+    datastore.with_host_status(|host_status| host_status.reboot_boot_id = None)?;
+
     let current_servicing_state = datastore.host_status().servicing_state;
     let ab_active_volume = match current_servicing_state {
         // For *Finalized, use the active volume set in Host Status
@@ -73,20 +94,7 @@ pub fn validate_boot(datastore: &mut DataStore) -> Result<BootValidationResult,
         _ => ServicingType::NoActiveServicing,
     };
 
-    // Create an EngineContext based on the Host Status
-    let ctx = EngineContext {
-        spec: datastore.host_status().spec.clone(),
-        spec_old: datastore.host_status().spec_old.clone(),
-        servicing_type,
-        ab_active_volume,
-        partition_paths: datastore.host_status().partition_paths.clone(),
-        disk_uuids: datastore.host_status().disk_uuids.clone(),
-        install_index: datastore.host_status().install_index,
-        image: None, // Not used for boot validation logic
-        storage_graph: engine::build_storage_graph(&datastore.host_status().spec.storage)?, // Build storage graph
-        filesystems: Vec::new(), // Left empty since context does not have image
-        is_uki: Some(efivar::current_var_is_uki()),
-    };
+    let ctx = boot_context(datastore.host_status(), servicing_type, ab_active_volume)?;
 
     // Get the block device path of the current root
     let current_root_path =
@@ -107,6 +115,7 @@ pub fn validate_boot(datastore: &mut DataStore) -> Result<BootValidationResult,
             // For *Finalized states, when booting from the expected
             // root, finish the commit process
             info!("Host successfully booted from updated target OS image");
+            check_verity_root_hash(&ctx, datastore.host_status().verity_root_hash.as_deref())?;
             return commit_finalized_on_expected_root(
                 &ctx,
                 datastore,
@@ -149,6 +158,7 @@ pub fn validate_boot(datastore: &mut DataStore) -> Result<BootValidationResult,
                 host_status.spec = host_status.spec_old.clone();
                 host_status.spec_old = Default::default();
                 host_status.servicing_state = ServicingState::Provisioned;
+                host_status.failed_boots = 0;
             })?;
 
             return Err(TridentError::new(
@@ -182,6 +192,93 @@ pub fn validate_boot(datastore: &mut DataStore) -> Result<BootValidationResult,
     }
 }
 
+/// Creates an EngineContext based on the Host Status, for the given servicing type and active
+/// volume.
+fn boot_context(
+    host_status: &HostStatus,
+    servicing_type: ServicingType,
+    ab_active_volume: Option<AbVolumeSelection>,
+) -> Result<EngineContext, TridentError> {
+    Ok(EngineContext {
+        spec: host_status.spec.clone(),
+        spec_old: host_status.spec_old.clone(),
+        servicing_type,
+        ab_active_volume,
+        partition_paths: host_status.partition_paths.clone(),
+        disk_uuids: host_status.disk_uuids.clone(),
+        install_index: host_status.install_index,
+        image: None, // Not used for boot validation logic
+        storage_graph: engine::build_storage_graph(&host_status.spec.storage)?, // Build storage graph
+        filesystems: Vec::new(), // Left empty since context does not have image
+        is_uki: Some(efivar::current_var_is_uki()),
+        paths: Default::default(),
+        scoped_update: false,
+        resolved_extension_urls: Default::default(),
+    })
+}
+
+/// Monitors the health of the target OS of an A/B update once it was committed, for the grace
+/// window of the health monitor of the Host Configuration, if any. If a run of the health checks
+/// fails during the window, the host is rolled back to the previous OS, whose Host Configuration
+/// is `spec_old`, exactly as when the health checks fail before the commit.
+#[tracing::instrument(skip_all)]
+pub fn monitor_committed_update(
+    datastore: &mut DataStore,
+    spec_old: HostConfiguration,
+) -> Result<BootValidationResult, TridentError> {
+    let Some(monitor) = datastore.host_status().spec.health.monitor.clone() else {
+        return Ok(BootValidationResult::ValidBootProvisioned);
+    };
+
+    // The active volume is the one of the previous OS, as before the commit, so that the update
+    // volume is the one of the target OS.
+    let previous_volume = match datastore.host_status().ab_active_volume {
+        Some(AbVolumeSelection::VolumeA) => AbVolumeSelection::VolumeB,
+        None | Some(AbVolumeSelection::VolumeB) => AbVolumeSelection::VolumeA,
+    };
+    let mut ctx = boot_context(
+        datastore.host_status(),
+        ServicingType::AbUpdate,
+        Some(previous_volume),
+    )?;
+    ctx.spec_old = spec_old.clone();
+
+    let Err(e) = health::monitor_health(&ctx, datastore, &monitor) else {
+        return Ok(BootValidationResult::ValidBootProvisioned);
+    };
+    error!("Health check(s) failure after commit: {e:?}");
+    let structured_error = serde_yaml::to_value(&e).structured(InternalError::SerializeError)?;
+
+    info!("Rolling back to the previous OS");
+    let esp_path = join_relative(host_root_path()?, ESP_MOUNT_POINT_PATH);
+    bootentries::set_default_to_volume(&ctx, &esp_path, previous_volume)
+        .message("Failed to make the previous OS the default boot option")?;
+
+    // Same Host Status as when the health checks fail before the commit, so that the rollback is
+    // validated on the next boot.
+    datastore.with_host_status(|host_status| {
+        host_status.servicing_state = ServicingState::AbUpdateHealthCheckFailed;
+        host_status.spec_old = spec_old;
+        host_status.ab_active_volume = Some(previous_volume);
+        host_status.last_error = Some(structured_error);
+    })?;
+    persist_failure_log(datastore, &e);
+
+    Ok(BootValidationResult::ValidBootHealthCheckFailed(e))
+}
+
+/// Returns the path of the root of the host, which is mounted elsewhere when Trident runs in a
+/// container.
+fn host_root_path() -> Result<PathBuf, TridentError> {
+    if container::is_running_in_container()
+        .message("Failed to check if Trident is running in a container")?
+    {
+        container::get_host_root_path().message("Failed to get host root path")
+    } else {
+        Ok(PathBuf::from(ROOT_MOUNT_POINT_PATH))
+    }
+}
+
 /// Completes the commit for AbUpdateFinalized and CleanInstallFinalized states when
 /// the host has booted from the expected root device. This includes running health
 /// checks, updating boot order, updating the encryption pcrlock policy if needed, and
@@ -195,13 +292,19 @@ fn commit_finalized_on_expected_root(
     // Run health checks to ensure the system is in the desired state
     let health_check_status =
         run_health_checks(ctx, datastore, current_servicing_state, servicing_type)?;
-    if let BootValidationResult::ValidBootHealthCheckFailed(err) = health_check_status {
-        if servicing_type == ServicingType::AbUpdate {
-            return Ok(BootValidationResult::ValidBootHealthCheckFailed(err));
-        } else {
+    match health_check_status {
+        BootValidationResult::ValidBootHealthCheckFailed(err)
+        | BootValidationResult::ValidBootHealthCheckFailedPowerOff(err)
+            if servicing_type != ServicingType::AbUpdate =>
+        {
             // Only CleanInstall is possible here; return the error.
             return Err(err);
         }
+        BootValidationResult::ValidBootHealthCheckFailed(_)
+        | BootValidationResult::ValidBootHealthCheckFailedPowerOff(_) => {
+            return Ok(health_check_status);
+        }
+        _ => {}
     }
 
     // If it's virtdeploy, after confirming that we have booted into the correct image, we need
@@ -223,15 +326,11 @@ fn commit_finalized_on_expected_root(
     }
 
     // Commit must finish configuring UEFI fallback as configured
-    let root_path = if container::is_running_in_container()
-        .message("Failed to check if Trident is running in a container")?
-    {
-        container::get_host_root_path().message("Failed to get host root path")?
-    } else {
-        PathBuf::from(ROOT_MOUNT_POINT_PATH)
-    };
+    let root_path = host_root_path()?;
     esp::set_uefi_fallback_contents(ctx, current_servicing_state, &root_path)
         .structured(ServicingError::SetUpUefiFallback)?;
+    esp::sync_esp_mirrors(ctx, &join_relative(&root_path, ESP_MOUNT_POINT_PATH))
+        .structured(ServicingError::SyncEspMirrors)?;
 
     // If this is a UKI image, then we need to re-generate pcrlock policy to include the PCRs
     // selected by the user for the current boot only.
@@ -286,15 +385,28 @@ fn commit_finalized_on_expected_root(
     datastore.with_host_status(|host_status| {
         host_status.servicing_state = ServicingState::Provisioned;
         host_status.spec_old = Default::default();
+        host_status.failed_boots = 0;
         host_status.ab_active_volume = match host_status.ab_active_volume {
             None | Some(AbVolumeSelection::VolumeB) => Some(AbVolumeSelection::VolumeA),
             Some(AbVolumeSelection::VolumeA) => Some(AbVolumeSelection::VolumeB),
         };
+        // Extension images of the target OS are merged when it boots.
+        extensions::status::record(host_status);
+        host_status.kernel_command_line = read_kernel_command_line();
     })?;
 
     Ok(BootValidationResult::ValidBootProvisioned)
 }
 
+/// Returns the kernel command line of the running OS. Failures are only logged, as the command line
+/// is only informational.
+fn read_kernel_command_line() -> Option<String> {
+    fs::read_to_string(KERNEL_COMMAND_LINE_PATH)
+        .inspect_err(|e| warn!("Failed to read '{KERNEL_COMMAND_LINE_PATH}': {e}"))
+        .ok()
+        .map(|cmdline| cmdline.trim().to_string())
+}
+
 fn run_health_checks(
     ctx: &EngineContext,
     datastore: &mut DataStore,
@@ -304,58 +416,65 @@ fn run_health_checks(
     match current_servicing_state {
         ServicingState::AbUpdateFinalized | ServicingState::CleanInstallFinalized => {
             // Execute health checks, if at least one fails, trigger rollback
-            match health::execute_health_checks(ctx) {
+            match health::execute_health_checks(ctx, datastore) {
                 Ok(()) => {}
                 Err(e) => {
                     error!("Health check(s) failure: {e:?}");
                     let structured_error =
                         serde_yaml::to_value(&e).structured(InternalError::SerializeError)?;
+
+                    // The rollback policy only applies to A/B updates.
+                    let mut action = FailureAction::Rollback;
+                    if servicing_type == ServicingType::AbUpdate {
+                        let policy = ctx.spec.health.rollback_policy.clone().unwrap_or_default();
+                        action = failure_action(&policy, datastore.host_status());
+                        if action == FailureAction::RetryNextBoot {
+                            if let Err(err) = bootentries::set_boot_next_to_current(ctx) {
+                                warn!("Failed to boot target OS again, not retrying: {err:?}");
+                                action = if policy.power_off {
+                                    FailureAction::PowerOff
+                                } else {
+                                    FailureAction::Rollback
+                                };
+                            }
+                        }
+                    }
+                    match action {
+                        FailureAction::RetryNextBoot => {
+                            info!("Rebooting into target OS to run health check(s) again")
+                        }
+                        FailureAction::Rollback => {}
+                        FailureAction::PowerOff => {
+                            info!("Powering off instead of rolling back, as per rollback policy")
+                        }
+                    }
+
                     // Update host status to reflect health check(s) failure
                     datastore.with_host_status(|host_status| {
                         host_status.servicing_state = match servicing_type {
+                            ServicingType::AbUpdate if action == FailureAction::RetryNextBoot => {
+                                current_servicing_state
+                            }
                             ServicingType::AbUpdate => ServicingState::AbUpdateHealthCheckFailed,
                             ServicingType::CleanInstall => ServicingState::NotProvisioned,
                             // Shouldn't happen because of previous checks
                             _ => current_servicing_state,
                         };
+                        if servicing_type == ServicingType::AbUpdate {
+                            host_status.failed_boots += 1;
+                        }
                         host_status.last_error = Some(structured_error);
                     })?;
 
-                    // Generate the new log filename
-                    let new_commit_failure_log_filename = format!(
-                        "trident-health-check-failure-{}.log",
-                        Utc::now().format("%Y%m%dT%H%M%SZ")
-                    );
-
-                    // Fetch the directory path from the full datastore path
-                    let datastore_path =
-                        datastore.host_status().spec.trident.datastore_path.clone();
-                    if let Some(datastore_dir) = datastore_path.parent() {
-                        let new_commit_failure_log_path: PathBuf =
-                            datastore_dir.join(new_commit_failure_log_filename);
-
-                        debug!(
-                            "Persisting Trident health check(s) failure to '{}' ",
-                            new_commit_failure_log_path.display()
-                        );
-
-                        // Copy the background log file to the new location
-                        if let Err(log_error) =
-                            fs::write(&new_commit_failure_log_path, format!("{e:?}"))
-                        {
-                            warn!(
-                                "Failed to persist Trident health check(s) failure to '{}': {}",
-                                new_commit_failure_log_path.display(),
-                                log_error
-                            );
-                        } else {
-                            debug!(
-                                "Successfully persisted Trident health check(s) failure to '{}'",
-                                new_commit_failure_log_path.display()
-                            );
+                    persist_failure_log(datastore, &e);
+                    return Ok(match action {
+                        FailureAction::RetryNextBoot | FailureAction::Rollback => {
+                            BootValidationResult::ValidBootHealthCheckFailed(e)
                         }
-                    }
-                    return Ok(BootValidationResult::ValidBootHealthCheckFailed(e));
+                        FailureAction::PowerOff => {
+                            BootValidationResult::ValidBootHealthCheckFailedPowerOff(e)
+                        }
+                    });
                 }
             };
         }
@@ -364,6 +483,75 @@ fn run_health_checks(
     Ok(BootValidationResult::ValidBootProvisioned)
 }
 
+/// Writes the health check(s) failure `e` to a log file next to the datastore. Failures to write it
+/// are only logged.
+fn persist_failure_log(datastore: &DataStore, e: &TridentError) {
+    // Generate the new log filename
+    let new_commit_failure_log_filename = format!(
+        "trident-health-check-failure-{}.log",
+        Utc::now().format("%Y%m%dT%H%M%SZ")
+    );
+
+    // Fetch the directory path from the full datastore path
+    let datastore_path = datastore.host_status().spec.trident.datastore_path.clone();
+    if let Some(datastore_dir) = datastore_path.parent() {
+        let new_commit_failure_log_path: PathBuf =
+            datastore_dir.join(new_commit_failure_log_filename);
+
+        debug!(
+            "Persisting Trident health check(s) failure to '{}' ",
+            new_commit_failure_log_path.display()
+        );
+
+        // Copy the background log file to the new location
+        if let Err(log_error) = fs::write(&new_commit_failure_log_path, format!("{e:?}")) {
+            warn!(
+                "Failed to persist Trident health check(s) failure to '{}': {}",
+                new_commit_failure_log_path.display(),
+                log_error
+            );
+        } else {
+            debug!(
+                "Successfully persisted Trident health check(s) failure to '{}'",
+                new_commit_failure_log_path.display()
+            );
+        }
+    }
+}
+
+/// Reaction to the failure of the health checks of an A/B update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FailureAction {
+    /// Reboot into the target OS to run the health checks again.
+    RetryNextBoot,
+    /// Reboot into the previous OS.
+    Rollback,
+    /// Power the host off; the previous OS boots when the host is powered on again.
+    PowerOff,
+}
+
+/// Returns the reaction to the failure of the health checks of the current boot, according to
+/// `policy` and to the outcome of the health checks and the failed boots in `host_status`.
+fn failure_action(policy: &RollbackPolicy, host_status: &HostStatus) -> FailureAction {
+    let failed_boots = host_status.failed_boots + 1;
+    let script_failed = host_status.health_checks.iter().any(|outcome| {
+        outcome.kind == HealthCheckKind::Script && outcome.verdict == HealthCheckVerdict::Fail
+    });
+    if failed_boots < policy.max_failed_boots
+        && !(script_failed && policy.script_failure == ScriptFailureAction::Rollback)
+    {
+        info!(
+            "Health check(s) failed in {failed_boots} of at most {} boot(s) of the target OS",
+            policy.max_failed_boots
+        );
+        FailureAction::RetryNextBoot
+    } else if policy.power_off {
+        FailureAction::PowerOff
+    } else {
+        FailureAction::Rollback
+    }
+}
+
 /// Returns the current root device path, i.e., the path of the root block device that the host
 /// booted from. The path is given in its canonical form.
 fn get_current_root_device_path(ctx: &EngineContext) -> Result<PathBuf, TridentError> {
@@ -671,6 +859,35 @@ fn validate_ab_active_volume_internal(
     Ok(())
 }
 
+/// Checks that the verity device of the target OS was opened with the root hash of the deployed
+/// OS image, i.e. that the booted kernel command line or UKI carries the expected root hash.
+fn check_verity_root_hash(ctx: &EngineContext, expected: Option<&str>) -> Result<(), TridentError> {
+    let (Some(expected), Some(verity_device_config)) = (expected, ctx.spec.storage.verity.first())
+    else {
+        return Ok(());
+    };
+
+    let verity_status = veritysetup::status(&verity_device_config.name)
+        .and_then(|status| status.active().context("Verity device is not active"))
+        .structured(ServicingError::GetVerityStatus {
+            device_name: verity_device_config.name.clone(),
+        })?;
+
+    if verity_status.root_hash != expected {
+        return Err(TridentError::new(ServicingError::VerityRootHashMismatch {
+            device_name: verity_device_config.name.clone(),
+            expected: expected.to_string(),
+            actual: verity_status.root_hash,
+        }));
+    }
+
+    debug!(
+        "Verity device '{}' was opened with the expected root hash '{expected}'",
+        verity_device_config.name
+    );
+    Ok(())
+}
+
 /// Returns the path of the verity data device for the given block device ID. Uses the
 /// `veritysetup` utility to fetch the actual data device path in the system.
 fn get_verity_data_device_path(
@@ -711,7 +928,7 @@ fn get_verity_data_device_path(
 mod tests {
     use super::*;
 
-    use std::path::PathBuf;
+    use std::{net::TcpListener, path::PathBuf};
 
     use const_format::formatcp;
     use maplit::btreemap;
@@ -719,14 +936,202 @@ mod tests {
     use osutils::testutils::repart::TEST_DISK_DEVICE_PATH;
     use trident_api::{
         config::{
-            AbUpdate, AbVolumePair, Disk, FileSystem, FileSystemSource, MountOptions, MountPoint,
-            Partition, PartitionType, VerityDevice,
+            AbUpdate, AbVolumePair, Check, CheckSeverity, Disk, FileSystem, FileSystemSource,
+            HealthMonitor, MountOptions, MountPoint, Partition, PartitionType,
+            ServicingTypeSelection, TcpCheck, TcpCheckTarget, VerityDevice,
         },
         constants::MOUNT_OPTION_READ_ONLY,
         error::ErrorKind,
-        status::AbVolumeSelection,
+        status::{AbVolumeSelection, HealthCheckOutcome},
     };
 
+    #[test]
+    fn test_failure_action() {
+        let policy = RollbackPolicy {
+            max_failed_boots: 3,
+            ..Default::default()
+        };
+        let mut host_status = HostStatus::default();
+        assert_eq!(
+            failure_action(&policy, &host_status),
+            FailureAction::RetryNextBoot
+        );
+        host_status.failed_boots = 2;
+        assert_eq!(
+            failure_action(&policy, &host_status),
+            FailureAction::Rollback
+        );
+        assert_eq!(
+            failure_action(
+                &RollbackPolicy {
+                    power_off: true,
+                    ..policy.clone()
+                },
+                &host_status
+            ),
+            FailureAction::PowerOff
+        );
+
+        // Script failures stop the retries right away when configured so.
+        host_status.failed_boots = 0;
+        host_status.health_checks = vec![HealthCheckOutcome {
+            name: "script".into(),
+            kind: HealthCheckKind::Script,
+            verdict: HealthCheckVerdict::Fail,
+            duration_millis: 10,
+            exit_code: Some(1),
+            output: None,
+            error: None,
+        }];
+        assert_eq!(
+            failure_action(&policy, &host_status),
+            FailureAction::RetryNextBoot
+        );
+        assert_eq!(
+            failure_action(
+                &RollbackPolicy {
+                    script_failure: ScriptFailureAction::Rollback,
+                    ..policy.clone()
+                },
+                &host_status
+            ),
+            FailureAction::Rollback
+        );
+
+        // Without a policy, the target OS is rolled back after the first failed boot.
+        assert_eq!(
+            failure_action(&RollbackPolicy::default(), &HostStatus::default()),
+            FailureAction::Rollback
+        );
+    }
+
+    #[test]
+    fn test_run_health_checks_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut datastore =
+            DataStore::open_or_create(&dir.path().join("datastore.sqlite")).unwrap();
+
+        // Nothing listens on the port once the listener is dropped, so the check fails.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut ctx = EngineContext {
+            servicing_type: ServicingType::AbUpdate,
+            ..Default::default()
+        };
+        ctx.spec.health.checks = vec![Check::TcpCheck(TcpCheck {
+            name: "telemetry".into(),
+            target: TcpCheckTarget {
+                host: "127.0.0.1".into(),
+                port,
+                timeout_seconds: 1,
+            },
+            retry: Default::default(),
+            severity: CheckSeverity::Warn,
+            run_on: vec![ServicingTypeSelection::AbUpdate],
+        })];
+
+        // A failed check with the warn severity does not fail the commit.
+        let result = run_health_checks(
+            &ctx,
+            &mut datastore,
+            ServicingState::AbUpdateFinalized,
+            ServicingType::AbUpdate,
+        )
+        .unwrap();
+        assert!(matches!(result, BootValidationResult::ValidBootProvisioned));
+
+        // The failure is recorded in the Host Status as a warning.
+        let host_status = datastore.host_status();
+        assert_eq!(host_status.failed_boots, 0);
+        assert!(host_status.last_error.is_none());
+        assert_eq!(host_status.health_checks.len(), 1);
+        assert_eq!(host_status.health_checks[0].name, "telemetry");
+        assert_eq!(
+            host_status.health_checks[0].verdict,
+            HealthCheckVerdict::Warn
+        );
+    }
+
+    #[test]
+    fn test_monitor_committed_update() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut datastore =
+            DataStore::open_or_create(&dir.path().join("datastore.sqlite")).unwrap();
+        datastore
+            .with_host_status(|host_status| {
+                host_status.servicing_state = ServicingState::Provisioned;
+                host_status.ab_active_volume = Some(AbVolumeSelection::VolumeB);
+            })
+            .unwrap();
+
+        // Nothing to monitor without a health monitor.
+        let result = monitor_committed_update(&mut datastore, Default::default()).unwrap();
+        assert!(matches!(result, BootValidationResult::ValidBootProvisioned));
+        assert!(datastore.host_status().health_checks.is_empty());
+
+        // Nothing listens on the port once the listener is dropped, so the check fails.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        datastore
+            .with_host_status(|host_status| {
+                host_status.spec.health.monitor = Some(HealthMonitor {
+                    window_seconds: 1,
+                    interval_seconds: 1,
+                });
+                host_status.spec.health.checks = vec![Check::TcpCheck(TcpCheck {
+                    name: "telemetry".into(),
+                    target: TcpCheckTarget {
+                        host: "127.0.0.1".into(),
+                        port,
+                        timeout_seconds: 1,
+                    },
+                    retry: Default::default(),
+                    severity: CheckSeverity::Warn,
+                    run_on: vec![ServicingTypeSelection::AbUpdate],
+                })];
+            })
+            .unwrap();
+
+        // A failed check with the warn severity does not roll the committed target OS back.
+        let result = monitor_committed_update(&mut datastore, Default::default()).unwrap();
+        assert!(matches!(result, BootValidationResult::ValidBootProvisioned));
+        let host_status = datastore.host_status();
+        assert_eq!(host_status.servicing_state, ServicingState::Provisioned);
+        assert_eq!(
+            host_status.ab_active_volume,
+            Some(AbVolumeSelection::VolumeB)
+        );
+        assert_eq!(host_status.health_checks.len(), 1);
+        assert_eq!(
+            host_status.health_checks[0].verdict,
+            HealthCheckVerdict::Warn
+        );
+    }
+
+    #[test]
+    fn test_check_verity_root_hash_skipped() {
+        // Nothing to check without a verity device.
+        let mut ctx = EngineContext::default();
+        check_verity_root_hash(&ctx, Some("root-hash")).unwrap();
+
+        // Nothing to check without a recorded root hash, e.g. when the target OS was staged by an
+        // older version of Trident.
+        ctx.spec.storage.verity.push(VerityDevice {
+            id: "root".into(),
+            name: "root".into(),
+            data_device_id: "root-data".into(),
+            hash_device_id: "root-hash".into(),
+            ..Default::default()
+        });
+        check_verity_root_hash(&ctx, None).unwrap();
+    }
+
     #[test]
     fn test_get_expected_root_device_path() {
         let mut ctx = EngineContext {
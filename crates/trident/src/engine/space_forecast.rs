@@ -0,0 +1,288 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error};
+use log::{debug, error, info, warn};
+use nix::sys::statvfs;
+
+use osutils::container;
+use trident_api::{
+    constants::internal_params::DISABLE_SPACE_FORECAST,
+    error::{InvalidInputError, TridentError, TridentResultExt},
+    primitives::bytes::ByteCount,
+};
+
+use crate::engine::{EngineContext, Subsystem};
+
+/// Free space kept on each filesystem for the logs, the datastore and filesystem metadata written
+/// while staging.
+const HEADROOM_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Space needed on a filesystem of the servicing OS to stage an update.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct SpaceDemand {
+    /// Directory written to. It does not need to exist yet.
+    pub path: PathBuf,
+
+    /// Artifact written, e.g. the URL of an extension image. An artifact written to several
+    /// directories of the same filesystem is only counted once, as it is moved between them.
+    pub artifact: String,
+
+    /// Estimated number of bytes written.
+    pub bytes: u64,
+}
+
+/// Free space on a filesystem.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct FilesystemSpace {
+    id: u64,
+    available: u64,
+}
+
+/// Space missing on a filesystem.
+#[derive(Debug, PartialEq, Eq)]
+struct Shortfall {
+    path: PathBuf,
+    artifacts: Vec<String>,
+    required: u64,
+    available: u64,
+}
+
+/// Checks that the filesystems of the servicing OS have enough free space to stage the update
+/// described by `ctx`, as forecast by the subsystems, so that staging does not run out of space
+/// halfway through.
+pub(super) fn check(
+    subsystems: &[Box<dyn Subsystem>],
+    ctx: &EngineContext,
+) -> Result<(), TridentError> {
+    if ctx.spec.internal_params.get_flag(DISABLE_SPACE_FORECAST) {
+        warn!("Skipping space forecast as '{DISABLE_SPACE_FORECAST}' is set");
+        return Ok(());
+    }
+
+    let demands = subsystems
+        .iter()
+        .flat_map(|subsystem| subsystem.forecast_space(ctx))
+        .collect::<Vec<_>>();
+    if demands.is_empty() {
+        debug!("No space needed on the servicing OS to stage the update");
+        return Ok(());
+    }
+
+    check_demands(&demands)?;
+    info!("Enough space is available on the servicing OS to stage the update");
+    Ok(())
+}
+
+/// Checks that the filesystems holding the directories of `demands` have enough free space for
+/// all of them. Returns an error naming the first filesystem that does not.
+pub(crate) fn check_demands(demands: &[SpaceDemand]) -> Result<(), TridentError> {
+    let shortfalls = shortfalls(demands, filesystem_space);
+    for shortfall in &shortfalls {
+        error!(
+            "Missing {} on the filesystem holding '{}' to stage {}",
+            ByteCount(shortfall.required - shortfall.available).to_human_readable_approx(),
+            shortfall.path.display(),
+            shortfall.artifacts.join(", ")
+        );
+    }
+    match shortfalls.into_iter().next() {
+        Some(shortfall) => Err(TridentError::new(InvalidInputError::InsufficientSpace {
+            path: shortfall.path.display().to_string(),
+            purposes: shortfall.artifacts.join(", "),
+            required: ByteCount(shortfall.required),
+            available: ByteCount(shortfall.available),
+        })),
+        None => Ok(()),
+    }
+}
+
+/// Sums up `demands` per filesystem, as returned by `stat`, and returns the filesystems without
+/// enough free space. Demands on directories that cannot be inspected are ignored.
+fn shortfalls(
+    demands: &[SpaceDemand],
+    stat: impl Fn(&Path) -> Result<FilesystemSpace, Error>,
+) -> Vec<Shortfall> {
+    // Filesystem ID -> (space, first directory, artifact -> bytes)
+    let mut filesystems = BTreeMap::<u64, (FilesystemSpace, &Path, BTreeMap<&str, u64>)>::new();
+    for demand in demands {
+        let space = match stat(&demand.path) {
+            Ok(space) => space,
+            Err(e) => {
+                warn!(
+                    "Not forecasting space for '{}': {e:?}",
+                    demand.path.display()
+                );
+                continue;
+            }
+        };
+        let (_, _, artifacts) = filesystems
+            .entry(space.id)
+            .or_insert_with(|| (space, demand.path.as_path(), BTreeMap::new()));
+        let bytes = artifacts.entry(demand.artifact.as_str()).or_default();
+        *bytes = (*bytes).max(demand.bytes);
+    }
+
+    filesystems
+        .into_values()
+        .filter_map(|(space, path, artifacts)| {
+            let required = artifacts.values().sum::<u64>() + HEADROOM_BYTES;
+            debug!(
+                "Forecast {} needed on the filesystem holding '{}', {} available",
+                ByteCount(required).to_human_readable_approx(),
+                path.display(),
+                ByteCount(space.available).to_human_readable_approx()
+            );
+            (required > space.available).then(|| Shortfall {
+                path: path.to_path_buf(),
+                artifacts: artifacts.into_keys().map(String::from).collect(),
+                required,
+                available: space.available,
+            })
+        })
+        .collect()
+}
+
+/// Returns the root of the servicing OS, i.e. the root of the host when Trident is running in a
+/// container.
+pub(crate) fn host_root() -> Result<PathBuf, Error> {
+    Ok(
+        if container::is_running_in_container()
+            .unstructured("Failed to check if Trident is running in a container")?
+        {
+            container::get_host_root_path().unstructured("Failed to get host root path")?
+        } else {
+            PathBuf::from("/")
+        },
+    )
+}
+
+/// Returns the total size of the files under `path`, which may not exist.
+pub(crate) fn directory_size(path: &Path) -> Result<u64, Error> {
+    if !path.exists() {
+        return Ok(0);
+    }
+    let mut size = 0;
+    for entry in
+        fs::read_dir(path).with_context(|| format!("Failed to read '{}'", path.display()))?
+    {
+        let entry = entry.with_context(|| format!("Failed to read '{}'", path.display()))?;
+        let metadata = entry
+            .metadata()
+            .with_context(|| format!("Failed to get metadata of '{}'", entry.path().display()))?;
+        size += if metadata.is_dir() {
+            directory_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
+/// Returns the free space on the filesystem holding `path`, or its closest existing ancestor.
+fn filesystem_space(path: &Path) -> Result<FilesystemSpace, Error> {
+    let existing = path
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(Path::new("/"));
+    let stat = statvfs::statvfs(existing)
+        .with_context(|| format!("Failed to get free space of '{}'", existing.display()))?;
+    Ok(FilesystemSpace {
+        id: stat.filesystem_id(),
+        available: stat.blocks_available() * stat.fragment_size(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::bail;
+
+    const MIB: u64 = 1024 * 1024;
+
+    fn demand(path: &str, artifact: &str, bytes: u64) -> SpaceDemand {
+        SpaceDemand {
+            path: path.into(),
+            artifact: artifact.into(),
+            bytes,
+        }
+    }
+
+    /// /var and /var/lib/extensions are on one filesystem, /boot/efi on another.
+    fn stat(path: &Path) -> Result<FilesystemSpace, Error> {
+        if path.starts_with("/var") {
+            Ok(FilesystemSpace {
+                id: 1,
+                available: 100 * MIB,
+            })
+        } else if path.starts_with("/boot/efi") {
+            Ok(FilesystemSpace {
+                id: 2,
+                available: 20 * MIB,
+            })
+        } else {
+            bail!("Unknown path")
+        }
+    }
+
+    #[test]
+    fn test_shortfalls() {
+        // An image moved from the staging directory is only counted once.
+        let demands = vec![
+            demand(
+                "/var/lib/trident/staging",
+                "https://example.com/a.raw",
+                60 * MIB,
+            ),
+            demand("/var/lib/extensions", "https://example.com/a.raw", 60 * MIB),
+            demand("/boot/efi/EFI/AZLB", "ESP files", 4 * MIB),
+            demand("/unknown", "https://example.com/b.raw", 500 * MIB),
+        ];
+        assert!(shortfalls(&demands, stat).is_empty());
+
+        let demands = vec![
+            demand(
+                "/var/lib/trident/staging",
+                "https://example.com/a.raw",
+                60 * MIB,
+            ),
+            demand("/var/lib/extensions", "https://example.com/b.raw", 30 * MIB),
+            demand("/boot/efi/EFI/AZLB", "ESP files", 4 * MIB),
+        ];
+        assert_eq!(
+            shortfalls(&demands, stat),
+            vec![Shortfall {
+                path: "/var/lib/trident/staging".into(),
+                artifacts: vec![
+                    "https://example.com/a.raw".into(),
+                    "https://example.com/b.raw".into()
+                ],
+                required: 90 * MIB + HEADROOM_BYTES,
+                available: 100 * MIB,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_directory_size() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a"), [0; 100]).unwrap();
+        fs::create_dir(dir.path().join("b")).unwrap();
+        fs::write(dir.path().join("b/c"), [0; 20]).unwrap();
+        assert_eq!(directory_size(dir.path()).unwrap(), 120);
+        assert_eq!(directory_size(&dir.path().join("d")).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_filesystem_space() {
+        let space = filesystem_space(Path::new("/")).unwrap();
+        assert_eq!(
+            filesystem_space(Path::new("/does/not/exist")).unwrap().id,
+            space.id
+        );
+    }
+}
@@ -37,6 +37,7 @@ use crate::{
         storage::encryption::uki::{TMP_UKI_NAME, UKI_DIRECTORY},
         EngineContext,
     },
+    secrets,
 };
 
 /// Closes all open LUKS2-encrypted volumes found on the system.
@@ -83,7 +84,7 @@ pub(super) fn create_encrypted_devices(
         if let Some(recovery_key_url) = &encryption.recovery_key_url {
             key_file_path = recovery_key_url.path().into()
         } else {
-            // Create a temporary file to store the recovery key file.
+            // Create a temporary file to store the provided or a generated recovery key.
             key_file_tmp =
                 NamedTempFile::new().structured(ServicingError::CreateRecoveryKeyFile)?;
             key_file_path = key_file_tmp.path().to_owned();
@@ -92,11 +93,15 @@ pub(super) fn create_encrypted_devices(
                     key_file: key_file_path.to_string_lossy().to_string(),
                 },
             )?;
-            encryption::generate_recovery_key_file(&key_file_path).structured(
-                ServicingError::GenerateRecoveryKeyFile {
-                    key_file: key_file_path.to_string_lossy().to_string(),
-                },
-            )?;
+            match &encryption.recovery_key {
+                Some(recovery_key) => fs::write(&key_file_path, secrets::resolve(recovery_key)?)
+                    .structured(ServicingError::CreateRecoveryKeyFile)?,
+                None => encryption::generate_recovery_key_file(&key_file_path).structured(
+                    ServicingError::GenerateRecoveryKeyFile {
+                        key_file: key_file_path.to_string_lossy().to_string(),
+                    },
+                )?,
+            }
         };
 
         debug!(
@@ -198,7 +203,7 @@ pub(super) fn create_encrypted_devices(
             // If the key file was randomly generated and NOT provided by the user as a
             // recovery key, remove the password key slot from the encrypted volume, as it's
             // not needed, for security
-            if encryption.recovery_key_url.is_none() {
+            if encryption.recovery_key_url.is_none() && encryption.recovery_key.is_none() {
                 debug!(
                         "Recovery key file not provided, so removing password key slot from encrypted volume with id '{}'",
                         ev.id
@@ -115,6 +115,17 @@ fn block_devices_needing_fs_creation(
         block_devices.push((effective_device_id.clone(), bd_path, fs_type.try_into()?));
     }
 
+    // ESP mirrors have no filesystem in the Host Configuration, but are formatted like the ESP
+    // during clean install, before the ESP is copied to them.
+    if ctx.servicing_type == ServicingType::CleanInstall {
+        for mirror_id in &ctx.spec.storage.esp_mirrors {
+            let bd_path = ctx.get_block_device_path(mirror_id).with_context(|| {
+                format!("Block device path not found for ESP mirror: {mirror_id:?}")
+            })?;
+            block_devices.push((mirror_id.clone(), bd_path, MkfsFileSystemType::Vfat));
+        }
+    }
+
     debug!(
         "Found {} block device{} needing filesystem creation",
         block_devices.len(),
@@ -396,6 +407,37 @@ mod tests {
         )));
     }
 
+    /// Test that block_devices_needing_fs_creation() returns the ESP mirrors only
+    /// during clean install.
+    #[test]
+    fn test_block_devices_needing_fs_creation_esp_mirrors() {
+        let mut ctx = EngineContext {
+            servicing_type: ServicingType::CleanInstall,
+            spec: HostConfiguration {
+                storage: StorageConfig {
+                    esp_mirrors: vec!["esp-mirror".into()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            partition_paths: btreemap! {
+                "esp-mirror".to_owned() => PathBuf::from("/dev/disk/by-partlabel/osp1"),
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            block_devices_needing_fs_creation(&ctx).unwrap(),
+            vec![(
+                "esp-mirror".to_owned(),
+                PathBuf::from("/dev/disk/by-partlabel/osp1"),
+                MkfsFileSystemType::Vfat
+            )]
+        );
+
+        ctx.servicing_type = ServicingType::AbUpdate;
+        assert!(block_devices_needing_fs_creation(&ctx).unwrap().is_empty());
+    }
+
     /// Test that block_devices_needing_fs_creation() does not return any block
     /// devices that are adopted ESP partitions.
     #[test]
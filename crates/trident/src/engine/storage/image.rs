@@ -15,7 +15,7 @@ use trident_api::{
 
 use crate::{
     engine::{context::filesystem::FileSystemDataImage, EngineContext},
-    io_utils::{hashing_reader::HashingReader384, image_streamer},
+    io_utils::{hashing_reader::HashingReader384, image_streamer, progress_reader::ProgressReader},
     osimage::OsImageFile,
 };
 
@@ -211,11 +211,13 @@ fn deploy_os_image_file(
         dev_info.size
     );
 
-    let stream = HashingReader384::new(
+    let stream = HashingReader384::new(ProgressReader::new(
         image_file
             .reader()
             .context("Failed to create reader for filesystem image file")?,
-    );
+        format!("Writing image to '{id}'"),
+        Some(image_file.compressed_size),
+    ));
 
     let computed_sha384 = image_streamer::stream_zstd_and_hash(stream, &block_device_path)
         .context(format!(
@@ -0,0 +1,374 @@
+use std::{
+    collections::HashSet,
+    fmt::{Display, Formatter, Result as FmtResult},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Error};
+use log::{debug, info};
+
+use osutils::{
+    block_devices::{self, ResolvedDisk},
+    dependencies::Dependency,
+    encryption,
+    lsblk::{self, BlockDevice, BlockDeviceType},
+    mdadm, mount,
+    swap::{self, SwapSpace},
+};
+use trident_api::config::{HostConfiguration, InUseDevicePolicy};
+
+use super::partitioning;
+
+/// Mount point reported by lsblk for active swap devices.
+const SWAP_MOUNT_POINT: &str = "[SWAP]";
+
+/// Kind of a device stacked on top of a target device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HolderKind {
+    Lvm,
+    DeviceMapper,
+    Encrypted,
+    Raid,
+    Other,
+}
+
+impl HolderKind {
+    fn of(device: &BlockDevice) -> Self {
+        match device.blkdev_type {
+            BlockDeviceType::Lvm => Self::Lvm,
+            BlockDeviceType::Crypt => Self::Encrypted,
+            BlockDeviceType::Dm | BlockDeviceType::Dmraid | BlockDeviceType::Mpath => {
+                Self::DeviceMapper
+            }
+            BlockDeviceType::Md | BlockDeviceType::Raid => Self::Raid,
+            // lsblk reports RAID arrays with their level as type, e.g. `raid1`.
+            _ if device.name.starts_with("/dev/md") => Self::Raid,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl Display for HolderKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.pad(match self {
+            Self::Lvm => "LVM volume",
+            Self::DeviceMapper => "device-mapper device",
+            Self::Encrypted => "encrypted volume",
+            Self::Raid => "RAID array",
+            Self::Other => "device",
+        })
+    }
+}
+
+/// Reason a target device cannot be written to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Blocker {
+    /// The device holds a mounted filesystem.
+    Mounted {
+        device: PathBuf,
+        mount_point: PathBuf,
+    },
+
+    /// The device is an active swap device.
+    Swap { device: PathBuf },
+
+    /// Another device is stacked on top of the device.
+    Held {
+        device: PathBuf,
+        holder: PathBuf,
+        kind: HolderKind,
+    },
+}
+
+impl Display for Blocker {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Mounted {
+                device,
+                mount_point,
+            } => write!(
+                f,
+                "'{}' is mounted at '{}'",
+                device.display(),
+                mount_point.display()
+            ),
+            Self::Swap { device } => write!(f, "'{}' is an active swap device", device.display()),
+            Self::Held {
+                device,
+                holder,
+                kind,
+            } => write!(
+                f,
+                "'{}' is held by {kind} '{}'",
+                device.display(),
+                holder.display()
+            ),
+        }
+    }
+}
+
+/// Checks that the devices on the disks of the Host Configuration that are about to be
+/// re-partitioned are not in use by the running system. Depending on the policy of the Host
+/// Configuration, devices in use are either released, or an error listing all of them is
+/// returned before anything is written to the disks.
+///
+/// Adopted partitions are kept by re-partitioning, so they may remain in use.
+pub(super) fn check_target_devices(host_config: &HostConfiguration) -> Result<(), Error> {
+    debug!("Checking whether target devices are in use");
+    let disks =
+        block_devices::get_resolved_disks(host_config).context("Failed to resolve disk paths")?;
+    let swaps = SwapSpace::read()
+        .context("Failed to list swap devices")?
+        .into_iter()
+        .map(|swap| PathBuf::from(swap.name))
+        .collect::<HashSet<_>>();
+
+    let mut blockers = Vec::new();
+    for disk in &disks {
+        find_disk_blockers(disk, &swaps, &mut blockers)
+            .with_context(|| format!("Failed to check whether disk '{}' is in use", disk.id))?;
+    }
+    if blockers.is_empty() {
+        debug!("No target device is in use");
+        return Ok(());
+    }
+
+    let list = blockers
+        .iter()
+        .map(|blocker| format!("\n- {blocker}"))
+        .collect::<String>();
+    match host_config.storage.in_use_devices {
+        InUseDevicePolicy::Fail => bail!(
+            "Target devices are in use, release them or set 'storage.inUseDevices' to \
+            'tear-down':{list}"
+        ),
+        InUseDevicePolicy::TearDown => {
+            info!("Releasing target devices in use:{list}");
+            tear_down(&blockers)
+        }
+    }
+}
+
+/// Finds the devices in use on a disk, skipping adopted partitions.
+fn find_disk_blockers(
+    disk: &ResolvedDisk,
+    swaps: &HashSet<PathBuf>,
+    blockers: &mut Vec<Blocker>,
+) -> Result<(), Error> {
+    let device = lsblk::get(&disk.bus_path)?;
+    if disk.spec.adopted_partitions.is_empty() {
+        find_blockers(&device, swaps, blockers);
+        return Ok(());
+    }
+
+    let unadopted = partitioning::unadopted_partitions(disk)?
+        .into_iter()
+        .map(|node| node.canonicalize().unwrap_or(node))
+        .collect::<HashSet<_>>();
+    for partition in device
+        .children
+        .iter()
+        .filter(|partition| unadopted.contains(Path::new(&partition.name)))
+    {
+        find_blockers(partition, swaps, blockers);
+    }
+    Ok(())
+}
+
+/// Finds the reasons `device` and its children are in use, in the order they must be released:
+/// devices stacked on top of others come before the devices they sit on.
+fn find_blockers(device: &BlockDevice, swaps: &HashSet<PathBuf>, blockers: &mut Vec<Blocker>) {
+    for child in &device.children {
+        find_blockers(child, swaps, blockers);
+    }
+
+    let name = PathBuf::from(&device.name);
+    // Devices stacked on several others, e.g. RAID arrays, are found once per member.
+    let mut push = |blocker: Blocker| {
+        if !blockers.contains(&blocker) {
+            blockers.push(blocker);
+        }
+    };
+    for child in &device.children {
+        if child.blkdev_type != BlockDeviceType::Partition {
+            push(Blocker::Held {
+                device: name.clone(),
+                holder: child.name.clone().into(),
+                kind: HolderKind::of(child),
+            });
+        }
+    }
+    for mount_point in &device.mountpoints {
+        if mount_point != Path::new(SWAP_MOUNT_POINT) {
+            push(Blocker::Mounted {
+                device: name.clone(),
+                mount_point: mount_point.clone(),
+            });
+        }
+    }
+    if swaps.contains(&name)
+        || device
+            .mountpoints
+            .iter()
+            .any(|mount_point| mount_point == Path::new(SWAP_MOUNT_POINT))
+    {
+        push(Blocker::Swap { device: name });
+    }
+}
+
+/// Releases the devices in use, in order.
+fn tear_down(blockers: &[Blocker]) -> Result<(), Error> {
+    let mut released = HashSet::new();
+    for blocker in blockers {
+        match blocker {
+            Blocker::Mounted { mount_point, .. } => {
+                debug!("Unmounting '{}'", mount_point.display());
+                mount::umount(mount_point, false)?;
+            }
+            Blocker::Swap { device } => {
+                debug!("Disabling swap on '{}'", device.display());
+                swap::swapoff(device)?;
+            }
+            Blocker::Held {
+                device,
+                holder,
+                kind,
+            } => {
+                // Holders of several devices only need to be stopped once.
+                if !released.insert(holder) {
+                    continue;
+                }
+                debug!("Stopping {kind} '{}'", holder.display());
+                match kind {
+                    HolderKind::Lvm | HolderKind::DeviceMapper => Dependency::Dmsetup
+                        .cmd()
+                        .arg("remove")
+                        .arg(holder)
+                        .run_and_check()
+                        .with_context(|| {
+                            format!("Failed to remove {kind} '{}'", holder.display())
+                        })?,
+                    HolderKind::Encrypted => {
+                        encryption::cryptsetup_close(&holder.to_string_lossy())?
+                    }
+                    HolderKind::Raid => mdadm::stop(holder)?,
+                    HolderKind::Other => bail!(
+                        "Cannot release '{}', it is held by '{}' of an unsupported type",
+                        device.display(),
+                        holder.display()
+                    ),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(name: &str, blkdev_type: BlockDeviceType, children: Vec<BlockDevice>) -> BlockDevice {
+        BlockDevice {
+            name: name.into(),
+            blkdev_type,
+            children,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_blockers() {
+        let mut lv = device("/dev/mapper/vg-data", BlockDeviceType::Lvm, vec![]);
+        lv.mountpoints = vec!["/data".into()];
+        let mut swap = device("/dev/sda3", BlockDeviceType::Partition, vec![]);
+        swap.mountpoints = vec![SWAP_MOUNT_POINT.into()];
+        let raid = device("/dev/md127", BlockDeviceType::Unknown, vec![]);
+        let disk = device(
+            "/dev/sda",
+            BlockDeviceType::Disk,
+            vec![
+                device("/dev/sda1", BlockDeviceType::Partition, vec![]),
+                device("/dev/sda2", BlockDeviceType::Partition, vec![lv]),
+                swap,
+                device("/dev/sda4", BlockDeviceType::Partition, vec![raid.clone()]),
+                device("/dev/sda5", BlockDeviceType::Partition, vec![raid]),
+            ],
+        );
+
+        let mut blockers = Vec::new();
+        find_blockers(
+            &disk,
+            &HashSet::from([PathBuf::from("/dev/sda3")]),
+            &mut blockers,
+        );
+        assert_eq!(
+            blockers,
+            vec![
+                Blocker::Mounted {
+                    device: "/dev/mapper/vg-data".into(),
+                    mount_point: "/data".into(),
+                },
+                Blocker::Held {
+                    device: "/dev/sda2".into(),
+                    holder: "/dev/mapper/vg-data".into(),
+                    kind: HolderKind::Lvm,
+                },
+                Blocker::Swap {
+                    device: "/dev/sda3".into(),
+                },
+                Blocker::Held {
+                    device: "/dev/sda4".into(),
+                    holder: "/dev/md127".into(),
+                    kind: HolderKind::Raid,
+                },
+                Blocker::Held {
+                    device: "/dev/sda5".into(),
+                    holder: "/dev/md127".into(),
+                    kind: HolderKind::Raid,
+                },
+            ]
+        );
+
+        // Nothing in use.
+        let mut blockers = Vec::new();
+        find_blockers(
+            &device(
+                "/dev/sdb",
+                BlockDeviceType::Disk,
+                vec![device("/dev/sdb1", BlockDeviceType::Partition, vec![])],
+            ),
+            &HashSet::new(),
+            &mut blockers,
+        );
+        assert!(blockers.is_empty());
+    }
+
+    #[test]
+    fn test_blocker_display() {
+        assert_eq!(
+            Blocker::Mounted {
+                device: "/dev/sda1".into(),
+                mount_point: "/mnt".into(),
+            }
+            .to_string(),
+            "'/dev/sda1' is mounted at '/mnt'"
+        );
+        assert_eq!(
+            Blocker::Swap {
+                device: "/dev/sda2".into(),
+            }
+            .to_string(),
+            "'/dev/sda2' is an active swap device"
+        );
+        assert_eq!(
+            Blocker::Held {
+                device: "/dev/sda3".into(),
+                holder: "/dev/mapper/luks".into(),
+                kind: HolderKind::Encrypted,
+            }
+            .to_string(),
+            "'/dev/sda3' is held by encrypted volume '/dev/mapper/luks'"
+        );
+    }
+}
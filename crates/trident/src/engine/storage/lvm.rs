@@ -0,0 +1,233 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, ensure, Context, Error};
+use log::{debug, info, trace};
+
+use osutils::{
+    dependencies::Dependency,
+    lsblk::{self, BlockDevice},
+    lvm::{self, LogicalVolumeKind, LogicalVolumeSize},
+};
+use trident_api::{
+    config::{HostConfiguration, PartitionSize, Storage, VolumeGroup},
+    BlockDeviceId,
+};
+
+use crate::engine::EngineContext;
+
+/// Returns whether the volume group is adopted, i.e. all its devices are adopted partitions.
+fn is_adopted(storage: &Storage, volume_group: &VolumeGroup) -> bool {
+    volume_group
+        .devices
+        .iter()
+        .all(|device_id| storage.is_adopted_partition(device_id))
+}
+
+/// Deactivates the volume groups with physical volumes on the disks of the Host Configuration,
+/// so that the devices below them can be closed and the disks re-partitioned.
+///
+/// Adopted volume groups sit on adopted partitions, which are kept by re-partitioning, so they
+/// are left active.
+#[tracing::instrument(skip_all)]
+pub(super) fn deactivate_pre_existing_volume_groups(
+    host_config: &HostConfiguration,
+) -> Result<(), Error> {
+    if !Dependency::Pvs.exists() {
+        trace!("LVM is not installed, no pre-existing volume groups to deactivate");
+        return Ok(());
+    }
+
+    let adopted = host_config
+        .storage
+        .lvm
+        .volume_groups
+        .iter()
+        .filter(|vg| is_adopted(&host_config.storage, vg))
+        .map(|vg| vg.name.as_str())
+        .collect::<HashSet<_>>();
+
+    // Collect all the devices on the disks of the Host Configuration.
+    let mut hc_devices = HashSet::new();
+    for disk in &host_config.storage.disks {
+        let disk_block_device = lsblk::get(&disk.device)
+            .with_context(|| format!("Failed to get block device '{}'", disk.device.display()))?;
+        collect_descendants(&disk_block_device, &mut hc_devices);
+    }
+
+    let volume_groups = lvm::list_physical_volumes()
+        .context("Failed to list physical volumes")?
+        .into_iter()
+        .filter(|pv| !pv.vg_name.is_empty() && hc_devices.contains(&pv.pv_name))
+        .map(|pv| pv.vg_name)
+        .collect::<HashSet<_>>();
+
+    for vg_name in volume_groups {
+        if adopted.contains(vg_name.as_str()) {
+            debug!("Keeping adopted volume group '{vg_name}' active");
+            continue;
+        }
+
+        debug!("Deactivating pre-existing volume group '{vg_name}'");
+        lvm::set_volume_group_active(&vg_name, false)
+            .with_context(|| format!("Failed to deactivate volume group '{vg_name}'"))?;
+    }
+
+    Ok(())
+}
+
+/// Adds the paths of all the descendants of `block_device` to `devices`.
+fn collect_descendants(block_device: &BlockDevice, devices: &mut HashSet<PathBuf>) {
+    for child in &block_device.children {
+        devices.insert(PathBuf::from(&child.name));
+        collect_descendants(child, devices);
+    }
+}
+
+/// Creates or adopts the volume groups of the Host Configuration, and creates their thin pools
+/// and logical volumes.
+#[tracing::instrument(name = "lvm_creation", fields(num_volume_groups = ctx.spec.storage.lvm.volume_groups.len()), skip_all)]
+pub(super) fn create_volume_groups(ctx: &EngineContext) -> Result<(), Error> {
+    let lvm_config = &ctx.spec.storage.lvm;
+    if lvm_config.volume_groups.is_empty() {
+        return Ok(());
+    }
+    if !Dependency::Vgcreate.exists() {
+        bail!("Failed to create LVM volume groups. LVM is required for volume groups");
+    }
+
+    for volume_group in &lvm_config.volume_groups {
+        let device_paths = get_device_paths(ctx, &volume_group.devices)?;
+
+        if is_adopted(&ctx.spec.storage, volume_group) {
+            info!("Adopting volume group '{}'", volume_group.id);
+            check_adopted_volume_group(&volume_group.name, &device_paths)?;
+            lvm::set_volume_group_active(&volume_group.name, true).with_context(|| {
+                format!("Failed to activate volume group '{}'", volume_group.name)
+            })?;
+        } else {
+            info!("Initializing '{}': creating volume group", volume_group.id);
+            lvm::create_physical_volumes(&device_paths).with_context(|| {
+                format!(
+                    "Failed to create physical volumes of volume group '{}'",
+                    volume_group.id
+                )
+            })?;
+            lvm::create_volume_group(&volume_group.name, &device_paths)
+                .with_context(|| format!("Failed to create volume group '{}'", volume_group.id))?;
+        }
+
+        create_logical_volumes(ctx, volume_group).with_context(|| {
+            format!(
+                "Failed to create logical volumes of volume group '{}'",
+                volume_group.id
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+fn get_device_paths(ctx: &EngineContext, devices: &[BlockDeviceId]) -> Result<Vec<PathBuf>, Error> {
+    devices
+        .iter()
+        .map(|device_id| {
+            ctx.get_block_device_path(device_id)
+                .with_context(|| format!("Failed to get block device path for '{device_id}'"))
+        })
+        .collect()
+}
+
+/// Checks that the devices of an adopted volume group are the physical volumes of an existing
+/// volume group with the expected name.
+fn check_adopted_volume_group(vg_name: &str, device_paths: &[PathBuf]) -> Result<(), Error> {
+    let physical_volumes = lvm::list_physical_volumes()
+        .context("Failed to list physical volumes")?
+        .into_iter()
+        .map(|pv| Ok((canonicalize(&pv.pv_name)?, pv)))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    for device_path in device_paths {
+        let device_path = canonicalize(device_path)?;
+        let (_, physical_volume) = physical_volumes
+            .iter()
+            .find(|(pv_path, _)| pv_path == &device_path)
+            .with_context(|| {
+                format!(
+                    "Adopted device '{}' is not a physical volume",
+                    device_path.display()
+                )
+            })?;
+        ensure!(
+            physical_volume.vg_name == vg_name,
+            "Adopted device '{}' belongs to volume group '{}' instead of '{vg_name}'",
+            device_path.display(),
+            physical_volume.vg_name
+        );
+    }
+
+    Ok(())
+}
+
+fn canonicalize(path: &Path) -> Result<PathBuf, Error> {
+    fs::canonicalize(path).with_context(|| format!("Failed to resolve '{}'", path.display()))
+}
+
+/// Creates the thin pools and logical volumes of `volume_group` that do not exist yet.
+///
+/// Volumes with a fixed size are created first, so that the volume that grows can take the space
+/// left. Thin logical volumes are created last, once their thin pools exist.
+fn create_logical_volumes(ctx: &EngineContext, volume_group: &VolumeGroup) -> Result<(), Error> {
+    let vg_name = volume_group.name.as_str();
+    let existing = lvm::list_logical_volumes(vg_name)
+        .context("Failed to list logical volumes")?
+        .into_iter()
+        .map(|lv| lv.lv_name)
+        .collect::<HashSet<_>>();
+
+    let mut volumes = volume_group
+        .thin_pools
+        .iter()
+        .map(|pool| (pool.name.as_str(), LogicalVolumeKind::ThinPool, pool.size))
+        .chain(
+            ctx.spec
+                .storage
+                .lvm
+                .logical_volumes_of(volume_group)
+                .map(|lv| {
+                    let kind = match &lv.thin_pool {
+                        Some(thin_pool) => LogicalVolumeKind::Thin {
+                            thin_pool: thin_pool.as_str(),
+                        },
+                        None => LogicalVolumeKind::Linear,
+                    };
+                    (lv.name.as_str(), kind, lv.size)
+                }),
+        )
+        .collect::<Vec<_>>();
+    volumes.sort_by_key(|(_, kind, size)| {
+        (
+            matches!(kind, LogicalVolumeKind::Thin { .. }),
+            *size == PartitionSize::Grow,
+        )
+    });
+
+    for (name, kind, size) in volumes {
+        if existing.contains(name) {
+            debug!("Logical volume '{name}' already exists in volume group '{vg_name}'");
+            continue;
+        }
+
+        let size = match size {
+            PartitionSize::Fixed(size) => LogicalVolumeSize::Bytes(size.bytes()),
+            PartitionSize::Grow => LogicalVolumeSize::Free,
+        };
+        lvm::create_logical_volume(vg_name, name, kind, size)
+            .with_context(|| format!("Failed to create logical volume '{name}'"))?;
+    }
+
+    Ok(())
+}
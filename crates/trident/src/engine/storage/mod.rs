@@ -14,6 +14,8 @@ mod common;
 pub mod encryption;
 mod filesystem;
 pub mod image;
+mod in_use;
+mod lvm;
 pub mod partitioning;
 pub mod raid;
 pub mod rebuild;
@@ -37,10 +39,14 @@ pub(super) fn create_block_devices(ctx: &mut EngineContext) -> Result<(), Triden
     // arrays, as both can sit on top of RAID arrays.
     close_pre_existing_devices(ctx).message("Closing pre-existing block devices failed")?;
 
+    // Make sure nothing on the disks is still in use before they are re-partitioned.
+    in_use::check_target_devices(&ctx.spec).structured(ServicingError::TargetDevicesInUse)?;
+
     partitioning::create_partitions(ctx).structured(ServicingError::CreatePartitions)?;
     raid::create_sw_raid(ctx, &ctx.spec).structured(ServicingError::CreateRaid)?;
     encryption::create_encrypted_devices(ctx, &ctx.spec)
         .message("Failed to create and open encrypted devices")?;
+    lvm::create_volume_groups(ctx).structured(ServicingError::CreateLvm)?;
 
     Ok(())
 }
@@ -49,9 +55,10 @@ pub(super) fn create_block_devices(ctx: &mut EngineContext) -> Result<(), Triden
 pub(super) fn close_pre_existing_devices(ctx: &EngineContext) -> Result<(), TridentError> {
     debug!("Closing pre-existing block devices");
 
-    // Close verity devices and encrypted volumes before stopping RAID
-    // arrays, as both can sit on top of RAID arrays.
+    // Close verity devices, LVM volume groups and encrypted volumes before
+    // stopping RAID arrays, as all can sit on top of RAID arrays.
     verity::stop_trident_servicing_devices(&ctx.spec).structured(ServicingError::CleanupVerity)?;
+    lvm::deactivate_pre_existing_volume_groups(&ctx.spec).structured(ServicingError::CleanupLvm)?;
     encryption::close_pre_existing_encrypted_volumes(&ctx.spec)
         .structured(ServicingError::CleanupEncryption)?;
     raid::stop_pre_existing_raid_arrays(&ctx.spec).structured(ServicingError::CleanupRaid)?;
@@ -196,37 +196,20 @@ fn partitioning_safety_check(disks: &Vec<ResolvedDisk>) -> Result<(), Error> {
             );
         }
 
-        let disk_info = SfDisk::get_info(&disk.bus_path).context(format!(
-            "Failed to retrieve information for disk '{}', the partition table could be missing or corrupted.",
-            disk.id
-        ))?;
-
-        let mut adopter = PartitionAdopter::new(&disk_info);
-
-        // Try to perform matching for all adopted partitions.
-        disk.spec
-            .adopted_partitions
-            .iter()
-            .try_for_each(|adopted_part| {
-                adopter
-                    .adopt(adopted_part)
-                    .context(format!("Failed to adopt partition '{}'", adopted_part.id))
-            })?;
-
         // Ensure that none of the unmatched partitions or their children are mounted.
-        adopter
-            .get_unmatched_partitions()
-            .try_for_each(|part| {
+        unadopted_partitions(disk)?
+            .iter()
+            .try_for_each(|node| {
                 debug!(
                     "Checking unmatched partition '{}' on disk '{}'",
-                    part.node.display(),
+                    node.display(),
                     disk.id
                 );
 
-                let Some(part_info) = lsblk::try_get(&part.node).with_context(|| {
+                let Some(part_info) = lsblk::try_get(node).with_context(|| {
                     format!(
                         "Failed to retrieve information for partition '{}' on disk '{}'.",
-                        part.node.display(),
+                        node.display(),
                         disk.id
                     )
                 })? else {
@@ -240,7 +223,7 @@ fn partitioning_safety_check(disks: &Vec<ResolvedDisk>) -> Result<(), Error> {
                 ensure!(
                     mnt_points.is_empty(),
                     "Partition '{}' on disk '{}' was not adopted, but it and its children have mount points: {}",
-                    part.node.display(),
+                    node.display(),
                     disk.id,
                     mnt_points.iter().map(|mnt| mnt.to_string_lossy()).collect::<Vec<_>>().join(", "),
                 );
@@ -254,6 +237,32 @@ fn partitioning_safety_check(disks: &Vec<ResolvedDisk>) -> Result<(), Error> {
     Ok(())
 }
 
+/// Returns the device paths of the partitions of a disk that re-partitioning deletes, i.e. all the
+/// partitions that are not adopted.
+pub(super) fn unadopted_partitions(disk: &ResolvedDisk) -> Result<Vec<PathBuf>, Error> {
+    let disk_info = SfDisk::get_info(&disk.bus_path).context(format!(
+        "Failed to retrieve information for disk '{}', the partition table could be missing or corrupted.",
+        disk.id
+    ))?;
+
+    let mut adopter = PartitionAdopter::new(&disk_info);
+
+    // Try to perform matching for all adopted partitions.
+    disk.spec
+        .adopted_partitions
+        .iter()
+        .try_for_each(|adopted_part| {
+            adopter
+                .adopt(adopted_part)
+                .context(format!("Failed to adopt partition '{}'", adopted_part.id))
+        })?;
+
+    Ok(adopter
+        .get_unmatched_partitions()
+        .map(|part| part.node.clone())
+        .collect())
+}
+
 /// Adopt partitions on a disk.
 ///
 /// This function will attempt to match the partitions on the disk with the
@@ -74,6 +74,17 @@ fn get_usr_verity_root_hash(ctx: &EngineContext) -> Result<String, Error> {
     Ok(verity.roothash.clone())
 }
 
+/// Gets the root hash of the verity device protecting the root or usr filesystem, if any.
+pub(crate) fn get_verity_root_hash(ctx: &EngineContext) -> Result<Option<String>, Error> {
+    if ctx.storage_graph.root_fs_is_verity() {
+        get_root_verity_root_hash(ctx).map(Some)
+    } else if ctx.storage_graph.usr_fs_is_verity() {
+        get_usr_verity_root_hash(ctx).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
 /// Setup verity devices.
 ///
 /// Assumes that images are already in place (data and hash), so that it can
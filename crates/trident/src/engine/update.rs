@@ -1,12 +1,15 @@
-use std::{path::PathBuf, time::Instant};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
 use log::{debug, info, warn};
 #[cfg(feature = "grpc-dangerous")]
 use tokio::sync::mpsc;
 
-use osutils::{chroot, container, path::join_relative};
+use osutils::{chroot, container, netplan::TRIDENT_NETPLAN_FILE, path::join_relative};
 use trident_api::{
-    config::{HostConfiguration, Operations},
+    config::{HostConfiguration, Operations, UpdateScope},
     constants::{
         internal_params::{ENABLE_UKI_SUPPORT, NO_TRANSITION},
         ESP_MOUNT_POINT_PATH, ROOT_MOUNT_POINT_PATH,
@@ -21,14 +24,20 @@ use trident_api::{
 use crate::{
     datastore::DataStore,
     engine::{
-        self, bootentries, rollback,
+        self, bootentries, config_drift, provenance, rollback,
         storage::{self, verity},
         EngineContext, NewrootMount, SUBSYSTEMS,
     },
-    monitor_metrics,
+    health, monitor_metrics,
     osimage::OsImage,
     subsystems::esp,
-    subsystems::hooks::HooksSubsystem,
+    subsystems::{
+        extensions::{self, ExtensionsSubsystem},
+        hooks::HooksSubsystem,
+        network::{self, NetplanBackup, NetworkSubsystem},
+        osconfig::OsConfigSubsystem,
+        storage::StorageSubsystem,
+    },
     ExitKind,
 };
 #[cfg(feature = "grpc-dangerous")]
@@ -42,6 +51,7 @@ pub(crate) fn update(
     state: &mut DataStore,
     allowed_operations: &Operations,
     image: OsImage,
+    config_commit: Option<String>,
     #[cfg(feature = "grpc-dangerous")] sender: &mut Option<GrpcSender>,
 ) -> Result<ExitKind, TridentError> {
     info!("Starting update");
@@ -69,6 +79,9 @@ pub(crate) fn update(
         image: Some(image),
         storage_graph: engine::build_storage_graph(&host_config.storage)?, // Build storage graph
         filesystems: Vec::new(), // Will be populated after dynamic validation
+        paths: Default::default(),
+        scoped_update: false,
+        resolved_extension_urls: Default::default(),
     };
 
     // Before starting an update servicing, need to validate that the active volume is set
@@ -93,6 +106,7 @@ pub(crate) fn update(
         .unwrap_or(ServicingType::NoActiveServicing);
     if servicing_type == ServicingType::NoActiveServicing {
         info!("No update servicing required");
+        engine::record_config_commit(state, config_commit)?;
         return Ok(ExitKind::Done);
     }
     debug!(
@@ -109,6 +123,9 @@ pub(crate) fn update(
 
     ctx.populate_filesystems()?;
 
+    // Refuse to start rather than running out of space halfway through staging.
+    engine::space_forecast::check(&subsystems, &ctx)?;
+
     let update_start_time = Instant::now();
     tracing::info!(
         metric_name = "update_start",
@@ -121,6 +138,7 @@ pub(crate) fn update(
         &mut subsystems,
         ctx,
         state,
+        config_commit,
         #[cfg(feature = "grpc-dangerous")]
         sender,
     )
@@ -153,6 +171,7 @@ pub(crate) fn update(
         ServicingType::NormalUpdate | ServicingType::HotPatch => {
             state.with_host_status(|host_status| {
                 host_status.servicing_state = ServicingState::Provisioned;
+                extensions::status::record(host_status);
             })?;
             #[cfg(feature = "grpc-dangerous")]
             grpc::send_host_status_state(sender, state)?;
@@ -174,10 +193,179 @@ pub(crate) fn update(
     }
 }
 
-/// Stages an update. Takes in 3-4 arguments:
+/// Applies only the selected configuration domains of the Host Configuration to the running OS, as a
+/// normal update. Only the subsystems responsible for the selected domains participate, and all
+/// other sections of the Host Configuration keep their current values.
+///
+/// Network changes are hot-applied. When `network_confirm_timeout` is set, they must be confirmed
+/// within the timeout, otherwise the previous network configuration is restored and the update
+/// fails without recording the new Host Configuration.
+#[tracing::instrument(skip_all)]
+pub(crate) fn scoped_update(
+    host_config: &HostConfiguration,
+    state: &mut DataStore,
+    scopes: &[UpdateScope],
+    image: OsImage,
+    network_confirm_timeout: Option<Duration>,
+    config_commit: Option<String>,
+) -> Result<ExitKind, TridentError> {
+    info!("Starting scoped update of {scopes:?}");
+
+    if state.host_status().servicing_state != ServicingState::Provisioned {
+        return Err(TridentError::new(InternalError::UnexpectedServicingState {
+            state: state.host_status().servicing_state,
+        }))
+        .message("Scoped updates can only be applied to a provisioned host");
+    }
+
+    let spec_old = state.host_status().spec.clone();
+    let mut ctx = EngineContext {
+        spec: scoped_host_config(&spec_old, host_config, scopes),
+        spec_old,
+        servicing_type: ServicingType::NormalUpdate,
+        partition_paths: state.host_status().partition_paths.clone(),
+        ab_active_volume: state.host_status().ab_active_volume,
+        disk_uuids: state.host_status().disk_uuids.clone(),
+        install_index: state.host_status().install_index,
+        is_uki: Some(image.is_uki() || host_config.internal_params.get_flag(ENABLE_UKI_SUPPORT)),
+        image: Some(image),
+        storage_graph: engine::build_storage_graph(&host_config.storage)?, // Build storage graph
+        filesystems: Vec::new(), // Will be populated after dynamic validation
+        paths: Default::default(),
+        scoped_update: true,
+        resolved_extension_urls: Default::default(),
+    };
+
+    let mut subsystems = scoped_subsystems(scopes);
+    engine::validate_host_config(&subsystems, &ctx)?;
+
+    // Storage changes can only be applied by a clean install, so they are only validated.
+    if ctx.spec.storage != ctx.spec_old.storage {
+        debug!("Storage configuration validated, reverting to the current storage configuration");
+        ctx.spec.storage = ctx.spec_old.storage.clone();
+        ctx.storage_graph = engine::build_storage_graph(&ctx.spec.storage)?;
+    }
+
+    if ctx.spec == ctx.spec_old {
+        info!("No changes to apply in {scopes:?}");
+        engine::record_config_commit(state, config_commit)?;
+        return Ok(ExitKind::Done);
+    }
+
+    ctx.populate_filesystems()?;
+    engine::space_forecast::check(&subsystems, &ctx)?;
+
+    // Save the current network configuration before the network subsystem overwrites it, so
+    // that the change can be reverted.
+    let netplan_backup = if ctx.spec.os.netplan != ctx.spec_old.os.netplan {
+        Some(
+            NetplanBackup::save(TRIDENT_NETPLAN_FILE)
+                .structured(ServicingError::ApplyNetplanConfig)?,
+        )
+    } else {
+        None
+    };
+
+    engine::prepare(&mut subsystems, &ctx)?;
+    engine::provision(&mut subsystems, &ctx, Path::new(ROOT_MOUNT_POINT_PATH))?;
+    engine::configure(&mut subsystems, &ctx)?;
+
+    if let Some(backup) = netplan_backup {
+        network::hot_apply(&backup, network_confirm_timeout)?;
+    }
+
+    engine::update_host_configuration(&subsystems, &mut ctx)?;
+
+    // Scoped updates do not deploy the OS image, so only the provenance of extensions changes.
+    let extension_artifacts = provenance::collect_extensions(&ctx, &state.host_status().artifacts);
+    state.with_host_status(|host_status| {
+        host_status.spec = ctx.spec;
+        if config_commit.is_some() {
+            host_status.config_commit = config_commit;
+        }
+        host_status
+            .artifacts
+            .retain(|artifact| !provenance::is_extension(artifact));
+        host_status.artifacts.extend(extension_artifacts);
+        extensions::status::record(host_status);
+    })?;
+
+    // Persist the Trident background log and metrics file to the updated target OS
+    engine::persist_background_log_and_metrics(
+        &state.host_status().spec.trident.datastore_path,
+        None,
+        state.host_status().servicing_state,
+    );
+
+    info!("Scoped update of {scopes:?} succeeded");
+    Ok(ExitKind::Done)
+}
+
+/// Returns the current Host Configuration with only the sections belonging to the given scopes
+/// taken from the new Host Configuration.
+fn scoped_host_config(
+    current: &HostConfiguration,
+    new: &HostConfiguration,
+    scopes: &[UpdateScope],
+) -> HostConfiguration {
+    let mut merged = current.clone();
+    for scope in scopes {
+        match scope {
+            UpdateScope::Sysexts => {
+                merged.os.sysexts = new.os.sysexts.clone();
+                merged.os.confexts = new.os.confexts.clone();
+                merged.os.sysext_policy.require_signature = new.os.sysext_policy.require_signature;
+            }
+            UpdateScope::Scripts => {
+                merged.scripts = new.scripts.clone();
+                merged.os.additional_files = new.os.additional_files.clone();
+            }
+            UpdateScope::Osconfig => {
+                merged.os.users = new.os.users.clone();
+                merged.os.hostname = new.os.hostname.clone();
+                merged.os.modules = new.os.modules.clone();
+                merged.os.services = new.os.services.clone();
+                merged.os.kernel_command_line = new.os.kernel_command_line.clone();
+                merged.os.selinux = new.os.selinux.clone();
+            }
+            UpdateScope::Storage => merged.storage = new.storage.clone(),
+            UpdateScope::Network => {
+                merged.os.netplan = new.os.netplan.clone();
+                merged.os.network = new.os.network.clone();
+                merged.os.pinned_interfaces = new.os.pinned_interfaces.clone();
+            }
+        }
+    }
+    merged
+}
+
+/// Returns fresh instances of the subsystems responsible for the given scopes, in the same order
+/// as they appear in SUBSYSTEMS.
+fn scoped_subsystems(scopes: &[UpdateScope]) -> Vec<Box<dyn Subsystem>> {
+    let mut subsystems: Vec<Box<dyn Subsystem>> = Vec::new();
+    if scopes.contains(&UpdateScope::Storage) {
+        subsystems.push(Box::<StorageSubsystem>::default());
+    }
+    if scopes.contains(&UpdateScope::Network) {
+        subsystems.push(Box::<NetworkSubsystem>::default());
+    }
+    if scopes.contains(&UpdateScope::Osconfig) {
+        subsystems.push(Box::<OsConfigSubsystem>::default());
+    }
+    if scopes.contains(&UpdateScope::Sysexts) {
+        subsystems.push(Box::<ExtensionsSubsystem>::default());
+    }
+    if scopes.contains(&UpdateScope::Scripts) {
+        subsystems.push(Box::<HooksSubsystem>::default());
+    }
+    subsystems
+}
+
+/// Stages an update. Takes in 4-5 arguments:
 /// - subsystems: A mutable reference to the list of subsystems.
 /// - ctx: EngineContext.
 /// - state: A mutable reference to the DataStore.
+/// - config_commit: Git commit the Host Configuration was loaded from, recorded once staged.
 /// - sender: Optional mutable reference to the gRPC sender.
 ///
 /// On success, returns an Option<NewrootMount>; This is not null only for A/B updates.
@@ -186,6 +374,7 @@ fn stage_update(
     subsystems: &mut [Box<dyn Subsystem>],
     mut ctx: EngineContext,
     state: &mut DataStore,
+    config_commit: Option<String>,
     #[cfg(feature = "grpc-dangerous")] sender: &mut Option<
         mpsc::UnboundedSender<Result<grpc::HostStatusState, tonic::Status>>,
     >,
@@ -216,6 +405,22 @@ fn stage_update(
         }
     };
 
+    // Local changes to /etc are captured before anything is staged, and kept from the last A/B
+    // update otherwise.
+    let config_drift = match ctx.servicing_type {
+        ServicingType::AbUpdate if ctx.spec.trident.capture_config_drift => {
+            match config_drift::capture(&ctx.paths.overlay_dir) {
+                Ok(changes) => Some(changes),
+                Err(e) => {
+                    warn!("Failed to capture local changes to /etc: {e:?}");
+                    None
+                }
+            }
+        }
+        ServicingType::AbUpdate => None,
+        _ => state.host_status().config_drift.clone(),
+    };
+
     engine::prepare(subsystems, &ctx)?;
 
     if let ServicingType::AbUpdate = ctx.servicing_type {
@@ -233,6 +438,7 @@ fn stage_update(
                 .structured(InternalError::Internal(
                     "No update volume despite there being an A/B update in progress",
                 ))?,
+            &ctx.paths,
         )?;
 
         engine::provision(subsystems, &ctx, newroot_mount.path())?;
@@ -256,10 +462,29 @@ fn stage_update(
 
     // Update the Host Configuration with information produced and stored in the
     // subsystems. Currently, this step is used only to update the final paths
-    // of sysexts and confexts configured in the extensions subsystem.
+    // of sysexts and confexts configured in the extensions subsystem, and to
+    // record the URLs they were downloaded from.
     engine::update_host_configuration(subsystems, &mut ctx)?;
     // Turn ctx into an immutable variable.
     let ctx = ctx;
+    let artifacts = provenance::collect(&ctx, &state.host_status().artifacts);
+
+    // The OS that is running is the one the A/B update rolls back to.
+    let previous_os_version = match ctx.servicing_type {
+        ServicingType::AbUpdate => health::os_version().unwrap_or_else(|e| {
+            warn!("Failed to get version of running OS: {e:?}");
+            None
+        }),
+        _ => state.host_status().previous_os_version.clone(),
+    };
+
+    // Only A/B updates deploy a new OS image, whose root hash the next boot is checked against.
+    let verity_root_hash = match ctx.servicing_type {
+        ServicingType::AbUpdate => {
+            verity::get_verity_root_hash(&ctx).structured(ServicingError::GetVerityRootHash)?
+        }
+        _ => state.host_status().verity_root_hash.clone(),
+    };
 
     // At this point, deployment has been staged, so update servicing state
     debug!(
@@ -277,6 +502,19 @@ fn stage_update(
             install_index: ctx.install_index,
             last_error: None,
             is_management_os: false,
+            config_commit: config_commit.or_else(|| hs.config_commit.take()),
+            artifacts,
+            reboot_boot_id: None,
+            identity: hs.identity.take(),
+            config_drift,
+            annotations: std::mem::take(&mut hs.annotations),
+            extensions: std::mem::take(&mut hs.extensions),
+            health_checks: Vec::new(),
+            health_check_history: std::mem::take(&mut hs.health_check_history),
+            previous_os_version,
+            failed_boots: 0,
+            kernel_command_line: hs.kernel_command_line.take(),
+            verity_root_hash,
         };
     })?;
     #[cfg(feature = "grpc-dangerous")]
@@ -326,6 +564,9 @@ pub(crate) fn finalize_update(
         storage_graph: engine::build_storage_graph(&state.host_status().spec.storage)?, // Build storage graph
         filesystems: Vec::new(), // Left empty since context does not have image
         is_uki: None,
+        paths: Default::default(),
+        scoped_update: false,
+        resolved_extension_urls: Default::default(),
     };
 
     let (root_path, esp_path) = if container::is_running_in_container()
@@ -345,6 +586,7 @@ pub(crate) fn finalize_update(
     // UEFI fallback, and a successful commit will finish it.
     esp::set_uefi_fallback_contents(&ctx, ServicingState::AbUpdateStaged, &root_path)
         .structured(ServicingError::SetUpUefiFallback)?;
+    esp::sync_esp_mirrors(&ctx, &esp_path).structured(ServicingError::SyncEspMirrors)?;
 
     debug!(
         "Updating host's servicing state to '{:?}'",
@@ -353,6 +595,14 @@ pub(crate) fn finalize_update(
     state.with_host_status(|status| status.servicing_state = ServicingState::AbUpdateFinalized)?;
     #[cfg(feature = "grpc-dangerous")]
     grpc::send_host_status_state(sender, state)?;
+    if !state
+        .host_status()
+        .spec
+        .internal_params
+        .get_flag(NO_TRANSITION)
+    {
+        engine::record_reboot(state)?;
+    }
     state.close();
 
     // Metric for update time in seconds
@@ -386,3 +636,64 @@ pub(crate) fn finalize_update(
         Ok(ExitKind::Done)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use trident_api::config::{Script, ScriptSource, Scripts, ServicingTypeSelection};
+
+    #[test]
+    fn test_scoped_host_config() {
+        let current = HostConfiguration::default();
+        let mut new = HostConfiguration::default();
+        new.os.hostname = Some("new-hostname".into());
+        new.scripts = Scripts {
+            post_configure: vec![Script {
+                name: "script".into(),
+                run_on: vec![ServicingTypeSelection::NormalUpdate],
+                source: ScriptSource::Content("echo hello".into()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        // No scopes keeps the current Host Configuration
+        assert_eq!(scoped_host_config(&current, &new, &[]), current);
+
+        // Only the selected sections are taken from the new Host Configuration
+        let merged = scoped_host_config(&current, &new, &[UpdateScope::Scripts]);
+        assert_eq!(merged.scripts, new.scripts);
+        assert_eq!(merged.os.hostname, None);
+
+        let merged = scoped_host_config(
+            &current,
+            &new,
+            &[UpdateScope::Scripts, UpdateScope::Osconfig],
+        );
+        assert_eq!(merged, new);
+        // The network scope only takes the network configuration
+        new.os.netplan = Some(Default::default());
+        new.os.network = Some(Default::default());
+        let merged = scoped_host_config(&current, &new, &[UpdateScope::Network]);
+        assert_eq!(merged.os.netplan, new.os.netplan);
+        assert_eq!(merged.os.network, new.os.network);
+        assert_eq!(merged.os.hostname, None);
+    }
+
+    #[test]
+    fn test_scoped_subsystems() {
+        assert!(scoped_subsystems(&[]).is_empty());
+
+        let names = scoped_subsystems(&[UpdateScope::Scripts, UpdateScope::Storage])
+            .iter()
+            .map(|subsystem| subsystem.name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["storage", "hooks"]);
+        let names = scoped_subsystems(&[UpdateScope::Osconfig, UpdateScope::Network])
+            .iter()
+            .map(|subsystem| subsystem.name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["network", "os-config"]);
+    }
+}
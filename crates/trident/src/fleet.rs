@@ -0,0 +1,447 @@
+use std::{
+    collections::HashSet,
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{bail, ensure, Context, Error};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+
+use trident_api::{
+    config::{HostConfiguration, Operations},
+    messages::{self, MessageKey},
+    status::{HostStatus, ServicingState},
+};
+use trident_client::{ClientError, TridentClient};
+
+use crate::table::format_table;
+
+/// Manifest listing the hosts of a small fleet, serviced together over the gRPC API of the
+/// Trident instance listening on each of them.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct FleetManifest {
+    /// Path to the Host Configuration applied to the hosts that do not have their own, relative
+    /// to the manifest.
+    #[serde(default)]
+    pub host_configuration: Option<PathBuf>,
+
+    /// Hosts of the fleet.
+    pub hosts: Vec<FleetHost>,
+}
+
+/// Host of a fleet.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct FleetHost {
+    /// Name of the host, used in progress messages and in the report.
+    pub name: String,
+
+    /// gRPC endpoint of Trident on the host, e.g. `http://10.0.0.2:50051`.
+    pub endpoint: String,
+
+    /// Path to the Host Configuration applied to this host, relative to the manifest.
+    #[serde(default)]
+    pub host_configuration: Option<PathBuf>,
+}
+
+impl FleetManifest {
+    /// Loads the manifest at `path`, resolving the paths of Host Configurations relative to it.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read '{}'", path.display()))?;
+        let mut manifest: Self = serde_yaml::from_str(&contents)
+            .with_context(|| format!("Failed to parse '{}'", path.display()))?;
+        manifest.validate()?;
+
+        let directory = path.parent().unwrap_or(Path::new(""));
+        let resolve = |config: &mut Option<PathBuf>| {
+            if let Some(config) = config {
+                *config = directory.join(&*config);
+            }
+        };
+        resolve(&mut manifest.host_configuration);
+        manifest
+            .hosts
+            .iter_mut()
+            .for_each(|host| resolve(&mut host.host_configuration));
+        Ok(manifest)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        ensure!(
+            !self.hosts.is_empty(),
+            "The manifest does not list any host"
+        );
+        let mut names = HashSet::new();
+        for host in &self.hosts {
+            ensure!(
+                names.insert(&host.name),
+                "Host '{}' is listed more than once",
+                host.name
+            );
+        }
+        Ok(())
+    }
+
+    /// Loads the Host Configurations applied to the hosts, in order.
+    pub fn host_configurations(&self) -> Result<Vec<HostConfiguration>, Error> {
+        self.hosts
+            .iter()
+            .map(|host| {
+                let path = self.host_configuration(host)?;
+                let contents = fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read '{}'", path.display()))?;
+                serde_yaml::from_str(&contents)
+                    .with_context(|| format!("Failed to parse '{}'", path.display()))
+            })
+            .collect()
+    }
+
+    /// Returns the path of the Host Configuration applied to `host`.
+    fn host_configuration<'a>(&'a self, host: &'a FleetHost) -> Result<&'a Path, Error> {
+        match host
+            .host_configuration
+            .as_ref()
+            .or(self.host_configuration.as_ref())
+        {
+            Some(path) => Ok(path),
+            None => bail!(
+                "No Host Configuration is set for host '{}', nor for the whole fleet",
+                host.name
+            ),
+        }
+    }
+}
+
+/// Consolidated report of an operation on the hosts of a fleet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetReport {
+    /// Outcome on each host, in the order of the manifest.
+    pub hosts: Vec<HostOutcome>,
+}
+
+/// Outcome of an operation on a host of a fleet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostOutcome {
+    /// Name of the host.
+    pub name: String,
+
+    /// Result of the operation on the host.
+    pub result: HostResult,
+
+    /// Last servicing state reported by the host.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub servicing_state: Option<ServicingState>,
+
+    /// Why the operation failed on the host.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of an operation on a host of a fleet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HostResult {
+    /// The operation succeeded and the host is provisioned.
+    Ok,
+
+    /// The host finalized servicing and is rebooting into the new OS, which it has not committed
+    /// yet.
+    Pending,
+
+    /// The operation failed on the host.
+    Failed,
+}
+
+impl FleetReport {
+    /// Returns the hosts on which the operation failed.
+    pub fn failed(&self) -> impl Iterator<Item = &HostOutcome> {
+        self.hosts
+            .iter()
+            .filter(|host| host.result == HostResult::Failed)
+    }
+
+    /// Renders the report as a table.
+    pub fn to_table(&self) -> String {
+        format_table(
+            &[
+                MessageKey::TableHost,
+                MessageKey::TableResult,
+                MessageKey::TableState,
+                MessageKey::TableError,
+            ],
+            self.hosts
+                .iter()
+                .map(|host| {
+                    vec![
+                        host.name.clone(),
+                        messages::render(
+                            match host.result {
+                                HostResult::Ok => MessageKey::FleetResultOk,
+                                HostResult::Pending => MessageKey::FleetResultPending,
+                                HostResult::Failed => MessageKey::FleetResultFailed,
+                            },
+                            &[],
+                        ),
+                        host.servicing_state.map_or_else(|| "-".into(), state_name),
+                        host.error.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Returns whether a host in servicing state `state` finalized servicing and is rebooting into the
+/// new OS, which it only commits after the reboot.
+fn is_finalized(state: ServicingState) -> bool {
+    matches!(
+        state,
+        ServicingState::CleanInstallFinalized | ServicingState::AbUpdateFinalized
+    )
+}
+
+/// Returns the name of a servicing state, as it appears in the Host Status.
+fn state_name(state: ServicingState) -> String {
+    serde_json::to_value(state)
+        .ok()
+        .and_then(|value| value.as_str().map(String::from))
+        .unwrap_or_else(|| format!("{state:?}"))
+}
+
+/// Applies `host_configs`, the Host Configurations of the hosts of the manifest as returned by
+/// [`FleetManifest::host_configurations`], with at most `concurrency` hosts serviced at once, and
+/// reports the progress of each host as it is serviced.
+pub fn apply(
+    manifest: &FleetManifest,
+    host_configs: Vec<HostConfiguration>,
+    allowed_operations: &Operations,
+    concurrency: usize,
+) -> Result<FleetReport, Error> {
+    let jobs = manifest
+        .hosts
+        .iter()
+        .cloned()
+        .zip(host_configs)
+        .collect::<Vec<_>>();
+    let allowed_operations = allowed_operations.clone();
+    run(concurrency, jobs, move |(host, host_config)| {
+        let allowed_operations = allowed_operations.clone();
+        async move {
+            let mut last_status = None;
+            let result = async {
+                let mut client = TridentClient::connect(host.endpoint.clone()).await?;
+                let mut statuses = client
+                    .update_host(&host_config, &allowed_operations)
+                    .await?;
+                while let Some(status) = statuses.next().await {
+                    let status = status?;
+                    info!(
+                        "[{}] Servicing state: {}",
+                        host.name,
+                        state_name(status.servicing_state)
+                    );
+                    last_status = Some(status);
+                }
+                Ok::<_, ClientError>(())
+            }
+            .await;
+
+            // Trident reboots the host once servicing is finalized, which closes the connection.
+            // The host is then reported as pending, until it commits the new OS.
+            let finalized = last_status
+                .as_ref()
+                .is_some_and(|status| is_finalized(status.servicing_state));
+            match result {
+                Err(e) if !finalized => outcome(&host, last_status.as_ref(), Some(e)),
+                _ => outcome(&host, last_status.as_ref(), None),
+            }
+        }
+    })
+}
+
+/// Retrieves the Host Status of the hosts of the manifest, with at most `concurrency` hosts
+/// queried at once.
+pub fn status(manifest: &FleetManifest, concurrency: usize) -> Result<FleetReport, Error> {
+    run(concurrency, manifest.hosts.clone(), |host| async move {
+        let result = async {
+            TridentClient::connect(host.endpoint.clone())
+                .await?
+                .get_host_status()
+                .await
+        }
+        .await;
+        match result {
+            Ok(status) => outcome(&host, Some(&status), None),
+            Err(e) => outcome(&host, None, Some(e)),
+        }
+    })
+}
+
+/// Runs `job` on each item of `jobs`, with at most `concurrency` jobs running at once, and
+/// collects the outcomes in order.
+fn run<T, F, Fut>(concurrency: usize, jobs: Vec<T>, job: F) -> Result<FleetReport, Error>
+where
+    F: Fn(T) -> Fut,
+    Fut: Future<Output = HostOutcome> + Send + 'static,
+{
+    let runtime = tokio::runtime::Runtime::new().context("Failed to start Tokio runtime")?;
+    runtime.block_on(async {
+        let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+        let handles = jobs
+            .into_iter()
+            .map(|item| {
+                let semaphore = semaphore.clone();
+                let future = job(item);
+                tokio::spawn(async move {
+                    let _permit = semaphore.acquire_owned().await;
+                    future.await
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut hosts = Vec::new();
+        for handle in handles {
+            hosts.push(handle.await.context("Fleet task failed")?);
+        }
+        Ok(FleetReport { hosts })
+    })
+}
+
+fn outcome(
+    host: &FleetHost,
+    status: Option<&HostStatus>,
+    error: Option<ClientError>,
+) -> HostOutcome {
+    // Trident records the error of a failed servicing in the Host Status.
+    let error = match (error, status.and_then(|status| status.last_error.as_ref())) {
+        (Some(e), _) => Some(format!("{:#}", Error::from(e))),
+        (None, Some(last_error)) => Some(
+            serde_yaml::to_string(last_error)
+                .unwrap_or_default()
+                .trim()
+                .replace('\n', " "),
+        ),
+        (None, None) => None,
+    };
+    let result = if let Some(error) = &error {
+        error!("[{}] Failed: {error}", host.name);
+        HostResult::Failed
+    } else if status.is_some_and(|status| is_finalized(status.servicing_state)) {
+        info!("[{}] Pending commit of the new OS", host.name);
+        HostResult::Pending
+    } else {
+        info!("[{}] Succeeded", host.name);
+        HostResult::Ok
+    };
+    HostOutcome {
+        name: host.name.clone(),
+        result,
+        servicing_state: status.map(|status| status.servicing_state),
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_manifest() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("hosts.yaml");
+        fs::write(
+            &path,
+            indoc::indoc! {"
+                hostConfiguration: fleet.yaml
+                hosts:
+                  - name: node-1
+                    endpoint: http://10.0.0.1:50051
+                  - name: node-2
+                    endpoint: http://10.0.0.2:50051
+                    hostConfiguration: /etc/node-2.yaml
+            "},
+        )
+        .unwrap();
+        let manifest = FleetManifest::load(&path).unwrap();
+        assert_eq!(
+            manifest.host_configuration(&manifest.hosts[0]).unwrap(),
+            dir.path().join("fleet.yaml")
+        );
+        assert_eq!(
+            manifest.host_configuration(&manifest.hosts[1]).unwrap(),
+            Path::new("/etc/node-2.yaml")
+        );
+
+        // Host names must be unique.
+        fs::write(
+            &path,
+            indoc::indoc! {"
+                hosts:
+                  - name: node-1
+                    endpoint: http://10.0.0.1:50051
+                  - name: node-1
+                    endpoint: http://10.0.0.2:50051
+            "},
+        )
+        .unwrap();
+        FleetManifest::load(&path).unwrap_err();
+
+        // Hosts without a Host Configuration cannot be serviced.
+        let host = FleetHost {
+            name: "node-1".into(),
+            endpoint: "http://10.0.0.1:50051".into(),
+            host_configuration: None,
+        };
+        let manifest = FleetManifest {
+            host_configuration: None,
+            hosts: vec![host.clone()],
+        };
+        manifest.host_configuration(&host).unwrap_err();
+    }
+
+    #[test]
+    fn test_to_table() {
+        let report = FleetReport {
+            hosts: vec![
+                HostOutcome {
+                    name: "node-1".into(),
+                    result: HostResult::Pending,
+                    servicing_state: Some(ServicingState::AbUpdateFinalized),
+                    error: None,
+                },
+                HostOutcome {
+                    name: "node-2".into(),
+                    result: HostResult::Ok,
+                    servicing_state: Some(ServicingState::Provisioned),
+                    error: None,
+                },
+                HostOutcome {
+                    name: "node-10".into(),
+                    result: HostResult::Failed,
+                    servicing_state: None,
+                    error: Some("Failed to connect to Trident".into()),
+                },
+            ],
+        };
+        assert_eq!(report.failed().count(), 1);
+        assert_eq!(
+            report.to_table(),
+            indoc::indoc! {"
+                HOST     RESULT   STATE                ERROR
+                node-1   pending  ab-update-finalized
+                node-2   ok       provisioned
+                node-10  failed   -                    Failed to connect to Trident
+            "}
+        );
+    }
+}
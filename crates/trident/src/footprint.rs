@@ -0,0 +1,220 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Error};
+use log::{debug, trace};
+use nix::sys::statvfs;
+use serde::Serialize;
+
+use osutils::dependencies;
+
+/// Interval at which the disk space used by the servicing OS is sampled.
+const SAMPLING_INTERVAL_MS: u64 = 500;
+
+/// Directories of the servicing OS that Trident writes to, whose filesystems are sampled.
+const SAMPLED_DIRECTORIES: [&str; 4] = ["/", "/run", "/tmp", "/var/tmp"];
+
+/// List of the kernel modules loaded on the running system.
+const PROC_MODULES_PATH: &str = "/proc/modules";
+
+/// What a servicing run used from the servicing OS, to help shrink installer images to what
+/// Trident needs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FootprintReport {
+    /// External binaries executed during the run.
+    pub binaries: Vec<InvokedBinary>,
+
+    /// Kernel modules loaded during the run.
+    pub kernel_modules: Vec<String>,
+
+    /// Disk space used on the filesystems of the servicing OS that Trident writes to.
+    pub disk_usage: Vec<DiskUsage>,
+}
+
+/// External binary executed during a servicing run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvokedBinary {
+    /// Name of the binary.
+    pub name: String,
+
+    /// Path the binary was executed from.
+    pub path: PathBuf,
+}
+
+/// Disk space used on a filesystem of the servicing OS during a servicing run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskUsage {
+    /// Directory whose filesystem is sampled.
+    pub path: PathBuf,
+
+    /// Bytes used when the run started.
+    pub initial_bytes: u64,
+
+    /// Highest number of bytes used during the run.
+    pub peak_bytes: u64,
+}
+
+/// Measures what a servicing run uses from the servicing OS, until it is finished.
+pub struct FootprintMonitor {
+    initial_modules: BTreeSet<String>,
+    stop: Arc<AtomicBool>,
+    join_handle: Option<thread::JoinHandle<Vec<DiskUsage>>>,
+}
+
+impl FootprintMonitor {
+    /// Starts measuring, sampling disk usage in a background thread.
+    pub fn start() -> Result<Self, Error> {
+        let initial_modules = read_modules()?;
+        let mut usage = sampled_filesystems()
+            .into_iter()
+            .map(|(path, used)| DiskUsage {
+                path,
+                initial_bytes: used,
+                peak_bytes: used,
+            })
+            .collect::<Vec<_>>();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let local_stop = stop.clone();
+        let join_handle = thread::spawn(move || {
+            while !local_stop.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(SAMPLING_INTERVAL_MS));
+                for entry in &mut usage {
+                    if let Ok(used) = used_bytes(&entry.path) {
+                        entry.peak_bytes = entry.peak_bytes.max(used);
+                    }
+                }
+            }
+            usage
+        });
+
+        Ok(Self {
+            initial_modules,
+            stop,
+            join_handle: Some(join_handle),
+        })
+    }
+
+    /// Stops measuring and returns the report.
+    pub fn finish(mut self) -> Result<FootprintReport, Error> {
+        self.stop.store(true, Ordering::SeqCst);
+        let disk_usage = match self.join_handle.take() {
+            Some(join_handle) => join_handle
+                .join()
+                .map_err(|e| anyhow::anyhow!("Disk usage sampling thread panicked: {e:?}"))?,
+            None => Vec::new(),
+        };
+
+        Ok(FootprintReport {
+            binaries: dependencies::invoked()
+                .into_iter()
+                .map(|(name, path)| InvokedBinary {
+                    name: name.into(),
+                    path,
+                })
+                .collect(),
+            kernel_modules: read_modules()?
+                .difference(&self.initial_modules)
+                .cloned()
+                .collect(),
+            disk_usage,
+        })
+    }
+}
+
+impl Drop for FootprintMonitor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Returns the names of the kernel modules currently loaded.
+fn read_modules() -> Result<BTreeSet<String>, Error> {
+    fs::read_to_string(PROC_MODULES_PATH)
+        .with_context(|| format!("Failed to read '{PROC_MODULES_PATH}'"))
+        .map(|contents| parse_modules(&contents))
+}
+
+/// Parses the names of the kernel modules out of the contents of /proc/modules.
+fn parse_modules(contents: &str) -> BTreeSet<String> {
+    contents
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(String::from)
+        .collect()
+}
+
+/// Returns the sampled directories that exist, one per filesystem, with the bytes used on their
+/// filesystem.
+fn sampled_filesystems() -> Vec<(PathBuf, u64)> {
+    let mut filesystems = BTreeSet::new();
+    SAMPLED_DIRECTORIES
+        .iter()
+        .filter_map(|directory| {
+            let stat = statvfs::statvfs(*directory)
+                .inspect_err(|e| trace!("Not sampling disk usage of '{directory}': {e}"))
+                .ok()?;
+            if !filesystems.insert(stat.filesystem_id()) {
+                debug!("Not sampling disk usage of '{directory}', its filesystem is sampled");
+                return None;
+            }
+            Some((PathBuf::from(directory), used(&stat)))
+        })
+        .collect()
+}
+
+/// Returns the bytes used on the filesystem holding `path`.
+fn used_bytes(path: &Path) -> Result<u64, Error> {
+    statvfs::statvfs(path)
+        .map(|stat| used(&stat))
+        .with_context(|| format!("Failed to get disk usage of '{}'", path.display()))
+}
+
+fn used(stat: &statvfs::Statvfs) -> u64 {
+    (stat.blocks() - stat.blocks_free()) * stat.fragment_size()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_modules() {
+        let modules = parse_modules(indoc::indoc! {"
+            dm_verity 45056 0 - Live 0x0000000000000000
+            raid1 57344 1 - Live 0x0000000000000000
+            md_mod 184320 2 raid1, Live 0x0000000000000000
+        "});
+        assert_eq!(
+            modules.into_iter().collect::<Vec<_>>(),
+            vec!["dm_verity", "md_mod", "raid1"]
+        );
+        assert!(parse_modules("").is_empty());
+    }
+
+    #[test]
+    fn test_footprint_monitor() {
+        let monitor = FootprintMonitor::start().unwrap();
+        let report = monitor.finish().unwrap();
+        assert!(report
+            .disk_usage
+            .iter()
+            .any(|usage| usage.path == Path::new("/")));
+        assert!(report
+            .disk_usage
+            .iter()
+            .all(|usage| usage.peak_bytes >= usage.initial_bytes));
+    }
+}
@@ -0,0 +1,152 @@
+use std::path::Path;
+
+use anyhow::Error;
+use log::{debug, info};
+
+use osutils::dependencies::Dependency;
+use trident_api::{
+    config::HostConfiguration,
+    error::{InitializationError, InvalidInputError, ReportError, TridentError},
+};
+
+use crate::validation;
+
+/// Host Configuration loaded from a Git repository, along with the commit it was taken from.
+pub(crate) struct GitHostConfiguration {
+    pub host_config: HostConfiguration,
+    pub commit: String,
+}
+
+/// Fetches the given branch or tag of a Git repository and loads the Host Configuration stored at
+/// `path` in it.
+///
+/// Only the tip of the reference is fetched, into a temporary repository that is discarded
+/// afterwards.
+pub(crate) fn load_host_config(
+    url: &str,
+    reference: &str,
+    path: &Path,
+) -> Result<GitHostConfiguration, TridentError> {
+    info!(
+        "Loading Host Configuration '{}' from Git repository '{url}' at '{reference}'",
+        path.display()
+    );
+
+    let error = || InvalidInputError::LoadHostConfigurationGit {
+        url: url.to_string(),
+        reference: reference.to_string(),
+        path: path.display().to_string(),
+    };
+
+    let repo = tempfile::tempdir().structured(error())?;
+    Dependency::Git
+        .cmd()
+        .arg("init")
+        .arg("--quiet")
+        .arg(repo.path())
+        .run_and_check()
+        .structured(error())?;
+    Dependency::Git
+        .cmd()
+        .arg("-C")
+        .arg(repo.path())
+        .arg("fetch")
+        .arg("--quiet")
+        .arg("--depth=1")
+        // Keep URLs and references starting with '-' from being parsed as options
+        .arg("--")
+        .arg(url)
+        .arg(reference)
+        .run_and_check()
+        .structured(error())?;
+
+    let commit = Dependency::Git
+        .cmd()
+        .arg("-C")
+        .arg(repo.path())
+        .arg("rev-parse")
+        .arg("FETCH_HEAD^{commit}")
+        .output_and_check()
+        .structured(error())?
+        .trim()
+        .to_string();
+    debug!("Reference '{reference}' of '{url}' resolved to commit '{commit}'");
+
+    let contents = Dependency::Git
+        .cmd()
+        .arg("-C")
+        .arg(repo.path())
+        .arg("show")
+        .arg(format!("{commit}:{}", path.display()))
+        .output_and_check()
+        .structured(error())?;
+
+    Ok(GitHostConfiguration {
+        host_config: validation::parse_host_config_with(&contents, path, |include| {
+            Dependency::Git
+                .cmd()
+                .arg("-C")
+                .arg(repo.path())
+                .arg("show")
+                .arg(format!("{commit}:{}", include.display()))
+                .output_and_check()
+                .map_err(Error::from)
+        })?,
+        commit,
+    })
+}
+
+/// Returns the SHA of the commit that the given branch or tag currently points to in the remote
+/// Git repository, without fetching it.
+pub(crate) fn resolve_remote_commit(url: &str, reference: &str) -> Result<String, TridentError> {
+    let error = || InitializationError::QueryGitReference {
+        url: url.to_string(),
+        reference: reference.to_string(),
+    };
+
+    let output = Dependency::Git
+        .cmd()
+        .arg("ls-remote")
+        .arg("--")
+        .arg(url)
+        .arg(reference)
+        .arg(format!("{reference}^{{}}"))
+        .output_and_check()
+        .structured(error())?;
+
+    parse_ls_remote(&output).structured(error())
+}
+
+/// Picks the commit SHA out of the output of `git ls-remote`. Annotated tags are listed twice, once
+/// for the tag object and once peeled (with a `^{}` suffix) for the commit it points to, in which
+/// case the peeled entry wins.
+fn parse_ls_remote(output: &str) -> Option<String> {
+    let entries = output
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .collect::<Vec<_>>();
+
+    entries
+        .iter()
+        .find(|(_, name)| name.ends_with("^{}"))
+        .or(entries.first())
+        .map(|(sha, _)| sha.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ls_remote() {
+        assert_eq!(parse_ls_remote(""), None);
+        assert_eq!(
+            parse_ls_remote("0123abcd\trefs/heads/main\n"),
+            Some("0123abcd".into())
+        );
+        assert_eq!(
+            parse_ls_remote("1111aaaa\trefs/tags/v1\n2222bbbb\trefs/tags/v1^{}\n"),
+            Some("2222bbbb".into())
+        );
+    }
+}
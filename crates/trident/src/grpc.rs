@@ -1,4 +1,7 @@
-use std::net::SocketAddr;
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Error};
 use log::info;
@@ -12,11 +15,11 @@ use tonic::{transport::Server, Request, Response, Status};
 use osutils::dependencies::Dependency;
 use trident_api::{
     config::HostConfiguration,
-    config::{GrpcConfiguration, Operations},
+    config::{GrpcConfiguration, Operation, Operations},
     error::{InternalError, ReportError, ServicingError, TridentError},
 };
 
-use crate::{datastore::DataStore, OrchestratorConnection};
+use crate::{datastore::DataStore, subsystems::network, OrchestratorConnection};
 
 pub mod protobufs {
     tonic::include_proto!("trident");
@@ -28,8 +31,10 @@ pub type GrpcSender = mpsc::UnboundedSender<Result<HostStatusState, tonic::Statu
 /// Implementation of the gRPC service.
 ///
 /// This struct contains a tokio Sender which it uses to enqueue commands to the main Trident
-/// thread. It also implements the gRPC service trait, which allows it to be used as a gRPC server.
-pub struct HostManagementImpl(Sender<(HostConfiguration, Operations, GrpcSender)>);
+/// thread, and the path of the datastore, from which the Host Status is read on every request so
+/// that it reflects any servicing done since Trident started listening. It also implements the
+/// gRPC service trait, which allows it to be used as a gRPC server.
+pub struct HostManagementImpl(Sender<(HostConfiguration, Operations, GrpcSender)>, PathBuf);
 
 #[tonic::async_trait]
 impl host_management_server::HostManagement for HostManagementImpl {
@@ -46,21 +51,48 @@ impl host_management_server::HostManagement for HostManagementImpl {
             .context("Failed to parse host config")
             .map_err(|e| Status::invalid_argument(format!("{e:?}")))?;
 
+        let allowed_operations = parse_allowed_operations(&request.allowed_operations)
+            .map_err(|e| Status::invalid_argument(format!("{e:?}")))?;
+
         let (tx, rx) = mpsc::unbounded_channel();
         self.0
-            .send((host_config, Operations::all(), tx))
+            .send((host_config, allowed_operations, tx))
             .await
             .context("Failed to enqueue 'HostUpdate' command to the main Trident thread")
             .map_err(|e| Status::from_error(e.into()))?;
 
         Ok(Response::new(UnboundedReceiverStream::new(rx)))
     }
+
+    async fn confirm_network(
+        &self,
+        _request: Request<ConfirmNetworkRequest>,
+    ) -> Result<Response<ConfirmNetworkResponse>, Status> {
+        info!("Received confirm_network request");
+        network::confirm_change().map_err(|e| Status::internal(format!("{e:?}")))?;
+        Ok(Response::new(ConfirmNetworkResponse {}))
+    }
+
+    async fn get_host_status(
+        &self,
+        _request: Request<GetHostStatusRequest>,
+    ) -> Result<Response<HostStatusState>, Status> {
+        info!("Received get_host_status request");
+        let status = DataStore::open_read_only(&self.1)
+            .and_then(|datastore| {
+                serde_yaml::to_string(datastore.host_status())
+                    .structured(InternalError::SerializeHostStatus)
+            })
+            .map_err(|e| Status::internal(format!("{e:?}")))?;
+        Ok(Response::new(HostStatusState { status }))
+    }
 }
 
 /// Start the gRPC server.
 pub(crate) fn start(
     grpc: &GrpcConfiguration,
     orchestrator: Option<&OrchestratorConnection>,
+    datastore_path: &Path,
     sender: Sender<(HostConfiguration, Operations, GrpcSender)>,
 ) -> Result<Runtime, TridentError> {
     // TODO: make firewall this configurable
@@ -76,7 +108,7 @@ pub(crate) fn start(
     rt.spawn(async move {
         Server::builder()
             .add_service(host_management_server::HostManagementServer::new(
-                HostManagementImpl(sender),
+                HostManagementImpl(sender, datastore_path.to_path_buf()),
             ))
             .serve(SocketAddr::new(addr, port))
             .await
@@ -106,6 +138,23 @@ pub(crate) fn send_host_status_state(
     Ok(())
 }
 
+/// Parses the comma-separated list of allowed operations of a request, as accepted by the
+/// `--allowed-operations` command line option. All operations are allowed when the list is empty.
+fn parse_allowed_operations(allowed_operations: &str) -> Result<Operations, Error> {
+    if allowed_operations.trim().is_empty() {
+        return Ok(Operations::all());
+    }
+
+    let mut operations = Operations::empty();
+    for operation in allowed_operations.split(',') {
+        operations.0.insert(
+            serde_yaml::from_str::<Operation>(operation.trim())
+                .with_context(|| format!("Unknown operation '{}'", operation.trim()))?,
+        );
+    }
+    Ok(operations)
+}
+
 fn open_firewall_for_grpc() -> Result<(), Error> {
     Dependency::Iptables
         .cmd()
@@ -120,3 +169,24 @@ fn open_firewall_for_grpc() -> Result<(), Error> {
         .run_and_check()
         .context("Failed to open firewall for gRPC")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_allowed_operations() {
+        assert!(parse_allowed_operations("").unwrap().has_finalize());
+        assert!(parse_allowed_operations("").unwrap().has_stage());
+
+        let operations = parse_allowed_operations("stage").unwrap();
+        assert!(operations.has_stage());
+        assert!(!operations.has_finalize());
+
+        let operations = parse_allowed_operations("stage, finalize").unwrap();
+        assert!(operations.has_stage());
+        assert!(operations.has_finalize());
+
+        parse_allowed_operations("stage,reboot").unwrap_err();
+    }
+}
@@ -0,0 +1,113 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Error};
+use log::{debug, info};
+
+use osutils::dependencies::Dependency;
+use trident_api::config::BootComplete;
+
+/// Target reached once the boot assessment of systemd succeeded.
+const BOOT_COMPLETE_TARGET: &str = "boot-complete.target";
+
+/// Service of systemd that fails when any unit has failed during the boot.
+const CHECK_NO_FAILURES_SERVICE: &str = "systemd-boot-check-no-failures.service";
+
+/// Interval between two queries of the state of the units.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Waits for `boot-complete.target`, and `systemd-boot-check-no-failures.service` if configured,
+/// to be active. Fails as soon as one of them fails, or when they are not active within the
+/// timeout.
+pub(super) fn check(boot_complete: &BootComplete) -> Result<(), Error> {
+    let mut units = vec![BOOT_COMPLETE_TARGET];
+    if boot_complete.check_no_failures {
+        units.push(CHECK_NO_FAILURES_SERVICE);
+    }
+
+    // Nothing may pull in boot-complete.target, so its start is queued along with the units
+    // ordered before it. The start is not awaited, so that the timeout applies.
+    debug!("Starting {}", units.join(", "));
+    Dependency::Systemctl
+        .cmd()
+        .env("SYSTEMD_IGNORE_CHROOT", "true")
+        .args(["start", "--no-block"])
+        .args(&units)
+        .run_and_check()
+        .with_context(|| format!("Failed to start {}", units.join(", ")))?;
+
+    let start_time = Instant::now();
+    let timeout = Duration::from_secs(boot_complete.timeout_seconds);
+    loop {
+        // 'systemctl is-active' fails when any unit is not active, while still printing the
+        // state of every unit.
+        let states = Dependency::Systemctl
+            .cmd()
+            .env("SYSTEMD_IGNORE_CHROOT", "true")
+            .arg("is-active")
+            .args(&units)
+            .output()
+            .context("Failed to query state of boot assessment units")?
+            .output();
+        if assess(&units, &states)? {
+            info!("Boot is complete");
+            return Ok(());
+        }
+        if start_time.elapsed() >= timeout {
+            bail!(
+                "Boot was not complete after {} seconds, states: {}",
+                boot_complete.timeout_seconds,
+                describe(&units, &states)
+            );
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Returns whether all `units` are active according to `states`, the output of
+/// `systemctl is-active` for them. Fails if any of them failed.
+fn assess(units: &[&str], states: &str) -> Result<bool, Error> {
+    let states = states.lines().map(str::trim).collect::<Vec<_>>();
+    if states.len() != units.len() {
+        bail!("Unexpected output of 'systemctl is-active': {states:?}");
+    }
+    if states.contains(&"failed") {
+        bail!(
+            "Boot assessment failed, states: {}",
+            describe(units, &states.join("\n"))
+        );
+    }
+    Ok(states.iter().all(|state| *state == "active"))
+}
+
+/// Returns the state of each of `units` in `states`, the output of `systemctl is-active`.
+fn describe(units: &[&str], states: &str) -> String {
+    units
+        .iter()
+        .zip(states.lines())
+        .map(|(unit, state)| format!("{unit} is {}", state.trim()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assess() {
+        let units = [BOOT_COMPLETE_TARGET, CHECK_NO_FAILURES_SERVICE];
+        assert!(assess(&units, "active\nactive\n").unwrap());
+        assert!(!assess(&units, "inactive\nactivating\n").unwrap());
+        assert_eq!(
+            assess(&units, "inactive\nfailed\n")
+                .unwrap_err()
+                .to_string(),
+            "Boot assessment failed, states: boot-complete.target is inactive, \
+            systemd-boot-check-no-failures.service is failed"
+        );
+        assess(&units, "active\n").unwrap_err();
+    }
+}
@@ -0,0 +1,130 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Error};
+use log::{debug, info};
+
+use osutils::dependencies::{Command, Dependency};
+use trident_api::config::{ContainerCheckWorkloads, ContainerRuntime};
+
+/// Interval between two queries of the state of the workloads.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Waits for the workloads of `workloads` to be ready. Fails with the state of the workloads that
+/// are not ready when the timeout is reached.
+pub(super) fn check(workloads: &ContainerCheckWorkloads) -> Result<(), Error> {
+    debug!(
+        "Waiting for {} '{}' to be ready",
+        match workloads.runtime {
+            ContainerRuntime::Podman => "container(s)",
+            ContainerRuntime::Cri => "pod(s)",
+        },
+        workloads.names.join("', '")
+    );
+    let start_time = Instant::now();
+    let timeout = Duration::from_secs(workloads.timeout_seconds);
+    loop {
+        let mut not_ready = Vec::new();
+        for name in &workloads.names {
+            if let Some(state) = query(workloads, name)? {
+                not_ready.push(state);
+            }
+        }
+        if not_ready.is_empty() {
+            info!("Workload(s) '{}' are ready", workloads.names.join("', '"));
+            return Ok(());
+        }
+        if start_time.elapsed() >= timeout {
+            bail!(
+                "Workload(s) not ready after {} seconds: {}",
+                workloads.timeout_seconds,
+                not_ready.join(", ")
+            );
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Returns the state of the workload `name` if it is not ready, or `None` if it is.
+fn query(workloads: &ContainerCheckWorkloads, name: &str) -> Result<Option<String>, Error> {
+    match workloads.runtime {
+        ContainerRuntime::Podman => {
+            let mut cmd = runtime_cmd(Dependency::Podman, "--url", workloads);
+            let output = cmd
+                .args(["container", "inspect", "--format"])
+                .arg("{{.State.Status}} {{.State.Health.Status}}")
+                .arg(name)
+                .output()
+                .with_context(|| format!("Failed to inspect container '{name}'"))?;
+            if !output.success() {
+                return Ok(Some(format!("container '{name}' was not found")));
+            }
+            Ok(assess_podman_state(name, &output.output()))
+        }
+        ContainerRuntime::Cri => {
+            let mut cmd = runtime_cmd(Dependency::Crictl, "--runtime-endpoint", workloads);
+            let pods = cmd
+                .args(["pods", "--state", "ready", "--quiet", "--name"])
+                .arg(format!("^{}$", regex::escape(name)))
+                .output_and_check()
+                .with_context(|| format!("Failed to list pods named '{name}'"))?;
+            Ok(pods
+                .trim()
+                .is_empty()
+                .then(|| format!("pod '{name}' is not ready")))
+        }
+    }
+}
+
+/// Returns the command of the CLI of the container runtime, connected to the endpoint of
+/// `workloads` with the flag `endpoint_flag` if configured.
+fn runtime_cmd(
+    dependency: Dependency,
+    endpoint_flag: &str,
+    workloads: &ContainerCheckWorkloads,
+) -> Command {
+    let mut cmd = dependency.cmd();
+    if let Some(endpoint) = &workloads.endpoint {
+        cmd.arg(endpoint_flag).arg(endpoint);
+    }
+    cmd
+}
+
+/// Returns the state of the container `name` if it is not ready, given `state`, the status and
+/// health status reported by `podman container inspect`. Containers without a health check have
+/// an empty health status.
+fn assess_podman_state(name: &str, state: &str) -> Option<String> {
+    let mut fields = state.split_whitespace();
+    let status = fields.next().unwrap_or("unknown");
+    let health = fields.next();
+    match (status, health) {
+        ("running", None | Some("healthy")) => None,
+        ("running", Some(health)) => Some(format!("container '{name}' is {health}")),
+        (status, _) => Some(format!("container '{name}' is {status}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assess_podman_state() {
+        assert_eq!(assess_podman_state("web", "running \n"), None);
+        assert_eq!(assess_podman_state("web", "running healthy\n"), None);
+        assert_eq!(
+            assess_podman_state("web", "running starting\n").unwrap(),
+            "container 'web' is starting"
+        );
+        assert_eq!(
+            assess_podman_state("web", "running unhealthy\n").unwrap(),
+            "container 'web' is unhealthy"
+        );
+        assert_eq!(
+            assess_podman_state("web", "exited \n").unwrap(),
+            "container 'web' is exited"
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::{bail, Context, Error};
+use log::debug;
+use nix::sys::statvfs;
+
+use osutils::path;
+use trident_api::{
+    config::{DiskUsageCheckCriteria, MountPointFreeSpace},
+    primitives::bytes::ByteCount,
+};
+
+use crate::engine::space_forecast;
+
+/// Checks that the filesystems of the mount points of `criteria` have enough free space.
+pub(super) fn check(criteria: &DiskUsageCheckCriteria) -> Result<(), Error> {
+    let root = space_forecast::host_root()?;
+    let mut failures = Vec::new();
+    for mount_point in &criteria.mount_points {
+        let path = path::join_relative(&root, &mount_point.path);
+        let stat = statvfs::statvfs(&path)
+            .with_context(|| format!("Failed to get free space of '{}'", path.display()))?;
+        let size = stat.blocks() * stat.fragment_size();
+        let free = stat.blocks_available() * stat.fragment_size();
+        debug!(
+            "Filesystem of '{}' has {} free of {}",
+            mount_point.path.display(),
+            ByteCount(free).to_human_readable_approx(),
+            ByteCount(size).to_human_readable_approx()
+        );
+        failures.extend(check_free_space(mount_point, size, free));
+    }
+
+    if !failures.is_empty() {
+        bail!("{}", failures.join("\n"));
+    }
+    Ok(())
+}
+
+/// Returns why a filesystem of `size` bytes with `free` bytes available does not have the free
+/// space required for `mount_point`, if it does not.
+fn check_free_space(mount_point: &MountPointFreeSpace, size: u64, free: u64) -> Vec<String> {
+    let mut failures = Vec::new();
+    if let Some(min_free_percent) = mount_point.min_free_percent {
+        if min_free_percent > 100 {
+            failures.push(format!(
+                "Minimum free space of '{}' is {min_free_percent}%, which is over 100%",
+                mount_point.path.display()
+            ));
+        } else if (free as u128) * 100 < (size as u128) * (min_free_percent as u128) {
+            failures.push(format!(
+                "Filesystem of '{}' has {:.1}% free, expected at least {min_free_percent}%",
+                mount_point.path.display(),
+                if size == 0 {
+                    0.0
+                } else {
+                    free as f64 * 100.0 / size as f64
+                }
+            ));
+        }
+    }
+    if let Some(min_free_bytes) = mount_point.min_free_bytes {
+        if free < min_free_bytes.bytes() {
+            failures.push(format!(
+                "Filesystem of '{}' has {} free, expected at least {}",
+                mount_point.path.display(),
+                ByteCount(free).to_human_readable_approx(),
+                min_free_bytes.to_human_readable_approx()
+            ));
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_free_space() {
+        let mount_point = |min_free_percent, min_free_bytes: Option<u64>| MountPointFreeSpace {
+            path: "/var".into(),
+            min_free_percent,
+            min_free_bytes: min_free_bytes.map(ByteCount),
+        };
+        let size = 10 << 30;
+        let free = 1 << 30;
+
+        assert!(check_free_space(&mount_point(None, None), size, free).is_empty());
+        assert!(check_free_space(&mount_point(Some(10), Some(1 << 30)), size, free).is_empty());
+        assert_eq!(
+            check_free_space(&mount_point(Some(20), Some(2 << 30)), size, free),
+            vec![
+                "Filesystem of '/var' has 10.0% free, expected at least 20%",
+                "Filesystem of '/var' has 1.0G free, expected at least 2.0G",
+            ]
+        );
+        assert_eq!(
+            check_free_space(&mount_point(Some(101), None), size, free),
+            vec!["Minimum free space of '/var' is 101%, which is over 100%"]
+        );
+    }
+}
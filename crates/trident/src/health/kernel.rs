@@ -0,0 +1,123 @@
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context, Error};
+use log::debug;
+use regex::RegexSet;
+
+use osutils::dmesg;
+use trident_api::config::KernelCheckCriteria;
+
+/// Taint flags of the running kernel.
+const TAINTED_PATH: &str = "/proc/sys/kernel/tainted";
+
+/// Maximum number of matching kernel log messages reported by a failed check.
+const MAX_REPORTED_MESSAGES: usize = 10;
+
+/// Checks that the running kernel meets `criteria`: it has none of the taint flags of the mask,
+/// and no message of its log matches the patterns.
+pub(super) fn check(criteria: &KernelCheckCriteria) -> Result<(), Error> {
+    let mut failures = Vec::new();
+
+    if criteria.taint_mask != 0 {
+        let tainted = fs::read_to_string(TAINTED_PATH)
+            .with_context(|| format!("Failed to read '{TAINTED_PATH}'"))?;
+        if let Some(failure) = check_taint(&tainted, criteria.taint_mask)? {
+            failures.push(failure);
+        }
+    }
+
+    if !criteria.dmesg_patterns.is_empty() {
+        let patterns =
+            RegexSet::new(&criteria.dmesg_patterns).context("Failed to parse dmesg patterns")?;
+        failures.extend(matching_messages(&patterns, &dmesg::read()?));
+    }
+
+    if !failures.is_empty() {
+        bail!("{}", failures.join("\n"));
+    }
+    debug!("Kernel is not tainted and logged no errors");
+    Ok(())
+}
+
+/// Returns a description of the taint flags of `tainted`, the contents of
+/// `/proc/sys/kernel/tainted`, that are in `mask`, if any.
+fn check_taint(tainted: &str, mask: u64) -> Result<Option<String>, Error> {
+    let tainted: u64 = tainted
+        .trim()
+        .parse()
+        .with_context(|| format!("Failed to parse taint flags '{}'", tainted.trim()))?;
+    let matched = tainted & mask;
+    if matched == 0 {
+        return Ok(None);
+    }
+    let bits = (0..u64::BITS)
+        .filter(|bit| matched & (1 << bit) != 0)
+        .map(|bit| bit.to_string())
+        .collect::<Vec<_>>();
+    Ok(Some(format!(
+        "Kernel is tainted with flags {tainted} (bits {} are in the mask {mask})",
+        bits.join(", ")
+    )))
+}
+
+/// Returns the messages of the kernel log `log` that match any of `patterns`, up to
+/// `MAX_REPORTED_MESSAGES` of them.
+fn matching_messages(patterns: &RegexSet, log: &str) -> Vec<String> {
+    let matched = log
+        .lines()
+        .filter(|line| patterns.is_match(line))
+        .collect::<Vec<_>>();
+    let mut failures = matched
+        .iter()
+        .take(MAX_REPORTED_MESSAGES)
+        .map(|line| format!("Kernel logged '{}'", line.trim()))
+        .collect::<Vec<_>>();
+    if matched.len() > MAX_REPORTED_MESSAGES {
+        failures.push(format!(
+            "Kernel logged {} more matching messages",
+            matched.len() - MAX_REPORTED_MESSAGES
+        ));
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_taint() {
+        assert_eq!(check_taint("0\n", u64::MAX).unwrap(), None);
+        // Bit 12 (out-of-tree module) is set but not in the mask.
+        assert_eq!(check_taint("4097\n", 1 << 9).unwrap(), None);
+        assert_eq!(
+            check_taint("4097\n", (1 << 12) | 1).unwrap().unwrap(),
+            "Kernel is tainted with flags 4097 (bits 0, 12 are in the mask 4097)"
+        );
+        check_taint("tainted", 1).unwrap_err();
+    }
+
+    #[test]
+    fn test_matching_messages() {
+        let patterns = RegexSet::new(["Call Trace", r"I/O error, dev \w+"]).unwrap();
+        let log = "Linux version 6.6.0\n\
+            Call Trace:\n\
+            eth0: link up\n\
+            blk_update_request: I/O error, dev sda, sector 0\n";
+        assert_eq!(
+            matching_messages(&patterns, log),
+            vec![
+                "Kernel logged 'Call Trace:'",
+                "Kernel logged 'blk_update_request: I/O error, dev sda, sector 0'",
+            ]
+        );
+
+        let log = "Call Trace:\n".repeat(MAX_REPORTED_MESSAGES + 2);
+        let failures = matching_messages(&patterns, &log);
+        assert_eq!(failures.len(), MAX_REPORTED_MESSAGES + 1);
+        assert_eq!(
+            failures.last().unwrap(),
+            "Kernel logged 2 more matching messages"
+        );
+    }
+}
@@ -0,0 +1,355 @@
+use std::{
+    collections::HashSet,
+    fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error};
+use glob::Pattern;
+use log::debug;
+
+use osutils::{dependencies::Dependency, path};
+use trident_api::{
+    config::{Check, ContainerRuntime, Health, Script, ScriptSource},
+    constants::DEFAULT_SCRIPT_INTERPRETER,
+    status::ServicingType,
+};
+
+/// Directories holding the unit files of systemd, relative to the root of an OS.
+const UNIT_DIRECTORIES: [&str; 3] = [
+    "etc/systemd/system",
+    "usr/lib/systemd/system",
+    "lib/systemd/system",
+];
+
+/// Directories searched for executables that are not given by absolute path, relative to the
+/// root of an OS.
+const EXECUTABLE_DIRECTORIES: [&str; 5] = ["usr/local/bin", "usr/bin", "usr/sbin", "bin", "sbin"];
+
+/// Services that synchronize the system clock.
+const TIME_SYNC_SERVICES: [&str; 2] = ["chronyd.service", "systemd-timesyncd.service"];
+
+/// Types of units that systemd generates at runtime rather than loading from unit files.
+const GENERATED_UNIT_TYPES: [&str; 5] = ["automount", "device", "mount", "scope", "swap"];
+
+/// Maximum number of symbolic links followed when resolving a path.
+const MAX_SYMLINKS: usize = 40;
+
+/// Returns the misconfigurations of the health checks of `health` against the OS whose root
+/// filesystem is at `root`: checks that never run, scripts or interpreters that do not exist or
+/// are not executable, systemd units that have no unit file, and container runtimes that are not
+/// installed.
+pub(crate) fn lint(health: &Health, root: &Path) -> Result<Vec<String>, Error> {
+    let units = list_unit_files(root)?;
+    debug!(
+        "Found {} unit file(s) under '{}'",
+        units.len(),
+        root.display()
+    );
+
+    let mut problems = Vec::new();
+    for check in &health.checks {
+        let mut check_problems = Vec::new();
+        if !check.should_run(ServicingType::CleanInstall)
+            && !check.should_run(ServicingType::AbUpdate)
+        {
+            check_problems.push(
+                "never runs, as 'runOn' selects neither 'clean-install' nor 'ab-update'".into(),
+            );
+        }
+        match check {
+            Check::Script(script) => check_problems.extend(lint_script(script, root)),
+            Check::SystemdCheck(systemd_check) => check_problems.extend(
+                systemd_check
+                    .systemd_services
+                    .iter()
+                    .filter_map(|service| lint_unit(service, &units)),
+            ),
+            Check::ContainerCheck(container_check) => {
+                let dependency = match container_check.workloads.runtime {
+                    ContainerRuntime::Podman => Dependency::Podman,
+                    ContainerRuntime::Cri => Dependency::Crictl,
+                };
+                if find_executable(root, Path::new(dependency.name())).is_none() {
+                    check_problems.push(format!("needs '{dependency}', which is not installed"));
+                }
+            }
+            Check::NetworkCheck(network_check) => {
+                if network_check.criteria.default_gateway
+                    && find_executable(root, Path::new(Dependency::Ping.name())).is_none()
+                {
+                    check_problems.push(format!(
+                        "needs '{}', which is not installed",
+                        Dependency::Ping
+                    ));
+                }
+            }
+            Check::DiskHealthCheck(_) => {
+                if find_executable(root, Path::new(Dependency::Smartctl.name())).is_none() {
+                    check_problems.push(format!(
+                        "needs '{}', which is not installed",
+                        Dependency::Smartctl
+                    ));
+                }
+            }
+            Check::TimeSyncCheck(_) => {
+                if !TIME_SYNC_SERVICES
+                    .iter()
+                    .any(|service| units.contains(*service))
+                {
+                    check_problems.push(format!(
+                        "needs a time synchronization service, but none of '{}' has a unit file",
+                        TIME_SYNC_SERVICES.join("', '")
+                    ));
+                }
+            }
+            Check::HttpCheck(_)
+            | Check::TcpCheck(_)
+            | Check::KernelCheck(_)
+            | Check::DiskUsageCheck(_) => {}
+        }
+        problems.extend(
+            check_problems
+                .into_iter()
+                .map(|problem| format!("Health check '{}' {problem}", check.name())),
+        );
+    }
+    Ok(problems)
+}
+
+/// Returns the problems of the script of a health check against the OS at `root`.
+fn lint_script(script: &Script, root: &Path) -> Vec<String> {
+    let mut problems = Vec::new();
+    let interpreter = script
+        .interpreter
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SCRIPT_INTERPRETER));
+    if find_executable(root, &interpreter).is_none() {
+        problems.push(format!(
+            "has interpreter '{}', which is not an executable",
+            interpreter.display()
+        ));
+    }
+    if let ScriptSource::Path(path) = &script.source {
+        if path.to_string_lossy().contains(['*', '?', '[']) {
+            let pattern = path::join_relative(root, path);
+            let matches = glob::glob(&pattern.to_string_lossy())
+                .is_ok_and(|mut paths| paths.any(|path| path.is_ok_and(|path| path.is_file())));
+            if !matches {
+                problems.push(format!(
+                    "has script pattern '{}', which matches no script",
+                    path.display()
+                ));
+            }
+        } else if !resolve(root, path).is_some_and(|path| path.is_file()) {
+            problems.push(format!(
+                "has script '{}', which does not exist",
+                path.display()
+            ));
+        }
+    }
+    problems
+}
+
+/// Returns the problem of the systemd unit or glob pattern `service`, given `units`, the names of
+/// the unit files of the OS, if it has one.
+fn lint_unit(service: &str, units: &HashSet<String>) -> Option<String> {
+    if service.contains(['*', '?', '[']) {
+        let pattern = match Pattern::new(service) {
+            Ok(pattern) => pattern,
+            Err(e) => return Some(format!("has invalid unit pattern '{service}': {e}")),
+        };
+        return (!units.iter().any(|unit| pattern.matches(unit)))
+            .then(|| format!("has unit pattern '{service}', which matches no unit file"));
+    }
+
+    let unit_type = service.rsplit_once('.').map(|(_, unit_type)| unit_type);
+    if unit_type.is_some_and(|unit_type| GENERATED_UNIT_TYPES.contains(&unit_type)) {
+        return None;
+    }
+    // Instances of a template, e.g. 'getty@tty1.service', are loaded from the unit file of the
+    // template, e.g. 'getty@.service'.
+    let template = service.split_once('@').and_then(|(prefix, instance)| {
+        let (_, unit_type) = instance.rsplit_once('.')?;
+        Some(format!("{prefix}@.{unit_type}"))
+    });
+    if units.contains(service) || template.is_some_and(|template| units.contains(&template)) {
+        None
+    } else {
+        Some(format!("has unit '{service}', which has no unit file"))
+    }
+}
+
+/// Returns the names of the unit files of systemd of the OS at `root`.
+fn list_unit_files(root: &Path) -> Result<HashSet<String>, Error> {
+    let mut units = HashSet::new();
+    for directory in UNIT_DIRECTORIES {
+        let Some(directory) = resolve(root, Path::new(directory)) else {
+            continue;
+        };
+        if !directory.is_dir() {
+            continue;
+        }
+        for entry in fs::read_dir(&directory)
+            .with_context(|| format!("Failed to list unit files in '{}'", directory.display()))?
+        {
+            let entry = entry.with_context(|| {
+                format!("Failed to list unit files in '{}'", directory.display())
+            })?;
+            if let Some(name) = entry.file_name().to_str() {
+                units.insert(name.to_string());
+            }
+        }
+    }
+    Ok(units)
+}
+
+/// Returns the path under `root` of the executable `executable` of the OS at `root`. Executables
+/// that are not given by absolute path are searched in the usual directories of executables.
+fn find_executable(root: &Path, executable: &Path) -> Option<PathBuf> {
+    let candidates = if executable.is_absolute() {
+        vec![executable.to_path_buf()]
+    } else {
+        EXECUTABLE_DIRECTORIES
+            .iter()
+            .map(|directory| Path::new("/").join(directory).join(executable))
+            .collect()
+    };
+    candidates
+        .into_iter()
+        .filter_map(|candidate| resolve(root, &candidate))
+        .find(|path| {
+            fs::metadata(path).is_ok_and(|metadata| {
+                metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+            })
+        })
+}
+
+/// Returns the path under `root` of `path` of the OS at `root`, following the symbolic links of
+/// its last component within `root`, or `None` if it does not exist. Absolute links point into
+/// `root` rather than into the running OS.
+fn resolve(root: &Path, path: &Path) -> Option<PathBuf> {
+    let mut path = Path::new("/").join(path);
+    for _ in 0..MAX_SYMLINKS {
+        let full_path = path::join_relative(root, &path);
+        let metadata = fs::symlink_metadata(&full_path).ok()?;
+        if !metadata.is_symlink() {
+            return Some(full_path);
+        }
+        let target = fs::read_link(&full_path).ok()?;
+        path = match path.parent() {
+            Some(parent) => parent.join(target),
+            None => target,
+        };
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::os::unix::fs::symlink;
+
+    use trident_api::config::{
+        ContainerCheck, ContainerCheckWorkloads, ServicingTypeSelection, SystemdCheck,
+    };
+
+    #[test]
+    fn test_lint_unit() {
+        let units = ["sshd.service", "getty@.service", "multi-user.target"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(lint_unit("sshd.service", &units), None);
+        assert_eq!(lint_unit("getty@tty1.service", &units), None);
+        assert_eq!(lint_unit("multi-user.target", &units), None);
+        assert_eq!(lint_unit("var.mount", &units), None);
+        assert_eq!(lint_unit("ssh*.service", &units), None);
+        assert_eq!(
+            lint_unit("kubelet.service", &units).unwrap(),
+            "has unit 'kubelet.service', which has no unit file"
+        );
+        assert_eq!(
+            lint_unit("serial-getty@ttyS0.service", &units).unwrap(),
+            "has unit 'serial-getty@ttyS0.service', which has no unit file"
+        );
+        assert_eq!(
+            lint_unit("myapp-*.service", &units).unwrap(),
+            "has unit pattern 'myapp-*.service', which matches no unit file"
+        );
+    }
+
+    #[test]
+    fn test_lint() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        fs::create_dir_all(root.join("usr/bin")).unwrap();
+        fs::create_dir_all(root.join("usr/lib/systemd/system")).unwrap();
+        fs::create_dir_all(root.join("etc/health")).unwrap();
+        symlink("usr/bin", root.join("bin")).unwrap();
+        for executable in ["usr/bin/bash", "usr/bin/podman"] {
+            fs::write(root.join(executable), "").unwrap();
+            fs::set_permissions(root.join(executable), fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        // Absolute links point into the root of the OS.
+        symlink("/usr/bin/bash", root.join("usr/bin/sh")).unwrap();
+        fs::write(root.join("usr/bin/python3"), "").unwrap();
+        fs::write(root.join("usr/lib/systemd/system/sshd.service"), "").unwrap();
+        fs::write(root.join("etc/health/check.sh"), "").unwrap();
+
+        let script = |name: &str, interpreter: Option<&str>, path: &str| {
+            Check::Script(Script {
+                name: name.into(),
+                run_on: vec![ServicingTypeSelection::AbUpdate],
+                interpreter: interpreter.map(PathBuf::from),
+                source: ScriptSource::Path(path.into()),
+                ..Default::default()
+            })
+        };
+        let health = Health {
+            checks: vec![
+                script("valid-script", None, "/etc/health/check.sh"),
+                script("bash-script", Some("bash"), "/etc/health/check.sh"),
+                script("python-script", Some("python3"), "/etc/health/missing.py"),
+                script("image-scripts", None, "/etc/health/*.sh"),
+                script("missing-scripts", None, "/usr/lib/trident/checks.d/*"),
+                Check::SystemdCheck(SystemdCheck {
+                    name: "services".into(),
+                    systemd_services: vec!["sshd.service".into(), "kubelet.service".into()],
+                    run_on: vec![ServicingTypeSelection::AbUpdate],
+                    ..Default::default()
+                }),
+                Check::ContainerCheck(ContainerCheck {
+                    name: "pods".into(),
+                    workloads: ContainerCheckWorkloads {
+                        runtime: ContainerRuntime::Cri,
+                        endpoint: None,
+                        names: vec!["web".into()],
+                        timeout_seconds: 60,
+                    },
+                    retry: Default::default(),
+                    severity: Default::default(),
+                    run_on: vec![ServicingTypeSelection::NormalUpdate],
+                }),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            lint(&health, root).unwrap(),
+            vec![
+                "Health check 'python-script' has interpreter 'python3', which is not an executable",
+                "Health check 'python-script' has script '/etc/health/missing.py', which does not \
+                exist",
+                "Health check 'missing-scripts' has script pattern '/usr/lib/trident/checks.d/*', \
+                which matches no script",
+                "Health check 'services' has unit 'kubelet.service', which has no unit file",
+                "Health check 'pods' never runs, as 'runOn' selects neither 'clean-install' nor \
+                'ab-update'",
+                "Health check 'pods' needs 'crictl', which is not installed",
+            ]
+        );
+    }
+}
@@ -1,32 +1,220 @@
 use std::{
-    path::Path,
+    net::{TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
     sync::mpsc,
     thread,
     time::{Duration, Instant},
 };
 
-use log::{debug, error, info};
+use anyhow::{bail, Context, Error};
+use chrono::{DateTime, Utc};
+use log::{debug, error, info, warn};
+use reqwest::{blocking::Client, Method};
+use serde::Serialize;
 
-use osutils::dependencies::Dependency;
+use osutils::{dependencies::Dependency, exe::OutputChecker, osrelease::OsRelease, path};
 use trident_api::{
-    config::{Check, SystemdCheck},
+    config::{
+        Check, CheckSeverity, HealthMonitor, HttpCheck, HttpMethod, Script, ScriptSource,
+        SystemdCheck, TcpCheck,
+    },
     constants::ROOT_MOUNT_POINT_PATH,
     error::{ServicingError, TridentError},
+    status::{
+        HealthCheckBoot, HealthCheckKind, HealthCheckOutcome, HealthCheckVerdict, HostStatus,
+        ServicingType,
+    },
 };
 
-use crate::{engine::EngineContext, subsystems::hooks};
+use crate::{
+    engine::{self, space_forecast, EngineContext},
+    subsystems::{extensions::status as extension_status, hooks},
+    DataStore,
+};
+
+mod boot_complete;
+mod container;
+mod disk;
+mod kernel;
+pub(crate) mod lint;
+mod network;
+mod smart;
+mod time_sync;
+
+/// Timeout of health check scripts that do not set one, so that a hung script cannot block the
+/// commit of the target OS forever.
+const DEFAULT_SCRIPT_CHECK_TIMEOUT_SECONDS: u64 = 300;
+
+/// Name of the health check of the extension images staged into the target OS.
+const EXTENSIONS_CHECK_NAME: &str = "extension-images";
+
+/// Name of the health check of the boot assessment of systemd.
+const BOOT_COMPLETE_CHECK_NAME: &str = "boot-complete";
+
+/// Maximum number of boots kept in the health check history of the Host Status.
+const MAX_HEALTH_CHECK_HISTORY_BOOTS: usize = 20;
+
+/// Maximum length of the output of a script check that is kept in its result. The end of the
+/// output is kept, as that is where scripts usually report why they failed.
+const MAX_OUTPUT_EXCERPT_BYTES: usize = 4096;
+
+/// Outcome of a single health check.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HealthCheckResult {
+    pub name: String,
+    pub kind: HealthCheckKind,
+    pub success: bool,
+    pub severity: CheckSeverity,
+    pub duration_seconds: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Outcome of a single attempt of a health check.
+struct CheckRun {
+    result: Result<(), String>,
+    /// Exit code of a script check.
+    exit_code: Option<i32>,
+    /// Excerpt of the output of a script check.
+    output: Option<String>,
+}
+
+impl From<Result<(), String>> for CheckRun {
+    fn from(result: Result<(), String>) -> Self {
+        Self {
+            result,
+            exit_code: None,
+            output: None,
+        }
+    }
+}
 
-#[derive(Debug)]
-struct ScriptError {
-    script_name: String,
-    error_message: String,
+/// Outcome of a set of health checks, in the order in which they were configured.
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HealthCheckReport {
+    pub checks: Vec<HealthCheckResult>,
+}
+
+impl HealthCheckReport {
+    /// Returns the results of the failed health checks that fail the health checks.
+    pub fn failures(&self) -> impl Iterator<Item = &HealthCheckResult> {
+        self.checks
+            .iter()
+            .filter(|check| !check.success && check.severity == CheckSeverity::Fail)
+    }
+
+    /// Returns the results of the failed health checks that only warrant a warning.
+    pub fn warnings(&self) -> impl Iterator<Item = &HealthCheckResult> {
+        self.checks
+            .iter()
+            .filter(|check| !check.success && check.severity == CheckSeverity::Warn)
+    }
+
+    /// Returns a summary of all failed health checks, one per line.
+    pub fn failure_details(&self) -> String {
+        self.failures()
+            .map(HealthCheckResult::details)
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl HealthCheckResult {
+    /// Returns the name of the check with its error, followed by its output, if any.
+    fn details(&self) -> String {
+        let details = format!("{}: {:?}", self.name, self.error.as_deref().unwrap_or(""));
+        match &self.output {
+            Some(output) => format!("{details}\nOutput of '{}':\n{output}", self.name),
+            None => details,
+        }
+    }
+
+    /// Returns the verdict of the check.
+    fn verdict(&self) -> HealthCheckVerdict {
+        match (self.success, self.severity) {
+            (true, _) => HealthCheckVerdict::Pass,
+            (false, CheckSeverity::Warn) => HealthCheckVerdict::Warn,
+            (false, CheckSeverity::Fail) => HealthCheckVerdict::Fail,
+        }
+    }
+
+    /// Returns the outcome of the check, as recorded in the Host Status.
+    fn outcome(&self) -> HealthCheckOutcome {
+        HealthCheckOutcome {
+            name: self.name.clone(),
+            kind: self.kind,
+            verdict: self.verdict(),
+            duration_millis: (self.duration_seconds * 1000.0).round() as u64,
+            exit_code: self.exit_code,
+            output: self.output.clone(),
+            error: self.error.clone(),
+        }
+    }
 }
 
 /// This function will be called outside the standard subsystem flow
-/// before Trident commits a target OS.
-pub fn execute_health_checks(ctx: &EngineContext) -> Result<(), TridentError> {
-    let hooks_subsystem = hooks::HooksSubsystem::new_for_local_scripts();
-    let health_checks = ctx
+/// before Trident commits a target OS. The outcome of each check is recorded in the Host Status,
+/// whether the checks succeed or not.
+pub fn execute_health_checks(
+    ctx: &EngineContext,
+    datastore: &mut DataStore,
+) -> Result<(), TridentError> {
+    let health_checks = configured_checks(ctx, datastore.host_status());
+    if !health_checks.is_empty() {
+        debug!("Running health check(s)");
+    }
+
+    // The configured checks run once the boot is complete, when all services had a chance to
+    // start.
+    let boot_complete = check_boot_complete(ctx);
+    let mut report = run_health_checks(health_checks, ctx);
+    report.checks.extend(boot_complete);
+    report.checks.extend(check_extensions(ctx));
+    assess_health_checks(ctx, datastore, &report)?;
+    Ok(())
+}
+
+/// Runs the configured health checks every interval of `monitor` until the end of its grace
+/// window, once the target OS was committed. Fails as soon as a run of the checks fails.
+pub fn monitor_health(
+    ctx: &EngineContext,
+    datastore: &mut DataStore,
+    monitor: &HealthMonitor,
+) -> Result<(), TridentError> {
+    let health_checks = configured_checks(ctx, datastore.host_status());
+    if health_checks.is_empty() {
+        return Ok(());
+    }
+    info!(
+        "Monitoring health for {} seconds after committing",
+        monitor.window_seconds
+    );
+    let end = Instant::now() + Duration::from_secs(monitor.window_seconds);
+    let interval = Duration::from_secs(monitor.interval_seconds);
+    loop {
+        let remaining = end.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        thread::sleep(interval.min(remaining));
+        debug!("Running health check(s) of the grace window");
+        let report = run_health_checks(health_checks.clone(), ctx);
+        assess_health_checks(ctx, datastore, &report)?;
+    }
+    info!("Health checks succeeded throughout the grace window");
+    Ok(())
+}
+
+/// Returns the health checks of the Host Configuration that run for the servicing type of `ctx`,
+/// with their script environment set.
+fn configured_checks(ctx: &EngineContext, host_status: &HostStatus) -> Vec<Check> {
+    let mut health_checks = ctx
         .spec
         .health
         .checks
@@ -34,74 +222,522 @@ pub fn execute_health_checks(ctx: &EngineContext) -> Result<(), TridentError> {
         .into_iter()
         .filter(|check| check.should_run(ctx.servicing_type))
         .collect::<Vec<_>>();
-    if !health_checks.is_empty() {
-        debug!("Running health check(s)");
+    add_script_environment(&mut health_checks, host_status);
+    health_checks
+}
+
+/// Records the outcome of the health checks of `report` in the Host Status, and fails if any of
+/// them failed.
+fn assess_health_checks(
+    ctx: &EngineContext,
+    datastore: &mut DataStore,
+    report: &HealthCheckReport,
+) -> Result<(), TridentError> {
+    let boot_id = engine::current_boot_id()
+        .map_err(|e| warn!("Failed to read boot ID for health check history: {e:?}"))
+        .ok();
+    let outcomes = report
+        .checks
+        .iter()
+        .map(HealthCheckResult::outcome)
+        .collect::<Vec<_>>();
+    datastore.with_host_status(|host_status| {
+        record_history(
+            &mut host_status.health_check_history,
+            boot_id,
+            outcomes.clone(),
+            Utc::now(),
+        );
+        host_status.health_checks = outcomes;
+    })?;
+    if report.failures().next().is_some() {
+        let health_check_errors_message = report.failure_details();
+        error!(
+            "Health checks completed with errors:\n{}",
+            health_check_errors_message
+        );
+        return Err(TridentError::new(ServicingError::HealthChecksFailed {
+            details: health_check_errors_message,
+            servicing_type: format!("{:?}", ctx.servicing_type),
+        }));
+    }
+
+    let warnings = report
+        .warnings()
+        .map(HealthCheckResult::details)
+        .collect::<Vec<_>>();
+    if !warnings.is_empty() {
+        warn!(
+            "Non-critical health checks failed:\n{}",
+            warnings.join("\n")
+        );
+    }
+    Ok(())
+}
+
+/// Records `outcomes`, the outcome of a run of the health checks at `now` in the boot `boot_id`,
+/// in `history`. Runs of the same boot are merged into one entry, and only the last boots are
+/// kept.
+fn record_history(
+    history: &mut Vec<HealthCheckBoot>,
+    boot_id: Option<String>,
+    outcomes: Vec<HealthCheckOutcome>,
+    now: DateTime<Utc>,
+) {
+    let failed = outcomes
+        .iter()
+        .any(|outcome| outcome.verdict == HealthCheckVerdict::Fail);
+    match history.last_mut() {
+        Some(last) if boot_id.is_some() && last.boot_id == boot_id => {
+            last.last_run = now;
+            last.runs += 1;
+            last.failed_runs += u32::from(failed);
+            last.checks = outcomes;
+        }
+        _ => history.push(HealthCheckBoot {
+            boot_id,
+            last_run: now,
+            runs: 1,
+            failed_runs: u32::from(failed),
+            checks: outcomes,
+        }),
+    }
+
+    let excess = history.len().saturating_sub(MAX_HEALTH_CHECK_HISTORY_BOOTS);
+    history.drain(..excess);
+}
+
+/// Sets the environment variables of the Health section, along with `OS_VERSION` and
+/// `PREVIOUS_OS_VERSION`, on the script checks of `health_checks`. Variables set by a script take
+/// precedence.
+pub(crate) fn add_script_environment(health_checks: &mut [Check], host_status: &HostStatus) {
+    let mut environment = host_status.spec.health.environment_variables.clone();
+    match os_version() {
+        Ok(Some(version)) => {
+            environment.insert("OS_VERSION".into(), version);
+        }
+        Ok(None) => {}
+        Err(e) => warn!("Failed to get version of running OS: {e:?}"),
+    }
+    if let Some(version) = &host_status.previous_os_version {
+        environment.insert("PREVIOUS_OS_VERSION".into(), version.clone());
+    }
+
+    for check in health_checks {
+        if let Check::Script(script) = check {
+            for (name, value) in &environment {
+                script
+                    .environment_variables
+                    .entry(name.clone())
+                    .or_insert_with(|| value.clone());
+            }
+        }
     }
+}
+
+/// Returns the `VERSION_ID` of the running OS, if it has one.
+pub(crate) fn os_version() -> Result<Option<String>, Error> {
+    let root = space_forecast::host_root()?;
+    Ok(OsRelease::read_root(&root)
+        .context("Failed to read OS release")?
+        .version_id)
+}
+
+/// Runs the given health checks in parallel and collects their results into a report. When the
+/// health checks have a deadline, the checks are cut short to complete by it.
+pub(crate) fn run_health_checks(
+    health_checks: Vec<Check>,
+    ctx: &EngineContext,
+) -> HealthCheckReport {
+    let health_checks = match space_forecast::host_root() {
+        Ok(root) => expand_script_patterns(health_checks, &root),
+        Err(e) => {
+            warn!("Failed to get host root path, not expanding script patterns: {e:?}");
+            health_checks
+        }
+    };
+    let hooks_subsystem = hooks::HooksSubsystem::new_for_local_scripts();
+    let deadline = ctx
+        .spec
+        .health
+        .timeout_seconds
+        .map(|timeout_seconds| Instant::now() + Duration::from_secs(timeout_seconds));
 
-    // Channel to collect script errors from threads
+    // Channel to collect check results from threads
     let (tx, rx) = mpsc::channel();
     // Create parallel health check threads within a scope, the
     // threads will all be joined before the scope ends.
     thread::scope(|s| {
-        for health_check in health_checks {
+        for (index, health_check) in health_checks.into_iter().enumerate() {
             let inner_subsystem = &hooks_subsystem;
             let inner_tx = tx.clone();
             s.spawn(move || {
-                match health_check {
+                let start_time = Instant::now();
+                let (name, kind) = match &health_check {
                     Check::SystemdCheck(systemd_check) => {
-                        if let Err(err) = run_systemd_check(&systemd_check) {
-                            if let Err(e) = inner_tx.send(ScriptError {
-                                script_name: systemd_check.name,
-                                error_message: format!("{err:?}"),
-                            }) {
-                                error!("Failed to send systemd check error: {e:?}");
-                            }
-                        }
+                        (systemd_check.name.clone(), HealthCheckKind::SystemdCheck)
+                    }
+                    Check::Script(script) => (script.name.clone(), HealthCheckKind::Script),
+                    Check::HttpCheck(http_check) => {
+                        (http_check.name.clone(), HealthCheckKind::HttpCheck)
                     }
-                    Check::Script(inner_script) => {
-                        if let Err(err) = inner_subsystem.run_script(
-                            &inner_script,
-                            ctx,
-                            Path::new(ROOT_MOUNT_POINT_PATH),
-                        ) {
-                            if let Err(e) = inner_tx.send(ScriptError {
-                                script_name: inner_script.name,
-                                error_message: format!("{err:?}"),
-                            }) {
-                                error!("Failed to send script error: {e:?}");
-                            }
-                        }
+                    Check::TcpCheck(tcp_check) => {
+                        (tcp_check.name.clone(), HealthCheckKind::TcpCheck)
+                    }
+                    Check::KernelCheck(kernel_check) => {
+                        (kernel_check.name.clone(), HealthCheckKind::KernelCheck)
+                    }
+                    Check::DiskUsageCheck(disk_usage_check) => (
+                        disk_usage_check.name.clone(),
+                        HealthCheckKind::DiskUsageCheck,
+                    ),
+                    Check::ContainerCheck(container_check) => (
+                        container_check.name.clone(),
+                        HealthCheckKind::ContainerCheck,
+                    ),
+                    Check::NetworkCheck(network_check) => {
+                        (network_check.name.clone(), HealthCheckKind::NetworkCheck)
+                    }
+                    Check::DiskHealthCheck(disk_health_check) => (
+                        disk_health_check.name.clone(),
+                        HealthCheckKind::DiskHealthCheck,
+                    ),
+                    Check::TimeSyncCheck(time_sync_check) => {
+                        (time_sync_check.name.clone(), HealthCheckKind::TimeSyncCheck)
                     }
                 };
+                let severity = health_check.severity();
+                let retry = health_check.retry();
+                let mut run = run_check(&health_check, inner_subsystem, ctx, deadline);
+                for attempt in 0..retry.retries {
+                    let Err(e) = &run.result else {
+                        break;
+                    };
+                    let backoff = retry.backoff(attempt);
+                    if deadline.is_some_and(|deadline| Instant::now() + backoff >= deadline) {
+                        info!("Not retrying health check '{name}' past the deadline: {e}");
+                        break;
+                    }
+                    info!(
+                        "Health check '{name}' failed, retrying in {} seconds ({} of {}): {e}",
+                        backoff.as_secs(),
+                        attempt + 1,
+                        retry.retries
+                    );
+                    thread::sleep(backoff);
+                    run = run_check(&health_check, inner_subsystem, ctx, deadline);
+                }
+                let check_result = HealthCheckResult {
+                    name,
+                    kind,
+                    success: run.result.is_ok(),
+                    severity,
+                    duration_seconds: start_time.elapsed().as_secs_f64(),
+                    exit_code: run.exit_code,
+                    output: run.output,
+                    error: run.result.err(),
+                };
+                if let Err(e) = inner_tx.send((index, check_result)) {
+                    error!("Failed to send health check result: {e:?}");
+                }
                 drop(inner_tx);
             });
         }
         drop(tx);
     });
 
-    // Collect messages from the channel
-    let mut health_check_errors = Vec::new();
-    while let Ok(script_error) = rx.recv() {
-        health_check_errors.push(script_error);
+    // Collect results from the channel, restoring the configured order
+    let mut results = Vec::new();
+    while let Ok(result) = rx.recv() {
+        results.push(result);
     }
+    results.sort_by_key(|(index, _)| *index);
 
-    // Create error collection from individual health check failures
-    let health_check_errors_message: String = health_check_errors
-        .iter()
-        .map(|e| format!("{}: {:?}", e.script_name, e.error_message))
-        .collect::<Vec<String>>()
-        .join("\n");
-    if !health_check_errors.is_empty() {
-        error!(
-            "Health checks completed with errors:\n{}",
-            health_check_errors_message
+    HealthCheckReport {
+        checks: results.into_iter().map(|(_, result)| result).collect(),
+    }
+}
+
+/// Runs a single attempt of the health check `check`.
+fn run_check(
+    check: &Check,
+    hooks_subsystem: &hooks::HooksSubsystem,
+    ctx: &EngineContext,
+    deadline: Option<Instant>,
+) -> CheckRun {
+    let check = match cut_to_deadline(check, deadline) {
+        Ok(check) => check,
+        Err(e) => return Err(e).into(),
+    };
+    let result = match &check {
+        Check::SystemdCheck(systemd_check) => {
+            run_systemd_check(systemd_check).map_err(|e| format!("{e:?}"))
+        }
+        Check::Script(script) => return run_script_check(script, hooks_subsystem, ctx),
+        Check::HttpCheck(http_check) => run_http_check(http_check).map_err(|e| format!("{e:?}")),
+        Check::TcpCheck(tcp_check) => run_tcp_check(tcp_check).map_err(|e| format!("{e:?}")),
+        Check::KernelCheck(kernel_check) => {
+            kernel::check(&kernel_check.criteria).map_err(|e| format!("{e:?}"))
+        }
+        Check::DiskUsageCheck(disk_usage_check) => {
+            disk::check(&disk_usage_check.criteria).map_err(|e| format!("{e:?}"))
+        }
+        Check::ContainerCheck(container_check) => {
+            container::check(&container_check.workloads).map_err(|e| format!("{e:?}"))
+        }
+        Check::NetworkCheck(network_check) => {
+            network::check(&network_check.criteria).map_err(|e| format!("{e:?}"))
+        }
+        Check::DiskHealthCheck(disk_health_check) => {
+            smart::check(&disk_health_check.criteria, &ctx.spec).map_err(|e| format!("{e:?}"))
+        }
+        Check::TimeSyncCheck(time_sync_check) => {
+            time_sync::check(&time_sync_check.criteria).map_err(|e| format!("{e:?}"))
+        }
+    };
+    result.into()
+}
+
+/// Returns `health_checks` with each script check whose path is a glob pattern, e.g.
+/// `/usr/lib/trident/checks.d/*`, replaced by one script check per file of the OS at `root` that
+/// the pattern matches, in alphabetical order. Each of these checks is named after the pattern
+/// check and the file name of its script. Patterns that match no file are kept, so that their
+/// check fails.
+fn expand_script_patterns(health_checks: Vec<Check>, root: &Path) -> Vec<Check> {
+    let mut expanded = Vec::new();
+    for check in health_checks {
+        let pattern = match &check {
+            Check::Script(Script {
+                source: ScriptSource::Path(path),
+                ..
+            }) if path.to_string_lossy().contains(['*', '?', '[']) => path.clone(),
+            _ => {
+                expanded.push(check);
+                continue;
+            }
+        };
+        let Check::Script(script) = &check else {
+            unreachable!()
+        };
+        let scripts = match find_scripts(&pattern, root) {
+            Ok(scripts) => scripts,
+            Err(e) => {
+                warn!(
+                    "Failed to find scripts matching '{}': {e:?}",
+                    pattern.display()
+                );
+                Vec::new()
+            }
+        };
+        if scripts.is_empty() {
+            expanded.push(check);
+            continue;
+        }
+        debug!(
+            "Expanded '{}' to {} script(s)",
+            pattern.display(),
+            scripts.len()
         );
-        return Err(TridentError::new(ServicingError::HealthChecksFailed {
-            details: health_check_errors_message,
-            servicing_type: format!("{:?}", ctx.servicing_type),
+        expanded.extend(scripts.into_iter().map(|path| {
+            let file_name = path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            Check::Script(Script {
+                name: format!("{}/{file_name}", script.name),
+                source: ScriptSource::Path(path),
+                ..script.clone()
+            })
         }));
     }
-    Ok(())
+    expanded
+}
+
+/// Returns the absolute paths, as seen from the OS at `root`, of the files of that OS that match
+/// `pattern`, in alphabetical order.
+fn find_scripts(pattern: &Path, root: &Path) -> Result<Vec<PathBuf>, Error> {
+    let full_pattern = path::join_relative(root, pattern);
+    let full_pattern = full_pattern
+        .to_str()
+        .with_context(|| format!("Pattern '{}' is not valid UTF-8", pattern.display()))?;
+    let mut scripts = Vec::new();
+    for entry in glob::glob(full_pattern)
+        .with_context(|| format!("Pattern '{}' is invalid", pattern.display()))?
+    {
+        let script = entry.context("Failed to read matching path")?;
+        if script.is_file() {
+            scripts.push(Path::new("/").join(script.strip_prefix(root)?));
+        }
+    }
+    Ok(scripts)
+}
+
+/// Runs the health check script `script` against the running OS, keeping its exit code and the
+/// end of its output.
+fn run_script_check(
+    script: &Script,
+    hooks_subsystem: &hooks::HooksSubsystem,
+    ctx: &EngineContext,
+) -> CheckRun {
+    // Patterns that matched scripts were expanded before running the checks.
+    if let ScriptSource::Path(path) = &script.source {
+        if path.to_string_lossy().contains(['*', '?', '[']) {
+            return Err(format!("No script matches '{}'", path.display())).into();
+        }
+    }
+    let result =
+        match hooks_subsystem.run_script_unchecked(script, ctx, Path::new(ROOT_MOUNT_POINT_PATH)) {
+            Ok(Some(result)) => result,
+            Ok(None) => return Ok(()).into(),
+            Err(e) => return Err(format!("{e:?}")).into(),
+        };
+    let check_result = if result.is_success() {
+        info!("Script '{}' executed successfully", script.name);
+        Ok(())
+    } else {
+        Err(format!(
+            "Script '{}' failed: {}",
+            script.name,
+            result.explain_exit()
+        ))
+    };
+    CheckRun {
+        result: check_result,
+        exit_code: result.exit_code(),
+        output: output_excerpt(&result.output),
+    }
+}
+
+/// Returns the end of `output`, up to `MAX_OUTPUT_EXCERPT_BYTES` of it, or `None` if it is empty.
+fn output_excerpt(output: &str) -> Option<String> {
+    let output = output.trim_end();
+    if output.is_empty() {
+        return None;
+    }
+    if output.len() <= MAX_OUTPUT_EXCERPT_BYTES {
+        return Some(output.into());
+    }
+    let mut start = output.len() - MAX_OUTPUT_EXCERPT_BYTES;
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    Some(format!("[...]{}", &output[start..]))
+}
+
+/// Returns `check` with its timeouts cut short to end by `deadline`, if any. Fails if the deadline
+/// has passed.
+fn cut_to_deadline(check: &Check, deadline: Option<Instant>) -> Result<Check, String> {
+    let mut check = check.clone();
+    if let Check::Script(script) = &mut check {
+        script
+            .timeout_seconds
+            .get_or_insert(DEFAULT_SCRIPT_CHECK_TIMEOUT_SECONDS);
+    }
+    let Some(deadline) = deadline else {
+        return Ok(check);
+    };
+
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining.is_zero() {
+        return Err("Health checks did not complete before their deadline".into());
+    }
+    // Timeouts are in whole seconds, so a check may overrun the deadline by up to a second.
+    let remaining = remaining.as_secs().max(1);
+    match &mut check {
+        Check::Script(script) => {
+            script.timeout_seconds = script.timeout_seconds.map(|t| t.min(remaining));
+        }
+        Check::SystemdCheck(systemd_check) => {
+            systemd_check.timeout_seconds = systemd_check.timeout_seconds.min(remaining as usize);
+        }
+        Check::HttpCheck(http_check) => {
+            http_check.request.timeout_seconds = http_check.request.timeout_seconds.min(remaining);
+        }
+        Check::TcpCheck(tcp_check) => {
+            tcp_check.target.timeout_seconds = tcp_check.target.timeout_seconds.min(remaining);
+        }
+        Check::ContainerCheck(container_check) => {
+            container_check.workloads.timeout_seconds =
+                container_check.workloads.timeout_seconds.min(remaining);
+        }
+        Check::NetworkCheck(network_check) => {
+            network_check.criteria.timeout_seconds =
+                network_check.criteria.timeout_seconds.min(remaining);
+        }
+        Check::TimeSyncCheck(time_sync_check) => {
+            time_sync_check.criteria.timeout_seconds =
+                time_sync_check.criteria.timeout_seconds.min(remaining);
+        }
+        Check::KernelCheck(_) | Check::DiskUsageCheck(_) | Check::DiskHealthCheck(_) => {}
+    }
+    Ok(check)
+}
+
+/// Checks that the extension images of the Host Configuration, which were staged into the
+/// target OS of a clean install or an A/B update, are merged on its first boot. Returns `None`
+/// when there is nothing to check.
+fn check_extensions(ctx: &EngineContext) -> Option<HealthCheckResult> {
+    if !matches!(
+        ctx.servicing_type,
+        ServicingType::CleanInstall | ServicingType::AbUpdate
+    ) || (ctx.spec.os.sysexts.is_empty() && ctx.spec.os.confexts.is_empty())
+    {
+        return None;
+    }
+
+    debug!("Checking that extension images are merged");
+    let start_time = Instant::now();
+    let result = extension_status::list(&ctx.spec)
+        .map_err(|e| format!("{e:?}"))
+        .and_then(|statuses| {
+            let drifted = extension_status::drift(&statuses);
+            if drifted.is_empty() {
+                Ok(())
+            } else {
+                Err(drifted
+                    .into_iter()
+                    .map(extension_status::describe_drift)
+                    .collect::<Vec<_>>()
+                    .join(", "))
+            }
+        });
+    Some(HealthCheckResult {
+        name: EXTENSIONS_CHECK_NAME.into(),
+        kind: HealthCheckKind::Extensions,
+        success: result.is_ok(),
+        severity: CheckSeverity::Fail,
+        duration_seconds: start_time.elapsed().as_secs_f64(),
+        exit_code: None,
+        output: None,
+        error: result.err(),
+    })
+}
+
+/// Waits for the boot of the target OS of a clean install or an A/B update to be complete, as
+/// assessed by systemd, if configured. Returns `None` when there is nothing to check.
+fn check_boot_complete(ctx: &EngineContext) -> Option<HealthCheckResult> {
+    let boot_complete = ctx.spec.health.boot_complete.as_ref()?;
+    if !matches!(
+        ctx.servicing_type,
+        ServicingType::CleanInstall | ServicingType::AbUpdate
+    ) {
+        return None;
+    }
+
+    debug!("Waiting for boot to be complete");
+    let start_time = Instant::now();
+    let result = boot_complete::check(boot_complete).map_err(|e| format!("{e:?}"));
+    Some(HealthCheckResult {
+        name: BOOT_COMPLETE_CHECK_NAME.into(),
+        kind: HealthCheckKind::BootComplete,
+        success: result.is_ok(),
+        severity: CheckSeverity::Fail,
+        duration_seconds: start_time.elapsed().as_secs_f64(),
+        exit_code: None,
+        output: None,
+        error: result.err(),
+    })
 }
 
 /// This function will be called outside the standard subsystem flow
@@ -111,62 +747,305 @@ pub fn execute_health_checks(ctx: &EngineContext) -> Result<(), TridentError> {
 /// systemctl status, are in good running state. If not, the function
 /// will retry until the specified timeout is reached. On timeout, the
 /// last error will be returned.
+///
+/// Service names with glob patterns, e.g. `myapp-*.service`, are expanded
+/// against the units known to systemd on each query. When configured, the
+/// whole system must also be running, as reported by `systemctl
+/// is-system-running`.
 fn run_systemd_check(check: &SystemdCheck) -> Result<(), TridentError> {
     let start_time = Instant::now();
     let timeout_duration = Duration::from_secs(check.timeout_seconds as u64);
 
-    let services_list = check.systemd_services.join(" ");
+    let mut services_list = check.systemd_services.join(" ");
+    if check.system_running {
+        services_list = format!("system {services_list}").trim_end().into();
+    }
     debug!("Checking status of systemd service(s) '{}'", &services_list);
 
     loop {
-        let status = Dependency::Systemctl
-            .cmd()
-            .env("SYSTEMD_IGNORE_CHROOT", "true")
-            .arg("status")
-            .args(&check.systemd_services)
-            .output();
-        let error = match status {
-            Ok(output) => match output.check() {
-                Ok(_) => {
-                    info!(
-                        "Service(s) '{services_list}' are active/running: {}",
-                        output.output_report()
-                    );
-                    return Ok(());
-                }
-                Err(e) => {
-                    info!("Service(s) '{services_list}' are not active/running: {e}");
-                    Some(e)
-                }
-            },
-            Err(e) => {
-                info!("Unable to query service(s) '{services_list}': {e}");
-                Some(e)
-            }
+        let result = if check.system_running {
+            query_system_state()
+        } else {
+            Ok(())
+        };
+        let error = match result.and_then(|()| query_services(check, &services_list)) {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
         };
         thread::sleep(Duration::from_millis(100));
         if start_time.elapsed() >= timeout_duration {
             return Err(TridentError::new(ServicingError::SystemdCheckTimeout {
                 services: services_list,
                 timeout_seconds: check.timeout_seconds,
-                last_error: error
-                    .map(|e| format!("{e:?}"))
-                    .unwrap_or_else(|| "No status retrieved".into()),
+                last_error: error,
             }));
         }
     }
 }
 
+/// Checks that the services of `check` are in good running state, when queried with systemctl
+/// status.
+fn query_services(check: &SystemdCheck, services_list: &str) -> Result<(), String> {
+    if check.systemd_services.is_empty() {
+        return Ok(());
+    }
+    let services = expand_services(&check.systemd_services).map_err(|e| {
+        info!("Unable to expand service(s) '{services_list}': {e}");
+        format!("{e:?}")
+    })?;
+    let output = Dependency::Systemctl
+        .cmd()
+        .env("SYSTEMD_IGNORE_CHROOT", "true")
+        .arg("status")
+        .args(&services)
+        .output()
+        .map_err(|e| {
+            info!("Unable to query service(s) '{services_list}': {e}");
+            format!("{e:?}")
+        })?;
+    match output.check() {
+        Ok(_) => {
+            info!(
+                "Service(s) '{}' are active/running: {}",
+                services.join(" "),
+                output.output_report()
+            );
+            Ok(())
+        }
+        Err(e) => {
+            info!("Service(s) '{services_list}' are not active/running: {e}");
+            Err(format!("{e:?}"))
+        }
+    }
+}
+
+/// Checks that the system is running according to `systemctl is-system-running`, which fails
+/// while the system is still starting, or when a unit failed.
+fn query_system_state() -> Result<(), String> {
+    let output = Dependency::Systemctl
+        .cmd()
+        .env("SYSTEMD_IGNORE_CHROOT", "true")
+        .arg("is-system-running")
+        .output()
+        .map_err(|e| {
+            info!("Unable to query system state: {e}");
+            format!("{e:?}")
+        })?;
+    let state = output.output();
+    let state = state.trim();
+    if state == "running" {
+        info!("System is running");
+        Ok(())
+    } else {
+        info!("System is not running: {state}");
+        Err(format!("System is {state}"))
+    }
+}
+
+/// Returns `services` with the names that are glob patterns replaced by the names of the units
+/// known to systemd that match them. Fails if a pattern matches no unit.
+fn expand_services(services: &[String]) -> Result<Vec<String>, Error> {
+    let mut expanded = Vec::new();
+    for service in services {
+        if !service.contains(['*', '?', '[']) {
+            expanded.push(service.clone());
+            continue;
+        }
+        let units = Dependency::Systemctl
+            .cmd()
+            .env("SYSTEMD_IGNORE_CHROOT", "true")
+            .args(["list-units", "--all", "--plain", "--no-legend", "--full"])
+            .arg(service)
+            .output_and_check()
+            .with_context(|| format!("Failed to list units matching '{service}'"))?;
+        let units = parse_unit_list(&units);
+        if units.is_empty() {
+            bail!("No unit matches '{service}'");
+        }
+        debug!("Expanded '{service}' to '{}'", units.join(" "));
+        expanded.extend(units);
+    }
+    Ok(expanded)
+}
+
+/// Returns the names of the units in `list`, the output of `systemctl list-units --plain
+/// --no-legend`.
+fn parse_unit_list(list: &str) -> Vec<String> {
+    list.lines()
+        // Units that are not found or failed may be marked with a leading bullet.
+        .filter_map(|line| line.trim_start_matches('●').split_whitespace().next())
+        .map(String::from)
+        .collect()
+}
+
+/// Sends the request of the HTTP check and verifies that the response has the expected status
+/// and, if configured, that its body contains the expected text.
+fn run_http_check(check: &HttpCheck) -> Result<(), Error> {
+    let request = &check.request;
+    debug!(
+        "Sending {:?} request to '{}' for health check '{}'",
+        request.method, request.url, check.name
+    );
+    match request.url.scheme() {
+        "http" | "https" => {}
+        scheme => bail!("Unsupported URL scheme '{scheme}' of '{}'", request.url),
+    }
+    let client = Client::builder()
+        .timeout(Duration::from_secs(request.timeout_seconds))
+        .danger_accept_invalid_certs(request.skip_tls_verify)
+        .build()
+        .context("Failed to create HTTP client")?;
+    let method = match request.method {
+        HttpMethod::Get => Method::GET,
+        HttpMethod::Head => Method::HEAD,
+        HttpMethod::Post => Method::POST,
+    };
+    let response = client
+        .request(method, request.url.clone())
+        .send()
+        .with_context(|| format!("Failed to send request to '{}'", request.url))?;
+
+    let status = response.status();
+    if status.as_u16() != request.expected_status {
+        bail!(
+            "Request to '{}' returned status {status}, expected {}",
+            request.url,
+            request.expected_status
+        );
+    }
+    if let Some(expected) = &request.body_contains {
+        let body = response
+            .text()
+            .with_context(|| format!("Failed to read response from '{}'", request.url))?;
+        if !body.contains(expected.as_str()) {
+            bail!(
+                "Response from '{}' does not contain '{expected}'",
+                request.url
+            );
+        }
+    }
+    info!("'{}' returned status {status}", request.url);
+    Ok(())
+}
+
+/// Opens a TCP connection to the port of the TCP check, trying each address its host resolves to
+/// until one accepts the connection.
+fn run_tcp_check(check: &TcpCheck) -> Result<(), Error> {
+    let target = &check.target;
+    let endpoint = format!("{}:{}", target.host, target.port);
+    debug!(
+        "Connecting to '{endpoint}' for health check '{}'",
+        check.name
+    );
+    let timeout = Duration::from_secs(target.timeout_seconds);
+    let mut last_error = None;
+    for address in (target.host.as_str(), target.port)
+        .to_socket_addrs()
+        .with_context(|| format!("Failed to resolve '{endpoint}'"))?
+    {
+        match TcpStream::connect_timeout(&address, timeout) {
+            Ok(_) => {
+                info!("'{endpoint}' accepted a connection on {address}");
+                return Ok(());
+            }
+            Err(e) => {
+                debug!("Failed to connect to '{endpoint}' on {address}: {e}");
+                last_error = Some(e);
+            }
+        }
+    }
+    match last_error {
+        Some(e) => Err(e).with_context(|| format!("Failed to connect to '{endpoint}'")),
+        None => bail!("'{endpoint}' did not resolve to any address"),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+
+    use trident_api::config::{HttpCheckRequest, TcpCheckTarget};
+    use url::Url;
+
+    #[test]
+    fn test_health_check_report_failures() {
+        let result = |name: &str, error: Option<&str>| HealthCheckResult {
+            name: name.into(),
+            kind: HealthCheckKind::Script,
+            success: error.is_none(),
+            severity: CheckSeverity::Fail,
+            duration_seconds: 0.0,
+            exit_code: None,
+            output: None,
+            error: error.map(Into::into),
+        };
+        let report = HealthCheckReport {
+            checks: vec![
+                result("first", None),
+                result("second", Some("failed")),
+                HealthCheckResult {
+                    severity: CheckSeverity::Warn,
+                    ..result("telemetry", Some("not running"))
+                },
+                result("third", Some("also failed")),
+            ],
+        };
+
+        assert_eq!(
+            report
+                .failures()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["second", "third"]
+        );
+        assert_eq!(
+            report.failure_details(),
+            "second: \"failed\"\nthird: \"also failed\""
+        );
+        assert_eq!(
+            report
+                .warnings()
+                .map(HealthCheckResult::details)
+                .collect::<Vec<_>>(),
+            vec!["telemetry: \"not running\""]
+        );
+    }
+
+    #[test]
+    fn test_check_extensions_skipped() {
+        let mut ctx = EngineContext {
+            servicing_type: ServicingType::AbUpdate,
+            ..Default::default()
+        };
+        // Without extension images, there is nothing to check.
+        assert!(check_extensions(&ctx).is_none());
+
+        // Extension images merged by a runtime update are checked when they are merged.
+        ctx.spec
+            .os
+            .sysexts
+            .push(trident_api::config::Extension::new(
+                url::Url::parse("https://example.com/docker.raw").unwrap(),
+                trident_api::primitives::hash::Sha384Hash::from("a".repeat(96)),
+            ));
+        ctx.servicing_type = ServicingType::HotPatch;
+        assert!(check_extensions(&ctx).is_none());
+    }
+
     #[test]
     fn test_run_systemd_check() {
         let mut check = SystemdCheck {
             name: "test-check".into(),
             systemd_services: vec!["nonexistent-service".into()],
+            system_running: false,
             timeout_seconds: 0,
+            retry: Default::default(),
+            severity: Default::default(),
             run_on: vec![],
         };
 
@@ -195,4 +1074,305 @@ mod tests {
             "Expected error message to contain 'Unit could not be found' error"
         );
     }
+
+    /// Serves `response` to each of `count` connections on a local port, returning the URL of
+    /// the server.
+    fn serve(response: &'static str, count: usize) -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!(
+            "http://{}/healthz",
+            listener.local_addr().unwrap()
+        ))
+        .unwrap();
+        thread::spawn(move || {
+            for stream in listener.incoming().take(count) {
+                let mut stream = stream.unwrap();
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request).unwrap();
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_run_http_check() {
+        let check = |url: Url, expected_status: u16, body_contains: Option<&str>| HttpCheck {
+            name: "api".into(),
+            request: HttpCheckRequest {
+                url,
+                method: HttpMethod::Get,
+                expected_status,
+                body_contains: body_contains.map(Into::into),
+                skip_tls_verify: false,
+                timeout_seconds: 5,
+            },
+            retry: Default::default(),
+            severity: Default::default(),
+            run_on: vec![],
+        };
+
+        let url = serve(
+            "HTTP/1.1 200 OK\r\nContent-Length: 7\r\nConnection: close\r\n\r\nserving",
+            3,
+        );
+        run_http_check(&check(url.clone(), 200, Some("serv"))).unwrap();
+
+        let e = run_http_check(&check(url.clone(), 200, Some("ready"))).unwrap_err();
+        assert!(e.to_string().contains("does not contain 'ready'"), "{e:?}");
+
+        let e = run_http_check(&check(url, 204, None)).unwrap_err();
+        assert!(
+            e.to_string()
+                .contains("returned status 200 OK, expected 204"),
+            "{e:?}"
+        );
+
+        let e =
+            run_http_check(&check(Url::parse("ftp://localhost/").unwrap(), 200, None)).unwrap_err();
+        assert!(
+            e.to_string().contains("Unsupported URL scheme 'ftp'"),
+            "{e:?}"
+        );
+    }
+
+    #[test]
+    fn test_run_tcp_check() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let check = TcpCheck {
+            name: "listener".into(),
+            target: TcpCheckTarget {
+                host: "127.0.0.1".into(),
+                port,
+                timeout_seconds: 1,
+            },
+            retry: Default::default(),
+            severity: Default::default(),
+            run_on: vec![],
+        };
+        run_tcp_check(&check).unwrap();
+
+        // Once the listener is closed, the port refuses connections.
+        drop(listener);
+        let e = run_tcp_check(&check).unwrap_err();
+        assert!(
+            e.to_string()
+                .contains(&format!("Failed to connect to '127.0.0.1:{port}'")),
+            "{e:?}"
+        );
+    }
+
+    #[test]
+    fn test_parse_unit_list() {
+        let list = "myapp-1.service loaded active running My app 1\n\
+            ● myapp-2.service loaded failed failed My app 2\n";
+        assert_eq!(
+            parse_unit_list(list),
+            vec!["myapp-1.service", "myapp-2.service"]
+        );
+        assert!(parse_unit_list("").is_empty());
+    }
+
+    #[test]
+    fn test_output_excerpt() {
+        assert_eq!(output_excerpt(""), None);
+        assert_eq!(output_excerpt("\n"), None);
+        assert_eq!(
+            output_excerpt("starting\nfailed\n").unwrap(),
+            "starting\nfailed"
+        );
+
+        // Only the end of long outputs is kept, without splitting characters.
+        let output = format!("é{}", "a".repeat(MAX_OUTPUT_EXCERPT_BYTES - 1));
+        assert_eq!(
+            output_excerpt(&output).unwrap(),
+            format!("[...]{}", "a".repeat(MAX_OUTPUT_EXCERPT_BYTES - 1))
+        );
+    }
+
+    #[test]
+    fn test_health_check_outcome() {
+        let result = HealthCheckResult {
+            name: "api".into(),
+            kind: HealthCheckKind::Script,
+            success: false,
+            severity: CheckSeverity::Warn,
+            duration_seconds: 1.25,
+            exit_code: Some(2),
+            output: Some("not ready".into()),
+            error: Some("Script 'api' failed: script exited with status: 2".into()),
+        };
+        assert_eq!(
+            result.outcome(),
+            HealthCheckOutcome {
+                name: "api".into(),
+                kind: HealthCheckKind::Script,
+                verdict: HealthCheckVerdict::Warn,
+                duration_millis: 1250,
+                exit_code: Some(2),
+                output: Some("not ready".into()),
+                error: Some("Script 'api' failed: script exited with status: 2".into()),
+            }
+        );
+        assert_eq!(
+            result.details(),
+            "api: \"Script 'api' failed: script exited with status: 2\"\nOutput of 'api':\nnot ready"
+        );
+
+        let result = HealthCheckResult {
+            success: true,
+            ..result
+        };
+        assert_eq!(result.verdict(), HealthCheckVerdict::Pass);
+    }
+
+    #[test]
+    fn test_expand_script_patterns() {
+        let root = tempfile::tempdir().unwrap();
+        let checks_dir = root.path().join("usr/lib/trident/checks.d");
+        std::fs::create_dir_all(checks_dir.join("subdir")).unwrap();
+        std::fs::write(checks_dir.join("20-network.sh"), "").unwrap();
+        std::fs::write(checks_dir.join("10-disk.sh"), "").unwrap();
+
+        let script = |name: &str, path: &str| {
+            Check::Script(Script {
+                name: name.into(),
+                source: ScriptSource::Path(path.into()),
+                timeout_seconds: Some(30),
+                ..Default::default()
+            })
+        };
+        let checks = vec![
+            script("image-checks", "/usr/lib/trident/checks.d/*"),
+            script("plain", "/etc/check.sh"),
+            script("missing", "/usr/lib/other/*.sh"),
+        ];
+        assert_eq!(
+            expand_script_patterns(checks, root.path()),
+            vec![
+                script(
+                    "image-checks/10-disk.sh",
+                    "/usr/lib/trident/checks.d/10-disk.sh"
+                ),
+                script(
+                    "image-checks/20-network.sh",
+                    "/usr/lib/trident/checks.d/20-network.sh"
+                ),
+                script("plain", "/etc/check.sh"),
+                script("missing", "/usr/lib/other/*.sh"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_record_history() {
+        let outcome = |verdict| HealthCheckOutcome {
+            name: "check".into(),
+            kind: HealthCheckKind::SystemdCheck,
+            verdict,
+            duration_millis: 10,
+            exit_code: None,
+            output: None,
+            error: None,
+        };
+        let boot = |index: usize| Some(format!("boot-{index}"));
+        let mut history = Vec::new();
+
+        // Runs of the same boot are merged.
+        record_history(
+            &mut history,
+            boot(0),
+            vec![outcome(HealthCheckVerdict::Fail)],
+            Utc::now(),
+        );
+        record_history(
+            &mut history,
+            boot(0),
+            vec![outcome(HealthCheckVerdict::Pass)],
+            Utc::now(),
+        );
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].runs, 2);
+        assert_eq!(history[0].failed_runs, 1);
+        assert_eq!(history[0].checks, vec![outcome(HealthCheckVerdict::Pass)]);
+
+        // Runs without a boot ID are never merged.
+        record_history(&mut history, None, Vec::new(), Utc::now());
+        record_history(&mut history, None, Vec::new(), Utc::now());
+        assert_eq!(history.len(), 3);
+
+        // Only the last boots are kept.
+        for index in 1..=MAX_HEALTH_CHECK_HISTORY_BOOTS {
+            record_history(&mut history, boot(index), Vec::new(), Utc::now());
+        }
+        assert_eq!(history.len(), MAX_HEALTH_CHECK_HISTORY_BOOTS);
+        assert_eq!(history[0].boot_id, boot(1));
+        assert_eq!(
+            history.last().unwrap().boot_id,
+            boot(MAX_HEALTH_CHECK_HISTORY_BOOTS)
+        );
+    }
+
+    #[test]
+    fn test_add_script_environment() {
+        let mut host_status = HostStatus {
+            previous_os_version: Some("3.0.20250101".into()),
+            ..Default::default()
+        };
+        host_status.spec.health.environment_variables = [
+            ("REGION".to_string(), "west".to_string()),
+            ("TIER".to_string(), "prod".to_string()),
+        ]
+        .into();
+        let mut checks = vec![
+            Check::Script(Script {
+                name: "script".into(),
+                environment_variables: [("TIER".to_string(), "canary".to_string())].into(),
+                ..Default::default()
+            }),
+            Check::SystemdCheck(SystemdCheck::default()),
+        ];
+
+        add_script_environment(&mut checks, &host_status);
+        let Check::Script(script) = &checks[0] else {
+            unreachable!()
+        };
+        assert_eq!(script.environment_variables["REGION"], "west");
+        assert_eq!(script.environment_variables["TIER"], "canary");
+        assert_eq!(
+            script.environment_variables["PREVIOUS_OS_VERSION"],
+            "3.0.20250101"
+        );
+    }
+
+    #[test]
+    fn test_cut_to_deadline() {
+        let script = Check::Script(Script {
+            name: "script".into(),
+            ..Default::default()
+        });
+        let systemd_check = Check::SystemdCheck(SystemdCheck {
+            timeout_seconds: 60,
+            ..Default::default()
+        });
+        let timeout = |check: Check| match check {
+            Check::Script(script) => script.timeout_seconds.unwrap() as usize,
+            Check::SystemdCheck(systemd_check) => systemd_check.timeout_seconds,
+            _ => unreachable!(),
+        };
+
+        // Without a deadline, only the default timeout of scripts applies.
+        assert_eq!(
+            timeout(cut_to_deadline(&script, None).unwrap()),
+            DEFAULT_SCRIPT_CHECK_TIMEOUT_SECONDS as usize
+        );
+        assert_eq!(timeout(cut_to_deadline(&systemd_check, None).unwrap()), 60);
+
+        let deadline = Some(Instant::now() + Duration::from_secs(30));
+        assert!(timeout(cut_to_deadline(&script, deadline).unwrap()) <= 30);
+        assert!(timeout(cut_to_deadline(&systemd_check, deadline).unwrap()) <= 30);
+
+        cut_to_deadline(&script, Some(Instant::now())).unwrap_err();
+    }
 }
@@ -0,0 +1,226 @@
+use std::{
+    fs,
+    net::{Ipv4Addr, Ipv6Addr, ToSocketAddrs},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Error};
+use log::{debug, info};
+use reqwest::blocking::Client;
+use url::Url;
+
+use osutils::dependencies::Dependency;
+use trident_api::config::NetworkCheckCriteria;
+
+/// IPv4 routing table of the kernel.
+const IPV4_ROUTES_PATH: &str = "/proc/net/route";
+
+/// IPv6 routing table of the kernel.
+const IPV6_ROUTES_PATH: &str = "/proc/net/ipv6_route";
+
+/// Flag of the routes that go through a gateway, `RTF_GATEWAY`.
+const ROUTE_FLAG_GATEWAY: u32 = 0x2;
+
+/// Checks that the network stack meets `criteria`: a default gateway answers, the names resolve,
+/// and the HTTPS probe answers.
+pub(super) fn check(criteria: &NetworkCheckCriteria) -> Result<(), Error> {
+    let timeout = Duration::from_secs(criteria.timeout_seconds);
+    let mut failures = Vec::new();
+
+    if criteria.default_gateway {
+        if let Err(e) = check_default_gateway(timeout) {
+            failures.push(format!("{e:#}"));
+        }
+    }
+    for name in &criteria.dns_names {
+        if let Err(e) = resolve(name, timeout) {
+            failures.push(format!("{e:#}"));
+        }
+    }
+    if let Some(url) = &criteria.https_probe {
+        if let Err(e) = probe(url, timeout) {
+            failures.push(format!("{e:#}"));
+        }
+    }
+
+    if !failures.is_empty() {
+        bail!("{}", failures.join("\n"));
+    }
+    Ok(())
+}
+
+/// Pings the default gateways until one answers within `timeout`.
+fn check_default_gateway(timeout: Duration) -> Result<(), Error> {
+    let mut gateways = read_routes(IPV4_ROUTES_PATH)
+        .map(|routes| parse_ipv4_default_gateways(&routes))
+        .unwrap_or_default();
+    gateways.extend(
+        read_routes(IPV6_ROUTES_PATH)
+            .map(|routes| parse_ipv6_default_gateways(&routes))
+            .unwrap_or_default(),
+    );
+    if gateways.is_empty() {
+        bail!("No default gateway is configured");
+    }
+
+    for gateway in &gateways {
+        debug!("Pinging default gateway '{gateway}'");
+        let output = Dependency::Ping
+            .cmd()
+            .args(["-c", "1", "-w"])
+            .arg(timeout.as_secs().max(1).to_string())
+            .arg(gateway)
+            .output()
+            .with_context(|| format!("Failed to ping default gateway '{gateway}'"))?;
+        if output.success() {
+            info!("Default gateway '{gateway}' is reachable");
+            return Ok(());
+        }
+        debug!("Default gateway '{gateway}' did not answer");
+    }
+    bail!(
+        "Default gateway(s) '{}' did not answer within {} seconds",
+        gateways.join("', '"),
+        timeout.as_secs()
+    )
+}
+
+/// Returns the content of the routing table at `path`, or `None` if it cannot be read, e.g. the
+/// IPv6 routing table when IPv6 is disabled.
+fn read_routes(path: &str) -> Option<String> {
+    fs::read_to_string(path)
+        .inspect_err(|e| debug!("Failed to read '{path}': {e}"))
+        .ok()
+}
+
+/// Returns the gateways of the default routes of `routes`, the content of `/proc/net/route`.
+fn parse_ipv4_default_gateways(routes: &str) -> Vec<String> {
+    routes
+        .lines()
+        .skip(1)
+        .filter_map(|route| {
+            // Iface, Destination, Gateway, Flags, RefCnt, Use, Metric, Mask, ...
+            let fields = route.split_whitespace().collect::<Vec<_>>();
+            let flags = u32::from_str_radix(fields.get(3)?, 16).ok()?;
+            if *fields.get(1)? != "00000000"
+                || *fields.get(7)? != "00000000"
+                || flags & ROUTE_FLAG_GATEWAY == 0
+            {
+                return None;
+            }
+            // Addresses are printed as integers in the byte order of the host.
+            let gateway = u32::from_str_radix(fields.get(2)?, 16).ok()?;
+            Some(Ipv4Addr::from(gateway.to_ne_bytes()).to_string())
+        })
+        .collect()
+}
+
+/// Returns the gateways of the default routes of `routes`, the content of
+/// `/proc/net/ipv6_route`. Link-local gateways are scoped to the interface of their route.
+fn parse_ipv6_default_gateways(routes: &str) -> Vec<String> {
+    routes
+        .lines()
+        .filter_map(|route| {
+            // Destination, prefix length, source, prefix length, next hop, metric, reference
+            // count, use count, flags, interface.
+            let fields = route.split_whitespace().collect::<Vec<_>>();
+            let flags = u32::from_str_radix(fields.get(8)?, 16).ok()?;
+            if u128::from_str_radix(fields.first()?, 16).ok()? != 0
+                || *fields.get(1)? != "00"
+                || flags & ROUTE_FLAG_GATEWAY == 0
+            {
+                return None;
+            }
+            let gateway = Ipv6Addr::from(u128::from_str_radix(fields.get(4)?, 16).ok()?);
+            if gateway.segments()[0] & 0xffc0 == 0xfe80 {
+                Some(format!("{gateway}%{}", fields.get(9)?))
+            } else {
+                Some(gateway.to_string())
+            }
+        })
+        .collect()
+}
+
+/// Resolves `name` to at least one address within `timeout`.
+fn resolve(name: &str, timeout: Duration) -> Result<(), Error> {
+    debug!("Resolving '{name}'");
+    // The resolver of the C library cannot be cut short, so it runs in a thread that is left
+    // behind when it does not complete in time.
+    let (tx, rx) = mpsc::channel();
+    let host = name.to_string();
+    thread::spawn(move || {
+        let _ = tx.send(
+            (host.as_str(), 0)
+                .to_socket_addrs()
+                .map(|addresses| addresses.count()),
+        );
+    });
+    match rx.recv_timeout(timeout) {
+        Ok(Ok(0)) => bail!("'{name}' did not resolve to any address"),
+        Ok(Ok(count)) => {
+            info!("'{name}' resolved to {count} address(es)");
+            Ok(())
+        }
+        Ok(Err(e)) => Err(e).with_context(|| format!("Failed to resolve '{name}'")),
+        Err(_) => bail!(
+            "Resolution of '{name}' did not complete within {} seconds",
+            timeout.as_secs()
+        ),
+    }
+}
+
+/// Sends a request to `url` and waits for a response, whatever its status.
+fn probe(url: &Url, timeout: Duration) -> Result<(), Error> {
+    if url.scheme() != "https" {
+        bail!("Unsupported URL scheme '{}' of '{url}'", url.scheme());
+    }
+    debug!("Probing '{url}'");
+    let client = Client::builder()
+        .timeout(timeout)
+        .build()
+        .context("Failed to create HTTP client")?;
+    let response = client
+        .get(url.clone())
+        .send()
+        .with_context(|| format!("Failed to send request to '{url}'"))?;
+    info!("'{url}' answered with status {}", response.status());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ipv4_default_gateways() {
+        let routes = "\
+Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+eth0\t00000000\t0100A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0
+eth0\t0000A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0
+wg0\t00000000\t00000000\t0001\t0\t0\t200\t00000000\t0\t0\t0
+";
+        let expected = if cfg!(target_endian = "little") {
+            vec!["192.168.0.1".to_string()]
+        } else {
+            vec!["1.0.168.192".to_string()]
+        };
+        assert_eq!(parse_ipv4_default_gateways(routes), expected);
+        assert!(parse_ipv4_default_gateways("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_ipv6_default_gateways() {
+        let routes = "\
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 fe800000000000000000000000000001 00000400 00000001 00000000 00000003     eth0
+20010db8000000000000000000000000 40 00000000000000000000000000000000 00 00000000000000000000000000000000 00000100 00000001 00000000 00000001     eth0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 20010db8000000000000000000000001 00000400 00000001 00000000 00000003     eth1
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 00000000000000000000000000000000 ffffffff 00000001 00000000 00200200       lo
+";
+        assert_eq!(
+            parse_ipv6_default_gateways(routes),
+            vec!["fe80::1%eth0".to_string(), "2001:db8::1".to_string()]
+        );
+    }
+}
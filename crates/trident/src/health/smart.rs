@@ -0,0 +1,100 @@
+use anyhow::{bail, Error};
+use log::info;
+
+use osutils::smartctl::{self, SmartHealth};
+use trident_api::config::{DiskHealthCheckCriteria, HostConfiguration};
+
+/// Checks that the disks of `criteria`, among the disks of `host_config`, are healthy according
+/// to SMART.
+pub(super) fn check(
+    criteria: &DiskHealthCheckCriteria,
+    host_config: &HostConfiguration,
+) -> Result<(), Error> {
+    let disks = host_config
+        .storage
+        .disks
+        .iter()
+        .filter(|disk| criteria.disks.is_empty() || criteria.disks.contains(&disk.id))
+        .collect::<Vec<_>>();
+    if disks.is_empty() {
+        bail!("No disk to check, as 'storage.disks' is empty");
+    }
+
+    let mut failures = Vec::new();
+    for disk in disks {
+        match smartctl::health(&disk.device) {
+            Ok(Some(health)) => {
+                if let Some(failure) = assess(&disk.id, &health, criteria.fail_on_past_failures) {
+                    failures.push(failure);
+                } else {
+                    info!("Disk '{}' is healthy", disk.id);
+                }
+            }
+            Ok(None) => info!("Skipped disk '{}', which does not support SMART", disk.id),
+            Err(e) => failures.push(format!("{e:#}")),
+        }
+    }
+
+    if !failures.is_empty() {
+        bail!("{}", failures.join("\n"));
+    }
+    Ok(())
+}
+
+/// Returns why the disk `disk_id` with SMART health `health` is not healthy, if it is not.
+fn assess(disk_id: &str, health: &SmartHealth, fail_on_past_failures: bool) -> Option<String> {
+    let state = if health.failing {
+        "is failing"
+    } else if health.past_failures && fail_on_past_failures {
+        "failed in the past"
+    } else {
+        return None;
+    };
+    if health.failed_attributes.is_empty() {
+        Some(format!("Disk '{disk_id}' {state} according to SMART"))
+    } else {
+        Some(format!(
+            "Disk '{disk_id}' {state} according to SMART, failed attributes: {}",
+            health.failed_attributes.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assess() {
+        let health =
+            |failing: bool, past_failures: bool, failed_attributes: Vec<&str>| SmartHealth {
+                failing,
+                past_failures,
+                failed_attributes: failed_attributes.into_iter().map(String::from).collect(),
+            };
+        assert_eq!(assess("os", &health(false, false, vec![]), true), None);
+        assert_eq!(
+            assess(
+                "os",
+                &health(true, false, vec!["Reallocated_Sector_Ct (FAILING_NOW)"]),
+                false
+            )
+            .unwrap(),
+            "Disk 'os' is failing according to SMART, failed attributes: Reallocated_Sector_Ct \
+            (FAILING_NOW)"
+        );
+
+        // Past failures only fail the check when configured to.
+        let past = health(false, true, vec!["Reallocated_Event_Count (In_the_past)"]);
+        assert_eq!(assess("os", &past, false), None);
+        assert_eq!(
+            assess("os", &past, true).unwrap(),
+            "Disk 'os' failed in the past according to SMART, failed attributes: \
+            Reallocated_Event_Count (In_the_past)"
+        );
+        assert_eq!(
+            assess("os", &health(true, false, vec![]), false).unwrap(),
+            "Disk 'os' is failing according to SMART"
+        );
+    }
+}
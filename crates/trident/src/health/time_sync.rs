@@ -0,0 +1,65 @@
+use std::{
+    io, mem, thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Error};
+use log::{debug, info};
+
+use trident_api::config::TimeSyncCheckCriteria;
+
+/// Interval between two queries of the state of the system clock.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Waits for the system clock to be synchronized. Fails when it is not synchronized within the
+/// timeout.
+pub(super) fn check(criteria: &TimeSyncCheckCriteria) -> Result<(), Error> {
+    debug!("Waiting for the system clock to be synchronized");
+    let start_time = Instant::now();
+    let timeout = Duration::from_secs(criteria.timeout_seconds);
+    loop {
+        if is_synchronized(clock_status()?) {
+            info!("System clock is synchronized");
+            return Ok(());
+        }
+        if start_time.elapsed() >= timeout {
+            bail!(
+                "System clock was not synchronized after {} seconds, check that chronyd or \
+                systemd-timesyncd is running and can reach a time server",
+                criteria.timeout_seconds
+            );
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Returns the status flags of the system clock kept by the kernel.
+fn clock_status() -> Result<libc::c_int, Error> {
+    // SAFETY: timex is plain data, for which all zeroes is valid. With no mode set, adjtimex only
+    // reads the state of the clock into it.
+    let mut timex: libc::timex = unsafe { mem::zeroed() };
+    if unsafe { libc::adjtimex(&mut timex) } < 0 {
+        return Err(io::Error::last_os_error()).context("Failed to query state of system clock");
+    }
+    Ok(timex.status)
+}
+
+/// Returns whether the system clock is synchronized according to its status flags `status`.
+/// Time synchronization daemons, such as chronyd and systemd-timesyncd, clear the unsynchronized
+/// flag once they synchronized the clock.
+fn is_synchronized(status: libc::c_int) -> bool {
+    status & libc::STA_UNSYNC == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_synchronized() {
+        assert!(is_synchronized(0));
+        assert!(is_synchronized(libc::STA_PLL));
+        assert!(!is_synchronized(libc::STA_UNSYNC));
+        assert!(!is_synchronized(libc::STA_PLL | libc::STA_UNSYNC));
+    }
+}
@@ -0,0 +1,252 @@
+//! Stable identity of the host in the fleet.
+//!
+//! When `trident.registrationUrl` is configured, Trident generates a UUID and a key pair the first
+//! time the target OS boots, and registers them with the fleet endpoint. The identity is kept in
+//! the Host Status, so it survives A/B updates, and the ID is attached to every message Trident
+//! sends: orchestrator reports, metrics and the Host Status itself.
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Error};
+use log::{debug, info, warn};
+use openssl::pkey::{PKey, Private};
+use serde::Serialize;
+use tempfile::NamedTempFile;
+use uuid::Uuid;
+
+use trident_api::{
+    config::EndpointTls,
+    error::{ReportError, ServicingError, TridentError},
+    status::{HostIdentity, HostStatus},
+};
+
+use crate::{datastore::DataStore, endpoint::HttpEndpoint};
+
+/// Name of the file holding the private key of the host, next to the datastore.
+const PRIVATE_KEY_FILE_NAME: &str = "identity.pem";
+
+/// Timeout of the registration request.
+const REGISTRATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// ID of the host, once known, for the rest of the process.
+static HOST_ID: OnceLock<Uuid> = OnceLock::new();
+
+/// Body of the registration request.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Registration<'a> {
+    id: Uuid,
+    public_key: &'a str,
+}
+
+/// Returns the ID of the host, if it has an identity.
+pub(crate) fn host_id() -> Option<Uuid> {
+    HOST_ID.get().copied()
+}
+
+/// Makes the ID of the host in the given Host Status, if any, available to `host_id`.
+pub(crate) fn publish(host_status: &HostStatus) {
+    if let Some(identity) = &host_status.identity {
+        // The ID never changes once generated, so it is fine if it was already set.
+        let _ = HOST_ID.set(identity.id);
+    }
+}
+
+/// Generates the identity of the host if needed, and registers it with the fleet endpoint
+/// configured in the Host Configuration, if it was not registered yet.
+pub(crate) fn register(datastore: &mut DataStore) -> Result<(), TridentError> {
+    let host_status = datastore.host_status();
+    let Some(url) = host_status.spec.trident.registration_url.clone() else {
+        return Ok(());
+    };
+    let tls = host_status.spec.trident.registration_tls.clone();
+
+    let identity = match &host_status.identity {
+        Some(identity) if identity.registered => return Ok(()),
+        Some(identity) => identity.clone(),
+        None => {
+            let private_key_path = private_key_path(&host_status.spec.trident.datastore_path);
+            let identity =
+                generate(&private_key_path).structured(ServicingError::GenerateHostIdentity)?;
+            info!("Generated host identity '{}'", identity.id);
+            datastore
+                .with_host_status(|host_status| host_status.identity = Some(identity.clone()))?;
+            publish(datastore.host_status());
+            identity
+        }
+    };
+
+    send_registration(&url, tls.as_ref(), &identity)
+        .structured(ServicingError::RegisterHost { url })?;
+    datastore.with_host_status(|host_status| {
+        if let Some(identity) = &mut host_status.identity {
+            identity.registered = true;
+        }
+    })
+}
+
+/// Returns the path of the private key of the host, next to the datastore.
+fn private_key_path(datastore_path: &Path) -> PathBuf {
+    datastore_path.with_file_name(PRIVATE_KEY_FILE_NAME)
+}
+
+/// Generates a new identity, with the key pair stored at `private_key_path`. Only root can read
+/// the private key.
+///
+/// A valid key left by a previous attempt, e.g. one that failed before the identity was saved in
+/// the Host Status, is reused. Otherwise, a new key is written to a temporary file that is renamed
+/// once complete, so that an interrupted write never leaves a partial key behind.
+fn generate(private_key_path: &Path) -> Result<HostIdentity, Error> {
+    let key = match load_private_key(private_key_path) {
+        Some(key) => {
+            debug!("Reusing private key at '{}'", private_key_path.display());
+            key
+        }
+        None => {
+            let key = PKey::generate_ed25519().context("Failed to generate key pair")?;
+            write_private_key(&key, private_key_path).with_context(|| {
+                format!(
+                    "Failed to write private key to '{}'",
+                    private_key_path.display()
+                )
+            })?;
+            key
+        }
+    };
+    let public_key = String::from_utf8(
+        key.public_key_to_pem()
+            .context("Failed to encode public key")?,
+    )
+    .context("Public key is not valid UTF-8")?;
+
+    Ok(HostIdentity {
+        id: Uuid::new_v4(),
+        public_key,
+        registered: false,
+    })
+}
+
+/// Returns the private key at `path`, if the file exists and holds a valid key.
+fn load_private_key(path: &Path) -> Option<PKey<Private>> {
+    let pem = fs::read(path).ok()?;
+    match PKey::private_key_from_pem(&pem) {
+        Ok(key) => Some(key),
+        Err(e) => {
+            warn!("Ignoring invalid private key at '{}': {e}", path.display());
+            None
+        }
+    }
+}
+
+/// Atomically writes `key` to `path`, readable only by root.
+fn write_private_key(key: &PKey<Private>, path: &Path) -> Result<(), Error> {
+    let parent = path
+        .parent()
+        .context("Private key path has no parent directory")?;
+    fs::create_dir_all(parent)
+        .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+
+    // Temporary files are created with mode 0600.
+    let mut file = NamedTempFile::new_in(parent).context("Failed to create temporary file")?;
+    file.write_all(&key.private_key_to_pem_pkcs8()?)?;
+    file.as_file().sync_all()?;
+    file.persist(path)?;
+    Ok(())
+}
+
+/// Sends the ID and public key of the host to the fleet endpoint.
+fn send_registration(
+    url: &str,
+    tls: Option<&EndpointTls>,
+    identity: &HostIdentity,
+) -> Result<(), Error> {
+    debug!("Registering host '{}' with '{url}'", identity.id);
+    let response = HttpEndpoint::new(url.into(), tls, Some(REGISTRATION_TIMEOUT))?
+        .post_json(serde_json::to_vec(&Registration {
+            id: identity.id,
+            public_key: &identity.public_key,
+        })?)
+        .context("Failed to send registration request")?;
+
+    if !response.status().is_success() {
+        bail!("Fleet endpoint returned status {}", response.status());
+    }
+
+    info!("Registered host '{}' with '{url}'", identity.id);
+    Ok(())
+}
+
+/// Registers the host, logging failures instead of returning them. Registration is optional and
+/// is retried the next time Trident commits, so it must not fail servicing.
+pub(crate) fn try_register(datastore: &mut DataStore) {
+    if let Err(e) = register(datastore) {
+        warn!("Failed to register host, will retry on next commit: {e:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_private_key_path() {
+        assert_eq!(
+            private_key_path(Path::new("/var/lib/trident/datastore.sqlite")),
+            PathBuf::from("/var/lib/trident/identity.pem")
+        );
+    }
+
+    #[test]
+    fn test_generate() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("trident").join(PRIVATE_KEY_FILE_NAME);
+
+        let identity = generate(&path).unwrap();
+        assert!(!identity.registered);
+        assert!(identity
+            .public_key
+            .starts_with("-----BEGIN PUBLIC KEY-----"));
+
+        let private_key = fs::read(&path).unwrap();
+        let key = PKey::private_key_from_pem(&private_key).unwrap();
+        assert_eq!(
+            key.public_key_to_pem().unwrap(),
+            identity.public_key.as_bytes()
+        );
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+
+        // A valid private key left by a previous attempt is reused
+        let reused = generate(&path).unwrap();
+        assert_ne!(reused.id, identity.id);
+        assert_eq!(reused.public_key, identity.public_key);
+
+        // An invalid private key is replaced
+        fs::write(&path, "not a key").unwrap();
+        let replaced = generate(&path).unwrap();
+        assert_ne!(replaced.public_key, identity.public_key);
+        PKey::private_key_from_pem(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+    }
+
+    #[test]
+    fn test_register_without_url() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut datastore = DataStore::open_or_create(&temp_dir.path().join("db.sqlite")).unwrap();
+        register(&mut datastore).unwrap();
+        assert_eq!(datastore.host_status().identity, None);
+    }
+}
@@ -0,0 +1,93 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use osutils::read_only::{efibootmgr, lsblk};
+use trident_api::{
+    constants::{VALID_CONFEXT_DIRECTORIES, VALID_SYSEXT_DIRECTORIES},
+    error::{InitializationError, ReportError, TridentError},
+    status::HostStatus,
+};
+
+use crate::datastore::DataStore;
+
+use super::{BootEntries, ReadOnlyHost};
+
+/// Extension of sysext and confext images.
+const EXTENSION_IMAGE_EXTENSION: &str = "raw";
+
+/// The running host, queried through read-only osutils calls only.
+///
+/// This is the single place where the inspect module reaches the host. It only reaches osutils
+/// through `osutils::read_only`, which does not export any function that modifies the system.
+pub(crate) struct SystemHost {
+    datastore_path: PathBuf,
+}
+
+impl SystemHost {
+    pub(crate) fn new(datastore_path: &Path) -> Self {
+        Self {
+            datastore_path: datastore_path.to_path_buf(),
+        }
+    }
+}
+
+impl ReadOnlyHost for SystemHost {
+    fn block_devices(&self) -> Result<Vec<lsblk::BlockDevice>, TridentError> {
+        lsblk::list().structured(InitializationError::InspectHost {
+            section: "block devices".into(),
+        })
+    }
+
+    fn boot_entries(&self) -> Result<BootEntries, TridentError> {
+        let output = efibootmgr::list_and_parse_bootmgr_entries().structured(
+            InitializationError::InspectHost {
+                section: "boot entries".into(),
+            },
+        )?;
+
+        Ok(BootEntries {
+            boot_current: output.boot_current,
+            boot_next: output.boot_next,
+            boot_order: output.boot_order,
+            entries: output
+                .boot_entries
+                .into_iter()
+                .map(|entry| (entry.id, entry.label))
+                .collect(),
+        })
+    }
+
+    fn extension_images(&self) -> Result<Vec<PathBuf>, TridentError> {
+        let mut images = Vec::new();
+        for directory in VALID_SYSEXT_DIRECTORIES
+            .iter()
+            .chain(VALID_CONFEXT_DIRECTORIES.iter())
+            .map(Path::new)
+            .filter(|directory| directory.is_dir())
+        {
+            let entries = fs::read_dir(directory).structured(InitializationError::InspectHost {
+                section: format!("extension images in '{}'", directory.display()),
+            })?;
+            images.extend(
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| {
+                        path.extension()
+                            .is_some_and(|extension| extension == EXTENSION_IMAGE_EXTENSION)
+                    }),
+            );
+        }
+        images.sort();
+
+        Ok(images)
+    }
+
+    fn host_status(&self) -> Result<HostStatus, TridentError> {
+        Ok(DataStore::open_read_only(&self.datastore_path)?
+            .host_status()
+            .clone())
+    }
+}
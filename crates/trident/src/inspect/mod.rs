@@ -0,0 +1,186 @@
+//! Read-only inspection of the host.
+//!
+//! All state is read through the [`ReadOnlyHost`] trait, which only exposes queries. Code in this
+//! module must not call into osutils or the datastore directly: the only implementation of the
+//! trait that touches the host lives in the `facade` submodule, which by convention only imports
+//! osutils through the `osutils::read_only` re-exports. Nothing enforces this, so reviewers of the
+//! `facade` submodule should reject any other osutils import.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use log::{debug, warn};
+use serde::Serialize;
+
+use osutils::read_only::lsblk::BlockDevice;
+use trident_api::{error::TridentError, status::HostStatus};
+
+use crate::cli::InspectSection;
+
+mod facade;
+
+pub(crate) use facade::SystemHost;
+
+/// Non-mutating queries about the state of the host.
+pub(crate) trait ReadOnlyHost {
+    /// Returns the block devices of the host, with their partitions and mount points.
+    fn block_devices(&self) -> Result<Vec<BlockDevice>, TridentError>;
+
+    /// Returns the UEFI boot entries and boot order.
+    fn boot_entries(&self) -> Result<BootEntries, TridentError>;
+
+    /// Returns the paths of the sysext and confext images available to the host.
+    fn extension_images(&self) -> Result<Vec<PathBuf>, TridentError>;
+
+    /// Returns the Host Status stored in the datastore.
+    fn host_status(&self) -> Result<HostStatus, TridentError>;
+}
+
+/// UEFI boot configuration of the host.
+#[derive(Serialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BootEntries {
+    pub boot_current: String,
+    pub boot_next: String,
+    pub boot_order: Vec<String>,
+    pub entries: BTreeMap<String, String>,
+}
+
+/// Result of inspecting the host. Sections that were not requested are omitted; sections that
+/// could not be read are reported in `errors` instead.
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InspectReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_devices: Option<Vec<BlockDevice>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boot: Option<BootEntries>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Vec<PathBuf>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host_status: Option<HostStatus>,
+
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub errors: BTreeMap<String, String>,
+}
+
+/// Inspects the requested sections of the host. All sections are inspected when `sections` is
+/// empty.
+///
+/// Failing to read a section does not stop the inspection, the error is recorded in the report.
+pub(crate) fn inspect(host: &impl ReadOnlyHost, sections: &[InspectSection]) -> InspectReport {
+    let requested = |section| sections.is_empty() || sections.contains(&section);
+    let mut report = InspectReport::default();
+
+    let mut record = |section: InspectSection, err: TridentError| {
+        warn!("Failed to inspect {section}: {err:?}");
+        report
+            .errors
+            .insert(section.to_string(), err.kind().to_string());
+    };
+
+    let mut block_devices = None;
+    let mut boot = None;
+    let mut extensions = None;
+    let mut host_status = None;
+
+    if requested(InspectSection::Disks) {
+        debug!("Inspecting block devices");
+        match host.block_devices() {
+            Ok(devices) => block_devices = Some(devices),
+            Err(err) => record(InspectSection::Disks, err),
+        }
+    }
+
+    if requested(InspectSection::Boot) {
+        debug!("Inspecting boot entries");
+        match host.boot_entries() {
+            Ok(entries) => boot = Some(entries),
+            Err(err) => record(InspectSection::Boot, err),
+        }
+    }
+
+    if requested(InspectSection::Extensions) {
+        debug!("Inspecting extension images");
+        match host.extension_images() {
+            Ok(images) => extensions = Some(images),
+            Err(err) => record(InspectSection::Extensions, err),
+        }
+    }
+
+    if requested(InspectSection::Datastore) {
+        debug!("Inspecting datastore");
+        match host.host_status() {
+            Ok(status) => host_status = Some(status),
+            Err(err) => record(InspectSection::Datastore, err),
+        }
+    }
+
+    InspectReport {
+        block_devices,
+        boot,
+        extensions,
+        host_status,
+        ..report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use trident_api::error::InitializationError;
+
+    struct FakeHost;
+
+    impl ReadOnlyHost for FakeHost {
+        fn block_devices(&self) -> Result<Vec<BlockDevice>, TridentError> {
+            Ok(vec![BlockDevice {
+                name: "/dev/sda".into(),
+                ..Default::default()
+            }])
+        }
+
+        fn boot_entries(&self) -> Result<BootEntries, TridentError> {
+            Err(TridentError::new(InitializationError::InspectHost {
+                section: "boot entries".into(),
+            }))
+        }
+
+        fn extension_images(&self) -> Result<Vec<PathBuf>, TridentError> {
+            Ok(vec![PathBuf::from("/var/lib/extensions/ext.raw")])
+        }
+
+        fn host_status(&self) -> Result<HostStatus, TridentError> {
+            Ok(HostStatus::default())
+        }
+    }
+
+    #[test]
+    fn test_inspect_all() {
+        let report = inspect(&FakeHost, &[]);
+        assert_eq!(report.block_devices.unwrap()[0].name, "/dev/sda");
+        assert_eq!(report.boot, None);
+        assert_eq!(
+            report.extensions,
+            Some(vec![PathBuf::from("/var/lib/extensions/ext.raw")])
+        );
+        assert_eq!(report.host_status, Some(HostStatus::default()));
+        assert_eq!(
+            report.errors.keys().collect::<Vec<_>>(),
+            vec![&InspectSection::Boot.to_string()]
+        );
+    }
+
+    #[test]
+    fn test_inspect_sections() {
+        let report = inspect(&FakeHost, &[InspectSection::Extensions]);
+        assert!(report.block_devices.is_none());
+        assert!(report.boot.is_none());
+        assert!(report.extensions.is_some());
+        assert!(report.host_status.is_none());
+        assert!(report.errors.is_empty());
+    }
+}
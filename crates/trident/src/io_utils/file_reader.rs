@@ -13,7 +13,7 @@ use std::{env, io::BufReader};
 
 use anyhow::{bail, ensure, Context, Error};
 use log::{debug, trace, warn};
-use oci_client::{secrets::RegistryAuth, Client as OciClient, Reference};
+use oci_client::{manifest::OciDescriptor, secrets::RegistryAuth, Client as OciClient, Reference};
 use reqwest::blocking::{Client, Response};
 use tokio::runtime::Runtime;
 use url::Url;
@@ -32,6 +32,12 @@ impl ReadSeek for Cursor<Vec<u8>> {}
 #[cfg(feature = "dangerous-options")]
 const DOCKER_CONFIG_FILE_PATH: &str = ".docker/config.json";
 
+/// Annotation holding the name of the file stored in a layer of an OCI artifact, as set by ORAS.
+const OCI_TITLE_ANNOTATION: &str = "org.opencontainers.image.title";
+
+/// File extension of the layer selected in OCI artifacts with several layers.
+const OCI_RAW_FILE_EXTENSION: &str = ".raw";
+
 /// An abstraction over a file reader that can be either a local file or an
 /// HTTP request.
 ///
@@ -116,6 +122,33 @@ impl FileReader {
         })
     }
 
+    /// Returns the URL the file is downloaded from, for files read over HTTP.
+    pub(crate) fn resolved_url(&self) -> Option<&Url> {
+        match self {
+            Self::Http(http_file) => Some(&http_file.url),
+            _ => None,
+        }
+    }
+
+    /// Returns the digest of the registry blob, e.g. `sha256:<hex>`, for files pulled from OCI
+    /// registries.
+    pub(crate) fn digest(&self) -> Option<&str> {
+        match self {
+            Self::Http(http_file) => http_file.digest.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the size of the file in bytes, if it can be determined.
+    pub(crate) fn size(&self) -> Option<u64> {
+        match self {
+            Self::File(file_path) => file_path.metadata().ok().map(|metadata| metadata.len()),
+            Self::Http(http_file) => Some(http_file.size),
+            #[cfg(test)]
+            Self::Buffer(cursor) => Some(cursor.get_ref().len() as u64),
+        }
+    }
+
     /// Returns an implementation of `Read` over the whole file. Creates a
     /// single HTTP request for the entire file, and is faster for sequentially
     /// reading an entire file.
@@ -141,6 +174,35 @@ impl FileReader {
     }
 }
 
+/// Selects the layer of an OCI artifact holding the file. Artifacts are expected to have a single
+/// layer, which is the file. Artifacts pushed with several files, e.g. by ORAS, must have exactly
+/// one layer titled with a `.raw` file name.
+fn select_layer(layers: &[OciDescriptor]) -> Result<&OciDescriptor, Error> {
+    if let [layer] = layers {
+        return Ok(layer);
+    }
+
+    let raw_layers = layers
+        .iter()
+        .filter(|layer| {
+            layer
+                .annotations
+                .as_ref()
+                .and_then(|annotations| annotations.get(OCI_TITLE_ANNOTATION))
+                .is_some_and(|title| title.ends_with(OCI_RAW_FILE_EXTENSION))
+        })
+        .collect::<Vec<_>>();
+    match raw_layers.as_slice() {
+        [layer] => Ok(*layer),
+        _ => bail!(
+            "Expected OCI artifact to contain 1 layer, or 1 layer titled '*{OCI_RAW_FILE_EXTENSION}', \
+            found {} layer(s) and {} titled '*{OCI_RAW_FILE_EXTENSION}'",
+            layers.len(),
+            raw_layers.len()
+        ),
+    }
+}
+
 /// A FILE-like object that is obtained through an HTTP request using range
 /// headers instead of a local file.
 ///
@@ -157,6 +219,9 @@ pub struct HttpFile {
     client: Client,
     timeout: Duration,
     token: Option<String>,
+
+    /// Digest of the registry blob, for files pulled from OCI registries.
+    digest: Option<String>,
 }
 
 impl HttpFile {
@@ -186,8 +251,10 @@ impl HttpFile {
             "https://{registry}/v2/{repository}/blobs/{digest}"
         ))?;
 
-        Self::new_inner(&http_url, Some(token), true, timeout)
-            .context("Failed to create HTTP file reader")
+        let mut http_file = Self::new_inner(&http_url, Some(token), true, timeout)
+            .context("Failed to create HTTP file reader")?;
+        http_file.digest = Some(digest);
+        Ok(http_file)
     }
 
     fn new_inner(
@@ -269,6 +336,7 @@ impl HttpFile {
             client,
             timeout,
             token,
+            digest: None,
         })
     }
 
@@ -348,15 +416,7 @@ impl HttpFile {
                         img_ref.repository(),
                         img_ref.registry()
                     ))?;
-                // Expect the artifact to have one layer, which is the image
-                ensure!(
-                    oci_image_manifest.layers.len() == 1,
-                    format!(
-                        "Expected OCI artifact to contain 1 layer, found {}",
-                        oci_image_manifest.layers.len()
-                    )
-                );
-                oci_image_manifest.layers[0].digest.clone()
+                select_layer(&oci_image_manifest.layers)?.digest.clone()
             }
         })
     }
@@ -601,6 +661,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_select_layer() {
+        let layer = |digest: &str, title: Option<&str>| OciDescriptor {
+            digest: digest.into(),
+            annotations: title.map(|title| {
+                [(OCI_TITLE_ANNOTATION.to_string(), title.to_string())]
+                    .into_iter()
+                    .collect()
+            }),
+            ..Default::default()
+        };
+
+        // A single layer is selected regardless of its title
+        let layers = [layer("sha256:a", None)];
+        assert_eq!(select_layer(&layers).unwrap().digest, "sha256:a");
+
+        // Among several layers, the one holding a .raw file is selected
+        let layers = [
+            layer("sha256:a", Some("README.md")),
+            layer("sha256:b", Some("debug.raw")),
+            layer("sha256:c", None),
+        ];
+        assert_eq!(select_layer(&layers).unwrap().digest, "sha256:b");
+
+        // Ambiguous or missing .raw layers are rejected
+        select_layer(&[]).unwrap_err();
+        select_layer(&[
+            layer("sha256:a", Some("debug.raw")),
+            layer("sha256:b", Some("tools.raw")),
+        ])
+        .unwrap_err();
+        select_layer(&[layer("sha256:a", None), layer("sha256:b", None)]).unwrap_err();
+    }
+
     #[test]
     fn test_retriable_request_sender_retry_count() {
         let tries = Arc::new(AtomicU16::new(0));
@@ -659,6 +753,7 @@ mod tests {
             client: Client::new(),
             timeout: Duration::from_secs(1),
             token: None,
+            digest: None,
         };
 
         assert_eq!(http_file.seek(SeekFrom::Start(50)).unwrap(), 50);
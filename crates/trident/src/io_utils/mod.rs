@@ -1,3 +1,5 @@
 pub mod file_reader;
 pub mod hashing_reader;
 pub mod image_streamer;
+pub mod progress;
+pub mod progress_reader;
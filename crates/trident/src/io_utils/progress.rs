@@ -0,0 +1,170 @@
+use std::time::{Duration, Instant};
+
+use log::info;
+
+use trident_api::primitives::bytes::ByteCount;
+
+/// Minimum time between two progress messages of the same operation.
+const MIN_REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Unit of the amount of work done by an operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgressUnit {
+    /// Amount of data, printed in human-readable form.
+    Bytes,
+
+    /// Number of items, e.g. files.
+    Items,
+}
+
+/// Logs the progress of a long-running operation, such as an image write or a download.
+///
+/// Progress messages are logged at most once every few seconds, regardless of how often the
+/// operation advances, and include the percentage done and the estimated time left when the total
+/// amount of work is known.
+pub struct Progress {
+    operation: String,
+    unit: ProgressUnit,
+    total: Option<u64>,
+    done: u64,
+    started: Instant,
+    last_report: Instant,
+    interval: Duration,
+}
+
+impl Progress {
+    /// Starts tracking `operation`, e.g. "Downloading extension image 'foo'". `total` is the
+    /// expected amount of work, if known.
+    pub fn new(operation: impl Into<String>, unit: ProgressUnit, total: Option<u64>) -> Self {
+        let now = Instant::now();
+        Self {
+            operation: operation.into(),
+            unit,
+            total,
+            done: 0,
+            started: now,
+            last_report: now,
+            interval: MIN_REPORT_INTERVAL,
+        }
+    }
+
+    /// Returns the amount of work done so far.
+    pub fn done(&self) -> u64 {
+        self.done
+    }
+
+    /// Records `amount` more work as done, and logs the progress if the last message is old enough.
+    pub fn advance(&mut self, amount: u64) {
+        self.done += amount;
+
+        let now = Instant::now();
+        if now.duration_since(self.last_report) >= self.interval {
+            self.last_report = now;
+            info!("{}", self.message(now.duration_since(self.started)));
+        }
+    }
+
+    /// Logs the completion of the operation, with the total amount of work done and the time it
+    /// took.
+    pub fn finish(&self) {
+        info!(
+            "{}: done, {} in {}",
+            self.operation,
+            self.format_amount(self.done),
+            format_duration(self.started.elapsed())
+        );
+    }
+
+    /// Returns the progress message, given the time elapsed since the start of the operation.
+    fn message(&self, elapsed: Duration) -> String {
+        let done = self.format_amount(self.done);
+        let Some(total) = self.total else {
+            return format!("{}: {done}", self.operation);
+        };
+
+        let mut message = format!(
+            "{}: {done} of {} ({}%)",
+            self.operation,
+            self.format_amount(total),
+            self.done.min(total) * 100 / total.max(1)
+        );
+        if self.done > 0 && self.done < total {
+            let left = elapsed.mul_f64((total - self.done) as f64 / self.done as f64);
+            message.push_str(&format!(", ETA {}", format_duration(left)));
+        }
+        message
+    }
+
+    fn format_amount(&self, amount: u64) -> String {
+        match self.unit {
+            ProgressUnit::Bytes => ByteCount::from(amount).to_human_readable_approx(),
+            ProgressUnit::Items => amount.to_string(),
+        }
+    }
+}
+
+/// Formats a duration with a precision of one second, e.g. "1h 02m 03s", "4m 05s" or "6s".
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes:02}m {seconds:02}s")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds:02}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::ZERO), "0s");
+        assert_eq!(format_duration(Duration::from_millis(6900)), "6s");
+        assert_eq!(format_duration(Duration::from_secs(245)), "4m 05s");
+        assert_eq!(format_duration(Duration::from_secs(3723)), "1h 02m 03s");
+    }
+
+    #[test]
+    fn test_message() {
+        let mut progress = Progress::new("Writing image", ProgressUnit::Items, Some(100));
+        assert_eq!(
+            progress.message(Duration::ZERO),
+            "Writing image: 0 of 100 (0%)"
+        );
+
+        progress.done = 25;
+        assert_eq!(
+            progress.message(Duration::from_secs(10)),
+            "Writing image: 25 of 100 (25%), ETA 30s"
+        );
+
+        progress.done = 100;
+        assert_eq!(
+            progress.message(Duration::from_secs(40)),
+            "Writing image: 100 of 100 (100%)"
+        );
+
+        let progress = Progress::new("Downloading", ProgressUnit::Bytes, None);
+        assert_eq!(progress.message(Duration::ZERO), "Downloading: 0B");
+    }
+
+    #[test]
+    fn test_advance_is_rate_limited() {
+        let mut progress = Progress::new("Copying", ProgressUnit::Items, Some(1000));
+        let started = progress.last_report;
+        for _ in 0..1000 {
+            progress.advance(1);
+        }
+        assert_eq!(progress.done(), 1000);
+        // The loop takes far less than the minimum interval, so nothing was reported.
+        assert_eq!(progress.last_report, started);
+
+        progress.interval = Duration::ZERO;
+        progress.advance(0);
+        assert!(progress.last_report > started);
+    }
+}
@@ -0,0 +1,62 @@
+use std::io::{self, Read};
+
+use super::progress::{Progress, ProgressUnit};
+
+/// This struct wraps a reader and logs how much data has been read from it, so that long
+/// downloads and image writes show signs of life.
+pub struct ProgressReader<R: Read> {
+    reader: R,
+    progress: Progress,
+    finished: bool,
+}
+
+impl<R: Read> ProgressReader<R> {
+    /// Wraps `reader`. `operation` describes what the data is read for in progress messages, and
+    /// `total` is the expected size of the data, if known.
+    pub fn new(reader: R, operation: impl Into<String>, total: Option<u64>) -> Self {
+        Self {
+            reader,
+            progress: Progress::new(operation, ProgressUnit::Bytes, total),
+            finished: false,
+        }
+    }
+
+    /// Returns the number of bytes read so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.progress.done()
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        if n > 0 {
+            self.progress.advance(n as u64);
+        } else if !buf.is_empty() && !self.finished {
+            self.finished = true;
+            self.progress.finish();
+        }
+
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    #[test]
+    fn test_progress_reader() {
+        let input = vec![7; 1000];
+        let mut reader = ProgressReader::new(Cursor::new(&input), "test", Some(1000));
+        assert!(!reader.finished);
+
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output).unwrap();
+        assert_eq!(output, input);
+        assert_eq!(reader.bytes_read(), 1000);
+        assert!(reader.finished);
+    }
+}
@@ -1,20 +1,27 @@
 use std::{
     fs,
+    net::{SocketAddr, TcpListener},
     path::{Path, PathBuf},
     time::Duration,
 };
 
-use cli::GetKind;
+use cli::{DiffFormat, GetKind, InspectSection, PlanFormat};
 use engine::{bootentries, EngineContext};
 use log::{debug, error, info, warn};
 use nix::unistd::Uid;
+use serde::Serialize;
 
-use osutils::{block_devices, container, dependencies::Dependency};
+use osutils::{block_devices, container, dependencies::Dependency, path, sysext};
 use trident_api::{
-    config::{GrpcConfiguration, HostConfiguration, HostConfigurationSource, Operations},
-    constants::internal_params::{
-        HTTP_CONNECTION_TIMEOUT_SECONDS, ORCHESTRATOR_CONNECTION_TIMEOUT_SECONDS,
-        WAIT_FOR_SYSTEMD_NETWORKD,
+    config::{
+        GrpcConfiguration, HostConfiguration, HostConfigurationSource, Operations, UpdateScope,
+    },
+    constants::{
+        internal_params::{
+            HTTP_CONNECTION_TIMEOUT_SECONDS, ORCHESTRATOR_CONNECTION_TIMEOUT_SECONDS,
+            WAIT_FOR_SYSTEMD_NETWORKD,
+        },
+        ROOT_MOUNT_POINT_PATH,
     },
     error::{
         ExecutionEnvironmentMisconfigurationError, InitializationError, InternalError,
@@ -26,29 +33,47 @@ use trident_api::{
 #[cfg(feature = "grpc-dangerous")]
 use grpc::GrpcSender;
 
+mod annotations;
 pub mod cli;
+mod compose;
 mod datastore;
+mod diff;
+mod endpoint;
 mod engine;
+#[cfg(feature = "grpc-dangerous")]
+mod fleet;
+mod footprint;
+mod gitops;
 mod health;
+mod identity;
+mod inspect;
 mod io_utils;
 mod logging;
+mod migrate;
 mod monitor_metrics;
 pub mod offline_init;
 mod orchestrate;
 pub mod osimage;
+mod plan;
+mod secrets;
+mod serve;
 mod subsystems;
+mod table;
+mod template;
 pub mod validation;
 
 #[cfg(feature = "grpc-dangerous")]
 mod grpc;
 
 use engine::{rollback, storage::rebuild};
+use subsystems::extensions;
 
 pub use datastore::DataStore;
-pub use engine::{provisioning_network, reboot};
+pub use engine::{power_off, provisioning_network, reboot};
+pub use footprint::FootprintMonitor;
 pub use logging::{
-    background_log::BackgroundLog, logstream::Logstream, multilog::MultiLogger,
-    tracestream::TraceStream,
+    background_log::BackgroundLog, journald::JournaldLog, logstream::Logstream,
+    multilog::MultiLogger, tracestream::TraceStream,
 };
 pub use orchestrate::OrchestratorConnection;
 
@@ -89,10 +114,16 @@ pub enum ExitKind {
     Done,
     /// Reboot is needed to complete the operation.
     NeedsReboot,
+    /// Power off is needed to complete the operation.
+    NeedsPowerOff,
 }
 
 pub struct Trident {
     host_config: Option<HostConfiguration>,
+
+    /// Commit that the Host Configuration was loaded from, when it came from a Git repository.
+    config_commit: Option<String>,
+
     orchestrator: Option<OrchestratorConnection>,
 
     #[cfg_attr(not(feature = "grpc-dangerous"), allow(unused))]
@@ -109,25 +140,28 @@ impl Trident {
         logstream: Logstream,
         tracestream: TraceStream,
     ) -> Result<Self, TridentError> {
-        let host_config = config_source
-            .map(|source| Self::load_host_config(&source))
-            .transpose()?;
+        let (host_config, config_commit) = match config_source {
+            Some(source) => {
+                let (host_config, config_commit) = Self::load_host_config(&source)?;
+                (Some(host_config), config_commit)
+            }
+            None => (None, None),
+        };
 
-        let (phonehome_url, logstream_url, connection_timeout_param, wait_for_network) =
+        let (trident_config, connection_timeout_param, wait_for_network) =
             if let Some(config) = &host_config {
                 (
-                    config.trident.phonehome.clone(),
-                    config.trident.logstream.clone(),
+                    config.trident.clone(),
                     config
                         .internal_params
                         .get_u16(ORCHESTRATOR_CONNECTION_TIMEOUT_SECONDS),
                     config.internal_params.get_flag(WAIT_FOR_SYSTEMD_NETWORKD),
                 )
             } else if let Ok(datastore) = DataStore::open(datastore_path) {
+                identity::publish(datastore.host_status());
                 let host_config = &datastore.host_status().spec;
                 (
-                    host_config.trident.phonehome.clone(),
-                    host_config.trident.logstream.clone(),
+                    host_config.trident.clone(),
                     host_config
                         .internal_params
                         .get_u16(ORCHESTRATOR_CONNECTION_TIMEOUT_SECONDS),
@@ -136,7 +170,7 @@ impl Trident {
                         .get_flag(WAIT_FOR_SYSTEMD_NETWORKD),
                 )
             } else {
-                (None, None, None, false)
+                (Default::default(), None, false)
             };
 
         if wait_for_network {
@@ -160,21 +194,25 @@ impl Trident {
         };
 
         // Set up logstream if configured
-        if let Some(url) = logstream_url {
+        if let Some(url) = &trident_config.logstream {
             logstream
-                .set_server(url.to_string())
+                .set_server(url.to_string(), trident_config.logstream_tls.as_ref())
                 .structured(InitializationError::ConnectToLogstream)?;
         }
 
-        let orchestrator = phonehome_url
-            .as_ref()
-            .and_then(|url| OrchestratorConnection::new(url.clone(), connection_timeout));
+        let orchestrator = trident_config.phonehome.as_ref().and_then(|url| {
+            OrchestratorConnection::new(
+                url.clone(),
+                trident_config.phonehome_tls.as_ref(),
+                connection_timeout,
+            )
+        });
 
         // Set up tracestream if configured, using phonehome url for now
-        if let Some(url) = phonehome_url {
+        if let Some(url) = &trident_config.phonehome {
             let trace_url = url.clone().replace("phonehome", "tracestream");
             tracestream
-                .set_server(trace_url)
+                .set_server(trace_url, trident_config.phonehome_tls.as_ref())
                 .structured(InitializationError::ConnectToTracestream)?;
         }
 
@@ -200,16 +238,19 @@ impl Trident {
 
         Ok(Self {
             host_config,
+            config_commit,
             orchestrator,
             server_runtime: None,
             grpc: None,
         })
     }
 
+    /// Loads the Host Configuration from the given source. Also returns the commit SHA the Host
+    /// Configuration was taken from, if the source is a Git repository.
     fn load_host_config(
         source: &HostConfigurationSource,
-    ) -> Result<HostConfiguration, TridentError> {
-        let host_config = match source {
+    ) -> Result<(HostConfiguration, Option<String>), TridentError> {
+        let (host_config, config_commit) = match source {
             // Load the Host Configuration from a file.
             HostConfigurationSource::File(path) => {
                 info!(
@@ -223,11 +264,22 @@ impl Trident {
                     },
                 )?;
 
-                validation::parse_host_config(&contents, path)?
+                (validation::parse_host_config(&contents, path)?, None)
             }
 
             // Use the embedded Host Configuration.
-            HostConfigurationSource::Embedded(contents) => *contents.clone(),
+            HostConfigurationSource::Embedded(contents) => (*contents.clone(), None),
+
+            // Fetch the Host Configuration from a Git repository.
+            HostConfigurationSource::Git {
+                url,
+                reference,
+                path,
+            } => {
+                let loaded = gitops::load_host_config(url, reference, path)?;
+                info!("Loaded Host Configuration from commit '{}'", loaded.commit);
+                (loaded.host_config, Some(loaded.commit))
+            }
         };
 
         info!(
@@ -236,11 +288,11 @@ impl Trident {
                 .unwrap_or("Failed to serialize Host Configuration".into())
         );
 
-        Ok(host_config)
+        Ok((host_config, config_commit))
     }
 
     pub fn start_network(config_source: HostConfigurationSource) -> Result<(), TridentError> {
-        let host_config = Self::load_host_config(&config_source)?;
+        let (host_config, _) = Self::load_host_config(&config_source)?;
 
         info!("Starting network");
         provisioning_network::start(&host_config).structured(ServicingError::StartNetwork)?;
@@ -248,12 +300,22 @@ impl Trident {
         Ok(())
     }
 
-    /// Listen for incoming commands from an orchestrator, and execute the first one.
-    pub fn listen(&mut self, datastore: &mut DataStore) -> Result<(), TridentError> {
+    /// Listen for incoming commands from an orchestrator, and execute the first one. The Host
+    /// Status served to the orchestrator is read from the datastore at `datastore_path`.
+    pub fn listen(
+        &mut self,
+        datastore: &mut DataStore,
+        datastore_path: &Path,
+    ) -> Result<(), TridentError> {
         #[cfg(feature = "grpc-dangerous")]
         if let Some(grpc) = &self.grpc {
             let (sender, mut receiver) = tokio::sync::mpsc::channel(1);
-            self.server_runtime = Some(grpc::start(grpc, self.orchestrator.as_ref(), sender)?);
+            self.server_runtime = Some(grpc::start(
+                grpc,
+                self.orchestrator.as_ref(),
+                datastore_path,
+                sender,
+            )?);
 
             if let Some((host_config, allowed_operations, sender)) = receiver.blocking_recv() {
                 self.host_config = Some(host_config);
@@ -267,7 +329,7 @@ impl Trident {
 
         // Avoid unused variable warning if grpc-dangerous is not enabled
         #[cfg(not(feature = "grpc-dangerous"))]
-        let _ = datastore;
+        let _ = (datastore, datastore_path);
 
         Ok(())
     }
@@ -280,6 +342,8 @@ impl Trident {
     where
         F: FnOnce(&mut DataStore) -> Result<T, TridentError>,
     {
+        identity::publish(datastore.host_status());
+
         // AbUpdateHealthCheckFailed is a special case where we would like
         // to preserve the last error across any recovery. This aids in
         // surfacing the original error.
@@ -386,6 +450,9 @@ impl Trident {
             storage_graph: engine::build_storage_graph(&host_config.storage)?, // Build storage graph
             filesystems: Vec::new(), // Left empty since context does not have image
             is_uki: None,
+            paths: Default::default(),
+            scoped_update: false,
+            resolved_extension_urls: Default::default(),
         };
 
         if ctx.ab_active_volume.is_none() {
@@ -418,6 +485,7 @@ impl Trident {
             .structured(InternalError::Internal(
                 "install called without Host Configuration set",
             ))?;
+        let config_commit = self.config_commit.clone();
 
         self.execute_and_record_error(datastore, |datastore| {
             host_config
@@ -474,6 +542,7 @@ impl Trident {
                         &allowed_operations,
                         multiboot,
                         image,
+                        config_commit,
                         #[cfg(feature = "grpc-dangerous")]
                         sender,
                     )
@@ -522,6 +591,7 @@ impl Trident {
                             &allowed_operations,
                             multiboot,
                             image,
+                            config_commit,
                             #[cfg(feature = "grpc-dangerous")]
                             sender,
                         )
@@ -550,6 +620,7 @@ impl Trident {
             .structured(InternalError::Internal(
                 "update called without Host Configuration set",
             ))?;
+        let config_commit = self.config_commit.clone();
 
         self.execute_and_record_error(datastore, |datastore| {
             if !datastore.is_persistent() {
@@ -578,7 +649,7 @@ impl Trident {
                 debug!("Host Configuration has been updated");
                 // If allowed operations include 'stage', start update
                 if allowed_operations.has_stage() {
-                    engine::update(&host_config, datastore, &allowed_operations, image, #[cfg(feature = "grpc-dangerous")] sender).message("Failed to execute an update")
+                    engine::update(&host_config, datastore, &allowed_operations, image, config_commit, #[cfg(feature = "grpc-dangerous")] sender).message("Failed to execute an update")
                 } else {
                     warn!("Host Configuration has been updated but allowed operations do not include 'stage'. Add 'stage' and re-run to stage the update");
                     Ok(ExitKind::Done)
@@ -607,7 +678,7 @@ impl Trident {
                     ServicingState::AbUpdateFinalized | ServicingState::Provisioned => {
                         // Need to either re-execute the failed update OR inform the user that no update
                         // is needed.
-                        engine::update(&host_config, datastore, &allowed_operations, image, #[cfg(feature = "grpc-dangerous")] sender).message("Failed to update host")
+                        engine::update(&host_config, datastore, &allowed_operations, image, config_commit, #[cfg(feature = "grpc-dangerous")] sender).message("Failed to update host")
                     }
                     servicing_state => {
                         Err(TridentError::new(InternalError::UnexpectedServicingState {
@@ -620,6 +691,131 @@ impl Trident {
         })
     }
 
+    /// Applies only the given configuration domains of the Host Configuration to the running OS,
+    /// leaving all other sections at their current values.
+    ///
+    /// When `network_confirm_timeout` is set, a hot-applied network change is reverted unless it
+    /// is confirmed with [`Trident::confirm_network`] within the timeout.
+    pub fn update_scoped(
+        &mut self,
+        datastore: &mut DataStore,
+        scopes: &[UpdateScope],
+        network_confirm_timeout: Option<Duration>,
+    ) -> Result<ExitKind, TridentError> {
+        let mut host_config = self
+            .host_config
+            .clone()
+            .structured(InternalError::Internal(
+                "update called without Host Configuration set",
+            ))?;
+        let config_commit = self.config_commit.clone();
+
+        self.execute_and_record_error(datastore, |datastore| {
+            if !datastore.is_persistent() {
+                return Err(TridentError::new(InvalidInputError::HostNotProvisioned))
+                    .message("Persistent datastore not found on host");
+            }
+
+            // Sections outside of the scopes are taken from the current Host Configuration, so
+            // the storage section may be omitted just like for regular updates.
+            if host_config.storage == Default::default() {
+                host_config.storage = datastore.host_status().spec.storage.clone();
+            }
+
+            host_config
+                .validate()
+                .map_err(Into::into)
+                .message("Invalid Host Configuration provided")?;
+
+            let image = Self::get_cosi_image(&mut host_config)?;
+
+            engine::scoped_update(
+                &host_config,
+                datastore,
+                scopes,
+                image,
+                network_confirm_timeout,
+                config_commit,
+            )
+            .message("Failed to execute a scoped update")
+        })
+    }
+
+    /// Confirms a hot-applied network configuration change, so that it is not reverted.
+    pub fn confirm_network() -> Result<(), TridentError> {
+        subsystems::network::confirm_change()
+    }
+
+    /// Watches a branch or tag of a Git repository, and updates the host with the Host
+    /// Configuration at `path` every time the reference advances to a new commit.
+    ///
+    /// Returns once an update requires a reboot. Failed updates are recorded in the Host Status as
+    /// usual, and retried on the next poll.
+    pub fn watch(
+        &mut self,
+        datastore: &mut DataStore,
+        url: &str,
+        reference: &str,
+        path: &Path,
+        interval: Duration,
+        allowed_operations: Operations,
+    ) -> Result<ExitKind, TridentError> {
+        info!(
+            "Watching Git repository '{url}' at '{reference}', polling every {}s",
+            interval.as_secs()
+        );
+
+        loop {
+            match gitops::resolve_remote_commit(url, reference) {
+                Ok(commit)
+                    if datastore.host_status().config_commit.as_ref() == Some(&commit)
+                        && datastore.host_status().last_error.is_none() =>
+                {
+                    debug!("Reference '{reference}' is still at applied commit '{commit}'");
+                }
+                Ok(commit) => {
+                    info!("Reconciling host with commit '{commit}' of '{reference}'");
+                    match self.reconcile_from_git(
+                        datastore,
+                        url,
+                        reference,
+                        path,
+                        &allowed_operations,
+                    ) {
+                        Ok(exit_kind @ (ExitKind::NeedsReboot | ExitKind::NeedsPowerOff)) => {
+                            return Ok(exit_kind)
+                        }
+                        Ok(ExitKind::Done) => info!("Reconciled host with commit '{commit}'"),
+                        Err(e) => error!("Failed to reconcile host with commit '{commit}': {e:?}"),
+                    }
+                }
+                Err(e) => warn!("Failed to query Git repository '{url}': {e:?}"),
+            }
+
+            std::thread::sleep(interval);
+        }
+    }
+
+    fn reconcile_from_git(
+        &mut self,
+        datastore: &mut DataStore,
+        url: &str,
+        reference: &str,
+        path: &Path,
+        allowed_operations: &Operations,
+    ) -> Result<ExitKind, TridentError> {
+        let loaded = gitops::load_host_config(url, reference, path)?;
+        self.host_config = Some(loaded.host_config);
+        self.config_commit = Some(loaded.commit);
+
+        self.update(
+            datastore,
+            allowed_operations.clone(),
+            #[cfg(feature = "grpc-dangerous")]
+            &mut None,
+        )
+    }
+
     pub fn commit(&mut self, datastore: &mut DataStore) -> Result<ExitKind, TridentError> {
         // If host's servicing state is *Finalized or *HealthCheckFailed, need to
         // re-evaluate the current state of the host.
@@ -630,16 +826,37 @@ impl Trident {
                 | ServicingState::AbUpdateHealthCheckFailed
         ) {
             info!("No servicing in progress, skipping commit");
+            if datastore.host_status().servicing_state == ServicingState::Provisioned {
+                identity::try_register(datastore);
+            }
             return Ok(ExitKind::Done);
         }
 
+        // Host Configuration of the previous OS, in case the target OS of an A/B update must be
+        // rolled back after it was committed.
+        let spec_old = (datastore.host_status().servicing_state
+            == ServicingState::AbUpdateFinalized)
+            .then(|| datastore.host_status().spec_old.clone());
+
         let rollback_result = self.execute_and_record_error(datastore, |datastore| {
             rollback::validate_boot(datastore).message(
                 "Failed to validate that firmware correctly booted from updated target OS image",
             )
         });
 
-        if rollback_result.is_ok() {
+        if matches!(
+            rollback_result,
+            Ok(rollback::BootValidationResult::ValidBootProvisioned)
+        ) {
+            identity::try_register(datastore);
+        }
+
+        if matches!(
+            rollback_result,
+            Ok(rollback::BootValidationResult::ValidBootProvisioned
+                | rollback::BootValidationResult::ValidBootHealthCheckFailed(_)
+                | rollback::BootValidationResult::ValidBootHealthCheckFailedPowerOff(_))
+        ) {
             if let Some(ref orchestrator) = self.orchestrator {
                 orchestrator.report_success(Some(
                     serde_yaml::to_string(&datastore.host_status())
@@ -648,12 +865,30 @@ impl Trident {
             }
         }
 
+        // The target OS is committed, so the health monitoring only starts now.
+        let rollback_result = match (rollback_result, spec_old) {
+            (Ok(rollback::BootValidationResult::ValidBootProvisioned), Some(spec_old)) => self
+                .execute_and_record_error(datastore, |datastore| {
+                    rollback::monitor_committed_update(datastore, spec_old)
+                        .message("Failed to monitor health of committed target OS")
+                }),
+            (rollback_result, _) => rollback_result,
+        };
+
         match rollback_result {
             Ok(rollback::BootValidationResult::ValidBootProvisioned) => Ok(ExitKind::Done),
             Ok(rollback::BootValidationResult::ValidBootHealthCheckFailed(e)) => {
                 debug!("Correct boot, but health check(s) failed: {e:?}");
                 Ok(ExitKind::NeedsReboot)
             }
+            Ok(rollback::BootValidationResult::ValidBootHealthCheckFailedPowerOff(e)) => {
+                debug!("Correct boot, but health check(s) failed, powering off: {e:?}");
+                Ok(ExitKind::NeedsPowerOff)
+            }
+            Ok(rollback::BootValidationResult::RebootPending) => {
+                warn!("Host did not reboot after finalizing servicing, rebooting again");
+                Ok(ExitKind::NeedsReboot)
+            }
             Err(e) => {
                 error!("Boot validation failed: {e:?}");
                 Err(e)
@@ -661,37 +896,577 @@ impl Trident {
         }
     }
 
-    pub fn get(
+    /// Runs the health checks from the current Host Configuration against the running OS, outside
+    /// of any servicing, and writes the resulting report. When `names` is not empty, only the
+    /// checks with these names are run.
+    pub fn run_health_checks(
         datastore_path: &Path,
+        names: &[String],
         output_path: &Option<PathBuf>,
-        kind: GetKind,
     ) -> Result<(), TridentError> {
         let host_status = DataStore::open(datastore_path)
             .message("Failed to open datastore")?
             .host_status()
             .clone();
 
-        let yaml = match kind {
-            GetKind::Configuration => serde_yaml::to_string(&host_status.spec)
-                .structured(InternalError::SerializeHostStatus)?,
-            GetKind::Status => serde_yaml::to_string(&host_status)
-                .structured(InternalError::SerializeHostStatus)?,
-            GetKind::LastError => serde_yaml::to_string(&host_status.last_error)
-                .structured(InternalError::SerializeError)?,
+        let mut checks = host_status.spec.health.checks.clone();
+        if let Some(name) = names
+            .iter()
+            .find(|name| !checks.iter().any(|check| check.name() == name.as_str()))
+        {
+            return Err(TridentError::new(InvalidInputError::UnknownHealthCheck {
+                name: name.clone(),
+            }));
+        }
+        if !names.is_empty() {
+            checks.retain(|check| names.iter().any(|name| name == check.name()));
+        }
+        health::add_script_environment(&mut checks, &host_status);
+
+        // Run every check as it would be run for the first servicing type it is configured for.
+        let mut report = health::HealthCheckReport::default();
+        for servicing_type in [ServicingType::AbUpdate, ServicingType::CleanInstall] {
+            let (selected, remaining) = checks
+                .into_iter()
+                .partition::<Vec<_>, _>(|check| check.should_run(servicing_type));
+            checks = remaining;
+            if selected.is_empty() {
+                continue;
+            }
+
+            let ctx = EngineContext {
+                spec: host_status.spec.clone(),
+                spec_old: Default::default(),
+                servicing_type,
+                ab_active_volume: host_status.ab_active_volume,
+                partition_paths: host_status.partition_paths.clone(),
+                disk_uuids: host_status.disk_uuids.clone(),
+                install_index: host_status.install_index,
+                image: None,
+                storage_graph: engine::build_storage_graph(&host_status.spec.storage)?,
+                filesystems: Vec::new(), // Left empty since context does not have image
+                is_uki: None,
+                paths: Default::default(),
+                scoped_update: false,
+                resolved_extension_urls: Default::default(),
+            };
+            info!(
+                "Running {} health check(s) as for '{servicing_type:?}'",
+                selected.len()
+            );
+            report
+                .checks
+                .extend(health::run_health_checks(selected, &ctx).checks);
+        }
+        for check in checks {
+            warn!(
+                "Skipping health check '{}', as it does not run on any servicing type",
+                check.name()
+            );
+        }
+
+        let yaml = serde_yaml::to_string(&report).structured(InternalError::SerializeError)?;
+        write_output(output_path, &yaml)?;
+
+        if report.failures().next().is_some() {
+            return Err(TridentError::new(ServicingError::HealthChecksFailed {
+                details: report.failure_details(),
+                servicing_type: format!("{:?}", ServicingType::NoActiveServicing),
+            }));
+        }
+
+        Ok(())
+    }
+
+    /// Checks the health checks of a Host Configuration against the OS whose root filesystem is at
+    /// `root`, and writes the problems found. Fails if any problem is found.
+    pub fn lint_health_checks(
+        config_path: &Path,
+        root: &Path,
+        output_path: &Option<PathBuf>,
+    ) -> Result<(), TridentError> {
+        let contents = fs::read_to_string(config_path).structured(
+            InvalidInputError::LoadHostConfigurationFile {
+                path: config_path.display().to_string(),
+            },
+        )?;
+        let host_config = validation::parse_host_config(&contents, config_path)?;
+        host_config
+            .validate()
+            .map_err(|e| TridentError::new(InvalidInputError::from(e)))
+            .message("Host Configuration is invalid")?;
+
+        let problems = health::lint::lint(&host_config.health, root).structured(
+            InvalidInputError::ReadInputFile {
+                path: root.display().to_string(),
+            },
+        )?;
+        let output = if problems.is_empty() {
+            format!(
+                "No problem found in {} health check(s)\n",
+                host_config.health.checks.len()
+            )
+        } else {
+            problems
+                .iter()
+                .map(|problem| format!("{problem}\n"))
+                .collect()
+        };
+        write_output(output_path, &output)?;
+
+        if !problems.is_empty() {
+            return Err(TridentError::new(InvalidInputError::InvalidHealthChecks {
+                count: problems.len(),
+            }));
+        }
+        Ok(())
+    }
+
+    /// Reports the state of the host without modifying it. When `sections` is not empty, only
+    /// these sections are inspected.
+    pub fn inspect(
+        datastore_path: &Path,
+        sections: &[InspectSection],
+        output_path: &Option<PathBuf>,
+    ) -> Result<(), TridentError> {
+        let report = inspect::inspect(&inspect::SystemHost::new(datastore_path), sections);
+
+        let yaml = serde_yaml::to_string(&report).structured(InternalError::SerializeError)?;
+        write_output(output_path, &yaml)?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "schema")]
+    pub fn print_schema(output_path: &Option<PathBuf>) -> Result<(), TridentError> {
+        let schema = serde_json::to_string_pretty(&HostConfiguration::generate_schema())
+            .structured(InternalError::SerializeError)?;
+        write_output(output_path, &schema)?;
+
+        Ok(())
+    }
+
+    pub fn list_extensions(
+        datastore_path: &Path,
+        json: bool,
+        output_path: &Option<PathBuf>,
+    ) -> Result<(), TridentError> {
+        // Without a datastore, there is no Host Configuration to match the images with.
+        let host_config = DataStore::open_read_only(datastore_path)
+            .map(|datastore| datastore.host_status().spec.clone())
+            .unwrap_or_default();
+
+        let statuses = extensions::status::list(&host_config)
+            .structured(ServicingError::ListExtensionImages)?;
+        let output = if json {
+            serde_json::to_string_pretty(&statuses).structured(InternalError::SerializeError)?
+        } else {
+            extensions::status::list_to_table(&statuses)
         };
+        write_output(output_path, &output)?;
 
-        match output_path {
-            Some(path) => {
-                info!("Writing to {:?}", &path);
-                fs::write(path, yaml).structured(InvalidInputError::WriteOutputFile {
-                    path: path.display().to_string(),
-                })?
+        Ok(())
+    }
+
+    pub fn extension_status(json: bool, output_path: &Option<PathBuf>) -> Result<(), TridentError> {
+        let statuses =
+            extensions::status::hierarchies().structured(ServicingError::ListExtensionImages)?;
+        let output = if json {
+            serde_json::to_string_pretty(&statuses).structured(InternalError::SerializeError)?
+        } else {
+            extensions::status::hierarchies_to_table(&statuses)
+        };
+        write_output(output_path, &output)?;
+
+        Ok(())
+    }
+
+    pub fn prune_extensions(
+        datastore_path: &Path,
+        keep: Option<usize>,
+        dry_run: bool,
+        json: bool,
+        output_path: &Option<PathBuf>,
+    ) -> Result<(), TridentError> {
+        // Without a datastore, no image is managed by Trident.
+        let host_config = DataStore::open_read_only(datastore_path)
+            .map(|datastore| datastore.host_status().spec.clone())
+            .unwrap_or_default();
+        let keep = keep
+            .or(host_config.os.sysext_policy.keep_unmanaged)
+            .unwrap_or_default();
+        let managed = host_config
+            .os
+            .sysexts
+            .iter()
+            .chain(&host_config.os.confexts)
+            .filter_map(|ext| ext.path.clone())
+            .collect();
+
+        let root = if container::is_running_in_container()? {
+            container::get_host_root_path()?
+        } else {
+            PathBuf::from(ROOT_MOUNT_POINT_PATH)
+        };
+        let _lock =
+            extensions::lock::lock(&root).structured(ServicingError::LockExtensionImages)?;
+        let images = extensions::prune::find_unmanaged(
+            &root,
+            host_config.os.sysext_policy.sysext_directory(),
+            &managed,
+            keep,
+        )
+        .structured(ServicingError::PruneExtensionImages)?;
+        if !dry_run {
+            let mut pruned_kinds = Vec::new();
+            for image in images.iter().filter(|image| image.pruned) {
+                info!(
+                    "Removing unmanaged {} image '{}'",
+                    image.kind,
+                    image.path.display()
+                );
+                fs::remove_file(path::join_relative(&root, &image.path))
+                    .structured(ServicingError::PruneExtensionImages)?;
+                if !pruned_kinds.contains(&image.kind) {
+                    pruned_kinds.push(image.kind);
+                }
+            }
+            // Unmerge the removed images.
+            for kind in pruned_kinds {
+                extensions::refresh_images(kind, &host_config)?;
+            }
+        }
+
+        let output = if json {
+            serde_json::to_string_pretty(&images).structured(InternalError::SerializeError)?
+        } else {
+            extensions::prune::to_table(&images, dry_run)
+        };
+        write_output(output_path, &output)?;
+
+        Ok(())
+    }
+
+    pub fn refresh_extensions(datastore_path: &Path) -> Result<(), TridentError> {
+        let host_config = DataStore::open_read_only(datastore_path)?
+            .host_status()
+            .spec
+            .clone();
+        let _lock = extensions::lock::lock(Path::new(ROOT_MOUNT_POINT_PATH))
+            .structured(ServicingError::LockExtensionImages)?;
+        for kind in [
+            sysext::ExtensionKind::Sysext,
+            sysext::ExtensionKind::Confext,
+        ] {
+            extensions::refresh_images(kind, &host_config)?;
+        }
+
+        let statuses = extensions::status::list(&host_config)
+            .structured(ServicingError::ListExtensionImages)?;
+        let drifted = extensions::status::drift(&statuses);
+        if !drifted.is_empty() {
+            let images = drifted
+                .into_iter()
+                .map(|status| format!("\n- {}", extensions::status::describe_drift(status)))
+                .collect();
+            return Err(TridentError::new(
+                ServicingError::ExtensionImagesNotMerged { images },
+            ));
+        }
+
+        info!("All extension images of the Host Configuration are merged");
+        Ok(())
+    }
+
+    pub fn serve_artifacts(
+        datastore_path: &Path,
+        config_path: &Option<PathBuf>,
+        address: SocketAddr,
+    ) -> Result<(), TridentError> {
+        let host_config = match config_path {
+            Some(config_path) => {
+                let contents = fs::read_to_string(config_path).structured(
+                    InvalidInputError::LoadHostConfigurationFile {
+                        path: config_path.display().to_string(),
+                    },
+                )?;
+                let host_config = validation::parse_host_config(&contents, config_path)?;
+                host_config
+                    .validate()
+                    .map_err(|e| TridentError::new(InvalidInputError::from(e)))
+                    .message("Host Configuration is invalid")?;
+                host_config
+            }
+            None => DataStore::open_read_only(datastore_path)?
+                .host_status()
+                .spec
+                .clone(),
+        };
+
+        let artifacts = serve::collect_artifacts(&host_config);
+        if artifacts.is_empty() {
+            return Err(TridentError::new(InvalidInputError::NoArtifactsToServe));
+        }
+
+        let listener =
+            TcpListener::bind(address).structured(InitializationError::ServeArtifacts {
+                address: address.to_string(),
+            })?;
+        for artifact in &artifacts {
+            info!(
+                "Serving {} '{}' at 'http://{address}{}'",
+                artifact.kind,
+                artifact.file.display(),
+                artifact.path
+            );
+        }
+        serve::serve(listener, artifacts).structured(InitializationError::ServeArtifacts {
+            address: address.to_string(),
+        })
+    }
+
+    pub fn plan(
+        config_path: &Path,
+        datastore_path: &Path,
+        format: PlanFormat,
+        output_path: &Option<PathBuf>,
+    ) -> Result<(), TridentError> {
+        let contents = fs::read_to_string(config_path).structured(
+            InvalidInputError::LoadHostConfigurationFile {
+                path: config_path.display().to_string(),
+            },
+        )?;
+        let host_config = validation::parse_host_config(&contents, config_path)?;
+        host_config
+            .validate()
+            .map_err(|e| TridentError::new(InvalidInputError::from(e)))
+            .message("Host Configuration is invalid")?;
+
+        // Hosts already provisioned by Trident get A/B updated.
+        let (servicing_type, old_host_config) = match DataStore::open_read_only(datastore_path) {
+            Ok(datastore)
+                if datastore.host_status().servicing_state == ServicingState::Provisioned =>
+            {
+                (
+                    ServicingType::AbUpdate,
+                    datastore.host_status().spec.clone(),
+                )
+            }
+            _ => (ServicingType::CleanInstall, HostConfiguration::default()),
+        };
+
+        let plan = plan::Plan::new(&host_config, &old_host_config, servicing_type);
+        let output = match format {
+            PlanFormat::Text => plan.to_text(),
+            PlanFormat::Dot => plan.to_dot(),
+        };
+        write_output(output_path, &output)?;
+
+        Ok(())
+    }
+
+    pub fn diff(
+        config_path: &Path,
+        datastore_path: &Path,
+        format: DiffFormat,
+        output_path: &Option<PathBuf>,
+    ) -> Result<(), TridentError> {
+        let contents = fs::read_to_string(config_path).structured(
+            InvalidInputError::LoadHostConfigurationFile {
+                path: config_path.display().to_string(),
+            },
+        )?;
+        let host_config = validation::parse_host_config(&contents, config_path)?;
+        host_config
+            .validate()
+            .map_err(|e| TridentError::new(InvalidInputError::from(e)))
+            .message("Host Configuration is invalid")?;
+
+        // Only the Host Configuration of a provisioned host is currently applied.
+        let old_host_config = match DataStore::open_read_only(datastore_path) {
+            Ok(datastore)
+                if datastore.host_status().servicing_state == ServicingState::Provisioned =>
+            {
+                Some(datastore.host_status().spec.clone())
             }
-            None => {
-                println!("{yaml}");
+            _ => None,
+        };
+
+        let diff = diff::Diff::new(&host_config, old_host_config.as_ref())?;
+        let output = match format {
+            DiffFormat::Text => diff.to_text(),
+            DiffFormat::Yaml => {
+                serde_yaml::to_string(&diff).structured(InternalError::SerializeError)?
             }
+        };
+        write_output(output_path, &output)?;
+
+        Ok(())
+    }
+
+    /// Sets the annotations of `set`, given as `KEY=VALUE`, and removes the annotations of
+    /// `remove` from the Host Status.
+    pub fn annotate(
+        datastore_path: &Path,
+        set: &[String],
+        remove: &[String],
+    ) -> Result<(), TridentError> {
+        if !datastore_path.exists() {
+            return Err(TridentError::new(InvalidInputError::HostNotProvisioned))
+                .message("Datastore file does not exist");
+        }
+        let set = set
+            .iter()
+            .map(|annotation| annotations::parse(annotation))
+            .collect::<Result<Vec<_>, _>>()?;
+        for key in remove {
+            annotations::validate_key(key)?;
         }
 
+        let mut datastore = DataStore::open(datastore_path).message("Failed to open datastore")?;
+        datastore.try_with_host_status(|host_status| {
+            // Update a copy, so that the Host Status is left untouched on failure.
+            let mut updated = host_status.annotations.clone();
+            annotations::update(&mut updated, set, remove)?;
+            host_status.annotations = updated;
+            Ok(())
+        })
+    }
+
+    pub fn get(
+        datastore_path: &Path,
+        output_path: &Option<PathBuf>,
+        kind: GetKind,
+        json: bool,
+    ) -> Result<(), TridentError> {
+        let datastore = DataStore::open(datastore_path).message("Failed to open datastore")?;
+        let host_status = datastore.host_status().clone();
+
+        let output = match kind {
+            GetKind::Configuration => Self::serialize(&host_status.spec, json)
+                .structured(InternalError::SerializeHostStatus)?,
+            GetKind::Status => Self::serialize(&host_status, json)
+                .structured(InternalError::SerializeHostStatus)?,
+            GetKind::LastError => Self::serialize(&host_status.last_error, json)
+                .structured(InternalError::SerializeError)?,
+            GetKind::Annotations => Self::serialize(&host_status.annotations, json)
+                .structured(InternalError::SerializeHostStatus)?,
+            GetKind::Artifacts => Self::serialize(&host_status.artifacts, json)
+                .structured(InternalError::SerializeHostStatus)?,
+            GetKind::HealthChecks => Self::serialize(&host_status.health_checks, json)
+                .structured(InternalError::SerializeHostStatus)?,
+            GetKind::HealthCheckHistory => Self::serialize(&host_status.health_check_history, json)
+                .structured(InternalError::SerializeHostStatus)?,
+            GetKind::ArtifactHistory => {
+                // Every update of the Host Status is stored, so only keep the revisions where
+                // the set of deployed artifacts changed.
+                let mut history = datastore
+                    .history()?
+                    .into_iter()
+                    .map(|host_status| host_status.artifacts)
+                    .filter(|artifacts| !artifacts.is_empty())
+                    .collect::<Vec<_>>();
+                history.dedup();
+                Self::serialize(&history, json).structured(InternalError::SerializeHostStatus)?
+            }
+        };
+
+        write_output(output_path, &output)?;
+
+        Ok(())
+    }
+
+    /// Serializes `value` as JSON if `json` is set, or as YAML otherwise.
+    fn serialize<T: Serialize>(value: &T, json: bool) -> Result<String, anyhow::Error> {
+        Ok(if json {
+            serde_json::to_string_pretty(value)?
+        } else {
+            serde_yaml::to_string(value)?
+        })
+    }
+
+    #[cfg(feature = "grpc-dangerous")]
+    pub fn fleet_apply(
+        manifest_path: &Path,
+        allowed_operations: Operations,
+        concurrency: usize,
+        json: bool,
+        output_path: &Option<PathBuf>,
+    ) -> Result<(), TridentError> {
+        let manifest = Self::load_fleet_manifest(manifest_path)?;
+        let host_configs =
+            manifest
+                .host_configurations()
+                .structured(InvalidInputError::LoadFleetManifest {
+                    path: manifest_path.display().to_string(),
+                })?;
+        let report = fleet::apply(&manifest, host_configs, &allowed_operations, concurrency)
+            .structured(InternalError::Internal(
+                "Failed to service the hosts of the fleet",
+            ))?;
+        Self::write_fleet_report(&report, json, output_path)
+    }
+
+    #[cfg(feature = "grpc-dangerous")]
+    pub fn fleet_status(
+        manifest_path: &Path,
+        concurrency: usize,
+        json: bool,
+        output_path: &Option<PathBuf>,
+    ) -> Result<(), TridentError> {
+        let manifest = Self::load_fleet_manifest(manifest_path)?;
+        let report = fleet::status(&manifest, concurrency).structured(InternalError::Internal(
+            "Failed to query the hosts of the fleet",
+        ))?;
+        Self::write_fleet_report(&report, json, output_path)
+    }
+
+    #[cfg(feature = "grpc-dangerous")]
+    fn load_fleet_manifest(manifest_path: &Path) -> Result<fleet::FleetManifest, TridentError> {
+        fleet::FleetManifest::load(manifest_path).structured(InvalidInputError::LoadFleetManifest {
+            path: manifest_path.display().to_string(),
+        })
+    }
+
+    /// Writes the consolidated report of a fleet operation, and fails if the operation failed on
+    /// any host.
+    #[cfg(feature = "grpc-dangerous")]
+    fn write_fleet_report(
+        report: &fleet::FleetReport,
+        json: bool,
+        output_path: &Option<PathBuf>,
+    ) -> Result<(), TridentError> {
+        let output = if json {
+            serde_json::to_string_pretty(report).structured(InternalError::SerializeError)?
+        } else {
+            report.to_table()
+        };
+        write_output(output_path, &output)?;
+
+        let hosts = report
+            .failed()
+            .map(|host| format!("\n- {}", host.name))
+            .collect::<String>();
+        if !hosts.is_empty() {
+            return Err(TridentError::new(ServicingError::FleetHostsFailed {
+                hosts,
+            }));
+        }
         Ok(())
     }
 }
+
+/// Writes the output of a command to `output_path`, or prints it to stdout when no path is given.
+fn write_output(output_path: &Option<PathBuf>, output: &str) -> Result<(), TridentError> {
+    match output_path {
+        Some(path) => {
+            info!("Writing to {:?}", &path);
+            fs::write(path, output).structured(InvalidInputError::WriteOutputFile {
+                path: path.display().to_string(),
+            })?
+        }
+        None if output.ends_with('\n') => print!("{output}"),
+        None => println!("{output}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,317 @@
+use std::{
+    io,
+    os::unix::net::UnixDatagram,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use crate::TRIDENT_VERSION;
+
+/// Path of the socket on which journald accepts messages in its native protocol.
+const JOURNALD_SOCKET_PATH: &str = "/run/systemd/journal/socket";
+
+/// Identifier under which Trident's messages are stored in the journal.
+const SYSLOG_IDENTIFIER: &str = "trident";
+
+/// Default maximum number of messages sent to journald per rate limiting interval.
+const DEFAULT_RATE_LIMIT_BURST: u32 = 1000;
+
+/// Default length of the rate limiting interval.
+const DEFAULT_RATE_LIMIT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Logger that sends log records to journald using its native protocol, so that every record is
+/// stored with structured fields (priority, source location, target, ...) instead of as flat text.
+///
+/// Sending never blocks: when journald cannot keep up, or when more records than allowed by the
+/// rate limit are logged, records are dropped and a summary of the number of suppressed records is
+/// sent once logging resumes.
+pub struct JournaldLog {
+    socket: Option<UnixDatagram>,
+    socket_path: PathBuf,
+    max_level: LevelFilter,
+    rate_limiter: Mutex<RateLimiter>,
+}
+
+impl JournaldLog {
+    pub fn new() -> Self {
+        Self::with_socket(JOURNALD_SOCKET_PATH)
+    }
+
+    /// Creates a logger that sends records to the journald socket at the given path.
+    pub fn with_socket(socket_path: impl AsRef<Path>) -> Self {
+        let socket = match UnixDatagram::unbound().and_then(|socket| {
+            socket.set_nonblocking(true)?;
+            Ok(socket)
+        }) {
+            Ok(socket) => Some(socket),
+            Err(err) => {
+                eprintln!("Logging setup error: failed to create journald socket: {err:?}");
+                None
+            }
+        };
+
+        Self {
+            socket,
+            socket_path: socket_path.as_ref().to_path_buf(),
+            max_level: LevelFilter::Trace,
+            rate_limiter: Mutex::new(RateLimiter::new(
+                DEFAULT_RATE_LIMIT_BURST,
+                DEFAULT_RATE_LIMIT_INTERVAL,
+            )),
+        }
+    }
+
+    pub fn with_max_level(self, max_level: LevelFilter) -> Self {
+        Self { max_level, ..self }
+    }
+
+    /// Sets the maximum number of records sent to journald per `interval`.
+    pub fn with_rate_limit(self, burst: u32, interval: Duration) -> Self {
+        Self {
+            rate_limiter: Mutex::new(RateLimiter::new(burst, interval)),
+            ..self
+        }
+    }
+
+    pub fn into_logger(self) -> Box<dyn Log> {
+        Box::new(self)
+    }
+
+    /// Best effort attempt to send a datagram to journald.
+    fn send(&self, payload: &[u8]) -> io::Result<()> {
+        match self.socket.as_ref() {
+            Some(socket) => socket.send_to(payload, &self.socket_path).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for JournaldLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Log for JournaldLog {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.socket.is_some() && metadata.level() <= self.max_level
+    }
+
+    fn log(&self, record: &Record) {
+        let Ok(mut rate_limiter) = self.rate_limiter.lock() else {
+            return;
+        };
+
+        let suppressed = match rate_limiter.acquire(Instant::now()) {
+            Some(suppressed) => suppressed,
+            None => return,
+        };
+        if suppressed > 0 {
+            let message = format!("Suppressed {suppressed} log messages");
+            let summary = encode_fields(&[
+                ("MESSAGE", message.as_str()),
+                ("PRIORITY", "4"),
+                ("SYSLOG_IDENTIFIER", SYSLOG_IDENTIFIER),
+            ]);
+            // If the summary cannot be sent, the count is lost; it is only informational.
+            let _ = self.send(&summary);
+        }
+
+        // When journald is not keeping up (or is not available), drop the record rather than
+        // blocking the caller.
+        if self.send(&encode_record(record)).is_err() {
+            rate_limiter.record_dropped();
+        }
+    }
+
+    fn flush(&self) {
+        // No-op, records are sent immediately
+    }
+}
+
+/// Token bucket style rate limiter: allows up to `burst` records per `interval`, and keeps track of
+/// the records dropped in the meantime.
+struct RateLimiter {
+    burst: u32,
+    interval: Duration,
+    window_start: Option<Instant>,
+    sent_in_window: u32,
+    suppressed: u64,
+}
+
+impl RateLimiter {
+    fn new(burst: u32, interval: Duration) -> Self {
+        Self {
+            burst,
+            interval,
+            window_start: None,
+            sent_in_window: 0,
+            suppressed: 0,
+        }
+    }
+
+    /// Attempts to acquire permission to send a record at time `now`. On success, returns the
+    /// number of records suppressed since the last successful acquisition, which is reset.
+    fn acquire(&mut self, now: Instant) -> Option<u64> {
+        match self.window_start {
+            Some(start) if now.duration_since(start) < self.interval => (),
+            _ => {
+                self.window_start = Some(now);
+                self.sent_in_window = 0;
+            }
+        }
+
+        if self.sent_in_window >= self.burst {
+            self.suppressed += 1;
+            return None;
+        }
+
+        self.sent_in_window += 1;
+        Some(std::mem::take(&mut self.suppressed))
+    }
+
+    /// Records that an acquired record could not be delivered.
+    fn record_dropped(&mut self) {
+        self.suppressed += 1;
+    }
+}
+
+/// Maps a log level to a syslog priority, as understood by journald.
+fn priority(level: Level) -> &'static str {
+    match level {
+        Level::Error => "3",
+        Level::Warn => "4",
+        Level::Info => "6",
+        Level::Debug | Level::Trace => "7",
+    }
+}
+
+/// Encodes a log record as a journald native protocol datagram.
+fn encode_record(record: &Record) -> Vec<u8> {
+    let message = record.args().to_string();
+    let line = record.line().map(|line| line.to_string());
+
+    let mut fields = vec![
+        ("MESSAGE", message.as_str()),
+        ("PRIORITY", priority(record.level())),
+        ("SYSLOG_IDENTIFIER", SYSLOG_IDENTIFIER),
+        ("TRIDENT_VERSION", TRIDENT_VERSION),
+        ("TRIDENT_LEVEL", record.level().as_str()),
+        ("TRIDENT_TARGET", record.target()),
+    ];
+    if let Some(module) = record.module_path() {
+        fields.push(("CODE_MODULE", module));
+    }
+    if let Some(file) = record.file() {
+        fields.push(("CODE_FILE", file));
+    }
+    if let Some(line) = line.as_deref() {
+        fields.push(("CODE_LINE", line));
+    }
+
+    encode_fields(&fields)
+}
+
+/// Encodes fields in the journald native protocol. Values without newlines are sent as
+/// `KEY=value`, others use the binary form with an explicit little-endian length.
+fn encode_fields(fields: &[(&str, &str)]) -> Vec<u8> {
+    let mut payload = Vec::new();
+    for (key, value) in fields {
+        payload.extend_from_slice(key.as_bytes());
+        if value.contains('\n') {
+            payload.push(b'\n');
+            payload.extend_from_slice(&(value.len() as u64).to_le_bytes());
+            payload.extend_from_slice(value.as_bytes());
+        } else {
+            payload.push(b'=');
+            payload.extend_from_slice(value.as_bytes());
+        }
+        payload.push(b'\n');
+    }
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_encode_fields() {
+        assert_eq!(
+            encode_fields(&[("MESSAGE", "hello"), ("PRIORITY", "6")]),
+            b"MESSAGE=hello\nPRIORITY=6\n"
+        );
+
+        let mut expected = b"MESSAGE\n".to_vec();
+        expected.extend_from_slice(&11u64.to_le_bytes());
+        expected.extend_from_slice(b"hello\nworld\n");
+        assert_eq!(encode_fields(&[("MESSAGE", "hello\nworld")]), expected);
+    }
+
+    #[test]
+    fn test_priority() {
+        assert_eq!(priority(Level::Error), "3");
+        assert_eq!(priority(Level::Warn), "4");
+        assert_eq!(priority(Level::Info), "6");
+        assert_eq!(priority(Level::Debug), "7");
+        assert_eq!(priority(Level::Trace), "7");
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let mut limiter = RateLimiter::new(2, Duration::from_secs(1));
+        let start = Instant::now();
+
+        assert_eq!(limiter.acquire(start), Some(0));
+        assert_eq!(limiter.acquire(start), Some(0));
+        assert_eq!(limiter.acquire(start + Duration::from_millis(500)), None);
+        limiter.record_dropped();
+
+        // A new window starts, reporting the records dropped in the previous one
+        assert_eq!(limiter.acquire(start + Duration::from_secs(1)), Some(2));
+        assert_eq!(limiter.acquire(start + Duration::from_secs(1)), Some(0));
+    }
+
+    #[test]
+    fn test_journald_log() {
+        let test_dir = tempdir().unwrap();
+        let socket_path = test_dir.path().join("socket");
+        let receiver = UnixDatagram::bind(&socket_path).unwrap();
+
+        let logger = JournaldLog::with_socket(&socket_path)
+            .with_max_level(LevelFilter::Info)
+            .into_logger();
+        assert!(!logger.enabled(&Metadata::builder().level(Level::Debug).build()));
+
+        let record = Record::builder()
+            .args(format_args!("test_message"))
+            .level(Level::Warn)
+            .target("test_target")
+            .module_path(Some("test_module"))
+            .file(Some("test_file.rs"))
+            .line(Some(42))
+            .build();
+        assert!(logger.enabled(record.metadata()));
+        logger.log(&record);
+
+        let mut buf = vec![0; 4096];
+        let len = receiver.recv(&mut buf).unwrap();
+        let payload = String::from_utf8(buf[..len].to_vec()).unwrap();
+        for field in [
+            "MESSAGE=test_message\n",
+            "PRIORITY=4\n",
+            "SYSLOG_IDENTIFIER=trident\n",
+            "TRIDENT_TARGET=test_target\n",
+            "CODE_MODULE=test_module\n",
+            "CODE_FILE=test_file.rs\n",
+            "CODE_LINE=42\n",
+        ] {
+            assert!(payload.contains(field), "Missing field '{field}'");
+        }
+    }
+}
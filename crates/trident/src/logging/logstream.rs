@@ -6,11 +6,15 @@ use std::sync::{
 use anyhow::Context;
 use log::{info, Log};
 
+use trident_api::config::EndpointTls;
+
+use crate::endpoint::HttpEndpoint;
+
 use super::LogEntry;
 
 pub struct Logstream {
     // TODO: Consider changing this to a LockOnce when rustc is updated to >=1.70
-    target: Arc<RwLock<Option<String>>>,
+    target: Arc<RwLock<Option<HttpEndpoint>>>,
     disabled: bool,
 }
 
@@ -29,18 +33,20 @@ impl Logstream {
         self.disabled = true;
     }
 
-    pub fn set_server(&self, url: String) -> Result<(), anyhow::Error> {
+    /// Sets the URL of the log server, and the TLS settings used to connect to it.
+    pub fn set_server(&self, url: String, tls: Option<&EndpointTls>) -> Result<(), anyhow::Error> {
         if self.disabled {
             info!("Logstream is disabled, ignoring set_server");
             return Ok(());
         }
 
-        reqwest::Url::parse(&url).context("Failed to parse logstream URL")?;
+        let endpoint =
+            HttpEndpoint::new(url, tls, None).context("Failed to set up logstream endpoint")?;
         let mut val = self
             .target
             .write()
             .map_err(|_| anyhow::anyhow!("Failed to lock logstream"))?;
-        val.replace(url);
+        val.replace(endpoint);
         Ok(())
     }
 
@@ -64,17 +70,15 @@ impl Logstream {
 /// Do not create this logger directly, use Logstream::make_logger instead.
 pub struct LogSender {
     max_level: log::LevelFilter,
-    server: Arc<RwLock<Option<String>>>,
-    client: reqwest::blocking::Client,
+    server: Arc<RwLock<Option<HttpEndpoint>>>,
     send_failed: AtomicBool,
 }
 
 impl LogSender {
-    fn new(server: Arc<RwLock<Option<String>>>, max_level: log::LevelFilter) -> Self {
+    fn new(server: Arc<RwLock<Option<HttpEndpoint>>>, max_level: log::LevelFilter) -> Self {
         Self {
             server,
             max_level,
-            client: reqwest::blocking::Client::new(),
             send_failed: AtomicBool::new(false),
         }
     }
@@ -87,7 +91,7 @@ impl LogSender {
         self.server.read().map(|s| s.is_some()).unwrap_or_default()
     }
 
-    fn get_server(&self) -> Option<String> {
+    fn get_server(&self) -> Option<HttpEndpoint> {
         self.server.read().map(|s| s.clone()).unwrap_or_default()
     }
 }
@@ -113,9 +117,9 @@ impl Log for LogSender {
                 }
             };
 
-            if let Err(e) = self.client.post(target).body(body).send() {
+            if let Err(e) = target.post(body.into_bytes()) {
                 if !self.send_failed.swap(true, Ordering::Relaxed) {
-                    eprintln!("Failed to send log entry: {e}");
+                    eprintln!("Failed to send log entry: {e:?}");
                 }
             }
         }
@@ -140,12 +144,12 @@ mod tests {
         );
 
         logstream
-            .set_server("http://localhost:8080".to_string())
+            .set_server("http://localhost:8080".to_string(), None)
             .unwrap();
 
         assert!(logger.has_server(), "Logstream should have a server");
         assert_eq!(
-            logger.get_server().unwrap(),
+            logger.get_server().unwrap().url(),
             "http://localhost:8080",
             "Logstream should have a server"
         );
@@ -170,7 +174,7 @@ mod tests {
         logstream.disable();
 
         logstream
-            .set_server("http://localhost:8080".to_string())
+            .set_server("http://localhost:8080".to_string(), None)
             .unwrap();
 
         assert!(!logger.has_server(), "Logstream should not have a server");
@@ -2,6 +2,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 pub(super) mod background_log;
+pub(super) mod journald;
 pub(super) mod logstream;
 pub(super) mod multilog;
 pub(super) mod tracestream;
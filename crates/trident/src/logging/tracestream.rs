@@ -24,7 +24,9 @@ use osutils::{
     uname,
 };
 
-use crate::{TRIDENT_METRICS_FILE_PATH, TRIDENT_VERSION};
+use trident_api::config::EndpointTls;
+
+use crate::{endpoint::HttpEndpoint, identity, TRIDENT_METRICS_FILE_PATH, TRIDENT_VERSION};
 
 /// The product uuid is used to identify the hardware that Trident is running on.
 const PRODUCT_UUID_FILE: &str = "/sys/class/dmi/id/product_uuid";
@@ -83,7 +85,7 @@ struct TraceEntry {
 pub struct TraceStream {
     // TODO: Consider changing this to a LockOnce when rustc is updated to
     // >=1.70
-    target: Arc<RwLock<Option<String>>>,
+    target: Arc<RwLock<Option<HttpEndpoint>>>,
     disabled: bool,
 }
 
@@ -98,18 +100,20 @@ impl TraceStream {
         self.disabled = true;
     }
 
-    pub fn set_server(&self, url: String) -> Result<(), anyhow::Error> {
+    /// Sets the URL of the trace server, and the TLS settings used to connect to it.
+    pub fn set_server(&self, url: String, tls: Option<&EndpointTls>) -> Result<(), anyhow::Error> {
         if self.disabled {
             info!("tracestream is disabled, ignoring set_server");
             return Ok(());
         }
 
-        reqwest::Url::parse(&url).context(format!("Failed to parse tracestream URL: {url}"))?;
+        let endpoint = HttpEndpoint::new(url.clone(), tls, None)
+            .context(format!("Failed to set up tracestream endpoint: {url}"))?;
         let mut val = self
             .target
             .write()
             .map_err(|_| anyhow::anyhow!("Failed to lock tracestream"))?;
-        val.replace(url);
+        val.replace(endpoint);
         Ok(())
     }
 
@@ -120,8 +124,7 @@ impl TraceStream {
 }
 
 pub struct TraceSender {
-    server: Arc<RwLock<Option<String>>>,
-    client: reqwest::blocking::Client,
+    server: Arc<RwLock<Option<HttpEndpoint>>>,
     metrics_file: Option<File>,
 }
 
@@ -132,10 +135,9 @@ struct ExecutionTime(Instant);
 /// the tracing-subscriber crate to handle the events and send them to the
 /// server.
 impl TraceSender {
-    fn new(server: Arc<RwLock<Option<String>>>) -> Self {
+    fn new(server: Arc<RwLock<Option<HttpEndpoint>>>) -> Self {
         Self {
             server,
-            client: reqwest::blocking::Client::new(),
             metrics_file: match files::create_file(TRIDENT_METRICS_FILE_PATH) {
                 Ok(f) => Some(f),
                 Err(err) => {
@@ -148,7 +150,7 @@ impl TraceSender {
         }
     }
 
-    fn get_server(&self) -> Option<String> {
+    fn get_server(&self) -> Option<HttpEndpoint> {
         self.server.read().map(|s| s.clone()).unwrap_or_default()
     }
 
@@ -217,7 +219,7 @@ where
             timestamp: Utc::now(),
             metric_name,
             value: json!(value),
-            additional_fields: ADDITIONAL_FIELDS.clone(),
+            additional_fields: additional_fields(),
             platform_info: PLATFORM_INFO.clone(),
         };
 
@@ -234,8 +236,8 @@ where
 
         // Send the trace entry to the server if it exists
         if let Some(target) = self.get_server() {
-            if let Err(e) = self.client.post(target).body(body).send() {
-                trace!("Failed to send trace entry: {}", e);
+            if let Err(e) = target.post(body.into_bytes()) {
+                trace!("Failed to send trace entry: {:?}", e);
             }
         }
     }
@@ -297,7 +299,7 @@ where
             timestamp: Utc::now(),
             metric_name: span.name().to_string(),
             value: json!(visitor.fields),
-            additional_fields: ADDITIONAL_FIELDS.clone(),
+            additional_fields: additional_fields(),
             platform_info: PLATFORM_INFO.clone(),
         };
 
@@ -314,8 +316,8 @@ where
 
         // Send the trace entry to the server if it exists
         if let Some(target) = self.get_server() {
-            if let Err(e) = self.client.post(target).body(body).send() {
-                trace!("Failed to send trace entry: {}", e);
+            if let Err(e) = target.post(body.into_bytes()) {
+                trace!("Failed to send trace entry: {:?}", e);
             }
         }
     }
@@ -355,6 +357,16 @@ fn populate_additional_fields() -> BTreeMap<String, Value> {
     additional_fields
 }
 
+/// Returns the additional fields attached to every trace entry, including the ID of the host once
+/// it is known.
+fn additional_fields() -> BTreeMap<String, Value> {
+    let mut additional_fields = ADDITIONAL_FIELDS.clone();
+    if let Some(host_id) = identity::host_id() {
+        additional_fields.insert("host_id".to_string(), json!(host_id));
+    }
+    additional_fields
+}
+
 /// Grab the os-release file and extract the VERSION field
 fn get_os_release() -> String {
     match OsRelease::read().map(|os_rel| os_rel.version) {
@@ -417,11 +429,11 @@ mod tests {
         );
 
         tracestream
-            .set_server("http://localhost:8080".to_string())
+            .set_server("http://localhost:8080".to_string(), None)
             .unwrap();
 
         assert_eq!(
-            trace_sender.get_server().unwrap(),
+            trace_sender.get_server().unwrap().url(),
             "http://localhost:8080",
             "tracestream should have a server"
         );
@@ -440,7 +452,7 @@ mod tests {
         tracestream.disable();
 
         tracestream
-            .set_server("http://localhost:8080".to_string())
+            .set_server("http://localhost:8080".to_string(), None)
             .unwrap();
 
         assert!(
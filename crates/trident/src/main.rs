@@ -1,27 +1,32 @@
-use std::{panic, path::PathBuf, process::ExitCode};
+use std::{panic, path::PathBuf, process::ExitCode, time::Duration};
 
 use anyhow::{Context, Error};
 use clap::Parser;
-use log::{error, info, LevelFilter};
+use log::{error, info, warn, LevelFilter};
 
+#[cfg(feature = "grpc-dangerous")]
+use trident::cli::FleetCommands;
 use trident::{
-    cli::{self, Cli, Commands, GetKind},
-    offline_init, validation, BackgroundLog, DataStore, ExitKind, Logstream, MultiLogger,
-    TraceStream, Trident, TRIDENT_BACKGROUND_LOG_PATH,
+    cli::{self, Cli, Commands, GetKind, HealthCommands, SysextCommands},
+    offline_init, validation, BackgroundLog, DataStore, ExitKind, FootprintMonitor, JournaldLog,
+    Logstream, MultiLogger, TraceStream, Trident, TRIDENT_BACKGROUND_LOG_PATH,
 };
 use trident_api::{
     config::HostConfigurationSource,
     constants::{AGENT_CONFIG_PATH, TRIDENT_DATASTORE_PATH_DEFAULT},
-    error::{InternalError, InvalidInputError, TridentError, TridentResultExt},
+    error::{InternalError, InvalidInputError, ReportError, TridentError, TridentResultExt},
+    messages::{self, MessageCatalog, MessageKey},
 };
 
 struct AgentConfig {
     datastore: PathBuf,
+    message_catalog: Option<PathBuf>,
 }
 
 fn load_agent_config() -> Result<AgentConfig, TridentError> {
     let mut config = AgentConfig {
         datastore: TRIDENT_DATASTORE_PATH_DEFAULT.into(),
+        message_catalog: None,
     };
 
     if let Ok(contents) = std::fs::read_to_string(AGENT_CONFIG_PATH) {
@@ -29,12 +34,32 @@ fn load_agent_config() -> Result<AgentConfig, TridentError> {
             if let Some(path) = line.strip_prefix("DatastorePath=") {
                 config.datastore = path.trim().into();
             }
+            if let Some(path) = line.strip_prefix("MessageCatalogPath=") {
+                config.message_catalog = Some(path.trim().into());
+            }
         }
     }
 
     Ok(config)
 }
 
+/// Loads the message catalog configured in the Agent Configuration, if any, and installs it so
+/// that operator-facing messages are rendered with it. Returns whether a catalog was installed.
+fn install_message_catalog() -> Result<bool, TridentError> {
+    let Some(path) = load_agent_config()?.message_catalog else {
+        return Ok(false);
+    };
+
+    let error = |explanation: String| InvalidInputError::LoadMessageCatalog {
+        path: path.display().to_string(),
+        explanation,
+    };
+    let contents = std::fs::read_to_string(&path).structured(error("cannot read file".into()))?;
+    let catalog = MessageCatalog::from_yaml(&contents)
+        .map_err(|e| TridentError::new(error(e.to_string())))?;
+    Ok(messages::install(catalog).is_ok())
+}
+
 fn run_trident(
     mut logstream: Logstream,
     mut tracestream: TraceStream,
@@ -45,8 +70,31 @@ fn run_trident(
 
     // Catch exit fast commands
     match &args.command {
-        Commands::Validate { config } => {
-            return validation::validate_host_config_file(config).map(|()| ExitKind::Done);
+        Commands::Validate {
+            config,
+            print,
+            offline,
+            root,
+        } => {
+            return validation::validate_host_config_file(
+                config,
+                *print,
+                offline.then_some(root.as_path()),
+            )
+            .map(|()| ExitKind::Done);
+        }
+
+        Commands::MigrateConfig { config, outfile } => {
+            return validation::migrate_host_config_file(config, outfile)
+                .message("Failed to migrate Host Configuration")
+                .map(|()| ExitKind::Done);
+        }
+
+        #[cfg(feature = "schema")]
+        Commands::Schema { outfile } => {
+            return Trident::print_schema(outfile)
+                .message("Failed to print Host Configuration schema")
+                .map(|()| ExitKind::Done);
         }
 
         #[cfg(feature = "pytest-generator")]
@@ -70,12 +118,169 @@ fn run_trident(
             .map(|()| ExitKind::Done);
         }
 
-        Commands::Get { kind, outfile } => {
-            return Trident::get(&load_agent_config()?.datastore, outfile, *kind)
+        Commands::Get {
+            kind,
+            outfile,
+            json,
+        } => {
+            return Trident::get(&load_agent_config()?.datastore, outfile, *kind, *json)
                 .message("Failed to retrieve Host Status")
                 .map(|()| ExitKind::Done);
         }
 
+        Commands::Annotate {
+            annotations,
+            remove,
+        } => {
+            return Trident::annotate(&load_agent_config()?.datastore, annotations, remove)
+                .message("Failed to annotate Host Status")
+                .map(|()| ExitKind::Done);
+        }
+
+        Commands::Health {
+            command: HealthCommands::Run { check, outfile },
+        } => {
+            return Trident::run_health_checks(&load_agent_config()?.datastore, check, outfile)
+                .message("Failed to run health checks")
+                .map(|()| ExitKind::Done);
+        }
+
+        Commands::Health {
+            command:
+                HealthCommands::Lint {
+                    config,
+                    root,
+                    outfile,
+                },
+        } => {
+            return Trident::lint_health_checks(config, root, outfile)
+                .message("Failed to lint health checks")
+                .map(|()| ExitKind::Done);
+        }
+
+        Commands::Sysext {
+            command: SysextCommands::List { json, outfile },
+        } => {
+            return Trident::list_extensions(&load_agent_config()?.datastore, *json, outfile)
+                .message("Failed to list extension images")
+                .map(|()| ExitKind::Done);
+        }
+
+        Commands::Sysext {
+            command: SysextCommands::Status { json, outfile },
+        } => {
+            return Trident::extension_status(*json, outfile)
+                .message("Failed to get status of extension images")
+                .map(|()| ExitKind::Done);
+        }
+
+        Commands::Sysext {
+            command:
+                SysextCommands::Prune {
+                    keep,
+                    dry_run,
+                    json,
+                    outfile,
+                },
+        } => {
+            return Trident::prune_extensions(
+                &load_agent_config()?.datastore,
+                *keep,
+                *dry_run,
+                *json,
+                outfile,
+            )
+            .message("Failed to prune extension images")
+            .map(|()| ExitKind::Done);
+        }
+
+        Commands::Sysext {
+            command: SysextCommands::RefreshCache,
+        } => {
+            return Trident::refresh_extensions(&load_agent_config()?.datastore)
+                .message("Failed to refresh extension images")
+                .map(|()| ExitKind::Done);
+        }
+
+        Commands::Inspect { section, outfile } => {
+            return Trident::inspect(&load_agent_config()?.datastore, section, outfile)
+                .message("Failed to inspect host")
+                .map(|()| ExitKind::Done);
+        }
+
+        Commands::Plan {
+            config,
+            format,
+            outfile,
+        } => {
+            return Trident::plan(config, &load_agent_config()?.datastore, *format, outfile)
+                .message("Failed to plan servicing")
+                .map(|()| ExitKind::Done);
+        }
+
+        Commands::Diff {
+            config,
+            format,
+            outfile,
+        } => {
+            return Trident::diff(config, &load_agent_config()?.datastore, *format, outfile)
+                .message("Failed to compare Host Configurations")
+                .map(|()| ExitKind::Done);
+        }
+
+        Commands::Serve {
+            artifacts: _,
+            listen,
+            config,
+        } => {
+            return Trident::serve_artifacts(&load_agent_config()?.datastore, config, *listen)
+                .message("Failed to serve artifacts")
+                .map(|()| ExitKind::Done);
+        }
+
+        #[cfg(feature = "grpc-dangerous")]
+        Commands::Fleet {
+            command:
+                FleetCommands::Apply {
+                    manifest,
+                    allowed_operations,
+                    concurrency,
+                    json,
+                    outfile,
+                },
+        } => {
+            return Trident::fleet_apply(
+                manifest,
+                cli::to_operations(allowed_operations),
+                *concurrency,
+                *json,
+                outfile,
+            )
+            .message("Failed to apply Host Configurations to the fleet")
+            .map(|()| ExitKind::Done);
+        }
+
+        #[cfg(feature = "grpc-dangerous")]
+        Commands::Fleet {
+            command:
+                FleetCommands::Status {
+                    manifest,
+                    concurrency,
+                    json,
+                    outfile,
+                },
+        } => {
+            return Trident::fleet_status(manifest, *concurrency, *json, outfile)
+                .message("Failed to get status of the fleet")
+                .map(|()| ExitKind::Done);
+        }
+
+        Commands::ConfirmNetwork => {
+            return Trident::confirm_network()
+                .message("Failed to confirm network configuration change")
+                .map(|()| ExitKind::Done);
+        }
+
         Commands::StartNetwork { config } => {
             // Lock the streams if we're starting the network
             // We have no network yet, so we can't send logs or traces anywhere
@@ -95,16 +300,29 @@ fn run_trident(
             | Commands::Update { status, error, .. }
             | Commands::Commit { status, error }
             | Commands::Listen { status, error }
-            | Commands::RebuildRaid { status, error, .. } => {
-                let config_path = match &args.command {
+            | Commands::RebuildRaid { status, error, .. }
+            | Commands::Watch { status, error, .. } => {
+                let config_source = match &args.command {
+                    Commands::Update {
+                        git_url: Some(url),
+                        git_ref,
+                        git_path,
+                        ..
+                    } => Some(HostConfigurationSource::Git {
+                        url: url.clone(),
+                        reference: git_ref.clone(),
+                        path: git_path.clone(),
+                    }),
                     Commands::Update { config, .. } | Commands::Install { config, .. } => {
-                        Some(config.clone())
+                        Some(HostConfigurationSource::File(config.clone()))
+                    }
+                    Commands::RebuildRaid { config, .. } => {
+                        config.clone().map(HostConfigurationSource::File)
                     }
-                    Commands::RebuildRaid { config, .. } => config.clone(),
                     _ => None,
                 };
 
-                if let Some(path) = &config_path {
+                if let Some(HostConfigurationSource::File(path)) = &config_source {
                     if !path.exists() {
                         return Err(TridentError::new(InvalidInputError::ReadInputFile {
                             path: path.to_string_lossy().to_string(),
@@ -123,7 +341,7 @@ fn run_trident(
                 }
 
                 let mut trident = Trident::new(
-                    config_path.map(HostConfigurationSource::File),
+                    config_source,
                     &agent_config.datastore,
                     logstream,
                     tracestream,
@@ -137,6 +355,21 @@ fn run_trident(
                 let mut datastore = DataStore::open_or_create(&agent_config.datastore)
                     .message("Failed to open datastore")?;
 
+                // Measure what the run uses from the servicing OS, if requested
+                let footprint = match &args.command {
+                    Commands::Install { footprint, .. } | Commands::Update { footprint, .. } => {
+                        footprint.as_ref()
+                    }
+                    _ => None,
+                }
+                .and_then(|path| match FootprintMonitor::start() {
+                    Ok(monitor) => Some((path, monitor)),
+                    Err(e) => {
+                        warn!("Failed to start measuring footprint: {e:?}");
+                        None
+                    }
+                });
+
                 // Execute the command
                 let res = match args.command {
                     Commands::Install {
@@ -150,6 +383,15 @@ fn run_trident(
                         #[cfg(feature = "grpc-dangerous")]
                         &mut None,
                     ),
+                    Commands::Update {
+                        ref only,
+                        confirm_timeout,
+                        ..
+                    } if !only.is_empty() => trident.update_scoped(
+                        &mut datastore,
+                        &cli::to_update_scopes(only),
+                        confirm_timeout.map(Duration::from_secs),
+                    ),
                     Commands::Update {
                         ref allowed_operations,
                         ..
@@ -160,19 +402,45 @@ fn run_trident(
                         &mut None,
                     ),
                     Commands::Commit { .. } => trident.commit(&mut datastore),
-                    Commands::Listen { .. } => {
-                        trident.listen(&mut datastore).map(|()| ExitKind::Done)
-                    }
+                    Commands::Watch {
+                        ref git_url,
+                        ref git_ref,
+                        ref git_path,
+                        interval,
+                        ref allowed_operations,
+                        ..
+                    } => trident.watch(
+                        &mut datastore,
+                        git_url,
+                        git_ref,
+                        git_path,
+                        Duration::from_secs(interval),
+                        cli::to_operations(allowed_operations),
+                    ),
+                    Commands::Listen { .. } => trident
+                        .listen(&mut datastore, &agent_config.datastore)
+                        .map(|()| ExitKind::Done),
                     Commands::RebuildRaid { .. } => trident
                         .rebuild_raid(&mut datastore)
                         .map(|()| ExitKind::Done),
                     _ => Err(TridentError::internal("Invalid command")),
                 };
 
+                // return footprint report if requested; main() reboots the host only after this
+                if let Some((path, monitor)) = footprint {
+                    if let Err(e) = monitor.finish().and_then(|report| {
+                        std::fs::write(path, serde_yaml::to_string(&report)?)
+                            .context("Failed to write footprint report")
+                    }) {
+                        error!("Failed to report footprint: {e:?}");
+                    }
+                }
+
                 // return HostStatus if requested
                 if status.is_some() {
-                    if let Err(e) = Trident::get(&agent_config.datastore, status, GetKind::Status)
-                        .message("Failed to retrieve Host Status")
+                    if let Err(e) =
+                        Trident::get(&agent_config.datastore, status, GetKind::Status, false)
+                            .message("Failed to retrieve Host Status")
                     {
                         error!("{e:?}");
                     }
@@ -191,7 +459,10 @@ fn run_trident(
                     }
                 }
 
-                res.message(format!("Failed to execute '{}' command", args.command))
+                res.message(messages::english(
+                    MessageKey::CommandFailed,
+                    &[("command", args.command.name())],
+                ))
             }
             _ => unreachable!(),
         }
@@ -208,13 +479,6 @@ fn setup_logging(args: &Cli) -> Result<Logstream, Error> {
 
     // Set up the multilogger
     let mut multilogger = MultiLogger::new()
-        // Add regular env_logger to output to stderr
-        .with_logger(Box::new(
-            env_logger::builder()
-                .format_timestamp(None)
-                .filter_level(args.verbosity)
-                .build(),
-        ))
         // Add logstream to send logs to the log server
         .with_logger(logstream.make_logger_with_level(LevelFilter::Trace))
         // Set the global filter for reqwest to debug
@@ -222,6 +486,25 @@ fn setup_logging(args: &Cli) -> Result<Logstream, Error> {
         // Set the global filter for goblin to off
         .with_global_filter("goblin", LevelFilter::Off);
 
+    // Add regular env_logger to output to stderr
+    if args.log_sink.stderr() {
+        multilogger.add_logger(Box::new(
+            env_logger::builder()
+                .format_timestamp(None)
+                .filter_level(args.verbosity)
+                .build(),
+        ));
+    }
+
+    // Add journald logger to store structured records in the journal
+    if args.log_sink.journald() {
+        multilogger.add_logger(
+            JournaldLog::new()
+                .with_max_level(args.verbosity)
+                .into_logger(),
+        );
+    }
+
     // Add background logger if we're running a command that needs it
     if matches!(
         args.command,
@@ -229,6 +512,7 @@ fn setup_logging(args: &Cli) -> Result<Logstream, Error> {
             | Commands::Update { .. }
             | Commands::Commit { .. }
             | Commands::RebuildRaid { .. }
+            | Commands::Watch { .. }
     ) {
         multilogger.add_logger(BackgroundLog::new(TRIDENT_BACKGROUND_LOG_PATH).into_logger());
     }
@@ -249,6 +533,7 @@ fn setup_tracing(args: &Cli) -> Result<TraceStream, Error> {
             | Commands::Update { .. }
             | Commands::Commit { .. }
             | Commands::RebuildRaid { .. }
+            | Commands::Watch { .. }
     ) {
         // Set up the trace sender
         let trace_sender = tracestream
@@ -282,11 +567,27 @@ fn main() -> ExitCode {
         return ExitCode::from(1);
     }
 
+    // Load the message catalog used for operator-facing messages. Logs always stay in English.
+    let localized = install_message_catalog().unwrap_or_else(|e| {
+        warn!("Failed to install message catalog, using English messages: {e:?}");
+        false
+    });
+
     // Invoke Trident
     match run_trident(logstream.unwrap(), tracestream.unwrap(), &args) {
         Ok(ExitKind::Done) => {}
         Err(e) => {
             error!("{e:?}");
+            if localized {
+                eprintln!(
+                    "{}: {}",
+                    messages::render(
+                        MessageKey::CommandFailed,
+                        &[("command", args.command.name())]
+                    ),
+                    e.summary()
+                );
+            }
             return ExitCode::from(2);
         }
         Ok(ExitKind::NeedsReboot) => {
@@ -295,6 +596,12 @@ fn main() -> ExitCode {
                 return ExitCode::from(3);
             }
         }
+        Ok(ExitKind::NeedsPowerOff) => {
+            if let Err(e) = trident::power_off() {
+                error!("Failed to power off: {e:?}");
+                return ExitCode::from(3);
+            }
+        }
     }
     ExitCode::SUCCESS
 }
@@ -0,0 +1,200 @@
+//! Migration of Host Configurations written for older API versions.
+//!
+//! Each migration upgrades a Host Configuration from one API version to the next, e.g. by
+//! renaming fields or filling in values that used to be implicit, so that Host Configurations
+//! keep working as the API evolves. Migrations operate on the YAML document, as older
+//! documents may not parse as the current `HostConfiguration`.
+//!
+//! Host Configurations without an `apiVersion` predate versioning. They are migrated from
+//! version 0 by `trident migrate-config`, but are otherwise parsed as the current version.
+
+use anyhow::{bail, Context, Error};
+use log::{debug, warn};
+use serde_yaml::{Mapping, Value};
+
+use trident_api::constants::HOST_CONFIGURATION_API_VERSION;
+
+/// Key of the API version of the Host Configuration.
+pub(crate) const API_VERSION_KEY: &str = "apiVersion";
+
+/// Migration from one API version to the next.
+type Migration = fn(&mut Mapping) -> Result<(), Error>;
+
+/// Migrations of the Host Configuration. The migration at index `i` upgrades Host
+/// Configurations of API version `i` to version `i + 1`.
+const MIGRATIONS: &[Migration] = &[migrate_from_unversioned];
+
+/// Returns the API version of the Host Configuration `value`, 0 if it has none.
+fn api_version(value: &Mapping) -> Result<u32, Error> {
+    let Some(version) = value.get(API_VERSION_KEY) else {
+        return Ok(0);
+    };
+
+    match version
+        .as_u64()
+        .and_then(|version| u32::try_from(version).ok())
+    {
+        Some(0) | None => bail!("API version must be a positive integer"),
+        Some(version) => Ok(version),
+    }
+}
+
+/// Upgrades the Host Configuration `value` to the current API version. Returns whether it was
+/// written for an older version.
+pub(crate) fn migrate(value: &mut Value) -> Result<bool, Error> {
+    let Value::Mapping(mapping) = value else {
+        bail!("Host Configuration must be a mapping");
+    };
+
+    let version = api_version(mapping)?;
+    if version > HOST_CONFIGURATION_API_VERSION {
+        bail!(
+            "API version {version} of the Host Configuration is newer than the latest supported \
+            version {HOST_CONFIGURATION_API_VERSION}"
+        );
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        debug!(
+            "Migrating Host Configuration from API version {from} to {}",
+            from + 1
+        );
+        migration(mapping).with_context(|| format!("Failed to migrate from API version {from}"))?;
+    }
+
+    if version == HOST_CONFIGURATION_API_VERSION {
+        return Ok(false);
+    }
+
+    // Keep the API version first, where readers expect it.
+    mapping.remove(API_VERSION_KEY);
+    let mut migrated = Mapping::new();
+    migrated.insert(
+        API_VERSION_KEY.into(),
+        Value::from(HOST_CONFIGURATION_API_VERSION),
+    );
+    migrated.extend(std::mem::take(mapping));
+    *mapping = migrated;
+
+    Ok(true)
+}
+
+/// Migrates Host Configurations that predate versioning:
+///
+/// - Old-style configurations nest the Host Configuration under `hostConfiguration`, next to
+///   settings of the agent, which are dropped.
+/// - `allowedOperations` is passed on the command line instead.
+/// - `servicingTypes` of systemd health checks is renamed to `runOn`.
+fn migrate_from_unversioned(mapping: &mut Mapping) -> Result<(), Error> {
+    if let Some(host_config) = mapping.remove("hostConfiguration") {
+        let Value::Mapping(host_config) = host_config else {
+            bail!("'hostConfiguration' must be a mapping");
+        };
+        for key in mapping.keys() {
+            warn!(
+                "Dropping '{}' from old-style configuration",
+                key.as_str().unwrap_or_default()
+            );
+        }
+        *mapping = host_config;
+    }
+
+    if mapping.remove("allowedOperations").is_some() {
+        warn!("Dropping 'allowedOperations', pass it with '--allowed-operations' instead");
+    }
+
+    let checks = mapping
+        .get_mut("health")
+        .and_then(|health| health.get_mut("checks"))
+        .and_then(Value::as_sequence_mut);
+    for check in checks.into_iter().flatten() {
+        let Some(check) = check.as_mapping_mut() else {
+            continue;
+        };
+        if check.contains_key("runOn") {
+            continue;
+        }
+        if let Some(servicing_types) = check.remove("servicingTypes") {
+            check.insert("runOn".into(), servicing_types);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    fn migrated(contents: &str) -> (bool, Value) {
+        let mut value = serde_yaml::from_str(contents).unwrap();
+        let changed = migrate(&mut value).unwrap();
+        (changed, value)
+    }
+
+    #[test]
+    fn test_migrate_unversioned() {
+        let (changed, value) = migrated(indoc! {"
+            allowedOperations: stage
+            hostConfiguration:
+              os:
+                hostname: host
+              health:
+                checks:
+                  - name: sshd
+                    systemdServices: [sshd.service]
+                    servicingTypes: [ab-update]
+                  - name: web
+                    systemdServices: [nginx.service]
+                    runOn: [clean-install]
+                    servicingTypes: [ab-update]
+        "});
+        assert!(changed);
+        assert_eq!(
+            value,
+            serde_yaml::from_str::<Value>(indoc! {"
+                apiVersion: 1
+                os:
+                  hostname: host
+                health:
+                  checks:
+                    - name: sshd
+                      systemdServices: [sshd.service]
+                      runOn: [ab-update]
+                    - name: web
+                      systemdServices: [nginx.service]
+                      runOn: [clean-install]
+                      servicingTypes: [ab-update]
+            "})
+            .unwrap()
+        );
+
+        // The API version is first.
+        assert_eq!(
+            value.as_mapping().unwrap().keys().next().unwrap(),
+            API_VERSION_KEY
+        );
+    }
+
+    #[test]
+    fn test_migrate_current() {
+        let contents = indoc! {"
+            apiVersion: 1
+            os:
+              hostname: host
+        "};
+        let (changed, value) = migrated(contents);
+        assert!(!changed);
+        assert_eq!(value, serde_yaml::from_str::<Value>(contents).unwrap());
+    }
+
+    #[test]
+    fn test_migrate_invalid_version() {
+        for contents in ["apiVersion: 2", "apiVersion: 0", "apiVersion: v1", "- os"] {
+            let mut value = serde_yaml::from_str(contents).unwrap();
+            migrate(&mut value).unwrap_err();
+        }
+    }
+}
@@ -1,6 +1,11 @@
 use log::{debug, error, warn};
 use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use trident_api::config::EndpointTls;
+
+use crate::{endpoint::HttpEndpoint, identity};
 
 /// Timeout in seconds for connecting to the orchestrator.
 pub const ORCHESTRATOR_CONNECTION_TIMEOUT_SECONDS: u16 = 60;
@@ -18,25 +23,38 @@ struct Message {
     state: State,
     message: String,
     host_status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    host_id: Option<Uuid>,
 }
 
 pub struct OrchestratorConnection {
-    url: String,
+    endpoint: HttpEndpoint,
 }
 impl OrchestratorConnection {
     /// Attempt to connect to the orchestrator, and return a connection if successful.
-    pub fn new(url: String, connection_timeout_secs: Option<u16>) -> Option<Self> {
+    pub fn new(
+        url: String,
+        tls: Option<&EndpointTls>,
+        connection_timeout_secs: Option<u16>,
+    ) -> Option<Self> {
         let timeout_duration = Duration::from_secs(
             connection_timeout_secs
                 .unwrap_or(ORCHESTRATOR_CONNECTION_TIMEOUT_SECONDS)
                 .into(),
         );
+        let endpoint = match HttpEndpoint::new(url, tls, None) {
+            Ok(endpoint) => endpoint,
+            Err(e) => {
+                warn!("Failed to set up orchestrator connection: {e:?}");
+                return None;
+            }
+        };
         let start_time = Instant::now();
         let sleep = 100;
 
         debug!(
             "Reporting status to orchestrator at {}, attempt connection for {} seconds",
-            url,
+            endpoint.url(),
             timeout_duration.as_secs()
         );
         for i in 0.. {
@@ -44,22 +62,21 @@ impl OrchestratorConnection {
                 break;
             }
 
-            if reqwest::blocking::Client::new()
-                .post(&url)
-                .body(
+            if endpoint
+                .post(
                     serde_json::to_vec(&Message {
                         state: State::Started,
                         message: format!("Trident started (connection attempt {i})"),
                         host_status: None,
+                        host_id: identity::host_id(),
                     })
                     .unwrap(),
                 )
-                .send()
                 .map(|r| r.status().is_success())
                 .unwrap_or(false)
             {
                 debug!("Connected to orchestrator");
-                return Some(Self { url });
+                return Some(Self { endpoint });
             }
             std::thread::sleep(std::time::Duration::from_millis(sleep));
         }
@@ -69,12 +86,8 @@ impl OrchestratorConnection {
     }
 
     fn send_message(&self, message: Message) {
-        if let Err(e) = reqwest::blocking::Client::new()
-            .post(&self.url)
-            .body(serde_json::to_vec(&message).unwrap())
-            .send()
-        {
-            error!("Orchestrator connection lost: {}", e);
+        if let Err(e) = self.endpoint.post(serde_json::to_vec(&message).unwrap()) {
+            error!("Orchestrator connection lost: {:?}", e);
         }
     }
 
@@ -83,6 +96,7 @@ impl OrchestratorConnection {
             state: State::Failed,
             message: error,
             host_status,
+            host_id: identity::host_id(),
         });
     }
 
@@ -92,6 +106,7 @@ impl OrchestratorConnection {
             state: State::Succeeded,
             message: "provisioning succeeded".to_string(),
             host_status,
+            host_id: identity::host_id(),
         })
     }
 }
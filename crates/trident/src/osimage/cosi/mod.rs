@@ -38,6 +38,7 @@ pub(super) struct Cosi {
     entries: HashMap<PathBuf, CosiEntry>,
     metadata: CosiMetadata,
     metadata_sha384: Sha384Hash,
+    metadata_verified: bool,
     reader: FileReader,
 }
 
@@ -72,6 +73,7 @@ impl Cosi {
             source: source.url.clone(),
             reader: cosi_reader,
             metadata_sha384: sha384,
+            metadata_verified: matches!(source.sha384, ImageSha384::Checksum(_)),
         })
     }
 
@@ -80,6 +82,14 @@ impl Cosi {
         &self.source
     }
 
+    /// Returns the URL the COSI file is actually read from, when it differs from the source URL,
+    /// e.g. the registry blob that an `oci://` URL resolved to.
+    pub(super) fn resolved_source(&self) -> Option<&Url> {
+        self.reader
+            .resolved_url()
+            .filter(|url| *url != &self.source)
+    }
+
     pub(super) fn is_uki(&self) -> bool {
         self.metadata.is_uki()
     }
@@ -113,6 +123,11 @@ impl Cosi {
     pub(super) fn metadata_sha384(&self) -> Sha384Hash {
         self.metadata_sha384.clone()
     }
+
+    /// Returns whether the metadata was checked against the hash in the Host Configuration.
+    pub(super) fn metadata_verified(&self) -> bool {
+        self.metadata_verified
+    }
 }
 
 /// Converts a COSI metadata Image to an OsImageFileSystem.
@@ -813,6 +828,7 @@ mod tests {
             },
             reader: FileReader::Buffer(data),
             metadata_sha384: Sha384Hash::from("0".repeat(96)),
+            metadata_verified: false,
         }
     }
 
@@ -833,6 +849,7 @@ mod tests {
             },
             reader: FileReader::Buffer(Cursor::new(Vec::<u8>::new())),
             metadata_sha384: Sha384Hash::from("0".repeat(96)),
+            metadata_verified: false,
         };
 
         // Weird behavior with none/multiple ESPs is primarily tested by the
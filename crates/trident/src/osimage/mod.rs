@@ -126,6 +126,15 @@ impl OsImage {
         }
     }
 
+    /// Returns the URL the OS image is actually read from, when it differs from the source URL.
+    pub(crate) fn resolved_source(&self) -> Option<&Url> {
+        match &self.0 {
+            OsImageInner::Cosi(cosi) => cosi.resolved_source(),
+            #[cfg(test)]
+            OsImageInner::Mock(_) => None,
+        }
+    }
+
     /// Returns an iterator over the available mount points provided by the OS image. It does not
     /// include the ESP filesystem mount point.
     pub(crate) fn available_mount_points<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Path> + 'a> {
@@ -183,6 +192,16 @@ impl OsImage {
             OsImageInner::Mock(mock) => mock.metadata_sha384(),
         }
     }
+
+    /// Returns whether the metadata of the OS image was checked against the hash in the Host
+    /// Configuration, rather than only computed.
+    pub(crate) fn metadata_verified(&self) -> bool {
+        match &self.0 {
+            OsImageInner::Cosi(cosi) => cosi.metadata_verified(),
+            #[cfg(test)]
+            OsImageInner::Mock(_) => false,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -324,6 +343,8 @@ mod tests {
             version: Some("os-version".into()),
             version_id: Some("os-version-id".into()),
             pretty_name: Some("pretty-name-1234".into()),
+            sysext_level: None,
+            confext_level: None,
         };
 
         let mock = OsImage::mock(MockOsImage {
@@ -0,0 +1,645 @@
+//! Servicing plans.
+//!
+//! A plan lists the steps Trident would take to apply a Host Configuration, and which steps each
+//! one waits for, without touching the host. It is derived from the Host Configuration alone, so
+//! it describes the shape of the servicing rather than every command that will be executed.
+//!
+//! The plan also lists which extension images would be merged, unmerged or kept, by comparing the
+//! Host Configuration with the one currently applied. Extension images are neither downloaded
+//! nor inspected.
+
+use std::fmt::{Display, Formatter, Result as FmtResult, Write};
+
+use serde::Serialize;
+
+use trident_api::{
+    config::{Extension, FileSystemSource, HostConfiguration},
+    messages::{self, MessageKey},
+    status::ServicingType,
+};
+use url::Url;
+
+/// Steps of a servicing, in execution order.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Plan {
+    pub servicing_type: ServicingType,
+    pub steps: Vec<Step>,
+    pub extensions: Vec<ExtensionChange>,
+}
+
+/// Single step of a servicing.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Step {
+    /// Unique identifier of the step, e.g. `mkfs:/var`.
+    pub id: String,
+
+    /// Human-readable description of the step.
+    pub description: String,
+
+    /// Identifiers of the steps that must complete before this one.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+}
+
+/// Planned change to an extension image.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExtensionChange {
+    /// Kind of the extension image, i.e. `sysext` or `confext`.
+    pub kind: &'static str,
+
+    /// URL of the extension image in the Host Configuration it comes from.
+    pub url: Url,
+
+    pub action: ExtensionAction,
+
+    /// Why the action is taken.
+    pub reason: String,
+}
+
+/// What happens to an extension image.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ExtensionAction {
+    /// The image is installed and merged on the target OS.
+    Merge,
+
+    /// The image is removed from the target OS.
+    Unmerge,
+
+    /// The image is already installed and is left in place.
+    Keep,
+}
+
+impl Display for ExtensionAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.pad(match self {
+            ExtensionAction::Merge => "merge",
+            ExtensionAction::Unmerge => "unmerge",
+            ExtensionAction::Keep => "keep",
+        })
+    }
+}
+
+impl Plan {
+    /// Builds the plan of the given servicing type for the Host Configuration. `old_host_config`
+    /// is the Host Configuration currently applied to the host, if any.
+    pub(crate) fn new(
+        host_config: &HostConfiguration,
+        old_host_config: &HostConfiguration,
+        servicing_type: ServicingType,
+    ) -> Self {
+        let mut plan = Plan {
+            servicing_type,
+            steps: Vec::new(),
+            extensions: [
+                plan_extensions(
+                    "sysext",
+                    &host_config.os.sysexts,
+                    &old_host_config.os.sysexts,
+                ),
+                plan_extensions(
+                    "confext",
+                    &host_config.os.confexts,
+                    &old_host_config.os.confexts,
+                ),
+            ]
+            .concat(),
+        };
+        let storage = &host_config.storage;
+        let scripts = &host_config.scripts;
+
+        let pre_servicing = plan.add_if(
+            !scripts.pre_servicing.is_empty(),
+            "scripts:pre-servicing",
+            messages::render(
+                MessageKey::PlanStepPreServicingScripts,
+                &[("count", &scripts.pre_servicing.len().to_string())],
+            ),
+            vec![],
+        );
+
+        // Block devices. A/B updates only write to the inactive volumes, so the layout of the
+        // disks is left untouched.
+        let mut block_devices = Vec::new();
+        if servicing_type == ServicingType::CleanInstall {
+            for disk in &storage.disks {
+                block_devices.extend(plan.add(
+                    format!("partition:{}", disk.id),
+                    messages::render(
+                        MessageKey::PlanStepPartition,
+                        &[
+                            ("disk", &disk.id),
+                            ("count", &disk.partitions.len().to_string()),
+                        ],
+                    ),
+                    pre_servicing.clone(),
+                ));
+            }
+
+            let partitions = block_devices.clone();
+            block_devices.extend(plan.add_if(
+                !storage.raid.software.is_empty(),
+                "raid",
+                messages::render(
+                    MessageKey::PlanStepRaid,
+                    &[("count", &storage.raid.software.len().to_string())],
+                ),
+                partitions,
+            ));
+
+            if let Some(encryption) = &storage.encryption {
+                block_devices = plan.add(
+                    "encryption",
+                    messages::render(
+                        MessageKey::PlanStepEncryption,
+                        &[("count", &encryption.volumes.len().to_string())],
+                    ),
+                    block_devices,
+                );
+            }
+
+            if !storage.lvm.volume_groups.is_empty() {
+                block_devices = plan.add(
+                    "lvm",
+                    messages::render(
+                        MessageKey::PlanStepLvm,
+                        &[
+                            (
+                                "volume_groups",
+                                &storage.lvm.volume_groups.len().to_string(),
+                            ),
+                            (
+                                "logical_volumes",
+                                &storage.lvm.logical_volumes.len().to_string(),
+                            ),
+                        ],
+                    ),
+                    block_devices,
+                );
+            }
+        } else if let Some(ab_update) = &storage.ab_update {
+            block_devices = plan.add(
+                "ab-volumes",
+                messages::render(
+                    MessageKey::PlanStepAbVolumes,
+                    &[("count", &ab_update.volume_pairs.len().to_string())],
+                ),
+                pre_servicing.clone(),
+            );
+        }
+        if block_devices.is_empty() {
+            block_devices = pre_servicing;
+        }
+
+        // Filesystems, either created empty or written from the OS image.
+        let ab_volume_ids = storage
+            .ab_update
+            .iter()
+            .flat_map(|ab_update| &ab_update.volume_pairs)
+            .map(|pair| &pair.id)
+            .collect::<Vec<_>>();
+        let mut filesystems = Vec::new();
+        for filesystem in &storage.filesystems {
+            // Only A/B volumes are written to during A/B updates.
+            if servicing_type != ServicingType::CleanInstall
+                && !filesystem
+                    .device_id
+                    .as_ref()
+                    .is_some_and(|id| ab_volume_ids.contains(&id))
+            {
+                continue;
+            }
+
+            let target = filesystem
+                .mount_point_path()
+                .map(|path| path.display().to_string())
+                .or_else(|| filesystem.device_id.clone())
+                .unwrap_or_default();
+            match &filesystem.source {
+                FileSystemSource::New(fs_type) => filesystems.extend(plan.add(
+                    format!("mkfs:{target}"),
+                    messages::render(
+                        MessageKey::PlanStepMkfs,
+                        &[
+                            ("filesystem_type", <&str>::from(fs_type)),
+                            ("target", &target),
+                        ],
+                    ),
+                    block_devices.clone(),
+                )),
+                FileSystemSource::Image => filesystems.extend(plan.add(
+                    format!("image:{target}"),
+                    messages::render(MessageKey::PlanStepImage, &[("target", &target)]),
+                    block_devices.clone(),
+                )),
+                FileSystemSource::Adopted(_) => {}
+            }
+        }
+        if filesystems.is_empty() {
+            filesystems = block_devices;
+        }
+
+        let filesystems = match plan.add_if(
+            !storage.verity.is_empty(),
+            "verity",
+            messages::render(
+                MessageKey::PlanStepVerity,
+                &[("count", &storage.verity.len().to_string())],
+            ),
+            filesystems.clone(),
+        ) {
+            verity if verity.is_empty() => filesystems,
+            verity => verity,
+        };
+
+        let bootloader = plan.add(
+            "bootloader",
+            messages::render(MessageKey::PlanStepBootloader, &[]),
+            filesystems.clone(),
+        );
+
+        let extensions = plan.add_if(
+            !host_config.os.sysexts.is_empty() || !host_config.os.confexts.is_empty(),
+            "extensions",
+            messages::render(
+                MessageKey::PlanStepExtensions,
+                &[
+                    ("sysexts", &host_config.os.sysexts.len().to_string()),
+                    ("confexts", &host_config.os.confexts.len().to_string()),
+                ],
+            ),
+            filesystems,
+        );
+
+        let provisioned = [bootloader, extensions].concat();
+        let post_provision = match plan.add_if(
+            !scripts.post_provision.is_empty(),
+            "scripts:post-provision",
+            messages::render(
+                MessageKey::PlanStepPostProvisionScripts,
+                &[("count", &scripts.post_provision.len().to_string())],
+            ),
+            provisioned.clone(),
+        ) {
+            scripts if scripts.is_empty() => provisioned,
+            scripts => scripts,
+        };
+
+        let os_config = plan.add(
+            "os-config",
+            messages::render(MessageKey::PlanStepOsConfig, &[]),
+            post_provision,
+        );
+        let configured = match plan.add_if(
+            !scripts.post_configure.is_empty(),
+            "scripts:post-configure",
+            messages::render(
+                MessageKey::PlanStepPostConfigureScripts,
+                &[("count", &scripts.post_configure.len().to_string())],
+            ),
+            os_config.clone(),
+        ) {
+            scripts if scripts.is_empty() => os_config,
+            scripts => scripts,
+        };
+
+        plan.add(
+            "finalize",
+            messages::render(MessageKey::PlanStepFinalize, &[]),
+            configured,
+        );
+
+        plan
+    }
+
+    /// Adds a step, returning its identifier as the dependencies of the next steps.
+    fn add(
+        &mut self,
+        id: impl Into<String>,
+        description: impl Into<String>,
+        depends_on: Vec<String>,
+    ) -> Vec<String> {
+        let id = id.into();
+        self.steps.push(Step {
+            id: id.clone(),
+            description: description.into(),
+            depends_on,
+        });
+        vec![id]
+    }
+
+    /// Adds a step if `condition` holds. Returns no dependencies otherwise.
+    fn add_if(
+        &mut self,
+        condition: bool,
+        id: impl Into<String>,
+        description: impl Into<String>,
+        depends_on: Vec<String>,
+    ) -> Vec<String> {
+        if condition {
+            self.add(id, description, depends_on)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Renders the plan as a numbered list of steps.
+    pub(crate) fn to_text(&self) -> String {
+        let mut text = messages::render(
+            MessageKey::PlanServicingType,
+            &[("servicing_type", &format!("{:?}", self.servicing_type))],
+        );
+        text.push('\n');
+        for (index, step) in self.steps.iter().enumerate() {
+            let _ = write!(text, "{:>3}. {} [{}]", index + 1, step.description, step.id);
+            if !step.depends_on.is_empty() {
+                let _ = write!(text, " after {}", step.depends_on.join(", "));
+            }
+            text.push('\n');
+        }
+
+        if !self.extensions.is_empty() {
+            text.push_str(&messages::render(MessageKey::PlanExtensionImages, &[]));
+            text.push('\n');
+            for change in &self.extensions {
+                let _ = writeln!(
+                    text,
+                    "  {:<7} {} '{}': {}",
+                    change.action, change.kind, change.url, change.reason
+                );
+            }
+        }
+        text
+    }
+
+    /// Renders the dependency graph of the steps in the Graphviz DOT language.
+    pub(crate) fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph plan {\n    rankdir=LR;\n    node [shape=box];\n");
+        let _ = writeln!(
+            dot,
+            "    label={};",
+            quote(&format!("{:?}", self.servicing_type))
+        );
+        for step in &self.steps {
+            let _ = writeln!(
+                dot,
+                "    {} [label={}];",
+                quote(&step.id),
+                quote(&step.description)
+            );
+        }
+        for step in &self.steps {
+            for dependency in &step.depends_on {
+                let _ = writeln!(dot, "    {} -> {};", quote(dependency), quote(&step.id));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Compares the extension images of one kind in the new and current Host Configurations. Images
+/// are identified by their SHA384 hash, like when servicing the host.
+fn plan_extensions(
+    kind: &'static str,
+    extensions: &[Extension],
+    old_extensions: &[Extension],
+) -> Vec<ExtensionChange> {
+    let change = |ext: &Extension, action, reason: String| ExtensionChange {
+        kind,
+        url: ext.url.clone(),
+        action,
+        reason,
+    };
+
+    let mut changes = extensions
+        .iter()
+        .map(|ext| {
+            match old_extensions
+                .iter()
+                .find(|old_ext| old_ext.sha384 == ext.sha384)
+            {
+                None => change(
+                    ext,
+                    ExtensionAction::Merge,
+                    messages::render(MessageKey::PlanExtensionNotInstalled, &[]),
+                ),
+                Some(old_ext) if ext.path.is_some() && ext.path != old_ext.path => change(
+                    ext,
+                    ExtensionAction::Keep,
+                    messages::render(
+                        MessageKey::PlanExtensionMoved,
+                        &[(
+                            "path",
+                            &ext.path
+                                .as_deref()
+                                .unwrap_or_default()
+                                .display()
+                                .to_string(),
+                        )],
+                    ),
+                ),
+                Some(_) => change(
+                    ext,
+                    ExtensionAction::Keep,
+                    messages::render(MessageKey::PlanExtensionInstalled, &[]),
+                ),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    changes.extend(
+        old_extensions
+            .iter()
+            .filter(|old_ext| !extensions.iter().any(|ext| ext.sha384 == old_ext.sha384))
+            .map(|old_ext| {
+                change(
+                    old_ext,
+                    ExtensionAction::Unmerge,
+                    messages::render(MessageKey::PlanExtensionRemoved, &[]),
+                )
+            }),
+    );
+
+    changes
+}
+
+/// Quotes a string as a DOT identifier.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use trident_api::{
+        config::{
+            AbUpdate, AbVolumePair, Disk, FileSystem, NewFileSystemType, Script, ScriptSource,
+            Scripts, Storage,
+        },
+        primitives::hash::Sha384Hash,
+    };
+
+    fn host_config() -> HostConfiguration {
+        HostConfiguration {
+            storage: Storage {
+                disks: vec![Disk {
+                    id: "os".into(),
+                    ..Default::default()
+                }],
+                ab_update: Some(AbUpdate {
+                    volume_pairs: vec![AbVolumePair {
+                        id: "root".into(),
+                        ..Default::default()
+                    }],
+                }),
+                filesystems: vec![
+                    FileSystem {
+                        device_id: Some("root".into()),
+                        source: FileSystemSource::Image,
+                        mount_point: Some("/".into()),
+                    },
+                    FileSystem {
+                        device_id: Some("var".into()),
+                        source: FileSystemSource::New(NewFileSystemType::Ext4),
+                        mount_point: Some("/var".into()),
+                    },
+                ],
+                ..Default::default()
+            },
+            scripts: Scripts {
+                post_configure: vec![Script {
+                    name: "hello".into(),
+                    source: ScriptSource::Content("echo hello".into()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn ids(plan: &Plan) -> Vec<&str> {
+        plan.steps.iter().map(|step| step.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_clean_install_plan() {
+        let plan = Plan::new(
+            &host_config(),
+            &HostConfiguration::default(),
+            ServicingType::CleanInstall,
+        );
+        assert_eq!(
+            ids(&plan),
+            vec![
+                "partition:os",
+                "image:/",
+                "mkfs:/var",
+                "bootloader",
+                "os-config",
+                "scripts:post-configure",
+                "finalize",
+            ]
+        );
+        assert_eq!(
+            plan.steps[3].depends_on,
+            vec!["image:/".to_string(), "mkfs:/var".to_string()]
+        );
+        assert_eq!(
+            plan.steps[6].depends_on,
+            vec!["scripts:post-configure".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_ab_update_plan() {
+        let plan = Plan::new(&host_config(), &host_config(), ServicingType::AbUpdate);
+        assert_eq!(
+            ids(&plan),
+            vec![
+                "ab-volumes",
+                "image:/",
+                "bootloader",
+                "os-config",
+                "scripts:post-configure",
+                "finalize",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let plan = Plan {
+            servicing_type: ServicingType::CleanInstall,
+            steps: vec![
+                Step {
+                    id: "partition:os".into(),
+                    description: "Partition disk 'os'".into(),
+                    depends_on: vec![],
+                },
+                Step {
+                    id: "mkfs:/var".into(),
+                    description: "Create \"ext4\" filesystem".into(),
+                    depends_on: vec!["partition:os".into()],
+                },
+            ],
+            extensions: vec![],
+        };
+        assert_eq!(
+            plan.to_dot(),
+            "digraph plan {\n    rankdir=LR;\n    node [shape=box];\n    label=\"CleanInstall\";\n    \
+             \"partition:os\" [label=\"Partition disk 'os'\"];\n    \
+             \"mkfs:/var\" [label=\"Create \\\"ext4\\\" filesystem\"];\n    \
+             \"partition:os\" -> \"mkfs:/var\";\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_plan_extensions() {
+        let extension = |name: &str, hash: char, path: Option<&str>| Extension {
+            path: path.map(PathBuf::from),
+            ..Extension::new(
+                Url::parse(&format!("https://example.com/{name}.raw")).unwrap(),
+                Sha384Hash::from(hash.to_string().repeat(96)),
+            )
+        };
+        let old_extensions = [
+            extension("kept", 'a', None),
+            extension("moved", 'b', Some("/var/lib/extensions/moved.raw")),
+            extension("removed", 'c', None),
+        ];
+        let extensions = [
+            extension("kept", 'a', None),
+            extension("moved", 'b', Some("/etc/extensions/moved.raw")),
+            extension("added", 'd', None),
+        ];
+
+        let changes = plan_extensions("sysext", &extensions, &old_extensions);
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| (change.url.path(), change.action))
+                .collect::<Vec<_>>(),
+            vec![
+                ("/kept.raw", ExtensionAction::Keep),
+                ("/moved.raw", ExtensionAction::Keep),
+                ("/added.raw", ExtensionAction::Merge),
+                ("/removed.raw", ExtensionAction::Unmerge),
+            ]
+        );
+        assert_eq!(
+            changes[1].reason,
+            "Image is already installed, and is moved to '/etc/extensions/moved.raw'"
+        );
+
+        // Nothing is planned for extension images on a clean install without any
+        assert!(plan_extensions("confext", &[], &[]).is_empty());
+    }
+}
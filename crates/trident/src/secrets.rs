@@ -0,0 +1,81 @@
+use std::{env, fs};
+
+use anyhow::{Context, Error};
+
+use trident_api::{
+    config::{HostConfigurationDynamicValidationError, Secret, SecretSource},
+    error::{InvalidInputError, ReportError, TridentError},
+};
+
+/// Returns the value of `secret`, reading referenced secrets from their source.
+///
+/// Referenced secrets are read from the servicing OS, so they must be resolved before entering
+/// the chroot of the target OS.
+pub(crate) fn resolve(secret: &Secret) -> Result<String, TridentError> {
+    match secret {
+        Secret::Inline(value) => Ok(value.clone()),
+        Secret::Reference { value_from } => read_source(value_from).structured(
+            InvalidInputError::from(HostConfigurationDynamicValidationError::ResolveSecret {
+                reference: value_from.to_string(),
+            }),
+        ),
+    }
+}
+
+fn read_source(source: &SecretSource) -> Result<String, Error> {
+    let mut value = match source {
+        SecretSource::File(path) => fs::read_to_string(path)
+            .with_context(|| format!("Failed to read '{}'", path.display()))?,
+        SecretSource::Env(name) => env::var(name)
+            .with_context(|| format!("Failed to read environment variable '{name}'"))?,
+    };
+
+    // Files written by `echo` or editors end with a newline, which is not part of the secret.
+    if value.ends_with('\n') {
+        value.pop();
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(
+            resolve(&Secret::Inline("inline\n".into())).unwrap(),
+            "inline\n"
+        );
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("password");
+        fs::write(&path, "from-file\n").unwrap();
+        assert_eq!(
+            resolve(&Secret::Reference {
+                value_from: SecretSource::File(path)
+            })
+            .unwrap(),
+            "from-file"
+        );
+
+        env::set_var("TRIDENT_TEST_SECRET", "from-env");
+        assert_eq!(
+            resolve(&Secret::Reference {
+                value_from: SecretSource::Env("TRIDENT_TEST_SECRET".into())
+            })
+            .unwrap(),
+            "from-env"
+        );
+
+        for missing in [
+            SecretSource::File(temp_dir.path().join("missing")),
+            SecretSource::Env("TRIDENT_TEST_MISSING_SECRET".into()),
+        ] {
+            resolve(&Secret::Reference {
+                value_from: missing,
+            })
+            .unwrap_err();
+        }
+    }
+}
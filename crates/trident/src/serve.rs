@@ -0,0 +1,583 @@
+//! Minimal HTTP server sharing the OS image and extension images of a host with peer installers,
+//! so that one seeded machine can act as a local mirror for factory or edge site installs.
+//!
+//! Only GET and HEAD requests are supported. Artifacts are served from a fixed table of paths, and
+//! single byte ranges are honored so that peers can stream COSI files the same way they would from
+//! any other HTTP server.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Error};
+use log::{debug, info, warn};
+use serde::Serialize;
+use url::Url;
+
+use trident_api::config::HostConfiguration;
+
+/// Time a peer has to send its request before the connection is dropped.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum size of the request line and headers of a request.
+const MAX_REQUEST_HEAD_SIZE: u64 = 16 * 1024;
+
+/// Maximum number of connections handled at the same time. Peers connecting beyond it are asked
+/// to retry later.
+const MAX_CONNECTIONS: usize = 32;
+
+/// Seconds peers are asked to wait before retrying when too many connections are open.
+const RETRY_AFTER_SECONDS: u64 = 5;
+
+/// File served to peers.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Artifact {
+    /// Path the artifact is served at, e.g. `/sysexts/docker.raw`.
+    pub path: String,
+
+    /// Kind of the artifact: `image`, `sysext` or `confext`.
+    pub kind: &'static str,
+
+    /// Local file the artifact is read from.
+    #[serde(skip)]
+    pub file: PathBuf,
+
+    /// SHA384 hash of the file, when known from the Host Configuration.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha384: Option<String>,
+
+    /// Size of the file in bytes.
+    pub size: u64,
+}
+
+/// Returns the artifacts of `host_config` that are available on this host: the OS image when its
+/// URL points to a local file, and the extension images at their path on the host.
+pub fn collect_artifacts(host_config: &HostConfiguration) -> Vec<Artifact> {
+    let mut candidates = Vec::new();
+    if let Some(image) = &host_config.image {
+        if let Some(file) = local_file(&image.url) {
+            // The hash of an OS image covers its metadata only, not the whole file.
+            candidates.push(("image", file, None));
+        }
+    }
+    for (kind, extensions) in [
+        ("sysext", &host_config.os.sysexts),
+        ("confext", &host_config.os.confexts),
+    ] {
+        for ext in extensions {
+            let file = ext
+                .path
+                .clone()
+                .filter(|path| path.is_file())
+                .or_else(|| local_file(&ext.url));
+            match file {
+                Some(file) => candidates.push((kind, file, Some(ext.sha384.to_string()))),
+                None => debug!("Extension image '{}' is not available locally", ext.url),
+            }
+        }
+    }
+
+    let mut artifacts: Vec<Artifact> = Vec::new();
+    for (kind, file, sha384) in candidates {
+        let Some(name) = file.file_name() else {
+            continue;
+        };
+        let directory = match kind {
+            "image" => "image",
+            "sysext" => "sysexts",
+            _ => "confexts",
+        };
+        let path = format!("/{directory}/{}", name.to_string_lossy());
+        if artifacts.iter().any(|artifact| artifact.path == path) {
+            warn!(
+                "Not serving '{}', another artifact is already served at '{path}'",
+                file.display()
+            );
+            continue;
+        }
+        match file.metadata() {
+            Ok(metadata) => artifacts.push(Artifact {
+                path,
+                kind,
+                file,
+                sha384,
+                size: metadata.len(),
+            }),
+            Err(e) => warn!("Not serving '{}': {e}", file.display()),
+        }
+    }
+    artifacts
+}
+
+/// Returns the path of a `file://` URL, if it points to an existing file.
+fn local_file(url: &Url) -> Option<PathBuf> {
+    if url.scheme() != "file" {
+        return None;
+    }
+    url.to_file_path().ok().filter(|path| path.is_file())
+}
+
+/// Serves `artifacts` to peers connecting to `listener`, until the process is stopped. Each
+/// connection is handled on its own thread, up to `MAX_CONNECTIONS` at the same time.
+pub fn serve(listener: TcpListener, artifacts: Vec<Artifact>) -> Result<(), Error> {
+    let manifest = serde_json::to_vec_pretty(&artifacts).context("Failed to serialize manifest")?;
+    let table = artifacts
+        .into_iter()
+        .map(|artifact| (artifact.path.clone(), artifact))
+        .collect::<BTreeMap<_, _>>();
+    let state = Arc::new((table, manifest));
+    let active = Arc::new(AtomicUsize::new(0));
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Failed to accept connection: {e}");
+                continue;
+            }
+        };
+        let Some(slot) = ConnectionSlot::acquire(&active) else {
+            debug!("Too many connections, asking peer to retry later");
+            let mut stream = stream;
+            let _ = respond(
+                &mut stream,
+                "503 Service Unavailable",
+                &[("Retry-After", RETRY_AFTER_SECONDS.to_string())],
+                None,
+            );
+            continue;
+        };
+        let state = state.clone();
+        thread::spawn(move || {
+            let _slot = slot;
+            let peer = stream
+                .peer_addr()
+                .map(|addr| addr.to_string())
+                .unwrap_or_else(|_| "unknown peer".into());
+            if let Err(e) = handle_connection(stream, &state.0, &state.1) {
+                debug!("Failed to serve request from {peer}: {e:?}");
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Slot of a connection being handled, released when dropped.
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    /// Takes one of the `MAX_CONNECTIONS` slots counted by `active`, if one is free.
+    fn acquire(active: &Arc<AtomicUsize>) -> Option<Self> {
+        active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count < MAX_CONNECTIONS).then_some(count + 1)
+            })
+            .ok()
+            .map(|_| Self(active.clone()))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Byte range requested by a peer, resolved against the size of the artifact.
+#[derive(Debug, PartialEq, Eq)]
+enum RangeRequest {
+    /// The whole file, when no range or an unsupported range was requested.
+    Full,
+
+    /// Bytes `start` to `end`, both inclusive.
+    Partial { start: u64, end: u64 },
+
+    /// A range that lies entirely past the end of the file.
+    Unsatisfiable,
+}
+
+/// Parses the value of a Range header for a file of `size` bytes. Only single ranges are
+/// supported; multiple or malformed ranges are ignored, as allowed by RFC 9110.
+fn parse_range(value: &str, size: u64) -> RangeRequest {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return RangeRequest::Full;
+    };
+    if spec.contains(',') {
+        return RangeRequest::Full;
+    }
+    let Some((start, end)) = spec.split_once('-') else {
+        return RangeRequest::Full;
+    };
+    let (start, end) = (start.trim(), end.trim());
+
+    if start.is_empty() {
+        // Suffix range: the last `end` bytes of the file.
+        return match end.parse::<u64>() {
+            Ok(0) => RangeRequest::Unsatisfiable,
+            Ok(_) if size == 0 => RangeRequest::Unsatisfiable,
+            Ok(length) => RangeRequest::Partial {
+                start: size - length.min(size),
+                end: size - 1,
+            },
+            Err(_) => RangeRequest::Full,
+        };
+    }
+
+    let Ok(start) = start.parse::<u64>() else {
+        return RangeRequest::Full;
+    };
+    let end = if end.is_empty() {
+        u64::MAX
+    } else {
+        match end.parse::<u64>() {
+            Ok(end) if end >= start => end,
+            _ => return RangeRequest::Full,
+        }
+    };
+    if start >= size {
+        return RangeRequest::Unsatisfiable;
+    }
+    RangeRequest::Partial {
+        start,
+        end: end.min(size - 1),
+    }
+}
+
+/// Handles a single request on `stream`. Connections are closed after each response.
+fn handle_connection(
+    stream: TcpStream,
+    artifacts: &BTreeMap<String, Artifact>,
+    manifest: &[u8],
+) -> Result<(), Error> {
+    stream
+        .set_read_timeout(Some(REQUEST_TIMEOUT))
+        .context("Failed to set read timeout")?;
+    let mut reader = BufReader::new(stream.try_clone().context("Failed to clone stream")?)
+        .take(MAX_REQUEST_HEAD_SIZE);
+    let mut writer = stream;
+
+    let mut request_line = String::new();
+    reader
+        .read_line(&mut request_line)
+        .context("Failed to read request line")?;
+    let mut range = None;
+    loop {
+        let mut line = String::new();
+        if reader
+            .read_line(&mut line)
+            .context("Failed to read header")?
+            == 0
+        {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("range") {
+                range = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return respond(&mut writer, "400 Bad Request", &[], None);
+    };
+    let path = target.split(['?', '#']).next().unwrap_or_default();
+    let head = match method {
+        "GET" => false,
+        "HEAD" => true,
+        _ => {
+            return respond(
+                &mut writer,
+                "405 Method Not Allowed",
+                &[("Allow", "GET, HEAD".into())],
+                None,
+            )
+        }
+    };
+
+    if path == "/" {
+        debug!("Serving manifest");
+        let body = (!head).then_some(manifest);
+        return respond(
+            &mut writer,
+            "200 OK",
+            &[
+                ("Content-Type", "application/json".into()),
+                ("Content-Length", manifest.len().to_string()),
+            ],
+            body.map(|body| Box::new(body) as Box<dyn Read + '_>),
+        );
+    }
+
+    let Some(artifact) = artifacts.get(path) else {
+        debug!("No artifact at '{path}'");
+        return respond(&mut writer, "404 Not Found", &[], None);
+    };
+    let mut file = File::open(&artifact.file)
+        .with_context(|| format!("Failed to open '{}'", artifact.file.display()))?;
+    let size = file
+        .metadata()
+        .with_context(|| format!("Failed to read metadata of '{}'", artifact.file.display()))?
+        .len();
+
+    let (status, start, length, content_range) =
+        match range.map_or(RangeRequest::Full, |range| parse_range(&range, size)) {
+            RangeRequest::Full => ("200 OK", 0, size, None),
+            RangeRequest::Partial { start, end } => (
+                "206 Partial Content",
+                start,
+                end - start + 1,
+                Some(format!("bytes {start}-{end}/{size}")),
+            ),
+            RangeRequest::Unsatisfiable => {
+                return respond(
+                    &mut writer,
+                    "416 Range Not Satisfiable",
+                    &[("Content-Range", format!("bytes */{size}"))],
+                    None,
+                );
+            }
+        };
+    info!(
+        "Serving '{}' ({status}, {length} bytes)",
+        artifact.file.display()
+    );
+
+    let mut headers = vec![
+        ("Content-Type", "application/octet-stream".to_string()),
+        ("Content-Length", length.to_string()),
+        ("Accept-Ranges", "bytes".into()),
+    ];
+    headers.extend(content_range.map(|range| ("Content-Range", range)));
+    let body = if head {
+        None
+    } else {
+        file.seek(SeekFrom::Start(start))
+            .with_context(|| format!("Failed to seek in '{}'", artifact.file.display()))?;
+        Some(Box::new(file.take(length)) as Box<dyn Read>)
+    };
+    respond(&mut writer, status, &headers, body)
+}
+
+/// Writes a response with the given status, headers and body. Responses without a body get an
+/// empty one, unless `headers` already set its length.
+fn respond(
+    writer: &mut impl Write,
+    status: &str,
+    headers: &[(&str, String)],
+    body: Option<Box<dyn Read + '_>>,
+) -> Result<(), Error> {
+    let mut head = format!("HTTP/1.1 {status}\r\nConnection: close\r\n");
+    for (name, value) in headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    if !headers.iter().any(|(name, _)| *name == "Content-Length") {
+        head.push_str("Content-Length: 0\r\n");
+    }
+    head.push_str("\r\n");
+    writer
+        .write_all(head.as_bytes())
+        .context("Failed to write response head")?;
+    if let Some(mut body) = body {
+        io::copy(&mut body, writer).context("Failed to write response body")?;
+    }
+    writer.flush().context("Failed to flush response")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use tempfile::TempDir;
+    use trident_api::{
+        config::{Extension, ImageSha384, OsImage},
+        primitives::hash::Sha384Hash,
+    };
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(
+            parse_range("bytes=0-9", 100),
+            RangeRequest::Partial { start: 0, end: 9 }
+        );
+        assert_eq!(
+            parse_range("bytes=90-", 100),
+            RangeRequest::Partial { start: 90, end: 99 }
+        );
+        assert_eq!(
+            parse_range("bytes=90-200", 100),
+            RangeRequest::Partial { start: 90, end: 99 }
+        );
+        assert_eq!(
+            parse_range("bytes=-10", 100),
+            RangeRequest::Partial { start: 90, end: 99 }
+        );
+        assert_eq!(
+            parse_range("bytes=-200", 100),
+            RangeRequest::Partial { start: 0, end: 99 }
+        );
+        assert_eq!(parse_range("bytes=100-", 100), RangeRequest::Unsatisfiable);
+        assert_eq!(parse_range("bytes=-0", 100), RangeRequest::Unsatisfiable);
+
+        // Unsupported or malformed ranges are ignored.
+        assert_eq!(parse_range("items=0-9", 100), RangeRequest::Full);
+        assert_eq!(parse_range("bytes=0-9,20-29", 100), RangeRequest::Full);
+        assert_eq!(parse_range("bytes=9-0", 100), RangeRequest::Full);
+        assert_eq!(parse_range("bytes=a-b", 100), RangeRequest::Full);
+    }
+
+    #[test]
+    fn test_collect_artifacts() {
+        let dir = TempDir::new().unwrap();
+        let image = dir.path().join("os.cosi");
+        let sysext = dir.path().join("docker.raw");
+        fs::write(&image, "image").unwrap();
+        fs::write(&sysext, "docker").unwrap();
+
+        let mut host_config = HostConfiguration {
+            image: Some(OsImage {
+                url: Url::from_file_path(&image).unwrap(),
+                sha384: ImageSha384::Ignored,
+            }),
+            ..Default::default()
+        };
+        host_config.os.sysexts = vec![
+            Extension {
+                path: Some(sysext.clone()),
+                ..Extension::new(
+                    Url::parse("https://example.com/docker.raw").unwrap(),
+                    Sha384Hash::from("a".repeat(96)),
+                )
+            },
+            // Not available locally.
+            Extension::new(
+                Url::parse("https://example.com/tools.raw").unwrap(),
+                Sha384Hash::from("b".repeat(96)),
+            ),
+            // Same file name as an image already served.
+            Extension::new(
+                Url::from_file_path(&sysext).unwrap(),
+                Sha384Hash::from("c".repeat(96)),
+            ),
+        ];
+
+        assert_eq!(
+            collect_artifacts(&host_config),
+            vec![
+                Artifact {
+                    path: "/image/os.cosi".into(),
+                    kind: "image",
+                    file: image,
+                    sha384: None,
+                    size: 5,
+                },
+                Artifact {
+                    path: "/sysexts/docker.raw".into(),
+                    kind: "sysext",
+                    file: sysext,
+                    sha384: Some("a".repeat(96)),
+                    size: 6,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_connection_slot() {
+        let active = Arc::new(AtomicUsize::new(0));
+        let slots = (0..MAX_CONNECTIONS)
+            .map(|_| ConnectionSlot::acquire(&active).unwrap())
+            .collect::<Vec<_>>();
+        assert!(ConnectionSlot::acquire(&active).is_none());
+
+        drop(slots);
+        assert_eq!(active.load(Ordering::Acquire), 0);
+        ConnectionSlot::acquire(&active).unwrap();
+    }
+
+    #[test]
+    fn test_serve() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("docker.raw");
+        fs::write(&file, "0123456789").unwrap();
+        let artifacts = vec![Artifact {
+            path: "/sysexts/docker.raw".into(),
+            kind: "sysext",
+            file,
+            sha384: None,
+            size: 10,
+        }];
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener, artifacts));
+
+        let client = reqwest::blocking::Client::new();
+        let url = format!("http://{address}/sysexts/docker.raw");
+
+        let response = client.get(&url).send().unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["Accept-Ranges"], "bytes");
+        assert_eq!(response.text().unwrap(), "0123456789");
+
+        let response = client
+            .get(&url)
+            .header("Range", "bytes=2-4")
+            .send()
+            .unwrap();
+        assert_eq!(response.status(), 206);
+        assert_eq!(response.headers()["Content-Range"], "bytes 2-4/10");
+        assert_eq!(response.text().unwrap(), "234");
+
+        let response = client
+            .get(&url)
+            .header("Range", "bytes=20-")
+            .send()
+            .unwrap();
+        assert_eq!(response.status(), 416);
+
+        let response = client.head(&url).send().unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["Content-Length"], "10");
+
+        let response = client.post(&url).send().unwrap();
+        assert_eq!(response.status(), 405);
+
+        let response = client
+            .get(format!("http://{address}/sysexts/../etc/passwd"))
+            .send()
+            .unwrap();
+        assert_eq!(response.status(), 404);
+
+        let manifest: serde_json::Value = serde_json::from_str(
+            &client
+                .get(format!("http://{address}/"))
+                .send()
+                .unwrap()
+                .text()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            manifest,
+            serde_json::json!([{"path": "/sysexts/docker.raw", "kind": "sysext", "size": 10}])
+        );
+    }
+}
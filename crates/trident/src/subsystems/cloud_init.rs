@@ -0,0 +1,151 @@
+use std::{fs, io::Read, path::Path, time::Duration};
+
+use anyhow::{Context, Error};
+use log::{debug, info};
+use url::Url;
+
+use trident_api::{
+    config::{CloudInitFile, HostConfigurationDynamicValidationError},
+    error::{InvalidInputError, ReportError, ServicingError, TridentError},
+    status::ServicingType,
+};
+
+use crate::{
+    engine::{EngineContext, Subsystem},
+    io_utils::file_reader::FileReader,
+    subsystems::extensions,
+};
+
+/// Directory of the NoCloud seed read by cloud-init.
+const NOCLOUD_SEED_PATH: &str = "/var/lib/cloud/seed/nocloud";
+
+/// Files that cloud-init requires in a NoCloud seed, written empty when not configured.
+const REQUIRED_SEED_FILES: &[&str] = &["user-data", "meta-data"];
+
+#[derive(Default)]
+pub struct CloudInitSubsystem {
+    /// Files of the NoCloud seed, by name, loaded before entering the target OS.
+    seed_files: Vec<(&'static str, String)>,
+}
+
+/// Returns whether the NoCloud seed must be written into the target OS.
+fn should_write_seed(ctx: &EngineContext) -> bool {
+    matches!(
+        ctx.servicing_type,
+        ServicingType::CleanInstall | ServicingType::AbUpdate
+    ) && ctx.spec.os.cloud_init.is_some()
+}
+
+impl Subsystem for CloudInitSubsystem {
+    fn name(&self) -> &'static str {
+        "cloud-init"
+    }
+
+    fn prepare(&mut self, ctx: &EngineContext) -> Result<(), TridentError> {
+        let Some(cloud_init) = &ctx.spec.os.cloud_init else {
+            return Ok(());
+        };
+        if !should_write_seed(ctx) {
+            return Ok(());
+        }
+
+        let timeout = extensions::connection_timeout(ctx);
+        self.seed_files = cloud_init
+            .files()
+            .map(|(name, file)| {
+                let contents = match file {
+                    CloudInitFile { url: Some(url), .. } => {
+                        load_file(url, timeout).structured(InvalidInputError::from(
+                            HostConfigurationDynamicValidationError::LoadCloudInitFile {
+                                name: name.into(),
+                                url: url.to_string(),
+                            },
+                        ))?
+                    }
+                    CloudInitFile { content, .. } => content.clone().unwrap_or_default(),
+                };
+                Ok((name, contents))
+            })
+            .collect::<Result<_, TridentError>>()?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "cloud_init_configuration", skip_all)]
+    fn configure(&mut self, ctx: &EngineContext) -> Result<(), TridentError> {
+        if !should_write_seed(ctx) {
+            return Ok(());
+        }
+
+        info!("Writing cloud-init NoCloud seed");
+        write_seed(Path::new(NOCLOUD_SEED_PATH), &self.seed_files)
+            .structured(ServicingError::WriteCloudInitSeed)
+    }
+}
+
+/// Reads the file at `url` into a string.
+fn load_file(url: &Url, timeout: Duration) -> Result<String, Error> {
+    let mut contents = String::new();
+    FileReader::new(url, timeout)
+        .context("Failed to create file reader")?
+        .complete_reader()
+        .context("Failed to open file")?
+        .read_to_string(&mut contents)
+        .context("Failed to read file")?;
+    Ok(contents)
+}
+
+/// Writes the NoCloud seed into `seed_dir`, replacing any previous seed. The files required by
+/// cloud-init that are not in `files` are written empty.
+fn write_seed(seed_dir: &Path, files: &[(&str, String)]) -> Result<(), Error> {
+    if seed_dir.exists() {
+        fs::remove_dir_all(seed_dir)
+            .with_context(|| format!("Failed to remove '{}'", seed_dir.display()))?;
+    }
+    fs::create_dir_all(seed_dir)
+        .with_context(|| format!("Failed to create directory '{}'", seed_dir.display()))?;
+
+    let missing = REQUIRED_SEED_FILES
+        .iter()
+        .filter(|required| !files.iter().any(|(name, _)| name == *required))
+        .map(|required| (*required, ""));
+    for (name, contents) in files
+        .iter()
+        .map(|(name, contents)| (*name, contents.as_str()))
+        .chain(missing)
+    {
+        let path = seed_dir.join(name);
+        debug!("Writing cloud-init file '{}'", path.display());
+        fs::write(&path, contents)
+            .with_context(|| format!("Failed to write '{}'", path.display()))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_seed() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let seed_dir = temp_dir.path().join("nocloud");
+        fs::create_dir_all(&seed_dir).unwrap();
+        fs::write(seed_dir.join("network-config"), "previous").unwrap();
+
+        write_seed(
+            &seed_dir,
+            &[("user-data", "#cloud-config\nhostname: edge\n".into())],
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(seed_dir.join("user-data")).unwrap(),
+            "#cloud-config\nhostname: edge\n"
+        );
+        assert_eq!(fs::read_to_string(seed_dir.join("meta-data")).unwrap(), "");
+        // The previous seed is replaced.
+        assert!(!seed_dir.join("network-config").exists());
+    }
+}
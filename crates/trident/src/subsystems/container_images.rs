@@ -0,0 +1,248 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, Permissions},
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, ensure, Context, Error};
+use log::{debug, info, warn};
+use sys_mount::{MountBuilder, MountFlags, UnmountFlags};
+
+use osutils::{dependencies::Dependency, path::join_relative, podman};
+use trident_api::{
+    config::{ContainerImage, HostConfigurationDynamicValidationError},
+    error::{InvalidInputError, ReportError, ServicingError, TridentError},
+    status::ServicingType,
+};
+
+use crate::{
+    engine::{EngineContext, Subsystem},
+    secrets,
+};
+
+/// Directory in the target OS where the archives of container images are mounted while they are
+/// loaded.
+///
+/// It is under `/run`, which is a tmpfs in the new root, so nothing is left behind in the target
+/// OS.
+const CONTAINER_IMAGE_ARCHIVES_MOUNT_PATH: &str = "/run/trident-container-images";
+
+/// Path in the target OS of the auth file holding the registry credentials of the images, in the
+/// same tmpfs as the archives.
+const REGISTRY_AUTH_FILE_PATH: &str = "/run/trident-container-images/auth.json";
+
+#[derive(Default)]
+pub struct ContainerImagesSubsystem {
+    /// Paths in the target OS of the archives mounted by `provision`, by image.
+    mounted_archives: BTreeMap<String, PathBuf>,
+
+    /// Path in the target OS of the registry auth file written by `provision`, if any.
+    auth_file: Option<PathBuf>,
+}
+
+/// Returns whether container images must be preloaded into the target OS.
+fn should_preload_images(ctx: &EngineContext) -> bool {
+    matches!(
+        ctx.servicing_type,
+        ServicingType::CleanInstall | ServicingType::AbUpdate
+    ) && !ctx.spec.os.container_images.is_empty()
+}
+
+impl Subsystem for ContainerImagesSubsystem {
+    fn name(&self) -> &'static str {
+        "container-images"
+    }
+
+    fn validate_host_config(&self, ctx: &EngineContext) -> Result<(), TridentError> {
+        if !should_preload_images(ctx) {
+            return Ok(());
+        }
+
+        for container_image in &ctx.spec.os.container_images {
+            if let Some(archive) = &container_image.archive {
+                if !archive.is_file() {
+                    return Err(TridentError::new(InvalidInputError::from(
+                        HostConfigurationDynamicValidationError::ContainerImageArchiveNotFound {
+                            image: container_image.image.clone(),
+                            path: archive.display().to_string(),
+                        },
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn provision(&mut self, ctx: &EngineContext, mount_path: &Path) -> Result<(), TridentError> {
+        if !should_preload_images(ctx) {
+            return Ok(());
+        }
+
+        for (index, container_image) in ctx.spec.os.container_images.iter().enumerate() {
+            let Some(archive) = &container_image.archive else {
+                continue;
+            };
+
+            let path = Path::new(CONTAINER_IMAGE_ARCHIVES_MOUNT_PATH).join(format!("{index}.tar"));
+            mount_archive(archive, &join_relative(mount_path, &path)).structured(
+                ServicingError::MountContainerImageArchive {
+                    image: container_image.image.clone(),
+                    path: archive.display().to_string(),
+                },
+            )?;
+            self.mounted_archives
+                .insert(container_image.image.clone(), path);
+        }
+
+        // Registry credentials may reference secrets of the servicing OS, so they are resolved
+        // before entering the target OS.
+        let credentials = ctx
+            .spec
+            .os
+            .container_images
+            .iter()
+            .filter(|container_image| container_image.archive.is_none())
+            .filter_map(|container_image| {
+                let credentials = container_image.registry_credentials.as_ref()?;
+                Some(secrets::resolve(&credentials.password).map(|password| {
+                    (
+                        podman::registry_of(&container_image.image),
+                        credentials.username.as_str(),
+                        password,
+                    )
+                }))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if !credentials.is_empty() {
+            let path = Path::new(REGISTRY_AUTH_FILE_PATH);
+            write_auth_file(
+                &join_relative(mount_path, path),
+                &podman::render_auth_file(credentials.iter().map(
+                    |(registry, username, password)| (*registry, *username, password.as_str()),
+                )),
+            )
+            .structured(ServicingError::PreloadContainerImages)?;
+            self.auth_file = Some(path.into());
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "container_images_preload", skip_all)]
+    fn configure(&mut self, ctx: &EngineContext) -> Result<(), TridentError> {
+        if !should_preload_images(ctx) {
+            return Ok(());
+        }
+
+        let result = preload_images(
+            &ctx.spec.os.container_images,
+            &self.mounted_archives,
+            self.auth_file.as_deref(),
+        );
+
+        if let Some(path) = self.auth_file.take() {
+            if let Err(e) = fs::remove_file(&path) {
+                warn!(
+                    "Failed to remove registry auth file '{}': {e}",
+                    path.display()
+                );
+            }
+        }
+
+        // Unmount the archives even if loading failed, so that the new root can be unmounted.
+        for path in std::mem::take(&mut self.mounted_archives).into_values() {
+            debug!("Unmounting container image archive '{}'", path.display());
+            if let Err(e) = sys_mount::unmount(&path, UnmountFlags::DETACH) {
+                warn!(
+                    "Failed to unmount container image archive '{}': {e}",
+                    path.display()
+                );
+            }
+        }
+
+        result.structured(ServicingError::PreloadContainerImages)
+    }
+}
+
+/// Bind mounts the archive of a container image at `target`.
+fn mount_archive(archive: &Path, target: &Path) -> Result<(), Error> {
+    debug!(
+        "Mounting container image archive '{}' at '{}'",
+        archive.display(),
+        target.display()
+    );
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+    }
+    // The target of a bind mount of a file must be an existing file.
+    File::create(target)
+        .with_context(|| format!("Failed to create file '{}'", target.display()))?;
+    MountBuilder::default()
+        .flags(MountFlags::BIND)
+        .mount(archive, target)
+        .with_context(|| {
+            format!(
+                "Failed to bind mount '{}' to '{}'",
+                archive.display(),
+                target.display()
+            )
+        })?;
+
+    Ok(())
+}
+
+/// Writes the registry auth file `contents` at `path`, readable by root only.
+fn write_auth_file(path: &Path, contents: &str) -> Result<(), Error> {
+    debug!("Writing registry auth file '{}'", path.display());
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+    }
+    // Restrict the permissions before writing the credentials.
+    File::create(path)
+        .with_context(|| format!("Failed to create file '{}'", path.display()))?
+        .set_permissions(Permissions::from_mode(0o600))
+        .with_context(|| format!("Failed to set permissions of '{}'", path.display()))?;
+    fs::write(path, contents).with_context(|| format!("Failed to write '{}'", path.display()))
+}
+
+/// Preloads `container_images` into the containers-storage of the running OS, loading the images
+/// with an archive from its path in `archives` and pulling the others with the registry
+/// credentials of `auth_file`.
+fn preload_images(
+    container_images: &[ContainerImage],
+    archives: &BTreeMap<String, PathBuf>,
+    auth_file: Option<&Path>,
+) -> Result<(), Error> {
+    if !Dependency::Podman.exists() {
+        bail!("Podman is not installed in the target OS");
+    }
+
+    for container_image in container_images {
+        let image = container_image.image.as_str();
+        if podman::image_exists(image)? {
+            debug!("Container image '{image}' already exists, skipping");
+            continue;
+        }
+
+        match archives.get(image) {
+            Some(archive) => {
+                info!("Loading container image '{image}' from its archive");
+                podman::load_images(archive)?;
+                ensure!(
+                    podman::image_exists(image)?,
+                    "Archive of container image '{image}' does not contain it"
+                );
+            }
+            None => {
+                info!("Pulling container image '{image}'");
+                podman::pull_image(image, auth_file)?;
+            }
+        }
+    }
+
+    Ok(())
+}
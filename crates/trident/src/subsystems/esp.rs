@@ -5,7 +5,7 @@ use std::{
 };
 
 use anyhow::{bail, ensure, Context, Error};
-use log::{debug, trace};
+use log::{debug, trace, warn};
 use reqwest::Url;
 use tempfile::{NamedTempFile, TempDir};
 
@@ -19,8 +19,8 @@ use trident_api::{
     config::UefiFallbackMode,
     constants::{
         internal_params::DISABLE_GRUB_NOPREFIX_CHECK, EFI_DEFAULT_BIN_DIRECTORY,
-        EFI_DEFAULT_BIN_RELATIVE_PATH, ESP_EFI_DIRECTORY, ESP_RELATIVE_MOUNT_POINT_PATH,
-        GRUB2_CONFIG_FILENAME, GRUB2_CONFIG_RELATIVE_PATH,
+        EFI_DEFAULT_BIN_RELATIVE_PATH, ESP_EFI_DIRECTORY, ESP_MOUNT_POINT_PATH,
+        ESP_RELATIVE_MOUNT_POINT_PATH, GRUB2_CONFIG_FILENAME, GRUB2_CONFIG_RELATIVE_PATH,
     },
     error::{ReportError, ServicingError, TridentError, TridentResultExt},
     status::{AbVolumeSelection, ServicingState, ServicingType},
@@ -29,6 +29,7 @@ use trident_api::{
 use crate::{
     engine::{
         boot::{self, uki, ESP_EXTRACTION_DIRECTORY},
+        space_forecast::{self, SpaceDemand},
         EngineContext, Subsystem,
     },
     io_utils::{
@@ -44,6 +45,20 @@ impl Subsystem for EspSubsystem {
         "esp"
     }
 
+    fn forecast_space(&self, ctx: &EngineContext) -> Vec<SpaceDemand> {
+        // Only A/B updates add boot files to the ESP of the servicing OS.
+        if ctx.servicing_type != ServicingType::AbUpdate {
+            return Vec::new();
+        }
+        match forecast_boot_files(ctx) {
+            Ok(demand) => vec![demand],
+            Err(e) => {
+                warn!("Failed to forecast the space needed for boot files on the ESP: {e:?}");
+                Vec::new()
+            }
+        }
+    }
+
     #[tracing::instrument(name = "esp_provision", skip_all)]
     fn provision(&mut self, ctx: &EngineContext, mount_path: &Path) -> Result<(), TridentError> {
         // Perform file-based deployment of ESP images, if needed, after filesystems have been
@@ -56,6 +71,44 @@ impl Subsystem for EspSubsystem {
     }
 }
 
+/// Estimates the space needed on the ESP of the servicing OS for the boot files of an A/B update.
+///
+/// The OS image only records the size of the whole ESP filesystem image, so the boot files of the
+/// active volume are used as an estimate of those of the update volume. The boot files already in
+/// the directory of the update volume are removed before the new ones are copied.
+fn forecast_boot_files(ctx: &EngineContext) -> Result<SpaceDemand, Error> {
+    let esp_path = path::join_relative(space_forecast::host_root()?, ESP_MOUNT_POINT_PATH);
+    let esp_efi_path = esp_path.join(ESP_EFI_DIRECTORY);
+    let update_dir = esp_efi_path
+        .join(boot::get_update_esp_dir_name(ctx).context("Failed to get ESP directory name")?);
+    let active_dir = esp_efi_path.join(boot::make_esp_dir_name(
+        ctx.install_index,
+        ctx.ab_active_volume.unwrap_or(AbVolumeSelection::VolumeA),
+    ));
+
+    let mut bytes = space_forecast::directory_size(&active_dir)?;
+    if ctx.is_uki().unstructured("UKI setting unknown")? {
+        // A new UKI is staged next to the UKI of the active volume.
+        bytes += fs::read_dir(esp_path.join(uki::UKI_DIRECTORY))
+            .context("Failed to read UKI directory")?
+            .filter_map(|entry| entry.ok()?.metadata().ok())
+            .map(|metadata| metadata.len())
+            .max()
+            .unwrap_or_default();
+    }
+    let bytes = bytes.saturating_sub(space_forecast::directory_size(&update_dir)?);
+    debug!(
+        "Forecast {bytes} bytes of boot files in '{}'",
+        update_dir.display()
+    );
+
+    Ok(SpaceDemand {
+        path: update_dir,
+        artifact: "boot files".into(),
+        bytes,
+    })
+}
+
 /// Configures UEFI fallback by copying boot files to the UEFI fallback folder
 /// based on the UEFI fallback mode and servicing type.
 ///
@@ -94,6 +147,75 @@ pub fn set_uefi_fallback_contents(
     Ok(())
 }
 
+/// Copies the contents of the ESP mounted at `esp_path` to the ESP mirrors of the Host
+/// Configuration, so that each mirror can boot the host on its own.
+///
+/// Each mirror is mounted on a temporary directory and its previous contents are replaced.
+pub fn sync_esp_mirrors(ctx: &EngineContext, esp_path: &Path) -> Result<(), Error> {
+    for mirror_id in &ctx.spec.storage.esp_mirrors {
+        debug!(
+            "Synchronizing ESP mirror '{mirror_id}' with '{}'",
+            esp_path.display()
+        );
+        let device_path = ctx
+            .get_block_device_path(mirror_id)
+            .with_context(|| format!("Failed to find device path of ESP mirror '{mirror_id}'"))?;
+
+        let temp_dir = TempDir::new().context("Failed to create a temporary mount directory")?;
+        mount::mount(
+            &device_path,
+            temp_dir.path(),
+            MountFileSystemType::Vfat,
+            &["umask=0077".into()],
+        )
+        .with_context(|| format!("Failed to mount ESP mirror '{mirror_id}'"))?;
+        let _mount_guard = MountGuard {
+            mount_dir: temp_dir.path(),
+        };
+
+        osutils::files::clean_directory(temp_dir.path())
+            .with_context(|| format!("Failed to clean ESP mirror '{mirror_id}'"))?;
+        copy_directory(esp_path, temp_dir.path())
+            .with_context(|| format!("Failed to copy ESP to ESP mirror '{mirror_id}'"))?;
+    }
+
+    Ok(())
+}
+
+/// Recursively copies the contents of `from_dir` into `to_dir`.
+fn copy_directory(from_dir: &Path, to_dir: &Path) -> Result<(), Error> {
+    for entry in fs::read_dir(from_dir)
+        .with_context(|| format!("Failed to read directory '{}'", from_dir.display()))?
+    {
+        let entry = entry.context("Failed to read entry")?;
+        let to_path = to_dir.join(entry.file_name());
+        if entry
+            .file_type()
+            .context("Failed to get file type")?
+            .is_dir()
+        {
+            fs::create_dir_all(&to_path)
+                .with_context(|| format!("Failed to create directory '{}'", to_path.display()))?;
+            copy_directory(&entry.path(), &to_path)?;
+        } else {
+            trace!(
+                "Copying file '{}' to '{}'",
+                entry.path().display(),
+                to_path.display()
+            );
+            fs::copy(entry.path(), &to_path).with_context(|| {
+                format!(
+                    "Failed to copy file '{}' to '{}'",
+                    entry.path().display(),
+                    to_path.display()
+                )
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
 /// Performs file-based deployment of ESP images from the OS image.
 fn deploy_esp(ctx: &EngineContext, mount_point: &Path) -> Result<(), Error> {
     trace!("Deploying ESP from OS image");
@@ -236,6 +358,7 @@ fn copy_file_artifacts(
     if ctx.is_uki().unstructured("UKI setting unknown")? {
         // Prepare ESP directory structure for UKI boot
         uki::prepare_esp_for_uki(mount_point)?;
+        uki::configure_boot_menu(mount_point, ctx.spec.os.boot_menu.as_ref())?;
 
         // Copy the UKI from the image into the ESP directory
         uki::stage_uki_on_esp(temp_mount_dir, mount_point)?;
@@ -1072,6 +1195,25 @@ mod tests {
         validate_fallback(&ctx, ServicingState::AbUpdateStaged, &file_names, "AZLA");
     }
 
+    #[test]
+    fn test_copy_directory() {
+        let from_dir = TempDir::new().unwrap();
+        let to_dir = TempDir::new().unwrap();
+        fs::create_dir_all(from_dir.path().join("EFI/AZLA")).unwrap();
+        fs::write(from_dir.path().join("EFI/AZLA/bootx64.efi"), "boot").unwrap();
+        fs::write(from_dir.path().join("loader.conf"), "timeout 0").unwrap();
+
+        copy_directory(from_dir.path(), to_dir.path()).unwrap();
+        assert_eq!(
+            fs::read_to_string(to_dir.path().join("EFI/AZLA/bootx64.efi")).unwrap(),
+            "boot"
+        );
+        assert_eq!(
+            fs::read_to_string(to_dir.path().join("loader.conf")).unwrap(),
+            "timeout 0"
+        );
+    }
+
     #[test]
     fn test_simple_copy_boot_files() {
         let from_dir = TempDir::new().unwrap();
@@ -0,0 +1,313 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use anyhow::{Context, Error};
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use serde::Serialize;
+
+use osutils::sysext::{self, ExtensionKind};
+use trident_api::config::{ExtensionScope, HostConfiguration};
+
+use super::status;
+
+/// Log of the extension images merged into and unmerged from the running OS, with one JSON record
+/// per line. It is kept across servicing, so it covers the whole life of the OS.
+const AUDIT_LOG_PATH: &str = "/var/log/trident-extensions-audit.jsonl";
+
+/// Extension images merged into the running OS, by name, with their ID and version.
+pub(super) type MergedImages = BTreeMap<String, (Option<String>, Option<String>)>;
+
+/// Change to the extension images merged into the running OS.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum AuditAction {
+    Merge,
+    Unmerge,
+}
+
+/// Whether an extension image ended up in the state Trident asked for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+/// Record of the audit log.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AuditRecord {
+    timestamp: DateTime<Utc>,
+
+    /// Kind of the image, `sysext` or `confext`.
+    kind: String,
+
+    /// Name of the image, i.e. its file name without `.raw`.
+    name: String,
+
+    /// SYSEXT_ID or CONFEXT_ID of the image, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+
+    /// SYSEXT_VERSION_ID or CONFEXT_VERSION_ID of the image, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+
+    action: AuditAction,
+
+    outcome: AuditOutcome,
+
+    /// Error that prevented the image from being merged.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Returns the extension images of `kind` merged into the running OS. Failures are only logged,
+/// as the audit log does not affect the merged images.
+pub(super) fn merged_images(kind: ExtensionKind) -> MergedImages {
+    let hierarchies = match sysext::status(kind) {
+        Ok(hierarchies) => hierarchies,
+        Err(e) => {
+            warn!("Failed to get merged {kind} images for the audit log: {e:?}");
+            return MergedImages::new();
+        }
+    };
+    hierarchies
+        .into_iter()
+        .flat_map(|hierarchy| hierarchy.extensions)
+        .map(|name| {
+            let release = status::read_merged_release(kind, &name).unwrap_or_default();
+            let id = match kind {
+                ExtensionKind::Sysext => release.sysext_id,
+                ExtensionKind::Confext => release.confext_id,
+            };
+            (name, (id, release.version_id))
+        })
+        .collect()
+}
+
+/// Appends the changes from the extension images of `kind` merged `before` a refresh to those
+/// merged `after` it to the audit log. When the refresh failed with `error`, the images of
+/// `host_config` that are not merged are recorded as failed merges.
+pub(super) fn record(
+    kind: ExtensionKind,
+    before: &MergedImages,
+    after: &MergedImages,
+    host_config: &HostConfiguration,
+    error: Option<String>,
+) {
+    let configured = match kind {
+        ExtensionKind::Sysext => &host_config.os.sysexts,
+        ExtensionKind::Confext => &host_config.os.confexts,
+    }
+    .iter()
+    .filter(|ext| ext.scope == ExtensionScope::System)
+    .filter_map(|ext| ext.path.as_deref()?.file_stem())
+    .map(|name| name.to_string_lossy().into_owned())
+    .collect::<Vec<_>>();
+
+    let records = audit(kind, before, after, &configured, error, Utc::now());
+    if records.is_empty() {
+        return;
+    }
+    if let Err(e) = append(Path::new(AUDIT_LOG_PATH), &records) {
+        warn!("Failed to write to extension audit log '{AUDIT_LOG_PATH}': {e:?}");
+    }
+}
+
+/// Returns the audit records of the changes from `before` to `after`, followed by failed merges of
+/// the `configured` images that are not merged if `error` is set.
+fn audit(
+    kind: ExtensionKind,
+    before: &MergedImages,
+    after: &MergedImages,
+    configured: &[String],
+    error: Option<String>,
+    now: DateTime<Utc>,
+) -> Vec<AuditRecord> {
+    let record =
+        |name: &str, (id, version): &(Option<String>, Option<String>), action| AuditRecord {
+            timestamp: now,
+            kind: kind.to_string(),
+            name: name.to_string(),
+            id: id.clone(),
+            version: version.clone(),
+            action,
+            outcome: AuditOutcome::Success,
+            error: None,
+        };
+
+    // An image whose ID or version changed was replaced, so it is unmerged and merged again.
+    let mut records = before
+        .iter()
+        .filter(|(name, image)| after.get(*name) != Some(*image))
+        .map(|(name, image)| record(name, image, AuditAction::Unmerge))
+        .collect::<Vec<_>>();
+    records.extend(
+        after
+            .iter()
+            .filter(|(name, image)| before.get(*name) != Some(*image))
+            .map(|(name, image)| record(name, image, AuditAction::Merge)),
+    );
+
+    if let Some(error) = error {
+        records.extend(
+            configured
+                .iter()
+                .filter(|name| !after.contains_key(*name))
+                .map(|name| AuditRecord {
+                    outcome: AuditOutcome::Failure,
+                    error: Some(error.clone()),
+                    ..record(name, &(None, None), AuditAction::Merge)
+                }),
+        );
+    }
+    records
+}
+
+/// Appends `records` to the audit log at `path`, one JSON record per line.
+fn append(path: &Path, records: &[AuditRecord]) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+    }
+    let mut lines = String::new();
+    for record in records {
+        debug!(
+            "Recording {:?} of {} image '{}' in the audit log",
+            record.action, record.kind, record.name
+        );
+        lines += &serde_json::to_string(record).context("Failed to serialize audit record")?;
+        lines.push('\n');
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(lines.as_bytes()))
+        .with_context(|| format!("Failed to append to '{}'", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    fn images(images: &[(&str, &str)]) -> MergedImages {
+        images
+            .iter()
+            .map(|(name, version)| {
+                (
+                    name.to_string(),
+                    (Some(name.to_string()), Some(version.to_string())),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_audit() {
+        let now = Utc::now();
+        let before = images(&[("debug", "1"), ("docker", "27"), ("tools", "3")]);
+        let after = images(&[("debug", "1"), ("docker", "28"), ("monitoring", "5")]);
+        let actions = |records: Vec<AuditRecord>| {
+            records
+                .into_iter()
+                .map(|record| (record.name, record.version, record.action, record.outcome))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            actions(audit(
+                ExtensionKind::Sysext,
+                &before,
+                &after,
+                &["monitoring".into()],
+                None,
+                now
+            )),
+            vec![
+                (
+                    "docker".to_string(),
+                    Some("27".to_string()),
+                    AuditAction::Unmerge,
+                    AuditOutcome::Success
+                ),
+                (
+                    "tools".to_string(),
+                    Some("3".to_string()),
+                    AuditAction::Unmerge,
+                    AuditOutcome::Success
+                ),
+                (
+                    "docker".to_string(),
+                    Some("28".to_string()),
+                    AuditAction::Merge,
+                    AuditOutcome::Success
+                ),
+                (
+                    "monitoring".to_string(),
+                    Some("5".to_string()),
+                    AuditAction::Merge,
+                    AuditOutcome::Success
+                ),
+            ]
+        );
+
+        // Images of the Host Configuration that are not merged after a failed refresh are
+        // recorded as failed merges.
+        let records = audit(
+            ExtensionKind::Sysext,
+            &before,
+            &before,
+            &["debug".into(), "monitoring".into()],
+            Some("incompatible".into()),
+            now,
+        );
+        assert_eq!(
+            records,
+            vec![AuditRecord {
+                timestamp: now,
+                kind: "sysext".into(),
+                name: "monitoring".into(),
+                id: None,
+                version: None,
+                action: AuditAction::Merge,
+                outcome: AuditOutcome::Failure,
+                error: Some("incompatible".into()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_append() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("log/audit.jsonl");
+        let record = AuditRecord {
+            timestamp: DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+                .unwrap()
+                .into(),
+            kind: "sysext".into(),
+            name: "debug".into(),
+            id: Some("debug".into()),
+            version: Some("1".into()),
+            action: AuditAction::Merge,
+            outcome: AuditOutcome::Success,
+            error: None,
+        };
+        append(&path, &[record.clone()]).unwrap();
+        append(&path, &[record]).unwrap();
+
+        let line = r#"{"timestamp":"2025-01-01T00:00:00Z","kind":"sysext","name":"debug","id":"debug","version":"1","action":"merge","outcome":"success"}"#;
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("{line}\n{line}\n")
+        );
+    }
+}
@@ -0,0 +1,337 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read},
+    os::unix::fs::symlink,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Error};
+use log::{debug, warn};
+use tempfile::NamedTempFile;
+
+use osutils::{mkfs, path};
+use trident_api::config::{Extension, ExtensionSource};
+
+use crate::io_utils::{
+    file_reader::FileReader, hashing_reader::compute_file_hash, progress_reader::ProgressReader,
+};
+
+use super::{
+    ExtensionType, CONFEXT_EXTENSION_RELEASE_DIRECTORY, EXTENSION_RELEASE,
+    SYSEXT_EXTENSION_RELEASE_DIRECTORY,
+};
+
+/// Extensions of tarballs, stripped from the last segment of their URL to name the image.
+const TARBALL_EXTENSIONS: [&str; 5] = [".tar.gz", ".tar.zst", ".tgz", ".tzst", ".tar"];
+
+/// Magic numbers of the compression formats of tarballs.
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Builds the extension image of `ext` from the directory tree or tarball at its URL into a new
+/// file in `staging_dir`, generating its extension-release file if the tree does not have one,
+/// and verifies the hash of the image. Returns the path of the image, which is removed on failure.
+pub(super) fn fetch(
+    ext: &Extension,
+    ext_type: &ExtensionType,
+    timeout: Duration,
+    staging_dir: &Path,
+) -> Result<PathBuf, Error> {
+    // Trees are unpacked or copied into the staging directory, which is removed along with them
+    // once the images are in place.
+    let tree = tempfile::Builder::new()
+        .prefix(".tree-")
+        .tempdir_in(staging_dir)
+        .context("Failed to create temporary directory")?;
+    let source_dir = match ext.source {
+        ExtensionSource::Directory => ext
+            .url
+            .to_file_path()
+            .ok()
+            .with_context(|| format!("Failed to get path of directory tree '{}'", ext.url))?,
+        ExtensionSource::Tarball => {
+            unpack(ext, timeout, tree.path())?;
+            tree.path().to_path_buf()
+        }
+        ExtensionSource::Image => bail!("Extension image '{}' is not a directory tree", ext.url),
+    };
+
+    let source_dir = if has_extension_release(&source_dir, ext_type)? {
+        source_dir
+    } else {
+        // The directory tree of the user is left untouched, so the extension-release file is
+        // generated in a copy of it.
+        if source_dir != tree.path() {
+            copy_tree(&source_dir, tree.path())?;
+        }
+        write_extension_release(tree.path(), &image_name(ext)?, ext_type)?;
+        tree.path().to_path_buf()
+    };
+
+    let temp_file: PathBuf = NamedTempFile::new_in(staging_dir)
+        .context("Failed to create temporary file")?
+        .into_temp_path()
+        .keep()
+        .context("Failed to persist temporary file")?;
+    debug!(
+        "Building extension image '{}' from '{}'",
+        temp_file.display(),
+        ext.url
+    );
+    let result = mkfs::erofs_image(&source_dir, &temp_file).and_then(|()| {
+        let (_, computed_sha384) = compute_file_hash(&temp_file)
+            .context("Failed to calculate SHA384 of extension image")?;
+        if ext.sha384 != computed_sha384 {
+            bail!(
+                "SHA384 mismatch for extension image built from '{}': expected {}, got {}",
+                ext.url,
+                ext.sha384,
+                computed_sha384
+            )
+        }
+        Ok(())
+    });
+
+    if let Err(e) = result {
+        if let Err(remove_err) = fs::remove_file(&temp_file) {
+            warn!(
+                "Failed to remove partially built extension image '{}': {remove_err}",
+                temp_file.display()
+            );
+        }
+        return Err(e);
+    }
+
+    Ok(temp_file)
+}
+
+/// Downloads the tarball of `ext` and unpacks it into `target_dir`. Tarballs compressed with gzip
+/// or zstd are decompressed.
+fn unpack(ext: &Extension, timeout: Duration, target_dir: &Path) -> Result<(), Error> {
+    debug!("Downloading extension tarball from '{}'", ext.url);
+    let file_reader = FileReader::new(&ext.url, timeout).context("Failed to create file reader")?;
+    let mut reader = BufReader::new(ProgressReader::new(
+        file_reader
+            .complete_reader()
+            .context("Failed to create complete file reader")?,
+        format!("Downloading extension tarball '{}'", ext.url),
+        file_reader.size(),
+    ));
+
+    let magic = reader
+        .fill_buf()
+        .context("Failed to read extension tarball")?;
+    let reader: Box<dyn Read> = if magic.starts_with(GZIP_MAGIC) {
+        Box::new(flate2::read::GzDecoder::new(reader))
+    } else if magic.starts_with(ZSTD_MAGIC) {
+        Box::new(
+            zstd::stream::read::Decoder::with_buffer(reader)
+                .context("Failed to create zstd decoder")?,
+        )
+    } else {
+        Box::new(reader)
+    };
+
+    let mut archive = tar::Archive::new(reader);
+    archive.set_preserve_permissions(true);
+    archive.unpack(target_dir).with_context(|| {
+        format!(
+            "Failed to unpack extension tarball '{}' into '{}'",
+            ext.url,
+            target_dir.display()
+        )
+    })
+}
+
+/// Returns whether the directory tree at `tree` has an extension-release file for `ext_type`.
+fn has_extension_release(tree: &Path, ext_type: &ExtensionType) -> Result<bool, Error> {
+    let release_dir = path::join_relative(tree, release_directory(ext_type));
+    if !release_dir.is_dir() {
+        return Ok(false);
+    }
+    let prefix = format!("{EXTENSION_RELEASE}.");
+    for entry in fs::read_dir(&release_dir)
+        .with_context(|| format!("Failed to read directory '{}'", release_dir.display()))?
+    {
+        let entry = entry
+            .with_context(|| format!("Failed to read directory '{}'", release_dir.display()))?;
+        if entry.file_name().to_string_lossy().starts_with(&prefix) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Writes an extension-release file for the image named `name` into the directory tree at `tree`,
+/// which matches any OS.
+fn write_extension_release(tree: &Path, name: &str, ext_type: &ExtensionType) -> Result<(), Error> {
+    let release_dir = path::join_relative(tree, release_directory(ext_type));
+    fs::create_dir_all(&release_dir)
+        .with_context(|| format!("Failed to create directory '{}'", release_dir.display()))?;
+    let id_key = match ext_type {
+        ExtensionType::Sysext => "SYSEXT_ID",
+        ExtensionType::Confext => "CONFEXT_ID",
+    };
+    let release_path = release_dir.join(format!("{EXTENSION_RELEASE}.{name}"));
+    debug!(
+        "Generating extension-release file '{}'",
+        release_path.display()
+    );
+    fs::write(&release_path, format!("ID=_any\n{id_key}={name}\n"))
+        .with_context(|| format!("Failed to write '{}'", release_path.display()))
+}
+
+/// Returns the directory holding the extension-release file of extension images of `ext_type`.
+fn release_directory(ext_type: &ExtensionType) -> &'static str {
+    match ext_type {
+        ExtensionType::Sysext => SYSEXT_EXTENSION_RELEASE_DIRECTORY,
+        ExtensionType::Confext => CONFEXT_EXTENSION_RELEASE_DIRECTORY,
+    }
+}
+
+/// Returns the name of the extension image built for `ext`: the file name of its path without
+/// `.raw`, or the last segment of its URL without its tarball extension.
+fn image_name(ext: &Extension) -> Result<String, Error> {
+    if let Some(stem) = ext.path.as_deref().and_then(Path::file_stem) {
+        return Ok(stem.to_string_lossy().into_owned());
+    }
+    let segment = ext
+        .url
+        .path_segments()
+        .and_then(|mut segments| segments.rfind(|segment| !segment.is_empty()))
+        .with_context(|| format!("Failed to get name of extension image '{}'", ext.url))?;
+    Ok(TARBALL_EXTENSIONS
+        .iter()
+        .find_map(|extension| segment.strip_suffix(extension))
+        .unwrap_or(segment)
+        .to_string())
+}
+
+/// Copies the directory tree at `from` into the existing directory `to`, keeping symlinks and
+/// permissions.
+fn copy_tree(from: &Path, to: &Path) -> Result<(), Error> {
+    for entry in fs::read_dir(from)
+        .with_context(|| format!("Failed to read directory '{}'", from.display()))?
+    {
+        let entry =
+            entry.with_context(|| format!("Failed to read directory '{}'", from.display()))?;
+        let source = entry.path();
+        let target = to.join(entry.file_name());
+        let metadata = fs::symlink_metadata(&source)
+            .with_context(|| format!("Failed to read metadata of '{}'", source.display()))?;
+        let file_type = metadata.file_type();
+        if file_type.is_dir() {
+            fs::create_dir(&target)
+                .with_context(|| format!("Failed to create directory '{}'", target.display()))?;
+            copy_tree(&source, &target)?;
+            fs::set_permissions(&target, metadata.permissions())
+                .with_context(|| format!("Failed to set permissions of '{}'", target.display()))?;
+        } else if file_type.is_symlink() {
+            let link = fs::read_link(&source)
+                .with_context(|| format!("Failed to read symlink '{}'", source.display()))?;
+            symlink(&link, &target)
+                .with_context(|| format!("Failed to create symlink '{}'", target.display()))?;
+        } else if file_type.is_file() {
+            fs::copy(&source, &target).with_context(|| {
+                format!(
+                    "Failed to copy '{}' to '{}'",
+                    source.display(),
+                    target.display()
+                )
+            })?;
+        } else {
+            bail!(
+                "Unsupported file type of '{}' in extension directory tree",
+                source.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::os::unix::fs::PermissionsExt;
+
+    use tempfile::TempDir;
+    use url::Url;
+
+    use trident_api::primitives::hash::Sha384Hash;
+
+    fn extension(url: &str, path: Option<&str>) -> Extension {
+        Extension {
+            path: path.map(PathBuf::from),
+            source: ExtensionSource::Tarball,
+            ..Extension::new(Url::parse(url).unwrap(), Sha384Hash::from("a".repeat(96)))
+        }
+    }
+
+    #[test]
+    fn test_image_name() {
+        for (url, path, name) in [
+            ("https://example.com/exts/debug.tar.zst", None, "debug"),
+            ("https://example.com/exts/debug.tgz", None, "debug"),
+            ("file:///srv/exts/debug/", None, "debug"),
+            (
+                "https://example.com/exts/debug.tar",
+                Some("/var/lib/extensions/tools.raw"),
+                "tools",
+            ),
+        ] {
+            assert_eq!(image_name(&extension(url, path)).unwrap(), name);
+        }
+    }
+
+    #[test]
+    fn test_extension_release() {
+        let tree = TempDir::new().unwrap();
+        assert!(!has_extension_release(tree.path(), &ExtensionType::Sysext).unwrap());
+
+        write_extension_release(tree.path(), "debug", &ExtensionType::Sysext).unwrap();
+        assert!(has_extension_release(tree.path(), &ExtensionType::Sysext).unwrap());
+        assert!(!has_extension_release(tree.path(), &ExtensionType::Confext).unwrap());
+        assert_eq!(
+            fs::read_to_string(
+                tree.path()
+                    .join("usr/lib/extension-release.d/extension-release.debug")
+            )
+            .unwrap(),
+            "ID=_any\nSYSEXT_ID=debug\n"
+        );
+    }
+
+    #[test]
+    fn test_copy_tree() {
+        let from = TempDir::new().unwrap();
+        let to = TempDir::new().unwrap();
+        fs::create_dir_all(from.path().join("usr/bin")).unwrap();
+        fs::write(from.path().join("usr/bin/tool"), "tool").unwrap();
+        fs::set_permissions(
+            from.path().join("usr/bin/tool"),
+            fs::Permissions::from_mode(0o755),
+        )
+        .unwrap();
+        symlink("tool", from.path().join("usr/bin/alias")).unwrap();
+
+        copy_tree(from.path(), to.path()).unwrap();
+        assert_eq!(
+            fs::read_to_string(to.path().join("usr/bin/tool")).unwrap(),
+            "tool"
+        );
+        assert_eq!(
+            fs::metadata(to.path().join("usr/bin/tool"))
+                .unwrap()
+                .permissions()
+                .mode()
+                & 0o777,
+            0o755
+        );
+        assert_eq!(
+            fs::read_link(to.path().join("usr/bin/alias")).unwrap(),
+            Path::new("tool")
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{bail, ensure, Context, Error};
+use log::{debug, warn};
+use tempfile::NamedTempFile;
+
+use trident_api::config::{Extension, ExtensionDelta};
+
+use crate::io_utils::{
+    file_reader::FileReader, hashing_reader::HashingReader384, image_streamer::stream_and_hash,
+    progress_reader::ProgressReader,
+};
+
+use super::ExtensionType;
+
+/// Largest window, as a power of two, accepted when applying a delta. Deltas of large images are
+/// produced with `--long`, which lets them reference the whole base image.
+const WINDOW_LOG_MAX: u32 = 31;
+
+/// Largest base image a delta can be applied to. The window of a delta cannot span more than this,
+/// so deltas of larger images cannot reference all of their base image.
+const MAX_BASE_SIZE: u64 = 1 << WINDOW_LOG_MAX;
+
+/// Extension images of the running OS that deltas can be applied to, by kind, ID and version.
+pub(super) type DeltaBases = HashMap<(ExtensionType, String, String), PathBuf>;
+
+/// Fetches `ext` by applying the first of its deltas whose base image is in `bases`, into a new
+/// file in `staging_dir`. Returns the path of the image, or `None` if no delta applies to the
+/// images of the running OS or if applying the delta fails, in which case the image should be
+/// downloaded in full.
+pub(super) fn fetch(
+    ext: &Extension,
+    ext_type: &ExtensionType,
+    bases: &DeltaBases,
+    timeout: Duration,
+    staging_dir: &Path,
+) -> Option<PathBuf> {
+    let (delta, base) = ext.deltas.iter().find_map(|delta| {
+        bases
+            .get(&(ext_type.clone(), delta.id.clone(), delta.version.clone()))
+            .map(|base| (delta, base))
+    })?;
+
+    debug!(
+        "Applying delta '{}' to extension image '{}'",
+        delta.url,
+        base.display()
+    );
+    match fetch_delta(ext, delta, base, timeout, staging_dir) {
+        Ok(path) => Some(path),
+        Err(e) => {
+            warn!(
+                "Failed to apply delta '{}', downloading extension image '{}' in full: {e:?}",
+                delta.url, ext.url
+            );
+            None
+        }
+    }
+}
+
+/// Downloads `delta` and applies it to the image at `base` into a new file in `staging_dir`, and
+/// verifies the hash of the result. The file is removed on failure.
+fn fetch_delta(
+    ext: &Extension,
+    delta: &ExtensionDelta,
+    base: &Path,
+    timeout: Duration,
+    staging_dir: &Path,
+) -> Result<PathBuf, Error> {
+    check_base_size(base)?;
+
+    let temp_file: PathBuf = NamedTempFile::new_in(staging_dir)
+        .context("Failed to create temporary file")?
+        .into_temp_path()
+        .keep()
+        .context("Failed to persist temporary file")?;
+
+    let result = (|| -> Result<(), Error> {
+        let file_reader =
+            FileReader::new(&delta.url, timeout).context("Failed to create file reader")?;
+        let reader = ProgressReader::new(
+            file_reader
+                .complete_reader()
+                .context("Failed to create complete file reader")?,
+            format!("Downloading delta '{}'", delta.url),
+            file_reader.size(),
+        );
+        let computed_sha384 = apply(base, reader, &temp_file)?;
+        if ext.sha384 != computed_sha384 {
+            bail!(
+                "SHA384 mismatch for extension image at '{}' built from delta '{}': expected {}, \
+                got {}",
+                ext.url,
+                delta.url,
+                ext.sha384,
+                computed_sha384
+            )
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        if let Err(remove_err) = fs::remove_file(&temp_file) {
+            warn!(
+                "Failed to remove partially built extension image '{}': {remove_err}",
+                temp_file.display()
+            );
+        }
+        return Err(e);
+    }
+
+    Ok(temp_file)
+}
+
+/// Checks that the image at `base` is small enough for a delta to be applied to it, before the
+/// delta is downloaded.
+fn check_base_size(base: &Path) -> Result<(), Error> {
+    let size = fs::metadata(base)
+        .with_context(|| format!("Failed to read metadata of base image '{}'", base.display()))?
+        .len();
+    ensure!(
+        size <= MAX_BASE_SIZE,
+        "Base image '{}' is {size} bytes, larger than the {MAX_BASE_SIZE} bytes deltas can be \
+        applied to",
+        base.display()
+    );
+    Ok(())
+}
+
+/// Applies the delta read from `delta` to the image at `base`, writing the result to `target`,
+/// which must exist. Returns the SHA384 hash of the result.
+///
+/// zstd references the base image as a prefix of the delta, so the base image is read into memory.
+fn apply(base: &Path, delta: impl Read, target: &Path) -> Result<String, Error> {
+    let base = fs::read(base)
+        .with_context(|| format!("Failed to read base image '{}'", base.display()))?;
+    let mut decoder = zstd::stream::read::Decoder::with_ref_prefix(BufReader::new(delta), &base)
+        .context("Failed to create delta decoder")?;
+    decoder
+        .window_log_max(WINDOW_LOG_MAX)
+        .context("Failed to set window size of delta decoder")?;
+    stream_and_hash(HashingReader384::new(decoder), target).context("Failed to apply delta")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    use sha2::{Digest, Sha384};
+
+    #[test]
+    fn test_apply() {
+        let dir = tempfile::tempdir().unwrap();
+        let base: Vec<u8> = (0..1 << 20).map(|i| (i % 251) as u8).collect();
+        let mut image = base.clone();
+        image[1000..1100].fill(0xff);
+        image.extend_from_slice(b"new contents");
+
+        let mut encoder =
+            zstd::stream::write::Encoder::with_ref_prefix(Vec::new(), 19, &base).unwrap();
+        encoder.write_all(&image).unwrap();
+        let delta = encoder.finish().unwrap();
+        assert!(delta.len() < image.len() / 100);
+
+        let base_path = dir.path().join("base.raw");
+        fs::write(&base_path, &base).unwrap();
+        let target = dir.path().join("image.raw");
+        fs::write(&target, b"").unwrap();
+        let sha384 = apply(&base_path, delta.as_slice(), &target).unwrap();
+        assert_eq!(fs::read(&target).unwrap(), image);
+        assert_eq!(sha384, format!("{:x}", Sha384::digest(&image)));
+
+        // A delta cannot be applied to another base image.
+        fs::write(&base_path, vec![0; 1 << 20]).unwrap();
+        assert_ne!(
+            apply(&base_path, delta.as_slice(), &target).ok(),
+            Some(sha384)
+        );
+    }
+
+    #[test]
+    fn test_check_base_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let base_path = dir.path().join("base.raw");
+
+        // Sparse files, so that the test does not need gigabytes of disk space.
+        let base = fs::File::create(&base_path).unwrap();
+        base.set_len(MAX_BASE_SIZE).unwrap();
+        check_base_size(&base_path).unwrap();
+
+        base.set_len(MAX_BASE_SIZE + 1).unwrap();
+        check_base_size(&base_path).unwrap_err();
+
+        check_base_size(&dir.path().join("missing.raw")).unwrap_err();
+    }
+}
@@ -0,0 +1,84 @@
+use std::{
+    fmt::{self, Debug},
+    fs::{self, File, OpenOptions},
+    path::Path,
+};
+
+use anyhow::{Context, Error};
+use log::{debug, info};
+use nix::{errno::Errno, fcntl::Flock, fcntl::FlockArg};
+
+use osutils::path;
+
+/// Lock file guarding the extension images and the versioned store of the running OS. It lives
+/// in /run, so a lock never outlives a boot.
+const LOCK_FILE: &str = "/run/trident/extensions.lock";
+
+/// Advisory lock over the extension images of an OS, held until dropped.
+///
+/// Trident invocations that add, prune, remove, or merge extension images take the lock, so that
+/// two invocations never move images or collect the store from under each other.
+pub(crate) struct ExtensionsLock {
+    _lock: Flock<File>,
+}
+
+impl Debug for ExtensionsLock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtensionsLock").finish_non_exhaustive()
+    }
+}
+
+/// Takes the lock over the extension images of the OS rooted at `root`, waiting for other
+/// Trident invocations holding it.
+pub(crate) fn lock(root: &Path) -> Result<ExtensionsLock, Error> {
+    let lock_path = path::join_relative(root, LOCK_FILE);
+    if let Some(parent) = lock_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("Failed to open lock file '{}'", lock_path.display()))?;
+
+    let lock = match Flock::lock(file, FlockArg::LockExclusiveNonblock) {
+        Ok(lock) => Ok(lock),
+        Err((file, Errno::EWOULDBLOCK)) => {
+            info!("Waiting for another Trident invocation to release the extension images");
+            Flock::lock(file, FlockArg::LockExclusive).map_err(|(_, e)| e)
+        }
+        Err((_, e)) => Err(e),
+    }
+    .with_context(|| format!("Failed to lock '{}'", lock_path.display()))?;
+    debug!("Locked extension images with '{}'", lock_path.display());
+    Ok(ExtensionsLock { _lock: lock })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lock() {
+        let root = TempDir::new().unwrap();
+        let lock_path = path::join_relative(root.path(), LOCK_FILE);
+
+        let held = lock(root.path()).unwrap();
+        assert!(lock_path.exists());
+
+        // The lock is exclusive, so another open file description cannot take it.
+        let other = File::open(&lock_path).unwrap();
+        let (other, e) = Flock::lock(other, FlockArg::LockExclusiveNonblock).unwrap_err();
+        assert_eq!(e, Errno::EWOULDBLOCK);
+
+        // Dropping the lock releases it.
+        drop(held);
+        let relocked = Flock::lock(other, FlockArg::LockExclusiveNonblock).unwrap();
+        drop(relocked);
+        drop(lock(root.path()).unwrap());
+    }
+}
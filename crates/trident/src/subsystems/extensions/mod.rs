@@ -2,31 +2,72 @@ use std::{
     collections::{HashMap, HashSet},
     fmt::Display,
     fs,
+    os::unix::fs::MetadataExt,
     path::{Path, PathBuf},
     time::Duration,
 };
 
-use anyhow::{bail, ensure, Context, Error};
-use log::{trace, warn};
+use anyhow::{bail, Context, Error};
+use log::{debug, error, info, trace, warn};
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
 use tempfile::NamedTempFile;
+use url::Url;
 
-use osutils::{container, dependencies::Dependency, path};
+use osutils::{
+    container,
+    osrelease::{ExtensionRelease, OsRelease},
+    path,
+    sysext::{self, ExtensionKind},
+};
+use sysdefs::arch::SystemArchitecture;
 use trident_api::{
-    config::Extension,
-    constants::internal_params::HTTP_CONNECTION_TIMEOUT_SECONDS,
-    error::{InternalError, ReportError, ServicingError, TridentError, TridentResultExt},
+    config::{
+        Extension, ExtensionMutability, ExtensionSource, HostConfiguration,
+        HostConfigurationDynamicValidationError,
+    },
+    constants::{
+        internal_params::{
+            DISABLE_SPACE_FORECAST, EXTENSION_CONCURRENCY, HTTP_CONNECTION_TIMEOUT_SECONDS,
+        },
+        DEFAULT_CONFEXT_DIRECTORY,
+    },
+    error::{
+        InternalError, InvalidInputError, ReportError, ServicingError, TridentError,
+        TridentResultExt,
+    },
     primitives::hash::Sha384Hash,
     status::ServicingType,
 };
 
 use crate::{
-    engine::{EngineContext, Subsystem},
+    engine::{
+        space_forecast::{self, SpaceDemand},
+        EngineContext, Subsystem,
+    },
     io_utils::{
-        file_reader::FileReader, hashing_reader::HashingReader384, image_streamer::stream_and_hash,
+        file_reader::FileReader,
+        hashing_reader::{HashingReader, HashingReader256, HashingReader384},
+        image_streamer::stream_and_hash,
+        progress_reader::ProgressReader,
     },
 };
 
+mod audit;
+mod convert;
+mod delta;
+pub(crate) mod lock;
+pub(crate) mod prune;
 mod release;
+mod removal;
+mod requirements;
+mod staging;
+pub(crate) mod status;
+mod store;
+mod transaction;
+
+use delta::DeltaBases;
+use lock::ExtensionsLock;
+use transaction::ExtensionTransaction;
 
 /// Extension-release
 const EXTENSION_RELEASE: &str = "extension-release";
@@ -36,8 +77,11 @@ const SYSEXT_EXTENSION_RELEASE_DIRECTORY: &str = "/usr/lib/extension-release.d/"
 /// Expected extension-release directory for confexts
 const CONFEXT_EXTENSION_RELEASE_DIRECTORY: &str = "/etc/extension-release.d/";
 
-/// Temporary directory on target OS for downloading extension images, relative to the newroot mountpoint
-const EXTENSION_IMAGE_STAGING_DIRECTORY: &str = "/var/lib/extensions/.staging";
+/// Default number of extension images downloaded and inspected concurrently.
+const DEFAULT_EXTENSION_CONCURRENCY: usize = 4;
+
+/// Prefix of the SHA256 digests of blobs in OCI registries.
+const OCI_SHA256_DIGEST_PREFIX: &str = "sha256:";
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ExtensionData {
@@ -63,6 +107,11 @@ pub struct ExtensionData {
 
     /// Sysext or confext.
     pub ext_type: ExtensionType,
+
+    /// URL the extension image was downloaded from, when it differs from its URL in the Host
+    /// Configuration, e.g. the registry blob that an `oci://` URL resolved to. Not set for images
+    /// that were not downloaded by the servicing.
+    pub resolved_url: Option<Url>,
 }
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
@@ -87,6 +136,18 @@ pub struct ExtensionsSubsystem {
 
     /// Extension images that are currently merged on the servicing OS.
     extensions_old: Vec<ExtensionData>,
+
+    /// Changes made to the extension images of the running OS during a runtime update, undone if
+    /// the new images fail to merge.
+    transaction: Option<ExtensionTransaction>,
+
+    /// Kinds of extension images of which unmanaged images were pruned or removed from the running
+    /// OS.
+    pruned: Vec<ExtensionKind>,
+
+    /// Lock over the extension images of the running OS, held from provisioning until the new
+    /// images are merged.
+    lock: Option<ExtensionsLock>,
 }
 impl Subsystem for ExtensionsSubsystem {
     fn name(&self) -> &'static str {
@@ -94,12 +155,51 @@ impl Subsystem for ExtensionsSubsystem {
     }
 
     fn provision(&mut self, ctx: &EngineContext, mount_path: &Path) -> Result<(), TridentError> {
-        // Define staging directory, in which extension images will be downloaded.
-        let staging_dir = path::join_relative(mount_path, EXTENSION_IMAGE_STAGING_DIRECTORY);
+        // Extension images may have been unmerged by hand since the last servicing, e.g. with
+        // systemd-sysext. Refreshing the images below merges them again.
+        if ctx.servicing_type != ServicingType::CleanInstall {
+            warn_drift(&ctx.spec_old);
+        }
+
+        // Other Trident invocations must not change the images of the running OS while they are
+        // replaced.
+        if ctx.servicing_type != ServicingType::CleanInstall
+            && ctx.servicing_type != ServicingType::AbUpdate
+        {
+            self.lock =
+                Some(lock::lock(mount_path).structured(ServicingError::LockExtensionImages)?);
+        }
 
-        // Download new extension images. Mount and process all extension images.
-        self.populate_extensions(ctx, &staging_dir)
-            .structured(InternalError::PopulateExtensionImages)?;
+        // Define staging directory, in which extension images will be downloaded.
+        let staging_dir = path::join_relative(mount_path, &ctx.paths.extension_staging_dir);
+
+        // Download new extension images. Mount and process all extension images, check that the
+        // new ones are compatible with the target OS and that their requirements are met, and
+        // verify their signatures if required. Do not leave downloaded images behind on failure.
+        let result = self
+            .populate_extensions(ctx, &staging_dir)
+            .structured(InternalError::PopulateExtensionImages)
+            .and_then(|releases| self.check_compatibility(ctx, &releases, mount_path))
+            .and_then(|()| self.order_extensions(ctx))
+            .and_then(|()| {
+                if ctx.spec.os.sysext_policy.require_signature {
+                    self.verify_signatures()
+                } else {
+                    Ok(())
+                }
+            })
+            .and_then(|()| self.check_copy_space(ctx, mount_path));
+        if let Err(e) = result {
+            if staging_dir.exists() {
+                if let Err(remove_err) = fs::remove_dir_all(&staging_dir) {
+                    warn!(
+                        "Failed to remove extension image staging directory '{}': {remove_err}",
+                        staging_dir.display()
+                    );
+                }
+            }
+            return Err(e);
+        }
 
         // Ensure that desired target directories exist on the target OS.
         self.create_directories(mount_path)
@@ -107,9 +207,41 @@ impl Subsystem for ExtensionsSubsystem {
 
         // Determine which images need to be removed and which should be added.
         // Copy extension images to their proper locations.
-        self.set_up_extensions(mount_path, ctx.servicing_type)
+        let mut transaction = self
+            .set_up_extensions(
+                mount_path,
+                ctx.servicing_type,
+                ctx.spec.os.sysext_policy.versioned_store,
+            )
             .structured(InternalError::SetUpExtensionImages)?;
 
+        // Unmanaged images only accumulate on the running OS. They are pruned and removed as part
+        // of the transaction, so they are restored along with the previous images if merging
+        // fails.
+        if ctx.servicing_type != ServicingType::CleanInstall
+            && ctx.servicing_type != ServicingType::AbUpdate
+        {
+            if let Some(keep) = ctx.spec.os.sysext_policy.keep_unmanaged {
+                self.prune_unmanaged(
+                    mount_path,
+                    ctx.spec.os.sysext_policy.sysext_directory(),
+                    keep,
+                    &mut transaction,
+                )
+                .structured(ServicingError::PruneExtensionImages)?;
+            }
+            if !ctx.spec.os.sysext_policy.remove.is_empty() {
+                self.remove_images(
+                    mount_path,
+                    ctx.spec.os.sysext_policy.sysext_directory(),
+                    &ctx.spec.os.sysext_policy.remove,
+                    &mut transaction,
+                )
+                .structured(ServicingError::RemoveExtensionImages)?;
+            }
+        }
+        self.transaction = Some(transaction);
+
         // Clean-up staging directory. Recursively remove all contents of
         // staging directory as well as the directory itself.
         fs::remove_dir_all(staging_dir).structured(InternalError::Internal(
@@ -119,6 +251,54 @@ impl Subsystem for ExtensionsSubsystem {
         Ok(())
     }
 
+    fn forecast_space(&self, ctx: &EngineContext) -> Vec<SpaceDemand> {
+        // After clean install and A/B update, extension images are placed on the new OS.
+        if ctx.servicing_type == ServicingType::CleanInstall
+            || ctx.servicing_type == ServicingType::AbUpdate
+        {
+            return Vec::new();
+        }
+        match forecast_images(ctx) {
+            Ok(demands) => demands,
+            Err(e) => {
+                warn!("Failed to forecast the space needed for extension images: {e:?}");
+                Vec::new()
+            }
+        }
+    }
+
+    fn configure(&mut self, ctx: &EngineContext) -> Result<(), TridentError> {
+        // After clean install and A/B update, extension images are merged when the target OS
+        // boots. Otherwise, the target OS is the running OS, so the new images are merged now.
+        if ctx.servicing_type == ServicingType::CleanInstall
+            || ctx.servicing_type == ServicingType::AbUpdate
+        {
+            return Ok(());
+        }
+
+        let _lock = self.lock.take();
+        let transaction = self.transaction.take().unwrap_or_default();
+        if let Err(e) = self.refresh(ctx, &ctx.spec) {
+            // Restore the previous set of images, so the running OS is not left with a mix of old
+            // and new images.
+            warn!("Failed to merge new extension images, restoring the previous ones");
+            match transaction.roll_back() {
+                Ok(()) => {
+                    if let Err(refresh_err) = self.refresh(ctx, &ctx.spec_old) {
+                        error!("Failed to merge the previous extension images: {refresh_err:?}");
+                    }
+                }
+                Err(rollback_err) => {
+                    error!("Failed to restore the previous extension images: {rollback_err:?}")
+                }
+            }
+            return Err(e);
+        }
+        transaction.commit();
+
+        Ok(())
+    }
+
     fn update_host_configuration(&self, ctx: &mut EngineContext) -> Result<(), TridentError> {
         // Update paths of sysexts in the Host Configuration.
         self.extensions
@@ -156,24 +336,229 @@ impl Subsystem for ExtensionsSubsystem {
                 Ok::<(), TridentError>(())
             })?;
 
+        // Record the URLs the downloaded extension images were read from.
+        ctx.resolved_extension_urls.extend(
+            self.extensions
+                .iter()
+                .filter_map(|ext| Some((ext.sha384.clone(), ext.resolved_url.clone()?))),
+        );
+
         Ok(())
     }
 }
 
 impl ExtensionsSubsystem {
+    /// Merges the current set of extension images into the running OS as set in `host_config`,
+    /// for the types of images whose set or mutability changed.
+    fn refresh(
+        &self,
+        ctx: &EngineContext,
+        host_config: &HostConfiguration,
+    ) -> Result<(), TridentError> {
+        if self.extensions_changed(&ExtensionType::Sysext)
+            || self.pruned.contains(&ExtensionKind::Sysext)
+            || ctx.spec.os.sysext_policy.mutable != ctx.spec_old.os.sysext_policy.mutable
+        {
+            refresh_images(ExtensionKind::Sysext, host_config)?;
+        }
+        if self.extensions_changed(&ExtensionType::Confext)
+            || self.pruned.contains(&ExtensionKind::Confext)
+        {
+            refresh_images(ExtensionKind::Confext, host_config)?;
+        }
+        Ok(())
+    }
+
+    /// Moves aside the unmanaged extension images of the OS at `mount_path`, keeping the `keep`
+    /// most recent images of each name. Sysext images are searched in `sysext_directory`.
+    fn prune_unmanaged(
+        &mut self,
+        mount_path: &Path,
+        sysext_directory: &Path,
+        keep: usize,
+        transaction: &mut ExtensionTransaction,
+    ) -> Result<(), Error> {
+        let managed = self
+            .extensions
+            .iter()
+            .map(|ext| ext.path.clone())
+            .collect::<HashSet<_>>();
+        for image in prune::find_unmanaged(mount_path, sysext_directory, &managed, keep)?
+            .into_iter()
+            .filter(|image| image.pruned)
+        {
+            debug!(
+                "Pruning unmanaged {} image '{}'",
+                image.kind,
+                image.path.display()
+            );
+            transaction.back_up(&path::join_relative(mount_path, &image.path))?;
+            if !self.pruned.contains(&image.kind) {
+                self.pruned.push(image.kind);
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves aside the sysext images of the OS at `mount_path` matching an ID or name in `remove`,
+    /// searching `sysext_directory` along with the other sysext directories.
+    fn remove_images(
+        &mut self,
+        mount_path: &Path,
+        sysext_directory: &Path,
+        remove: &[String],
+        transaction: &mut ExtensionTransaction,
+    ) -> Result<(), Error> {
+        let managed = self
+            .extensions
+            .iter()
+            .map(|ext| ext.path.clone())
+            .collect::<HashSet<_>>();
+        for image in removal::find_removed(mount_path, sysext_directory, &managed, remove)? {
+            info!("Removing sysext image '{}'", image.display());
+            transaction.back_up(&path::join_relative(mount_path, &image))?;
+            if !self.pruned.contains(&ExtensionKind::Sysext) {
+                self.pruned.push(ExtensionKind::Sysext);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether the set of extension images of the given type differs from the one on the
+    /// servicing OS.
+    fn extensions_changed(&self, ext_type: &ExtensionType) -> bool {
+        let images = |extensions: &[ExtensionData]| {
+            extensions
+                .iter()
+                .filter(|ext| &ext.ext_type == ext_type)
+                .map(|ext| (ext.path.clone(), ext.sha384.clone()))
+                .collect::<HashSet<_>>()
+        };
+        images(&self.extensions) != images(&self.extensions_old)
+    }
+
+    /// Checks that all sysext and confext images that should be merged on the target OS are signed
+    /// by a trusted key.
+    fn verify_signatures(&self) -> Result<(), TridentError> {
+        self.extensions.iter().try_for_each(|ext| {
+            sysext::verify_signature(&ext.temp_path).structured(
+                ServicingError::VerifyExtensionImageSignature {
+                    name: ext.name.clone(),
+                },
+            )
+        })
+    }
+
+    /// Checks that the extension images that should be merged on the target OS, mounted at
+    /// `mount_path`, match the scope they are merged into, the architecture of the host and the
+    /// release of the target OS. `releases` holds the extension-release files of
+    /// `self.extensions`, in the same order.
+    fn check_compatibility(
+        &self,
+        ctx: &EngineContext,
+        releases: &[ExtensionRelease],
+        mount_path: &Path,
+    ) -> Result<(), TridentError> {
+        if releases.is_empty() {
+            return Ok(());
+        }
+
+        let scopes = ctx
+            .spec
+            .os
+            .sysexts
+            .iter()
+            .map(|ext| ((ExtensionType::Sysext, ext.sha384.clone()), ext.scope))
+            .chain(
+                ctx.spec
+                    .os
+                    .confexts
+                    .iter()
+                    .map(|ext| ((ExtensionType::Confext, ext.sha384.clone()), ext.scope)),
+            )
+            .collect::<HashMap<_, _>>();
+
+        let target_os = OsRelease::read_root(mount_path).structured(InternalError::Internal(
+            "Failed to read os-release of the target OS",
+        ))?;
+        self.extensions
+            .iter()
+            .zip(releases)
+            .try_for_each(|(ext, extension_release)| {
+                release::check_compatibility(
+                    extension_release,
+                    &ext.ext_type,
+                    scopes
+                        .get(&(ext.ext_type.clone(), ext.sha384.clone()))
+                        .copied()
+                        .unwrap_or_default(),
+                    SystemArchitecture::current(),
+                    &target_os,
+                )
+                .map_err(|explanation| {
+                    TridentError::new(InvalidInputError::from(
+                        HostConfigurationDynamicValidationError::IncompatibleExtensionImage {
+                            name: ext.name.clone(),
+                            explanation,
+                        },
+                    ))
+                })
+            })
+    }
+
+    /// Checks that the extension images required by each image of `self.extensions` are also
+    /// merged, and orders `self.extensions` so that required images come first.
+    fn order_extensions(&mut self, ctx: &EngineContext) -> Result<(), TridentError> {
+        let requires = ctx
+            .spec
+            .os
+            .sysexts
+            .iter()
+            .map(|ext| {
+                (
+                    (ExtensionType::Sysext, ext.sha384.clone()),
+                    ext.requires.as_slice(),
+                )
+            })
+            .chain(ctx.spec.os.confexts.iter().map(|ext| {
+                (
+                    (ExtensionType::Confext, ext.sha384.clone()),
+                    ext.requires.as_slice(),
+                )
+            }))
+            .collect::<HashMap<_, _>>();
+        self.extensions = requirements::order(std::mem::take(&mut self.extensions), |ext| {
+            requires
+                .get(&(ext.ext_type.clone(), ext.sha384.clone()))
+                .copied()
+                .unwrap_or_default()
+        })
+        .map_err(|e| TridentError::new(InvalidInputError::from(e)))?;
+        Ok(())
+    }
+
+    /// Populates `self.extensions` and `self.extensions_old`. Returns the extension-release files
+    /// of the extension images in `self.extensions`, in the same order.
     #[allow(unused)]
     fn populate_extensions(
         &mut self,
         ctx: &EngineContext,
         staging_dir: &Path,
-    ) -> Result<(), Error> {
-        let timeout = Duration::from_secs(
-            ctx.spec
-                .internal_params
-                .get_u64(HTTP_CONNECTION_TIMEOUT_SECONDS)
-                .and_then(|timeout| timeout.ok())
-                .unwrap_or(10),
-        );
+    ) -> Result<Vec<ExtensionRelease>, Error> {
+        let timeout = connection_timeout(ctx);
+
+        let concurrency = ctx
+            .spec
+            .internal_params
+            .get_u16(EXTENSION_CONCURRENCY)
+            .and_then(|concurrency| concurrency.ok())
+            .filter(|concurrency| *concurrency > 0)
+            .map(usize::from)
+            .unwrap_or(DEFAULT_EXTENSION_CONCURRENCY);
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(concurrency)
+            .build()
+            .context("Failed to create thread pool for extension images")?;
 
         // Create temporary directory in which to download extension images
         // before copying them to their final path.
@@ -182,31 +567,65 @@ impl ExtensionsSubsystem {
                 .with_context(|| format!("Failed to create dir '{}'", staging_dir.display()))?;
         };
 
-        self.populate_extensions_inner(ctx, timeout, staging_dir, ExtensionType::Sysext, true)?;
-        self.populate_extensions_inner(ctx, timeout, staging_dir, ExtensionType::Sysext, false)?;
-        self.populate_extensions_inner(ctx, timeout, staging_dir, ExtensionType::Confext, true)?;
-        self.populate_extensions_inner(ctx, timeout, staging_dir, ExtensionType::Confext, false)?;
-        Ok(())
+        // The images of the running OS are processed first, so that deltas of new images can be
+        // applied to them.
+        let mut releases = Vec::new();
+        let mut bases = DeltaBases::new();
+        for (ext_type, new) in [
+            (ExtensionType::Sysext, false),
+            (ExtensionType::Sysext, true),
+            (ExtensionType::Confext, false),
+            (ExtensionType::Confext, true),
+        ] {
+            let ext_releases = self.populate_extensions_inner(
+                ctx,
+                &pool,
+                timeout,
+                staging_dir,
+                ext_type,
+                new,
+                &bases,
+            )?;
+            if new {
+                releases.extend(ext_releases);
+            } else {
+                let old = &self.extensions_old[self.extensions_old.len() - ext_releases.len()..];
+                bases.extend(old.iter().zip(ext_releases).filter_map(|(ext, release)| {
+                    Some((
+                        (ext.ext_type.clone(), ext.id.clone(), release.version_id?),
+                        ext.temp_path.clone(),
+                    ))
+                }));
+            }
+        }
+        Ok(releases)
     }
 
-    /// Updates `self.extensions` or `self.extensions_old`. Takes in 4
+    /// Updates `self.extensions` or `self.extensions_old`. Takes in 5
     /// arguments:
     /// - self: ExtensionsSubsystem.
     /// - ctx: EngineContext.
+    /// - pool: Thread pool bounding how many extension images are processed
+    ///   concurrently.
     /// - timeout: Time out on HTTP requests.
     /// - ext_type: ExtensionType, indicating which API should be processed.
     /// - new: Boolean indicating whether this function should populate
     ///   `self.extensions` or `self.extensions_old`. When populating
     ///   `self.extensions_old`, expect all extensions in the old Host Configuration
     ///   to be present on the servicing OS so we will not download any new images.
+    /// - bases: Extension images of the running OS that deltas of new images can be applied to.
+    ///
+    /// Returns the extension-release files of the processed extension images.
     fn populate_extensions_inner(
         &mut self,
         ctx: &EngineContext,
+        pool: &ThreadPool,
         timeout: Duration,
         staging_dir: &Path,
         ext_type: ExtensionType,
         new: bool,
-    ) -> Result<(), Error> {
+        bases: &DeltaBases,
+    ) -> Result<Vec<ExtensionRelease>, Error> {
         let hc_extensions = match (new, &ext_type) {
             (true, ExtensionType::Sysext) => &ctx.spec.os.sysexts,
             (false, ExtensionType::Sysext) => &ctx.spec_old.os.sysexts,
@@ -214,102 +633,71 @@ impl ExtensionsSubsystem {
             (false, ExtensionType::Confext) => &ctx.spec_old.os.confexts,
         };
 
-        for ext in hc_extensions {
-            let extension_file = if new {
-                // First, check if this extension already exists on the system.
-                if let Some(existing_file_path) = match &ext_type {
-                    ExtensionType::Sysext => {
-                        check_for_existing_image(ext, &ctx.spec_old.os.sysexts)
-                    }
-                    ExtensionType::Confext => {
-                        check_for_existing_image(ext, &ctx.spec_old.os.confexts)
-                    }
-                } {
-                    // Check if Trident is running in a container, and adjust path accordingly.
-                    let adjusted_path = adjust_path_if_container(existing_file_path.clone())?;
-                    // Ensure that file exists.
-                    ensure!(
-                        adjusted_path.exists(),
-                        "Expected to find extension image from URL '{}' at path '{}' based on previous Host Configuration, but path does not exist",
-                        ext.url,
-                        existing_file_path.display() // Display the unadjusted path for readability
-                    );
-                    adjusted_path
-                } else {
-                    // The extension is new to the OS, so we need to download it.
-                    // Create and persist a temporary file; get its path.
-                    let temp_file: PathBuf = NamedTempFile::new_in(staging_dir)
-                        .context("Failed to create temporary file")?
-                        .into_temp_path()
-                        .keep()
-                        .context("Failed to persist temporary file")?;
-
-                    // Download the extension image to this temporary file.
-                    let reader = FileReader::new(&ext.url, timeout)
-                        .context("Failed to create file reader")?
-                        .complete_reader()
-                        .context("Failed to create complete file reader")?;
-                    let hash_reader = HashingReader384::new(reader);
-                    let computed_sha384 = stream_and_hash(hash_reader, &temp_file)
-                        .context("Failed to download extension image and calculate its hash")?;
-
-                    // Ensure computed SHA384 matches SHA384 in Host Configuration.
-                    if ext.sha384 != computed_sha384 {
-                        bail!(
-                            "SHA384 mismatch for extension image at '{}': expected {}, got {}",
-                            ext.url,
-                            ext.sha384,
-                            computed_sha384
-                        )
-                    }
-
-                    temp_file
-                }
-            } else {
-                // For extension images from the old Host Configuration, use the
-                // existing file.
-                let path = ext.path.clone().with_context(|| {
-                    format!(
-                        "Failed to retrieve current path of extension image '{}'",
-                        ext.url
-                    )
-                })?;
-                // Check if Trident is running in a container, and adjust path accordingly.
-                let adjusted_path = adjust_path_if_container(path.clone())?;
-                // Ensure that file exists
-                ensure!(
-                    adjusted_path.exists(),
-                    "Expected to find extension image from URL '{}' at path '{}', but path does not exist",
-                    ext.url,
-                    path.display() // Display unadjusted path for readability
-                );
-                adjusted_path
-            };
-
-            // Create temporary mountpoint, which will be used to read the extension-release file
-            let temp_mp = tempfile::tempdir()?;
-
-            // Attach a device and mount the extension
-            let device_path = attach_device_and_mount(&extension_file, temp_mp.path())
-                .context("Failed to mount")?;
+        // Extension images are downloaded and inspected in parallel, but are
+        // recorded in the order of the Host Configuration.
+        let (ext_data, releases): (Vec<_>, Vec<_>) = pool
+            .install(|| {
+                hc_extensions
+                    .par_iter()
+                    .map(|ext| {
+                        prepare_extension(ctx, timeout, staging_dir, ext, &ext_type, new, bases)
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            })?
+            .into_iter()
+            .flatten()
+            .unzip();
+        if new {
+            self.extensions.extend(ext_data);
+        } else {
+            self.extensions_old.extend(ext_data);
+        }
 
-            // Get extension-release file
-            let ext_data_result =
-                release::read_extension_release(temp_mp.path(), &extension_file, ext, &ext_type);
+        Ok(releases)
+    }
 
-            // Clean-Up: unmount and detach the device
-            detach_device_and_unmount(device_path, temp_mp.path()).context("Failed to unmount")?;
+    /// Checks that the filesystems of the target OS at `mount_path` have enough free space for the
+    /// extension images that are copied rather than moved to their paths, i.e. the images that are
+    /// not on the filesystem of their path yet. Unlike the space forecast, this also covers images
+    /// of unknown size, as they are all downloaded by now.
+    fn check_copy_space(&self, ctx: &EngineContext, mount_path: &Path) -> Result<(), TridentError> {
+        if ctx.spec.internal_params.get_flag(DISABLE_SPACE_FORECAST) {
+            return Ok(());
+        }
+        let demands = self
+            .copy_demands(mount_path, ctx.spec.os.sysext_policy.versioned_store)
+            .structured(InternalError::SetUpExtensionImages)?;
+        space_forecast::check_demands(&demands)
+    }
 
-            let ext_data =
-                ext_data_result.context("Failed to get extension-release information")?;
-            if new {
-                self.extensions.push(ext_data);
+    /// Returns the space needed to copy the extension images to their paths on the target OS at
+    /// `mount_path`, or to the store next to them if `versioned` is set.
+    fn copy_demands(&self, mount_path: &Path, versioned: bool) -> Result<Vec<SpaceDemand>, Error> {
+        let mut demands = Vec::new();
+        for ext in &self.extensions {
+            let new_path = path::join_relative(mount_path, &ext.path);
+            let target = if versioned {
+                store::stored_path(&new_path, &ext.name, &ext.sha384)?
             } else {
-                self.extensions_old.push(ext_data);
+                new_path
+            };
+            if target == ext.temp_path || (versioned && target.exists()) {
+                continue;
             }
+            // Images linked into a store are always copied, so that the stored image stays.
+            let directory = target.parent().unwrap_or(mount_path);
+            if !(versioned && ext.temp_path.is_symlink())
+                && same_filesystem(&ext.temp_path, directory)?
+            {
+                continue;
+            }
+            demands.push(SpaceDemand {
+                path: directory.to_path_buf(),
+                artifact: format!("{} image '{}'", ext.ext_type, ext.name),
+                bytes: staging::remaining_bytes(&ext.temp_path, &target)?,
+            });
         }
-
-        Ok(())
+        Ok(demands)
     }
 
     /// Ensures that all target directories for extension images exist on the
@@ -353,11 +741,23 @@ impl ExtensionsSubsystem {
     ///   - If the hash is the same, the extension is renamed/copied from its
     ///     old location on the servicing OS to its new location on the target
     ///     OS.
+    ///
+    /// During a runtime update, removed extensions are moved aside rather than deleted, and all
+    /// changes are recorded in the returned transaction, so that they can be undone if the new
+    /// set of extensions fails to merge.
+    ///
+    /// When `versioned` is set, images are placed into the content-addressed store next to their
+    /// target paths, and their target paths are symlinks to the stored images.
     fn set_up_extensions(
         &self,
         mount_path: &Path,
         servicing_type: ServicingType,
-    ) -> Result<(), Error> {
+        versioned: bool,
+    ) -> Result<ExtensionTransaction, Error> {
+        let runtime_update = !(servicing_type == ServicingType::CleanInstall
+            || servicing_type == ServicingType::AbUpdate);
+        let mut transaction = ExtensionTransaction::default();
+
         let old_exts_hashmap: HashMap<_, _> = self
             .extensions_old
             .iter()
@@ -398,59 +798,521 @@ impl ExtensionsSubsystem {
             .map(|(_, ext)| ext)
             .collect();
 
+        // On Clean Install and A/B Update, it is not necessary to remove
+        // extensions from the servicing OS as these will not be present on the
+        // target OS. (We also do not expect any existing extension images on
+        // the servicing OS for Clean Install.)
+        if runtime_update {
+            // Otherwise, move existing extensions that are not in the new
+            // Host Configuration aside, before new extensions take their
+            // place. Note that for any extension in 'extensions_to_remove',
+            // ext.path and ext.temp_path will be the same because each
+            // ExtensionData object in the vector was populated from the old
+            // Host Configuration.
+            for ext in extensions_to_remove {
+                if ext.temp_path.exists() {
+                    transaction.back_up(&ext.temp_path)?;
+                }
+            }
+        }
+
         // Add new extensions that should be added
         for ext in extensions_to_add {
             let new_path = path::join_relative(mount_path, &ext.path);
+            if versioned {
+                place_in_store(ext, &new_path, runtime_update, &mut transaction)?;
+                continue;
+            }
+            if new_path == ext.temp_path {
+                continue;
+            }
             trace!(
                 "Copying {} '{}' to path {}",
                 ext.ext_type,
                 ext.name,
                 new_path.display()
             );
-            // Attempt atomic rename first, for extensions that were newly
-            // downloaded to the staging directory.
-            if let Err(e) = fs::rename(&ext.temp_path, &new_path) {
+            if runtime_update && new_path.exists() {
+                transaction.back_up(&new_path)?;
+            }
+            move_or_copy(&ext.temp_path, &new_path, &ext.sha384, &mut transaction)?;
+        }
+
+        // Only the stored images of the new and the previous set of extensions are kept, so that
+        // rolling back to the previous set does not download its images again.
+        if versioned && runtime_update {
+            let referenced = self
+                .extensions
+                .iter()
+                .chain(&self.extensions_old)
+                .map(|ext| {
+                    store::stored_path(
+                        &path::join_relative(mount_path, &ext.path),
+                        &ext.name,
+                        &ext.sha384,
+                    )
+                })
+                .collect::<Result<HashSet<_>, _>>()?;
+            let directories = self
+                .extensions
+                .iter()
+                .filter_map(|ext| ext.path.parent())
+                .map(|directory| path::join_relative(mount_path, directory))
+                .collect::<HashSet<_>>();
+            store::collect_garbage(directories.iter().map(PathBuf::as_path), &referenced);
+        }
+
+        Ok(transaction)
+    }
+}
+
+/// Returns whether the file at `path` is on the filesystem holding `directory`, or its closest
+/// existing ancestor, so that it can be renamed into `directory`.
+fn same_filesystem(path: &Path, directory: &Path) -> Result<bool, Error> {
+    let device = fs::metadata(path)
+        .with_context(|| format!("Failed to read metadata of '{}'", path.display()))?
+        .dev();
+    let existing = directory
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(Path::new("/"));
+    let directory_device = fs::metadata(existing)
+        .with_context(|| format!("Failed to read metadata of '{}'", existing.display()))?
+        .dev();
+    Ok(device == directory_device)
+}
+
+/// Moves the extension image at `from` to `to`, falling back to copying it, and records the change
+/// in `transaction`.
+fn move_or_copy(
+    from: &Path,
+    to: &Path,
+    sha384: &Sha384Hash,
+    transaction: &mut ExtensionTransaction,
+) -> Result<(), Error> {
+    // Attempt atomic rename first, for extensions that were newly
+    // downloaded to the staging directory.
+    if let Err(e) = fs::rename(from, to) {
+        warn!(
+            "Failed to atomically rename '{}' to '{}': {e}. Attempting file copy instead.",
+            from.display(),
+            to.display()
+        );
+        // Fall back to file copy if this fails, i.e. if the files are
+        // not on the same filesystem. This will be the default for
+        // extensions existing on the servicing OS. Copies interrupted
+        // by an earlier run are resumed.
+        staging::copy_resumable(from, to, sha384)?;
+        transaction.record_copy(to);
+    } else {
+        transaction.record_move(from, to);
+    }
+    Ok(())
+}
+
+/// Places the extension image into the store next to `new_path`, unless it is already stored,
+/// and links `new_path` to the stored image. Changes are recorded in `transaction`.
+fn place_in_store(
+    ext: &ExtensionData,
+    new_path: &Path,
+    runtime_update: bool,
+    transaction: &mut ExtensionTransaction,
+) -> Result<(), Error> {
+    let stored = store::stored_path(new_path, &ext.name, &ext.sha384)?;
+    if stored.exists() && store::is_linked(new_path, &stored) {
+        return Ok(());
+    }
+    trace!(
+        "Storing {} '{}' at path {}",
+        ext.ext_type,
+        ext.name,
+        stored.display()
+    );
+
+    // Images already linked into a store are copied, so that the image they link to stays in
+    // its store.
+    let source_linked = ext.temp_path.is_symlink();
+    if !stored.exists() {
+        if let Some(parent) = stored.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+        }
+        if source_linked {
+            staging::copy_resumable(&ext.temp_path, &stored, &ext.sha384)?;
+            transaction.record_copy(&stored);
+        } else {
+            move_or_copy(&ext.temp_path, &stored, &ext.sha384, transaction)?;
+        }
+    }
+
+    // The image moved to its new path, so its link at its previous path is moved aside.
+    if runtime_update && source_linked && ext.temp_path != new_path {
+        transaction.back_up(&ext.temp_path)?;
+    }
+    if fs::symlink_metadata(new_path).is_ok() {
+        if runtime_update {
+            transaction.back_up(new_path)?;
+        } else {
+            fs::remove_file(new_path).with_context(|| {
+                format!("Failed to remove extension image '{}'", new_path.display())
+            })?;
+        }
+    }
+    store::link(&stored, new_path)?;
+    transaction.record_copy(new_path);
+    Ok(())
+}
+
+/// Returns the timeout of HTTP connections used to fetch extension images and other files.
+pub(crate) fn connection_timeout(ctx: &EngineContext) -> Duration {
+    Duration::from_secs(
+        ctx.spec
+            .internal_params
+            .get_u64(HTTP_CONNECTION_TIMEOUT_SECONDS)
+            .and_then(|timeout| timeout.ok())
+            .unwrap_or(10),
+    )
+}
+
+/// Estimates the space needed on the running OS for the extension images new to it, from the size
+/// of the files reported by the servers they are fetched from. Images of unknown size are left
+/// out. New images are downloaded into the staging directory, then moved or copied to the
+/// directory they are placed in.
+fn forecast_images(ctx: &EngineContext) -> Result<Vec<SpaceDemand>, Error> {
+    let root = space_forecast::host_root()?;
+    let staging_dir = path::join_relative(&root, &ctx.paths.extension_staging_dir);
+    let timeout = connection_timeout(ctx);
+
+    let extensions = ctx
+        .spec
+        .os
+        .sysexts
+        .iter()
+        .map(|ext| (ext, ExtensionType::Sysext))
+        .chain(
+            ctx.spec
+                .os
+                .confexts
+                .iter()
+                .map(|ext| (ext, ExtensionType::Confext)),
+        );
+    let mut demands = Vec::new();
+    for (ext, ext_type) in extensions {
+        let (old_extensions, default_directory) = match ext_type {
+            ExtensionType::Sysext => (
+                &ctx.spec_old.os.sysexts,
+                ctx.spec.os.sysext_policy.sysext_directory(),
+            ),
+            ExtensionType::Confext => (
+                &ctx.spec_old.os.confexts,
+                Path::new(DEFAULT_CONFEXT_DIRECTORY),
+            ),
+        };
+        if check_for_existing_image(ext, old_extensions).is_some()
+            || (ctx.spec.os.sysext_policy.versioned_store
+                && find_stored_image(ext, default_directory)?.is_some())
+        {
+            continue;
+        }
+
+        let size = match FileReader::new(&ext.url, timeout).map(|reader| reader.size()) {
+            Ok(Some(size)) => size,
+            Ok(None) => {
+                debug!("Size of extension image '{}' is unknown", ext.url);
+                continue;
+            }
+            Err(e) => {
+                debug!("Failed to get size of extension image '{}': {e:?}", ext.url);
+                continue;
+            }
+        };
+        let directory = ext
+            .path
+            .as_deref()
+            .and_then(Path::parent)
+            .unwrap_or(default_directory);
+        for path in [staging_dir.clone(), path::join_relative(&root, directory)] {
+            demands.push(SpaceDemand {
+                path,
+                artifact: format!("extension image '{}'", ext.url),
+                bytes: size,
+            });
+        }
+    }
+    Ok(demands)
+}
+
+/// Warns about the extension images of `host_config` that are not merged into the running OS.
+/// Failures are only logged, as the images are merged again when they are refreshed.
+fn warn_drift(host_config: &HostConfiguration) {
+    if host_config.os.sysexts.is_empty() && host_config.os.confexts.is_empty() {
+        return;
+    }
+    match status::list(host_config) {
+        Ok(statuses) => {
+            for drifted in status::drift(&statuses) {
                 warn!(
-                    "Failed to atomically rename '{}' to '{}': {e}. Attempting file copy instead.",
-                    ext.temp_path.display(),
-                    new_path.display()
+                    "Extension images drifted from the Host Configuration: {}",
+                    status::describe_drift(drifted)
                 );
-                // Fall back to file copy if this fails, i.e. if the files are
-                // not on the same filesystem. This will be the default for
-                // extensions existing on the servicing OS.
-                fs::copy(&ext.temp_path, &new_path).context(format!(
-                    "Failed to copy extension image from '{}' to '{}'",
-                    ext.temp_path.display(),
-                    new_path.display()
-                ))?;
             }
         }
+        Err(e) => debug!("Failed to check extension images for drift: {e:?}"),
+    }
+}
 
-        // On Clean Install and A/B Update, it is not necessary to remove
-        // extensions from the servicing OS as these will not be present on the
-        // target OS. (We also do not expect any existing extension images on
-        // the servicing OS for Clean Install.)
-        if !(servicing_type == ServicingType::CleanInstall
-            || servicing_type == ServicingType::AbUpdate)
+/// Merges the extension images of the given kind into the running OS, with the mutability set in
+/// `host_config`. Only the hierarchy of sysext images can be made writable. The images merged and
+/// unmerged are recorded in the audit log.
+pub(crate) fn refresh_images(
+    kind: ExtensionKind,
+    host_config: &HostConfiguration,
+) -> Result<(), TridentError> {
+    let mutable = match kind {
+        ExtensionKind::Sysext => host_config.os.sysext_policy.mutable,
+        ExtensionKind::Confext => ExtensionMutability::default(),
+    };
+    let before = audit::merged_images(kind);
+    let result = sysext::refresh(kind, mutable);
+    let after = audit::merged_images(kind);
+    audit::record(
+        kind,
+        &before,
+        &after,
+        host_config,
+        result.as_ref().err().map(|e| e.to_string()),
+    );
+    result
+}
+
+/// Fetches a single extension image, downloading it into `staging_dir` if it is
+/// new to the OS, and reads its extension-release file.
+///
+/// Images of the previous Host Configuration that are missing from the servicing OS, e.g. because
+/// they were deleted by hand, are skipped when `new` is false, and downloaded again when `new` is
+/// true, so that the target OS gets all the images of the Host Configuration.
+fn prepare_extension(
+    ctx: &EngineContext,
+    timeout: Duration,
+    staging_dir: &Path,
+    ext: &Extension,
+    ext_type: &ExtensionType,
+    new: bool,
+    bases: &DeltaBases,
+) -> Result<Option<(ExtensionData, ExtensionRelease)>, Error> {
+    let default_directory = match ext_type {
+        ExtensionType::Sysext => ctx.spec.os.sysext_policy.sysext_directory(),
+        ExtensionType::Confext => Path::new(DEFAULT_CONFEXT_DIRECTORY),
+    };
+    let mut resolved_url = None;
+    let extension_file = if new {
+        // First, check if this extension already exists on the system.
+        let existing_file = match ext_type {
+            ExtensionType::Sysext => check_for_existing_image(ext, &ctx.spec_old.os.sysexts),
+            ExtensionType::Confext => check_for_existing_image(ext, &ctx.spec_old.os.confexts),
+        }
+        .map(|existing_file_path| existing_image(ext, existing_file_path))
+        .transpose()?
+        .flatten();
+        if let Some(existing_file) = existing_file {
+            existing_file
+        } else if let Some(stored_path) = ctx
+            .spec
+            .os
+            .sysext_policy
+            .versioned_store
+            .then(|| find_stored_image(ext, default_directory))
+            .transpose()?
+            .flatten()
         {
-            // Otherwise, remove existing extensions that are not in the new
-            // Host Configuration.
-            for ext in extensions_to_remove {
-                // Check that file still exists. If the file was renamed in the
-                // step above, there is no need to remove it. Note that for any
-                // extension in 'extensions_to_remove', ext.path and
-                // ext.temp_path will be the same because each ExtensionData
-                // object in the vector was populated from the old Host
-                // Configuration.
-                if ext.temp_path.exists() {
-                    fs::remove_file(&ext.temp_path).with_context(|| {
-                        format!("Failed to delete file at '{}'", ext.temp_path.display())
-                    })?;
+            // An earlier version of the OS used this extension image, and it is still stored.
+            debug!(
+                "Reusing stored extension image '{}' for URL '{}'",
+                stored_path.display(),
+                ext.url
+            );
+            stored_path
+        } else if let Some(path) = delta::fetch(ext, ext_type, bases, timeout, staging_dir) {
+            // The extension is new to the OS, but a delta applies to an earlier version of it.
+            path
+        } else if ext.source != ExtensionSource::Image {
+            // The extension is new to the OS and is built from a directory tree or a tarball.
+            convert::fetch(ext, ext_type, timeout, staging_dir)?
+        } else {
+            // The extension is new to the OS, so we need to download it.
+            let (path, url) = download_extension_image(ext, timeout, staging_dir)?;
+            resolved_url = url;
+            path
+        }
+    } else {
+        // For extension images from the old Host Configuration, use the
+        // existing file.
+        let path = ext.path.clone().with_context(|| {
+            format!(
+                "Failed to retrieve current path of extension image '{}'",
+                ext.url
+            )
+        })?;
+        match existing_image(ext, path)? {
+            Some(adjusted_path) => adjusted_path,
+            None => return Ok(None),
+        }
+    };
+
+    // Create a unique temporary mountpoint, which will be used to read the
+    // extension-release file. It is removed once the extension is unmounted, including on
+    // errors.
+    let temp_mp = tempfile::Builder::new()
+        .prefix("trident-extension-")
+        .tempdir()
+        .context("Failed to create temporary mount point")?;
+
+    // Mount the extension. The guard unmounts it if reading the extension-release file
+    // fails.
+    let image =
+        sysext::mount_read_only(&extension_file, temp_mp.path()).context("Failed to mount")?;
+
+    // Get extension-release file
+    let ext_data_result = release::read_extension_release(
+        temp_mp.path(),
+        &extension_file,
+        ext,
+        ext_type,
+        default_directory,
+    );
+
+    // Clean-Up: unmount the extension, which also releases its devices
+    image.unmount().context("Failed to unmount")?;
+
+    ext_data_result
+        .context("Failed to get extension-release information")
+        .map(|(ext_data, release)| {
+            Some((
+                ExtensionData {
+                    resolved_url,
+                    ..ext_data
+                },
+                release,
+            ))
+        })
+}
+
+/// Returns the path of the extension image of the previous Host Configuration at `path` on the
+/// servicing OS, adjusted if Trident is running in a container, or `None` if it does not exist.
+fn existing_image(ext: &Extension, path: PathBuf) -> Result<Option<PathBuf>, Error> {
+    // Check if Trident is running in a container, and adjust path accordingly.
+    let adjusted_path = adjust_path_if_container(path.clone())?;
+    if adjusted_path.exists() {
+        return Ok(Some(adjusted_path));
+    }
+    warn!(
+        "Expected to find extension image from URL '{}' at path '{}' based on previous Host \
+        Configuration, but path does not exist",
+        ext.url,
+        path.display() // Display the unadjusted path for readability
+    );
+    Ok(None)
+}
+
+/// Downloads the extension image from `ext.url`, which may be a local file, an HTTP(S) URL or an
+/// OCI artifact, into a new file in `staging_dir`, and verifies its hashes, along with the digest
+/// of OCI artifacts. Returns the path of the downloaded image, along with the URL it was read from
+/// when it differs from `ext.url`. The file is removed if the download or the verification fails.
+fn download_extension_image(
+    ext: &Extension,
+    timeout: Duration,
+    staging_dir: &Path,
+) -> Result<(PathBuf, Option<Url>), Error> {
+    // Create and persist a temporary file; get its path.
+    let temp_file: PathBuf = NamedTempFile::new_in(staging_dir)
+        .context("Failed to create temporary file")?
+        .into_temp_path()
+        .keep()
+        .context("Failed to persist temporary file")?;
+
+    let result = (|| -> Result<Option<Url>, Error> {
+        debug!("Downloading extension image from '{}'", ext.url);
+        let file_reader =
+            FileReader::new(&ext.url, timeout).context("Failed to create file reader")?;
+        let reader = file_reader
+            .complete_reader()
+            .context("Failed to create complete file reader")?;
+        let progress_reader = ProgressReader::new(
+            reader,
+            format!("Downloading extension image '{}'", ext.url),
+            file_reader.size(),
+        );
+        // Compute the SHA256 hash alongside the SHA384 hash, so the image is only read once.
+        let mut sha256_reader = HashingReader256::new(progress_reader);
+        let hash_reader = HashingReader384::new(&mut sha256_reader);
+        let computed_sha384 = stream_and_hash(hash_reader, &temp_file)
+            .context("Failed to download extension image and calculate its hash")?;
+        let computed_sha256 = sha256_reader.hash();
+
+        // Ensure computed SHA384 matches SHA384 in Host Configuration.
+        if ext.sha384 != computed_sha384 {
+            bail!(
+                "SHA384 mismatch for extension image at '{}': expected {}, got {}",
+                ext.url,
+                ext.sha384,
+                computed_sha384
+            )
+        }
+
+        // Ensure computed SHA256 matches SHA256 in Host Configuration, if provided, and the
+        // digest of the registry blob for images pulled from OCI registries.
+        let oci_sha256 = match file_reader.digest() {
+            Some(digest) => match digest.strip_prefix(OCI_SHA256_DIGEST_PREFIX) {
+                Some(sha256) => Some(sha256),
+                None => {
+                    warn!(
+                        "Cannot verify digest '{digest}' of extension image at '{}', as only \
+                        SHA256 digests are supported",
+                        ext.url
+                    );
+                    None
                 }
+            },
+            None => None,
+        };
+        if let Some(sha256) = &ext.sha256 {
+            if *sha256 != computed_sha256 {
+                bail!(
+                    "SHA256 mismatch for extension image at '{}': expected {}, got {}",
+                    ext.url,
+                    sha256,
+                    computed_sha256
+                )
+            }
+        }
+        if let Some(oci_sha256) = oci_sha256 {
+            if oci_sha256 != computed_sha256 {
+                bail!(
+                    "Digest mismatch for extension image at '{}': expected \
+                    {OCI_SHA256_DIGEST_PREFIX}{oci_sha256}, got \
+                    {OCI_SHA256_DIGEST_PREFIX}{computed_sha256}",
+                    ext.url
+                )
             }
         }
 
-        Ok(())
+        Ok(file_reader
+            .resolved_url()
+            .filter(|url| *url != &ext.url)
+            .cloned())
+    })();
+
+    match result {
+        Ok(resolved_url) => Ok((temp_file, resolved_url)),
+        Err(e) => {
+            if let Err(remove_err) = fs::remove_file(&temp_file) {
+                warn!(
+                    "Failed to remove partially downloaded extension image '{}': {remove_err}",
+                    temp_file.display()
+                );
+            }
+            Err(e)
+        }
     }
 }
 
@@ -465,6 +1327,19 @@ fn check_for_existing_image(ext: &Extension, old_hc_extensions: &[Extension]) ->
         .clone()
 }
 
+/// Helper function to find the extension image in the store of the directory it is placed in.
+fn find_stored_image(ext: &Extension, default_directory: &Path) -> Result<Option<PathBuf>, Error> {
+    let directory = ext
+        .path
+        .as_deref()
+        .and_then(Path::parent)
+        .unwrap_or(default_directory);
+    Ok(store::find(
+        &adjust_path_if_container(directory.to_path_buf())?,
+        &ext.sha384,
+    ))
+}
+
 /// Helper function that prepends host root path to a path, if Trident is
 /// running in a container.
 fn adjust_path_if_container(path: PathBuf) -> Result<PathBuf, Error> {
@@ -482,57 +1357,6 @@ fn adjust_path_if_container(path: PathBuf) -> Result<PathBuf, Error> {
     )
 }
 
-/// Helper function to mount the extension image.
-fn attach_device_and_mount(image_file_path: &Path, mount_path: &Path) -> Result<String, Error> {
-    let loop_device_output = Dependency::Losetup
-        .cmd()
-        .arg("-f")
-        .arg("--show")
-        .arg(image_file_path)
-        .output_and_check()
-        .context("Failed to attach loop device")?;
-    let loop_device = loop_device_output.trim();
-
-    // Must mount with option '-t ddi', which internally invokes systemd-dissect
-    // as a helper to parse the partitions in the image.
-    let mount_result = Dependency::Mount
-        .cmd()
-        .arg("-t")
-        .arg("ddi")
-        .arg(loop_device)
-        .arg(mount_path)
-        .run_and_check();
-    if let Err(e) = mount_result {
-        // Detach the loop device if mounting failed.
-        Dependency::Losetup
-            .cmd()
-            .arg("-d")
-            .arg(loop_device)
-            .run_and_check()
-            .context("Failed to clean up loop device after mount failed")?;
-        // After detaching the loop device, return mount error.
-        return Err(e.into());
-    }
-
-    Ok(loop_device.to_string())
-}
-
-/// Helper function to unmount the extension image.
-fn detach_device_and_unmount(device_path: String, mount_path: &Path) -> Result<(), Error> {
-    Dependency::Umount
-        .cmd()
-        .arg(mount_path)
-        .run_and_check()
-        .context("Failed to unmount extension image")?;
-    Dependency::Losetup
-        .cmd()
-        .arg("-d")
-        .arg(device_path)
-        .run_and_check()
-        .context("Failed to detach loop device")?;
-    Ok(())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -559,6 +1383,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_extensions_changed() {
+        let ext = |ext_type: ExtensionType, sha384: &str| ExtensionData {
+            id: "ext".to_string(),
+            name: "ext".to_string(),
+            sha384: Sha384Hash::from(sha384),
+            path: PathBuf::from("/var/lib/extensions/ext.raw"),
+            temp_path: PathBuf::from("/var/lib/extensions/ext.raw"),
+            ext_type,
+            resolved_url: None,
+        };
+
+        let subsystem = ExtensionsSubsystem {
+            extensions: vec![ext(ExtensionType::Sysext, "a")],
+            extensions_old: vec![ext(ExtensionType::Sysext, "a")],
+            ..Default::default()
+        };
+        assert!(!subsystem.extensions_changed(&ExtensionType::Sysext));
+        assert!(!subsystem.extensions_changed(&ExtensionType::Confext));
+
+        let subsystem = ExtensionsSubsystem {
+            extensions: vec![ext(ExtensionType::Sysext, "b")],
+            extensions_old: vec![
+                ext(ExtensionType::Sysext, "a"),
+                ext(ExtensionType::Confext, "c"),
+            ],
+            ..Default::default()
+        };
+        assert!(subsystem.extensions_changed(&ExtensionType::Sysext));
+        assert!(subsystem.extensions_changed(&ExtensionType::Confext));
+    }
+
     #[test]
     fn test_create_directories() {
         let subsystem = ExtensionsSubsystem {
@@ -571,6 +1427,7 @@ mod tests {
                     path: PathBuf::from("/etc/extensions/sysext1.raw"),
                     temp_path: PathBuf::from("/var/lib/extensions/.staging/sysext1.raw"),
                     ext_type: ExtensionType::Sysext,
+                    resolved_url: None,
                 },
                 // Sysext in /var/lib/extensions (default)
                 ExtensionData {
@@ -580,6 +1437,7 @@ mod tests {
                     path: PathBuf::from("/var/lib/extensions/sysext2.raw"),
                     temp_path: PathBuf::from("/var/lib/extensions/.staging/sysext2.raw"),
                     ext_type: ExtensionType::Sysext,
+                    resolved_url: None,
                 },
                 // Sysext in /.extra/sysext
                 ExtensionData {
@@ -589,6 +1447,7 @@ mod tests {
                     path: PathBuf::from("/.extra/sysext/sysext3.raw"),
                     temp_path: PathBuf::from("/var/lib/extensions/.staging/sysext3.raw"),
                     ext_type: ExtensionType::Sysext,
+                    resolved_url: None,
                 },
                 // Confext in /var/lib/confexts (default)
                 ExtensionData {
@@ -598,6 +1457,7 @@ mod tests {
                     path: PathBuf::from("/var/lib/confexts/confext1.raw"),
                     temp_path: PathBuf::from("/var/lib/extensions/.staging/confext1.raw"),
                     ext_type: ExtensionType::Confext,
+                    resolved_url: None,
                 },
                 // Confext in /usr/lib/confexts
                 ExtensionData {
@@ -607,6 +1467,7 @@ mod tests {
                     path: PathBuf::from("/usr/lib/confexts/confext2.raw"),
                     temp_path: PathBuf::from("/var/lib/extensions/.staging/confext2.raw"),
                     ext_type: ExtensionType::Confext,
+                    resolved_url: None,
                 },
                 // Confext in /usr/local/lib/confexts
                 ExtensionData {
@@ -616,9 +1477,11 @@ mod tests {
                     path: PathBuf::from("/usr/local/lib/confexts/confext3.raw"),
                     temp_path: PathBuf::from("/var/lib/extensions/.staging/confext3.raw"),
                     ext_type: ExtensionType::Confext,
+                    resolved_url: None,
                 },
             ],
             extensions_old: vec![],
+            ..Default::default()
         };
 
         let mount_path = TempDir::new().unwrap();
@@ -638,19 +1501,63 @@ mod tests {
         assert!(mount_path.path().join("usr/local/lib/confexts").exists());
     }
 
+    #[test]
+    fn test_copy_demands() {
+        let mount_path = TempDir::new().unwrap();
+        let staged = mount_path.path().join("staging/sysext1.raw");
+        fs::create_dir_all(staged.parent().unwrap()).unwrap();
+        fs::write(&staged, [0; 100]).unwrap();
+        let mut subsystem = ExtensionsSubsystem {
+            extensions: vec![ExtensionData {
+                id: "sysext1".to_string(),
+                name: "sysext1".to_string(),
+                sha384: Sha384Hash::from("a".repeat(96)),
+                path: PathBuf::from("/var/lib/extensions/sysext1.raw"),
+                temp_path: staged.clone(),
+                ext_type: ExtensionType::Sysext,
+                resolved_url: None,
+            }],
+            ..Default::default()
+        };
+
+        // Images on the filesystem of their path are moved, so no space is needed.
+        assert!(subsystem
+            .copy_demands(mount_path.path(), false)
+            .unwrap()
+            .is_empty());
+        assert!(subsystem
+            .copy_demands(mount_path.path(), true)
+            .unwrap()
+            .is_empty());
+
+        // Images linked into a store are copied into the new store.
+        let linked = mount_path.path().join("staging/linked.raw");
+        std::os::unix::fs::symlink(&staged, &linked).unwrap();
+        subsystem.extensions[0].temp_path = linked;
+        assert_eq!(
+            subsystem.copy_demands(mount_path.path(), true).unwrap(),
+            vec![SpaceDemand {
+                path: mount_path.path().join("var/lib/extensions/.trident-store"),
+                artifact: "sysext image 'sysext1'".to_string(),
+                bytes: 100,
+            }]
+        );
+    }
+
     #[test]
     fn test_update_host_configuration_sysexts() {
         let mut ctx = EngineContext::default();
         ctx.spec.os.sysexts = vec![
+            Extension::new(
+                Url::parse("https://example.com/sysext1.raw").unwrap(),
+                Sha384Hash::from("a".repeat(96)),
+            ),
             Extension {
-                url: Url::parse("https://example.com/sysext1.raw").unwrap(),
-                sha384: Sha384Hash::from("a".repeat(96)),
-                path: None,
-            },
-            Extension {
-                url: Url::parse("https://example.com/sysext2.raw").unwrap(),
-                sha384: Sha384Hash::from("b".repeat(96)),
                 path: Some(PathBuf::from("/etc/extensions/sysext2.raw")),
+                ..Extension::new(
+                    Url::parse("https://example.com/sysext2.raw").unwrap(),
+                    Sha384Hash::from("b".repeat(96)),
+                )
             },
         ];
 
@@ -661,21 +1568,32 @@ mod tests {
                     name: "sysext1".to_string(),
                     sha384: Sha384Hash::from("a".repeat(96)),
                     path: PathBuf::from("/var/lib/extensions/sysext1.raw"),
-                    temp_path: PathBuf::from(EXTENSION_IMAGE_STAGING_DIRECTORY).join("sysext1.raw"),
+                    temp_path: EngineContext::default()
+                        .paths
+                        .extension_staging_dir
+                        .join("sysext1.raw"),
 
                     ext_type: ExtensionType::Sysext,
+
+                    resolved_url: None,
                 },
                 ExtensionData {
                     id: "sysext2".to_string(),
                     name: "sysext2".to_string(),
                     sha384: Sha384Hash::from("b".repeat(96)),
                     path: PathBuf::from("/etc/extensions/sysext2.raw"),
-                    temp_path: PathBuf::from(EXTENSION_IMAGE_STAGING_DIRECTORY).join("sysext2.raw"),
+                    temp_path: EngineContext::default()
+                        .paths
+                        .extension_staging_dir
+                        .join("sysext2.raw"),
 
                     ext_type: ExtensionType::Sysext,
+
+                    resolved_url: None,
                 },
             ],
             extensions_old: vec![],
+            ..Default::default()
         };
         subsystem.update_host_configuration(&mut ctx).unwrap();
 
@@ -691,15 +1609,16 @@ mod tests {
     fn test_update_host_configuration_confexts() {
         let mut ctx = EngineContext::default();
         ctx.spec.os.confexts = vec![
+            Extension::new(
+                Url::parse("https://example.com/confext1.raw").unwrap(),
+                Sha384Hash::from("a".repeat(96)),
+            ),
             Extension {
-                url: Url::parse("https://example.com/confext1.raw").unwrap(),
-                sha384: Sha384Hash::from("a".repeat(96)),
-                path: None,
-            },
-            Extension {
-                url: Url::parse("https://example.com/confext2.raw").unwrap(),
-                sha384: Sha384Hash::from("b".repeat(96)),
                 path: Some(PathBuf::from("/usr/lib/confexts/confext2.raw")),
+                ..Extension::new(
+                    Url::parse("https://example.com/confext2.raw").unwrap(),
+                    Sha384Hash::from("b".repeat(96)),
+                )
             },
         ];
 
@@ -710,9 +1629,12 @@ mod tests {
                     name: "confext1".to_string(),
                     sha384: Sha384Hash::from("a".repeat(96)),
                     path: PathBuf::from("/var/lib/confexts/confext1.raw"),
-                    temp_path: PathBuf::from(EXTENSION_IMAGE_STAGING_DIRECTORY)
+                    temp_path: EngineContext::default()
+                        .paths
+                        .extension_staging_dir
                         .join("confext1.raw"),
                     ext_type: ExtensionType::Confext,
+                    resolved_url: None,
                 },
                 ExtensionData {
                     id: "confext2".to_string(),
@@ -721,9 +1643,11 @@ mod tests {
                     path: PathBuf::from("/usr/lib/confexts/confext2.raw"),
                     temp_path: PathBuf::from("/var/lib/extensions/.staging/confext2.raw"),
                     ext_type: ExtensionType::Confext,
+                    resolved_url: None,
                 },
             ],
             extensions_old: vec![],
+            ..Default::default()
         };
         subsystem.update_host_configuration(&mut ctx).unwrap();
 
@@ -746,11 +1670,15 @@ mod functional_test {
     use url::Url;
 
     use osutils::{
+        dependencies::Dependency,
         filesystems::{MkfsFileSystemType, MountFileSystemType},
         mkfs, mount,
     };
     use pytest_gen::functional_test;
-    use trident_api::constants::{DEFAULT_CONFEXT_DIRECTORY, DEFAULT_SYSEXT_DIRECTORY};
+    use trident_api::{
+        constants::{DEFAULT_CONFEXT_DIRECTORY, DEFAULT_SYSEXT_DIRECTORY},
+        primitives::hash::Sha256Hash,
+    };
 
     /// Helper to create a minimal Discoverable Disk Image extension for testing
     fn create_test_extension_image(
@@ -829,24 +1757,20 @@ mod functional_test {
                 create_test_extension_image(&path, ext_name, ext_type, ext_release_content);
             match (ext_type, new) {
                 (ExtensionType::Sysext, true) => output.spec.os.sysexts.push(Extension {
-                    url: Url::from_file_path(path).unwrap(),
-                    sha384: test_ext_hash,
                     path: file_path.clone(),
+                    ..Extension::new(Url::from_file_path(path).unwrap(), test_ext_hash)
                 }),
                 (ExtensionType::Confext, true) => output.spec.os.confexts.push(Extension {
-                    url: Url::from_file_path(path).unwrap(),
-                    sha384: test_ext_hash,
                     path: file_path.clone(),
+                    ..Extension::new(Url::from_file_path(path).unwrap(), test_ext_hash)
                 }),
                 (ExtensionType::Sysext, false) => output.spec_old.os.sysexts.push(Extension {
-                    url: Url::from_file_path(path).unwrap(),
-                    sha384: test_ext_hash,
                     path: file_path.clone(),
+                    ..Extension::new(Url::from_file_path(path).unwrap(), test_ext_hash)
                 }),
                 (ExtensionType::Confext, false) => output.spec_old.os.confexts.push(Extension {
-                    url: Url::from_file_path(path).unwrap(),
-                    sha384: test_ext_hash,
                     path: file_path.clone(),
+                    ..Extension::new(Url::from_file_path(path).unwrap(), test_ext_hash)
                 }),
             }
         }
@@ -980,10 +1904,42 @@ mod functional_test {
         // Create Extension with incorrect hash
         let wrong_hash = Sha384Hash::from("a".repeat(96));
         let extension_url = Url::from_file_path(&temp_file).unwrap();
+        let hc_extension = Extension::new(extension_url.clone(), wrong_hash.clone());
+
+        // Attempt to process - should fail due to hash mismatch
+        let mut ctx = EngineContext::default();
+        ctx.spec.os.sysexts = vec![hc_extension];
+        let mut subsystem = ExtensionsSubsystem::default();
+        let error = subsystem
+            .populate_extensions(&ctx, &temp_dir())
+            .unwrap_err()
+            .to_string();
+
+        assert_eq!(error, format!("SHA384 mismatch for extension image at '{extension_url}': expected {wrong_hash}, got {actual_hash}"));
+    }
+
+    #[functional_test]
+    fn test_populate_extensions_sha256_mismatch() {
+        // Create an extension image
+        let temp_file = NamedTempFile::new()
+            .unwrap()
+            .into_temp_path()
+            .keep()
+            .unwrap();
+        let hash = create_test_extension_image(
+            &temp_file,
+            "test_ext",
+            &ExtensionType::Sysext,
+            "ID=_any\nSYSEXT_ID=test_ext",
+        );
+        let actual_sha256 = compute_file_sha256(&temp_file).unwrap();
+
+        // Create Extension with correct SHA384 but incorrect SHA256
+        let wrong_sha256 = Sha256Hash::from("a".repeat(64));
+        let extension_url = Url::from_file_path(&temp_file).unwrap();
         let hc_extension = Extension {
-            url: extension_url.clone(),
-            sha384: wrong_hash.clone(),
-            path: None,
+            sha256: Some(wrong_sha256.clone()),
+            ..Extension::new(extension_url.clone(), hash)
         };
 
         // Attempt to process - should fail due to hash mismatch
@@ -995,7 +1951,7 @@ mod functional_test {
             .unwrap_err()
             .to_string();
 
-        assert_eq!(error, format!("SHA384 mismatch for extension image at '{extension_url}': expected {wrong_hash}, got {actual_hash}"));
+        assert_eq!(error, format!("SHA256 mismatch for extension image at '{extension_url}': expected {wrong_sha256}, got {actual_sha256}"));
     }
 
     // Location of existing ext doesn't exist
@@ -1017,28 +1973,32 @@ mod functional_test {
         let ext_url = Url::from_file_path(&temp_file).unwrap();
         let ext_path = PathBuf::from("/etc/extensions/test_ext.raw"); // No file exists at this path
         let hc_extension = Extension {
-            url: ext_url.clone(),
-            sha384: hash,
             path: Some(ext_path.clone()),
+            ..Extension::new(ext_url, hash)
         };
 
-        // Attempt to process as an existing Extension
+        // The image is missing from the servicing OS, e.g. because it was deleted by hand.
+        assert_eq!(existing_image(&hc_extension, ext_path).unwrap(), None);
+
+        // Processing it as an existing Extension skips it
         let mut ctx = EngineContext::default();
         ctx.spec_old.os.sysexts = vec![hc_extension];
         let mut subsystem = ExtensionsSubsystem::default();
-        let error = subsystem
-            .populate_extensions(&ctx, &temp_dir())
-            .unwrap_err()
-            .to_string();
+        let releases = subsystem.populate_extensions(&ctx, &temp_dir()).unwrap();
 
-        assert_eq!(error, format!("Expected to find extension image from URL '{ext_url}' at path '{}', but path does not exist", ext_path.display()));
+        assert!(releases.is_empty());
+        assert!(subsystem.extensions_old.is_empty());
+        assert!(subsystem.extensions.is_empty());
     }
 
     #[functional_test]
     fn test_set_up_extensions_add() {
         // Test adding new extensions
         let mount_path = TempDir::new().unwrap();
-        let staging_dir = path::join_relative(mount_path.path(), EXTENSION_IMAGE_STAGING_DIRECTORY);
+        let staging_dir = path::join_relative(
+            mount_path.path(),
+            EngineContext::default().paths.extension_staging_dir,
+        );
         fs::create_dir_all(&staging_dir).unwrap();
 
         // Create a new extension in staging directory
@@ -1059,8 +2019,10 @@ mod functional_test {
                 path: target_path.clone(),
                 temp_path: temp_file.path().to_path_buf(),
                 ext_type: ExtensionType::Sysext,
+                resolved_url: None,
             }],
             extensions_old: vec![],
+            ..Default::default()
         };
 
         // Create necessary directories
@@ -1068,7 +2030,7 @@ mod functional_test {
 
         // Run set_up_extensions
         subsystem
-            .set_up_extensions(mount_path.path(), ServicingType::CleanInstall)
+            .set_up_extensions(mount_path.path(), ServicingType::CleanInstall, false)
             .unwrap();
 
         // Verify the extension was copied to the target location
@@ -1087,7 +2049,10 @@ mod functional_test {
     fn test_set_up_extensions_add_duplicate_id() {
         // Test adding new extensions where a sysext and confext have the same ID
         let mount_path = TempDir::new().unwrap();
-        let staging_dir = path::join_relative(mount_path.path(), EXTENSION_IMAGE_STAGING_DIRECTORY);
+        let staging_dir = path::join_relative(
+            mount_path.path(),
+            EngineContext::default().paths.extension_staging_dir,
+        );
         fs::create_dir_all(&staging_dir).unwrap();
 
         // Create a sysext and confext with the same "ID"
@@ -1117,6 +2082,7 @@ mod functional_test {
                     path: sysext_target_path.clone(),
                     temp_path: sysext_file.path().to_path_buf(),
                     ext_type: ExtensionType::Sysext,
+                    resolved_url: None,
                 },
                 ExtensionData {
                     id: "new_ext".to_string(),
@@ -1125,9 +2091,11 @@ mod functional_test {
                     path: confext_target_path.clone(),
                     temp_path: confext_file.path().to_path_buf(),
                     ext_type: ExtensionType::Confext,
+                    resolved_url: None,
                 },
             ],
             extensions_old: vec![],
+            ..Default::default()
         };
 
         // Create necessary directories
@@ -1135,7 +2103,7 @@ mod functional_test {
 
         // Run set_up_extensions
         subsystem
-            .set_up_extensions(mount_path.path(), ServicingType::CleanInstall)
+            .set_up_extensions(mount_path.path(), ServicingType::CleanInstall, false)
             .unwrap();
 
         // Verify the extensions were copied to their target locations
@@ -1180,7 +2148,9 @@ mod functional_test {
                 path: old_ext.path().to_path_buf(),
                 temp_path: old_ext.path().to_path_buf(),
                 ext_type: ExtensionType::Sysext,
+                resolved_url: None,
             }],
+            ..Default::default()
         };
 
         let mount_path = TempDir::new().unwrap();
@@ -1188,7 +2158,7 @@ mod functional_test {
         subsystem.create_directories(mount_path.path()).unwrap();
         // Run set_up_extensions with A/B update (should NOT remove old extensions)
         subsystem
-            .set_up_extensions(mount_path.path(), ServicingType::AbUpdate)
+            .set_up_extensions(mount_path.path(), ServicingType::AbUpdate, false)
             .unwrap();
         // Verify the extension still exists
         assert!(
@@ -1200,7 +2170,7 @@ mod functional_test {
 
         // Run set_up_extensions with HotPath (should remove old extensions)
         subsystem
-            .set_up_extensions(mount_path.path(), ServicingType::HotPatch)
+            .set_up_extensions(mount_path.path(), ServicingType::HotPatch, false)
             .unwrap();
         // Verify the extension was removed
         assert!(!old_ext.path().exists(), "Old extension should be removed");
@@ -1224,7 +2194,10 @@ mod functional_test {
 
         // Create new version with different content
         let mount_path = TempDir::new().unwrap();
-        let staging_dir = path::join_relative(mount_path.path(), EXTENSION_IMAGE_STAGING_DIRECTORY);
+        let staging_dir = path::join_relative(
+            mount_path.path(),
+            EngineContext::default().paths.extension_staging_dir,
+        );
         fs::create_dir_all(&staging_dir).unwrap();
         let new_ext = NamedTempFile::new_in(&staging_dir).unwrap();
         let new_hash = create_test_extension_image(
@@ -1243,6 +2216,7 @@ mod functional_test {
                 path: PathBuf::from(target_path),
                 temp_path: new_ext.path().to_path_buf(),
                 ext_type: ExtensionType::Sysext,
+                resolved_url: None,
             }],
             extensions_old: vec![ExtensionData {
                 id: "my_ext".to_string(), // Matching ID
@@ -1251,14 +2225,16 @@ mod functional_test {
                 path: old_ext.path().to_path_buf(),
                 temp_path: old_ext.path().to_path_buf(),
                 ext_type: ExtensionType::Sysext,
+                resolved_url: None,
             }],
+            ..Default::default()
         };
 
         // Create necessary directories
         subsystem.create_directories(mount_path.path()).unwrap();
         // Run set_up_extensions; A/B update
         subsystem
-            .set_up_extensions(mount_path.path(), ServicingType::AbUpdate)
+            .set_up_extensions(mount_path.path(), ServicingType::AbUpdate, false)
             .unwrap();
 
         // Verify old extension was NOT removed, since servicing type is A/B update
@@ -1290,7 +2266,10 @@ mod functional_test {
 
         // Create new version with different content
         let mount_path = TempDir::new().unwrap();
-        let staging_dir = path::join_relative(mount_path.path(), EXTENSION_IMAGE_STAGING_DIRECTORY);
+        let staging_dir = path::join_relative(
+            mount_path.path(),
+            EngineContext::default().paths.extension_staging_dir,
+        );
         fs::create_dir_all(&staging_dir).unwrap();
         let new_ext = NamedTempFile::new_in(&staging_dir).unwrap();
         let new_hash = create_test_extension_image(
@@ -1309,6 +2288,7 @@ mod functional_test {
                 path: PathBuf::from(target_path),
                 temp_path: new_ext.path().to_path_buf(),
                 ext_type: ExtensionType::Sysext,
+                resolved_url: None,
             }],
             extensions_old: vec![ExtensionData {
                 id: "my_ext".to_string(), // Matching ID
@@ -1317,14 +2297,16 @@ mod functional_test {
                 path: old_ext.path().to_path_buf(),
                 temp_path: old_ext.path().to_path_buf(),
                 ext_type: ExtensionType::Sysext,
+                resolved_url: None,
             }],
+            ..Default::default()
         };
 
         // Create necessary directories
         subsystem.create_directories(mount_path.path()).unwrap();
         // Run set_up_extensions; hot patch update
         subsystem
-            .set_up_extensions(mount_path.path(), ServicingType::HotPatch)
+            .set_up_extensions(mount_path.path(), ServicingType::HotPatch, false)
             .unwrap();
 
         // Verify old extension was removed, since servicing type is not A/B
@@ -1350,8 +2332,8 @@ mod functional_test {
         loopback.as_file().set_len(1024 * 1024).unwrap();
         mkfs::run(loopback.path(), MkfsFileSystemType::Ext4).unwrap();
 
-        let old_ext_mount = Path::new("/mnt/tmpfs");
-        fs::create_dir_all(old_ext_mount).unwrap();
+        let old_ext_mount_dir = TempDir::new().unwrap();
+        let old_ext_mount = old_ext_mount_dir.path();
         mount::mount(
             "tmpfs",
             old_ext_mount,
@@ -1359,6 +2341,9 @@ mod functional_test {
             &["size=1M".into()],
         )
         .unwrap();
+        let _old_ext_mount_guard = mount::MountGuard {
+            mount_dir: old_ext_mount,
+        };
 
         // Create old extension
         let old_ext = NamedTempFile::new_in(old_ext_mount).unwrap();
@@ -1378,6 +2363,7 @@ mod functional_test {
                 path: PathBuf::from(target_path),
                 temp_path: old_ext.path().to_path_buf(), // Sysext exists on servicing OS, so temp_path should point to this file.
                 ext_type: ExtensionType::Sysext,
+                resolved_url: None,
             }],
             extensions_old: vec![ExtensionData {
                 id: "my_ext".to_string(),
@@ -1386,7 +2372,9 @@ mod functional_test {
                 path: old_ext.path().to_path_buf(),
                 temp_path: old_ext.path().to_path_buf(),
                 ext_type: ExtensionType::Sysext,
+                resolved_url: None,
             }],
+            ..Default::default()
         };
 
         let mount_path = TempDir::new().unwrap();
@@ -1394,7 +2382,7 @@ mod functional_test {
         subsystem.create_directories(mount_path.path()).unwrap();
         // Run set_up_extensions
         subsystem
-            .set_up_extensions(mount_path.path(), ServicingType::AbUpdate)
+            .set_up_extensions(mount_path.path(), ServicingType::AbUpdate, false)
             .unwrap();
 
         // Verify old extension was not removed. Since old extension exists on a
@@ -1414,10 +2402,6 @@ mod functional_test {
             fs::read(path::join_relative(mount_path.path(), target_path)).unwrap(),
             "Old extension should match version on target OS"
         );
-
-        // Clean-up
-        drop(old_ext);
-        mount::umount(old_ext_mount, true).unwrap();
     }
 
     #[functional_test]
@@ -1443,6 +2427,7 @@ mod functional_test {
                 path: PathBuf::from(target_path),
                 temp_path: old_ext.path().to_path_buf(), // Sysext exists on servicing OS, so temp_path should point to this file.
                 ext_type: ExtensionType::Sysext,
+                resolved_url: None,
             }],
             extensions_old: vec![ExtensionData {
                 id: "my_ext".to_string(),
@@ -1451,7 +2436,9 @@ mod functional_test {
                 path: old_ext.path().to_path_buf(),
                 temp_path: old_ext.path().to_path_buf(),
                 ext_type: ExtensionType::Sysext,
+                resolved_url: None,
             }],
+            ..Default::default()
         };
 
         let mount_path = TempDir::new().unwrap();
@@ -1459,7 +2446,7 @@ mod functional_test {
         subsystem.create_directories(mount_path.path()).unwrap();
         // Run set_up_extensions
         subsystem
-            .set_up_extensions(mount_path.path(), ServicingType::HotPatch)
+            .set_up_extensions(mount_path.path(), ServicingType::HotPatch, false)
             .unwrap();
 
         // Verify old extension was removed. Since servicing OS == target OS,
@@ -0,0 +1,306 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{Context, Error};
+use log::debug;
+use serde::Serialize;
+
+use osutils::{path, sysext::ExtensionKind};
+use trident_api::{
+    constants::DEFAULT_CONFEXT_DIRECTORY,
+    messages::{self, MessageKey},
+};
+
+use crate::table::format_table;
+
+/// Extension image found on the OS that is not part of the Host Configuration.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UnmanagedImage {
+    /// Kind of the image, `sysext` or `confext`.
+    #[serde(serialize_with = "serialize_kind")]
+    pub kind: ExtensionKind,
+
+    /// Name of the image, without its version. Only the most recent images of each name are
+    /// kept.
+    pub name: String,
+
+    /// Path of the image, relative to the OS.
+    pub path: PathBuf,
+
+    /// Whether the image is pruned, or kept as one of the most recent images of its name.
+    pub pruned: bool,
+}
+
+fn serialize_kind<S: serde::Serializer>(kind: &ExtensionKind, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(kind)
+}
+
+/// Finds the `.raw` extension images in the extension image directories of the OS rooted at
+/// `root` that are not in `managed`, the paths of the images of the Host Configuration. Sysext
+/// images are searched in `sysext_directory`, the directory Trident installs them to, and confext
+/// images in the default confext directory. Other directories may hold images shipped with the
+/// OS, so they are never pruned.
+///
+/// Images are grouped by name, ignoring a trailing version, so that e.g. `docker_28.0.4.raw` and
+/// `docker-27.1.raw` are versions of `docker`. The `keep` most recently modified images of each
+/// name are kept, and all the others are marked as pruned.
+pub(crate) fn find_unmanaged(
+    root: &Path,
+    sysext_directory: &Path,
+    managed: &HashSet<PathBuf>,
+    keep: usize,
+) -> Result<Vec<UnmanagedImage>, Error> {
+    let directories = [
+        (ExtensionKind::Sysext, sysext_directory),
+        (ExtensionKind::Confext, Path::new(DEFAULT_CONFEXT_DIRECTORY)),
+    ];
+    let mut groups = BTreeMap::<(usize, String), Vec<(SystemTime, PathBuf)>>::new();
+    for (kind_index, (_, directory)) in directories.into_iter().enumerate() {
+        let host_directory = path::join_relative(root, directory);
+        if !host_directory.is_dir() {
+            continue;
+        }
+        for entry in fs::read_dir(&host_directory)
+            .with_context(|| format!("Failed to read directory '{}'", host_directory.display()))?
+        {
+            let entry = entry.with_context(|| {
+                format!("Failed to read directory '{}'", host_directory.display())
+            })?;
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let Some(stem) = file_name.strip_suffix(".raw") else {
+                continue;
+            };
+            // Hidden files, e.g. in-flight downloads, are not extension images. Images may be
+            // symlinks into the versioned store.
+            if stem.starts_with('.')
+                || !entry
+                    .file_type()
+                    .is_ok_and(|t| t.is_file() || t.is_symlink())
+            {
+                continue;
+            }
+            let image_path = directory.join(&file_name);
+            if managed.contains(&image_path) {
+                continue;
+            }
+
+            let modified = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .with_context(|| {
+                    format!("Failed to read metadata of '{}'", image_path.display())
+                })?;
+            groups
+                .entry((kind_index, image_name(stem).to_string()))
+                .or_default()
+                .push((modified, image_path));
+        }
+    }
+
+    let mut images = Vec::new();
+    for ((kind_index, name), mut group) in groups {
+        let kind = directories[kind_index].0;
+        // Most recent first.
+        group.sort_by(|a, b| b.cmp(a));
+        for (index, (_, path)) in group.into_iter().enumerate() {
+            debug!(
+                "Found unmanaged {kind} image '{}' ({})",
+                path.display(),
+                if index < keep { "kept" } else { "pruned" }
+            );
+            images.push(UnmanagedImage {
+                kind,
+                name: name.clone(),
+                path,
+                pruned: index >= keep,
+            });
+        }
+    }
+    Ok(images)
+}
+
+/// Returns the name of an extension image from the stem of its file name, dropping the version
+/// that follows the first `_` or `-` followed by a digit.
+fn image_name(stem: &str) -> &str {
+    stem.char_indices()
+        .zip(stem.chars().skip(1))
+        .find(|((_, c), next)| matches!(c, '_' | '-') && next.is_ascii_digit())
+        .map_or(stem, |((index, _), _)| &stem[..index])
+}
+
+/// Renders the unmanaged images as a table.
+pub(crate) fn to_table(images: &[UnmanagedImage], dry_run: bool) -> String {
+    format_table(
+        &[
+            MessageKey::TableKind,
+            MessageKey::TableName,
+            MessageKey::TableAction,
+            MessageKey::TablePath,
+        ],
+        images
+            .iter()
+            .map(|image| {
+                vec![
+                    image.kind.to_string(),
+                    image.name.clone(),
+                    messages::render(
+                        match (image.pruned, dry_run) {
+                            (true, false) => MessageKey::PruneActionPruned,
+                            (true, true) => MessageKey::PruneActionWouldPrune,
+                            (false, _) => MessageKey::PruneActionKept,
+                        },
+                        &[],
+                    ),
+                    image.path.display().to_string(),
+                ]
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{fs::File, os::unix::fs::symlink, time::Duration};
+
+    use tempfile::TempDir;
+
+    use trident_api::constants::DEFAULT_SYSEXT_DIRECTORY;
+
+    #[test]
+    fn test_image_name() {
+        assert_eq!(image_name("docker"), "docker");
+        assert_eq!(image_name("docker_28.0.4"), "docker");
+        assert_eq!(image_name("docker-27.1"), "docker");
+        assert_eq!(image_name("cloud-init_1"), "cloud-init");
+        assert_eq!(image_name("k8s-v1.30"), "k8s-v1.30");
+    }
+
+    #[test]
+    fn test_find_unmanaged() {
+        let root = TempDir::new().unwrap();
+        let sysexts = path::join_relative(root.path(), DEFAULT_SYSEXT_DIRECTORY);
+        let confexts = path::join_relative(root.path(), DEFAULT_CONFEXT_DIRECTORY);
+        fs::create_dir_all(sysexts.join(".trident-backup")).unwrap();
+        fs::create_dir_all(&confexts).unwrap();
+
+        let epoch = SystemTime::UNIX_EPOCH;
+        for (file, age) in [
+            (sysexts.join("docker.raw"), 0),
+            (sysexts.join("docker_1.raw"), 3),
+            (sysexts.join("docker_2.raw"), 2),
+            (sysexts.join("docker_3.raw"), 1),
+            (sysexts.join("notes.txt"), 0),
+            (sysexts.join(".download.raw"), 0),
+            (sysexts.join(".trident-backup/docker_0.raw"), 0),
+            (confexts.join("motd.raw"), 0),
+        ] {
+            File::create(&file)
+                .unwrap()
+                .set_modified(epoch + Duration::from_secs(100 - age))
+                .unwrap();
+        }
+
+        let managed = HashSet::from([Path::new(DEFAULT_SYSEXT_DIRECTORY).join("docker.raw")]);
+        let sysext_directory = Path::new(DEFAULT_SYSEXT_DIRECTORY);
+        let images = find_unmanaged(root.path(), sysext_directory, &managed, 1).unwrap();
+        let image = |kind: ExtensionKind, name: &str, file: &str, pruned| UnmanagedImage {
+            kind,
+            name: name.into(),
+            path: Path::new(match kind {
+                ExtensionKind::Sysext => DEFAULT_SYSEXT_DIRECTORY,
+                ExtensionKind::Confext => DEFAULT_CONFEXT_DIRECTORY,
+            })
+            .join(file),
+            pruned,
+        };
+        assert_eq!(
+            images,
+            vec![
+                image(ExtensionKind::Sysext, "docker", "docker_3.raw", false),
+                image(ExtensionKind::Sysext, "docker", "docker_2.raw", true),
+                image(ExtensionKind::Sysext, "docker", "docker_1.raw", true),
+                image(ExtensionKind::Confext, "motd", "motd.raw", false),
+            ]
+        );
+
+        let images = find_unmanaged(root.path(), sysext_directory, &managed, 0).unwrap();
+        assert!(images.iter().all(|image| image.pruned));
+
+        // Sysext images are only searched in the configured install path.
+        let etc_sysexts = path::join_relative(root.path(), "/etc/extensions");
+        fs::create_dir_all(&etc_sysexts).unwrap();
+        File::create(etc_sysexts.join("debug.raw")).unwrap();
+        let images =
+            find_unmanaged(root.path(), Path::new("/etc/extensions"), &managed, 0).unwrap();
+        assert_eq!(
+            images
+                .iter()
+                .map(|image| image.path.as_path())
+                .collect::<Vec<_>>(),
+            vec![
+                Path::new("/etc/extensions/debug.raw"),
+                Path::new("/var/lib/confexts/motd.raw"),
+            ]
+        );
+
+        // Nothing to prune on an OS without extension image directories.
+        let empty = TempDir::new().unwrap();
+        assert!(find_unmanaged(empty.path(), sysext_directory, &managed, 0)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_find_unmanaged_symlinks() {
+        let root = TempDir::new().unwrap();
+        let sysexts = path::join_relative(root.path(), DEFAULT_SYSEXT_DIRECTORY);
+        fs::create_dir_all(sysexts.join(".trident-store")).unwrap();
+        fs::write(sysexts.join(".trident-store/0123.raw"), b"").unwrap();
+        symlink(".trident-store/0123.raw", sysexts.join("kubelet.raw")).unwrap();
+        symlink(".trident-store/4567.raw", sysexts.join("debug.raw")).unwrap();
+
+        // Symlinks into the versioned store are images too, even once the stored image is gone.
+        let mut images = find_unmanaged(
+            root.path(),
+            Path::new(DEFAULT_SYSEXT_DIRECTORY),
+            &HashSet::new(),
+            0,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|image| image.path)
+        .collect::<Vec<_>>();
+        images.sort();
+        assert_eq!(
+            images,
+            vec![
+                PathBuf::from("/var/lib/extensions/debug.raw"),
+                PathBuf::from("/var/lib/extensions/kubelet.raw"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_table() {
+        let images = vec![UnmanagedImage {
+            kind: ExtensionKind::Sysext,
+            name: "docker".into(),
+            path: "/var/lib/extensions/docker_1.raw".into(),
+            pruned: true,
+        }];
+        assert_eq!(
+            to_table(&images, true),
+            indoc::indoc! {"
+                KIND    NAME    ACTION       PATH
+                sysext  docker  would prune  /var/lib/extensions/docker_1.raw
+            "}
+        );
+    }
+}
@@ -7,11 +7,12 @@ use anyhow::{ensure, Context, Error};
 use const_format::formatcp;
 use log::debug;
 
-use osutils::{osrelease::ExtensionRelease, path};
-use trident_api::{
-    config::Extension,
-    constants::{DEFAULT_CONFEXT_DIRECTORY, DEFAULT_SYSEXT_DIRECTORY},
+use osutils::{
+    osrelease::{ExtensionRelease, OsRelease},
+    path,
 };
+use sysdefs::arch::SystemArchitecture;
+use trident_api::config::{Extension, ExtensionScope};
 
 use crate::subsystems::extensions::{
     ExtensionData, ExtensionType, CONFEXT_EXTENSION_RELEASE_DIRECTORY, EXTENSION_RELEASE,
@@ -20,13 +21,22 @@ use crate::subsystems::extensions::{
 
 const EXTENSION_RELEASE_PREFIX: &str = formatcp!("{EXTENSION_RELEASE}.");
 
-/// Helper function to extract information from extension-release file.
+/// Value of ID and ARCHITECTURE in an extension-release file matching any OS.
+const ANY: &str = "_any";
+
+/// Scopes of extension images that do not set SYSEXT_SCOPE or CONFEXT_SCOPE.
+const DEFAULT_SCOPES: &str = "system portable";
+
+/// Helper function to extract information from extension-release file. Returns the extension
+/// data along with the parsed extension-release file. Images without an explicit path are placed
+/// in `default_directory`.
 pub(crate) fn read_extension_release(
     mount_point: &Path,
     curr_path: &Path,
     ext: &Extension,
     ext_type: &ExtensionType,
-) -> Result<ExtensionData, Error> {
+    default_directory: &Path,
+) -> Result<(ExtensionData, ExtensionRelease), Error> {
     debug!(
         "Processing extension-release file for extension image at '{}'",
         ext.url
@@ -76,24 +86,113 @@ pub(crate) fn read_extension_release(
         .to_string();
     let path = match &ext.path {
         Some(path) => path.clone(),
-        None => match ext_type {
-            ExtensionType::Sysext => {
-                PathBuf::from(DEFAULT_SYSEXT_DIRECTORY).join(format!("{name}.raw"))
-            }
-            ExtensionType::Confext => {
-                PathBuf::from(DEFAULT_CONFEXT_DIRECTORY).join(format!("{name}.raw"))
-            }
+        None => default_directory.join(format!("{name}.raw")),
+    };
+
+    Ok((
+        ExtensionData {
+            id: extension_id,
+            name,
+            sha384: ext.sha384.clone(),
+            path,
+            temp_path: curr_path.to_path_buf(),
+            ext_type: ext_type.clone(),
+            resolved_url: None,
         },
+        extension_release,
+    ))
+}
+
+/// Checks that an extension image can be merged into `scope` on the target OS, following the
+/// rules of systemd-sysext: SYSEXT_SCOPE (or CONFEXT_SCOPE) must list the scope, ARCHITECTURE must
+/// match the host, and unless ID is `_any`, ID must match the target OS along with SYSEXT_LEVEL
+/// (or CONFEXT_LEVEL), or VERSION_ID when no level is set.
+///
+/// Returns an explanation of the mismatch if the extension image is not compatible.
+pub(crate) fn check_compatibility(
+    extension_release: &ExtensionRelease,
+    ext_type: &ExtensionType,
+    scope: ExtensionScope,
+    architecture: SystemArchitecture,
+    target_os: &OsRelease,
+) -> Result<(), String> {
+    let scopes = extension_release.scope.as_deref().unwrap_or(DEFAULT_SCOPES);
+    if !scopes.split_whitespace().any(|s| s == scope.as_str()) {
+        let scope_key = match ext_type {
+            ExtensionType::Sysext => "SYSEXT_SCOPE",
+            ExtensionType::Confext => "CONFEXT_SCOPE",
+        };
+        return Err(format!(
+            "{scope_key} is '{scopes}', but the image is merged into '{}'",
+            scope.as_str()
+        ));
+    }
+
+    if let Some(ext_architecture) = extension_release
+        .architecture
+        .as_deref()
+        .filter(|arch| *arch != ANY)
+    {
+        let host_architecture = systemd_architecture(architecture);
+        if ext_architecture != host_architecture {
+            return Err(format!(
+                "ARCHITECTURE is '{ext_architecture}', but host is '{host_architecture}'"
+            ));
+        }
+    }
+
+    let ext_os = &extension_release.os_release;
+    let ext_id = ext_os
+        .id
+        .as_deref()
+        .ok_or("ID is not set in extension-release")?;
+    if ext_id == ANY {
+        return Ok(());
+    }
+    let target_id = target_os.id.as_deref().unwrap_or_default();
+    if ext_id != target_id {
+        return Err(format!("ID is '{ext_id}', but target OS is '{target_id}'"));
+    }
+
+    let (level_key, ext_level, target_level) = match ext_type {
+        ExtensionType::Sysext => (
+            "SYSEXT_LEVEL",
+            &ext_os.sysext_level,
+            &target_os.sysext_level,
+        ),
+        ExtensionType::Confext => (
+            "CONFEXT_LEVEL",
+            &ext_os.confext_level,
+            &target_os.confext_level,
+        ),
+    };
+    let (key, ext_value, target_value) = match ext_level {
+        Some(ext_level) => (level_key, ext_level, target_level),
+        None => (
+            "VERSION_ID",
+            ext_os.version_id.as_ref().ok_or_else(|| {
+                format!("Neither {level_key} nor VERSION_ID is set in extension-release")
+            })?,
+            &target_os.version_id,
+        ),
     };
+    let target_value = target_value.as_deref().unwrap_or_default();
+    if ext_value != target_value {
+        return Err(format!(
+            "{key} is '{ext_value}', but target OS is '{target_value}'"
+        ));
+    }
+
+    Ok(())
+}
 
-    Ok(ExtensionData {
-        id: extension_id,
-        name,
-        sha384: ext.sha384.clone(),
-        path,
-        temp_path: curr_path.to_path_buf(),
-        ext_type: ext_type.clone(),
-    })
+/// Returns the name of the architecture as used in the ARCHITECTURE field of extension-release
+/// files.
+fn systemd_architecture(architecture: SystemArchitecture) -> &'static str {
+    match architecture {
+        SystemArchitecture::Amd64 => "x86-64",
+        SystemArchitecture::Aarch64 => "arm64",
+    }
 }
 
 #[cfg(test)]
@@ -103,14 +202,19 @@ mod tests {
     use std::{fs::File, io::Write};
 
     use tempfile::TempDir;
-    use trident_api::primitives::hash::Sha384Hash;
+    use trident_api::{
+        constants::{DEFAULT_CONFEXT_DIRECTORY, DEFAULT_SYSEXT_DIRECTORY},
+        primitives::hash::Sha384Hash,
+    };
     use url::Url;
 
     fn create_extension(hash: Sha384Hash, path: Option<PathBuf>) -> Extension {
         Extension {
-            url: Url::parse("https://example.com/test-extension").unwrap(),
-            sha384: hash.clone(),
             path,
+            ..Extension::new(
+                Url::parse("https://example.com/test-extension").unwrap(),
+                hash.clone(),
+            )
         }
     }
 
@@ -133,13 +237,15 @@ mod tests {
         let current_path = Path::new("/tmp/file");
         let extension = create_extension(hash.clone(), None);
 
-        let extension_data = read_extension_release(
+        let (extension_data, extension_release) = read_extension_release(
             mount_point,
             current_path,
             &extension,
             &ExtensionType::Sysext,
+            Path::new(DEFAULT_SYSEXT_DIRECTORY),
         )
         .unwrap();
+        assert_eq!(extension_release.architecture, Some("x86-64".to_string()));
         let expected_extension_data = ExtensionData {
             id: "test".to_string(),
             name: "test_1.0.0".to_string(),
@@ -147,6 +253,7 @@ mod tests {
             path: PathBuf::from(DEFAULT_SYSEXT_DIRECTORY).join("test_1.0.0.raw"),
             temp_path: PathBuf::from(current_path),
             ext_type: ExtensionType::Sysext,
+            resolved_url: None,
         };
         assert_eq!(extension_data, expected_extension_data);
 
@@ -154,11 +261,12 @@ mod tests {
         let final_path = PathBuf::from("/etc/extensions/test_1.0.0.raw");
         let extension_with_path = create_extension(hash.clone(), Some(final_path.clone()));
 
-        let extension_data = read_extension_release(
+        let (extension_data, _) = read_extension_release(
             mount_point,
             current_path,
             &extension_with_path,
             &ExtensionType::Sysext,
+            Path::new(DEFAULT_SYSEXT_DIRECTORY),
         )
         .unwrap();
         let expected_extension_data = ExtensionData {
@@ -168,8 +276,22 @@ mod tests {
             path: final_path,
             temp_path: PathBuf::from(current_path),
             ext_type: ExtensionType::Sysext,
+            resolved_url: None,
         };
         assert_eq!(extension_data, expected_extension_data);
+        // Images without an explicit path are placed in the configured directory
+        let (extension_data, _) = read_extension_release(
+            mount_point,
+            current_path,
+            &extension,
+            &ExtensionType::Sysext,
+            Path::new("/etc/extensions"),
+        )
+        .unwrap();
+        assert_eq!(
+            extension_data.path,
+            PathBuf::from("/etc/extensions/test_1.0.0.raw")
+        );
     }
 
     // Extension-release directory does not exist
@@ -186,6 +308,7 @@ mod tests {
             current_path,
             &extension,
             &ExtensionType::Sysext,
+            Path::new(DEFAULT_SYSEXT_DIRECTORY),
         );
         assert_eq!(
             result.unwrap_err().to_string(),
@@ -197,6 +320,7 @@ mod tests {
             current_path,
             &extension,
             &ExtensionType::Confext,
+            Path::new(DEFAULT_CONFEXT_DIRECTORY),
         );
         assert_eq!(
             result.unwrap_err().to_string(),
@@ -223,6 +347,7 @@ mod tests {
             current_path,
             &extension,
             &ExtensionType::Sysext,
+            Path::new(DEFAULT_SYSEXT_DIRECTORY),
         );
         assert_eq!(
             result.unwrap_err().to_string(),
@@ -239,6 +364,7 @@ mod tests {
             current_path,
             &extension,
             &ExtensionType::Sysext,
+            Path::new(DEFAULT_SYSEXT_DIRECTORY),
         );
         assert_eq!(
             result.unwrap_err().to_string(),
@@ -270,6 +396,7 @@ mod tests {
             current_path,
             &extension,
             &ExtensionType::Sysext,
+            Path::new(DEFAULT_SYSEXT_DIRECTORY),
         );
         assert_eq!(
             result.unwrap_err().to_string(),
@@ -300,6 +427,7 @@ mod tests {
             current_path,
             &extension,
             &ExtensionType::Sysext,
+            Path::new(DEFAULT_SYSEXT_DIRECTORY),
         )
         .unwrap_err();
         assert_eq!(
@@ -307,4 +435,95 @@ mod tests {
             "Extension-release filename must begin with 'extension-release.'"
         );
     }
+
+    #[test]
+    fn test_check_compatibility() {
+        let target_os = OsRelease {
+            id: Some("azurelinux".into()),
+            version_id: Some("3.0".into()),
+            sysext_level: Some("1".into()),
+            ..Default::default()
+        };
+        let release = |data: &str| {
+            let tempdir = TempDir::new().unwrap();
+            let file = tempdir.path().join("extension-release.test");
+            fs::write(&file, data).unwrap();
+            ExtensionRelease::read_file(file).unwrap()
+        };
+        let check = |data: &str, ext_type: ExtensionType| {
+            check_compatibility(
+                &release(data),
+                &ext_type,
+                ExtensionScope::System,
+                SystemArchitecture::Amd64,
+                &target_os,
+            )
+        };
+
+        // Compatible extension images
+        check("ID=_any\nARCHITECTURE=_any", ExtensionType::Sysext).unwrap();
+        check("ID=_any\nARCHITECTURE=x86-64", ExtensionType::Sysext).unwrap();
+        check("ID=azurelinux\nSYSEXT_LEVEL=1", ExtensionType::Sysext).unwrap();
+        check("ID=azurelinux\nVERSION_ID=3.0", ExtensionType::Sysext).unwrap();
+        check("ID=azurelinux\nVERSION_ID=3.0", ExtensionType::Confext).unwrap();
+
+        // Incompatible extension images
+        assert_eq!(
+            check("ID=_any\nARCHITECTURE=arm64", ExtensionType::Sysext).unwrap_err(),
+            "ARCHITECTURE is 'arm64', but host is 'x86-64'"
+        );
+        assert_eq!(
+            check("SYSEXT_ID=test", ExtensionType::Sysext).unwrap_err(),
+            "ID is not set in extension-release"
+        );
+        assert_eq!(
+            check("ID=fedora\nVERSION_ID=3.0", ExtensionType::Sysext).unwrap_err(),
+            "ID is 'fedora', but target OS is 'azurelinux'"
+        );
+        assert_eq!(
+            check("ID=azurelinux\nSYSEXT_LEVEL=2", ExtensionType::Sysext).unwrap_err(),
+            "SYSEXT_LEVEL is '2', but target OS is '1'"
+        );
+        assert_eq!(
+            check("ID=azurelinux\nCONFEXT_LEVEL=1", ExtensionType::Confext).unwrap_err(),
+            "CONFEXT_LEVEL is '1', but target OS is ''"
+        );
+        assert_eq!(
+            check("ID=azurelinux\nVERSION_ID=2.0", ExtensionType::Sysext).unwrap_err(),
+            "VERSION_ID is '2.0', but target OS is '3.0'"
+        );
+        assert_eq!(
+            check("ID=azurelinux", ExtensionType::Sysext).unwrap_err(),
+            "Neither SYSEXT_LEVEL nor VERSION_ID is set in extension-release"
+        );
+
+        // Scopes
+        check("ID=_any\nSYSEXT_SCOPE=initrd system", ExtensionType::Sysext).unwrap();
+        assert_eq!(
+            check(
+                "ID=_any\nSYSEXT_SCOPE=initrd portable",
+                ExtensionType::Sysext
+            )
+            .unwrap_err(),
+            "SYSEXT_SCOPE is 'initrd portable', but the image is merged into 'system'"
+        );
+        assert_eq!(
+            check("ID=_any\nCONFEXT_SCOPE=portable", ExtensionType::Confext).unwrap_err(),
+            "CONFEXT_SCOPE is 'portable', but the image is merged into 'system'"
+        );
+        let check_initrd = |data: &str| {
+            check_compatibility(
+                &release(data),
+                &ExtensionType::Sysext,
+                ExtensionScope::Initrd,
+                SystemArchitecture::Amd64,
+                &target_os,
+            )
+        };
+        check_initrd("ID=_any\nSYSEXT_SCOPE=initrd").unwrap();
+        assert_eq!(
+            check_initrd("ID=_any").unwrap_err(),
+            "SYSEXT_SCOPE is 'system portable', but the image is merged into 'initrd'"
+        );
+    }
 }
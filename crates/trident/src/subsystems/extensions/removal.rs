@@ -0,0 +1,199 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error};
+use log::{debug, warn};
+
+use osutils::{osrelease::ExtensionRelease, path};
+use trident_api::constants::DEFAULT_SYSEXT_DIRECTORY;
+
+use super::{EXTENSION_RELEASE, SYSEXT_EXTENSION_RELEASE_DIRECTORY};
+
+/// Directories searched for the sysext images to remove, along with the sysext install path.
+/// Images in other directories are shipped with the OS or merged into the initrd, so they are
+/// never removed.
+const SEARCHED_DIRECTORIES: [&str; 3] = [
+    "/etc/extensions",
+    "/run/extensions",
+    DEFAULT_SYSEXT_DIRECTORY,
+];
+
+/// Finds the `.raw` sysext images of the OS rooted at `root` that match an entry of `remove`,
+/// either by name or by the SYSEXT_ID of their extension-release file in the merged /usr
+/// hierarchy. `sysext_directory`, the directory Trident installs sysext images to, is searched
+/// along with the usual sysext directories. Returns the paths of the images, relative to the OS.
+///
+/// Images in `managed`, the paths of the images of the Host Configuration, are never returned.
+pub(super) fn find_removed(
+    root: &Path,
+    sysext_directory: &Path,
+    managed: &HashSet<PathBuf>,
+    remove: &[String],
+) -> Result<Vec<PathBuf>, Error> {
+    let mut directories = SEARCHED_DIRECTORIES.map(Path::new).to_vec();
+    if !directories.contains(&sysext_directory) {
+        directories.push(sysext_directory);
+    }
+
+    let mut matched = HashSet::new();
+    let mut images = Vec::new();
+    for directory in directories {
+        let host_directory = path::join_relative(root, directory);
+        if !host_directory.is_dir() {
+            continue;
+        }
+        for entry in fs::read_dir(&host_directory)
+            .with_context(|| format!("Failed to read directory '{}'", host_directory.display()))?
+        {
+            let entry = entry.with_context(|| {
+                format!("Failed to read directory '{}'", host_directory.display())
+            })?;
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let Some(name) = file_name.strip_suffix(".raw") else {
+                continue;
+            };
+            // Hidden files, e.g. in-flight downloads, are not extension images.
+            if name.starts_with('.') {
+                continue;
+            }
+
+            let entry_matched = remove
+                .iter()
+                .find(|entry| entry.as_str() == name)
+                .or_else(|| {
+                    let id = merged_sysext_id(root, name)?;
+                    remove.iter().find(|entry| **entry == id)
+                });
+            let Some(entry_matched) = entry_matched else {
+                continue;
+            };
+            matched.insert(entry_matched.as_str());
+
+            let image_path = directory.join(&file_name);
+            if managed.contains(&image_path) {
+                warn!(
+                    "Not removing sysext image '{}' matching '{entry_matched}', as it is part of \
+                    the Host Configuration",
+                    image_path.display()
+                );
+                continue;
+            }
+            debug!(
+                "Found sysext image '{}' matching '{entry_matched}'",
+                image_path.display()
+            );
+            images.push(image_path);
+        }
+    }
+
+    for entry in remove {
+        if !matched.contains(entry.as_str()) {
+            debug!("No sysext image matching '{entry}' to remove");
+        }
+    }
+    Ok(images)
+}
+
+/// Returns the SYSEXT_ID of the sysext image named `name`, if it is merged into /usr on the OS
+/// rooted at `root`.
+fn merged_sysext_id(root: &Path, name: &str) -> Option<String> {
+    let release_path = path::join_relative(root, SYSEXT_EXTENSION_RELEASE_DIRECTORY)
+        .join(format!("{EXTENSION_RELEASE}.{name}"));
+    if !release_path.exists() {
+        return None;
+    }
+    ExtensionRelease::read_file(&release_path)
+        .inspect_err(|e| debug!("Failed to read extension-release of '{name}': {e:?}"))
+        .ok()?
+        .sysext_id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_find_removed() {
+        let root = TempDir::new().unwrap();
+        let sysexts = path::join_relative(root.path(), DEFAULT_SYSEXT_DIRECTORY);
+        let etc_sysexts = path::join_relative(root.path(), "/etc/extensions");
+        let releases = path::join_relative(root.path(), SYSEXT_EXTENSION_RELEASE_DIRECTORY);
+        fs::create_dir_all(&sysexts).unwrap();
+        fs::create_dir_all(&etc_sysexts).unwrap();
+        fs::create_dir_all(&releases).unwrap();
+
+        for file in [
+            sysexts.join("docker_28.0.4.raw"),
+            sysexts.join("debug.raw"),
+            sysexts.join("kubernetes.raw"),
+            sysexts.join(".debug.raw"),
+            etc_sysexts.join("debug.raw"),
+        ] {
+            fs::write(file, b"").unwrap();
+        }
+        fs::write(
+            releases.join("extension-release.docker_28.0.4"),
+            "ID=_any\nSYSEXT_ID=docker\n",
+        )
+        .unwrap();
+
+        let managed = HashSet::from([Path::new("/etc/extensions/debug.raw").to_path_buf()]);
+        let sysext_directory = Path::new(DEFAULT_SYSEXT_DIRECTORY);
+        let mut images = find_removed(
+            root.path(),
+            sysext_directory,
+            &managed,
+            &["docker".into(), "debug".into(), "missing".into()],
+        )
+        .unwrap();
+        images.sort();
+        assert_eq!(
+            images,
+            vec![
+                PathBuf::from("/var/lib/extensions/debug.raw"),
+                PathBuf::from("/var/lib/extensions/docker_28.0.4.raw"),
+            ]
+        );
+
+        // Images in a custom install path are found too.
+        let initrd_sysexts = path::join_relative(root.path(), "/.extra/sysext");
+        fs::create_dir_all(&initrd_sysexts).unwrap();
+        fs::write(initrd_sysexts.join("kubernetes.raw"), b"").unwrap();
+        assert_eq!(
+            find_removed(
+                root.path(),
+                sysext_directory,
+                &managed,
+                &["kubernetes".into()]
+            )
+            .unwrap(),
+            vec![PathBuf::from("/var/lib/extensions/kubernetes.raw")]
+        );
+        assert_eq!(
+            find_removed(
+                root.path(),
+                Path::new("/.extra/sysext"),
+                &managed,
+                &["kubernetes".into()]
+            )
+            .unwrap(),
+            vec![
+                PathBuf::from("/var/lib/extensions/kubernetes.raw"),
+                PathBuf::from("/.extra/sysext/kubernetes.raw"),
+            ]
+        );
+
+        // Nothing to remove on an OS without extension image directories.
+        let empty = TempDir::new().unwrap();
+        assert!(
+            find_removed(empty.path(), sysext_directory, &managed, &["docker".into()])
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
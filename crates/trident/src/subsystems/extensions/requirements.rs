@@ -0,0 +1,231 @@
+use std::{collections::HashMap, path::Path};
+
+use trident_api::config::HostConfigurationDynamicValidationError;
+
+use super::{ExtensionData, ExtensionType};
+
+/// Progress of the depth-first search through the requirements of an extension image.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Unvisited,
+    Visiting,
+    Done,
+}
+
+/// Orders `extensions` so that the images required by an image come before it, keeping the order
+/// of the Host Configuration otherwise. `requires` returns the IDs of the images of the same kind
+/// required by an image.
+///
+/// Fails if a required image is not in `extensions`, if images require each other, or if the file
+/// name of a required image does not sort before the file name of the image requiring it, as
+/// systemd-sysext overlays the images of a kind in the order of their file names.
+pub(super) fn order<'a>(
+    extensions: Vec<ExtensionData>,
+    requires: impl Fn(&ExtensionData) -> &'a [String],
+) -> Result<Vec<ExtensionData>, HostConfigurationDynamicValidationError> {
+    let indexes = extensions
+        .iter()
+        .enumerate()
+        .map(|(index, ext)| ((&ext.ext_type, ext.id.as_str()), index))
+        .collect::<HashMap<(&ExtensionType, &str), usize>>();
+
+    let mut dependencies = Vec::with_capacity(extensions.len());
+    for ext in &extensions {
+        let mut required_indexes = Vec::new();
+        for required in requires(ext) {
+            let Some(index) = indexes.get(&(&ext.ext_type, required.as_str())) else {
+                return Err(
+                    HostConfigurationDynamicValidationError::ExtensionImageRequirementMissing {
+                        name: ext.name.clone(),
+                        required: required.clone(),
+                    },
+                );
+            };
+            required_indexes.push(*index);
+        }
+        dependencies.push(required_indexes);
+    }
+
+    let mut marks = vec![Mark::Unvisited; extensions.len()];
+    let mut stack = Vec::new();
+    let mut ordered = Vec::with_capacity(extensions.len());
+    for index in 0..extensions.len() {
+        visit(index, &dependencies, &mut marks, &mut stack, &mut ordered).map_err(|cycle| {
+            HostConfigurationDynamicValidationError::ExtensionImageRequirementCycle {
+                cycle: cycle
+                    .iter()
+                    .chain(cycle.first())
+                    .map(|index| extensions[*index].name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" -> "),
+            }
+        })?;
+    }
+
+    for (ext, required_indexes) in extensions.iter().zip(&dependencies) {
+        for required in required_indexes.iter().map(|index| &extensions[*index]) {
+            if file_name(&required.path) >= file_name(&ext.path) {
+                return Err(
+                    HostConfigurationDynamicValidationError::ExtensionImageRequirementOrder {
+                        name: ext.name.clone(),
+                        file_name: file_name(&ext.path),
+                        required: required.name.clone(),
+                        required_file_name: file_name(&required.path),
+                    },
+                );
+            }
+        }
+    }
+
+    let mut extensions = extensions.into_iter().map(Some).collect::<Vec<_>>();
+    Ok(ordered
+        .into_iter()
+        .filter_map(|index| extensions[index].take())
+        .collect())
+}
+
+/// Returns the file name of an extension image, by which systemd-sysext orders the images it
+/// merges.
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Appends `index` to `ordered` after the images it requires. Returns the indexes of the images
+/// forming a cycle, if any.
+fn visit(
+    index: usize,
+    dependencies: &[Vec<usize>],
+    marks: &mut [Mark],
+    stack: &mut Vec<usize>,
+    ordered: &mut Vec<usize>,
+) -> Result<(), Vec<usize>> {
+    match marks[index] {
+        Mark::Done => return Ok(()),
+        Mark::Visiting => {
+            let start = stack.iter().position(|i| *i == index).unwrap_or_default();
+            return Err(stack[start..].to_vec());
+        }
+        Mark::Unvisited => {}
+    }
+
+    marks[index] = Mark::Visiting;
+    stack.push(index);
+    for dependency in &dependencies[index] {
+        visit(*dependency, dependencies, marks, stack, ordered)?;
+    }
+    stack.pop();
+    marks[index] = Mark::Done;
+    ordered.push(index);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use trident_api::primitives::hash::Sha384Hash;
+
+    fn extension(id: &str, file_name: &str, ext_type: ExtensionType) -> ExtensionData {
+        ExtensionData {
+            id: id.into(),
+            name: id.into(),
+            sha384: Sha384Hash::from("a".repeat(96)),
+            path: PathBuf::from(format!("/var/lib/extensions/{file_name}")),
+            temp_path: PathBuf::from(format!("/tmp/{id}.raw")),
+            ext_type,
+            resolved_url: None,
+        }
+    }
+
+    fn ids(extensions: &[ExtensionData]) -> Vec<&str> {
+        extensions.iter().map(|ext| ext.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_order() {
+        let requirements = HashMap::from([
+            ("driver", vec!["firmware".to_string()]),
+            ("tools", vec!["driver".to_string(), "firmware".to_string()]),
+        ]);
+        let requires = |ext: &ExtensionData| {
+            requirements
+                .get(ext.id.as_str())
+                .map(Vec::as_slice)
+                .unwrap_or_default()
+        };
+
+        let extensions = vec![
+            extension("tools", "30-tools.raw", ExtensionType::Sysext),
+            extension("debug", "00-debug.raw", ExtensionType::Sysext),
+            extension("driver", "20-driver.raw", ExtensionType::Sysext),
+            extension("firmware", "10-firmware.raw", ExtensionType::Sysext),
+        ];
+        let ordered = order(extensions, requires).unwrap();
+        assert_eq!(ids(&ordered), vec!["firmware", "driver", "tools", "debug"]);
+
+        // Required images must be of the same kind.
+        let extensions = vec![
+            extension("driver", "20-driver.raw", ExtensionType::Sysext),
+            extension("firmware", "10-firmware.raw", ExtensionType::Confext),
+        ];
+        assert_eq!(
+            order(extensions, requires).unwrap_err(),
+            HostConfigurationDynamicValidationError::ExtensionImageRequirementMissing {
+                name: "driver".into(),
+                required: "firmware".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_order_cycle() {
+        let requirements = HashMap::from([
+            ("a", vec!["b".to_string()]),
+            ("b", vec!["c".to_string()]),
+            ("c", vec!["a".to_string()]),
+        ]);
+        let extensions = vec![
+            extension("a", "a.raw", ExtensionType::Sysext),
+            extension("b", "b.raw", ExtensionType::Sysext),
+            extension("c", "c.raw", ExtensionType::Sysext),
+        ];
+        assert_eq!(
+            order(extensions, |ext| requirements[ext.id.as_str()].as_slice()).unwrap_err(),
+            HostConfigurationDynamicValidationError::ExtensionImageRequirementCycle {
+                cycle: "a -> b -> c -> a".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_order_file_names() {
+        let firmware = vec!["firmware".to_string()];
+        let requires = |ext: &ExtensionData| {
+            if ext.id == "driver" {
+                firmware.as_slice()
+            } else {
+                &[]
+            }
+        };
+
+        // systemd-sysext would overlay the firmware on top of the driver.
+        let extensions = vec![
+            extension("driver", "driver.raw", ExtensionType::Sysext),
+            extension("firmware", "firmware.raw", ExtensionType::Sysext),
+        ];
+        assert_eq!(
+            order(extensions, requires).unwrap_err(),
+            HostConfigurationDynamicValidationError::ExtensionImageRequirementOrder {
+                name: "driver".into(),
+                file_name: "driver.raw".into(),
+                required: "firmware".into(),
+                required_file_name: "firmware.raw".into(),
+            }
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Error};
+use log::{debug, warn};
+
+use trident_api::primitives::hash::Sha384Hash;
+
+use crate::io_utils::hashing_reader::compute_file_hash;
+
+/// Suffix of the file an extension image is copied into before it is renamed to its path. The
+/// file does not end in `.raw`, so systemd never merges a partially copied image.
+const PART_SUFFIX: &str = ".part";
+
+/// Copies the extension image at `from` to `to`, through a `.part` file next to `to` that is
+/// renamed to `to` once the copy is complete and matches `sha384`.
+///
+/// A `.part` file left behind by an interrupted copy is resumed instead of copying the image
+/// from scratch. If the resumed copy does not match `sha384`, the image is copied again in full.
+pub(super) fn copy_resumable(from: &Path, to: &Path, sha384: &Sha384Hash) -> Result<(), Error> {
+    let part = part_path(to)?;
+    let source_len = fs::metadata(from)
+        .with_context(|| format!("Failed to read metadata of '{}'", from.display()))?
+        .len();
+
+    let resumed_len = match fs::metadata(&part) {
+        Ok(metadata) if metadata.len() <= source_len => metadata.len(),
+        _ => 0,
+    };
+    if resumed_len > 0 {
+        debug!(
+            "Resuming copy of extension image '{}' to '{}' at byte {resumed_len}",
+            from.display(),
+            part.display()
+        );
+    }
+
+    let mut verified = copy_from(from, &part, resumed_len).and_then(|()| verify(&part, sha384));
+    if verified.is_err() && resumed_len > 0 {
+        warn!(
+            "Resumed copy of extension image '{}' is corrupt, copying it again in full",
+            from.display()
+        );
+        verified = copy_from(from, &part, 0).and_then(|()| verify(&part, sha384));
+    }
+    if let Err(e) = verified {
+        // A complete copy that does not match the image cannot be resumed, unlike a copy that
+        // stopped halfway through.
+        if copy_complete(from, &part) {
+            let _ = fs::remove_file(&part);
+        }
+        return Err(e);
+    }
+
+    fs::rename(&part, to).with_context(|| {
+        format!(
+            "Failed to rename '{}' to '{}'",
+            part.display(),
+            to.display()
+        )
+    })
+}
+
+/// Returns the number of bytes still to be written to copy the extension image at `from` to `to`,
+/// taking into account the `.part` file of an interrupted copy.
+pub(super) fn remaining_bytes(from: &Path, to: &Path) -> Result<u64, Error> {
+    let source_len = fs::metadata(from)
+        .with_context(|| format!("Failed to read metadata of '{}'", from.display()))?
+        .len();
+    let resumed_len = match fs::metadata(part_path(to)?) {
+        Ok(metadata) if metadata.len() <= source_len => metadata.len(),
+        _ => 0,
+    };
+    Ok(source_len - resumed_len)
+}
+
+/// Returns the path of the `.part` file that `path` is copied into.
+fn part_path(path: &Path) -> Result<PathBuf, Error> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("Failed to get file name of '{}'", path.display()))?;
+    let mut part_name = file_name.to_os_string();
+    part_name.push(PART_SUFFIX);
+    Ok(path.with_file_name(part_name))
+}
+
+/// Copies `from` into `part`, starting at byte `offset` of both files. Bytes of `part` past
+/// `offset` are discarded.
+fn copy_from(from: &Path, part: &Path, offset: u64) -> Result<(), Error> {
+    let mut source =
+        File::open(from).with_context(|| format!("Failed to open '{}'", from.display()))?;
+    let mut target = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(part)
+        .with_context(|| format!("Failed to open '{}'", part.display()))?;
+    target
+        .set_len(offset)
+        .with_context(|| format!("Failed to truncate '{}'", part.display()))?;
+    source
+        .seek(SeekFrom::Start(offset))
+        .and_then(|_| target.seek(SeekFrom::Start(offset)))
+        .and_then(|_| io::copy(&mut source, &mut target))
+        .and_then(|_| target.sync_all())
+        .with_context(|| {
+            format!(
+                "Failed to copy extension image from '{}' to '{}'",
+                from.display(),
+                part.display()
+            )
+        })
+}
+
+/// Checks that the file at `path` matches `sha384`.
+fn verify(path: &Path, sha384: &Sha384Hash) -> Result<(), Error> {
+    let (_, computed_sha384) = compute_file_hash(path)
+        .with_context(|| format!("Failed to compute hash of '{}'", path.display()))?;
+    if *sha384 != computed_sha384 {
+        bail!(
+            "SHA384 mismatch for extension image copied to '{}': expected {sha384}, got \
+            {computed_sha384}",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Returns whether `part` is as long as `from`, i.e. whether the copy ran to completion.
+fn copy_complete(from: &Path, part: &Path) -> bool {
+    match (fs::metadata(from), fs::metadata(part)) {
+        (Ok(source), Ok(target)) => source.len() == target.len(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use sha2::{Digest, Sha384};
+
+    fn sha384(data: &[u8]) -> Sha384Hash {
+        Sha384Hash::from(format!("{:x}", Sha384::digest(data)))
+    }
+
+    #[test]
+    fn test_copy_resumable() {
+        let dir = tempfile::tempdir().unwrap();
+        let image: Vec<u8> = (0..100_000).map(|i| (i % 251) as u8).collect();
+        let from = dir.path().join("staging.raw");
+        let to = dir.path().join("ext.raw");
+        let part = dir.path().join("ext.raw.part");
+        fs::write(&from, &image).unwrap();
+
+        copy_resumable(&from, &to, &sha384(&image)).unwrap();
+        assert_eq!(fs::read(&to).unwrap(), image);
+        assert!(!part.exists());
+
+        // An interrupted copy is resumed.
+        fs::remove_file(&to).unwrap();
+        fs::write(&part, &image[..40_000]).unwrap();
+        copy_resumable(&from, &to, &sha384(&image)).unwrap();
+        assert_eq!(fs::read(&to).unwrap(), image);
+        assert!(!part.exists());
+
+        // A corrupt partial copy is replaced.
+        fs::remove_file(&to).unwrap();
+        fs::write(&part, vec![0; 40_000]).unwrap();
+        copy_resumable(&from, &to, &sha384(&image)).unwrap();
+        assert_eq!(fs::read(&to).unwrap(), image);
+
+        // Only the part of the image that was not copied yet remains to be written.
+        fs::remove_file(&to).unwrap();
+        assert_eq!(remaining_bytes(&from, &to).unwrap(), 100_000);
+        fs::write(&part, &image[..40_000]).unwrap();
+        assert_eq!(remaining_bytes(&from, &to).unwrap(), 60_000);
+        fs::write(&part, vec![0; 200_000]).unwrap();
+        assert_eq!(remaining_bytes(&from, &to).unwrap(), 100_000);
+        copy_resumable(&from, &to, &sha384(&image)).unwrap();
+
+        // A copy that does not match the expected hash is not renamed into place.
+        fs::remove_file(&to).unwrap();
+        copy_resumable(&from, &to, &sha384(b"other")).unwrap_err();
+        assert!(!to.exists());
+        assert!(!part.exists());
+    }
+}
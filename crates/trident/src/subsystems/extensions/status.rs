@@ -0,0 +1,554 @@
+use std::{
+    collections::HashSet,
+    fmt::{Display, Formatter, Result as FmtResult},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use serde::Serialize;
+use url::Url;
+
+use osutils::{
+    osrelease::ExtensionRelease,
+    sysext::{self, ExtensionKind, ListedImage},
+};
+use trident_api::{
+    config::{Extension, ExtensionScope, HostConfiguration},
+    messages::{self, MessageKey},
+    status::{ArtifactKind, HostStatus, MergedExtension},
+};
+
+use crate::{io_utils::hashing_reader::compute_file_hash, table::format_table};
+
+use super::{
+    CONFEXT_EXTENSION_RELEASE_DIRECTORY, EXTENSION_RELEASE, SYSEXT_EXTENSION_RELEASE_DIRECTORY,
+};
+
+/// Whether an extension image is merged into the running OS.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum MergeState {
+    /// The image is merged into the running OS.
+    Merged,
+
+    /// The image is found by systemd, but not merged.
+    Unmerged,
+
+    /// The image is in the Host Configuration, but systemd does not find it.
+    Missing,
+}
+
+impl Display for MergeState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.pad(match self {
+            Self::Merged => "merged",
+            Self::Unmerged => "unmerged",
+            Self::Missing => "missing",
+        })
+    }
+}
+
+/// State of an extension image, combining what systemd reports on the running OS with the
+/// extension images of the Host Configuration Trident last applied.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExtensionStatus {
+    /// Kind of the image, `sysext` or `confext`.
+    pub kind: String,
+
+    /// Name of the image, unknown for images of the Host Configuration that are missing and have
+    /// no explicit path.
+    pub name: Option<String>,
+
+    /// SYSEXT_ID or CONFEXT_ID of the image. Only known for merged images.
+    pub id: Option<String>,
+
+    /// SYSEXT_VERSION_ID or CONFEXT_VERSION_ID of the image. Only known for merged images.
+    pub version: Option<String>,
+
+    /// SYSEXT_SCOPE or CONFEXT_SCOPE of the image. Only known for merged images.
+    pub scope: Option<String>,
+
+    /// Path of the image.
+    pub path: Option<PathBuf>,
+
+    /// Whether the image is merged into the running OS.
+    pub state: MergeState,
+
+    /// URL the image was installed from, when it is part of the Host Configuration.
+    pub url: Option<Url>,
+}
+
+/// Hierarchy of the running OS that extension images are merged into.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HierarchyStatus {
+    /// Kind of the images merged into the hierarchy, `sysext` or `confext`.
+    pub kind: String,
+
+    /// Path of the hierarchy, e.g. `/usr`.
+    pub hierarchy: PathBuf,
+
+    /// Names of the images merged into the hierarchy.
+    pub extensions: Vec<String>,
+}
+
+/// Returns the state of all the extension images found by systemd on the running OS, followed by
+/// the extension images of `host_config` that systemd does not find. Images merged into the
+/// initrd are not visible on the running OS and are left out.
+pub(crate) fn list(host_config: &HostConfiguration) -> Result<Vec<ExtensionStatus>, Error> {
+    let mut statuses = Vec::new();
+    for (kind, configured) in [
+        (ExtensionKind::Sysext, &host_config.os.sysexts),
+        (ExtensionKind::Confext, &host_config.os.confexts),
+    ] {
+        let merged = sysext::status(kind)?
+            .into_iter()
+            .flat_map(|hierarchy| hierarchy.extensions)
+            .collect::<HashSet<_>>();
+
+        let mut remaining = configured
+            .iter()
+            .filter(|ext| ext.scope == ExtensionScope::System)
+            .collect::<Vec<_>>();
+        for image in sysext::list(kind)? {
+            let url = take_configured(&mut remaining, &image).map(|ext| ext.url.clone());
+            let (state, release) = if merged.contains(&image.name) {
+                (MergeState::Merged, read_merged_release(kind, &image.name))
+            } else {
+                (MergeState::Unmerged, None)
+            };
+            let release = release.unwrap_or_default();
+            statuses.push(ExtensionStatus {
+                kind: kind.to_string(),
+                id: match kind {
+                    ExtensionKind::Sysext => release.sysext_id,
+                    ExtensionKind::Confext => release.confext_id,
+                },
+                version: release.version_id,
+                scope: release.scope,
+                name: Some(image.name),
+                path: Some(image.path),
+                state,
+                url,
+            });
+        }
+
+        statuses.extend(remaining.into_iter().map(|ext| {
+            ExtensionStatus {
+                kind: kind.to_string(),
+                name: ext
+                    .path
+                    .as_ref()
+                    .and_then(|path| path.file_stem())
+                    .map(|name| name.to_string_lossy().into_owned()),
+                id: None,
+                version: None,
+                scope: None,
+                path: ext.path.clone(),
+                state: MergeState::Missing,
+                url: Some(ext.url.clone()),
+            }
+        }));
+    }
+
+    Ok(statuses)
+}
+
+/// Returns the hierarchies of the running OS and the extension images merged into them.
+pub(crate) fn hierarchies() -> Result<Vec<HierarchyStatus>, Error> {
+    let mut statuses = Vec::new();
+    for kind in [ExtensionKind::Sysext, ExtensionKind::Confext] {
+        statuses.extend(
+            sysext::status(kind)?
+                .into_iter()
+                .map(|hierarchy| HierarchyStatus {
+                    kind: kind.to_string(),
+                    hierarchy: hierarchy.hierarchy,
+                    extensions: hierarchy.extensions,
+                }),
+        );
+    }
+    Ok(statuses)
+}
+
+/// Returns the extension images of the Host Configuration that are not merged into the running
+/// OS, i.e. whose state drifted from the Host Configuration, e.g. because systemd-sysext or
+/// systemd-confext were run by hand.
+pub(crate) fn drift(statuses: &[ExtensionStatus]) -> Vec<&ExtensionStatus> {
+    statuses
+        .iter()
+        .filter(|status| status.url.is_some() && status.state != MergeState::Merged)
+        .collect()
+}
+
+/// Describes an extension image of the Host Configuration that is not merged.
+pub(crate) fn describe_drift(status: &ExtensionStatus) -> String {
+    let image = match (&status.path, &status.url) {
+        (Some(path), _) => path.display().to_string(),
+        (None, Some(url)) => url.to_string(),
+        (None, None) => "unknown".into(),
+    };
+    match status.state {
+        MergeState::Missing => format!("{} image '{image}' is missing", status.kind),
+        _ => format!("{} image '{image}' is {}", status.kind, status.state),
+    }
+}
+
+/// Records the extension images merged on the running OS in `host_status`. Failures are only
+/// logged, as the images are merged either way.
+pub(crate) fn record(host_status: &mut HostStatus) {
+    match list(&host_status.spec) {
+        Ok(statuses) => {
+            host_status.extensions = to_merged(
+                &statuses,
+                &host_status.spec,
+                &host_status.extensions,
+                Utc::now(),
+            );
+        }
+        Err(e) => warn!("Failed to record merged extension images: {e:?}"),
+    }
+}
+
+/// Returns the merged images of `statuses`, with the digests of the images of `host_config`.
+/// Images that `previous` already recorded as merged keep their merge time, and other images are
+/// merged at `now`.
+fn to_merged(
+    statuses: &[ExtensionStatus],
+    host_config: &HostConfiguration,
+    previous: &[MergedExtension],
+    now: DateTime<Utc>,
+) -> Vec<MergedExtension> {
+    let kinds = [
+        (
+            ExtensionKind::Sysext,
+            ArtifactKind::Sysext,
+            &host_config.os.sysexts,
+        ),
+        (
+            ExtensionKind::Confext,
+            ArtifactKind::Confext,
+            &host_config.os.confexts,
+        ),
+    ];
+    statuses
+        .iter()
+        .filter(|status| status.state == MergeState::Merged)
+        .filter_map(|status| {
+            let (_, kind, configured) = kinds
+                .iter()
+                .find(|(ext_kind, _, _)| status.kind == ext_kind.to_string())?;
+            let digest = status
+                .url
+                .as_ref()
+                .and_then(|url| configured.iter().find(|ext| ext.url == *url))
+                .map(|ext| format!("sha384:{}", ext.sha384));
+            let path = status.path.clone()?;
+            let merged_at = previous
+                .iter()
+                .find(|merged| {
+                    merged.kind == *kind
+                        && merged.path == path
+                        && merged.version == status.version
+                        && merged.digest == digest
+                })
+                .map_or(now, |merged| merged.merged_at);
+            Some(MergedExtension {
+                kind: *kind,
+                name: status.name.clone()?,
+                id: status.id.clone(),
+                version: status.version.clone(),
+                digest,
+                path,
+                merged_at,
+            })
+        })
+        .collect()
+}
+
+/// Removes from `configured` and returns the extension image of the Host Configuration that
+/// `image` was installed from, if any. Images are matched by path when the Host Configuration
+/// sets one, and by hash otherwise.
+fn take_configured<'a>(
+    configured: &mut Vec<&'a Extension>,
+    image: &ListedImage,
+) -> Option<&'a Extension> {
+    if let Some(index) = configured
+        .iter()
+        .position(|ext| ext.path.as_deref() == Some(image.path.as_path()))
+    {
+        return Some(configured.remove(index));
+    }
+
+    if configured.is_empty() || image.image_type != "raw" {
+        return None;
+    }
+    let sha384 = match compute_file_hash(&image.path) {
+        Ok((_, sha384)) => sha384,
+        Err(e) => {
+            debug!(
+                "Failed to hash extension image '{}': {e}",
+                image.path.display()
+            );
+            return None;
+        }
+    };
+    let index = configured.iter().position(|ext| ext.sha384 == sha384)?;
+    Some(configured.remove(index))
+}
+
+/// Reads the extension-release file of a merged extension image from the running OS.
+pub(super) fn read_merged_release(kind: ExtensionKind, name: &str) -> Option<ExtensionRelease> {
+    let directory = match kind {
+        ExtensionKind::Sysext => SYSEXT_EXTENSION_RELEASE_DIRECTORY,
+        ExtensionKind::Confext => CONFEXT_EXTENSION_RELEASE_DIRECTORY,
+    };
+    let path = Path::new(directory).join(format!("{EXTENSION_RELEASE}.{name}"));
+    ExtensionRelease::read_file(&path)
+        .inspect_err(|e| debug!("Failed to read extension-release of '{name}': {e:?}"))
+        .ok()
+}
+
+/// Renders the extension images as a table.
+pub(crate) fn list_to_table(statuses: &[ExtensionStatus]) -> String {
+    let or_dash = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".into());
+    format_table(
+        &[
+            MessageKey::TableKind,
+            MessageKey::TableName,
+            MessageKey::TableId,
+            MessageKey::TableVersion,
+            MessageKey::TableScope,
+            MessageKey::TableState,
+            MessageKey::TablePath,
+        ],
+        statuses
+            .iter()
+            .map(|status| {
+                vec![
+                    status.kind.clone(),
+                    or_dash(&status.name),
+                    or_dash(&status.id),
+                    or_dash(&status.version),
+                    or_dash(&status.scope),
+                    status.state.to_string(),
+                    or_dash(&status.path.as_ref().map(|p| p.display().to_string())),
+                ]
+            })
+            .collect(),
+    )
+}
+
+/// Renders the hierarchies as a table.
+pub(crate) fn hierarchies_to_table(statuses: &[HierarchyStatus]) -> String {
+    format_table(
+        &[
+            MessageKey::TableKind,
+            MessageKey::TableHierarchy,
+            MessageKey::TableExtensions,
+        ],
+        statuses
+            .iter()
+            .map(|status| {
+                vec![
+                    status.kind.clone(),
+                    status.hierarchy.display().to_string(),
+                    if status.extensions.is_empty() {
+                        messages::render(MessageKey::HierarchyNoExtensions, &[])
+                    } else {
+                        status.extensions.join(", ")
+                    },
+                ]
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use tempfile::TempDir;
+    use trident_api::primitives::hash::Sha384Hash;
+
+    #[test]
+    fn test_take_configured() {
+        let dir = TempDir::new().unwrap();
+        let image_path = dir.path().join("docker.raw");
+        fs::write(&image_path, "docker").unwrap();
+        let (_, sha384) = compute_file_hash(&image_path).unwrap();
+
+        let by_path = Extension {
+            path: Some(dir.path().join("tools.raw")),
+            ..Extension::new(
+                Url::parse("https://example.com/tools.raw").unwrap(),
+                Sha384Hash::from("0".repeat(96)),
+            )
+        };
+        let by_hash = Extension::new(
+            Url::parse("https://example.com/docker.raw").unwrap(),
+            Sha384Hash::from(sha384),
+        );
+        let mut configured = vec![&by_path, &by_hash];
+
+        let image = |name: &str, image_type: &str| ListedImage {
+            name: name.into(),
+            image_type: image_type.into(),
+            path: dir.path().join(format!("{name}.raw")),
+        };
+
+        // Directories are never hashed.
+        assert_eq!(
+            take_configured(&mut configured, &image("docker", "directory")),
+            None
+        );
+        assert_eq!(
+            take_configured(&mut configured, &image("docker", "raw")),
+            Some(&by_hash)
+        );
+        assert_eq!(
+            take_configured(&mut configured, &image("tools", "raw")),
+            Some(&by_path)
+        );
+        assert!(configured.is_empty());
+    }
+
+    #[test]
+    fn test_drift() {
+        let status = |state, url: Option<&str>| ExtensionStatus {
+            kind: "sysext".into(),
+            name: Some("docker".into()),
+            id: None,
+            version: None,
+            scope: None,
+            path: Some("/var/lib/extensions/docker.raw".into()),
+            state,
+            url: url.map(|url| Url::parse(url).unwrap()),
+        };
+        let url = Some("https://example.com/docker.raw");
+        let statuses = vec![
+            status(MergeState::Merged, url),
+            status(MergeState::Unmerged, url),
+            // Images that are not part of the Host Configuration never drift.
+            status(MergeState::Unmerged, None),
+            ExtensionStatus {
+                path: None,
+                ..status(MergeState::Missing, url)
+            },
+        ];
+
+        let drifted = drift(&statuses);
+        assert_eq!(
+            drifted
+                .iter()
+                .map(|s| describe_drift(s))
+                .collect::<Vec<_>>(),
+            vec![
+                "sysext image '/var/lib/extensions/docker.raw' is unmerged",
+                "sysext image 'https://example.com/docker.raw' is missing",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_merged() {
+        let mut host_config = HostConfiguration::default();
+        host_config.os.sysexts.push(Extension::new(
+            Url::parse("https://example.com/docker.raw").unwrap(),
+            Sha384Hash::from("a".repeat(96)),
+        ));
+        let status = |name: &str, state, url: Option<&str>| ExtensionStatus {
+            kind: "sysext".into(),
+            name: Some(name.into()),
+            id: Some(name.into()),
+            version: Some("1".into()),
+            scope: None,
+            path: Some(format!("/var/lib/extensions/{name}.raw").into()),
+            state,
+            url: url.map(|url| Url::parse(url).unwrap()),
+        };
+        let statuses = vec![
+            status(
+                "docker",
+                MergeState::Merged,
+                Some("https://example.com/docker.raw"),
+            ),
+            status("debug", MergeState::Merged, None),
+            status("tools", MergeState::Unmerged, None),
+        ];
+
+        let first = DateTime::from_timestamp(1_000, 0).unwrap();
+        let merged = to_merged(&statuses, &host_config, &[], first);
+        assert_eq!(
+            merged,
+            vec![
+                MergedExtension {
+                    kind: ArtifactKind::Sysext,
+                    name: "docker".into(),
+                    id: Some("docker".into()),
+                    version: Some("1".into()),
+                    digest: Some(format!("sha384:{}", "a".repeat(96))),
+                    path: "/var/lib/extensions/docker.raw".into(),
+                    merged_at: first,
+                },
+                MergedExtension {
+                    kind: ArtifactKind::Sysext,
+                    name: "debug".into(),
+                    id: Some("debug".into()),
+                    version: Some("1".into()),
+                    digest: None,
+                    path: "/var/lib/extensions/debug.raw".into(),
+                    merged_at: first,
+                },
+            ]
+        );
+
+        // Images merged earlier keep their merge time, unless they changed.
+        let second = DateTime::from_timestamp(2_000, 0).unwrap();
+        let mut statuses = statuses;
+        statuses[1].version = Some("2".into());
+        let merged = to_merged(&statuses, &host_config, &merged, second);
+        assert_eq!(merged[0].merged_at, first);
+        assert_eq!(merged[1].merged_at, second);
+    }
+
+    #[test]
+    fn test_list_to_table() {
+        let statuses = vec![
+            ExtensionStatus {
+                kind: "sysext".into(),
+                name: Some("docker".into()),
+                id: Some("docker".into()),
+                version: Some("28.0.4".into()),
+                scope: Some("system".into()),
+                path: Some("/var/lib/extensions/docker.raw".into()),
+                state: MergeState::Merged,
+                url: None,
+            },
+            ExtensionStatus {
+                kind: "confext".into(),
+                name: None,
+                id: None,
+                version: None,
+                scope: None,
+                path: None,
+                state: MergeState::Missing,
+                url: Some(Url::parse("https://example.com/conf.raw").unwrap()),
+            },
+        ];
+
+        assert_eq!(
+            list_to_table(&statuses),
+            indoc::indoc! {"
+                KIND     NAME    ID      VERSION  SCOPE   STATE    PATH
+                sysext   docker  docker  28.0.4   system  merged   /var/lib/extensions/docker.raw
+                confext  -       -       -        -       missing  -
+            "}
+        );
+    }
+}
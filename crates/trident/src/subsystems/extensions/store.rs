@@ -0,0 +1,182 @@
+use std::{
+    collections::HashSet,
+    fs,
+    os::unix::fs::symlink,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error};
+use log::{debug, trace, warn};
+
+use trident_api::primitives::hash::Sha384Hash;
+
+use crate::io_utils::hashing_reader::compute_file_hash;
+
+/// Name of the directory, next to extension images, holding the content-addressed store of
+/// extension images. systemd ignores hidden directories when looking for extension images.
+const STORE_DIRECTORY: &str = ".trident-store";
+
+/// Number of hexadecimal digits of the SHA384 hash of an image used in its file name in the
+/// store.
+const HASH_PREFIX_LENGTH: usize = 16;
+
+/// Returns the path of the image named `name` with hash `sha384` in the store of the image placed
+/// at `path`, i.e. `<parent of path>/.trident-store/<name>-<hash prefix>.raw`.
+pub(super) fn stored_path(path: &Path, name: &str, sha384: &Sha384Hash) -> Result<PathBuf, Error> {
+    let parent = path
+        .parent()
+        .with_context(|| format!("Extension image path '{}' has no parent", path.display()))?;
+    Ok(parent.join(STORE_DIRECTORY).join(file_name(name, sha384)))
+}
+
+/// Returns the file name of an image in the store.
+fn file_name(name: &str, sha384: &Sha384Hash) -> String {
+    let hash = sha384.as_str();
+    format!(
+        "{name}-{}.raw",
+        hash.get(..HASH_PREFIX_LENGTH).unwrap_or(hash)
+    )
+}
+
+/// Finds the image with hash `sha384` in the store of `directory`, whatever its name. The full
+/// hash of candidate images is verified, so a corrupted image is never reused.
+pub(super) fn find(directory: &Path, sha384: &Sha384Hash) -> Option<PathBuf> {
+    let suffix = file_name("", sha384);
+    fs::read_dir(directory.join(STORE_DIRECTORY))
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().ends_with(&suffix))
+        })
+        .find(|path| match compute_file_hash(path) {
+            Ok((_, hash)) if *sha384 == hash => true,
+            Ok(_) => {
+                warn!(
+                    "Ignoring stored extension image '{}', its hash does not match",
+                    path.display()
+                );
+                false
+            }
+            Err(e) => {
+                warn!(
+                    "Ignoring stored extension image '{}', failed to compute its hash: {e}",
+                    path.display()
+                );
+                false
+            }
+        })
+}
+
+/// Returns whether `path` is a symlink to the stored image `stored`.
+pub(super) fn is_linked(path: &Path, stored: &Path) -> bool {
+    fs::read_link(path).is_ok_and(|target| Some(target) == relative_target(stored))
+}
+
+/// Creates a symlink at `path` to the stored image `stored`. The target of the symlink is
+/// relative, so that it resolves both on the target OS and from the servicing OS.
+pub(super) fn link(stored: &Path, path: &Path) -> Result<(), Error> {
+    let target = relative_target(stored).with_context(|| {
+        format!(
+            "Stored extension image path '{}' has no file name",
+            stored.display()
+        )
+    })?;
+    trace!(
+        "Linking extension image '{}' to '{}'",
+        path.display(),
+        target.display()
+    );
+    symlink(&target, path).with_context(|| {
+        format!(
+            "Failed to link extension image '{}' to '{}'",
+            path.display(),
+            target.display()
+        )
+    })
+}
+
+fn relative_target(stored: &Path) -> Option<PathBuf> {
+    Some(Path::new(STORE_DIRECTORY).join(stored.file_name()?))
+}
+
+/// Removes the images of the stores of `directories` that are not in `referenced`. Failures are
+/// only logged, as leftover images do not affect the merged images.
+pub(super) fn collect_garbage<'a>(
+    directories: impl IntoIterator<Item = &'a Path>,
+    referenced: &HashSet<PathBuf>,
+) {
+    for directory in directories {
+        let Ok(entries) = fs::read_dir(directory.join(STORE_DIRECTORY)) else {
+            continue;
+        };
+        for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+            if referenced.contains(&path) {
+                continue;
+            }
+            debug!("Removing stored extension image '{}'", path.display());
+            if let Err(e) = fs::remove_file(&path) {
+                warn!(
+                    "Failed to remove stored extension image '{}': {e}",
+                    path.display()
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_stored_path() {
+        let sha384 = Sha384Hash::from("0123456789abcdef".repeat(6));
+        assert_eq!(
+            stored_path(
+                Path::new("/var/lib/extensions/docker.raw"),
+                "docker",
+                &sha384
+            )
+            .unwrap(),
+            Path::new("/var/lib/extensions/.trident-store/docker-0123456789abcdef.raw")
+        );
+        stored_path(Path::new("/"), "docker", &sha384).unwrap_err();
+    }
+
+    #[test]
+    fn test_store() {
+        let dir = TempDir::new().unwrap();
+        let (_, hash) = {
+            let image = dir.path().join("image");
+            fs::write(&image, "image").unwrap();
+            compute_file_hash(&image).unwrap()
+        };
+        let sha384 = Sha384Hash::from(hash);
+        assert_eq!(find(dir.path(), &sha384), None);
+
+        let path = dir.path().join("docker.raw");
+        let stored = stored_path(&path, "docker", &sha384).unwrap();
+        fs::create_dir(stored.parent().unwrap()).unwrap();
+        fs::write(&stored, "image").unwrap();
+        assert_eq!(find(dir.path(), &sha384), Some(stored.clone()));
+
+        assert!(!is_linked(&path, &stored));
+        link(&stored, &path).unwrap();
+        assert!(is_linked(&path, &stored));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "image");
+
+        // Corrupted images are not reused.
+        fs::write(&stored, "corrupted").unwrap();
+        assert_eq!(find(dir.path(), &sha384), None);
+
+        let other = stored_path(&path, "other", &Sha384Hash::from("a".repeat(96))).unwrap();
+        fs::write(&other, "other").unwrap();
+        collect_garbage([dir.path()], &HashSet::from([stored.clone()]));
+        assert!(stored.exists());
+        assert!(!other.exists());
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error};
+use log::{debug, warn};
+
+/// Name of the directory, next to extension images, in which the images replaced or removed
+/// during a runtime update are kept until the new set of images is merged. systemd ignores hidden
+/// directories when looking for extension images.
+const BACKUP_DIRECTORY: &str = ".trident-backup";
+
+/// Change made to the extension images of the running OS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Change {
+    /// An image was moved aside into a backup directory.
+    BackedUp { original: PathBuf, backup: PathBuf },
+
+    /// An image was moved into place from `from`.
+    Moved { from: PathBuf, to: PathBuf },
+
+    /// An image was copied into place.
+    Copied { path: PathBuf },
+}
+
+/// Changes made to the extension images of the running OS during a runtime update, so that the
+/// previous set of images can be restored if merging the new set fails.
+///
+/// Images that are replaced or removed are moved aside instead of being deleted, and are only
+/// deleted once the transaction is committed.
+#[derive(Debug, Default)]
+pub(super) struct ExtensionTransaction {
+    changes: Vec<Change>,
+}
+
+impl ExtensionTransaction {
+    /// Moves the image at `path` aside, into the backup directory next to it.
+    pub fn back_up(&mut self, path: &Path) -> Result<(), Error> {
+        let backup = backup_path(path)?;
+        if let Some(parent) = backup.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+        }
+        debug!(
+            "Backing up extension image '{}' to '{}'",
+            path.display(),
+            backup.display()
+        );
+        fs::rename(path, &backup).with_context(|| {
+            format!(
+                "Failed to back up extension image '{}' to '{}'",
+                path.display(),
+                backup.display()
+            )
+        })?;
+        self.changes.push(Change::BackedUp {
+            original: path.to_path_buf(),
+            backup,
+        });
+        Ok(())
+    }
+
+    /// Records that an image was moved from `from` to `to`.
+    pub fn record_move(&mut self, from: &Path, to: &Path) {
+        self.changes.push(Change::Moved {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        });
+    }
+
+    /// Records that an image was copied to `path`.
+    pub fn record_copy(&mut self, path: &Path) {
+        self.changes.push(Change::Copied {
+            path: path.to_path_buf(),
+        });
+    }
+
+    /// Deletes the backed up images, once the new set of images is merged. Failures are only
+    /// logged, as the new set of images is in place either way.
+    pub fn commit(self) {
+        for change in self.changes {
+            if let Change::BackedUp { backup, .. } = change {
+                if let Err(e) = fs::remove_file(&backup) {
+                    warn!(
+                        "Failed to delete backed up extension image '{}': {e}",
+                        backup.display()
+                    );
+                }
+                // Only succeeds once the backup directory is empty.
+                if let Some(parent) = backup.parent() {
+                    let _ = fs::remove_dir(parent);
+                }
+            }
+        }
+    }
+
+    /// Undoes all changes, in reverse order, restoring the previous set of images.
+    ///
+    /// Images moved out of a directory that no longer exists, i.e. out of the staging directory,
+    /// are deleted instead of being moved back.
+    pub fn roll_back(self) -> Result<(), Error> {
+        for change in self.changes.into_iter().rev() {
+            match change {
+                Change::BackedUp { original, backup } => {
+                    debug!(
+                        "Restoring extension image '{}' from '{}'",
+                        original.display(),
+                        backup.display()
+                    );
+                    fs::rename(&backup, &original).with_context(|| {
+                        format!(
+                            "Failed to restore extension image '{}' from '{}'",
+                            original.display(),
+                            backup.display()
+                        )
+                    })?;
+                    if let Some(parent) = backup.parent() {
+                        let _ = fs::remove_dir(parent);
+                    }
+                }
+                Change::Moved { from, to } if from.parent().is_some_and(Path::exists) => {
+                    debug!(
+                        "Moving extension image '{}' back to '{}'",
+                        to.display(),
+                        from.display()
+                    );
+                    fs::rename(&to, &from).with_context(|| {
+                        format!(
+                            "Failed to move extension image '{}' back to '{}'",
+                            to.display(),
+                            from.display()
+                        )
+                    })?;
+                }
+                Change::Moved { to: path, .. } | Change::Copied { path } => {
+                    debug!("Removing extension image '{}'", path.display());
+                    fs::remove_file(&path).with_context(|| {
+                        format!("Failed to remove extension image '{}'", path.display())
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the path an image is backed up to, in the backup directory next to it.
+fn backup_path(path: &Path) -> Result<PathBuf, Error> {
+    let parent = path
+        .parent()
+        .with_context(|| format!("Extension image path '{}' has no parent", path.display()))?;
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("Extension image path '{}' has no file name", path.display()))?;
+    Ok(parent.join(BACKUP_DIRECTORY).join(file_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_roll_back() {
+        let dir = TempDir::new().unwrap();
+        let old = dir.path().join("old.raw");
+        let kept = dir.path().join("kept.raw");
+        let staging = dir.path().join(".staging");
+        fs::write(&old, "old").unwrap();
+        fs::write(&kept, "kept").unwrap();
+        fs::create_dir(&staging).unwrap();
+        fs::write(staging.join("new.raw"), "new").unwrap();
+
+        let mut transaction = ExtensionTransaction::default();
+        transaction.back_up(&old).unwrap();
+        assert!(!old.exists());
+        assert!(dir.path().join(BACKUP_DIRECTORY).join("old.raw").exists());
+
+        let moved = dir.path().join("moved.raw");
+        fs::rename(&kept, &moved).unwrap();
+        transaction.record_move(&kept, &moved);
+
+        let new = dir.path().join("new.raw");
+        fs::rename(staging.join("new.raw"), &new).unwrap();
+        transaction.record_move(&staging.join("new.raw"), &new);
+        fs::remove_dir(&staging).unwrap();
+
+        transaction.roll_back().unwrap();
+        assert_eq!(fs::read_to_string(&old).unwrap(), "old");
+        assert_eq!(fs::read_to_string(&kept).unwrap(), "kept");
+        assert!(!moved.exists());
+        assert!(!new.exists());
+        assert!(!dir.path().join(BACKUP_DIRECTORY).exists());
+    }
+
+    #[test]
+    fn test_commit() {
+        let dir = TempDir::new().unwrap();
+        let old = dir.path().join("old.raw");
+        fs::write(&old, "old").unwrap();
+
+        let mut transaction = ExtensionTransaction::default();
+        transaction.back_up(&old).unwrap();
+        transaction.record_copy(&dir.path().join("new.raw"));
+        transaction.commit();
+
+        assert!(!old.exists());
+        assert!(!dir.path().join(BACKUP_DIRECTORY).exists());
+    }
+}
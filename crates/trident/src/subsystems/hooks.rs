@@ -4,12 +4,18 @@ use std::{
     fs,
     os::unix::fs::PermissionsExt,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use anyhow::{Context, Error};
 use log::{debug, info, trace};
 
-use osutils::{container, exe::OutputChecker, files, scripts::ScriptRunner};
+use osutils::{
+    container,
+    exe::OutputChecker,
+    files,
+    scripts::{ScriptResult, ScriptRunner},
+};
 use trident_api::{
     config::{
         HostConfigurationDynamicValidationError, HostConfigurationStaticValidationError, Script,
@@ -23,7 +29,10 @@ use trident_api::{
     status::ServicingType,
 };
 
-use crate::engine::{EngineContext, Subsystem};
+use crate::{
+    engine::{EngineContext, Subsystem},
+    io_utils::progress::{Progress, ProgressUnit},
+};
 
 #[derive(Clone, Debug)]
 struct StagedFile {
@@ -135,10 +144,14 @@ impl Subsystem for HooksSubsystem {
 
     #[tracing::instrument(name = "hooks_configuration", skip_all)]
     fn configure(&mut self, ctx: &EngineContext) -> Result<(), TridentError> {
-        if !ctx.spec.os.additional_files.is_empty() {
-            debug!("Adding additional files");
-        }
-        for file in &ctx.spec.os.additional_files {
+        let additional_files = &ctx.spec.os.additional_files;
+        let mut progress = Progress::new(
+            "Adding additional files",
+            ProgressUnit::Items,
+            Some(additional_files.len() as u64),
+        );
+        for file in additional_files {
+            trace!("Adding additional file '{}'", file.destination.display());
             let (content, original_mode) = if let Some(ref content) = file.content {
                 (content.as_bytes().to_vec(), None)
             } else if let Some(ref path) = file.source {
@@ -179,6 +192,10 @@ impl Subsystem for HooksSubsystem {
                     file_name: file.destination.to_string_lossy().to_string(),
                 },
             )?;
+            progress.advance(1);
+        }
+        if !additional_files.is_empty() {
+            progress.finish();
         }
 
         if !ctx.spec.scripts.post_configure.is_empty() {
@@ -240,13 +257,41 @@ impl HooksSubsystem {
         ctx: &EngineContext,
         target_root: &Path,
     ) -> Result<(), Error> {
+        let Some(result) = self.run_script_unchecked(script, ctx, target_root)? else {
+            return Ok(());
+        };
+        result
+            .check()
+            .context("Script exited with an error")
+            .with_context(|| format!("Script '{}' failed", script.name))?;
+        let output = result.output_report();
+
+        info!("Script '{}' executed successfully", script.name);
+        if output.trim().is_empty() {
+            debug!("Script '{}' produced no output", script.name);
+        } else {
+            debug!("Script '{}':\n{}", script.name, output);
+        }
+
+        Ok(())
+    }
+
+    /// Runs a script from the Host Configuration like `run_script`, but returns its exit status
+    /// and output without checking them. Returns `None` if the script does not run for the
+    /// servicing type of `ctx`.
+    pub fn run_script_unchecked(
+        &self,
+        script: &Script,
+        ctx: &EngineContext,
+        target_root: &Path,
+    ) -> Result<Option<ScriptResult>, Error> {
         if !script.should_run(ctx.servicing_type) {
             trace!(
                 "Skipping script '{}' for servicing type '{:?}'",
                 script.name,
                 ctx.servicing_type
             );
-            return Ok(());
+            return Ok(None);
         }
 
         let interpreter: PathBuf = script
@@ -302,6 +347,7 @@ impl HooksSubsystem {
         };
 
         let mut script_runner = ScriptRunner::new_interpreter(interpreter, content);
+        script_runner.timeout = script.timeout_seconds.map(Duration::from_secs);
 
         // Set arguments
         script_runner
@@ -328,19 +374,11 @@ impl HooksSubsystem {
                 .insert(OsStr::new("PHONEHOME_URL"), OsStr::new(phonehome_url));
         }
 
-        let output = script_runner
-            .output_check()
-            .with_context(|| format!("Script '{}' failed", script.name))?
-            .output_report();
-
-        info!("Script '{}' executed successfully", script.name);
-        if output.trim().is_empty() {
-            debug!("Script '{}' produced no output", script.name);
-        } else {
-            debug!("Script '{}':\n{}", script.name, output);
-        }
-
-        Ok(())
+        script_runner
+            .run()
+            .context("Failed to run script")
+            .with_context(|| format!("Script '{}' failed", script.name))
+            .map(Some)
     }
 
     /// This function will be called outside the standard subsystem flow
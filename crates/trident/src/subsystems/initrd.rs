@@ -1,10 +1,22 @@
+use std::path::Path;
+
 use log::{debug, info};
 
 use osutils::mkinitrd;
-use trident_api::{constants::internal_params::DRACUT_DEBUG, error::TridentError};
+use trident_api::{
+    config::{ExtensionScope, HostConfiguration, HostConfigurationDynamicValidationError},
+    constants::{internal_params::DRACUT_DEBUG, INITRD_SYSEXT_DIRECTORY},
+    error::{InvalidInputError, TridentError},
+};
 
 use crate::engine::{EngineContext, Subsystem};
 
+/// Dracut modules needed to unlock encrypted volumes with the TPM 2.0 device during boot.
+const ENCRYPTION_DRACUT_MODULES: [&str; 2] = ["crypt", "tpm2-tss"];
+
+/// Dracut module activating LVM logical volumes during boot.
+const LVM_DRACUT_MODULE: &str = "lvm";
+
 #[derive(Default)]
 pub struct InitrdSubsystem;
 impl Subsystem for InitrdSubsystem {
@@ -16,6 +28,18 @@ impl Subsystem for InitrdSubsystem {
         false
     }
 
+    fn validate_host_config(&self, ctx: &EngineContext) -> Result<(), TridentError> {
+        // Extension images are merged into the initrd by including them when regenerating it,
+        // which does not happen with UKIs.
+        if has_initrd_sysexts(&ctx.spec) && ctx.is_uki()? {
+            return Err(TridentError::new(InvalidInputError::from(
+                HostConfigurationDynamicValidationError::ExtensionImagesInInitrdWithUki,
+            )));
+        }
+
+        Ok(())
+    }
+
     #[tracing::instrument(name = "initrd_regeneration", skip_all)]
     fn configure(&mut self, ctx: &EngineContext) -> Result<(), TridentError> {
         if ctx.is_uki()? {
@@ -27,10 +51,83 @@ impl Subsystem for InitrdSubsystem {
         // behavior and speedier subsequent boots, we will regenerate the host-specific initrd
         // here.
 
-        // At the moment, this is needed for RAID, encryption, adding a root
+        // At the moment, this is needed for RAID, encryption, LVM, adding a root
         // password into initrd and to update the hardcoded UUID of the ESP.
 
+        // The initrd also carries the extension images merged into it, which systemd-sysext
+        // finds in the initrd's sysext directory.
+        let mut includes = Vec::new();
+        if has_initrd_sysexts(&ctx.spec) {
+            // mkinitrd cannot include extension images. Whether the target OS uses it is only
+            // known here, in the chroot of the target OS.
+            if mkinitrd::uses_mkinitrd() {
+                return Err(TridentError::new(InvalidInputError::from(
+                    HostConfigurationDynamicValidationError::ExtensionImagesInInitrdWithMkinitrd,
+                )));
+            }
+            includes.push(Path::new(INITRD_SYSEXT_DIRECTORY));
+        }
+
         info!("Regenerating initrd");
-        mkinitrd::execute(ctx.spec.internal_params.get_flag(DRACUT_DEBUG))
+        mkinitrd::execute(
+            ctx.spec.internal_params.get_flag(DRACUT_DEBUG),
+            &includes,
+            &dracut_modules(&ctx.spec),
+        )
+    }
+}
+
+/// Returns the dracut modules to add to the initrd of `host_config`. With mkinitrd, which cannot
+/// add them, the modules must be part of the mkinitrd configuration of the image.
+fn dracut_modules(host_config: &HostConfiguration) -> Vec<&'static str> {
+    // The crypttab generated for encrypted volumes is only honored in the initrd if the modules
+    // unlocking them with the TPM 2.0 device are present, and logical volumes are only activated
+    // with the LVM module.
+    let mut modules = Vec::new();
+    if host_config.storage.encryption.is_some() {
+        modules.extend(ENCRYPTION_DRACUT_MODULES);
+    }
+    if !host_config.storage.lvm.volume_groups.is_empty() {
+        modules.push(LVM_DRACUT_MODULE);
+    }
+    modules
+}
+
+/// Returns whether any extension image of `host_config` is merged into the initrd.
+fn has_initrd_sysexts(host_config: &HostConfiguration) -> bool {
+    host_config
+        .os
+        .sysexts
+        .iter()
+        .any(|ext| ext.scope == ExtensionScope::Initrd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use trident_api::config::{Encryption, VolumeGroup};
+
+    #[test]
+    fn test_dracut_modules() {
+        let mut host_config = HostConfiguration::default();
+        assert!(dracut_modules(&host_config).is_empty());
+
+        host_config.storage.encryption = Some(Encryption::default());
+        assert_eq!(dracut_modules(&host_config), vec!["crypt", "tpm2-tss"]);
+
+        host_config.storage.lvm.volume_groups.push(VolumeGroup {
+            id: "vg".into(),
+            name: "data".into(),
+            devices: vec!["part".into()],
+            thin_pools: vec![],
+        });
+        assert_eq!(
+            dracut_modules(&host_config),
+            vec!["crypt", "tpm2-tss", "lvm"]
+        );
+
+        host_config.storage.encryption = None;
+        assert_eq!(dracut_modules(&host_config), vec!["lvm"]);
     }
 }
@@ -10,9 +10,7 @@ use log::info;
 use osutils::path;
 use trident_api::{
     config::HostConfigurationDynamicValidationError,
-    constants::{
-        internal_params::SELF_UPGRADE_TRIDENT, AGENT_CONFIG_PATH, TRIDENT_DATASTORE_PATH_DEFAULT,
-    },
+    constants::{internal_params::SELF_UPGRADE_TRIDENT, TRIDENT_DATASTORE_PATH_DEFAULT},
     error::{InvalidInputError, ReportError, ServicingError, TridentError, TridentResultExt},
     status::ServicingType,
 };
@@ -72,7 +70,7 @@ impl Subsystem for ManagementSubsystem {
     #[tracing::instrument(name = "management_configure", skip_all)]
     fn configure(&mut self, ctx: &EngineContext) -> Result<(), TridentError> {
         configure_agent_config(
-            AGENT_CONFIG_PATH,
+            &ctx.paths.agent_config_path,
             &ctx.spec.trident.datastore_path,
             ctx.storage_graph.root_fs_is_verity(),
         )
@@ -80,12 +78,12 @@ impl Subsystem for ManagementSubsystem {
 }
 
 fn configure_agent_config(
-    agent_config_path: &str,
+    agent_config_path: &Path,
     datastore_path: &Path,
     is_root_verity: bool,
 ) -> Result<(), TridentError> {
     // Ensure that Trident agent config exists with correct datastore path
-    if Path::new(agent_config_path).exists() {
+    if agent_config_path.exists() {
         // If the agent config exists, check that the datastore matches the expected path.
         if let Ok(contents) = std::fs::read_to_string(agent_config_path) {
             let mut datastore_path_configured = TRIDENT_DATASTORE_PATH_DEFAULT;
@@ -122,7 +120,7 @@ fn configure_agent_config(
         let datastore_configuration = format!("DatastorePath={}", datastore_path.display());
         fs::write(agent_config_path, datastore_configuration).structured(
             ServicingError::CreateConfigurationFile {
-                path: agent_config_path.into(),
+                path: agent_config_path.display().to_string(),
             },
         )?;
     }
@@ -139,6 +137,8 @@ mod tests {
         error::ErrorKind,
     };
 
+    use crate::engine::EnginePaths;
+
     #[test]
     fn test_validate_host_config() {
         let mgmt_mod = ManagementSubsystem;
@@ -187,7 +187,7 @@ mod tests {
             let agent_config_folder = tempfile::tempdir().unwrap();
             let agent_config_path = agent_config_folder.path().join("trident.conf");
             configure_agent_config(
-                &agent_config_path.to_string_lossy(),
+                &agent_config_path,
                 Path::new(TRIDENT_DATASTORE_PATH_DEFAULT),
                 false,
             )
@@ -202,7 +202,7 @@ mod tests {
             let agent_config_folder = tempfile::tempdir().unwrap();
             let agent_config_path = agent_config_folder.path().join("trident.conf");
             configure_agent_config(
-                &agent_config_path.to_string_lossy(),
+                &agent_config_path,
                 Path::new(nonstandard_datastore_path),
                 false,
             )
@@ -219,7 +219,7 @@ mod tests {
             let agent_config_folder = tempfile::tempdir().unwrap();
             let agent_config_path = agent_config_folder.path().join("trident.conf");
             configure_agent_config(
-                &agent_config_path.to_string_lossy(),
+                &agent_config_path,
                 Path::new(nonstandard_datastore_path),
                 true,
             )
@@ -237,7 +237,7 @@ mod tests {
             .unwrap();
 
             configure_agent_config(
-                &agent_config_path.to_string_lossy(),
+                &agent_config_path,
                 Path::new(nonstandard_datastore_path),
                 false,
             )
@@ -256,11 +256,30 @@ mod tests {
             .unwrap();
 
             configure_agent_config(
-                &agent_config_path.to_string_lossy(),
+                &agent_config_path,
                 Path::new(nonstandard_datastore_path),
                 false,
             )
             .unwrap_err();
         }
     }
+
+    #[test]
+    fn test_configure_redirected_agent_config() {
+        let agent_config_folder = tempfile::tempdir().unwrap();
+        let mut ctx = EngineContext {
+            paths: EnginePaths {
+                agent_config_path: agent_config_folder.path().join("trident.conf"),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        ctx.spec.trident.datastore_path = "/var/lib/trident/nonstandard-datastore.sqlite".into();
+
+        ManagementSubsystem.configure(&ctx).unwrap();
+        assert_eq!(
+            fs::read_to_string(&ctx.paths.agent_config_path).unwrap(),
+            "DatastorePath=/var/lib/trident/nonstandard-datastore.sqlite"
+        );
+    }
 }
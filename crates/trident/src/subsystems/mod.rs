@@ -1,3 +1,5 @@
+pub(crate) mod cloud_init;
+pub(crate) mod container_images;
 pub(crate) mod esp;
 pub(crate) mod extensions;
 pub(crate) mod hooks;
@@ -5,5 +7,6 @@ pub(crate) mod initrd;
 pub(crate) mod management;
 pub(crate) mod network;
 pub(crate) mod osconfig;
+pub(crate) mod packages;
 pub(crate) mod selinux;
 pub(crate) mod storage;
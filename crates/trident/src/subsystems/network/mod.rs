@@ -0,0 +1,469 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Error};
+use log::{debug, info, warn};
+
+use osutils::{files, netplan};
+use trident_api::{
+    config::PinnedInterface,
+    error::{ReportError, ServicingError, TridentError},
+};
+
+use crate::engine::{EngineContext, Subsystem};
+
+mod networkd;
+
+const CLOUD_INIT_CONFIG_DIR: &str = "/etc/cloud/cloud.cfg.d";
+const CLOUD_INIT_DISABLE_FILE: &str = "99-use-trident-networking.cfg";
+const CLOUD_INIT_DISABLE_CONTENT: &str = "network: {config: disabled}";
+
+/// Directory in which systemd-udevd and systemd-networkd look for administrator-provided
+/// configuration files.
+const SYSTEMD_NETWORK_CONFIG_DIR: &str = "/etc/systemd/network";
+
+/// Prefix of the `.link` files generated by Trident. The low number ensures they take precedence
+/// over the default `99-default.link` shipped with systemd.
+const PINNED_INTERFACE_LINK_FILE_PREFIX: &str = "10-trident-";
+
+/// Directory of the drop-in configuration files of systemd-resolved.
+const SYSTEMD_RESOLVED_CONFIG_DIR: &str = "/etc/systemd/resolved.conf.d";
+
+/// Directory listing the network interfaces known to the kernel.
+pub(crate) const SYSFS_NET_DIR: &str = "/sys/class/net";
+
+/// Marker file that confirms a hot-applied network configuration change. It lives under /run so
+/// that a confirmation never survives a reboot.
+const NETWORK_CONFIRMATION_FILE: &str = "/run/trident/network-confirmed";
+
+/// Interval at which the confirmation marker is checked while waiting for a confirmation.
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Default, Debug)]
+pub struct NetworkSubsystem;
+impl Subsystem for NetworkSubsystem {
+    fn name(&self) -> &'static str {
+        "network"
+    }
+
+    fn validate_host_config(&self, ctx: &EngineContext) -> Result<(), TridentError> {
+        // Without pinning, a kernel or udev change in an updated image may rename the NICs of a
+        // multi-NIC host, breaking any network configuration that references them by name.
+        if ctx.spec.os.pinned_interfaces.is_empty() {
+            match count_physical_interfaces(SYSFS_NET_DIR) {
+                Ok(count) if count > 1 => warn!(
+                    "Host has {count} physical network interfaces, but no interface names are \
+                    pinned in 'os.pinnedInterfaces'. Interfaces may be renamed by future image \
+                    updates"
+                ),
+                Ok(_) => (),
+                Err(e) => debug!("Failed to count physical network interfaces: {e:?}"),
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "network_configuration", skip_all)]
+    fn configure(&mut self, ctx: &EngineContext) -> Result<(), TridentError> {
+        write_pinned_interface_link_files(
+            SYSTEMD_NETWORK_CONFIG_DIR,
+            &ctx.spec.os.pinned_interfaces,
+        )
+        .structured(ServicingError::WritePinnedInterfaceLinkFiles)?;
+
+        networkd::write(
+            ctx.spec.os.network.as_ref(),
+            SYSTEMD_NETWORK_CONFIG_DIR,
+            SYSTEMD_RESOLVED_CONFIG_DIR,
+        )
+        .structured(ServicingError::WriteNetworkdConfig)?;
+
+        match ctx.spec.os.netplan.as_ref() {
+            Some(config) => {
+                debug!("Configuring network");
+                netplan::write(config).structured(ServicingError::WriteNetplanConfig)?;
+                netplan::generate().structured(ServicingError::GenerateNetplanConfig)?;
+
+                // We need to disable cloud-init's network configuration when
+                // Trident is configuring the network, otherwise cloud-init may
+                // deploy additional configurations that are undesired and may
+                // conflict with or otherwise affect Trident's network setup.
+                disable_cloud_init_networking(CLOUD_INIT_CONFIG_DIR)?;
+            }
+            None if ctx.spec.os.network.is_some() => {
+                // Same as above, for the systemd-networkd configuration.
+                disable_cloud_init_networking(CLOUD_INIT_CONFIG_DIR)?;
+            }
+            None => {
+                debug!("Network config not provided");
+            }
+        }
+        Ok(())
+    }
+}
+
+fn disable_cloud_init_networking(config_dir: impl AsRef<Path>) -> Result<(), TridentError> {
+    if !config_dir.as_ref().exists() {
+        debug!(
+            "Cloud-init config dir {} does not exist, skipping disabling cloud-init networking",
+            config_dir.as_ref().display()
+        );
+        return Ok(());
+    }
+
+    debug!("Disabling cloud-init networking");
+    let cloud_init_disable_path = config_dir.as_ref().join(CLOUD_INIT_DISABLE_FILE);
+    fs::write(&cloud_init_disable_path, CLOUD_INIT_DISABLE_CONTENT)
+        .with_context(|| {
+            format!(
+                "Failed to write to cloud-init disable file at {}",
+                cloud_init_disable_path.display()
+            )
+        })
+        .structured(ServicingError::DisableCloudInitNetworking)
+}
+
+/// Returns the number of network interfaces backed by a physical device.
+fn count_physical_interfaces(sysfs_net_dir: impl AsRef<Path>) -> Result<usize, Error> {
+    let entries = fs::read_dir(sysfs_net_dir.as_ref()).with_context(|| {
+        format!(
+            "Failed to read directory '{}'",
+            sysfs_net_dir.as_ref().display()
+        )
+    })?;
+
+    let mut count = 0;
+    for entry in entries {
+        let entry = entry.context("Failed to read network interface entry")?;
+        // Virtual interfaces (loopback, bridges, bonds, ...) have no backing device.
+        if entry.path().join("device").exists() {
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+/// Renders the contents of the `.link` file that pins the given interface's name.
+fn render_link_file(interface: &PinnedInterface) -> String {
+    format!(
+        "# Generated by Trident. Do not edit.\n\
+        [Match]\n\
+        MACAddress={}\n\
+        \n\
+        [Link]\n\
+        Name={}\n",
+        interface.normalized_mac_address(),
+        interface.name
+    )
+}
+
+/// Writes one `.link` file per pinned interface into `config_dir`, and removes any previously
+/// generated `.link` files for interfaces that are no longer pinned.
+fn write_pinned_interface_link_files(
+    config_dir: impl AsRef<Path>,
+    pinned_interfaces: &[PinnedInterface],
+) -> Result<(), Error> {
+    let config_dir = config_dir.as_ref();
+
+    if config_dir.exists() {
+        for entry in fs::read_dir(config_dir)
+            .with_context(|| format!("Failed to read directory '{}'", config_dir.display()))?
+        {
+            let path = entry.context("Failed to read directory entry")?.path();
+            let is_generated = path.extension().is_some_and(|ext| ext == "link")
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(PINNED_INTERFACE_LINK_FILE_PREFIX));
+            if is_generated {
+                debug!("Removing stale link file '{}'", path.display());
+                fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove '{}'", path.display()))?;
+            }
+        }
+    }
+
+    if pinned_interfaces.is_empty() {
+        return Ok(());
+    }
+
+    files::create_dirs(config_dir)?;
+    for interface in pinned_interfaces {
+        let path = config_dir.join(format!(
+            "{PINNED_INTERFACE_LINK_FILE_PREFIX}{}.link",
+            interface.name
+        ));
+        debug!(
+            "Pinning network interface '{}' to MAC address '{}' in '{}'",
+            interface.name,
+            interface.mac_address,
+            path.display()
+        );
+        fs::write(&path, render_link_file(interface))
+            .with_context(|| format!("Failed to write link file '{}'", path.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Contents of a netplan config file before a network configuration change, so that the change
+/// can be reverted.
+pub(crate) struct NetplanBackup {
+    path: PathBuf,
+    contents: Option<Vec<u8>>,
+}
+
+impl NetplanBackup {
+    /// Saves the current contents of the netplan config file at `path`, which may not exist yet.
+    pub(crate) fn save(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let contents = match fs::read(path) {
+            Ok(contents) => Some(contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read netplan config '{}'", path.display()))
+            }
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            contents,
+        })
+    }
+
+    /// Puts back the saved contents of the netplan config file, removing the file if it did not
+    /// exist when the backup was saved.
+    fn restore(&self) -> Result<(), Error> {
+        match &self.contents {
+            Some(contents) => fs::write(&self.path, contents).with_context(|| {
+                format!("Failed to restore netplan config '{}'", self.path.display())
+            }),
+            None if self.path.exists() => fs::remove_file(&self.path).with_context(|| {
+                format!("Failed to remove netplan config '{}'", self.path.display())
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Applies the netplan configuration written by the network subsystem to the running OS.
+///
+/// When `confirm_timeout` is set, the change must be confirmed with [`confirm_change`] within the
+/// timeout. Otherwise, the configuration saved in `backup` is restored and applied again, so that
+/// a bad network configuration cannot lock operators out of a remote host.
+pub(crate) fn hot_apply(
+    backup: &NetplanBackup,
+    confirm_timeout: Option<Duration>,
+) -> Result<(), TridentError> {
+    let confirmation_file = Path::new(NETWORK_CONFIRMATION_FILE);
+    // A confirmation left over from a previous change must not confirm this one.
+    clear_confirmation(confirmation_file).structured(ServicingError::ApplyNetplanConfig)?;
+
+    info!("Applying network configuration");
+    if let Err(e) = netplan::apply() {
+        revert(backup)?;
+        return Err(e).structured(ServicingError::ApplyNetplanConfig);
+    }
+
+    let Some(timeout) = confirm_timeout else {
+        return Ok(());
+    };
+
+    info!(
+        "Waiting up to {} seconds for the network configuration change to be confirmed",
+        timeout.as_secs()
+    );
+    if wait_for_confirmation(confirmation_file, timeout, CONFIRMATION_POLL_INTERVAL) {
+        info!("Network configuration change confirmed");
+        return Ok(());
+    }
+
+    warn!("Network configuration change was not confirmed, reverting");
+    revert(backup)?;
+    Err(TridentError::new(
+        ServicingError::NetworkChangeNotConfirmed {
+            timeout_seconds: timeout.as_secs(),
+        },
+    ))
+}
+
+/// Confirms the network configuration change that is currently waiting for a confirmation.
+pub(crate) fn confirm_change() -> Result<(), TridentError> {
+    let confirmation_file = Path::new(NETWORK_CONFIRMATION_FILE);
+    if let Some(parent) = confirmation_file.parent() {
+        files::create_dirs(parent).structured(ServicingError::ConfirmNetworkChange)?;
+    }
+    fs::write(confirmation_file, "").structured(ServicingError::ConfirmNetworkChange)
+}
+
+/// Restores and applies the network configuration saved in `backup`.
+fn revert(backup: &NetplanBackup) -> Result<(), TridentError> {
+    backup
+        .restore()
+        .structured(ServicingError::RevertNetworkConfig)?;
+    netplan::generate().structured(ServicingError::RevertNetworkConfig)?;
+    netplan::apply().structured(ServicingError::RevertNetworkConfig)
+}
+
+/// Removes the confirmation marker, if present.
+fn clear_confirmation(confirmation_file: &Path) -> Result<(), Error> {
+    match fs::remove_file(confirmation_file) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e).with_context(|| {
+            format!(
+                "Failed to remove network confirmation marker '{}'",
+                confirmation_file.display()
+            )
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Waits until the confirmation marker appears, for at most `timeout`. Returns whether the change
+/// was confirmed, consuming the marker if so.
+fn wait_for_confirmation(
+    confirmation_file: &Path,
+    timeout: Duration,
+    poll_interval: Duration,
+) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if confirmation_file.exists() {
+            if let Err(e) = clear_confirmation(confirmation_file) {
+                warn!("{e:?}");
+            }
+            return true;
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return false;
+        }
+        thread::sleep(poll_interval.min(deadline - now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_physical_interfaces() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        for iface in ["lo", "br0", "eth0", "eth1"] {
+            fs::create_dir(temp_dir.path().join(iface)).unwrap();
+        }
+        for iface in ["eth0", "eth1"] {
+            fs::create_dir(temp_dir.path().join(iface).join("device")).unwrap();
+        }
+        assert_eq!(count_physical_interfaces(temp_dir.path()).unwrap(), 2);
+
+        count_physical_interfaces(temp_dir.path().join("non_existent")).unwrap_err();
+    }
+
+    #[test]
+    fn test_write_pinned_interface_link_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_dir = temp_dir.path().join("network");
+
+        // Nothing is created when there are no pinned interfaces
+        write_pinned_interface_link_files(&config_dir, &[]).unwrap();
+        assert!(!config_dir.exists());
+
+        let pinned = vec![
+            PinnedInterface {
+                name: "mgmt0".into(),
+                mac_address: "AA:BB:CC:DD:EE:FF".into(),
+            },
+            PinnedInterface {
+                name: "data0".into(),
+                mac_address: "00:11:22:33:44:55".into(),
+            },
+        ];
+        write_pinned_interface_link_files(&config_dir, &pinned).unwrap();
+        assert_eq!(
+            fs::read_to_string(config_dir.join("10-trident-mgmt0.link")).unwrap(),
+            "# Generated by Trident. Do not edit.\n[Match]\nMACAddress=aa:bb:cc:dd:ee:ff\n\n[Link]\nName=mgmt0\n"
+        );
+        assert!(config_dir.join("10-trident-data0.link").exists());
+
+        // Unpinned interfaces are cleaned up, while other files are left untouched
+        fs::write(config_dir.join("20-custom.link"), "").unwrap();
+        write_pinned_interface_link_files(&config_dir, &pinned[..1]).unwrap();
+        assert!(config_dir.join("10-trident-mgmt0.link").exists());
+        assert!(!config_dir.join("10-trident-data0.link").exists());
+        assert!(config_dir.join("20-custom.link").exists());
+    }
+
+    #[test]
+    fn test_disable_cloud_init_networking() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_dir = temp_dir.path();
+        disable_cloud_init_networking(config_dir).unwrap();
+        let disable_file_path = config_dir.join(CLOUD_INIT_DISABLE_FILE);
+        let content = fs::read_to_string(disable_file_path).unwrap();
+        assert_eq!(content, CLOUD_INIT_DISABLE_CONTENT);
+    }
+
+    #[test]
+    fn test_disable_cloud_init_networking_non_existent_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_dir = temp_dir.path().join("non_existent");
+        // Should not error even if the directory does not exist
+        disable_cloud_init_networking(&config_dir).unwrap();
+        assert!(!config_dir.exists());
+        assert!(!config_dir.join(CLOUD_INIT_DISABLE_FILE).exists());
+        // Check that the temp dir still exists and is empty
+        assert!(temp_dir.path().exists());
+        assert!(temp_dir.path().read_dir().unwrap().next().is_none());
+    }
+
+    #[test]
+    fn test_netplan_backup() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("99-trident.yaml");
+
+        // A file that did not exist is removed on restore
+        let backup = NetplanBackup::save(&path).unwrap();
+        fs::write(&path, "new").unwrap();
+        backup.restore().unwrap();
+        assert!(!path.exists());
+
+        // An existing file gets its previous contents back
+        fs::write(&path, "old").unwrap();
+        let backup = NetplanBackup::save(&path).unwrap();
+        fs::write(&path, "new").unwrap();
+        backup.restore().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+    }
+
+    #[test]
+    fn test_wait_for_confirmation() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let marker = temp_dir.path().join("network-confirmed");
+
+        assert!(!wait_for_confirmation(
+            &marker,
+            Duration::from_millis(30),
+            Duration::from_millis(10)
+        ));
+
+        fs::write(&marker, "").unwrap();
+        assert!(wait_for_confirmation(
+            &marker,
+            Duration::from_millis(30),
+            Duration::from_millis(10)
+        ));
+        // The confirmation is consumed
+        assert!(!marker.exists());
+
+        // Removing a missing marker is not an error
+        clear_confirmation(&marker).unwrap();
+    }
+}
@@ -0,0 +1,376 @@
+use std::{fmt::Write, fs, path::Path};
+
+use anyhow::{Context, Error};
+use log::debug;
+
+use osutils::files;
+use trident_api::config::{Addressing, Dns, Network};
+
+/// Prefix of the `.network` and `.netdev` files generated by Trident. It sorts after the `.link`
+/// files of the pinned interfaces, and before the files shipped by distributions.
+const NETWORKD_FILE_PREFIX: &str = "20-trident-";
+
+/// Extensions of the systemd-networkd configuration files generated by Trident.
+const NETWORKD_FILE_EXTENSIONS: [&str; 2] = ["network", "netdev"];
+
+/// Name of the drop-in configuration file of systemd-resolved generated by Trident.
+const RESOLVED_DROP_IN_FILE: &str = "10-trident.conf";
+
+/// Header of all files generated by Trident.
+const HEADER: &str = "# Generated by Trident. Do not edit.\n";
+
+/// Writes the systemd-networkd configuration files of `network` into `network_dir`, and the DNS
+/// configuration into `resolved_dir`. Files previously generated by Trident that are not part of
+/// the configuration anymore are removed.
+pub(super) fn write(
+    network: Option<&Network>,
+    network_dir: impl AsRef<Path>,
+    resolved_dir: impl AsRef<Path>,
+) -> Result<(), Error> {
+    let network_dir = network_dir.as_ref();
+    let resolved_path = resolved_dir.as_ref().join(RESOLVED_DROP_IN_FILE);
+
+    remove_generated_files(network_dir)?;
+    if resolved_path.exists() {
+        debug!(
+            "Removing stale DNS configuration '{}'",
+            resolved_path.display()
+        );
+        fs::remove_file(&resolved_path)
+            .with_context(|| format!("Failed to remove '{}'", resolved_path.display()))?;
+    }
+
+    let Some(network) = network else {
+        return Ok(());
+    };
+
+    let rendered = render(network);
+    if !rendered.is_empty() {
+        files::create_dirs(network_dir)?;
+    }
+    for (name, contents) in rendered {
+        let path = network_dir.join(name);
+        debug!("Writing network configuration '{}'", path.display());
+        fs::write(&path, contents)
+            .with_context(|| format!("Failed to write '{}'", path.display()))?;
+    }
+
+    if let Some(dns) = &network.dns {
+        files::create_dirs(resolved_dir.as_ref())?;
+        debug!("Writing DNS configuration '{}'", resolved_path.display());
+        fs::write(&resolved_path, render_dns(dns))
+            .with_context(|| format!("Failed to write '{}'", resolved_path.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Removes the systemd-networkd configuration files previously generated by Trident.
+fn remove_generated_files(network_dir: &Path) -> Result<(), Error> {
+    if !network_dir.exists() {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(network_dir)
+        .with_context(|| format!("Failed to read directory '{}'", network_dir.display()))?
+    {
+        let path = entry.context("Failed to read directory entry")?.path();
+        let is_generated = path
+            .extension()
+            .is_some_and(|ext| NETWORKD_FILE_EXTENSIONS.iter().any(|e| ext == *e))
+            && path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(NETWORKD_FILE_PREFIX));
+        if is_generated {
+            debug!("Removing stale network configuration '{}'", path.display());
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove '{}'", path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders the systemd-networkd configuration files of `network`, as pairs of file name and
+/// contents.
+fn render(network: &Network) -> Vec<(String, String)> {
+    let mut files = Vec::new();
+
+    for interface in &network.interfaces {
+        let vlans = vlans_on(network, &interface.name);
+        files.push((
+            format!("{NETWORKD_FILE_PREFIX}{}.network", interface.name),
+            render_network(&interface.name, &interface.addressing, &vlans, None),
+        ));
+    }
+
+    for bond in &network.bonds {
+        files.push((
+            format!("{NETWORKD_FILE_PREFIX}{}.netdev", bond.name),
+            format!(
+                "{HEADER}[NetDev]\nName={}\nKind=bond\n\n[Bond]\nMode={}\n",
+                bond.name,
+                bond.mode.as_networkd_str()
+            ),
+        ));
+        let vlans = vlans_on(network, &bond.name);
+        files.push((
+            format!("{NETWORKD_FILE_PREFIX}{}.network", bond.name),
+            render_network(&bond.name, &bond.addressing, &vlans, None),
+        ));
+        for member in &bond.interfaces {
+            files.push((
+                format!("{NETWORKD_FILE_PREFIX}{member}.network"),
+                render_network(
+                    member,
+                    &Addressing::default(),
+                    &[],
+                    Some(bond.name.as_str()),
+                ),
+            ));
+        }
+    }
+
+    for vlan in &network.vlans {
+        files.push((
+            format!("{NETWORKD_FILE_PREFIX}{}.netdev", vlan.name),
+            format!(
+                "{HEADER}[NetDev]\nName={}\nKind=vlan\n\n[VLAN]\nId={}\n",
+                vlan.name, vlan.id
+            ),
+        ));
+        files.push((
+            format!("{NETWORKD_FILE_PREFIX}{}.network", vlan.name),
+            render_network(&vlan.name, &vlan.addressing, &[], None),
+        ));
+    }
+
+    files
+}
+
+/// Returns the names of the VLANs carried by the interface or bond `link`.
+fn vlans_on<'a>(network: &'a Network, link: &str) -> Vec<&'a str> {
+    network
+        .vlans
+        .iter()
+        .filter(|vlan| vlan.link == link)
+        .map(|vlan| vlan.name.as_str())
+        .collect()
+}
+
+/// Renders the `.network` file of the interface `name`. Members of a bond only get enslaved to
+/// their `bond`.
+fn render_network(
+    name: &str,
+    addressing: &Addressing,
+    vlans: &[&str],
+    bond: Option<&str>,
+) -> String {
+    let mut contents = format!("{HEADER}[Match]\nName={name}\n");
+
+    if let Some(mtu) = addressing.mtu {
+        let _ = write!(contents, "\n[Link]\nMTUBytes={mtu}\n");
+    }
+
+    contents.push_str("\n[Network]\n");
+    let dhcp = match (addressing.dhcp4, addressing.dhcp6) {
+        (true, true) => Some("yes"),
+        (true, false) => Some("ipv4"),
+        (false, true) => Some("ipv6"),
+        (false, false) => None,
+    };
+    if let Some(dhcp) = dhcp {
+        let _ = writeln!(contents, "DHCP={dhcp}");
+    }
+    for address in &addressing.addresses {
+        let _ = writeln!(contents, "Address={address}");
+    }
+    for vlan in vlans {
+        let _ = writeln!(contents, "VLAN={vlan}");
+    }
+    if let Some(bond) = bond {
+        let _ = writeln!(contents, "Bond={bond}");
+    }
+    // Interfaces without addresses, such as bond members or VLAN carriers, must still be brought
+    // up, without waiting for an address.
+    if dhcp.is_none() && addressing.addresses.is_empty() {
+        contents.push_str("LinkLocalAddressing=no\nConfigureWithoutCarrier=yes\n");
+    }
+
+    for route in &addressing.routes {
+        contents.push_str("\n[Route]\n");
+        if !route.is_default() {
+            let _ = writeln!(contents, "Destination={}", route.to);
+        }
+        let _ = writeln!(contents, "Gateway={}", route.via);
+        if let Some(metric) = route.metric {
+            let _ = writeln!(contents, "Metric={metric}");
+        }
+    }
+
+    contents
+}
+
+/// Renders the drop-in configuration file of systemd-resolved for `dns`.
+fn render_dns(dns: &Dns) -> String {
+    let mut contents = format!("{HEADER}[Resolve]\n");
+    if !dns.servers.is_empty() {
+        let _ = writeln!(contents, "DNS={}", dns.servers.join(" "));
+    }
+    if !dns.search_domains.is_empty() {
+        let _ = writeln!(contents, "Domains={}", dns.search_domains.join(" "));
+    }
+    contents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use trident_api::config::{Bond, BondMode, NetworkInterface, Route, Vlan};
+
+    fn sample_network() -> Network {
+        Network {
+            interfaces: vec![NetworkInterface {
+                name: "eth0".into(),
+                addressing: Addressing {
+                    dhcp4: true,
+                    ..Default::default()
+                },
+            }],
+            bonds: vec![Bond {
+                name: "bond0".into(),
+                interfaces: vec!["eth1".into(), "eth2".into()],
+                mode: BondMode::Ieee8023ad,
+                addressing: Addressing {
+                    addresses: vec!["192.168.0.10/24".into()],
+                    routes: vec![Route {
+                        to: "default".into(),
+                        via: "192.168.0.1".into(),
+                        metric: Some(100),
+                    }],
+                    mtu: Some(9000),
+                    ..Default::default()
+                },
+            }],
+            vlans: vec![Vlan {
+                name: "vlan100".into(),
+                id: 100,
+                link: "bond0".into(),
+                addressing: Addressing {
+                    addresses: vec!["10.0.0.10/16".into()],
+                    routes: vec![Route {
+                        to: "10.1.0.0/16".into(),
+                        via: "10.0.0.1".into(),
+                        metric: None,
+                    }],
+                    ..Default::default()
+                },
+            }],
+            dns: Some(Dns {
+                servers: vec!["192.168.0.1".into(), "2001:db8::1".into()],
+                search_domains: vec!["example.com".into()],
+            }),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let files = render(&sample_network());
+        let names = files
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "20-trident-eth0.network",
+                "20-trident-bond0.netdev",
+                "20-trident-bond0.network",
+                "20-trident-eth1.network",
+                "20-trident-eth2.network",
+                "20-trident-vlan100.netdev",
+                "20-trident-vlan100.network",
+            ]
+        );
+
+        assert_eq!(
+            files[0].1,
+            "# Generated by Trident. Do not edit.\n[Match]\nName=eth0\n\n[Network]\nDHCP=ipv4\n"
+        );
+        assert_eq!(
+            files[1].1,
+            "# Generated by Trident. Do not edit.\n[NetDev]\nName=bond0\nKind=bond\n\n[Bond]\nMode=802.3ad\n"
+        );
+        assert_eq!(
+            files[2].1,
+            "# Generated by Trident. Do not edit.\n[Match]\nName=bond0\n\n[Link]\nMTUBytes=9000\n\n\
+            [Network]\nAddress=192.168.0.10/24\nVLAN=vlan100\n\n\
+            [Route]\nGateway=192.168.0.1\nMetric=100\n"
+        );
+        assert_eq!(
+            files[3].1,
+            "# Generated by Trident. Do not edit.\n[Match]\nName=eth1\n\n\
+            [Network]\nBond=bond0\nLinkLocalAddressing=no\nConfigureWithoutCarrier=yes\n"
+        );
+        assert_eq!(
+            files[5].1,
+            "# Generated by Trident. Do not edit.\n[NetDev]\nName=vlan100\nKind=vlan\n\n[VLAN]\nId=100\n"
+        );
+        assert_eq!(
+            files[6].1,
+            "# Generated by Trident. Do not edit.\n[Match]\nName=vlan100\n\n\
+            [Network]\nAddress=10.0.0.10/16\n\n\
+            [Route]\nDestination=10.1.0.0/16\nGateway=10.0.0.1\n"
+        );
+    }
+
+    #[test]
+    fn test_render_dns() {
+        assert_eq!(
+            render_dns(sample_network().dns.as_ref().unwrap()),
+            "# Generated by Trident. Do not edit.\n[Resolve]\nDNS=192.168.0.1 2001:db8::1\nDomains=example.com\n"
+        );
+    }
+
+    #[test]
+    fn test_write() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let network_dir = temp_dir.path().join("network");
+        let resolved_dir = temp_dir.path().join("resolved.conf.d");
+
+        // Nothing is created without a network configuration
+        write(None, &network_dir, &resolved_dir).unwrap();
+        assert!(!network_dir.exists());
+        assert!(!resolved_dir.exists());
+
+        let network = sample_network();
+        write(Some(&network), &network_dir, &resolved_dir).unwrap();
+        assert_eq!(fs::read_dir(&network_dir).unwrap().count(), 7);
+        assert!(resolved_dir.join(RESOLVED_DROP_IN_FILE).exists());
+
+        // Stale files are cleaned up, while other files are left untouched
+        fs::write(network_dir.join("10-trident-eth0.link"), "").unwrap();
+        fs::write(network_dir.join("30-custom.network"), "").unwrap();
+        let network = Network {
+            interfaces: network.interfaces,
+            ..Default::default()
+        };
+        write(Some(&network), &network_dir, &resolved_dir).unwrap();
+        let mut names = fs::read_dir(&network_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "10-trident-eth0.link",
+                "20-trident-eth0.network",
+                "30-custom.network"
+            ]
+        );
+        assert!(!resolved_dir.join(RESOLVED_DROP_IN_FILE).exists());
+    }
+}
@@ -0,0 +1,122 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Error};
+use log::info;
+
+use trident_api::config::Time;
+
+/// Path of the chrony configuration.
+pub(super) const CHRONY_CONFIG_PATH: &str = "/etc/chrony.conf";
+
+/// First line of the chrony configuration written by Trident.
+const CHRONY_CONFIG_HEADER: &str = "# Generated by Trident. Do not edit.";
+
+/// Stepping of the clock when `os.time` does not set it: during the first three updates, when
+/// the clock is off by more than one second.
+const DEFAULT_MAKESTEP: &str = "makestep 1.0 3";
+
+/// Writes the chrony configuration of `time` at `config_path`, replacing the one of the image.
+pub(super) fn configure(time: &Time, config_path: &Path) -> Result<(), Error> {
+    info!("Configuring time synchronization");
+    if let Some(parent) = config_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+    }
+    fs::write(config_path, render_config(time))
+        .with_context(|| format!("Failed to write '{}'", config_path.display()))
+}
+
+/// Renders the chrony configuration of `time`. Servers are preferred, so that the pools are only
+/// selected when no server is reachable.
+fn render_config(time: &Time) -> String {
+    let mut config = format!("{CHRONY_CONFIG_HEADER}\n");
+    for server in &time.servers {
+        config.push_str(&format!("server {server} iburst prefer\n"));
+    }
+    for pool in &time.fallback_pools {
+        config.push_str(&format!("pool {pool} iburst\n"));
+    }
+
+    match &time.step {
+        // chrony takes the threshold in seconds, and a limit of -1 to step at any time.
+        Some(step) => config.push_str(&format!(
+            "makestep {}.{:03} {}\n",
+            step.threshold_milliseconds / 1000,
+            step.threshold_milliseconds % 1000,
+            step.limit.map_or(-1, i64::from)
+        )),
+        None => {
+            config.push_str(DEFAULT_MAKESTEP);
+            config.push('\n');
+        }
+    }
+
+    config.push_str("driftfile /var/lib/chrony/drift\nrtcsync\n");
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use trident_api::config::ClockStep;
+
+    #[test]
+    fn test_render_config() {
+        assert_eq!(
+            render_config(&Time {
+                servers: vec!["time1.example.com".into(), "10.0.0.1".into()],
+                fallback_pools: vec!["pool.ntp.org".into()],
+                step: None,
+            }),
+            "# Generated by Trident. Do not edit.\n\
+            server time1.example.com iburst prefer\n\
+            server 10.0.0.1 iburst prefer\n\
+            pool pool.ntp.org iburst\n\
+            makestep 1.0 3\n\
+            driftfile /var/lib/chrony/drift\n\
+            rtcsync\n"
+        );
+
+        let config = render_config(&Time {
+            servers: vec![],
+            fallback_pools: vec!["pool.ntp.org".into()],
+            step: Some(ClockStep {
+                threshold_milliseconds: 1500,
+                limit: None,
+            }),
+        });
+        assert!(config.contains("\nmakestep 1.500 -1\n"));
+
+        let config = render_config(&Time {
+            servers: vec!["time.example.com".into()],
+            fallback_pools: vec![],
+            step: Some(ClockStep {
+                threshold_milliseconds: 100,
+                limit: Some(5),
+            }),
+        });
+        assert!(config.contains("\nmakestep 0.100 5\n"));
+    }
+
+    #[test]
+    fn test_configure() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("etc/chrony.conf");
+        fs::create_dir_all(config_path.parent().unwrap()).unwrap();
+        fs::write(&config_path, "pool 2.azurelinux.pool.ntp.org iburst\n").unwrap();
+
+        configure(
+            &Time {
+                servers: vec!["time.example.com".into()],
+                ..Default::default()
+            },
+            &config_path,
+        )
+        .unwrap();
+        let contents = fs::read_to_string(&config_path).unwrap();
+        assert!(contents.starts_with(CHRONY_CONFIG_HEADER));
+        assert!(contents.contains("server time.example.com iburst prefer\n"));
+        assert!(!contents.contains("azurelinux"));
+    }
+}
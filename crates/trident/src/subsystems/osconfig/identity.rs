@@ -0,0 +1,102 @@
+use anyhow::{ensure, Context, Error};
+use log::debug;
+
+use osutils::dependencies::Dependency;
+use trident_api::config::Identity;
+
+use crate::template;
+
+/// Maximum length of a hostname, as defined by the kernel's HOST_NAME_MAX.
+const MAX_HOSTNAME_LENGTH: usize = 64;
+
+/// Resolves the variables of the hostname `template` on this host.
+pub(super) fn resolve_hostname(template: &str) -> Result<String, Error> {
+    let hostname = template::render_text(template)
+        .with_context(|| format!("Failed to resolve hostname template '{template}'"))?;
+
+    let hostname = sanitize_hostname(&hostname);
+    ensure!(
+        !hostname.is_empty() && hostname.len() <= MAX_HOSTNAME_LENGTH,
+        "Hostname '{hostname}' resolved from template '{template}' must have between 1 and \
+        {MAX_HOSTNAME_LENGTH} characters"
+    );
+    debug!("Resolved hostname template '{template}' to '{hostname}'");
+    Ok(hostname)
+}
+
+/// Lowercases `hostname` and replaces the characters that are not allowed in hostnames with `-`.
+fn sanitize_hostname(hostname: &str) -> String {
+    hostname
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>()
+        .trim_matches(['-', '.'])
+        .to_string()
+}
+
+/// Applies the timezone, locale and keymap of `identity` to the OS.
+pub(super) fn apply(identity: &Identity) -> Result<(), Error> {
+    let mut cmd = Dependency::SystemdFirstboot.cmd();
+    // Settings of the image are overwritten.
+    cmd.arg("--force");
+    let mut configured = false;
+    for (option, value) in [
+        ("--timezone", &identity.timezone),
+        ("--locale", &identity.locale),
+        ("--keymap", &identity.keymap),
+    ] {
+        if let Some(value) = value {
+            debug!("Setting '{option}' to '{value}'");
+            cmd.arg(option).arg(value);
+            configured = true;
+        }
+    }
+    if !configured {
+        return Ok(());
+    }
+
+    cmd.run_and_check()
+        .context("Failed to set timezone, locale and keymap")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_hostname() {
+        assert_eq!(sanitize_hostname("edge-ABC123"), "edge-abc123");
+        assert_eq!(sanitize_hostname("edge-S/N 42_x"), "edge-s-n-42-x");
+        assert_eq!(sanitize_hostname("-host.lab."), "host.lab");
+        assert_eq!(sanitize_hostname("  "), "");
+    }
+
+    #[test]
+    fn test_resolve_hostname() {
+        assert_eq!(resolve_hostname("Edge_01.lab").unwrap(), "edge-01.lab");
+        assert_eq!(
+            resolve_hostname("edge-${env.TRIDENT_TEST_UNSET_SITE:-Paris}").unwrap(),
+            "edge-paris"
+        );
+        resolve_hostname("edge-${env.TRIDENT_TEST_UNSET_SITE}").unwrap_err();
+        resolve_hostname("--").unwrap_err();
+    }
+
+    #[test]
+    fn test_apply_nothing() {
+        // Nothing is run when no setting is configured.
+        apply(&Identity {
+            hostname: Some("host".into()),
+            ..Default::default()
+        })
+        .unwrap();
+    }
+}
@@ -0,0 +1,249 @@
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fs,
+    os::unix::fs::{self as unix_fs, MetadataExt},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error};
+use glob::Pattern;
+use log::{debug, trace};
+
+use osutils::{path, scripts::ScriptRunner};
+use trident_api::config::Migration;
+
+use crate::io_utils::progress::{Progress, ProgressUnit};
+
+/// File copied from the servicing OS into the updated OS.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct MigratedFile {
+    /// Absolute path of the file, in both OSes.
+    pub path: PathBuf,
+
+    /// Path of the original file on the servicing OS.
+    pub source: PathBuf,
+
+    /// Path of the copy, under the mount point of the updated OS.
+    pub target: PathBuf,
+}
+
+/// Copies the files of the servicing OS selected by the migration rules into the updated OS
+/// mounted at `target_root`, then runs the transforms on them. Returns the copied files, in the
+/// order they were copied.
+pub(super) fn migrate(
+    migration: &Migration,
+    source_root: &Path,
+    target_root: &Path,
+) -> Result<Vec<MigratedFile>, Error> {
+    let migrated_files = select(migration, source_root, target_root)?;
+    let mut progress = Progress::new(
+        "Migrating files",
+        ProgressUnit::Items,
+        Some(migrated_files.len() as u64),
+    );
+    for file in &migrated_files {
+        copy(&file.source, &file.target)
+            .with_context(|| format!("Failed to migrate '{}'", file.path.display()))?;
+        progress.advance(1);
+    }
+    if !migrated_files.is_empty() {
+        progress.finish();
+    }
+
+    for transform in &migration.transforms {
+        let pattern = Pattern::new(&transform.pattern)
+            .with_context(|| format!("Invalid migration pattern '{}'", transform.pattern))?;
+        for file in migrated_files
+            .iter()
+            .filter(|file| pattern.matches_path(&file.path))
+        {
+            debug!(
+                "Transforming migrated file '{}' with '{}'",
+                file.path.display(),
+                transform.command
+            );
+            ScriptRunner::new_bash(transform.command.as_bytes())
+                .with_env_vars(HashMap::from([
+                    (OsStr::new("MIGRATED_FILE"), file.target.as_os_str()),
+                    (OsStr::new("SOURCE_FILE"), file.source.as_os_str()),
+                ]))
+                .run_check()
+                .with_context(|| {
+                    format!(
+                        "Failed to transform migrated file '{}' with '{}'",
+                        file.path.display(),
+                        transform.command
+                    )
+                })?;
+        }
+    }
+
+    Ok(migrated_files)
+}
+
+/// Returns the files under `source_root` matching the include patterns and none of the exclude
+/// patterns, with the path of their copy under `target_root`. Directories are not selected
+/// themselves, but files inside them can be.
+fn select(
+    migration: &Migration,
+    source_root: &Path,
+    target_root: &Path,
+) -> Result<Vec<MigratedFile>, Error> {
+    let exclude = migration
+        .exclude
+        .iter()
+        .map(|pattern| {
+            Pattern::new(pattern).with_context(|| format!("Invalid migration pattern '{pattern}'"))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut files = Vec::new();
+    for include in &migration.include {
+        let pattern = path::join_relative(source_root, include);
+        let pattern = pattern
+            .to_str()
+            .with_context(|| format!("Migration pattern '{include}' is not valid UTF-8"))?;
+        for source in
+            glob::glob(pattern).with_context(|| format!("Invalid migration pattern '{include}'"))?
+        {
+            let source = source.context("Failed to read path matching migration pattern")?;
+            if source.is_dir() {
+                continue;
+            }
+
+            let path = Path::new("/").join(
+                source
+                    .strip_prefix(source_root)
+                    .context("Matched path is outside of the servicing OS root")?,
+            );
+            if exclude.iter().any(|pattern| pattern.matches_path(&path))
+                || files.iter().any(|file: &MigratedFile| file.path == path)
+            {
+                continue;
+            }
+
+            files.push(MigratedFile {
+                target: path::join_relative(target_root, &path),
+                path,
+                source,
+            });
+        }
+    }
+
+    Ok(files)
+}
+
+/// Copies a file or symlink, preserving its permissions and ownership, and creating
+/// the missing parent directories.
+///
+/// Files that are already shared between both OSes, e.g. on a /var volume that is not A/B updated,
+/// are left untouched.
+fn copy(source: &Path, target: &Path) -> Result<(), Error> {
+    let metadata = source
+        .symlink_metadata()
+        .with_context(|| format!("Failed to read metadata of '{}'", source.display()))?;
+    if let Ok(target_metadata) = target.symlink_metadata() {
+        if target_metadata.dev() == metadata.dev() && target_metadata.ino() == metadata.ino() {
+            debug!(
+                "Skipping migration of '{}', which is shared with the updated OS",
+                source.display()
+            );
+            return Ok(());
+        }
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+    }
+
+    trace!("Migrating '{}'", source.display());
+    if metadata.is_symlink() {
+        let link = fs::read_link(source)
+            .with_context(|| format!("Failed to read symlink '{}'", source.display()))?;
+        if target.symlink_metadata().is_ok() {
+            fs::remove_file(target)
+                .with_context(|| format!("Failed to remove '{}'", target.display()))?;
+        }
+        unix_fs::symlink(link, target)
+            .with_context(|| format!("Failed to create symlink '{}'", target.display()))?;
+    } else {
+        fs::copy(source, target).with_context(|| {
+            format!(
+                "Failed to copy '{}' to '{}'",
+                source.display(),
+                target.display()
+            )
+        })?;
+    }
+
+    unix_fs::lchown(target, Some(metadata.uid()), Some(metadata.gid()))
+        .with_context(|| format!("Failed to set ownership of '{}'", target.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let path = path::join_relative(root, path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_select() {
+        let source_root = TempDir::new().unwrap();
+        write(source_root.path(), "/etc/ssh/ssh_host_rsa_key", "rsa");
+        write(
+            source_root.path(),
+            "/etc/ssh/ssh_host_rsa_key.pub",
+            "rsa.pub",
+        );
+        write(source_root.path(), "/var/lib/app/state", "state");
+        write(source_root.path(), "/var/lib/app/cache/blob", "blob");
+
+        let migration = Migration {
+            include: vec![
+                "/etc/ssh/ssh_host_*".into(),
+                "/var/lib/app/**/*".into(),
+                "/etc/ssh/ssh_host_rsa_key".into(),
+            ],
+            exclude: vec!["/etc/ssh/*.pub".into(), "/var/lib/app/cache/*".into()],
+            transforms: vec![],
+        };
+
+        assert_eq!(
+            select(&migration, source_root.path(), Path::new("/mnt/newroot")).unwrap(),
+            vec![
+                MigratedFile {
+                    path: PathBuf::from("/etc/ssh/ssh_host_rsa_key"),
+                    source: source_root.path().join("etc/ssh/ssh_host_rsa_key"),
+                    target: PathBuf::from("/mnt/newroot/etc/ssh/ssh_host_rsa_key"),
+                },
+                MigratedFile {
+                    path: PathBuf::from("/var/lib/app/state"),
+                    source: source_root.path().join("var/lib/app/state"),
+                    target: PathBuf::from("/mnt/newroot/var/lib/app/state"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_nothing_by_default() {
+        let source_root = TempDir::new().unwrap();
+        write(source_root.path(), "/etc/app.conf", "conf");
+
+        assert!(select(
+            &Migration::default(),
+            source_root.path(),
+            Path::new("/mnt/newroot")
+        )
+        .unwrap()
+        .is_empty());
+    }
+}
@@ -1,11 +1,11 @@
-use std::{fs, path::Path};
+use std::{collections::BTreeMap, fs, path::Path};
 
 use anyhow::Context;
 use log::{debug, error, info, warn};
 
 use osutils::{osmodifier::OSModifierConfig, path};
 use trident_api::{
-    config::{ManagementOs, SshMode},
+    config::{KernelCommandLine, ManagementOs, SshMode},
     constants::internal_params::DISABLE_HOSTNAME_CARRY_OVER,
     error::{ExecutionEnvironmentMisconfigurationError, ReportError, ServicingError, TridentError},
     status::ServicingType,
@@ -16,6 +16,9 @@ use crate::{
     OS_MODIFIER_BINARY_PATH, OS_MODIFIER_NEWROOT_PATH,
 };
 
+mod chrony;
+mod identity;
+mod migration;
 mod users;
 
 /// Path to the machine-id file, as expected by SystemD.
@@ -30,14 +33,29 @@ const SYSTEMD_SYSEXT: &str = "systemd-sysext";
 /// SystemD service for merging confexts.
 const SYSTEMD_CONFEXT: &str = "systemd-confext";
 
+/// SystemD service applying the network configuration rendered from `os.network`.
+const SYSTEMD_NETWORKD: &str = "systemd-networkd";
+
+/// SystemD service applying the DNS configuration rendered from `os.network`.
+const SYSTEMD_RESOLVED: &str = "systemd-resolved";
+
+/// Service synchronizing the clock with the NTP sources of `os.time`.
+const CHRONYD: &str = "chronyd";
+
 /// Returns whether the given OS configuration requires the os-modifier binary to be present.
 fn os_config_requires_os_modifier(ctx: &EngineContext) -> bool {
     let os_config = &ctx.spec.os;
     !os_config.users.is_empty()
         || os_config.hostname.is_some()
+        || os_config
+            .identity
+            .as_ref()
+            .is_some_and(|identity| identity.hostname.is_some())
         || !os_config.modules.is_empty()
         || !os_config.services.enable.is_empty()
         || !os_config.services.disable.is_empty()
+        || os_config.network.is_some()
+        || os_config.time.is_some()
         || !os_config.kernel_command_line.extra_command_line.is_empty()
         || should_carry_over_hostname(ctx)
 }
@@ -58,9 +76,16 @@ fn should_carry_over_hostname(ctx: &EngineContext) -> bool {
         && ctx.servicing_type == ServicingType::AbUpdate
 }
 
-#[derive(Default, Debug)]
+// Not `Debug`, so that the resolved passwords cannot end up in the logs.
+#[derive(Default)]
 pub struct OsConfigSubsystem {
     prev_hostname: Option<String>,
+
+    /// Hostname resolved from the hostname template of `os.identity`.
+    templated_hostname: Option<String>,
+
+    /// Passwords of `os.users` resolved from their secrets, by username.
+    passwords: BTreeMap<String, String>,
 }
 impl Subsystem for OsConfigSubsystem {
     fn name(&self) -> &'static str {
@@ -81,6 +106,24 @@ impl Subsystem for OsConfigSubsystem {
         Ok(())
     }
 
+    fn prepare(&mut self, ctx: &EngineContext) -> Result<(), TridentError> {
+        // The variables of the template are resolved on the host, before entering the target OS.
+        self.templated_hostname = ctx
+            .spec
+            .os
+            .identity
+            .as_ref()
+            .and_then(|identity| identity.hostname.as_deref())
+            .map(identity::resolve_hostname)
+            .transpose()
+            .structured(ServicingError::ResolveHostname)?;
+
+        // Referenced passwords are read from the servicing OS, before entering the target OS.
+        self.passwords = users::resolve_passwords(&ctx.spec.os.users)?;
+
+        Ok(())
+    }
+
     #[tracing::instrument(name = "osconfig_provision", skip_all)]
     fn provision(&mut self, ctx: &EngineContext, mount_path: &Path) -> Result<(), TridentError> {
         if ctx.servicing_type == ServicingType::AbUpdate {
@@ -101,6 +144,17 @@ impl Subsystem for OsConfigSubsystem {
                         .to_string(),
                 );
             }
+
+            // Copy the files selected by the migration rules into the updated volume.
+            let migrated_files =
+                migration::migrate(&ctx.spec.os.migration, Path::new("/"), mount_path)
+                    .structured(ServicingError::MigrateFiles)?;
+            if !migrated_files.is_empty() {
+                info!(
+                    "Migrated {} file(s) into the updated OS",
+                    migrated_files.len()
+                );
+            }
         }
 
         Ok(())
@@ -108,8 +162,10 @@ impl Subsystem for OsConfigSubsystem {
 
     #[tracing::instrument(name = "osconfig_configuration", skip_all)]
     fn configure(&mut self, ctx: &EngineContext) -> Result<(), TridentError> {
+        // Scoped updates apply the OS configuration directly to the running OS.
         if ctx.servicing_type != ServicingType::CleanInstall
             && ctx.servicing_type != ServicingType::AbUpdate
+            && !ctx.scoped_update
         {
             debug!(
                 "Skipping step 'Configure' for subsystem '{}' during servicing type '{:?}'",
@@ -119,6 +175,16 @@ impl Subsystem for OsConfigSubsystem {
             return Ok(());
         }
 
+        if let Some(os_identity) = &ctx.spec.os.identity {
+            debug!("Setting up timezone, locale and keymap");
+            identity::apply(os_identity).structured(ServicingError::ConfigureIdentity)?;
+        }
+
+        if let Some(time) = &ctx.spec.os.time {
+            chrony::configure(time, Path::new(chrony::CHRONY_CONFIG_PATH))
+                .structured(ServicingError::ConfigureTimeSynchronization)?;
+        }
+
         if !os_config_requires_os_modifier(ctx) {
             debug!(
                 "Skipping step 'Configure' for subsystem '{}' as OS modifier is not required",
@@ -134,13 +200,16 @@ impl Subsystem for OsConfigSubsystem {
 
         if !ctx.spec.os.users.is_empty() {
             debug!("Setting up users");
-            os_modifier_config.users =
-                users::set_up_users(&ctx.spec.os.users).structured(ServicingError::SetUpUsers)?;
+            os_modifier_config.users = users::set_up_users(&ctx.spec.os.users, &self.passwords)
+                .structured(ServicingError::SetUpUsers)?;
         }
 
         if ctx.spec.os.hostname.is_some() {
             debug!("Setting up hostname");
             os_modifier_config.hostname = ctx.spec.os.hostname.clone();
+        } else if self.templated_hostname.is_some() {
+            debug!("Setting up hostname from template");
+            os_modifier_config.hostname = self.templated_hostname.clone();
         } else if should_carry_over_hostname(ctx) {
             // If no hostname is provided during A/B Update, carry forward the existing machine
             // hostname into the new root
@@ -157,6 +226,8 @@ impl Subsystem for OsConfigSubsystem {
             || !ctx.spec.os.services.disable.is_empty()
             || !ctx.spec.os.sysexts.is_empty()
             || !ctx.spec.os.confexts.is_empty()
+            || ctx.spec.os.network.is_some()
+            || ctx.spec.os.time.is_some()
         {
             debug!("Setting up services");
             let mut services = ctx.spec.os.services.clone();
@@ -171,6 +242,20 @@ impl Subsystem for OsConfigSubsystem {
                 services.enable.push(SYSTEMD_CONFEXT.to_string());
             }
 
+            if let Some(network) = &ctx.spec.os.network {
+                debug!("Enabling {SYSTEMD_NETWORKD} service");
+                services.enable.push(SYSTEMD_NETWORKD.to_string());
+                if network.dns.is_some() {
+                    debug!("Enabling {SYSTEMD_RESOLVED} service");
+                    services.enable.push(SYSTEMD_RESOLVED.to_string());
+                }
+            }
+
+            if ctx.spec.os.time.is_some() {
+                debug!("Enabling {CHRONYD} service");
+                services.enable.push(CHRONYD.to_string());
+            }
+
             os_modifier_config.services = Some(services);
         }
 
@@ -189,7 +274,11 @@ impl Subsystem for OsConfigSubsystem {
                     .extra_command_line
                     .join(", ")
             );
-            os_modifier_config.kernel_command_line = Some(ctx.spec.os.kernel_command_line.clone());
+            // Removed parameters are applied by Trident when it updates the GRUB config.
+            os_modifier_config.kernel_command_line = Some(KernelCommandLine {
+                extra_command_line: ctx.spec.os.kernel_command_line.extra_command_line.clone(),
+                ..Default::default()
+            });
         }
 
         // If we have a UKI image, update SELinux mode here since it cannot be set via kernel
@@ -199,8 +288,14 @@ impl Subsystem for OsConfigSubsystem {
             os_modifier_config.selinux = Some(ctx.spec.os.selinux.clone());
         }
 
+        // Outside of the new root, OS modifier is invoked from its original location.
+        let os_modifier_path = if ctx.scoped_update {
+            OS_MODIFIER_BINARY_PATH
+        } else {
+            OS_MODIFIER_NEWROOT_PATH
+        };
         os_modifier_config
-            .call_os_modifier(Path::new(OS_MODIFIER_NEWROOT_PATH))
+            .call_os_modifier(Path::new(os_modifier_path))
             .structured(ServicingError::RunOsModifier)?;
 
         Ok(())
@@ -256,8 +351,11 @@ impl Subsystem for MosConfigSubsystem {
         if !ctx.spec.management_os.users.is_empty() {
             info!("Setting up users for management OS");
             let os_modifier_config = OSModifierConfig {
-                users: users::set_up_users(&ctx.spec.management_os.users)
-                    .structured(ServicingError::SetUpUsers)?,
+                users: users::set_up_users(
+                    &ctx.spec.management_os.users,
+                    &users::resolve_passwords(&ctx.spec.management_os.users)?,
+                )
+                .structured(ServicingError::SetUpUsers)?,
                 ..Default::default()
             };
             os_modifier_config
@@ -292,8 +390,8 @@ impl Subsystem for MosConfigSubsystem {
 mod tests {
     use trident_api::{
         config::{
-            HostConfiguration, KernelCommandLine, ManagementOs, Module, Os, Password, Selinux,
-            Services, User,
+            HostConfiguration, KernelCommandLine, ManagementOs, Migration, Module, Network, Os,
+            Password, Selinux, Services, SysextPolicy, User,
         },
         status::ServicingType,
     };
@@ -311,16 +409,27 @@ mod tests {
             spec: HostConfiguration {
                 os: Os {
                     netplan: None,
+                    network: None,
+                    pinned_interfaces: vec![],
                     selinux: Selinux::default(),
                     users: vec![],
                     additional_files: vec![],
+                    packages: vec![],
+                    package_repository: None,
+                    container_images: vec![],
+                    cloud_init: None,
                     hostname: None,
+                    identity: None,
+                    time: None,
                     modules: vec![],
                     services: Services::default(),
                     kernel_command_line: KernelCommandLine::default(),
                     sysexts: vec![],
                     confexts: vec![],
+                    sysext_policy: SysextPolicy::default(),
+                    migration: Migration::default(),
                     uefi_fallback: None,
+                    boot_menu: None,
                 },
                 ..Default::default()
             },
@@ -357,9 +466,14 @@ mod tests {
         ctx = mk_ctx();
         ctx.spec.os.kernel_command_line = KernelCommandLine {
             extra_command_line: vec!["test".to_string()],
+            ..Default::default()
         };
         assert!(os_config_requires_os_modifier(&ctx));
 
+        ctx = mk_ctx();
+        ctx.spec.os.network = Some(Network::default());
+        assert!(os_config_requires_os_modifier(&ctx));
+
         ctx = mk_ctx();
         ctx.servicing_type = ServicingType::AbUpdate;
         assert!(os_config_requires_os_modifier(&ctx));
@@ -475,6 +589,7 @@ mod functional_test {
         // Configure OsConfig subsystem and set prev_hostname parameter
         let mut os_config_subsystem = OsConfigSubsystem {
             prev_hostname: Some("carry-over-hostname".into()),
+            ..Default::default()
         };
         let _ = os_config_subsystem.configure(&ctx);
 
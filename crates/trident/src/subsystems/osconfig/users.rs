@@ -1,4 +1,6 @@
 use std::{
+    collections::BTreeMap,
+    fs,
     io::Write,
     path::{Path, PathBuf},
 };
@@ -6,14 +8,48 @@ use std::{
 use anyhow::{bail, Context, Error};
 use log::{debug, warn};
 
-use osutils::osmodifier::{MICPassword, MICUser, PasswordType};
-use trident_api::config::{Password, SshMode, User};
+use osutils::{
+    dependencies::Dependency,
+    files,
+    osmodifier::{MICPassword, MICUser, PasswordType},
+};
+use trident_api::{
+    config::{Password, SshMode, User},
+    error::TridentError,
+};
+
+use crate::secrets;
 
 const SSHD_CONFIG_FILE: &str = "/etc/ssh/sshd_config";
 const SSHD_CONFIG_DIR: &str = "/etc/ssh/sshd_config.d";
 const GLOBAL_CONFIG_FILE_NAME: &str = "global_user.conf";
 
-pub(super) fn set_up_users(users: &[User]) -> Result<Vec<MICUser>, Error> {
+/// Sudoers drop-in file holding the sudo rules of Trident-managed users. sudo skips drop-in files
+/// whose names contain a dot, so the name has no extension.
+const SUDOERS_FILE: &str = "/etc/sudoers.d/trident-users";
+
+/// Permissions required by sudo for sudoers files.
+const SUDOERS_FILE_MODE: u32 = 0o440;
+
+/// Resolves the passwords of `users` that are set, by username.
+///
+/// Referenced passwords are read from the servicing OS, so this must run before entering the chroot
+/// of the target OS.
+pub(super) fn resolve_passwords(users: &[User]) -> Result<BTreeMap<String, String>, TridentError> {
+    users
+        .iter()
+        .filter_map(|user| {
+            let secret = user.password.secret()?;
+            Some(secrets::resolve(secret).map(|password| (user.name.clone(), password)))
+        })
+        .collect()
+}
+
+/// Sets up `users`, whose passwords were resolved into `passwords` by `resolve_passwords`.
+pub(super) fn set_up_users(
+    users: &[User],
+    passwords: &BTreeMap<String, String>,
+) -> Result<Vec<MICUser>, Error> {
     if Path::new(SSHD_CONFIG_FILE).exists() {
         debug!("Setting up sshd config");
 
@@ -85,10 +121,12 @@ pub(super) fn set_up_users(users: &[User]) -> Result<Vec<MICUser>, Error> {
         warn!("sshd_config not found, skipping sshd config");
     }
 
-    Ok(users
+    write_sudoers(users, SUDOERS_FILE).context("Failed to set up sudo rules")?;
+
+    users
         .iter()
-        .map(|user| create_mic_user(user.clone()))
-        .collect())
+        .map(|user| create_mic_user(user.clone(), passwords))
+        .collect()
 }
 
 fn ssh_global_config(users: &[User]) -> Result<(), Error> {
@@ -152,15 +190,74 @@ fn ssh_global_config(users: &[User]) -> Result<(), Error> {
         .context("Failed to write global user sshd config")
 }
 
-fn create_mic_user(user: User) -> MICUser {
-    let (password_type, password_text) = match user.password {
+/// Writes the sudo rules of `users` into the sudoers drop-in file at `path`, or removes the file
+/// when no user has sudo rules.
+fn write_sudoers(users: &[User], path: impl AsRef<Path>) -> Result<(), Error> {
+    let path = path.as_ref();
+    let Some(contents) = render_sudoers(users) else {
+        if path.exists() {
+            debug!("Removing stale sudoers file '{}'", path.display());
+            fs::remove_file(path)
+                .with_context(|| format!("Failed to remove '{}'", path.display()))?;
+        }
+        return Ok(());
+    };
+
+    debug!("Writing sudo rules to '{}'", path.display());
+    files::write_file(path, SUDOERS_FILE_MODE, contents.as_bytes())?;
+
+    // A sudoers file with a syntax error disables sudo for everyone, so it must not be left behind.
+    if Dependency::Visudo.exists() {
+        if let Err(e) = Dependency::Visudo
+            .cmd()
+            .arg("--check")
+            .arg("--quiet")
+            .arg("--file")
+            .arg(path)
+            .run_and_check()
+        {
+            fs::remove_file(path).with_context(|| {
+                format!("Failed to remove invalid sudoers file '{}'", path.display())
+            })?;
+            return Err(e).context("Sudo rules are invalid");
+        }
+    } else {
+        warn!("visudo not found, skipping validation of sudo rules");
+    }
+
+    Ok(())
+}
+
+/// Renders the sudoers file granting the sudo rules of `users`, or `None` if no user has any.
+fn render_sudoers(users: &[User]) -> Option<String> {
+    let rules = users
+        .iter()
+        .flat_map(|user| {
+            user.sudo_rules
+                .iter()
+                .map(move |rule| format!("{} {}\n", user.name, rule.trim()))
+        })
+        .collect::<String>();
+    (!rules.is_empty()).then(|| format!("# Generated by Trident. Do not edit.\n{rules}"))
+}
+
+fn create_mic_user(user: User, passwords: &BTreeMap<String, String>) -> Result<MICUser, Error> {
+    let password_type = match user.password {
         #[cfg(feature = "dangerous-options")]
-        Password::DangerousPlainText(ref s) => (PasswordType::PlainText, Some(s.as_str())),
+        Password::DangerousPlainText(_) => PasswordType::PlainText,
 
         #[cfg(feature = "dangerous-options")]
-        Password::DangerousHashed(ref s) => (PasswordType::Hashed, Some(s.as_str())),
+        Password::DangerousHashed(_) => PasswordType::Hashed,
 
-        Password::Locked => (PasswordType::Locked, None::<&str>),
+        Password::Locked => PasswordType::Locked,
+    };
+    let password_text = match user.password.secret() {
+        Some(_) => Some(
+            passwords
+                .get(&user.name)
+                .with_context(|| format!("Password of user '{}' was not resolved", user.name))?,
+        ),
+        None => None,
     };
 
     let password_expires_days = {
@@ -177,10 +274,10 @@ fn create_mic_user(user: User) -> MICUser {
 
     let mic_password = password_text.map(|password_text| MICPassword {
         password_type,
-        value: password_text.to_string(),
+        value: password_text.clone(),
     });
 
-    MICUser {
+    Ok(MICUser {
         name: user.name,
         uid: user.uid,
         password: mic_password,
@@ -190,5 +287,47 @@ fn create_mic_user(user: User) -> MICUser {
         secondary_groups: user.secondary_groups,
         startup_command: user.startup_command,
         home_directory: user.home_directory,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_sudoers() {
+        let mut users = vec![
+            User {
+                name: "admin".to_string(),
+                sudo_rules: vec![
+                    "ALL=(ALL) NOPASSWD: ALL".to_string(),
+                    " ALL=(root) /usr/bin/systemctl ".to_string(),
+                ],
+                ..Default::default()
+            },
+            User {
+                name: "guest".to_string(),
+                ..Default::default()
+            },
+        ];
+        assert_eq!(
+            render_sudoers(&users).unwrap(),
+            "# Generated by Trident. Do not edit.\n\
+            admin ALL=(ALL) NOPASSWD: ALL\n\
+            admin ALL=(root) /usr/bin/systemctl\n"
+        );
+
+        users[0].sudo_rules.clear();
+        assert_eq!(render_sudoers(&users), None);
+    }
+
+    #[test]
+    fn test_write_sudoers_removes_stale_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("trident-users");
+        fs::write(&path, "admin ALL=(ALL) ALL\n").unwrap();
+
+        write_sudoers(&[], &path).unwrap();
+        assert!(!path.exists());
     }
 }
@@ -0,0 +1,132 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Error};
+use log::{debug, info};
+use sys_mount::{MountBuilder, MountFlags, UnmountFlags};
+
+use osutils::{packages::PackageManager, path::join_relative};
+use trident_api::{
+    config::HostConfigurationDynamicValidationError,
+    error::{InvalidInputError, ReportError, ServicingError, TridentError},
+    status::ServicingType,
+};
+
+use crate::engine::{EngineContext, Subsystem};
+
+/// Path in the target OS where the local package repository is mounted during clean install.
+///
+/// It is under `/run`, which is a tmpfs in the new root, so nothing is left behind in the target
+/// OS.
+const PACKAGE_REPOSITORY_MOUNT_PATH: &str = "/run/trident-package-repository";
+
+#[derive(Default)]
+pub struct PackagesSubsystem {
+    /// Whether the local package repository is mounted in the target OS.
+    repository_mounted: bool,
+}
+
+/// Returns whether packages must be installed into the target OS.
+fn should_install_packages(ctx: &EngineContext) -> bool {
+    ctx.servicing_type == ServicingType::CleanInstall && !ctx.spec.os.packages.is_empty()
+}
+
+impl Subsystem for PackagesSubsystem {
+    fn name(&self) -> &'static str {
+        "packages"
+    }
+
+    fn validate_host_config(&self, ctx: &EngineContext) -> Result<(), TridentError> {
+        if !should_install_packages(ctx) {
+            return Ok(());
+        }
+
+        if let Some(repository) = &ctx.spec.os.package_repository {
+            if !repository.is_dir() {
+                return Err(TridentError::new(InvalidInputError::from(
+                    HostConfigurationDynamicValidationError::PackageRepositoryNotFound {
+                        path: repository.display().to_string(),
+                    },
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn provision(&mut self, ctx: &EngineContext, mount_path: &Path) -> Result<(), TridentError> {
+        if !should_install_packages(ctx) {
+            return Ok(());
+        }
+
+        if let Some(repository) = &ctx.spec.os.package_repository {
+            mount_repository(
+                repository,
+                &join_relative(mount_path, PACKAGE_REPOSITORY_MOUNT_PATH),
+            )
+            .structured(ServicingError::MountPackageRepository {
+                path: repository.display().to_string(),
+            })?;
+            self.repository_mounted = true;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "packages_installation", skip_all)]
+    fn configure(&mut self, ctx: &EngineContext) -> Result<(), TridentError> {
+        if !should_install_packages(ctx) {
+            return Ok(());
+        }
+
+        let repository = self
+            .repository_mounted
+            .then(|| Path::new(PACKAGE_REPOSITORY_MOUNT_PATH));
+        let result = install_packages(&ctx.spec.os.packages, repository);
+
+        // Unmount the repository even if the installation failed, so that the new root can be
+        // unmounted.
+        if self.repository_mounted {
+            debug!("Unmounting package repository '{PACKAGE_REPOSITORY_MOUNT_PATH}'");
+            sys_mount::unmount(PACKAGE_REPOSITORY_MOUNT_PATH, UnmountFlags::DETACH)
+                .context("Failed to unmount package repository")
+                .structured(ServicingError::InstallPackages)?;
+            self.repository_mounted = false;
+        }
+
+        result.structured(ServicingError::InstallPackages)
+    }
+}
+
+/// Bind mounts the local package repository at `target`.
+fn mount_repository(repository: &Path, target: &Path) -> Result<(), Error> {
+    debug!(
+        "Mounting package repository '{}' at '{}'",
+        repository.display(),
+        target.display()
+    );
+    fs::create_dir_all(target)
+        .with_context(|| format!("Failed to create directory '{}'", target.display()))?;
+    MountBuilder::default()
+        .flags(MountFlags::BIND)
+        .mount(repository, target)
+        .with_context(|| {
+            format!(
+                "Failed to bind mount '{}' to '{}'",
+                repository.display(),
+                target.display()
+            )
+        })?;
+
+    Ok(())
+}
+
+/// Installs `packages` in the running OS with its package manager.
+fn install_packages(packages: &[String], repository: Option<&Path>) -> Result<(), Error> {
+    let package_manager =
+        PackageManager::detect().context("Neither tdnf nor dnf is installed in the target OS")?;
+    info!(
+        "Installing {} package(s) with {package_manager:?}",
+        packages.len()
+    );
+    package_manager.install(packages, repository)
+}
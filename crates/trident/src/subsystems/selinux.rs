@@ -1,12 +1,12 @@
 use std::{
-    fs::File,
+    fs::{self, File},
     io::{BufRead, BufReader},
     path::{Path, PathBuf},
     str::FromStr,
 };
 
 use anyhow::{bail, Context, Error};
-use log::warn;
+use log::{debug, warn};
 
 use osutils::dependencies::{Dependency, DependencyResultExt};
 
@@ -69,14 +69,43 @@ fn get_selinux_mode(selinux_config_path: impl AsRef<Path>) -> Result<SelinuxMode
     );
 }
 
+/// SELinux policy module read from the servicing OS, to be installed into the target OS.
+struct PolicyModule {
+    /// File name of the module, whose extension tells `semodule` its format.
+    file_name: String,
+    contents: Vec<u8>,
+}
+
 #[derive(Default)]
-pub struct SelinuxSubsystem;
+pub struct SelinuxSubsystem {
+    /// Policy modules of the Host Configuration, read before entering the target OS.
+    policy_modules: Vec<PolicyModule>,
+}
 
 impl Subsystem for SelinuxSubsystem {
     fn name(&self) -> &'static str {
         "selinux"
     }
 
+    fn prepare(&mut self, ctx: &EngineContext) -> Result<(), TridentError> {
+        self.policy_modules = ctx
+            .spec
+            .os
+            .selinux
+            .policy_modules
+            .iter()
+            .map(|path| {
+                read_policy_module(path).structured(InvalidInputError::from(
+                    HostConfigurationDynamicValidationError::LoadSelinuxPolicyModule {
+                        path: path.display().to_string(),
+                    },
+                ))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(())
+    }
+
     #[tracing::instrument(name = "selinux_configuration", skip_all)]
     fn configure(&mut self, ctx: &EngineContext) -> Result<(), TridentError> {
         // Only continue if the servicing type is a clean install or AB update.
@@ -111,6 +140,9 @@ impl Subsystem for SelinuxSubsystem {
         // If the final SELinux state is disabled, return early, no relabeling
         // is necessary.
         if final_selinux_mode == SelinuxMode::Disabled {
+            if !self.policy_modules.is_empty() {
+                warn!("SELinux is disabled, skipping installation of SELinux policy modules");
+            }
             return Ok(());
         }
 
@@ -124,6 +156,12 @@ impl Subsystem for SelinuxSubsystem {
 
         validate_final_selinux_mode(ctx, final_selinux_mode)?;
 
+        // Modules may define file contexts, so they must be installed before relabeling.
+        if !self.policy_modules.is_empty() {
+            install_policy_modules(&self.policy_modules)
+                .structured(ServicingError::InstallSelinuxPolicyModules)?;
+        }
+
         perform_relabel(ctx)
     }
 }
@@ -226,6 +264,41 @@ fn validate_final_selinux_mode(
     Ok(())
 }
 
+/// Reads the SELinux policy module at `path`.
+fn read_policy_module(path: &Path) -> Result<PolicyModule, Error> {
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .with_context(|| format!("Invalid file name of '{}'", path.display()))?
+        .to_string();
+    let contents =
+        fs::read(path).with_context(|| format!("Failed to read file '{}'", path.display()))?;
+
+    Ok(PolicyModule {
+        file_name,
+        contents,
+    })
+}
+
+/// Installs `modules` into the SELinux policy of the OS with a single `semodule` transaction, so
+/// that modules depending on each other can be installed together.
+fn install_policy_modules(modules: &[PolicyModule]) -> Result<(), Error> {
+    let temp_dir = tempfile::tempdir().context("Failed to create temporary directory")?;
+
+    let mut cmd = Dependency::Semodule.cmd();
+    for module in modules {
+        let path = temp_dir.path().join(&module.file_name);
+        fs::write(&path, &module.contents)
+            .with_context(|| format!("Failed to write '{}'", path.display()))?;
+        debug!("Installing SELinux policy module '{}'", module.file_name);
+        cmd.arg("--install").arg(path);
+    }
+
+    cmd.run_and_check()
+        .context("Failed to run semodule command")?;
+    Ok(())
+}
+
 /// Runs the setfiles command to relabel the required filesystems.
 fn perform_relabel(ctx: &EngineContext) -> Result<(), TridentError> {
     let selinux_type =
@@ -401,6 +474,19 @@ mod tests {
         assert_eq!(filesystems_to_relabel(&ctx).unwrap(), expected);
     }
 
+    #[test]
+    fn test_read_policy_module() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("app.cil");
+        fs::write(&path, "(allow app_t app_t (file (read)))").unwrap();
+
+        let module = read_policy_module(&path).unwrap();
+        assert_eq!(module.file_name, "app.cil");
+        assert_eq!(module.contents, b"(allow app_t app_t (file (read)))");
+
+        read_policy_module(&temp_dir.path().join("missing.pp")).unwrap_err();
+    }
+
     #[test]
     fn test_get_selinux_mode_success_enforcing() {
         let mut temp_file = NamedTempFile::new().unwrap();
@@ -527,16 +613,14 @@ mod tests {
 
         // Test case 2: dynamic validation fails with SELinux in enforcing mode
         // and extension images present.
-        ctx.spec.os.sysexts.push(Extension {
-            url: Url::parse("https://example.com/sysext").unwrap(),
-            sha384: Sha384Hash::from("a".repeat(96)),
-            path: None,
-        });
-        ctx.spec.os.confexts.push(Extension {
-            url: Url::parse("https://example.com/confext").unwrap(),
-            sha384: Sha384Hash::from("b".repeat(96)),
-            path: None,
-        });
+        ctx.spec.os.sysexts.push(Extension::new(
+            Url::parse("https://example.com/sysext").unwrap(),
+            Sha384Hash::from("a".repeat(96)),
+        ));
+        ctx.spec.os.confexts.push(Extension::new(
+            Url::parse("https://example.com/confext").unwrap(),
+            Sha384Hash::from("b".repeat(96)),
+        ));
 
         let err = validate_final_selinux_mode(&ctx, SelinuxMode::Enforcing).unwrap_err();
         assert_eq!(
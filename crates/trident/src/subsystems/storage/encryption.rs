@@ -22,7 +22,7 @@ use trident_api::{
 
 use crate::{
     engine::{storage::encryption as engine_encryption, EngineContext},
-    ServicingType,
+    secrets, ServicingType,
 };
 
 const CRYPTTAB_PATH: &str = "/etc/crypttab";
@@ -88,6 +88,15 @@ pub(super) fn validate_host_config(ctx: &EngineContext) -> Result<(), TridentErr
             }
         }
 
+        // A recovery key read from a secret reference must be readable and not empty.
+        if let Some(recovery_key) = &encryption.recovery_key {
+            if secrets::resolve(recovery_key)?.is_empty() {
+                return Err(TridentError::new(InvalidInputError::from(
+                    HostConfigurationDynamicValidationError::EncryptionRecoveryKeyEmpty,
+                )));
+            }
+        }
+
         // We've already validated that only supported PCRs, i.e. 4, 7, and/or 11, are specified;
         // but we also need to ensure that only PCR 7 is specified for grub images.
         if !ctx.is_uki()? {
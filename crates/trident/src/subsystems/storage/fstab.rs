@@ -67,6 +67,13 @@ pub(super) fn generate_fstab(ctx: &EngineContext, output_path: &Path) -> Result<
 
     // Add the swap entries to the list of entries
     entries.extend(swap_entries);
+    entries.extend(
+        ctx.spec
+            .storage
+            .swap_files
+            .iter()
+            .map(|swap_file| TabFileEntry::new_swap(&swap_file.path)),
+    );
 
     let fstab = TabFile { entries };
 
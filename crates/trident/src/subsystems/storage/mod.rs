@@ -1,13 +1,15 @@
 use std::{
     collections::HashMap,
+    fs,
     path::{Path, PathBuf},
 };
 
+use anyhow::{Context, Error};
 use log::{debug, error, warn};
 
 use osutils::lsblk;
 use trident_api::{
-    config::HostConfigurationDynamicValidationError,
+    config::{Disk, DiskClass, HostConfigurationDynamicValidationError},
     constants::internal_params::RELAXED_COSI_VALIDATION,
     error::{
         InvalidInputError, ReportError, ServicingError, TridentError, TridentResultExt,
@@ -24,11 +26,15 @@ mod fstab;
 mod image;
 mod osimage;
 mod raid;
+mod swap;
 mod verity;
 
 const ENCRYPTION_SUBSYSTEM_NAME: &str = "encryption";
 const OSIMAGE_SUBSYSTEM_NAME: &str = "osimage";
 
+/// Directory where the kernel exposes the attributes of block devices.
+const SYSFS_BLOCK_DIRECTORY: &str = "/sys/class/block";
+
 #[derive(Default, Debug)]
 pub(crate) struct StorageSubsystem;
 impl Subsystem for StorageSubsystem {
@@ -128,6 +134,32 @@ impl Subsystem for StorageSubsystem {
             }
         }
 
+        // On clean install, ensure both volumes of every A/B volume pair are on disks of the same
+        // class. Disks with kernel device paths were already checked when building the storage
+        // graph, but stable paths can only be resolved on the target system. The layout cannot
+        // change afterwards, so there is nothing to check on updates.
+        let ab_volumes = match ctx.servicing_type {
+            ServicingType::CleanInstall => ctx.storage_graph.ab_volume_disks(),
+            _ => Vec::new(),
+        };
+        for ab_volume in ab_volumes {
+            let (Some(volume_a_classes), Some(volume_b_classes)) = (
+                disk_classes(&ab_volume.volume_a_disks),
+                disk_classes(&ab_volume.volume_b_disks),
+            ) else {
+                continue;
+            };
+            if volume_a_classes != volume_b_classes {
+                return Err(TridentError::new(InvalidInputError::from(
+                    HostConfigurationDynamicValidationError::AbVolumeDiskClassMismatch {
+                        ab_volume_id: ab_volume.id.clone(),
+                        volume_a_classes: join_classes(&volume_a_classes),
+                        volume_b_classes: join_classes(&volume_b_classes),
+                    },
+                )));
+            }
+        }
+
         encryption::validate_host_config(ctx).message(format!(
             "Step 'Validate' failed for subunit '{ENCRYPTION_SUBSYSTEM_NAME}'"
         ))?;
@@ -205,10 +237,71 @@ impl Subsystem for StorageSubsystem {
         // Persist on reboots
         raid::configure(ctx).structured(ServicingError::CreateMdadmConf)?;
 
+        swap::create_swap_files(ctx).structured(ServicingError::CreateSwapFiles)?;
+        swap::configure_zram(ctx, Path::new(swap::ZRAM_GENERATOR_CONFIG_PATH))
+            .structured(ServicingError::ConfigureZram)?;
+
         Ok(())
     }
 }
 
+/// Returns the sorted, deduplicated classes of the given disks, or `None` with a warning if the
+/// class of one of them is unknown.
+fn disk_classes(disks: &[&Disk]) -> Option<Vec<DiskClass>> {
+    let mut classes = disks
+        .iter()
+        .map(|disk| {
+            disk_class(Path::new(SYSFS_BLOCK_DIRECTORY), &disk.device)
+                .inspect_err(|e| {
+                    warn!(
+                        "Not checking the disk class of A/B volume pairs on disk '{}', as it is \
+                        unknown: {e:?}",
+                        disk.id
+                    )
+                })
+                .ok()
+        })
+        .collect::<Option<Vec<_>>>()?;
+    classes.sort();
+    classes.dedup();
+    Some(classes)
+}
+
+/// Returns the class of the disk at the given device path, based on its kernel name or, when the
+/// name is not enough, on whether the kernel reports it as rotational under `sysfs_block`.
+fn disk_class(sysfs_block: &Path, device: &Path) -> Result<DiskClass, Error> {
+    let device = device
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve device path '{}'", device.display()))?;
+    let name = device
+        .file_name()
+        .and_then(|name| name.to_str())
+        .with_context(|| format!("Failed to get kernel name of '{}'", device.display()))?;
+
+    if let Some(class) = DiskClass::from_kernel_name(name) {
+        return Ok(class);
+    }
+
+    let rotational_path = sysfs_block.join(name).join("queue/rotational");
+    let rotational = fs::read_to_string(&rotational_path)
+        .with_context(|| format!("Failed to read '{}'", rotational_path.display()))?;
+
+    Ok(if rotational.trim() == "1" {
+        DiskClass::Rotational
+    } else {
+        DiskClass::SolidState
+    })
+}
+
+/// Returns a comma-separated list of disk classes.
+fn join_classes(classes: &[DiskClass]) -> String {
+    classes
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -400,6 +493,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_disk_class() {
+        let sysfs_block = tempfile::tempdir().unwrap();
+        let dev = tempfile::tempdir().unwrap();
+
+        for (name, rotational) in [("sda", "1\n"), ("sdb", "0\n")] {
+            let queue = sysfs_block.path().join(name).join("queue");
+            fs::create_dir_all(&queue).unwrap();
+            fs::write(queue.join("rotational"), rotational).unwrap();
+        }
+        for name in ["sda", "sdb", "sdc", "nvme0n1"] {
+            fs::write(dev.path().join(name), "").unwrap();
+        }
+
+        assert_eq!(
+            disk_class(sysfs_block.path(), &dev.path().join("sda")).unwrap(),
+            DiskClass::Rotational
+        );
+        assert_eq!(
+            disk_class(sysfs_block.path(), &dev.path().join("sdb")).unwrap(),
+            DiskClass::SolidState
+        );
+
+        // NVMe drives are classified by their name alone
+        assert_eq!(
+            disk_class(sysfs_block.path(), &dev.path().join("nvme0n1")).unwrap(),
+            DiskClass::Nvme
+        );
+
+        // Disks the kernel does not know about cannot be classified
+        disk_class(sysfs_block.path(), &dev.path().join("sdc")).unwrap_err();
+    }
+
     // Validating the Storage subsystem include encryption configuration validation.
     #[test]
     fn test_validate_host_config_encryption_invalid_fail() {
@@ -0,0 +1,182 @@
+use std::{
+    fs::{self, File, Permissions},
+    io::{self, BufWriter, Read, Write},
+    os::unix::fs::PermissionsExt,
+    path::Path,
+};
+
+use anyhow::{Context, Error};
+use log::{debug, info};
+
+use osutils::swap;
+use trident_api::config::{SwapFile, Zram};
+
+use crate::engine::EngineContext;
+
+/// Path of the configuration of systemd's zram-generator.
+pub(super) const ZRAM_GENERATOR_CONFIG_PATH: &str = "/etc/systemd/zram-generator.conf";
+
+/// First line of the zram-generator configuration written by Trident.
+const ZRAM_GENERATOR_CONFIG_HEADER: &str = "# Generated by Trident. Do not edit.";
+
+/// Size of the chunks of zeros written to swap files.
+const SWAP_FILE_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Creates the swap files of the Host Configuration in the target OS.
+///
+/// Swap files cannot have holes, so they are filled with zeros rather than allocated sparsely.
+/// Swap files that already exist with the expected size, e.g. on a data volume kept by an A/B
+/// update, are reused.
+pub(super) fn create_swap_files(ctx: &EngineContext) -> Result<(), Error> {
+    for swap_file in &ctx.spec.storage.swap_files {
+        create_swap_file(swap_file).with_context(|| {
+            format!("Failed to create swap file '{}'", swap_file.path.display())
+        })?;
+    }
+
+    Ok(())
+}
+
+fn create_swap_file(swap_file: &SwapFile) -> Result<(), Error> {
+    let path = &swap_file.path;
+    let size = swap_file.size.bytes();
+    if fs::metadata(path).is_ok_and(|metadata| metadata.is_file() && metadata.len() == size) {
+        debug!("Reusing swap file '{}'", path.display());
+        return Ok(());
+    }
+
+    info!("Creating swap file '{}' of {} bytes", path.display(), size);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+    }
+
+    write_zeros(path, size)?;
+    // Swap files must not be readable by other users, mkswap warns otherwise.
+    fs::set_permissions(path, Permissions::from_mode(0o600))
+        .context("Failed to set permissions of swap file")?;
+
+    swap::mkswap(path)
+}
+
+/// Writes a file of `size` zeros at `path`, replacing any existing file.
+fn write_zeros(path: &Path, size: u64) -> Result<(), Error> {
+    let mut writer = BufWriter::with_capacity(
+        SWAP_FILE_CHUNK_SIZE,
+        File::create(path).context("Failed to create file")?,
+    );
+    io::copy(&mut io::repeat(0).take(size), &mut writer).context("Failed to write file")?;
+    writer
+        .into_inner()
+        .context("Failed to flush file")?
+        .sync_all()
+        .context("Failed to sync file")
+}
+
+/// Writes the configuration of systemd's zram-generator at `config_path`, or removes the one
+/// previously written by Trident when zram is not configured.
+pub(super) fn configure_zram(ctx: &EngineContext, config_path: &Path) -> Result<(), Error> {
+    let Some(zram) = &ctx.spec.storage.zram else {
+        if fs::read_to_string(config_path)
+            .is_ok_and(|contents| contents.starts_with(ZRAM_GENERATOR_CONFIG_HEADER))
+        {
+            debug!("Removing zram configuration '{}'", config_path.display());
+            fs::remove_file(config_path)
+                .with_context(|| format!("Failed to remove '{}'", config_path.display()))?;
+        }
+        return Ok(());
+    };
+
+    info!("Configuring zram device");
+    if let Some(parent) = config_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+    }
+    let mut file = File::create(config_path)
+        .with_context(|| format!("Failed to create '{}'", config_path.display()))?;
+    file.write_all(render_zram_config(zram).as_bytes())
+        .with_context(|| format!("Failed to write '{}'", config_path.display()))
+}
+
+/// Renders the zram-generator configuration of `zram`.
+fn render_zram_config(zram: &Zram) -> String {
+    // zram-generator takes the size in MiB.
+    let mut config = format!(
+        "{ZRAM_GENERATOR_CONFIG_HEADER}\n[zram0]\nzram-size = {}\n",
+        zram.size.bytes().div_ceil(1024 * 1024)
+    );
+    if let Some(algorithm) = zram.compression_algorithm {
+        config.push_str(&format!("compression-algorithm = {}\n", algorithm.name()));
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use trident_api::{
+        config::{Storage, ZramCompressionAlgorithm},
+        primitives::bytes::ByteCount,
+    };
+
+    #[test]
+    fn test_render_zram_config() {
+        assert_eq!(
+            render_zram_config(&Zram {
+                size: ByteCount::from(4u64 << 30),
+                compression_algorithm: Some(ZramCompressionAlgorithm::Zstd),
+            }),
+            "# Generated by Trident. Do not edit.\n[zram0]\nzram-size = 4096\n\
+            compression-algorithm = zstd\n"
+        );
+        assert_eq!(
+            render_zram_config(&Zram {
+                size: ByteCount::from(1000),
+                compression_algorithm: None,
+            }),
+            "# Generated by Trident. Do not edit.\n[zram0]\nzram-size = 1\n"
+        );
+    }
+
+    #[test]
+    fn test_configure_zram() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("zram-generator.conf");
+        let mut ctx = EngineContext::default();
+        ctx.spec.storage = Storage {
+            zram: Some(Zram {
+                size: ByteCount::from(2u64 << 30),
+                compression_algorithm: None,
+            }),
+            ..Default::default()
+        };
+
+        configure_zram(&ctx, &config_path).unwrap();
+        assert!(fs::read_to_string(&config_path)
+            .unwrap()
+            .contains("zram-size = 2048\n"));
+
+        // The configuration written by Trident is removed along with zram.
+        ctx.spec.storage.zram = None;
+        configure_zram(&ctx, &config_path).unwrap();
+        assert!(!config_path.exists());
+
+        // A configuration shipped in the OS image is kept.
+        fs::write(&config_path, "[zram0]\n").unwrap();
+        configure_zram(&ctx, &config_path).unwrap();
+        assert!(config_path.exists());
+    }
+
+    #[test]
+    fn test_write_zeros() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("swapfile");
+        fs::write(&path, "previous contents").unwrap();
+
+        write_zeros(&path, 3 * 1024 * 1024 + 5).unwrap();
+        let contents = fs::read(&path).unwrap();
+        assert_eq!(contents.len(), 3 * 1024 * 1024 + 5);
+        assert!(contents.iter().all(|&byte| byte == 0));
+    }
+}
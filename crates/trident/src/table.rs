@@ -0,0 +1,50 @@
+//! Formatting of plain-text tables printed by the CLI.
+
+use trident_api::messages::{self, MessageKey};
+
+/// Formats rows as left-aligned columns separated by two spaces, below a header rendered from the
+/// given message keys.
+pub(crate) fn format_table(header: &[MessageKey], rows: Vec<Vec<String>>) -> String {
+    let header = header
+        .iter()
+        .map(|key| messages::render(*key, &[]))
+        .collect::<Vec<_>>();
+    let mut widths = header.iter().map(String::len).collect::<Vec<_>>();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        table.push_str(line.trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_table() {
+        assert_eq!(
+            format_table(
+                &[MessageKey::TableHost, MessageKey::TableResult],
+                vec![
+                    vec!["node-1".into(), "ok".into()],
+                    vec!["node-10".into(), "".into()],
+                ]
+            ),
+            "HOST     RESULT\nnode-1   ok\nnode-10\n"
+        );
+    }
+}
@@ -0,0 +1,449 @@
+//! Variable substitution in the Host Configuration.
+//!
+//! A Host Configuration can reference variables as `${namespace.name}`, which are replaced with
+//! their value when the Host Configuration is loaded, so that a single template can serve a whole
+//! fleet. The namespace selects where the value comes from:
+//!
+//! - `env`: environment variable of Trident, e.g. `${env.SITE}`.
+//! - `values`: key of the values file at `/etc/trident/values.yaml`, nested keys being separated
+//!   by dots, e.g. `${values.network.address}`.
+//! - `smbios`: SMBIOS field exposed in `/sys/class/dmi/id`, e.g. `${smbios.product_serial}`.
+//! - `imds`: text field of the Azure Instance Metadata Service, e.g. `${imds.compute/name}`.
+//!
+//! `${namespace.name:-default}` falls back to `default` when the variable is not defined, and
+//! `$${namespace.name}` is left as `${namespace.name}`. References without one of the namespaces
+//! above, such as `${HOME}` in the content of a script, are left untouched.
+//!
+//! Variables are only substituted in the strings of the parsed YAML document, so that their value
+//! cannot change its structure. A string that is a single reference takes the type of its value
+//! when it is a number or a boolean.
+//!
+//! The hostname template of `os.identity` references the same variables, which are resolved by
+//! [`render_text`] when the host is serviced.
+
+use std::{collections::HashMap, env, fs, path::Path, time::Duration};
+
+use anyhow::{bail, ensure, Context, Error};
+use lazy_static::lazy_static;
+use log::debug;
+use regex::{Captures, Regex};
+use reqwest::blocking::Client;
+use serde_yaml::{value::TaggedValue, Mapping, Value};
+
+use trident_api::constants::TEMPLATE_VALUES_PATH;
+
+/// Directory exposing the SMBIOS fields of the host.
+const SMBIOS_PATH: &str = "/sys/class/dmi/id";
+
+/// URL of the instance metadata of the Azure Instance Metadata Service.
+const IMDS_INSTANCE_URL: &str = "http://169.254.169.254/metadata/instance";
+
+/// API version of the Azure Instance Metadata Service.
+const IMDS_API_VERSION: &str = "2021-02-01";
+
+/// Timeout of the requests to the Azure Instance Metadata Service.
+const IMDS_TIMEOUT: Duration = Duration::from_secs(5);
+
+lazy_static! {
+    /// Regular expression matching variable references, along with their escaped form.
+    static ref VARIABLE_REGEX: Regex =
+        Regex::new(r"(\$?)\$\{(env|values|smbios|imds)\.([A-Za-z0-9_./-]+)(?::-([^}]*))?\}")
+            .expect("Failed to compile regex");
+}
+
+/// Source of the value of a variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Namespace {
+    Env,
+    Values,
+    Smbios,
+    Imds,
+}
+
+impl Namespace {
+    fn parse(namespace: &str) -> Self {
+        match namespace {
+            "env" => Self::Env,
+            "values" => Self::Values,
+            "smbios" => Self::Smbios,
+            _ => Self::Imds,
+        }
+    }
+}
+
+/// Replaces the variables referenced in the YAML document `contents` with their value on this
+/// host.
+pub(crate) fn render(contents: &str) -> Result<String, Error> {
+    render_with(contents, host_lookup())
+}
+
+/// Replaces the variables referenced in `text`, e.g. a hostname template, with their value on this
+/// host.
+pub(crate) fn render_text(text: &str) -> Result<String, Error> {
+    Renderer::new(host_lookup()).render(text)
+}
+
+/// Returns the lookup of variables on this host. The values file is only loaded when a `values`
+/// variable is referenced.
+fn host_lookup() -> impl FnMut(Namespace, &str) -> Result<Option<String>, Error> {
+    let mut values = None;
+    move |namespace, name| match namespace {
+        Namespace::Env => Ok(env::var(name).ok()),
+        Namespace::Values => {
+            if values.is_none() {
+                values = Some(load_values(Path::new(TEMPLATE_VALUES_PATH))?);
+            }
+            Ok(values
+                .as_ref()
+                .and_then(|values| lookup_value(values, name)))
+        }
+        Namespace::Smbios => read_smbios_field(name),
+        Namespace::Imds => query_imds(name),
+    }
+}
+
+/// Replaces the variables referenced in the strings of the YAML document `contents` with the
+/// value returned by `lookup`, which is called once per variable. Fails if a variable without a
+/// default value is not defined.
+fn render_with(
+    contents: &str,
+    lookup: impl FnMut(Namespace, &str) -> Result<Option<String>, Error>,
+) -> Result<String, Error> {
+    if !VARIABLE_REGEX.is_match(contents) {
+        return Ok(contents.into());
+    }
+
+    let document: Value = serde_yaml::from_str(contents).context("Failed to parse YAML")?;
+    let rendered = Renderer::new(lookup).render_value(document)?;
+    serde_yaml::to_string(&rendered).context("Failed to serialize YAML")
+}
+
+/// Substitutes variables with the value returned by `lookup`, which is called once per variable.
+struct Renderer<F> {
+    lookup: F,
+    resolved: HashMap<(Namespace, String), Option<String>>,
+}
+
+impl<F> Renderer<F>
+where
+    F: FnMut(Namespace, &str) -> Result<Option<String>, Error>,
+{
+    fn new(lookup: F) -> Self {
+        Self {
+            lookup,
+            resolved: HashMap::new(),
+        }
+    }
+
+    /// Replaces the variables referenced in the strings of `value`, mapping keys included.
+    fn render_value(&mut self, value: Value) -> Result<Value, Error> {
+        Ok(match value {
+            Value::String(text) => {
+                let rendered = self.render(&text)?;
+                if is_reference(&text) {
+                    if let Ok(typed @ (Value::Number(_) | Value::Bool(_))) =
+                        serde_yaml::from_str::<Value>(&rendered)
+                    {
+                        return Ok(typed);
+                    }
+                }
+                Value::String(rendered)
+            }
+            Value::Sequence(sequence) => Value::Sequence(
+                sequence
+                    .into_iter()
+                    .map(|value| self.render_value(value))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Mapping(mapping) => Value::Mapping(
+                mapping
+                    .into_iter()
+                    .map(|(key, value)| Ok((self.render_value(key)?, self.render_value(value)?)))
+                    .collect::<Result<Mapping, Error>>()?,
+            ),
+            Value::Tagged(tagged) => Value::Tagged(Box::new(TaggedValue {
+                tag: tagged.tag,
+                value: self.render_value(tagged.value)?,
+            })),
+            value => value,
+        })
+    }
+
+    /// Replaces the variables referenced in `text`. Fails if a variable without a default value is
+    /// not defined.
+    fn render(&mut self, text: &str) -> Result<String, Error> {
+        let mut error = None;
+        let rendered = VARIABLE_REGEX.replace_all(text, |captures: &Captures| {
+            let reference = &captures[0];
+            // Escaped references lose their first '$'.
+            if !captures[1].is_empty() {
+                return reference[1..].to_string();
+            }
+            if error.is_some() {
+                return String::new();
+            }
+
+            let namespace = Namespace::parse(&captures[2]);
+            let name = &captures[3];
+            let value = match self.resolved.get(&(namespace, name.into())) {
+                Some(value) => value.clone(),
+                None => match (self.lookup)(namespace, name) {
+                    Ok(value) => {
+                        debug!(
+                            "Resolved variable '{}.{name}' in Host Configuration",
+                            &captures[2]
+                        );
+                        self.resolved
+                            .insert((namespace, name.into()), value.clone());
+                        value
+                    }
+                    Err(e) => {
+                        error =
+                            Some(e.context(format!("Failed to resolve variable '{reference}'")));
+                        return String::new();
+                    }
+                },
+            };
+
+            match value.or_else(|| captures.get(4).map(|default| default.as_str().into())) {
+                Some(value) => value,
+                None => {
+                    error = Some(anyhow::anyhow!(
+                        "Variable '{reference}' is not defined and has no default value"
+                    ));
+                    String::new()
+                }
+            }
+        });
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(rendered.into_owned()),
+        }
+    }
+}
+
+/// Returns whether `text` is a single, unescaped variable reference.
+fn is_reference(text: &str) -> bool {
+    VARIABLE_REGEX
+        .captures(text)
+        .is_some_and(|captures| captures[0].len() == text.len() && captures[1].is_empty())
+}
+
+/// Loads the values file at `path`.
+fn load_values(path: &Path) -> Result<Value, Error> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read values file '{}'", path.display()))?;
+    serde_yaml::from_str(&contents)
+        .with_context(|| format!("Failed to parse values file '{}'", path.display()))
+}
+
+/// Returns the scalar at the dot-separated key `name` of `values`, if any.
+fn lookup_value(values: &Value, name: &str) -> Option<String> {
+    let value = name
+        .split('.')
+        .try_fold(values, |value, key| value.get(key))?;
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// Reads the SMBIOS field `name`, if the host exposes it.
+fn read_smbios_field(name: &str) -> Result<Option<String>, Error> {
+    ensure!(
+        !name.contains('/') && !name.starts_with('.'),
+        "SMBIOS field '{name}' is invalid"
+    );
+    let path = Path::new(SMBIOS_PATH).join(name);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let value = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read '{}'", path.display()))?;
+    Ok(Some(value.trim().into()))
+}
+
+/// Queries the text field `name` of the instance metadata of the Azure Instance Metadata
+/// Service, e.g. `compute/name`.
+fn query_imds(name: &str) -> Result<Option<String>, Error> {
+    ensure!(
+        !name.split('/').any(|segment| segment == ".."),
+        "Instance metadata field '{name}' is invalid"
+    );
+    let response = Client::builder()
+        .timeout(IMDS_TIMEOUT)
+        // The Instance Metadata Service must be reached directly, never through a proxy.
+        .no_proxy()
+        .build()
+        .context("Failed to create HTTP client")?
+        .get(format!("{IMDS_INSTANCE_URL}/{name}"))
+        .query(&[("api-version", IMDS_API_VERSION), ("format", "text")])
+        .header("Metadata", "true")
+        .send()
+        .context("Failed to query the Instance Metadata Service")?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        bail!(
+            "Instance Metadata Service returned status '{}'",
+            response.status()
+        );
+    }
+    Ok(Some(response.text().context(
+        "Failed to read the response of the Instance Metadata Service",
+    )?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(namespace: Namespace, name: &str) -> Result<Option<String>, Error> {
+        Ok(match (namespace, name) {
+            (Namespace::Env, "SITE") => Some("paris".into()),
+            (Namespace::Smbios, "product_serial") => Some("SN-42".into()),
+            (Namespace::Imds, "compute/name") => Some("edge-7".into()),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_render_with() {
+        let template = indoc::indoc! {r#"
+            os:
+              hostname: ${env.SITE}-${smbios.product_serial}
+              users:
+                - name: ${env.USER_NAME:-admin}
+            scripts:
+              postConfigure:
+                - name: hello
+                  content: echo "${HOME} $${env.SITE} ${imds.compute/name}"
+        "#};
+        assert_eq!(
+            serde_yaml::from_str::<Value>(&render_with(template, lookup).unwrap()).unwrap(),
+            serde_yaml::from_str::<Value>(indoc::indoc! {r#"
+                os:
+                  hostname: paris-SN-42
+                  users:
+                    - name: admin
+                scripts:
+                  postConfigure:
+                    - name: hello
+                      content: echo "${HOME} ${env.SITE} edge-7"
+            "#})
+            .unwrap()
+        );
+
+        // Contents without variables are kept as is.
+        assert_eq!(
+            render_with("# Comment\nos: {}\n", lookup).unwrap(),
+            "# Comment\nos: {}\n"
+        );
+
+        let error = render_with("hostname: ${values.hostname}", lookup).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Variable '${values.hostname}' is not defined and has no default value"
+        );
+
+        // Variables are looked up once.
+        let mut lookups = 0;
+        render_with("${env.A} ${env.A} ${env.B:-b}", |_, _| {
+            lookups += 1;
+            Ok(Some("a".into()))
+        })
+        .unwrap();
+        assert_eq!(lookups, 2);
+    }
+
+    #[test]
+    fn test_render_with_hostile_value() {
+        // Values cannot change the structure of the document.
+        let hostile = "x\nos:\n  hostname: evil # comment: y";
+        let rendered = render_with(
+            "motd: ${values.motd}\nbanner: '> ${values.motd}'\n",
+            |_, _| Ok(Some(hostile.into())),
+        )
+        .unwrap();
+        let document: Value = serde_yaml::from_str(&rendered).unwrap();
+        assert_eq!(document.as_mapping().unwrap().len(), 2);
+        assert_eq!(document["motd"].as_str().unwrap(), hostile);
+        assert_eq!(document["banner"].as_str().unwrap(), format!("> {hostile}"));
+    }
+
+    #[test]
+    fn test_render_with_types() {
+        let template = indoc::indoc! {"
+            vlan: ${values.vlan}
+            enabled: ${values.enabled}
+            name: vlan-${values.vlan}
+            ${values.key}: ${values.name}
+        "};
+        let rendered = render_with(template, |_, name| {
+            Ok(Some(
+                match name {
+                    "vlan" => "42",
+                    "enabled" => "true",
+                    "key" => "site",
+                    _ => "paris: 1",
+                }
+                .into(),
+            ))
+        })
+        .unwrap();
+
+        // A string that is a single reference takes the type of a number or boolean value.
+        let document: Value = serde_yaml::from_str(&rendered).unwrap();
+        assert_eq!(document["vlan"], Value::from(42));
+        assert_eq!(document["enabled"], Value::Bool(true));
+        assert_eq!(document["name"], Value::from("vlan-42"));
+        assert_eq!(document["site"], Value::from("paris: 1"));
+    }
+
+    #[test]
+    fn test_render() {
+        let mut renderer = Renderer::new(lookup);
+        assert_eq!(
+            renderer.render("edge-${smbios.product_serial}").unwrap(),
+            "edge-SN-42"
+        );
+        assert_eq!(renderer.render("edge-{serial}").unwrap(), "edge-{serial}");
+        renderer.render("edge-${imds.compute/missing}").unwrap_err();
+
+        assert!(is_reference("${env.SITE}"));
+        assert!(is_reference("${env.SITE:-paris}"));
+        assert!(!is_reference("$${env.SITE}"));
+        assert!(!is_reference("site-${env.SITE}"));
+    }
+
+    #[test]
+    fn test_lookup_value() {
+        let values: Value = serde_yaml::from_str(indoc::indoc! {"
+            hostname: edge-1
+            network:
+              address: 10.0.0.2/24
+              vlan: 42
+            disks: [sda]
+        "})
+        .unwrap();
+        assert_eq!(lookup_value(&values, "hostname").unwrap(), "edge-1");
+        assert_eq!(
+            lookup_value(&values, "network.address").unwrap(),
+            "10.0.0.2/24"
+        );
+        assert_eq!(lookup_value(&values, "network.vlan").unwrap(), "42");
+        assert_eq!(lookup_value(&values, "network"), None);
+        assert_eq!(lookup_value(&values, "disks"), None);
+        assert_eq!(lookup_value(&values, "missing.key"), None);
+    }
+
+    #[test]
+    fn test_read_smbios_field() {
+        read_smbios_field("../../etc/passwd").unwrap_err();
+        read_smbios_field("..").unwrap_err();
+    }
+}
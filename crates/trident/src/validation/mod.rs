@@ -0,0 +1,256 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error};
+use log::{debug, info};
+use serde_yaml::Value;
+
+use trident_api::{
+    config::HostConfiguration,
+    error::{InternalError, InvalidInputError, ReportError, TridentError, TridentResultExt},
+};
+
+use crate::{compose, migrate, template};
+
+mod offline;
+
+pub(crate) fn parse_host_config(
+    contents: &str,
+    path: impl AsRef<Path>,
+) -> Result<HostConfiguration, TridentError> {
+    parse_host_config_with(contents, path, |include| {
+        fs::read_to_string(include).map_err(Error::from)
+    })
+}
+
+/// Parses the Host Configuration at `path`, loading the fragments it includes with `load`.
+pub(crate) fn parse_host_config_with(
+    contents: &str,
+    path: impl AsRef<Path>,
+    mut load: impl FnMut(&Path) -> Result<String, Error>,
+) -> Result<HostConfiguration, TridentError> {
+    let rendered = template::render(contents).structured(
+        InvalidInputError::RenderHostConfigurationTemplate {
+            path: path.as_ref().display().to_string(),
+        },
+    )?;
+    let merged = merge_includes(&rendered, path.as_ref(), &mut load).structured(
+        InvalidInputError::ResolveHostConfigurationIncludes {
+            path: path.as_ref().display().to_string(),
+        },
+    )?;
+    let contents = merged.as_deref().unwrap_or(&rendered);
+    let upgraded =
+        upgrade_api_version(contents).structured(InvalidInputError::MigrateHostConfiguration {
+            path: path.as_ref().display().to_string(),
+        })?;
+    let contents = upgraded.as_deref().unwrap_or(contents);
+
+    let parsed =
+        serde_yaml::from_str(contents).structured(InvalidInputError::ParseHostConfigurationFile {
+            path: path.as_ref().display().to_string(),
+        });
+
+    if parsed.is_err() {
+        match serde_yaml::from_str::<serde_yaml::Value>(contents) {
+            Ok(value) => {
+                // Detect a few common issues with the Host Configuration
+                if value.get("hostConfiguration").is_some() {
+                    return Err(TridentError::new(InvalidInputError::OldStyleConfiguration));
+                } else if value.get("allowedOperations").is_some() {
+                    return Err(TridentError::new(
+                        InvalidInputError::AllowedOperationsInHostConfiguration,
+                    ));
+                }
+            }
+            Err(_) => return parsed.message("Host Configuration is not valid YAML"),
+        }
+    }
+    parsed
+}
+
+/// Returns the Host Configuration with the fragments it includes merged into it, or `None` if it
+/// does not include any.
+fn merge_includes(
+    contents: &str,
+    path: &Path,
+    load: &mut impl FnMut(&Path) -> Result<String, Error>,
+) -> Result<Option<String>, Error> {
+    // Invalid YAML is reported when parsing the Host Configuration.
+    let Ok(value) = serde_yaml::from_str::<Value>(contents) else {
+        return Ok(None);
+    };
+    if value.get(compose::INCLUDE_KEY).is_none() {
+        return Ok(None);
+    }
+
+    let value = compose::resolve_includes(value, path, load)?;
+    serde_yaml::to_string(&value)
+        .map(Some)
+        .context("Failed to serialize merged Host Configuration")
+}
+
+/// Returns the Host Configuration migrated to the current API version, or `None` if it is already
+/// at the current version. Host Configurations without an API version are parsed as the current
+/// version.
+fn upgrade_api_version(contents: &str) -> Result<Option<String>, Error> {
+    // Invalid YAML is reported when parsing the Host Configuration.
+    let Ok(mut value) = serde_yaml::from_str::<Value>(contents) else {
+        return Ok(None);
+    };
+    if value.get(migrate::API_VERSION_KEY).is_none() || !migrate::migrate(&mut value)? {
+        return Ok(None);
+    }
+
+    serde_yaml::to_string(&value)
+        .map(Some)
+        .context("Failed to serialize migrated Host Configuration")
+}
+
+/// Migrates the Host Configuration file at `path` to the current API version, and writes the
+/// result to `output_path`, or prints it.
+///
+/// Only the file itself is migrated, not the fragments it includes.
+pub fn migrate_host_config_file(
+    path: impl AsRef<Path>,
+    output_path: &Option<PathBuf>,
+) -> Result<(), TridentError> {
+    let contents =
+        fs::read_to_string(path.as_ref()).structured(InvalidInputError::ReadInputFile {
+            path: path.as_ref().display().to_string(),
+        })?;
+    let mut value = serde_yaml::from_str::<Value>(&contents).structured(
+        InvalidInputError::ParseHostConfigurationFile {
+            path: path.as_ref().display().to_string(),
+        },
+    )?;
+
+    let migrated =
+        migrate::migrate(&mut value).structured(InvalidInputError::MigrateHostConfiguration {
+            path: path.as_ref().display().to_string(),
+        })?;
+    if !migrated {
+        info!("Host Configuration is already at the current API version");
+    }
+
+    let yaml = serde_yaml::to_string(&value).structured(InternalError::SerializeError)?;
+    match output_path {
+        Some(output_path) => {
+            info!("Writing to {:?}", &output_path);
+            fs::write(output_path, yaml).structured(InvalidInputError::WriteOutputFile {
+                path: output_path.display().to_string(),
+            })?
+        }
+        None => {
+            print!("{yaml}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates the Host Configuration file at `path`. When `print` is set, also prints the
+/// resolved Host Configuration, with its fragments merged and its variables substituted.
+///
+/// When `offline_root` is set, also validates the Host Configuration offline, looking up the
+/// local files it references under `offline_root`.
+pub fn validate_host_config_file(
+    path: impl AsRef<Path>,
+    print: bool,
+    offline_root: Option<&Path>,
+) -> Result<(), TridentError> {
+    info!(
+        "Validating Host Configuration file: {}",
+        path.as_ref().display()
+    );
+
+    let contents =
+        fs::read_to_string(path.as_ref()).structured(InvalidInputError::ReadInputFile {
+            path: path.as_ref().display().to_string(),
+        })?;
+
+    let parsed = parse_host_config(&contents, path.as_ref())
+        .message("Failed to parse Host Configuration")?;
+
+    validate_host_config(&parsed)?;
+    if let Some(root) = offline_root {
+        offline::validate(&parsed, root).message("Offline validation failed")?;
+        info!("Host Configuration passed offline validation");
+    }
+
+    if print {
+        print!(
+            "{}",
+            serde_yaml::to_string(&parsed).structured(InternalError::SerializeHostStatus)?
+        );
+    }
+    Ok(())
+}
+
+fn validate_host_config(hc: &HostConfiguration) -> Result<(), TridentError> {
+    hc.validate()
+        .map_err(|e| TridentError::new(InvalidInputError::from(e)))
+        .message("Host Configuration is invalid")?;
+
+    info!("Host Configuration is valid");
+    debug!(
+        "Parsed contents:\n{}",
+        serde_yaml::to_string(hc).structured(InternalError::SerializeHostStatus)?
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_validate_embedded_host_configuration() {
+        let func_test_trident_config = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../../tests/functional_tests/trident-setup.yaml");
+        validate_host_config_file(func_test_trident_config, false, None)
+            .expect("Failed to validate functional test Host Configuration");
+    }
+
+    #[test]
+    fn test_parse_host_config_with_includes() {
+        let host_config = parse_host_config_with(
+            "include: [base.yaml]\nos:\n  hostname: edge-1\n",
+            "/configs/edge.yaml",
+            |path| {
+                assert_eq!(path, Path::new("/configs/base.yaml"));
+                Ok("os:\n  hostname: base\n  users:\n    - name: admin\n".into())
+            },
+        )
+        .unwrap();
+        assert_eq!(host_config.os.hostname.as_deref(), Some("edge-1"));
+        assert_eq!(host_config.os.users[0].name, "admin");
+    }
+
+    #[test]
+    fn test_parse_host_config_api_version() {
+        let host_config =
+            parse_host_config("apiVersion: 1\nos:\n  hostname: edge-1\n", "hc.yaml").unwrap();
+        assert_eq!(host_config.api_version, Some(1));
+        assert_eq!(host_config.os.hostname.as_deref(), Some("edge-1"));
+
+        parse_host_config("apiVersion: 2\nos:\n  hostname: edge-1\n", "hc.yaml").unwrap_err();
+    }
+
+    #[test]
+    fn test_migrate_host_config_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("hc.yaml");
+        let output_path = temp_dir.path().join("migrated.yaml");
+        fs::write(&path, "hostConfiguration:\n  os:\n    hostname: edge-1\n").unwrap();
+
+        migrate_host_config_file(&path, &Some(output_path.clone())).unwrap();
+        let contents = fs::read_to_string(&output_path).unwrap();
+        assert_eq!(contents, "apiVersion: 1\nos:\n  hostname: edge-1\n");
+        parse_host_config(&contents, &output_path).unwrap();
+    }
+}
@@ -0,0 +1,460 @@
+//! Offline validation of Host Configurations.
+//!
+//! Offline validation performs the checks of dynamic validation that do not depend on the state
+//! of the host, without requiring root permissions or touching any device, so that Host
+//! Configurations can be validated in CI pipelines. Local files referenced by the Host
+//! Configuration are looked up under a root directory, e.g. a tree of the files that will be
+//! present on the servicing OS.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use url::Url;
+
+use osutils::path::join_relative;
+use trident_api::{
+    config::{
+        ExtensionSource, HostConfiguration, HostConfigurationDynamicValidationError, ScriptSource,
+        Secret, SecretSource, SelinuxMode,
+    },
+    error::{InvalidInputError, TridentError},
+};
+
+/// URL schemes Trident can fetch files from.
+const SUPPORTED_URL_SCHEMES: &[&str] = &["file", "http", "https", "oci"];
+
+/// Validates `host_config` offline, looking up the local files it references under `root`.
+/// `host_config` must have passed static validation.
+pub(super) fn validate(host_config: &HostConfiguration, root: &Path) -> Result<(), TridentError> {
+    validate_disks(host_config)
+        .and_then(|()| validate_files(host_config, root))
+        .and_then(|()| validate_urls(host_config, root))
+        .and_then(|()| validate_selinux(host_config))
+        .map_err(|e| TridentError::new(InvalidInputError::from(e)))
+}
+
+/// Returns the path under `root` of `path`, a path of the servicing OS. Relative paths are
+/// relative to the working directory.
+fn under_root(root: &Path, path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        join_relative(root, path)
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Checks that the disks refer to distinct devices under `/dev`. Device paths are compared as
+/// written, as links such as `/dev/disk/by-path/*` can only be resolved on the host.
+fn validate_disks(
+    host_config: &HostConfiguration,
+) -> Result<(), HostConfigurationDynamicValidationError> {
+    let mut devices = HashMap::<&Path, &str>::new();
+    for disk in &host_config.storage.disks {
+        if !disk.device.starts_with("/dev") {
+            return Err(
+                HostConfigurationDynamicValidationError::InvalidDiskBlockDevicePath {
+                    name: disk.id.clone(),
+                    device: disk.device.display().to_string(),
+                },
+            );
+        }
+
+        if let Some(other) = devices.insert(&disk.device, &disk.id) {
+            return Err(
+                HostConfigurationDynamicValidationError::DiskDefinitionsReferToSameDevice {
+                    disk1: other.into(),
+                    disk2: disk.id.clone(),
+                    device: disk.device.display().to_string(),
+                },
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that the local files referenced by the Host Configuration exist under `root`. Secrets
+/// read from environment variables are not checked, as the environment of Trident is unknown.
+fn validate_files(
+    host_config: &HostConfiguration,
+    root: &Path,
+) -> Result<(), HostConfigurationDynamicValidationError> {
+    let scripts = &host_config.scripts;
+    for script in scripts
+        .pre_servicing
+        .iter()
+        .chain(&scripts.post_provision)
+        .chain(&scripts.post_configure)
+    {
+        if let ScriptSource::Path(path) = &script.source {
+            if !under_root(root, path).is_file() {
+                return Err(HostConfigurationDynamicValidationError::InvalidScriptPath {
+                    name: script.name.clone(),
+                    path: path.display().to_string(),
+                });
+            }
+        }
+    }
+
+    let os = &host_config.os;
+    for file in &os.additional_files {
+        if let Some(path) = &file.source {
+            if !under_root(root, path).is_file() {
+                return Err(
+                    HostConfigurationDynamicValidationError::LoadAdditionalFile {
+                        name: file.destination.display().to_string(),
+                        path: path.display().to_string(),
+                    },
+                );
+            }
+        }
+    }
+
+    for path in &os.selinux.policy_modules {
+        if !under_root(root, path).is_file() {
+            return Err(
+                HostConfigurationDynamicValidationError::LoadSelinuxPolicyModule {
+                    path: path.display().to_string(),
+                },
+            );
+        }
+    }
+
+    if let Some(repository) = &os.package_repository {
+        if !under_root(root, repository).is_dir() {
+            return Err(
+                HostConfigurationDynamicValidationError::PackageRepositoryNotFound {
+                    path: repository.display().to_string(),
+                },
+            );
+        }
+    }
+
+    for container_image in &os.container_images {
+        if let Some(archive) = &container_image.archive {
+            if !under_root(root, archive).is_file() {
+                return Err(
+                    HostConfigurationDynamicValidationError::ContainerImageArchiveNotFound {
+                        image: container_image.image.clone(),
+                        path: archive.display().to_string(),
+                    },
+                );
+            }
+        }
+    }
+
+    let secrets = os
+        .users
+        .iter()
+        .filter_map(|user| user.password.secret())
+        .chain(os.container_images.iter().filter_map(|container_image| {
+            container_image
+                .registry_credentials
+                .as_ref()
+                .map(|credentials| &credentials.password)
+        }));
+    for secret in secrets {
+        if let Secret::Reference { value_from } = secret {
+            if let SecretSource::File(path) = value_from {
+                if !under_root(root, path).is_file() {
+                    return Err(HostConfigurationDynamicValidationError::ResolveSecret {
+                        reference: value_from.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that the URLs of the Host Configuration have a supported scheme, and that the local
+/// files they reference exist under `root`.
+fn validate_urls(
+    host_config: &HostConfiguration,
+    root: &Path,
+) -> Result<(), HostConfigurationDynamicValidationError> {
+    let os = &host_config.os;
+    if let Some(image) = &host_config.image {
+        validate_url(&image.url, false, root)?;
+    }
+
+    for extension in os.sysexts.iter().chain(&os.confexts) {
+        validate_url(
+            &extension.url,
+            extension.source == ExtensionSource::Directory,
+            root,
+        )?;
+        for delta in &extension.deltas {
+            validate_url(&delta.url, false, root)?;
+        }
+    }
+
+    for (_, file) in os
+        .cloud_init
+        .iter()
+        .flat_map(|cloud_init| cloud_init.files())
+    {
+        if let Some(url) = &file.url {
+            validate_url(url, false, root)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that `url` has a supported scheme and, for `file://` URLs, that the file, or the
+/// directory if `directory` is set, exists under `root`.
+fn validate_url(
+    url: &Url,
+    directory: bool,
+    root: &Path,
+) -> Result<(), HostConfigurationDynamicValidationError> {
+    if !SUPPORTED_URL_SCHEMES.contains(&url.scheme()) {
+        return Err(
+            HostConfigurationDynamicValidationError::UnsupportedUrlScheme {
+                url: url.to_string(),
+                scheme: url.scheme().into(),
+            },
+        );
+    }
+
+    if url.scheme() == "file" {
+        let path = under_root(root, Path::new(url.path()));
+        let found = if directory {
+            path.is_dir()
+        } else {
+            path.is_file()
+        };
+        if !found {
+            return Err(HostConfigurationDynamicValidationError::UrlSourceNotFound {
+                url: url.to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the SELinux mode against the options it is incompatible with. Dynamic validation also
+/// checks the mode resulting from the OS image, which is unknown offline.
+fn validate_selinux(
+    host_config: &HostConfiguration,
+) -> Result<(), HostConfigurationDynamicValidationError> {
+    let os = &host_config.os;
+    if os.selinux.mode == Some(SelinuxMode::Enforcing)
+        && !(os.sysexts.is_empty() && os.confexts.is_empty())
+    {
+        return Err(
+            HostConfigurationDynamicValidationError::ExtensionImagesAndSelinuxUnsupported {
+                selinux_mode: SelinuxMode::Enforcing.to_string(),
+            },
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use indoc::indoc;
+
+    use trident_api::config::Disk;
+
+    fn host_config(contents: &str) -> HostConfiguration {
+        serde_yaml::from_str(contents).unwrap()
+    }
+
+    #[test]
+    fn test_under_root() {
+        assert_eq!(
+            under_root(Path::new("/"), Path::new("/etc/hosts")),
+            Path::new("/etc/hosts")
+        );
+        assert_eq!(
+            under_root(Path::new("/tmp/root"), Path::new("/etc/hosts")),
+            Path::new("/tmp/root/etc/hosts")
+        );
+        assert_eq!(
+            under_root(Path::new("/tmp/root"), Path::new("scripts/setup.sh")),
+            Path::new("scripts/setup.sh")
+        );
+    }
+
+    #[test]
+    fn test_validate_disks() {
+        let disks = |devices: &[&str]| {
+            let mut host_config = HostConfiguration::default();
+            host_config.storage.disks = devices
+                .iter()
+                .enumerate()
+                .map(|(index, device)| Disk {
+                    id: format!("disk{index}"),
+                    device: device.into(),
+                    ..Default::default()
+                })
+                .collect();
+            host_config
+        };
+
+        validate_disks(&disks(&[
+            "/dev/sda",
+            "/dev/disk/by-path/pci-0000:00:1f.2-ata-2",
+        ]))
+        .unwrap();
+
+        assert!(matches!(
+            validate_disks(&disks(&["/tmp/disk.img"])),
+            Err(HostConfigurationDynamicValidationError::InvalidDiskBlockDevicePath { .. })
+        ));
+
+        assert_eq!(
+            validate_disks(&disks(&["/dev/sda", "/dev/sda"])),
+            Err(
+                HostConfigurationDynamicValidationError::DiskDefinitionsReferToSameDevice {
+                    disk1: "disk0".into(),
+                    disk2: "disk1".into(),
+                    device: "/dev/sda".into(),
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_validate_files() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("opt/scripts")).unwrap();
+        fs::write(root.path().join("opt/scripts/setup.sh"), "true").unwrap();
+        fs::create_dir_all(root.path().join("run/secrets")).unwrap();
+        fs::write(root.path().join("run/secrets/password"), "hunter2").unwrap();
+
+        let valid = host_config(indoc! {"
+            scripts:
+              postConfigure:
+                - name: setup
+                  runOn: [all]
+                  path: /opt/scripts/setup.sh
+            os:
+              containerImages:
+                - image: registry.example.com/app:1.0
+                  registryCredentials:
+                    username: admin
+                    password:
+                      valueFrom:
+                        file: /run/secrets/password
+        "});
+        validate_files(&valid, root.path()).unwrap();
+
+        // Files are looked up under the root only.
+        assert_eq!(
+            validate_files(&valid, Path::new("/nonexistent")),
+            Err(HostConfigurationDynamicValidationError::InvalidScriptPath {
+                name: "setup".into(),
+                path: "/opt/scripts/setup.sh".into(),
+            })
+        );
+
+        assert_eq!(
+            validate_files(
+                &host_config(indoc! {"
+                    os:
+                      additionalFiles:
+                        - destination: /etc/motd
+                          source: /opt/files/motd
+                "}),
+                root.path()
+            ),
+            Err(
+                HostConfigurationDynamicValidationError::LoadAdditionalFile {
+                    name: "/etc/motd".into(),
+                    path: "/opt/files/motd".into(),
+                }
+            )
+        );
+
+        assert_eq!(
+            validate_files(
+                &host_config(indoc! {"
+                    os:
+                      packageRepository: /opt/scripts/setup.sh
+                "}),
+                root.path()
+            ),
+            Err(
+                HostConfigurationDynamicValidationError::PackageRepositoryNotFound {
+                    path: "/opt/scripts/setup.sh".into(),
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_validate_url() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("images/sysext")).unwrap();
+        fs::write(root.path().join("images/os.cosi"), "").unwrap();
+
+        for url in [
+            "https://example.com/os.cosi",
+            "oci://registry.example.com/os:1.0",
+            "file:///images/os.cosi",
+        ] {
+            validate_url(&url.parse().unwrap(), false, root.path()).unwrap();
+        }
+        validate_url(&"file:///images/sysext".parse().unwrap(), true, root.path()).unwrap();
+
+        assert_eq!(
+            validate_url(
+                &"ftp://example.com/os.cosi".parse().unwrap(),
+                false,
+                root.path()
+            ),
+            Err(
+                HostConfigurationDynamicValidationError::UnsupportedUrlScheme {
+                    url: "ftp://example.com/os.cosi".into(),
+                    scheme: "ftp".into(),
+                }
+            )
+        );
+        for (url, directory) in [
+            ("file:///images/missing.cosi", false),
+            ("file:///images/sysext", false),
+            ("file:///images/os.cosi", true),
+        ] {
+            assert!(matches!(
+                validate_url(&url.parse().unwrap(), directory, root.path()),
+                Err(HostConfigurationDynamicValidationError::UrlSourceNotFound { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_validate_selinux() {
+        validate_selinux(&host_config(indoc! {"
+            os:
+              selinux:
+                mode: enforcing
+        "}))
+        .unwrap();
+
+        let sysext = format!(
+            "os:\n  sysexts:\n    - url: https://example.com/debug.raw\n      sha384: {}\n",
+            "a".repeat(96)
+        );
+        validate_selinux(&host_config(&sysext)).unwrap();
+        assert!(matches!(
+            validate_selinux(&host_config(&format!(
+                "{sysext}  selinux:\n    mode: enforcing\n"
+            ))),
+            Err(
+                HostConfigurationDynamicValidationError::ExtensionImagesAndSelinuxUnsupported { .. }
+            )
+        ));
+    }
+}
@@ -40,18 +40,33 @@ pub enum HostConfigurationStaticValidationError {
     #[error("Datastore path '{datastore_path}' must be in a known volume")]
     DatastorePathNotInKnownVolume { datastore_path: String },
 
+    #[error("Container image '{image}' is listed more than once")]
+    DuplicateContainerImage { image: String },
+
     #[error("Host Configuration contains extension images with duplicate hashes '{hash}', but extension images must be unique")]
     DuplicateExtensionImage { hash: String },
 
     #[error("Host Configuration contains extension images with duplicate paths '{path}', but extension images must have unique paths")]
     DuplicateExtensionImagePath { path: String },
 
+    #[error("Host Configuration configures multiple network interfaces with the same name '{name}' in 'os.network', but interface names must be unique")]
+    DuplicateNetworkInterfaceName { name: String },
+
+    #[error("Host Configuration pins multiple network interfaces to the same MAC address '{mac_address}', but MAC addresses must be unique")]
+    DuplicatePinnedInterfaceMacAddress { mac_address: String },
+
+    #[error("Host Configuration pins multiple network interfaces with the same name '{name}', but interface names must be unique")]
+    DuplicatePinnedInterfaceName { name: String },
+
     #[error("Host Configuration contains duplicate usernames '{username}', but usernames must be unique")]
     DuplicateUsernames { username: String },
 
     #[error("Underlying device of encrypted volume '{encrypted_volume}' must be a partition or a software RAID array")]
     EncryptedVolumeNotPartitionOrRaid { encrypted_volume: String },
 
+    #[error("Encryption has both 'recoveryKey' and 'recoveryKeyUrl', but only one of them can be specified")]
+    EncryptionRecoveryKeyConfiguredTwice,
+
     #[error("Failed to find expected mount point '{mount_point_path}'")]
     ExpectedMountPointNotFound { mount_point_path: String },
 
@@ -69,9 +84,18 @@ pub enum HostConfigurationStaticValidationError {
     )]
     ExtensionImageInvalidFileExtension { path: String },
 
+    #[error("Extension image '{url}' cannot be merged into the initrd: {explanation}")]
+    ExtensionImageInvalidInitrdScope { url: String, explanation: String },
+
+    #[error("Extension image '{url}' has an invalid source: {explanation}")]
+    ExtensionImageInvalidSource { url: String, explanation: String },
+
     #[error("Extension image path '{path}' must be on a known A/B volume")]
     ExtensionImageNotOnABVolume { path: String },
 
+    #[error("Host Configuration has both 'os.hostname' and 'os.identity.hostname', but only one of them can be specified")]
+    HostnameConfiguredTwice,
+
     #[error(
         "The Host Configuration is using both an image and partition images, these APIs are \
         mutually exclusive"
@@ -81,18 +105,108 @@ pub enum HostConfigurationStaticValidationError {
     #[error(transparent)]
     InvalidStorageGraph(#[from] StorageGraphBuildError),
 
+    #[error("Threshold of clock steps must not be zero")]
+    InvalidClockStepThreshold,
+
+    #[error("cloud-init file '{name}' is invalid: {explanation}")]
+    InvalidCloudInitFile { name: String, explanation: String },
+
+    #[error("Container image '{image}' is invalid: {explanation}")]
+    InvalidContainerImage { image: String, explanation: String },
+
+    #[error("DNS server '{server}' is invalid, must be an IP address")]
+    InvalidDnsServer { server: String },
+
+    #[error("ESP mirror '{device_id}' is invalid: {explanation}")]
+    InvalidEspMirror {
+        device_id: String,
+        explanation: String,
+    },
+
     #[error("Encryption recovery key URL '{url}' has invalid scheme '{scheme}'")]
     InvalidEncryptionRecoveryKeyUrlScheme { url: String, scheme: String },
 
+    #[error("TLS configuration of the {endpoint} endpoint is invalid: {explanation}")]
+    InvalidEndpointTls {
+        endpoint: String,
+        explanation: String,
+    },
+
+    #[error("Health monitoring interval must be at least 1 second")]
+    InvalidHealthMonitorInterval,
+
+    #[error("Hostname template '{template}' is invalid: {explanation}")]
+    InvalidHostnameTemplate {
+        template: String,
+        explanation: String,
+    },
+
     #[error("Interface name '{name}' is invalid")]
     InvalidInterfaceName { name: String },
 
+    #[error("Kernel command line parameter '{parameter}' is invalid")]
+    InvalidKernelParameter { parameter: String },
+
+    #[error("MAC address '{mac_address}' is invalid, must be in the format 'xx:xx:xx:xx:xx:xx'")]
+    InvalidMacAddress { mac_address: String },
+
+    #[error("Migration pattern '{pattern}' is invalid: {explanation}")]
+    InvalidMigrationPattern {
+        pattern: String,
+        explanation: String,
+    },
+
     #[error("Netplan version '{version}' is invalid, must always be '2'")]
     InvalidNetplanVersion { version: u8 },
 
+    #[error("Network address '{address}' is invalid, must be an IP address with a prefix length, e.g. '192.168.0.10/24'")]
+    InvalidNetworkAddress { address: String },
+
+    #[error("NTP source '{address}' is invalid, must be a hostname or an IP address")]
+    InvalidNtpSource { address: String },
+
+    #[error("Package name '{name}' is invalid")]
+    InvalidPackageName { name: String },
+
+    #[error("Package repository path '{path}' must be absolute")]
+    InvalidPackageRepositoryPath { path: String },
+
+    #[error("Maximum number of failed boots of the rollback policy must be at least 1")]
+    InvalidRollbackMaxFailedBoots,
+
+    #[error("Route to '{to}' via '{via}' is invalid, must go to 'default' or to an IP address with a prefix length, via an IP address")]
+    InvalidRoute { to: String, via: String },
+
+    #[error("Secret reference to {reference} is invalid: {explanation}")]
+    InvalidSecretReference {
+        reference: String,
+        explanation: String,
+    },
+
+    #[error("SELinux policy module '{path}' is invalid, must be a '.pp' or '.cil' file")]
+    InvalidSelinuxPolicyModule { path: String },
+
+    #[error("Timezone '{timezone}' is invalid, must be a name of the tz database, e.g. 'Etc/UTC'")]
+    InvalidTimezone { timezone: String },
+
     #[error("Invalid URL provided '{url}': '{explanation}'")]
     InvalidSourceUrl { url: String, explanation: String },
 
+    #[error("Sudo rule '{rule}' of user '{username}' is invalid, must be a single non-empty line")]
+    InvalidSudoRule { username: String, rule: String },
+
+    #[error("Swap file '{path}' is invalid: {explanation}")]
+    InvalidSwapFile { path: String, explanation: String },
+
+    #[error("VLAN '{name}' has invalid ID {id}, must be between 1 and 4094")]
+    InvalidVlanId { name: String, id: u16 },
+
+    #[error("Size of the zram device must not be zero")]
+    InvalidZramSize,
+
+    #[error("Kernel command line parameter '{parameter}' is both added and removed")]
+    KernelParameterAddedAndRemoved { parameter: String },
+
     #[error("Mount point '{mount_point_path}' must be backed by A/B update volume pair")]
     MountPointNotBackedByAbUpdateVolumePair { mount_point_path: String },
 
@@ -102,6 +216,12 @@ pub enum HostConfigurationStaticValidationError {
     #[error("Mount point '{mount_point_path}' must be backed by an image")]
     MountPointNotBackedByImage { mount_point_path: String },
 
+    #[error("Host Configuration has both 'os.network' and 'os.netplan', but only one of them can be specified")]
+    NetworkAndNetplanConfigured,
+
+    #[error("'os.time' must have at least one NTP server or fallback pool")]
+    NoNtpSources,
+
     #[error(
         "Directory '{VAR_TMP_PATH}' must be on a read-write volume, but is on a read-only \
         volume mounted at '{mount_point_path}'"
@@ -122,6 +242,14 @@ pub enum HostConfigurationStaticValidationError {
         mount_point_path: String,
     },
 
+    #[error(
+        "Packages cannot be installed when the root or /usr filesystem is a verity filesystem"
+    )]
+    PackagesOnVerityFilesystem,
+
+    #[error("Kernel command line parameter '{parameter}' is set by Trident and cannot be removed")]
+    ProtectedKernelParameterRemoved { parameter: String },
+
     #[error("Path '{path}' must be absolute")]
     PathNotAbsolute { path: String },
 
@@ -134,6 +262,15 @@ pub enum HostConfigurationStaticValidationError {
     #[error("Cannot request self-upgrade of Trident when a read-only verity filesystem is mounted at '/'")]
     SelfUpgradeOnReadOnlyRootVerityFs,
 
+    #[error("VLAN '{name}' is on '{link}', which is not an interface or a bond of 'os.network'")]
+    UnknownVlanLink { name: String, link: String },
+
+    #[error("Health check '{name}' checks disk '{disk_id}', which is not in 'storage.disks'")]
+    UnknownDiskHealthCheckDisk { name: String, disk_id: String },
+
+    #[error("API version {version} of the Host Configuration is not supported, the latest supported version is {supported}")]
+    UnsupportedApiVersion { version: u32, supported: u32 },
+
     #[error(
         "List of PCRs in encryption config contains unsupported PCRs '{pcrs}'.\n
         Only PCRs 4, 7, and 11 are supported"
@@ -176,9 +313,23 @@ impl From<HostConfigurationStaticValidationError> for TridentError {
 #[derive(thiserror::Error, Serialize, Deserialize, Debug, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub enum HostConfigurationDynamicValidationError {
+    #[error(
+        "A/B volume pair '{ab_volume_id}' has volumes on disks of different classes: volume A \
+        is on {volume_a_classes}, while volume B is on {volume_b_classes}. Place both volumes of \
+        an A/B volume pair on disks of the same class"
+    )]
+    AbVolumeDiskClassMismatch {
+        ab_volume_id: String,
+        volume_a_classes: String,
+        volume_b_classes: String,
+    },
+
     #[error("Cannot adopt partitions on disk '{disk_id}', as it does not use GPT partitioning")]
     AdoptPartitionsOnNonGptPartitionedDisk { disk_id: String },
 
+    #[error("Archive '{path}' of container image '{image}' is not a file")]
+    ContainerImageArchiveNotFound { image: String, path: String },
+
     #[error("Datastore path was changed from '{current}' to '{new}', but can only be changed during clean install")]
     DatastorePathChanged { current: String, new: String },
 
@@ -209,12 +360,48 @@ pub enum HostConfigurationDynamicValidationError {
     #[error("Encryption recovery key file '{key_file}' must be a regular file")]
     EncryptionKeyNotRegularFile { key_file: String },
 
+    #[error("Encryption recovery key must not be empty")]
+    EncryptionRecoveryKeyEmpty,
+
+    #[error("Extension images require each other: {cycle}")]
+    ExtensionImageRequirementCycle { cycle: String },
+
+    #[error(
+        "Extension image '{name}' requires extension image '{required}', which is not part of the \
+        Host Configuration"
+    )]
+    ExtensionImageRequirementMissing { name: String, required: String },
+
+    #[error(
+        "Extension image '{name}' requires extension image '{required}', but its file name \
+        '{file_name}' sorts before '{required_file_name}'. systemd-sysext overlays extension \
+        images in the order of their file names, so required images must sort first"
+    )]
+    ExtensionImageRequirementOrder {
+        name: String,
+        file_name: String,
+        required: String,
+        required_file_name: String,
+    },
+
     #[error(
         "SELinux is not supported with sysexts and confexts. SELinux is set to '{selinux_mode}', \
         but should be set to 'disabled'"
     )]
     ExtensionImagesAndSelinuxUnsupported { selinux_mode: String },
 
+    #[error(
+        "Extension images cannot be merged into the initrd when it is regenerated with mkinitrd, \
+        which does not support including them"
+    )]
+    ExtensionImagesInInitrdWithMkinitrd,
+
+    #[error(
+        "Extension images cannot be merged into the initrd of UKIs, as Trident does not \
+        regenerate it"
+    )]
+    ExtensionImagesInInitrdWithUki,
+
     #[error(
         "Since update image is a grub image, list of PCRs in encryption config contains invalid PCRs: '{pcrs}'. \
         Only PCR 7 is valid for grub images"
@@ -230,6 +417,9 @@ pub enum HostConfigurationDynamicValidationError {
     #[error("Cannot update image on standalone block device '{device_id}' during A/B update")]
     ImageUpdateOnStandaloneBlockDevice { device_id: String },
 
+    #[error("Extension image '{name}' is not compatible with the target OS: {explanation}")]
+    IncompatibleExtensionImage { name: String, explanation: String },
+
     #[error("Encryption recovery key file has invalid path '{path}'")]
     InvalidEncryptionKeyFilePath { path: String },
 
@@ -245,9 +435,21 @@ pub enum HostConfigurationDynamicValidationError {
     #[error("Failed to load additional file '{name}' to be placed at '{path}'")]
     LoadAdditionalFile { name: String, path: String },
 
+    #[error("Failed to load cloud-init file '{name}' from '{url}'")]
+    LoadCloudInitFile { name: String, url: String },
+
     #[error("Failed to load script '{name}' at '{path}'")]
     LoadScript { name: String, path: String },
 
+    #[error("Failed to load SELinux policy module at '{path}'")]
+    LoadSelinuxPolicyModule { path: String },
+
+    #[error("Package repository '{path}' is not a directory")]
+    PackageRepositoryNotFound { path: String },
+
+    #[error("Failed to read secret from {reference}")]
+    ResolveSecret { reference: String },
+
     #[error(
         "SELinux is not supported with root-verity and grub. SELinux is set to '{selinux_mode}', \
         but should be set to 'disabled'"
@@ -268,4 +470,10 @@ pub enum HostConfigurationDynamicValidationError {
 
     #[error("Cannot modify storage configuration during update")]
     StorageConfigurationChanged,
+
+    #[error("URL '{url}' has unsupported scheme '{scheme}'")]
+    UnsupportedUrlScheme { url: String, scheme: String },
+
+    #[error("Local file referenced by URL '{url}' does not exist")]
+    UrlSourceNotFound { url: String },
 }
@@ -1,13 +1,41 @@
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use log::warn;
 use serde::{Deserialize, Serialize};
+use url::Url;
 
 #[cfg(feature = "schemars")]
 use schemars::JsonSchema;
 
+use crate::config::host::error::HostConfigurationStaticValidationError;
 use crate::config::host::scripts::{Script, ServicingTypeSelection};
+use crate::is_default;
+use crate::primitives::bytes::ByteCount;
 use crate::status::ServicingType;
+use crate::BlockDeviceId;
 
 const DEFAULT_SYSTEMD_CHECK_TIMEOUT_SECONDS: usize = 30;
 
+const DEFAULT_CHECK_RETRY_INTERVAL_SECONDS: u64 = 5;
+
+const DEFAULT_HTTP_CHECK_TIMEOUT_SECONDS: u64 = 10;
+
+const DEFAULT_HTTP_CHECK_EXPECTED_STATUS: u16 = 200;
+
+const DEFAULT_TCP_CHECK_TIMEOUT_SECONDS: u64 = 10;
+
+const DEFAULT_CONTAINER_CHECK_TIMEOUT_SECONDS: u64 = 120;
+
+const DEFAULT_NETWORK_CHECK_TIMEOUT_SECONDS: u64 = 10;
+
+const DEFAULT_TIME_SYNC_CHECK_TIMEOUT_SECONDS: u64 = 120;
+
+const DEFAULT_BOOT_COMPLETE_TIMEOUT_SECONDS: u64 = 300;
+
+const DEFAULT_HEALTH_MONITOR_INTERVAL_SECONDS: u64 = 60;
+/// Default number of boots of the target OS in which the health checks may fail.
+const DEFAULT_ROLLBACK_MAX_FAILED_BOOTS: u32 = 1;
+
 /// Configuration for the host OS health.
 #[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
@@ -26,6 +54,226 @@ pub struct Health {
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub checks: Vec<Check>,
+
+    /// Environment variables set for every health check script, so that a generic script can be
+    /// reused across environments. Variables set by a script take precedence. In addition,
+    /// `OS_VERSION` is set to the `VERSION_ID` of the target OS and, after an A/B update,
+    /// `PREVIOUS_OS_VERSION` to the one of the OS it was updated from.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub environment_variables: HashMap<String, String>,
+
+    /// Time all checks must complete in, in seconds, counted from the start of the checks. The
+    /// checks run concurrently; the timeouts of each check are cut short to the deadline, and
+    /// checks are not retried past it. Checks have no common deadline when not specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<u64>,
+
+    /// Hardware validation run before a clean install. If any of the enabled tests fail, Trident
+    /// refuses to provision the host and leaves its disks untouched.
+    ///
+    /// Intended for factory provisioning lines, where faulty machines should be set aside before
+    /// they are imaged. Burn-in is skipped when not specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burn_in: Option<BurnIn>,
+
+    /// Whether to wait for `boot-complete.target` to be reached before committing the target OS,
+    /// so that the commit is gated on the boot assessment of systemd: the units ordered before
+    /// `boot-complete.target` must succeed. `boot-complete.target` is not awaited when not
+    /// specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub boot_complete: Option<BootComplete>,
+
+    /// Whether to keep running the checks periodically for a grace window once the target OS of
+    /// an A/B update was committed. If a check fails during the window, the host is rolled back
+    /// to the previous OS. The checks only run once, before the commit, when not specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<HealthMonitor>,
+
+    /// How Trident reacts when the health checks of an A/B update fail. The target OS is rolled
+    /// back after the first boot in which the health checks fail when not specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollback_policy: Option<RollbackPolicy>,
+}
+
+/// Grace window during which the health checks keep running after the target OS is committed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct HealthMonitor {
+    /// Length of the grace window, in seconds, counted from the commit of the target OS.
+    pub window_seconds: u64,
+
+    /// Time between two runs of the checks, in seconds. The default is 60 seconds.
+    #[serde(
+        default = "HealthMonitor::default_interval",
+        skip_serializing_if = "HealthMonitor::is_default_interval"
+    )]
+    pub interval_seconds: u64,
+}
+
+impl HealthMonitor {
+    fn default_interval() -> u64 {
+        DEFAULT_HEALTH_MONITOR_INTERVAL_SECONDS
+    }
+
+    fn is_default_interval(interval_seconds: &u64) -> bool {
+        *interval_seconds == DEFAULT_HEALTH_MONITOR_INTERVAL_SECONDS
+    }
+}
+
+/// Reaction of Trident to the failure of the health checks of an A/B update.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct RollbackPolicy {
+    /// Number of boots of the target OS in which the health checks may fail before Trident stops
+    /// retrying. Until then, Trident reboots into the target OS and runs the health checks again.
+    /// The default is 1, i.e. no retry.
+    #[serde(
+        default = "RollbackPolicy::default_max_failed_boots",
+        skip_serializing_if = "RollbackPolicy::is_default_max_failed_boots"
+    )]
+    pub max_failed_boots: u32,
+
+    /// What a failure of a script check leads to. The default is `retry-next-boot`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub script_failure: ScriptFailureAction,
+
+    /// Whether to power the host off instead of rebooting it into the previous OS once Trident
+    /// stops retrying. The rollback completes when the host is powered on again. The default is
+    /// false.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub power_off: bool,
+}
+
+impl Default for RollbackPolicy {
+    fn default() -> Self {
+        Self {
+            max_failed_boots: DEFAULT_ROLLBACK_MAX_FAILED_BOOTS,
+            script_failure: ScriptFailureAction::default(),
+            power_off: false,
+        }
+    }
+}
+
+impl RollbackPolicy {
+    fn default_max_failed_boots() -> u32 {
+        DEFAULT_ROLLBACK_MAX_FAILED_BOOTS
+    }
+
+    fn is_default_max_failed_boots(max_failed_boots: &u32) -> bool {
+        *max_failed_boots == DEFAULT_ROLLBACK_MAX_FAILED_BOOTS
+    }
+}
+
+/// What a failure of a script check of an A/B update leads to.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum ScriptFailureAction {
+    /// # Retry Next Boot
+    ///
+    /// The failure counts as a failed boot, like the failure of any other check: Trident reboots
+    /// into the target OS to run the health checks again, unless `maxFailedBoots` is reached.
+    #[default]
+    RetryNextBoot,
+
+    /// # Rollback
+    ///
+    /// The failure stops the retries right away, regardless of `maxFailedBoots`.
+    Rollback,
+}
+
+/// Wait for the boot of the target OS to be complete, as assessed by systemd.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct BootComplete {
+    /// Time to wait for `boot-complete.target`, in seconds. The default is 300 seconds.
+    #[serde(
+        default = "BootComplete::default_timeout",
+        skip_serializing_if = "BootComplete::is_default_timeout"
+    )]
+    pub timeout_seconds: u64,
+
+    /// Whether to also require `systemd-boot-check-no-failures.service` to succeed, i.e. no unit
+    /// to have failed by the time the boot is complete. The default is false.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub check_no_failures: bool,
+}
+
+impl Default for BootComplete {
+    fn default() -> Self {
+        Self {
+            timeout_seconds: DEFAULT_BOOT_COMPLETE_TIMEOUT_SECONDS,
+            check_no_failures: false,
+        }
+    }
+}
+
+impl BootComplete {
+    fn default_timeout() -> u64 {
+        DEFAULT_BOOT_COMPLETE_TIMEOUT_SECONDS
+    }
+
+    fn is_default_timeout(timeout_seconds: &u64) -> bool {
+        *timeout_seconds == DEFAULT_BOOT_COMPLETE_TIMEOUT_SECONDS
+    }
+}
+
+impl Health {
+    pub(crate) fn validate(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        // Health checks only run when committing a clean install or an A/B update, so a check
+        // selecting none of them is never run.
+        for check in &self.checks {
+            if !check.should_run(ServicingType::CleanInstall)
+                && !check.should_run(ServicingType::AbUpdate)
+            {
+                warn!(
+                    "Health check '{}' never runs, as its 'runOn' selects neither 'clean-install' \
+                    nor 'ab-update'",
+                    check.name()
+                );
+            }
+        }
+
+        if self
+            .monitor
+            .as_ref()
+            .is_some_and(|monitor| monitor.interval_seconds == 0)
+        {
+            return Err(HostConfigurationStaticValidationError::InvalidHealthMonitorInterval);
+        }
+        if self
+            .rollback_policy
+            .as_ref()
+            .is_some_and(|policy| policy.max_failed_boots == 0)
+        {
+            return Err(HostConfigurationStaticValidationError::InvalidRollbackMaxFailedBoots);
+        }
+        Ok(())
+    }
+}
+
+/// Quick hardware validation run before a clean install.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct BurnIn {
+    /// Amount of memory to test with `memtester`, in MiB. Memory is not tested when not
+    /// specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_test_mib: Option<u64>,
+
+    /// Whether to run a SMART short self-test on every disk in `storage.disks`. Only disks that
+    /// SMART reports as failing fail the burn-in; disks that do not support SMART, such as virtio
+    /// disks, are skipped. Requires `smartctl`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub smart_test: bool,
+
+    /// Names of the network interfaces that must have a link, e.g. `eth0`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub link_interfaces: Vec<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -39,6 +287,8 @@ pub enum Check {
     /// on the target OS (where the target OS '/' is mounted as '/host').
     ///
     /// Scripts that are configured with a path source must exist in the target OS.
+    /// The path may be a glob pattern, e.g. `/usr/lib/trident/checks.d/*`, to run
+    /// every script of the target OS that it matches as a separate check.
     Script(Script),
 
     /// # SystemdCheck
@@ -47,9 +297,75 @@ pub enum Check {
     /// by `systemctl status` returning success. The success or failure of this
     /// check will define the health of the target OS.
     SystemdCheck(SystemdCheck),
+
+    /// # HttpCheck
+    ///
+    /// HTTP request that needs to be answered with the expected status, e.g. to verify that an
+    /// API is serving. The request is sent from where Trident is running.
+    HttpCheck(HttpCheck),
+
+    /// # TcpCheck
+    ///
+    /// TCP port that needs to accept connections, e.g. to verify that a daemon is listening. The
+    /// connection is made from where Trident is running.
+    TcpCheck(TcpCheck),
+
+    /// # KernelCheck
+    ///
+    /// Kernel that must not be tainted and must not have logged errors, e.g. to catch driver
+    /// regressions.
+    KernelCheck(KernelCheck),
+
+    /// # DiskUsageCheck
+    ///
+    /// Filesystems that need to have enough free space, e.g. to catch updates whose larger image
+    /// or migration scripts fill up `/var`.
+    DiskUsageCheck(DiskUsageCheck),
+
+    /// # ContainerCheck
+    ///
+    /// Containers or pods that need to be running and ready, e.g. to verify that the workloads of
+    /// the host came back up after the update.
+    ContainerCheck(ContainerCheck),
+
+    /// # NetworkCheck
+    ///
+    /// Network stack that needs to work, e.g. to catch a new image that breaks the configuration
+    /// of the network: the default gateway needs to be reachable, names need to resolve and,
+    /// optionally, an HTTPS endpoint needs to answer.
+    NetworkCheck(NetworkCheck),
+
+    /// # DiskHealthCheck
+    ///
+    /// Disks that need to be healthy according to SMART, e.g. to avoid committing an update onto
+    /// dying media. Requires `smartctl`.
+    DiskHealthCheck(DiskHealthCheck),
+
+    /// # TimeSyncCheck
+    ///
+    /// System clock that needs to be synchronized by a time synchronization daemon, such as
+    /// `chronyd` or `systemd-timesyncd`, e.g. because TLS-based workloads fail when the time is
+    /// wrong.
+    TimeSyncCheck(TimeSyncCheck),
 }
 
 impl Check {
+    /// Returns the name of the check.
+    pub fn name(&self) -> &str {
+        match self {
+            Check::Script(script) => &script.name,
+            Check::SystemdCheck(systemd_check) => &systemd_check.name,
+            Check::HttpCheck(http_check) => &http_check.name,
+            Check::TcpCheck(tcp_check) => &tcp_check.name,
+            Check::KernelCheck(kernel_check) => &kernel_check.name,
+            Check::DiskUsageCheck(disk_usage_check) => &disk_usage_check.name,
+            Check::ContainerCheck(container_check) => &container_check.name,
+            Check::NetworkCheck(network_check) => &network_check.name,
+            Check::DiskHealthCheck(disk_health_check) => &disk_health_check.name,
+            Check::TimeSyncCheck(time_sync_check) => &time_sync_check.name,
+        }
+    }
+
     /// Returns true if the check should be executed on this servicing type.
     pub fn should_run(&self, servicing_type: ServicingType) -> bool {
         match servicing_type {
@@ -59,8 +375,115 @@ impl Check {
         match self {
             Check::Script(script) => script.should_run(servicing_type),
             Check::SystemdCheck(systemd_check) => systemd_check.should_run(servicing_type),
+            Check::HttpCheck(http_check) => http_check.should_run(servicing_type),
+            Check::TcpCheck(tcp_check) => tcp_check.should_run(servicing_type),
+            Check::KernelCheck(kernel_check) => kernel_check.should_run(servicing_type),
+            Check::DiskUsageCheck(disk_usage_check) => disk_usage_check.should_run(servicing_type),
+            Check::ContainerCheck(container_check) => container_check.should_run(servicing_type),
+            Check::NetworkCheck(network_check) => network_check.should_run(servicing_type),
+            Check::DiskHealthCheck(disk_health_check) => {
+                disk_health_check.should_run(servicing_type)
+            }
+            Check::TimeSyncCheck(time_sync_check) => time_sync_check.should_run(servicing_type),
         }
     }
+
+    /// Returns whether a failure of the check fails the health checks.
+    pub fn severity(&self) -> CheckSeverity {
+        match self {
+            Check::Script(script) => script.severity,
+            Check::SystemdCheck(systemd_check) => systemd_check.severity,
+            Check::HttpCheck(http_check) => http_check.severity,
+            Check::TcpCheck(tcp_check) => tcp_check.severity,
+            Check::KernelCheck(kernel_check) => kernel_check.severity,
+            Check::DiskUsageCheck(disk_usage_check) => disk_usage_check.severity,
+            Check::ContainerCheck(container_check) => container_check.severity,
+            Check::NetworkCheck(network_check) => network_check.severity,
+            Check::DiskHealthCheck(disk_health_check) => disk_health_check.severity,
+            Check::TimeSyncCheck(time_sync_check) => time_sync_check.severity,
+        }
+    }
+
+    /// Returns how the check is retried when it fails.
+    pub fn retry(&self) -> &CheckRetry {
+        match self {
+            Check::Script(script) => &script.retry,
+            Check::SystemdCheck(systemd_check) => &systemd_check.retry,
+            Check::HttpCheck(http_check) => &http_check.retry,
+            Check::TcpCheck(tcp_check) => &tcp_check.retry,
+            Check::KernelCheck(kernel_check) => &kernel_check.retry,
+            Check::DiskUsageCheck(disk_usage_check) => &disk_usage_check.retry,
+            Check::ContainerCheck(container_check) => &container_check.retry,
+            Check::NetworkCheck(network_check) => &network_check.retry,
+            Check::DiskHealthCheck(disk_health_check) => &disk_health_check.retry,
+            Check::TimeSyncCheck(time_sync_check) => &time_sync_check.retry,
+        }
+    }
+}
+
+/// Retries of a health check that fails, e.g. because a service needs a few seconds after boot
+/// to become healthy.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct CheckRetry {
+    /// Number of times the check is re-attempted before it is declared failed. By default, checks
+    /// are not retried.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub retries: u32,
+
+    /// Time to wait before the first retry, in seconds. The wait doubles before each further
+    /// retry. Defaults to 5 seconds.
+    #[serde(
+        default = "CheckRetry::default_interval",
+        skip_serializing_if = "CheckRetry::is_default_interval"
+    )]
+    pub interval_seconds: u64,
+}
+
+impl Default for CheckRetry {
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            interval_seconds: DEFAULT_CHECK_RETRY_INTERVAL_SECONDS,
+        }
+    }
+}
+
+impl CheckRetry {
+    /// Returns the time to wait before retry number `retry`, counting from 0.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        Duration::from_secs(
+            self.interval_seconds
+                .saturating_mul(2u64.saturating_pow(retry)),
+        )
+    }
+
+    fn default_interval() -> u64 {
+        DEFAULT_CHECK_RETRY_INTERVAL_SECONDS
+    }
+
+    fn is_default_interval(interval_seconds: &u64) -> bool {
+        *interval_seconds == DEFAULT_CHECK_RETRY_INTERVAL_SECONDS
+    }
+}
+
+/// Consequence of the failure of a health check.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum CheckSeverity {
+    /// # Fail
+    ///
+    /// The failure of the check fails the health checks, which rolls back an A/B update.
+    #[default]
+    Fail,
+
+    /// # Warn
+    ///
+    /// The failure of the check is recorded as a warning in the Host Status, without failing the
+    /// health checks. Intended for non-critical checks, e.g. of a telemetry agent.
+    Warn,
 }
 
 /// Custom serialization and deserialization for Check enum.
@@ -73,7 +496,52 @@ impl<'de> serde::Deserialize<'de> for Check {
     {
         let value = serde_yaml::Value::deserialize(deserializer)?;
         if let Some(mapping) = value.as_mapping() {
-            if mapping.contains_key(serde_yaml::Value::String("systemdServices".to_string())) {
+            if mapping.contains_key(serde_yaml::Value::String("httpCheck".to_string())) {
+                // Deserialize as HttpCheck
+                let http_check: HttpCheck =
+                    serde_yaml::from_value(value).map_err(serde::de::Error::custom)?;
+                return Ok(Check::HttpCheck(http_check));
+            } else if mapping.contains_key(serde_yaml::Value::String("tcpCheck".to_string())) {
+                // Deserialize as TcpCheck
+                let tcp_check: TcpCheck =
+                    serde_yaml::from_value(value).map_err(serde::de::Error::custom)?;
+                return Ok(Check::TcpCheck(tcp_check));
+            } else if mapping.contains_key(serde_yaml::Value::String("kernelCheck".to_string())) {
+                // Deserialize as KernelCheck
+                let kernel_check: KernelCheck =
+                    serde_yaml::from_value(value).map_err(serde::de::Error::custom)?;
+                return Ok(Check::KernelCheck(kernel_check));
+            } else if mapping.contains_key(serde_yaml::Value::String("diskUsageCheck".to_string()))
+            {
+                // Deserialize as DiskUsageCheck
+                let disk_usage_check: DiskUsageCheck =
+                    serde_yaml::from_value(value).map_err(serde::de::Error::custom)?;
+                return Ok(Check::DiskUsageCheck(disk_usage_check));
+            } else if mapping.contains_key(serde_yaml::Value::String("containerCheck".to_string()))
+            {
+                // Deserialize as ContainerCheck
+                let container_check: ContainerCheck =
+                    serde_yaml::from_value(value).map_err(serde::de::Error::custom)?;
+                return Ok(Check::ContainerCheck(container_check));
+            } else if mapping.contains_key(serde_yaml::Value::String("networkCheck".to_string())) {
+                // Deserialize as NetworkCheck
+                let network_check: NetworkCheck =
+                    serde_yaml::from_value(value).map_err(serde::de::Error::custom)?;
+                return Ok(Check::NetworkCheck(network_check));
+            } else if mapping.contains_key(serde_yaml::Value::String("diskHealthCheck".to_string()))
+            {
+                // Deserialize as DiskHealthCheck
+                let disk_health_check: DiskHealthCheck =
+                    serde_yaml::from_value(value).map_err(serde::de::Error::custom)?;
+                return Ok(Check::DiskHealthCheck(disk_health_check));
+            } else if mapping.contains_key(serde_yaml::Value::String("timeSyncCheck".to_string())) {
+                // Deserialize as TimeSyncCheck
+                let time_sync_check: TimeSyncCheck =
+                    serde_yaml::from_value(value).map_err(serde::de::Error::custom)?;
+                return Ok(Check::TimeSyncCheck(time_sync_check));
+            } else if mapping.contains_key(serde_yaml::Value::String("systemdServices".to_string()))
+                || mapping.contains_key(serde_yaml::Value::String("systemRunning".to_string()))
+            {
                 // Deserialize as SystemdCheck
                 let systemd_check: SystemdCheck =
                     serde_yaml::from_value(value).map_err(serde::de::Error::custom)?;
@@ -98,6 +566,14 @@ impl serde::Serialize for Check {
         match self {
             Check::Script(script) => script.serialize(serializer),
             Check::SystemdCheck(systemd_check) => systemd_check.serialize(serializer),
+            Check::HttpCheck(http_check) => http_check.serialize(serializer),
+            Check::TcpCheck(tcp_check) => tcp_check.serialize(serializer),
+            Check::KernelCheck(kernel_check) => kernel_check.serialize(serializer),
+            Check::DiskUsageCheck(disk_usage_check) => disk_usage_check.serialize(serializer),
+            Check::ContainerCheck(container_check) => container_check.serialize(serializer),
+            Check::NetworkCheck(network_check) => network_check.serialize(serializer),
+            Check::DiskHealthCheck(disk_health_check) => disk_health_check.serialize(serializer),
+            Check::TimeSyncCheck(time_sync_check) => time_sync_check.serialize(serializer),
         }
     }
 }
@@ -112,10 +588,19 @@ pub struct SystemdCheck {
     #[serde(default, skip_serializing_if = "String::is_empty")]
     pub name: String,
 
-    /// List of systemd services that need to be in successful state.
+    /// List of systemd services that need to be in successful state. Names can be glob
+    /// patterns, e.g. `myapp-*.service`, which are expanded against the units known to systemd
+    /// when the check runs. A pattern that matches no unit fails the check.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub systemd_services: Vec<String>,
 
+    /// Whether the whole system also needs to be running, as reported by
+    /// `systemctl is-system-running`: the boot is complete, no unit has failed and no job is
+    /// queued. Can be used without `systemdServices` as a single gate on the health of the
+    /// system. The default is false.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub system_running: bool,
+
     /// Timeout for the systemd check, in seconds. If the service is found to be
     /// in an unsuccessful state, it will be requeried every 100ms until the timeout is reached.
     /// If the timeout is reached and the service is still unsuccessful, an error is returned.
@@ -124,33 +609,30 @@ pub struct SystemdCheck {
     #[serde(default = "SystemdCheck::default_timeout")]
     pub timeout_seconds: usize,
 
+    /// Retries of the check when the services are still unsuccessful after the timeout.
+    #[serde(flatten)]
+    pub retry: CheckRetry,
+
+    /// Whether a failure of the check fails the health checks. The default is `fail`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub severity: CheckSeverity,
+
     /// List of servicing types that the check should run on.
     /// Valid servicing types are CleanInstall and AbUpdate, if
     /// All is specified, the check will run for both CleanInstall
-    /// and AbUpdate.
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// and AbUpdate. Can also be set as `servicingTypes`.
+    #[serde(
+        default,
+        alias = "servicingTypes",
+        skip_serializing_if = "Vec::is_empty"
+    )]
     pub run_on: Vec<ServicingTypeSelection>,
 }
 
 impl SystemdCheck {
     /// Returns true if servicing type is enabled for this script.
     pub fn should_run(&self, servicing_type: ServicingType) -> bool {
-        if self.run_on.contains(&ServicingTypeSelection::All) {
-            return true;
-        }
-        match servicing_type {
-            ServicingType::CleanInstall => {
-                self.run_on.contains(&ServicingTypeSelection::CleanInstall)
-            }
-            ServicingType::NormalUpdate => {
-                self.run_on.contains(&ServicingTypeSelection::NormalUpdate)
-            }
-            ServicingType::AbUpdate => self.run_on.contains(&ServicingTypeSelection::AbUpdate),
-            ServicingType::UpdateAndReboot => self
-                .run_on
-                .contains(&ServicingTypeSelection::UpdateAndReboot),
-            _ => false,
-        }
+        ServicingTypeSelection::any_selects(&self.run_on, servicing_type)
     }
 
     /// Default timeout for systemd check.
@@ -159,6 +641,574 @@ impl SystemdCheck {
     }
 }
 
+/// A check that sends an HTTP request from the host and verifies the response.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct HttpCheck {
+    /// Name of the check.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+
+    /// HTTP request to send and the response it expects.
+    #[serde(rename = "httpCheck")]
+    pub request: HttpCheckRequest,
+
+    /// Retries of the check when the request fails or the response is unexpected.
+    #[serde(flatten)]
+    pub retry: CheckRetry,
+
+    /// Whether a failure of the check fails the health checks. The default is `fail`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub severity: CheckSeverity,
+
+    /// List of servicing types that the check should run on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub run_on: Vec<ServicingTypeSelection>,
+}
+
+impl HttpCheck {
+    /// Returns true if servicing type is enabled for this check.
+    pub fn should_run(&self, servicing_type: ServicingType) -> bool {
+        ServicingTypeSelection::any_selects(&self.run_on, servicing_type)
+    }
+}
+
+/// HTTP request of an HTTP check.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct HttpCheckRequest {
+    /// URL to send the request to. Only `http` and `https` URLs are supported.
+    pub url: Url,
+
+    /// HTTP method of the request. The default is `GET`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub method: HttpMethod,
+
+    /// Status code the response must have. The default is 200.
+    #[serde(
+        default = "HttpCheckRequest::default_expected_status",
+        skip_serializing_if = "HttpCheckRequest::is_default_expected_status"
+    )]
+    pub expected_status: u16,
+
+    /// Text the body of the response must contain, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_contains: Option<String>,
+
+    /// Whether to accept any TLS certificate from the server, e.g. a self-signed certificate of a
+    /// local API. The default is false.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub skip_tls_verify: bool,
+
+    /// Timeout for the request, in seconds. The default is 10 seconds.
+    #[serde(
+        default = "HttpCheckRequest::default_timeout",
+        skip_serializing_if = "HttpCheckRequest::is_default_timeout"
+    )]
+    pub timeout_seconds: u64,
+}
+
+impl HttpCheckRequest {
+    fn default_expected_status() -> u16 {
+        DEFAULT_HTTP_CHECK_EXPECTED_STATUS
+    }
+
+    fn is_default_expected_status(expected_status: &u16) -> bool {
+        *expected_status == DEFAULT_HTTP_CHECK_EXPECTED_STATUS
+    }
+
+    fn default_timeout() -> u64 {
+        DEFAULT_HTTP_CHECK_TIMEOUT_SECONDS
+    }
+
+    fn is_default_timeout(timeout_seconds: &u64) -> bool {
+        *timeout_seconds == DEFAULT_HTTP_CHECK_TIMEOUT_SECONDS
+    }
+}
+
+/// HTTP method of the request of an HTTP check.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum HttpMethod {
+    #[default]
+    Get,
+    Head,
+    Post,
+}
+
+/// A check that opens a TCP connection from the host.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct TcpCheck {
+    /// Name of the check.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+
+    /// TCP port that needs to accept connections.
+    #[serde(rename = "tcpCheck")]
+    pub target: TcpCheckTarget,
+
+    /// Retries of the check when the port does not accept connections.
+    #[serde(flatten)]
+    pub retry: CheckRetry,
+
+    /// Whether a failure of the check fails the health checks. The default is `fail`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub severity: CheckSeverity,
+
+    /// List of servicing types that the check should run on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub run_on: Vec<ServicingTypeSelection>,
+}
+
+impl TcpCheck {
+    /// Returns true if servicing type is enabled for this check.
+    pub fn should_run(&self, servicing_type: ServicingType) -> bool {
+        ServicingTypeSelection::any_selects(&self.run_on, servicing_type)
+    }
+}
+
+/// TCP port of a TCP check.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct TcpCheckTarget {
+    /// Host name or IP address to connect to. When the host name resolves to several addresses,
+    /// connecting to any of them is enough.
+    pub host: String,
+
+    /// Port to connect to.
+    pub port: u16,
+
+    /// Timeout for the connection, in seconds. The default is 10 seconds.
+    #[serde(
+        default = "TcpCheckTarget::default_timeout",
+        skip_serializing_if = "TcpCheckTarget::is_default_timeout"
+    )]
+    pub timeout_seconds: u64,
+}
+
+impl TcpCheckTarget {
+    fn default_timeout() -> u64 {
+        DEFAULT_TCP_CHECK_TIMEOUT_SECONDS
+    }
+
+    fn is_default_timeout(timeout_seconds: &u64) -> bool {
+        *timeout_seconds == DEFAULT_TCP_CHECK_TIMEOUT_SECONDS
+    }
+}
+
+/// A check of the state of the running kernel.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct KernelCheck {
+    /// Name of the check.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+
+    /// Conditions the kernel must meet.
+    #[serde(rename = "kernelCheck")]
+    pub criteria: KernelCheckCriteria,
+
+    /// Retries of the check when the kernel does not meet the conditions.
+    #[serde(flatten)]
+    pub retry: CheckRetry,
+
+    /// Whether a failure of the check fails the health checks. The default is `fail`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub severity: CheckSeverity,
+
+    /// List of servicing types that the check should run on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub run_on: Vec<ServicingTypeSelection>,
+}
+
+impl KernelCheck {
+    /// Returns true if servicing type is enabled for this check.
+    pub fn should_run(&self, servicing_type: ServicingType) -> bool {
+        ServicingTypeSelection::any_selects(&self.run_on, servicing_type)
+    }
+}
+
+/// Conditions of a kernel check.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct KernelCheckCriteria {
+    /// Taint flags the kernel must not have, as a bit mask of the values of
+    /// `/proc/sys/kernel/tainted`. For example, 4096 (bit 12) fails the check when an
+    /// out-of-tree module is loaded. By default, taint flags are not checked.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub taint_mask: u64,
+
+    /// Regular expressions that no message of the kernel log may match, e.g. `Call Trace` or
+    /// `I/O error`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dmesg_patterns: Vec<String>,
+}
+
+/// A check of the free space of filesystems of the host.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct DiskUsageCheck {
+    /// Name of the check.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+
+    /// Free space the filesystems need to have.
+    #[serde(rename = "diskUsageCheck")]
+    pub criteria: DiskUsageCheckCriteria,
+
+    /// Retries of the check when a filesystem does not have enough free space.
+    #[serde(flatten)]
+    pub retry: CheckRetry,
+
+    /// Whether a failure of the check fails the health checks. The default is `fail`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub severity: CheckSeverity,
+
+    /// List of servicing types that the check should run on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub run_on: Vec<ServicingTypeSelection>,
+}
+
+impl DiskUsageCheck {
+    /// Returns true if servicing type is enabled for this check.
+    pub fn should_run(&self, servicing_type: ServicingType) -> bool {
+        ServicingTypeSelection::any_selects(&self.run_on, servicing_type)
+    }
+}
+
+/// Conditions of a disk usage check.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct DiskUsageCheckCriteria {
+    /// Mount points whose filesystems need to have enough free space.
+    pub mount_points: Vec<MountPointFreeSpace>,
+}
+
+/// Free space the filesystem of a mount point needs to have. When both a percentage and a number
+/// of bytes are set, the filesystem needs to have both.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct MountPointFreeSpace {
+    /// Path of the mount point, or of any directory on its filesystem.
+    pub path: PathBuf,
+
+    /// Minimum free space, as a percentage of the size of the filesystem, from 0 to 100.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_free_percent: Option<u8>,
+
+    /// Minimum free space, in bytes. Accepts a number of bytes, or a number followed by a `K`,
+    /// `M`, `G`, or `T` suffix, e.g. `2G`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_free_bytes: Option<ByteCount>,
+}
+
+/// A check of the containers or pods running on the host.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ContainerCheck {
+    /// Name of the check.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+
+    /// Containers or pods that need to be running and ready.
+    #[serde(rename = "containerCheck")]
+    pub workloads: ContainerCheckWorkloads,
+
+    /// Retries of the check when a workload is not ready within the timeout.
+    #[serde(flatten)]
+    pub retry: CheckRetry,
+
+    /// Whether a failure of the check fails the health checks. The default is `fail`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub severity: CheckSeverity,
+
+    /// List of servicing types that the check should run on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub run_on: Vec<ServicingTypeSelection>,
+}
+
+impl ContainerCheck {
+    /// Returns true if servicing type is enabled for this check.
+    pub fn should_run(&self, servicing_type: ServicingType) -> bool {
+        ServicingTypeSelection::any_selects(&self.run_on, servicing_type)
+    }
+}
+
+/// Workloads of a container check.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ContainerCheckWorkloads {
+    /// Container runtime that runs the workloads. The default is `podman`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub runtime: ContainerRuntime,
+
+    /// Socket of the container runtime, e.g. `unix:///run/containerd/containerd.sock`. The
+    /// default socket of the runtime is used when not specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+
+    /// Names of the workloads: containers for `podman`, pods for `cri`.
+    pub names: Vec<String>,
+
+    /// Time the workloads have to become ready, in seconds. The default is 120 seconds.
+    #[serde(
+        default = "ContainerCheckWorkloads::default_timeout",
+        skip_serializing_if = "ContainerCheckWorkloads::is_default_timeout"
+    )]
+    pub timeout_seconds: u64,
+}
+
+impl ContainerCheckWorkloads {
+    fn default_timeout() -> u64 {
+        DEFAULT_CONTAINER_CHECK_TIMEOUT_SECONDS
+    }
+
+    fn is_default_timeout(timeout_seconds: &u64) -> bool {
+        *timeout_seconds == DEFAULT_CONTAINER_CHECK_TIMEOUT_SECONDS
+    }
+}
+
+/// Container runtime of a container check.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum ContainerRuntime {
+    /// # Podman
+    ///
+    /// Containers managed by Podman, queried with `podman`. A container is ready when it is
+    /// running and, if it has a health check, healthy.
+    #[default]
+    Podman,
+
+    /// # CRI
+    ///
+    /// Pods managed by a Kubernetes container runtime, such as containerd or CRI-O, queried with
+    /// `crictl`. A pod is ready when its sandbox is ready.
+    Cri,
+}
+
+/// A check of the network stack of the host.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct NetworkCheck {
+    /// Name of the check.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+
+    /// Conditions the network stack must meet.
+    #[serde(rename = "networkCheck")]
+    pub criteria: NetworkCheckCriteria,
+
+    /// Retries of the check when the network stack does not meet the conditions.
+    #[serde(flatten)]
+    pub retry: CheckRetry,
+
+    /// Whether a failure of the check fails the health checks. The default is `fail`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub severity: CheckSeverity,
+
+    /// List of servicing types that the check should run on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub run_on: Vec<ServicingTypeSelection>,
+}
+
+impl NetworkCheck {
+    /// Returns true if servicing type is enabled for this check.
+    pub fn should_run(&self, servicing_type: ServicingType) -> bool {
+        ServicingTypeSelection::any_selects(&self.run_on, servicing_type)
+    }
+}
+
+/// Conditions of a network check.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct NetworkCheckCriteria {
+    /// Whether a default gateway needs to be configured and to answer `ping`. When there are
+    /// several default gateways, e.g. for IPv4 and IPv6, one answering is enough. The default is
+    /// true.
+    #[serde(
+        default = "NetworkCheckCriteria::default_check_gateway",
+        skip_serializing_if = "NetworkCheckCriteria::is_default_check_gateway"
+    )]
+    pub default_gateway: bool,
+
+    /// Names that need to resolve to at least one address, e.g. `mcr.microsoft.com`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dns_names: Vec<String>,
+
+    /// HTTPS URL that needs to answer, with any status, to verify that the host can reach the
+    /// outside over TLS. No request is sent when not specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub https_probe: Option<Url>,
+
+    /// Timeout of each of the conditions, in seconds. The default is 10 seconds.
+    #[serde(
+        default = "NetworkCheckCriteria::default_timeout",
+        skip_serializing_if = "NetworkCheckCriteria::is_default_timeout"
+    )]
+    pub timeout_seconds: u64,
+}
+
+impl Default for NetworkCheckCriteria {
+    fn default() -> Self {
+        Self {
+            default_gateway: true,
+            dns_names: Vec::new(),
+            https_probe: None,
+            timeout_seconds: DEFAULT_NETWORK_CHECK_TIMEOUT_SECONDS,
+        }
+    }
+}
+
+impl NetworkCheckCriteria {
+    fn default_check_gateway() -> bool {
+        true
+    }
+
+    fn is_default_check_gateway(default_gateway: &bool) -> bool {
+        *default_gateway
+    }
+
+    fn default_timeout() -> u64 {
+        DEFAULT_NETWORK_CHECK_TIMEOUT_SECONDS
+    }
+
+    fn is_default_timeout(timeout_seconds: &u64) -> bool {
+        *timeout_seconds == DEFAULT_NETWORK_CHECK_TIMEOUT_SECONDS
+    }
+}
+
+/// A check of the health of the disks of the host, as reported by SMART.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct DiskHealthCheck {
+    /// Name of the check.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+
+    /// Disks that need to be healthy.
+    #[serde(rename = "diskHealthCheck")]
+    pub criteria: DiskHealthCheckCriteria,
+
+    /// Retries of the check when a disk is not healthy.
+    #[serde(flatten)]
+    pub retry: CheckRetry,
+
+    /// Whether a failure of the check fails the health checks. The default is `fail`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub severity: CheckSeverity,
+
+    /// List of servicing types that the check should run on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub run_on: Vec<ServicingTypeSelection>,
+}
+
+impl DiskHealthCheck {
+    /// Returns true if servicing type is enabled for this check.
+    pub fn should_run(&self, servicing_type: ServicingType) -> bool {
+        ServicingTypeSelection::any_selects(&self.run_on, servicing_type)
+    }
+}
+
+/// Disks of a disk health check. A disk is healthy when its overall SMART self-assessment passes
+/// and none of its prefail attributes is at or below its threshold. Disks that do not support
+/// SMART, such as most virtual disks, are skipped.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct DiskHealthCheckCriteria {
+    /// IDs of the disks of `storage.disks` to check. All disks of `storage.disks` are checked
+    /// when not specified.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disks: Vec<BlockDeviceId>,
+
+    /// Whether to also fail when attributes of a disk were at or below their threshold in the
+    /// past, though they no longer are. The default is false.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub fail_on_past_failures: bool,
+}
+
+/// A check of the synchronization of the system clock.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct TimeSyncCheck {
+    /// Name of the check.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+
+    /// Synchronization of the system clock to wait for.
+    #[serde(rename = "timeSyncCheck")]
+    pub criteria: TimeSyncCheckCriteria,
+
+    /// Retries of the check when the clock is not synchronized within the timeout.
+    #[serde(flatten)]
+    pub retry: CheckRetry,
+
+    /// Whether a failure of the check fails the health checks. The default is `fail`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub severity: CheckSeverity,
+
+    /// List of servicing types that the check should run on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub run_on: Vec<ServicingTypeSelection>,
+}
+
+impl TimeSyncCheck {
+    /// Returns true if servicing type is enabled for this check.
+    pub fn should_run(&self, servicing_type: ServicingType) -> bool {
+        ServicingTypeSelection::any_selects(&self.run_on, servicing_type)
+    }
+}
+
+/// Synchronization of the system clock of a time synchronization check. The clock is
+/// synchronized once the time synchronization daemon reports it so to the kernel.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct TimeSyncCheckCriteria {
+    /// Time the clock has to become synchronized, in seconds. The default is 120 seconds.
+    #[serde(
+        default = "TimeSyncCheckCriteria::default_timeout",
+        skip_serializing_if = "TimeSyncCheckCriteria::is_default_timeout"
+    )]
+    pub timeout_seconds: u64,
+}
+
+impl Default for TimeSyncCheckCriteria {
+    fn default() -> Self {
+        Self {
+            timeout_seconds: DEFAULT_TIME_SYNC_CHECK_TIMEOUT_SECONDS,
+        }
+    }
+}
+
+impl TimeSyncCheckCriteria {
+    fn default_timeout() -> u64 {
+        DEFAULT_TIME_SYNC_CHECK_TIMEOUT_SECONDS
+    }
+
+    fn is_default_timeout(timeout_seconds: &u64) -> bool {
+        *timeout_seconds == DEFAULT_TIME_SYNC_CHECK_TIMEOUT_SECONDS
+    }
+}
+
 /// Unit Test for should_run
 #[cfg(test)]
 mod tests {
@@ -179,10 +1229,91 @@ mod tests {
                 Check::SystemdCheck(SystemdCheck {
                     name: "test-systemd-check".into(),
                     systemd_services: vec!["test-service".into()],
+                    system_running: false,
                     timeout_seconds: 60,
                     run_on: vec![run_on_servicing_type.clone()],
+                    retry: CheckRetry {
+                        retries: 3,
+                        interval_seconds: 10,
+                    },
+                    severity: CheckSeverity::Warn,
+                }),
+                Check::HttpCheck(HttpCheck {
+                    name: "test-http-check".into(),
+                    request: HttpCheckRequest {
+                        url: Url::parse("https://localhost:8443/healthz").unwrap(),
+                        method: HttpMethod::Head,
+                        expected_status: 204,
+                        body_contains: None,
+                        skip_tls_verify: true,
+                        timeout_seconds: 10,
+                    },
+                    retry: Default::default(),
+                    severity: Default::default(),
+                    run_on: vec![run_on_servicing_type.clone()],
+                }),
+                Check::KernelCheck(KernelCheck {
+                    name: "test-kernel-check".into(),
+                    criteria: KernelCheckCriteria {
+                        taint_mask: 1 << 12,
+                        dmesg_patterns: vec!["Call Trace".into()],
+                    },
+                    retry: Default::default(),
+                    severity: Default::default(),
+                    run_on: vec![run_on_servicing_type.clone()],
+                }),
+                Check::DiskUsageCheck(DiskUsageCheck {
+                    name: "test-disk-usage-check".into(),
+                    criteria: DiskUsageCheckCriteria {
+                        mount_points: vec![MountPointFreeSpace {
+                            path: "/var".into(),
+                            min_free_percent: Some(10),
+                            min_free_bytes: Some(ByteCount(2 << 30)),
+                        }],
+                    },
+                    retry: Default::default(),
+                    severity: Default::default(),
+                    run_on: vec![run_on_servicing_type.clone()],
+                }),
+                Check::TcpCheck(TcpCheck {
+                    name: "test-tcp-check".into(),
+                    target: TcpCheckTarget {
+                        host: "localhost".into(),
+                        port: 22,
+                        timeout_seconds: 5,
+                    },
+                    retry: Default::default(),
+                    severity: Default::default(),
+                    run_on: vec![run_on_servicing_type.clone()],
+                }),
+                Check::TimeSyncCheck(TimeSyncCheck {
+                    name: "test-time-sync-check".into(),
+                    criteria: TimeSyncCheckCriteria {
+                        timeout_seconds: 60,
+                    },
+                    retry: Default::default(),
+                    severity: Default::default(),
+                    run_on: vec![run_on_servicing_type.clone()],
+                }),
+                Check::NetworkCheck(NetworkCheck {
+                    name: "test-network-check".into(),
+                    criteria: NetworkCheckCriteria {
+                        default_gateway: false,
+                        dns_names: vec!["example.com".into()],
+                        https_probe: Some(Url::parse("https://example.com").unwrap()),
+                        timeout_seconds: 5,
+                    },
+                    retry: Default::default(),
+                    severity: Default::default(),
+                    run_on: vec![run_on_servicing_type.clone()],
                 }),
             ],
+            environment_variables: HashMap::new(),
+            timeout_seconds: None,
+            burn_in: None,
+            boot_complete: None,
+            monitor: None,
+            rollback_policy: None,
         }
     }
 
@@ -229,4 +1360,228 @@ mod tests {
         let deserialized: Vec<Check> = serde_yaml::from_str(&serialized).unwrap();
         assert_eq!(health.checks, deserialized);
     }
+
+    #[test]
+    fn test_check_retry() {
+        let check: Check =
+            serde_yaml::from_str("name: check\ncontent: exit 0\nretries: 2\nintervalSeconds: 3\n")
+                .unwrap();
+        let retry = check.retry();
+        assert_eq!(retry.retries, 2);
+        assert_eq!(retry.backoff(0), Duration::from_secs(3));
+        assert_eq!(retry.backoff(2), Duration::from_secs(12));
+
+        // Checks are not retried by default.
+        let check: Check = serde_yaml::from_str("systemdServices: [sshd]\n").unwrap();
+        assert_eq!(*check.retry(), CheckRetry::default());
+        assert!(!serde_yaml::to_string(&check).unwrap().contains("interval"));
+    }
+
+    #[test]
+    fn test_http_check_defaults() {
+        let check: Check = serde_yaml::from_str(
+            "name: api\nhttpCheck:\n  url: http://localhost:8080/healthz\n  bodyContains: ok\n",
+        )
+        .unwrap();
+        let Check::HttpCheck(http_check) = &check else {
+            panic!("Expected an HTTP check, got {check:?}");
+        };
+        assert_eq!(
+            http_check.request,
+            HttpCheckRequest {
+                url: Url::parse("http://localhost:8080/healthz").unwrap(),
+                method: HttpMethod::Get,
+                expected_status: 200,
+                body_contains: Some("ok".into()),
+                skip_tls_verify: false,
+                timeout_seconds: 10,
+            }
+        );
+        assert_eq!(
+            serde_yaml::to_string(&check).unwrap(),
+            "name: api\nhttpCheck:\n  url: http://localhost:8080/healthz\n  bodyContains: ok\n"
+        );
+    }
+
+    #[test]
+    fn test_tcp_check_defaults() {
+        let check: Check =
+            serde_yaml::from_str("tcpCheck:\n  host: localhost\n  port: 5432\n").unwrap();
+        assert_eq!(
+            check,
+            Check::TcpCheck(TcpCheck {
+                name: String::new(),
+                target: TcpCheckTarget {
+                    host: "localhost".into(),
+                    port: 5432,
+                    timeout_seconds: 10,
+                },
+                retry: Default::default(),
+                severity: Default::default(),
+                run_on: vec![],
+            })
+        );
+
+        serde_yaml::from_str::<Check>("tcpCheck:\n  host: localhost\n").unwrap_err();
+    }
+
+    #[test]
+    fn test_container_check_defaults() {
+        let check: Check =
+            serde_yaml::from_str("name: web\ncontainerCheck:\n  names: [nginx]\n").unwrap();
+        assert_eq!(
+            check,
+            Check::ContainerCheck(ContainerCheck {
+                name: "web".into(),
+                workloads: ContainerCheckWorkloads {
+                    runtime: ContainerRuntime::Podman,
+                    endpoint: None,
+                    names: vec!["nginx".into()],
+                    timeout_seconds: DEFAULT_CONTAINER_CHECK_TIMEOUT_SECONDS,
+                },
+                retry: Default::default(),
+                severity: Default::default(),
+                run_on: vec![],
+            })
+        );
+        assert_eq!(
+            serde_yaml::to_string(&check).unwrap(),
+            "name: web\ncontainerCheck:\n  names:\n  - nginx\n"
+        );
+
+        let check: Check =
+            serde_yaml::from_str("containerCheck:\n  runtime: cri\n  names: [kube-proxy]\n")
+                .unwrap();
+        let Check::ContainerCheck(check) = check else {
+            panic!("Expected a container check");
+        };
+        assert_eq!(check.workloads.runtime, ContainerRuntime::Cri);
+    }
+
+    #[test]
+    fn test_network_check_defaults() {
+        let check: Check = serde_yaml::from_str("name: network\nnetworkCheck: {}\n").unwrap();
+        assert_eq!(
+            check,
+            Check::NetworkCheck(NetworkCheck {
+                name: "network".into(),
+                criteria: NetworkCheckCriteria::default(),
+                retry: Default::default(),
+                severity: Default::default(),
+                run_on: vec![],
+            })
+        );
+        assert_eq!(
+            serde_yaml::to_string(&check).unwrap(),
+            "name: network\nnetworkCheck: {}\n"
+        );
+
+        let check: Check = serde_yaml::from_str(
+            "networkCheck:\n  defaultGateway: false\n  dnsNames: [example.com]\n",
+        )
+        .unwrap();
+        let Check::NetworkCheck(check) = check else {
+            panic!("Expected a network check");
+        };
+        assert!(!check.criteria.default_gateway);
+        assert_eq!(check.criteria.dns_names, vec!["example.com".to_string()]);
+    }
+
+    #[test]
+    fn test_disk_health_check_defaults() {
+        let check: Check = serde_yaml::from_str("diskHealthCheck: {}\n").unwrap();
+        assert_eq!(
+            check,
+            Check::DiskHealthCheck(DiskHealthCheck {
+                name: String::new(),
+                criteria: DiskHealthCheckCriteria::default(),
+                retry: Default::default(),
+                severity: Default::default(),
+                run_on: vec![],
+            })
+        );
+
+        let check: Check =
+            serde_yaml::from_str("diskHealthCheck:\n  disks: [os]\n  failOnPastFailures: true\n")
+                .unwrap();
+        let Check::DiskHealthCheck(check) = check else {
+            panic!("Expected a disk health check");
+        };
+        assert_eq!(
+            check.criteria,
+            DiskHealthCheckCriteria {
+                disks: vec!["os".into()],
+                fail_on_past_failures: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_systemd_check_system_running() {
+        let check: Check = serde_yaml::from_str("name: settled\nsystemRunning: true\n").unwrap();
+        let Check::SystemdCheck(check) = check else {
+            panic!("Expected a systemd check");
+        };
+        assert!(check.system_running);
+        assert!(check.systemd_services.is_empty());
+    }
+
+    #[test]
+    fn test_systemd_check_servicing_types() {
+        let check: Check =
+            serde_yaml::from_str("systemdServices: [sshd]\nservicingTypes: [clean-install]\n")
+                .unwrap();
+        assert!(check.should_run(ServicingType::CleanInstall));
+        assert!(!check.should_run(ServicingType::AbUpdate));
+        assert!(serde_yaml::to_string(&check).unwrap().contains("runOn"));
+    }
+
+    #[test]
+    fn test_health_monitor() {
+        let health: Health = serde_yaml::from_str("monitor:\n  windowSeconds: 1800\n").unwrap();
+        assert_eq!(
+            health.monitor,
+            Some(HealthMonitor {
+                window_seconds: 1800,
+                interval_seconds: DEFAULT_HEALTH_MONITOR_INTERVAL_SECONDS,
+            })
+        );
+        health.validate().unwrap();
+
+        let health: Health =
+            serde_yaml::from_str("monitor:\n  windowSeconds: 1800\n  intervalSeconds: 0\n")
+                .unwrap();
+        assert_eq!(
+            health.validate().unwrap_err(),
+            HostConfigurationStaticValidationError::InvalidHealthMonitorInterval
+        );
+    }
+
+    #[test]
+    fn test_rollback_policy() {
+        let health: Health = serde_yaml::from_str("rollbackPolicy: {}\n").unwrap();
+        assert_eq!(health.rollback_policy, Some(RollbackPolicy::default()));
+        health.validate().unwrap();
+
+        let health: Health = serde_yaml::from_str(
+            "rollbackPolicy:\n  maxFailedBoots: 3\n  scriptFailure: rollback\n  powerOff: true\n",
+        )
+        .unwrap();
+        assert_eq!(
+            health.rollback_policy,
+            Some(RollbackPolicy {
+                max_failed_boots: 3,
+                script_failure: ScriptFailureAction::Rollback,
+                power_off: true,
+            })
+        );
+        health.validate().unwrap();
+
+        let health: Health =
+            serde_yaml::from_str("rollbackPolicy:\n  maxFailedBoots: 0\n").unwrap();
+        assert_eq!(
+            health.validate().unwrap_err(),
+            HostConfigurationStaticValidationError::InvalidRollbackMaxFailedBoots
+        );
+    }
 }
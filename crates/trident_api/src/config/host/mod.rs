@@ -8,7 +8,8 @@ use schemars::JsonSchema;
 
 use crate::{
     constants::{
-        internal_params::SELF_UPGRADE_TRIDENT, DEFAULT_CONFEXT_DIRECTORY, DEFAULT_SYSEXT_DIRECTORY,
+        internal_params::SELF_UPGRADE_TRIDENT, DEFAULT_CONFEXT_DIRECTORY,
+        HOST_CONFIGURATION_API_VERSION,
     },
     is_default,
     storage_graph::graph::StorageGraph,
@@ -20,11 +21,12 @@ pub(crate) mod image;
 pub(crate) mod internal_params;
 pub(crate) mod os;
 pub(crate) mod scripts;
+pub(crate) mod secret;
 pub(crate) mod storage;
 pub(crate) mod trident;
 
 use error::HostConfigurationStaticValidationError;
-use health::Health;
+use health::{Check, Health};
 use image::OsImage;
 use internal_params::InternalParams;
 use os::{ManagementOs, Os, SelinuxMode};
@@ -37,6 +39,14 @@ use trident::Trident;
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 #[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct HostConfiguration {
+    /// API version of the Host Configuration.
+    ///
+    /// Host Configurations written for an older version are upgraded when they are loaded, and
+    /// can be upgraded permanently with `trident migrate-config`. When not set, the Host
+    /// Configuration is expected to follow the current version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<u32>,
+
     /// The Trident Management configuration controls the installation of the
     /// Trident agent onto the target OS.
     #[serde(default, skip_serializing_if = "is_default")]
@@ -80,6 +90,17 @@ pub struct HostConfiguration {
 
 impl HostConfiguration {
     pub fn validate(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        if let Some(version) = self.api_version {
+            if version == 0 || version > HOST_CONFIGURATION_API_VERSION {
+                return Err(
+                    HostConfigurationStaticValidationError::UnsupportedApiVersion {
+                        version,
+                        supported: HOST_CONFIGURATION_API_VERSION,
+                    },
+                );
+            }
+        }
+
         let require_root_mount_point = self.trident != Trident::default()
             || self.scripts != Scripts::default()
             || self.os != Os::default()
@@ -87,15 +108,23 @@ impl HostConfiguration {
         let graph = self.storage.validate(require_root_mount_point)?;
         self.os.validate()?;
         self.scripts.validate()?;
+        self.health.validate()?;
         self.management_os.validate()?;
         self.trident.validate()?;
 
         self.validate_root_verity_config(&graph)?;
 
+        // Packages cannot be installed on a read-only verity filesystem.
+        if !self.os.packages.is_empty() && (graph.root_fs_is_verity() || graph.usr_fs_is_verity()) {
+            return Err(HostConfigurationStaticValidationError::PackagesOnVerityFilesystem);
+        }
+
         self.validate_datastore_location()?;
 
         self.validate_extension_images_locations(&graph)?;
 
+        self.validate_disk_health_checks()?;
+
         Ok(())
     }
 
@@ -188,12 +217,11 @@ impl HostConfiguration {
 
         // Find all directories in which sysexts or confexts will be placed.
         let mut dirs = HashSet::new();
-        dirs.extend(
-            self.os
-                .sysexts
-                .iter()
-                .map(|ext| ext.path.clone().unwrap_or(DEFAULT_SYSEXT_DIRECTORY.into())),
-        );
+        dirs.extend(self.os.sysexts.iter().map(|ext| {
+            ext.path
+                .clone()
+                .unwrap_or(self.os.sysext_policy.sysext_directory().into())
+        }));
         dirs.extend(
             self.os
                 .confexts
@@ -228,6 +256,29 @@ impl HostConfiguration {
         Ok(())
     }
 
+    /// Ensure that disk health checks only check disks of `storage.disks`.
+    fn validate_disk_health_checks(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        for check in &self.health.checks {
+            let Check::DiskHealthCheck(disk_health_check) = check else {
+                continue;
+            };
+            if let Some(disk_id) = disk_health_check
+                .criteria
+                .disks
+                .iter()
+                .find(|disk_id| !self.storage.disks.iter().any(|disk| &disk.id == *disk_id))
+            {
+                return Err(
+                    HostConfigurationStaticValidationError::UnknownDiskHealthCheckDisk {
+                        name: disk_health_check.name.clone(),
+                        disk_id: disk_id.clone(),
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+
     #[cfg(feature = "schemars")]
     pub fn generate_schema() -> schemars::schema::RootSchema {
         use schemars::schema::Schema;
@@ -268,9 +319,9 @@ mod tests {
 
     use crate::{
         config::{
-            AbUpdate, AbVolumePair, Disk, Extension, FileSystem, FileSystemSource, MountOptions,
-            MountPoint, NewFileSystemType, Partition, PartitionTableType, PartitionType,
-            VerityDevice,
+            AbUpdate, AbVolumePair, Disk, DiskHealthCheck, DiskHealthCheckCriteria, Extension,
+            FileSystem, FileSystemSource, MountOptions, MountPoint, NewFileSystemType, Partition,
+            PartitionTableType, PartitionType, VerityDevice,
         },
         constants::{
             internal_params::SELF_UPGRADE_TRIDENT, MOUNT_OPTION_READ_ONLY, ROOT_MOUNT_POINT_PATH,
@@ -290,26 +341,36 @@ mod tests {
 
         host_config.os.sysexts = vec![
             Extension {
-                url: Url::parse("https://example.com/sysext1.raw").unwrap(),
-                sha384: Sha384Hash::from("a".repeat(96)),
-                path: None, // Defaults to a file inside /var/lib/extensions
+                // Defaults to a file inside /var/lib/extensions
+                requires: Vec::new(),
+                ..Extension::new(
+                    Url::parse("https://example.com/sysext1.raw").unwrap(),
+                    Sha384Hash::from("a".repeat(96)),
+                )
             },
             Extension {
-                url: Url::parse("https://example.com/sysext2.raw").unwrap(),
-                sha384: Sha384Hash::from("b".repeat(96)),
                 path: Some(PathBuf::from("/etc/extensions/sysext2.raw")),
+                ..Extension::new(
+                    Url::parse("https://example.com/sysext2.raw").unwrap(),
+                    Sha384Hash::from("b".repeat(96)),
+                )
             },
         ];
         host_config.os.confexts = vec![
             Extension {
-                url: Url::parse("https://example.com/confext1.raw").unwrap(),
-                sha384: Sha384Hash::from("c".repeat(96)),
-                path: None, // Defaults to a file inside /var/lib/confexts
+                // Defaults to a file inside /var/lib/confexts
+                requires: Vec::new(),
+                ..Extension::new(
+                    Url::parse("https://example.com/confext1.raw").unwrap(),
+                    Sha384Hash::from("c".repeat(96)),
+                )
             },
             Extension {
-                url: Url::parse("https://example.com/confext2.raw").unwrap(),
-                sha384: Sha384Hash::from("d".repeat(96)),
                 path: Some(PathBuf::from("/usr/lib/confexts/confext2.raw")),
+                ..Extension::new(
+                    Url::parse("https://example.com/confext2.raw").unwrap(),
+                    Sha384Hash::from("d".repeat(96)),
+                )
             },
         ];
 
@@ -396,9 +457,12 @@ mod tests {
     fn test_validate_extension_image_location_failure() {
         let mut host_config = HostConfiguration::default();
         host_config.os.sysexts = vec![Extension {
-            url: Url::parse("https://example.com/sysext1.raw").unwrap(),
-            sha384: Sha384Hash::from("a".repeat(96)),
-            path: None, // Defaults to a file inside /var/lib/extensions
+            // Defaults to a file inside /var/lib/extensions
+            requires: Vec::new(),
+            ..Extension::new(
+                Url::parse("https://example.com/sysext1.raw").unwrap(),
+                Sha384Hash::from("a".repeat(96)),
+            )
         }];
 
         // /var/lib/extensions/ is not on a shared partition
@@ -680,4 +744,63 @@ mod tests {
             .set_flag_false(SELF_UPGRADE_TRIDENT.into());
         host_config.validate_root_verity_config(&graph).unwrap();
     }
+
+    #[test]
+    fn test_validate_disk_health_checks() {
+        let mut host_config = HostConfiguration {
+            storage: Storage {
+                disks: vec![Disk {
+                    id: "os".into(),
+                    device: "/dev/sda".into(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let check = |disks: Vec<&str>| {
+            Check::DiskHealthCheck(DiskHealthCheck {
+                name: "disks".into(),
+                criteria: DiskHealthCheckCriteria {
+                    disks: disks.into_iter().map(String::from).collect(),
+                    fail_on_past_failures: false,
+                },
+                retry: Default::default(),
+                severity: Default::default(),
+                run_on: vec![],
+            })
+        };
+
+        host_config.health.checks = vec![check(vec![]), check(vec!["os"])];
+        host_config.validate_disk_health_checks().unwrap();
+
+        host_config.health.checks = vec![check(vec!["os", "data"])];
+        assert_eq!(
+            host_config.validate_disk_health_checks().unwrap_err(),
+            HostConfigurationStaticValidationError::UnknownDiskHealthCheckDisk {
+                name: "disks".into(),
+                disk_id: "data".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_validate_api_version() {
+        let mut host_config = HostConfiguration::default();
+        host_config.validate().unwrap();
+
+        host_config.api_version = Some(HOST_CONFIGURATION_API_VERSION);
+        host_config.validate().unwrap();
+
+        for version in [0, HOST_CONFIGURATION_API_VERSION + 1] {
+            host_config.api_version = Some(version);
+            assert_eq!(
+                host_config.validate().unwrap_err(),
+                HostConfigurationStaticValidationError::UnsupportedApiVersion {
+                    version,
+                    supported: HOST_CONFIGURATION_API_VERSION,
+                }
+            );
+        }
+    }
 }
@@ -0,0 +1,94 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+
+/// Settings of the boot menu of the installed bootloader, GRUB or systemd-boot when using UKIs.
+///
+/// Settings that are not set keep the defaults of the bootloader. Hiding the menu does not remove
+/// any boot entry, so the entries of the A/B volumes Trident rolls back to remain available.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct BootMenu {
+    /// Number of seconds the bootloader waits before booting the default entry. `0` boots the
+    /// default entry immediately.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u32>,
+
+    /// Whether the boot menu is shown while the bootloader waits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<MenuVisibility>,
+
+    /// Resolution of the console of the bootloader. Only supported by systemd-boot.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub console_mode: Option<ConsoleMode>,
+}
+
+/// Visibility of the boot menu.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum MenuVisibility {
+    /// # Menu
+    ///
+    /// The boot menu is shown until the timeout expires. Without a timeout, the bootloader waits
+    /// until an entry is selected.
+    Menu,
+
+    /// # Countdown
+    ///
+    /// A countdown is shown until the timeout expires, and pressing a key shows the boot menu.
+    /// systemd-boot has no countdown, and shows the boot menu instead.
+    Countdown,
+
+    /// # Hidden
+    ///
+    /// Nothing is shown, and pressing a key before the timeout expires shows the boot menu.
+    /// systemd-boot boots the default entry immediately unless a key is pressed, regardless of
+    /// the timeout.
+    Hidden,
+}
+
+impl Display for MenuVisibility {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.pad(match self {
+            Self::Menu => "menu",
+            Self::Countdown => "countdown",
+            Self::Hidden => "hidden",
+        })
+    }
+}
+
+/// Console mode of systemd-boot.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum ConsoleMode {
+    /// # Keep
+    ///
+    /// Keep the mode selected by the firmware.
+    Keep,
+
+    /// # Auto
+    ///
+    /// Pick a suitable mode automatically.
+    Auto,
+
+    /// # Max
+    ///
+    /// Pick the mode with the highest resolution.
+    Max,
+}
+
+impl Display for ConsoleMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.pad(match self {
+            Self::Keep => "keep",
+            Self::Auto => "auto",
+            Self::Max => "max",
+        })
+    }
+}
@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+
+use crate::config::HostConfigurationStaticValidationError;
+
+/// cloud-init configuration to pass through to the target OS.
+///
+/// Trident writes the files as a NoCloud seed in `/var/lib/cloud/seed/nocloud` of the target OS,
+/// which cloud-init picks up on boot, so that existing cloud-init workflows keep working on hosts
+/// installed by Trident. cloud-init must be installed in the target OS, with the NoCloud
+/// datasource enabled.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct CloudInit {
+    /// User data, e.g. a `#cloud-config` document. An empty file is written when not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_data: Option<CloudInitFile>,
+
+    /// Instance metadata, e.g. the `instance-id`. An empty file is written when not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta_data: Option<CloudInitFile>,
+
+    /// Network configuration, in the cloud-init network configuration format.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_config: Option<CloudInitFile>,
+}
+
+/// File of the NoCloud seed, embedded in the Host Configuration or referenced by URL.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct CloudInitFile {
+    /// Contents of the file. Conflicts with `url`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+
+    /// URL of the file, with the `file://`, `http://` or `https://` scheme. Conflicts with
+    /// `content`.
+    ///
+    /// The file is fetched from the servicing OS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<Url>,
+}
+
+impl CloudInit {
+    /// Returns the files of the NoCloud seed that are set, along with their name in the seed.
+    pub fn files(&self) -> impl Iterator<Item = (&'static str, &CloudInitFile)> {
+        [
+            ("user-data", &self.user_data),
+            ("meta-data", &self.meta_data),
+            ("network-config", &self.network_config),
+        ]
+        .into_iter()
+        .filter_map(|(name, file)| file.as_ref().map(|file| (name, file)))
+    }
+
+    pub(super) fn validate(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        for (name, file) in self.files() {
+            let explanation = match (&file.content, &file.url) {
+                (Some(_), Some(_)) => Some("'content' and 'url' cannot both be set"),
+                (None, None) => Some("either 'content' or 'url' must be set"),
+                (None, Some(url)) if !["file", "http", "https"].contains(&url.scheme()) => {
+                    Some("its URL must have the 'file', 'http' or 'https' scheme")
+                }
+                _ => None,
+            };
+
+            if let Some(explanation) = explanation {
+                return Err(
+                    HostConfigurationStaticValidationError::InvalidCloudInitFile {
+                        name: name.into(),
+                        explanation: explanation.into(),
+                    },
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let cloud_init = CloudInit {
+            user_data: Some(CloudInitFile {
+                content: Some("#cloud-config\n".into()),
+                url: None,
+            }),
+            meta_data: Some(CloudInitFile {
+                content: None,
+                url: Some(Url::parse("https://example.com/meta-data").unwrap()),
+            }),
+            network_config: None,
+        };
+        cloud_init.validate().unwrap();
+        assert_eq!(
+            cloud_init.files().map(|(name, _)| name).collect::<Vec<_>>(),
+            vec!["user-data", "meta-data"]
+        );
+
+        for (network_config, explanation) in [
+            (
+                CloudInitFile::default(),
+                "either 'content' or 'url' must be set",
+            ),
+            (
+                CloudInitFile {
+                    content: Some("version: 2\n".into()),
+                    url: Some(Url::parse("file:///network-config").unwrap()),
+                },
+                "'content' and 'url' cannot both be set",
+            ),
+            (
+                CloudInitFile {
+                    content: None,
+                    url: Some(Url::parse("oci://registry.example.com/network-config:1").unwrap()),
+                },
+                "its URL must have the 'file', 'http' or 'https' scheme",
+            ),
+        ] {
+            let cloud_init = CloudInit {
+                network_config: Some(network_config),
+                ..Default::default()
+            };
+            assert_eq!(
+                cloud_init.validate().unwrap_err(),
+                HostConfigurationStaticValidationError::InvalidCloudInitFile {
+                    name: "network-config".into(),
+                    explanation: explanation.into(),
+                }
+            );
+        }
+    }
+}
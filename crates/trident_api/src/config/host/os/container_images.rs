@@ -0,0 +1,150 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+
+use crate::config::{HostConfigurationStaticValidationError, Secret};
+
+/// Container image to preload into the containers-storage of the target OS, so that the
+/// workloads using it can start on first boot without pulling it from a registry.
+///
+/// Images are loaded with `podman`, which must be installed in the target OS.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ContainerImage {
+    /// Reference of the image, e.g. `mcr.microsoft.com/azurelinux/base/core:3.0`.
+    ///
+    /// Without `archive`, the image is pulled from its registry, which must be reachable from
+    /// the host during servicing.
+    pub image: String,
+
+    /// Absolute path, on the servicing OS, of an archive to load the image from instead of
+    /// pulling it, e.g. a file of the installation media.
+    ///
+    /// The archive is a tarball created by `podman save` or `docker save`, in the docker-archive
+    /// or oci-archive format, and must contain the image tagged as `image`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<PathBuf>,
+
+    /// Credentials to authenticate to the registry of the image when pulling it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry_credentials: Option<RegistryCredentials>,
+}
+
+/// Credentials to authenticate to a container registry.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct RegistryCredentials {
+    /// Name of the user to authenticate as.
+    pub username: String,
+
+    /// Password or token of the user. Use a secret reference to keep it out of the stored Host
+    /// Configuration.
+    pub password: Secret,
+}
+
+impl ContainerImage {
+    pub(super) fn validate(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        let explanation = if self.image.is_empty() {
+            Some("it must not be empty")
+        } else if self.image.starts_with('-') || self.image.contains(char::is_whitespace) {
+            Some("it must not start with '-' or contain whitespace")
+        } else if self.image.contains("://") {
+            Some("it must be an image reference, without a transport or URL scheme")
+        } else if self
+            .archive
+            .as_ref()
+            .is_some_and(|archive| !archive.is_absolute())
+        {
+            Some("the path of its archive must be absolute")
+        } else if self.archive.is_some() && self.registry_credentials.is_some() {
+            Some("registry credentials are only used to pull images without an archive")
+        } else if self
+            .registry_credentials
+            .as_ref()
+            .is_some_and(|credentials| credentials.username.is_empty())
+        {
+            Some("the username of its registry credentials must not be empty")
+        } else {
+            None
+        };
+
+        match explanation {
+            Some(explanation) => Err(
+                HostConfigurationStaticValidationError::InvalidContainerImage {
+                    image: self.image.clone(),
+                    explanation: explanation.into(),
+                },
+            ),
+            None => self
+                .registry_credentials
+                .as_ref()
+                .map_or(Ok(()), |credentials| credentials.password.validate()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(image: &str, archive: Option<&str>) -> ContainerImage {
+        ContainerImage {
+            image: image.into(),
+            archive: archive.map(PathBuf::from),
+            registry_credentials: None,
+        }
+    }
+
+    fn credentials(username: &str) -> Option<RegistryCredentials> {
+        Some(RegistryCredentials {
+            username: username.into(),
+            password: Secret::Inline("token".into()),
+        })
+    }
+
+    #[test]
+    fn test_validate() {
+        image("registry.example.com/app:1.0", None)
+            .validate()
+            .unwrap();
+        image("app@sha256:0123", Some("/media/images/app.tar"))
+            .validate()
+            .unwrap();
+        ContainerImage {
+            registry_credentials: credentials("robot"),
+            ..image("registry.example.com/app:1.0", None)
+        }
+        .validate()
+        .unwrap();
+
+        for invalid in [
+            image("", None),
+            image("-app", None),
+            image("app 1.0", None),
+            image("docker://registry.example.com/app:1.0", None),
+            image("app:1.0", Some("images/app.tar")),
+            ContainerImage {
+                registry_credentials: credentials("robot"),
+                ..image("app:1.0", Some("/media/images/app.tar"))
+            },
+            ContainerImage {
+                registry_credentials: credentials(""),
+                ..image("app:1.0", None)
+            },
+        ] {
+            assert!(
+                matches!(
+                    invalid.validate(),
+                    Err(HostConfigurationStaticValidationError::InvalidContainerImage { .. })
+                ),
+                "'{}' should be invalid",
+                invalid.image
+            );
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use std::{ffi::OsStr, path::PathBuf};
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
 
 #[cfg(feature = "schemars")]
 use schemars::JsonSchema;
@@ -7,10 +10,17 @@ use url::Url;
 
 use crate::{
     config::HostConfigurationStaticValidationError,
-    constants::{VALID_CONFEXT_DIRECTORIES, VALID_SYSEXT_DIRECTORIES},
-    primitives::hash::Sha384Hash,
+    constants::{
+        DEFAULT_SYSEXT_DIRECTORY, INITRD_SYSEXT_DIRECTORY, VALID_CONFEXT_DIRECTORIES,
+        VALID_SYSEXT_DIRECTORIES,
+    },
+    is_default,
+    primitives::hash::{Sha256Hash, Sha384Hash},
 };
 
+#[cfg(feature = "schemars")]
+use crate::schema_helpers::unit_enum_with_untagged_variant;
+
 /// Data about an extension image (sysext or confext) to merge onto the target OS.
 ///
 /// Extension image must be a [Discoverable Disk
@@ -23,16 +33,25 @@ pub struct Extension {
     /// Image](https://uapi-group.org/specifications/specs/discoverable_disk_image/).
     ///
     /// URLs may have one of the following four schemes: `http://`, `https://`, `file://`, or
-    /// `oci://`. Extension image files stored in OCI registries must allow for
-    /// anonymous pulls.
+    /// `oci://`, e.g. `oci://registry.example.com/exts/debug:1.2`. Extension image files stored in
+    /// OCI registries must allow for anonymous pulls. The OCI artifact must have a single layer,
+    /// or exactly one layer holding a `.raw` file, as pushed by ORAS. The digest of the layer is
+    /// verified in addition to `sha384`.
     pub url: Url,
 
     /// The Sha384 of the entire extension image file.
     pub sha384: Sha384Hash,
 
+    /// Optional Sha256 of the entire extension image file, for publishers that only provide
+    /// Sha256 digests. When specified, it is verified in addition to `sha384` before the image
+    /// is installed on the target OS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<Sha256Hash>,
+
     /// The absolute path of the extension image in the target OS.
     ///
-    /// By default, sysexts are placed in /var/lib/extensions/. Trident supports
+    /// By default, sysexts are placed in /var/lib/extensions/, or in the `installPath` of the
+    /// sysext policy. Trident supports
     /// placing sysexts in:
     ///
     /// - /etc/extensions/
@@ -53,14 +72,298 @@ pub struct Extension {
     /// /run/sysexts/ and /run/confexts/ are not supported.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub path: Option<PathBuf>,
+
+    /// IDs of the other extension images of the same kind that this image requires, i.e. the
+    /// `SYSEXT_ID` or `CONFEXT_ID` of their extension-release file, e.g. the firmware sysext
+    /// required by a driver sysext. Trident sets up required images before the images that
+    /// require them.
+    ///
+    /// systemd-sysext merges all the extension images of a kind in a single overlay, stacked in
+    /// the order of their file names, so that later images take precedence. Trident refuses to
+    /// merge the extension images when a required image is not part of the Host Configuration,
+    /// when images require each other, or when the file name of a required image does not sort
+    /// before the file name of the image that requires it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<String>,
+
+    /// Deltas from earlier versions of this extension image, produced with `zstd
+    /// --patch-from=<earlier image> <image>`, to avoid downloading large images in full when only
+    /// a few blocks change.
+    ///
+    /// When an extension image of the running OS matches the ID and version of a delta, Trident
+    /// downloads the delta and applies it to that image instead of downloading this image. The
+    /// result is verified against `sha384`, and this image is downloaded in full if the delta
+    /// cannot be applied. Deltas can only be applied to base images of up to 2 GiB, the largest
+    /// window Trident decodes deltas with, and this image is downloaded in full when the base image
+    /// is larger.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deltas: Vec<ExtensionDelta>,
+
+    /// Scope the extension image is merged into, which must be listed in the `SYSEXT_SCOPE` or
+    /// `CONFEXT_SCOPE` of its extension-release file. When these are not set, extension images
+    /// are only merged into the system.
+    #[serde(default, skip_serializing_if = "ExtensionScope::is_system")]
+    pub scope: ExtensionScope,
+
+    /// Format of the artifact at `url`. Directory trees and tarballs are converted to an
+    /// uncompressed EROFS image by Trident, so the servicing OS must provide `mkfs.erofs`.
+    ///
+    /// When the tree does not have an extension-release file, Trident generates one with `ID=_any`
+    /// and the name of the image as `SYSEXT_ID` or `CONFEXT_ID`. The name of the image is the file
+    /// name of `path` without `.raw`, or the last segment of `url` without its tarball extension.
+    ///
+    /// Images are built reproducibly, with all files owned by root and all timestamps set to the
+    /// epoch, so `sha384` is the hash of the image Trident builds, which is verified like the hash
+    /// of a downloaded image. Converted images are not signed, so they are rejected when
+    /// `os.sysextPolicy.requireSignature` is set.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub source: ExtensionSource,
+}
+
+/// Format of the artifact an extension image is fetched from.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum ExtensionSource {
+    /// # Image
+    ///
+    /// The artifact is the extension image.
+    #[default]
+    Image,
+
+    /// # Directory
+    ///
+    /// The artifact is a directory tree on the servicing OS, e.g. holding `usr/` for a sysext,
+    /// whose URL must have the `file://` scheme.
+    Directory,
+
+    /// # Tarball
+    ///
+    /// The artifact is a tarball of the directory tree, either uncompressed or compressed with
+    /// gzip or zstd.
+    Tarball,
+}
+
+/// Scope an extension image is merged into.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum ExtensionScope {
+    /// # System
+    ///
+    /// Merge the extension image into the running system.
+    #[default]
+    System,
+
+    /// # Initrd
+    ///
+    /// Merge the extension image into the initrd. Only sysexts can be merged into the initrd. They
+    /// must be placed in /.extra/sysext/, and are added to the initrd when it is regenerated,
+    /// which requires the systemd-sysext dracut module. The initrd of UKIs is not regenerated, and
+    /// mkinitrd cannot include extension images, so UKIs and OSes regenerating their initrd with
+    /// mkinitrd are not supported.
+    Initrd,
+}
+
+impl ExtensionScope {
+    /// Returns the name of the scope as used in `SYSEXT_SCOPE` and `CONFEXT_SCOPE`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::System => "system",
+            Self::Initrd => "initrd",
+        }
+    }
+
+    fn is_system(&self) -> bool {
+        *self == Self::System
+    }
+}
+
+/// Delta from an earlier version of an extension image.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ExtensionDelta {
+    /// ID of the earlier extension image, i.e. the `SYSEXT_ID` or `CONFEXT_ID` of its
+    /// extension-release file.
+    pub id: String,
+
+    /// Version of the earlier extension image, i.e. the `SYSEXT_VERSION_ID` or
+    /// `CONFEXT_VERSION_ID` of its extension-release file.
+    pub version: String,
+
+    /// URL of the delta, with the same schemes as the URL of extension images.
+    pub url: Url,
+}
+
+/// Policy applied to all sysext images.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct SysextPolicy {
+    /// Only install sysext and confext images whose dm-verity root hash is signed by a key
+    /// trusted by the OS performing the servicing. Unsigned or badly-signed images are rejected
+    /// before they are installed, so they are never merged.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub require_signature: bool,
+
+    /// Number of unmanaged extension images of each name to keep in /var/lib/extensions/ and
+    /// /var/lib/confexts/ after a runtime update. Unmanaged images are `.raw` files that are not
+    /// part of the Host Configuration, e.g. images left behind by earlier versions or placed by
+    /// hand; the most recently modified ones are kept. When not set, unmanaged images are never
+    /// pruned automatically, but can be pruned with `trident sysext prune`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_unmanaged: Option<usize>,
+
+    /// Directory of the target OS in which sysext images without an explicit `path` are placed,
+    /// e.g. /etc/extensions/ to keep them across a factory reset of /var. Must be one of the
+    /// directories supported for the `path` of sysext images; /run/extensions/ is not supported,
+    /// as images placed there would not survive a reboot. Defaults to /var/lib/extensions/.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub install_path: Option<PathBuf>,
+
+    /// Keep extension images in a content-addressed store, the hidden `.trident-store/`
+    /// directory next to them, and place a symlink to the stored image at the path of each
+    /// image. The images replaced by a runtime update stay in the store, so rolling back to them
+    /// does not download them again. Stored images that are not part of the current or the
+    /// previous Host Configuration are removed.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub versioned_store: bool,
+
+    /// Sysext images to remove from the running OS during a runtime update, e.g. images placed by
+    /// hand or images whose artifact is no longer available. Each entry is the `SYSEXT_ID` of an
+    /// image or its name, i.e. its file name without `.raw`. IDs are read from the extension
+    /// images merged into /usr, so the images do not need to be mounted.
+    ///
+    /// Images are searched for in /etc/extensions/, /run/extensions/ and /var/lib/extensions/.
+    /// Images of the Host Configuration are never removed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove: Vec<String>,
+
+    /// Whether the /usr hierarchy that sysext images are merged into is writable, as set with
+    /// `systemd-sysext refresh --mutable`. Writes are stored in /var/lib/extensions.mutable/usr/.
+    /// Writable hierarchies are meant for development hosts; by default, the merged hierarchy is
+    /// read-only.
+    #[serde(default, skip_serializing_if = "is_default")]
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(schema_with = "unit_enum_with_untagged_variant::<ExtensionMutability, bool>")
+    )]
+    pub mutable: ExtensionMutability,
+}
+
+/// Mutability of a hierarchy that extension images are merged into.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum ExtensionMutability {
+    /// # Auto
+    ///
+    /// The hierarchy is writable if its directory in /var/lib/extensions.mutable/ exists.
+    Auto,
+
+    /// # Import
+    ///
+    /// The contents of the directory of the hierarchy in /var/lib/extensions.mutable/ are merged
+    /// as a read-only layer, but the hierarchy is not writable.
+    Import,
+
+    /// # Enabled
+    ///
+    /// `true` makes the hierarchy writable, `false` keeps it read-only.
+    #[serde(untagged)]
+    Enabled(bool),
+}
+
+impl Default for ExtensionMutability {
+    fn default() -> Self {
+        ExtensionMutability::Enabled(false)
+    }
+}
+
+impl ExtensionMutability {
+    /// Returns the value of the `--mutable` option of systemd-sysext and systemd-confext.
+    pub fn as_arg(&self) -> &'static str {
+        match self {
+            ExtensionMutability::Auto => "auto",
+            ExtensionMutability::Import => "import",
+            ExtensionMutability::Enabled(true) => "yes",
+            ExtensionMutability::Enabled(false) => "no",
+        }
+    }
+}
+
+impl SysextPolicy {
+    /// Returns the directory in which sysext images without an explicit path are placed.
+    pub fn sysext_directory(&self) -> &Path {
+        self.install_path
+            .as_deref()
+            .unwrap_or(Path::new(DEFAULT_SYSEXT_DIRECTORY))
+    }
+
+    pub fn validate(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        let Some(install_path) = &self.install_path else {
+            return Ok(());
+        };
+        if !VALID_SYSEXT_DIRECTORIES
+            .iter()
+            .any(|valid_dir| install_path == Path::new(valid_dir))
+        {
+            return Err(
+                HostConfigurationStaticValidationError::ExtensionImageInvalidDirectory {
+                    path: install_path.display().to_string(),
+                    valid_directories: VALID_SYSEXT_DIRECTORIES.join(", "),
+                },
+            );
+        }
+        Ok(())
+    }
 }
 
 impl Extension {
+    /// Returns an extension image downloaded from `url` with the given hash, with the default
+    /// settings for all other fields.
+    pub fn new(url: Url, sha384: Sha384Hash) -> Self {
+        Self {
+            url,
+            sha384,
+            sha256: None,
+            path: None,
+            requires: Vec::new(),
+            deltas: Vec::new(),
+            scope: ExtensionScope::default(),
+            source: ExtensionSource::default(),
+        }
+    }
+
     pub fn validate_sysext(&self) -> Result<(), HostConfigurationStaticValidationError> {
-        self.validate(&VALID_SYSEXT_DIRECTORIES)
+        match self.scope {
+            ExtensionScope::System => self.validate(&VALID_SYSEXT_DIRECTORIES),
+            ExtensionScope::Initrd => {
+                if self.path.is_none() {
+                    return Err(
+                        HostConfigurationStaticValidationError::ExtensionImageInvalidInitrdScope {
+                            url: self.url.to_string(),
+                            explanation: format!(
+                                "'path' must be set to a file in '{INITRD_SYSEXT_DIRECTORY}'"
+                            ),
+                        },
+                    );
+                }
+                self.validate(&[INITRD_SYSEXT_DIRECTORY])
+            }
+        }
     }
 
     pub fn validate_confext(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        if self.scope == ExtensionScope::Initrd {
+            return Err(
+                HostConfigurationStaticValidationError::ExtensionImageInvalidInitrdScope {
+                    url: self.url.to_string(),
+                    explanation: "only sysexts can be merged into the initrd".into(),
+                },
+            );
+        }
         self.validate(&VALID_CONFEXT_DIRECTORIES)
     }
 
@@ -68,6 +371,17 @@ impl Extension {
         &self,
         valid_directories: &[&str],
     ) -> Result<(), HostConfigurationStaticValidationError> {
+        if self.source == ExtensionSource::Directory && self.url.scheme() != "file" {
+            return Err(
+                HostConfigurationStaticValidationError::ExtensionImageInvalidSource {
+                    url: self.url.to_string(),
+                    explanation: "directory trees must be on the servicing OS, with a 'file://' \
+                        URL"
+                    .into(),
+                },
+            );
+        }
+
         // Ensure that the path, if given, is a valid path for the
         // extension image to be placed.
         let Some(path) = &self.path else {
@@ -118,9 +432,11 @@ mod tests {
 
     fn create_test_extension(path: Option<PathBuf>) -> Extension {
         Extension {
-            url: Url::parse("http://example.com/test.raw").unwrap(),
-            sha384: Sha384Hash::from("a".repeat(96)),
             path,
+            ..Extension::new(
+                Url::parse("http://example.com/test.raw").unwrap(),
+                Sha384Hash::from("a".repeat(96)),
+            )
         }
     }
 
@@ -159,6 +475,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_validate_directory_source() {
+        let mut ext = create_test_extension(None);
+        ext.source = ExtensionSource::Directory;
+        assert_eq!(
+            ext.validate_sysext().unwrap_err(),
+            HostConfigurationStaticValidationError::ExtensionImageInvalidSource {
+                url: "http://example.com/test.raw".into(),
+                explanation: "directory trees must be on the servicing OS, with a 'file://' URL"
+                    .into(),
+            }
+        );
+        ext.url = Url::parse("file:///srv/sysexts/test").unwrap();
+        ext.validate_sysext().unwrap();
+        ext.validate_confext().unwrap();
+    }
+
+    #[test]
+    fn test_validate_initrd_scope() {
+        let mut ext = create_test_extension(Some(PathBuf::from("/.extra/sysext/test.raw")));
+        ext.scope = ExtensionScope::Initrd;
+        ext.validate_sysext().unwrap();
+        assert_eq!(
+            ext.validate_confext().unwrap_err(),
+            HostConfigurationStaticValidationError::ExtensionImageInvalidInitrdScope {
+                url: "http://example.com/test.raw".into(),
+                explanation: "only sysexts can be merged into the initrd".into(),
+            }
+        );
+
+        // Sysexts merged into the initrd must be placed in /.extra/sysext/.
+        ext.path = Some(PathBuf::from("/var/lib/extensions/test.raw"));
+        assert_eq!(
+            ext.validate_sysext().unwrap_err(),
+            HostConfigurationStaticValidationError::ExtensionImageInvalidDirectory {
+                path: "/var/lib/extensions/test.raw".into(),
+                valid_directories: INITRD_SYSEXT_DIRECTORY.into(),
+            }
+        );
+        ext.path = None;
+        ext.validate_sysext().unwrap_err();
+    }
+
     #[test]
     fn test_validate_invalid_directory_fails() {
         let path = PathBuf::from("/opt/invalid/test.raw");
@@ -254,4 +613,27 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_validate_sysext_policy_install_path() {
+        let mut policy = SysextPolicy::default();
+        policy.validate().unwrap();
+        assert_eq!(
+            policy.sysext_directory(),
+            Path::new(DEFAULT_SYSEXT_DIRECTORY)
+        );
+
+        policy.install_path = Some("/etc/extensions/".into());
+        policy.validate().unwrap();
+        assert_eq!(policy.sysext_directory(), Path::new("/etc/extensions"));
+
+        policy.install_path = Some("/run/extensions".into());
+        assert_eq!(
+            policy.validate().unwrap_err(),
+            HostConfigurationStaticValidationError::ExtensionImageInvalidDirectory {
+                path: "/run/extensions".into(),
+                valid_directories: VALID_SYSEXT_DIRECTORIES.join(", ")
+            }
+        );
+    }
 }
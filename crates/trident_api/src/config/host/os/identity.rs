@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+
+use crate::config::HostConfigurationStaticValidationError;
+
+/// Identity of the host in the target OS: its hostname, timezone, locale and keymap.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Identity {
+    /// Template of the hostname, e.g. `edge-${smbios.product_serial}` or `${imds.compute/name}`.
+    /// Variables are referenced as in the rest of the Host Configuration, and are resolved on the
+    /// host when it is serviced.
+    ///
+    /// The resolved hostname is lowercased, and characters that are not allowed in hostnames are
+    /// replaced with `-`. Cannot be used together with `os.hostname`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+
+    /// Timezone, as a name of the tz database, e.g. `Etc/UTC` or `Europe/Paris`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+
+    /// System locale, e.g. `en_US.UTF-8`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+
+    /// Keymap of the virtual console, e.g. `us`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keymap: Option<String>,
+}
+
+impl Identity {
+    pub(super) fn validate(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        if let Some(template) = &self.hostname {
+            if template.is_empty() {
+                return Err(
+                    HostConfigurationStaticValidationError::InvalidHostnameTemplate {
+                        template: template.clone(),
+                        explanation: "template is empty".into(),
+                    },
+                );
+            }
+            if template
+                .match_indices('{')
+                .any(|(index, _)| !template[..index].ends_with('$'))
+            {
+                return Err(
+                    HostConfigurationStaticValidationError::InvalidHostnameTemplate {
+                        template: template.clone(),
+                        explanation: "variables must be referenced as '${namespace.name}'".into(),
+                    },
+                );
+            }
+        }
+
+        // The timezone names a file under /usr/share/zoneinfo, so it must not escape it.
+        if let Some(timezone) = &self.timezone {
+            if timezone.is_empty()
+                || timezone.split('/').any(|component| {
+                    component.is_empty()
+                        || component == "."
+                        || component == ".."
+                        || !component
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || "_+-".contains(c))
+                })
+            {
+                return Err(HostConfigurationStaticValidationError::InvalidTimezone {
+                    timezone: timezone.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let mut identity = Identity {
+            hostname: Some("edge-${smbios.product_uuid}".into()),
+            timezone: Some("America/Argentina/Buenos_Aires".into()),
+            locale: Some("en_US.UTF-8".into()),
+            keymap: Some("us".into()),
+        };
+        identity.validate().unwrap();
+
+        identity.hostname = Some("".into());
+        assert_eq!(
+            identity.validate().unwrap_err(),
+            HostConfigurationStaticValidationError::InvalidHostnameTemplate {
+                template: "".into(),
+                explanation: "template is empty".into()
+            }
+        );
+
+        identity.hostname = Some("edge-{serial}".into());
+        assert_eq!(
+            identity.validate().unwrap_err(),
+            HostConfigurationStaticValidationError::InvalidHostnameTemplate {
+                template: "edge-{serial}".into(),
+                explanation: "variables must be referenced as '${namespace.name}'".into()
+            }
+        );
+
+        identity.hostname = None;
+        for timezone in ["", "/etc/passwd", "../../etc/passwd", "Europe//Paris"] {
+            identity.timezone = Some(timezone.into());
+            assert_eq!(
+                identity.validate().unwrap_err(),
+                HostConfigurationStaticValidationError::InvalidTimezone {
+                    timezone: timezone.into()
+                }
+            );
+        }
+    }
+}
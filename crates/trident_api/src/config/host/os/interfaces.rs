@@ -0,0 +1,190 @@
+use std::collections::HashSet;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+
+use crate::config::HostConfigurationStaticValidationError;
+
+/// Maximum length of a network interface name, as defined by the kernel's IFNAMSIZ (16) minus the
+/// trailing NUL byte.
+const MAX_INTERFACE_NAME_LENGTH: usize = 15;
+
+lazy_static! {
+    /// Regular expression for validating MAC addresses in the colon-separated notation.
+    static ref MAC_ADDRESS_REGEX: Regex =
+        Regex::new(r"^([[:xdigit:]]{2}:){5}[[:xdigit:]]{2}$").expect("Failed to compile regex");
+}
+
+/// Pins the name of a network interface to its MAC address.
+///
+/// Trident generates a systemd `.link` file in the target OS for every pinned interface, so that
+/// the interface keeps the same name even if a newer kernel or udev version would otherwise pick a
+/// different predictable name. This keeps existing network configuration, such as Netplan
+/// configuration referencing the interface by name, working across updates.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct PinnedInterface {
+    /// Name to assign to the interface, e.g. `eth0` or `mgmt0`.
+    pub name: String,
+
+    /// MAC address of the interface, in the colon-separated notation, e.g. `00:11:22:33:44:55`.
+    pub mac_address: String,
+}
+
+impl PinnedInterface {
+    /// Returns the MAC address in the canonical lowercase notation.
+    pub fn normalized_mac_address(&self) -> String {
+        self.mac_address.to_lowercase()
+    }
+
+    fn validate(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        validate_interface_name(&self.name)?;
+
+        if !MAC_ADDRESS_REGEX.is_match(&self.mac_address) {
+            return Err(HostConfigurationStaticValidationError::InvalidMacAddress {
+                mac_address: self.mac_address.clone(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Validates that `name` can be used as the name of a network interface by the kernel.
+pub(super) fn validate_interface_name(
+    name: &str,
+) -> Result<(), HostConfigurationStaticValidationError> {
+    if name.is_empty()
+        || name.len() > MAX_INTERFACE_NAME_LENGTH
+        || name == "."
+        || name == ".."
+        || name
+            .chars()
+            .any(|c| c == '/' || c == ':' || c.is_whitespace())
+    {
+        return Err(
+            HostConfigurationStaticValidationError::InvalidInterfaceName {
+                name: name.to_string(),
+            },
+        );
+    }
+    Ok(())
+}
+
+/// Validates a list of pinned interfaces. Every entry must be valid on its own, and no two entries
+/// may share a name or a MAC address.
+pub(super) fn validate_pinned_interfaces(
+    pinned_interfaces: &[PinnedInterface],
+) -> Result<(), HostConfigurationStaticValidationError> {
+    let mut names = HashSet::new();
+    let mut mac_addresses = HashSet::new();
+    for interface in pinned_interfaces {
+        interface.validate()?;
+
+        if !names.insert(&interface.name) {
+            return Err(
+                HostConfigurationStaticValidationError::DuplicatePinnedInterfaceName {
+                    name: interface.name.clone(),
+                },
+            );
+        }
+
+        if !mac_addresses.insert(interface.normalized_mac_address()) {
+            return Err(
+                HostConfigurationStaticValidationError::DuplicatePinnedInterfaceMacAddress {
+                    mac_address: interface.mac_address.clone(),
+                },
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pin(name: &str, mac_address: &str) -> PinnedInterface {
+        PinnedInterface {
+            name: name.into(),
+            mac_address: mac_address.into(),
+        }
+    }
+
+    #[test]
+    fn test_validate_pinned_interface() {
+        pin("eth0", "00:11:22:33:44:55").validate().unwrap();
+        pin("mgmt0", "AA:bb:CC:dd:EE:ff").validate().unwrap();
+        pin("a23456789012345", "00:11:22:33:44:55")
+            .validate()
+            .unwrap();
+
+        for name in ["", "a234567890123456", "eth 0", "eth/0", "eth:0", ".", ".."] {
+            assert_eq!(
+                pin(name, "00:11:22:33:44:55").validate(),
+                Err(
+                    HostConfigurationStaticValidationError::InvalidInterfaceName {
+                        name: name.into()
+                    }
+                )
+            );
+        }
+
+        for mac_address in [
+            "",
+            "00:11:22:33:44",
+            "00:11:22:33:44:55:66",
+            "00-11-22-33-44-55",
+            "001122334455",
+            "00:11:22:33:44:5g",
+        ] {
+            assert_eq!(
+                pin("eth0", mac_address).validate(),
+                Err(HostConfigurationStaticValidationError::InvalidMacAddress {
+                    mac_address: mac_address.into()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_pinned_interfaces() {
+        validate_pinned_interfaces(&[]).unwrap();
+        validate_pinned_interfaces(&[
+            pin("eth0", "00:11:22:33:44:55"),
+            pin("eth1", "00:11:22:33:44:56"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            validate_pinned_interfaces(&[
+                pin("eth0", "00:11:22:33:44:55"),
+                pin("eth0", "00:11:22:33:44:56"),
+            ]),
+            Err(
+                HostConfigurationStaticValidationError::DuplicatePinnedInterfaceName {
+                    name: "eth0".into()
+                }
+            )
+        );
+
+        // MAC addresses are compared case-insensitively
+        assert_eq!(
+            validate_pinned_interfaces(&[
+                pin("eth0", "aa:bb:cc:dd:ee:ff"),
+                pin("eth1", "AA:BB:CC:DD:EE:FF"),
+            ]),
+            Err(
+                HostConfigurationStaticValidationError::DuplicatePinnedInterfaceMacAddress {
+                    mac_address: "AA:BB:CC:DD:EE:FF".into()
+                }
+            )
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use std::path::Path;
+
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+
+use crate::config::HostConfigurationStaticValidationError;
+
+/// Directories of the servicing OS that files can be migrated from.
+pub const MIGRATION_DIRECTORIES: [&str; 2] = ["/etc", "/var"];
+
+/// Rules selecting the files in /etc and /var of the servicing OS that are copied into the updated
+/// OS during an A/B update.
+///
+/// Only the files matching `include`, and not matching `exclude`, are copied. By default, no files
+/// are copied, apart from the machine ID and hostname.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Migration {
+    /// Glob patterns of the files to copy, e.g. `/etc/ssh/ssh_host_*_key` or `/var/lib/app/**/*`.
+    /// Patterns must be absolute paths under /etc or /var.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+
+    /// Glob patterns of the files matching `include` that must not be copied.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+
+    /// Commands transforming the copied files, in order, before the updated OS boots.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transforms: Vec<MigrationTransform>,
+}
+
+/// Command run on the files copied into the updated OS that match a pattern.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct MigrationTransform {
+    /// Glob pattern of the copied files to transform.
+    pub pattern: String,
+
+    /// Bash command run on the servicing OS once for each copied file matching `pattern`. The path
+    /// of the copy in the updated OS is passed in the `MIGRATED_FILE` environment variable, and the
+    /// path of the original file in the `SOURCE_FILE` environment variable.
+    pub command: String,
+}
+
+impl Migration {
+    pub fn validate(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        self.include
+            .iter()
+            .chain(&self.exclude)
+            .chain(self.transforms.iter().map(|transform| &transform.pattern))
+            .try_for_each(|pattern| validate_pattern(pattern))
+    }
+}
+
+/// Checks that a pattern is a valid glob pattern for an absolute path under one of the migration
+/// directories.
+fn validate_pattern(pattern: &str) -> Result<(), HostConfigurationStaticValidationError> {
+    let invalid =
+        |explanation: &str| HostConfigurationStaticValidationError::InvalidMigrationPattern {
+            pattern: pattern.to_string(),
+            explanation: explanation.to_string(),
+        };
+
+    if let Err(e) = Pattern::new(pattern) {
+        return Err(invalid(e.msg));
+    }
+
+    let path = Path::new(pattern);
+    if !MIGRATION_DIRECTORIES
+        .iter()
+        .any(|directory| path.starts_with(directory) && path != Path::new(directory))
+    {
+        return Err(invalid("must be an absolute path under /etc or /var"));
+    }
+
+    if path
+        .components()
+        .any(|component| component.as_os_str() == "..")
+    {
+        return Err(invalid("must not contain '..'"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let mut migration = Migration {
+            include: vec!["/etc/ssh/ssh_host_*_key".into(), "/var/lib/app/**/*".into()],
+            exclude: vec!["/var/lib/app/cache/**".into()],
+            transforms: vec![MigrationTransform {
+                pattern: "/etc/app.conf".into(),
+                command: "sed -i 's/v1/v2/' \"$MIGRATED_FILE\"".into(),
+            }],
+        };
+        migration.validate().unwrap();
+
+        for pattern in [
+            "etc/foo",
+            "/usr/lib/foo",
+            "/etc",
+            "/etcetera/foo",
+            "/etc/../usr/foo",
+        ] {
+            migration.include = vec![pattern.into()];
+            assert!(
+                matches!(
+                    migration.validate(),
+                    Err(HostConfigurationStaticValidationError::InvalidMigrationPattern { .. })
+                ),
+                "Pattern '{pattern}' should be rejected"
+            );
+        }
+
+        migration.include = vec!["/etc/[foo".into()];
+        migration.validate().unwrap_err();
+    }
+}
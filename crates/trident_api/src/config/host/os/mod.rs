@@ -1,6 +1,7 @@
 use std::collections::HashSet;
 use std::{
     fmt::{Display, Formatter, Result as FmtResult},
+    path::PathBuf,
     str::FromStr,
 };
 
@@ -16,16 +17,32 @@ use crate::is_default;
 use super::error::HostConfigurationStaticValidationError;
 
 pub mod additional_files;
+pub mod boot_menu;
+pub mod cloud_init;
+pub mod container_images;
 pub mod extensions;
+pub mod identity;
+pub mod interfaces;
+pub mod migration;
 pub mod modules;
 mod network;
+pub mod networking;
 pub mod services;
+pub mod time;
 pub mod users;
 
 use additional_files::AdditionalFile;
-use extensions::Extension;
+use boot_menu::BootMenu;
+use cloud_init::CloudInit;
+use container_images::ContainerImage;
+use extensions::{Extension, SysextPolicy};
+use identity::Identity;
+use interfaces::PinnedInterface;
+use migration::Migration;
 use modules::Module;
+use networking::Network;
 use services::Services;
+use time::Time;
 use users::User;
 
 /// Configuration for the host OS.
@@ -43,6 +60,18 @@ pub struct Os {
     )]
     pub netplan: Option<NetworkConfig>,
 
+    /// Network configuration for the target OS, rendered into systemd-networkd configuration
+    /// files. Cannot be used together with `netplan`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<Network>,
+
+    /// Network interfaces whose names should be pinned to their MAC addresses in the target OS.
+    ///
+    /// Pinning protects network configuration that references interfaces by name from NIC
+    /// renames caused by kernel or udev changes in updated images.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pinned_interfaces: Vec<PinnedInterface>,
+
     /// SELinux configuration for the host.
     ///
     /// Note: SELinux cannot be used in conjunction with vfat or NTFS filesystems. When SELinux is
@@ -59,10 +88,46 @@ pub struct Os {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub additional_files: Vec<AdditionalFile>,
 
+    /// Packages to install into the target OS during clean install, e.g. `tmux` or
+    /// `tmux-3.4`.
+    ///
+    /// Packages are installed with tdnf, or dnf when the target OS does not have tdnf, from the
+    /// repositories configured in the target OS or from `packageRepository`. A/B updates do not
+    /// install packages, as they replace the whole OS with the new image.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<String>,
+
+    /// Absolute path, on the servicing OS, of a local RPM repository to install `packages` from,
+    /// e.g. a directory of the installation media. When set, only this repository is used, so
+    /// that packages can be installed on hosts without network access.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package_repository: Option<PathBuf>,
+
+    /// Container images to preload into the containers-storage of the target OS.
+    ///
+    /// Images are pulled from their registry or loaded from a local archive during clean install
+    /// and A/B update. Images that already exist in the target OS, e.g. on a `/var` volume kept
+    /// by an A/B update, are not loaded again.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub container_images: Vec<ContainerImage>,
+
+    /// cloud-init configuration, written as a NoCloud seed in the target OS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cloud_init: Option<CloudInit>,
+
     /// Hostname of the system.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub hostname: Option<String>,
 
+    /// Identity of the host: hostname template, timezone, locale and keymap.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<Identity>,
+
+    /// Time synchronization of the host with NTP. Correct time is required by TLS, e.g. to
+    /// download updates and run HTTP health checks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<Time>,
+
     /// Kernel modules to configure.
     #[serde(default, skip_serializing_if = "is_default")]
     pub modules: Vec<Module>,
@@ -83,17 +148,97 @@ pub struct Os {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub confexts: Vec<Extension>,
 
+    /// Policy applied to all sysext images.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub sysext_policy: SysextPolicy,
+
+    /// Rules selecting the files in /etc and /var copied from the servicing OS into the updated OS
+    /// during an A/B update.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub migration: Migration,
+
     /// Options for configuring the UEFI fallback.
     #[serde(default, skip_serializing_if = "is_default")]
     pub uefi_fallback: Option<UefiFallbackMode>,
+
+    /// Settings of the boot menu of the installed bootloader.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub boot_menu: Option<BootMenu>,
 }
 
-/// Additional kernel command line options to add to the image.
+/// Kernel command line parameters to change in the bootloader entry of the target OS.
 #[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 #[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct KernelCommandLine {
+    /// Parameters to add to the kernel command line, e.g. `console=ttyS0`.
+    #[serde(default)]
     pub extra_command_line: Vec<String>,
+
+    /// Parameters to remove from the kernel command line of the image. A parameter without a
+    /// value, e.g. `quiet`, also removes the parameters with the same key and any value. Only
+    /// supported with GRUB.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_command_line: Vec<String>,
+}
+
+/// Keys of the kernel command line parameters that Trident sets itself, and that cannot be
+/// removed.
+const PROTECTED_KERNEL_PARAMETERS: [&str; 3] = ["root", "selinux", "enforcing"];
+
+impl KernelCommandLine {
+    fn validate(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        if let Some(parameter) = self
+            .extra_command_line
+            .iter()
+            .find(|parameter| parameter.trim().is_empty())
+        {
+            return Err(
+                HostConfigurationStaticValidationError::InvalidKernelParameter {
+                    parameter: parameter.clone(),
+                },
+            );
+        }
+
+        for parameter in &self.removed_command_line {
+            // Removed parameters are matched by a shell script, so they are restricted to the
+            // characters that are not special to the shell.
+            if parameter.is_empty()
+                || parameter.starts_with('=')
+                || !parameter
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "._-=,:/@+".contains(c))
+            {
+                return Err(
+                    HostConfigurationStaticValidationError::InvalidKernelParameter {
+                        parameter: parameter.clone(),
+                    },
+                );
+            }
+
+            let key = parameter.split('=').next().unwrap_or_default();
+            if PROTECTED_KERNEL_PARAMETERS.contains(&key) {
+                return Err(
+                    HostConfigurationStaticValidationError::ProtectedKernelParameterRemoved {
+                        parameter: parameter.clone(),
+                    },
+                );
+            }
+
+            if self.extra_command_line.iter().any(|extra| {
+                extra == parameter
+                    || (!parameter.contains('=') && extra.split('=').next() == Some(parameter))
+            }) {
+                return Err(
+                    HostConfigurationStaticValidationError::KernelParameterAddedAndRemoved {
+                        parameter: parameter.clone(),
+                    },
+                );
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// Configuration for SELinux mode
@@ -108,6 +253,36 @@ pub struct Selinux {
     /// Otherwise, when using root-verity, SELinux must not be enabled in the OS image or SELinux
     /// should be explicitly set to `disabled`.
     pub mode: Option<SelinuxMode>,
+
+    /// Absolute paths to SELinux policy modules on the servicing OS, either compiled (`.pp`) or in
+    /// the Common Intermediate Language (`.cil`). They are installed into the policy of the target
+    /// OS with `semodule` before its filesystems are relabeled. Ignored when SELinux is disabled.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policy_modules: Vec<PathBuf>,
+}
+
+impl Selinux {
+    fn validate(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        for path in &self.policy_modules {
+            if !path.is_absolute() {
+                return Err(HostConfigurationStaticValidationError::PathNotAbsolute {
+                    path: path.display().to_string(),
+                });
+            }
+            if !path
+                .extension()
+                .is_some_and(|ext| ext == "pp" || ext == "cil")
+            {
+                return Err(
+                    HostConfigurationStaticValidationError::InvalidSelinuxPolicyModule {
+                        path: path.display().to_string(),
+                    },
+                );
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// SELinux mode
@@ -227,16 +402,76 @@ impl Os {
                     username: user.name.clone(),
                 });
             }
+            user.validate()?;
         }
 
         for file in &self.additional_files {
             file.validate()?;
         }
 
+        if let Some(package) = self.packages.iter().find(|package| {
+            package.is_empty() || package.starts_with('-') || package.contains(char::is_whitespace)
+        }) {
+            return Err(HostConfigurationStaticValidationError::InvalidPackageName {
+                name: package.clone(),
+            });
+        }
+        if let Some(repository) = &self.package_repository {
+            if !repository.is_absolute() {
+                return Err(
+                    HostConfigurationStaticValidationError::InvalidPackageRepositoryPath {
+                        path: repository.to_string_lossy().to_string(),
+                    },
+                );
+            }
+        }
+
+        let mut container_images = HashSet::new();
+        for container_image in &self.container_images {
+            if !container_images.insert(&container_image.image) {
+                return Err(
+                    HostConfigurationStaticValidationError::DuplicateContainerImage {
+                        image: container_image.image.clone(),
+                    },
+                );
+            }
+            container_image.validate()?;
+        }
+
         if let Some(network) = self.netplan.as_ref() {
             network::validate_netplan(network)?;
         }
 
+        if let Some(network) = self.network.as_ref() {
+            if self.netplan.is_some() {
+                return Err(HostConfigurationStaticValidationError::NetworkAndNetplanConfigured);
+            }
+            network.validate()?;
+        }
+
+        interfaces::validate_pinned_interfaces(&self.pinned_interfaces)?;
+
+        self.selinux.validate()?;
+
+        self.kernel_command_line.validate()?;
+
+        if let Some(identity) = &self.identity {
+            if self.hostname.is_some() && identity.hostname.is_some() {
+                return Err(HostConfigurationStaticValidationError::HostnameConfiguredTwice);
+            }
+            identity.validate()?;
+        }
+
+        self.migration.validate()?;
+
+        if let Some(cloud_init) = &self.cloud_init {
+            cloud_init.validate()?;
+        }
+
+        if let Some(time) = &self.time {
+            time.validate()?;
+        }
+
         // Warn if SELinux is not disabled and sysexts or confexts are specified.
         if let Some(selinux_mode) = self.selinux.mode {
             if !(self.sysexts.is_empty() && self.confexts.is_empty())
@@ -250,6 +485,7 @@ impl Os {
         }
 
         // Validate sysexts
+        self.sysext_policy.validate()?;
         let mut sysext_hashes = HashSet::new();
         let mut sysext_paths = HashSet::new();
         self.sysexts.iter().try_for_each(|ext| {
@@ -324,7 +560,7 @@ mod tests {
 
     use users::Password;
 
-    use crate::primitives::hash::Sha384Hash;
+    use crate::{config::ExtensionMutability, primitives::hash::Sha384Hash};
 
     #[test]
     fn test_validate_os_users() {
@@ -361,19 +597,181 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_validate_os_users_sudo_rules() {
+        let mut config = Os::default();
+        config.users.push(User {
+            name: "test".to_string(),
+            sudo_rules: vec!["ALL=(ALL) NOPASSWD: ALL".to_string()],
+            ..Default::default()
+        });
+        config.validate().unwrap();
+
+        for rule in [
+            "",
+            " ",
+            "ALL=(ALL) ALL\nroot ALL=(ALL) ALL",
+            "ALL=(ALL) \\\nALL",
+        ] {
+            config.users[0].sudo_rules = vec![rule.to_string()];
+            assert_eq!(
+                config.validate(),
+                Err(HostConfigurationStaticValidationError::InvalidSudoRule {
+                    username: "test".to_string(),
+                    rule: rule.to_string()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_selinux_policy_modules() {
+        let mut config = Os::default();
+        config.selinux.policy_modules = vec![
+            PathBuf::from("/opt/policy/app.pp"),
+            PathBuf::from("/opt/policy/app.cil"),
+        ];
+        config.validate().unwrap();
+
+        config.selinux.policy_modules = vec![PathBuf::from("policy/app.pp")];
+        assert_eq!(
+            config.validate(),
+            Err(HostConfigurationStaticValidationError::PathNotAbsolute {
+                path: "policy/app.pp".to_string()
+            })
+        );
+
+        config.selinux.policy_modules = vec![PathBuf::from("/opt/policy/app.te")];
+        assert_eq!(
+            config.validate(),
+            Err(
+                HostConfigurationStaticValidationError::InvalidSelinuxPolicyModule {
+                    path: "/opt/policy/app.te".to_string()
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_validate_kernel_command_line() {
+        let mut config = Os::default();
+        config.kernel_command_line = KernelCommandLine {
+            extra_command_line: vec!["console=ttyS0".to_string()],
+            removed_command_line: vec!["quiet".to_string(), "console=tty0".to_string()],
+        };
+        config.validate().unwrap();
+
+        for parameter in ["", "a b", "$(reboot)", "=1"] {
+            config.kernel_command_line.removed_command_line = vec![parameter.to_string()];
+            assert_eq!(
+                config.validate(),
+                Err(
+                    HostConfigurationStaticValidationError::InvalidKernelParameter {
+                        parameter: parameter.to_string()
+                    }
+                )
+            );
+        }
+
+        config.kernel_command_line.removed_command_line = vec!["root=/dev/sda2".to_string()];
+        assert_eq!(
+            config.validate(),
+            Err(
+                HostConfigurationStaticValidationError::ProtectedKernelParameterRemoved {
+                    parameter: "root=/dev/sda2".to_string()
+                }
+            )
+        );
+
+        for parameter in ["console", "console=ttyS0"] {
+            config.kernel_command_line.removed_command_line = vec![parameter.to_string()];
+            assert_eq!(
+                config.validate(),
+                Err(
+                    HostConfigurationStaticValidationError::KernelParameterAddedAndRemoved {
+                        parameter: parameter.to_string()
+                    }
+                )
+            );
+        }
+
+        config.kernel_command_line.removed_command_line.clear();
+        config.kernel_command_line.extra_command_line = vec![" ".to_string()];
+        assert_eq!(
+            config.validate(),
+            Err(
+                HostConfigurationStaticValidationError::InvalidKernelParameter {
+                    parameter: " ".to_string()
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_validate_identity() {
+        let mut config = Os {
+            hostname: Some("host".to_string()),
+            identity: Some(Identity {
+                timezone: Some("Etc/UTC".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        config.validate().unwrap();
+
+        config.identity.as_mut().unwrap().hostname =
+            Some("edge-${smbios.product_serial}".to_string());
+        assert_eq!(
+            config.validate(),
+            Err(HostConfigurationStaticValidationError::HostnameConfiguredTwice)
+        );
+    }
+
+    #[test]
+    fn test_validate_packages() {
+        let mut config = Os {
+            packages: vec!["tmux".into(), "vim-enhanced-9.1".into()],
+            package_repository: Some(PathBuf::from("/mnt/cdrom/repo")),
+            ..Default::default()
+        };
+        config.validate().unwrap();
+
+        for name in ["", "--nogpgcheck", "vim tmux"] {
+            config.packages = vec![name.into()];
+            assert_eq!(
+                config.validate(),
+                Err(HostConfigurationStaticValidationError::InvalidPackageName {
+                    name: name.into()
+                })
+            );
+        }
+
+        config.packages = vec!["tmux".into()];
+        config.package_repository = Some(PathBuf::from("repo"));
+        assert_eq!(
+            config.validate(),
+            Err(
+                HostConfigurationStaticValidationError::InvalidPackageRepositoryPath {
+                    path: "repo".into()
+                }
+            )
+        );
+    }
+
     #[test]
     fn test_validate_extensions_success() {
         let mut config = Os::default();
         config.sysexts.push(Extension {
-            url: Url::parse("http://example.com/ext1.raw").unwrap(),
-            sha384: Sha384Hash::from("a".repeat(96)),
             path: Some(PathBuf::from("/var/lib/extensions/ext1.raw")),
+            ..Extension::new(
+                Url::parse("http://example.com/ext1.raw").unwrap(),
+                Sha384Hash::from("a".repeat(96)),
+            )
         });
-        config.sysexts.push(Extension {
-            url: Url::parse("http://example.com/ext2.raw").unwrap(),
-            sha384: Sha384Hash::from("b".repeat(96)),
-            path: None,
-        });
+        config.sysexts.push(Extension::new(
+            Url::parse("http://example.com/ext2.raw").unwrap(),
+            Sha384Hash::from("b".repeat(96)),
+        ));
         config.validate().unwrap();
     }
 
@@ -382,14 +780,18 @@ mod tests {
         let mut config = Os::default();
         let duplicate_hash = Sha384Hash::from("a".repeat(96));
         config.sysexts.push(Extension {
-            url: Url::parse("http://example.com/ext1.raw").unwrap(),
-            sha384: duplicate_hash.clone(),
             path: Some(PathBuf::from("/var/lib/extensions/ext1.raw")),
+            ..Extension::new(
+                Url::parse("http://example.com/ext1.raw").unwrap(),
+                duplicate_hash.clone(),
+            )
         });
         config.sysexts.push(Extension {
-            url: Url::parse("http://example.com/ext2.raw").unwrap(),
-            sha384: duplicate_hash.clone(),
             path: Some(PathBuf::from("/var/lib/extensions/ext2.raw")),
+            ..Extension::new(
+                Url::parse("http://example.com/ext2.raw").unwrap(),
+                duplicate_hash.clone(),
+            )
         });
 
         assert_eq!(
@@ -405,14 +807,18 @@ mod tests {
         let mut config = Os::default();
         let duplicate_path = PathBuf::from("/var/lib/extensions/ext.raw");
         config.sysexts.push(Extension {
-            url: Url::parse("http://example.com/ext1.raw").unwrap(),
-            sha384: Sha384Hash::from("a".repeat(96)),
             path: Some(duplicate_path.clone()),
+            ..Extension::new(
+                Url::parse("http://example.com/ext1.raw").unwrap(),
+                Sha384Hash::from("a".repeat(96)),
+            )
         });
         config.sysexts.push(Extension {
-            url: Url::parse("http://example.com/ext2.raw").unwrap(),
-            sha384: Sha384Hash::from("b".repeat(96)),
             path: Some(duplicate_path.clone()),
+            ..Extension::new(
+                Url::parse("http://example.com/ext2.raw").unwrap(),
+                Sha384Hash::from("b".repeat(96)),
+            )
         });
 
         assert_eq!(
@@ -423,6 +829,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_serde_sysext_policy() {
+        let config: Os = serde_yaml::from_str("sysextPolicy:\n  keepUnmanaged: 2").unwrap();
+        assert_eq!(config.sysext_policy.keep_unmanaged, Some(2));
+
+        let config: Os =
+            serde_yaml::from_str("sysextPolicy:\n  installPath: /etc/extensions").unwrap();
+        assert_eq!(
+            config.sysext_policy.install_path,
+            Some(PathBuf::from("/etc/extensions"))
+        );
+        config.validate().unwrap();
+
+        let config: Os = serde_yaml::from_str("sysextPolicy:\n  versionedStore: true").unwrap();
+        assert!(config.sysext_policy.versioned_store);
+
+        let config: Os = serde_yaml::from_str("sysextPolicy:\n  requireSignature: true").unwrap();
+        assert!(config.sysext_policy.require_signature);
+
+        for (mutable, expected) in [
+            ("true", ExtensionMutability::Enabled(true)),
+            ("false", ExtensionMutability::Enabled(false)),
+            ("auto", ExtensionMutability::Auto),
+            ("import", ExtensionMutability::Import),
+        ] {
+            let config: Os =
+                serde_yaml::from_str(&format!("sysextPolicy:\n  mutable: {mutable}")).unwrap();
+            assert_eq!(config.sysext_policy.mutable, expected);
+        }
+        assert_eq!(ExtensionMutability::Enabled(true).as_arg(), "yes");
+        serde_yaml::from_str::<Os>("sysextPolicy:\n  mutable: ephemeral").unwrap_err();
+
+        // The default policy is omitted
+        let serialized = serde_yaml::to_string(&Os::default()).unwrap();
+        assert!(!serialized.contains("sysextPolicy"));
+        let config: Os = serde_yaml::from_str(&serialized).unwrap();
+        assert_eq!(config.sysext_policy, SysextPolicy::default());
+    }
+
     #[test]
     fn test_serde_uefi_fallback_mode() {
         let mut config = Os {
@@ -455,4 +900,40 @@ mod tests {
         let deserialized = serde_yaml::from_str::<Os>(&serialized).unwrap();
         assert!(deserialized.uefi_fallback.is_none());
     }
+
+    #[test]
+    fn test_serde_boot_menu() {
+        let config: Os = serde_yaml::from_str(indoc::indoc! {"
+            bootMenu:
+              timeout: 0
+              visibility: hidden
+              consoleMode: max
+        "})
+        .unwrap();
+        assert_eq!(
+            config.boot_menu,
+            Some(BootMenu {
+                timeout: Some(0),
+                visibility: Some(boot_menu::MenuVisibility::Hidden),
+                console_mode: Some(boot_menu::ConsoleMode::Max),
+            })
+        );
+
+        // Unset settings are omitted
+        let serialized = serde_yaml::to_string(&Os {
+            boot_menu: Some(BootMenu {
+                timeout: Some(5),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(serialized.contains("bootMenu:\n  timeout: 5\n"));
+        assert!(!serialized.contains("visibility"));
+        assert!(!serde_yaml::to_string(&Os::default())
+            .unwrap()
+            .contains("bootMenu"));
+
+        serde_yaml::from_str::<Os>("bootMenu:\n  visibility: splash\n").unwrap_err();
+    }
 }
@@ -0,0 +1,454 @@
+use std::{collections::HashSet, net::IpAddr};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+
+use crate::{config::HostConfigurationStaticValidationError, is_default};
+
+use super::interfaces;
+
+/// Lowest VLAN ID that can be assigned to a VLAN.
+const MIN_VLAN_ID: u16 = 1;
+
+/// Highest VLAN ID that can be assigned to a VLAN.
+const MAX_VLAN_ID: u16 = 4094;
+
+/// Network configuration of the target OS.
+///
+/// Trident renders it into systemd-networkd configuration files in the target OS during
+/// provisioning and enables `systemd-networkd`, so that the host comes up reachable without any
+/// configuration after the install. For configurations not covered here, use `os.netplan`
+/// instead; the two are mutually exclusive.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Network {
+    /// Ethernet interfaces to configure.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub interfaces: Vec<NetworkInterface>,
+
+    /// Bonds to create, aggregating several interfaces.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bonds: Vec<Bond>,
+
+    /// VLANs to create on top of an interface or a bond.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vlans: Vec<Vlan>,
+
+    /// DNS configuration of the host, applied by `systemd-resolved`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns: Option<Dns>,
+}
+
+/// Ethernet interface of the network configuration.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct NetworkInterface {
+    /// Name of the interface, e.g. `eth0`. Use `os.pinnedInterfaces` to make the name stable.
+    pub name: String,
+
+    /// Addresses and routes of the interface.
+    #[serde(flatten)]
+    pub addressing: Addressing,
+}
+
+/// Bond of the network configuration.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Bond {
+    /// Name of the bond, e.g. `bond0`.
+    pub name: String,
+
+    /// Names of the interfaces aggregated by the bond. They cannot be configured on their own.
+    pub interfaces: Vec<String>,
+
+    /// Bonding mode. The default is `active-backup`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub mode: BondMode,
+
+    /// Addresses and routes of the bond.
+    #[serde(flatten)]
+    pub addressing: Addressing,
+}
+
+/// Bonding mode of a bond.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum BondMode {
+    /// # Balance Round-Robin
+    ///
+    /// Packets are sent on each interface in turn.
+    BalanceRr,
+
+    /// # Active Backup
+    ///
+    /// Only one interface is active; another one takes over when it fails. Works with any
+    /// switch.
+    #[default]
+    ActiveBackup,
+
+    /// # Balance XOR
+    ///
+    /// Packets are spread across the interfaces by a hash of their addresses.
+    BalanceXor,
+
+    /// # Broadcast
+    ///
+    /// Packets are sent on all interfaces.
+    Broadcast,
+
+    /// # 802.3ad
+    ///
+    /// IEEE 802.3ad dynamic link aggregation (LACP). Requires a switch that supports it.
+    #[serde(rename = "802.3ad")]
+    Ieee8023ad,
+
+    /// # Balance TLB
+    ///
+    /// Outgoing traffic is spread across the interfaces according to their load.
+    BalanceTlb,
+
+    /// # Balance ALB
+    ///
+    /// Outgoing and incoming traffic are spread across the interfaces according to their load.
+    BalanceAlb,
+}
+
+impl BondMode {
+    /// Returns the name of the mode in the configuration of systemd-networkd.
+    pub fn as_networkd_str(&self) -> &'static str {
+        match self {
+            BondMode::BalanceRr => "balance-rr",
+            BondMode::ActiveBackup => "active-backup",
+            BondMode::BalanceXor => "balance-xor",
+            BondMode::Broadcast => "broadcast",
+            BondMode::Ieee8023ad => "802.3ad",
+            BondMode::BalanceTlb => "balance-tlb",
+            BondMode::BalanceAlb => "balance-alb",
+        }
+    }
+}
+
+/// VLAN of the network configuration.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Vlan {
+    /// Name of the VLAN interface, e.g. `vlan100`.
+    pub name: String,
+
+    /// VLAN ID, from 1 to 4094.
+    pub id: u16,
+
+    /// Name of the interface or bond of `os.network` that carries the VLAN.
+    pub link: String,
+
+    /// Addresses and routes of the VLAN.
+    #[serde(flatten)]
+    pub addressing: Addressing,
+}
+
+/// Addresses and routes of an interface, bond or VLAN.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Addressing {
+    /// Whether to get an IPv4 address with DHCP. The default is false.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub dhcp4: bool,
+
+    /// Whether to get an IPv6 address with DHCPv6. The default is false.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub dhcp6: bool,
+
+    /// Static addresses, with their prefix length, e.g. `192.168.0.10/24` or `2001:db8::10/64`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub addresses: Vec<String>,
+
+    /// Static routes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routes: Vec<Route>,
+
+    /// MTU, in bytes. The default MTU of the interface is kept when not specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<u32>,
+}
+
+/// Static route of an interface, bond or VLAN.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Route {
+    /// Destination of the route, with its prefix length, e.g. `10.0.0.0/8`, or `default` for the
+    /// default route.
+    pub to: String,
+
+    /// Address of the gateway of the route.
+    pub via: String,
+
+    /// Metric of the route. Routes with a lower metric are preferred.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metric: Option<u32>,
+}
+
+impl Route {
+    /// Returns whether the route is a default route.
+    pub fn is_default(&self) -> bool {
+        self.to == "default"
+    }
+}
+
+/// DNS configuration of the network configuration.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Dns {
+    /// Addresses of the DNS servers.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub servers: Vec<String>,
+
+    /// Domains searched for names that are not fully qualified.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub search_domains: Vec<String>,
+}
+
+impl Network {
+    pub(super) fn validate(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        // Bond members get a configuration file of their own, so their names must be unique as
+        // well.
+        let mut names = HashSet::new();
+        let all_names = self
+            .interfaces
+            .iter()
+            .map(|interface| &interface.name)
+            .chain(self.bonds.iter().map(|bond| &bond.name))
+            .chain(self.bonds.iter().flat_map(|bond| &bond.interfaces))
+            .chain(self.vlans.iter().map(|vlan| &vlan.name));
+        for name in all_names {
+            interfaces::validate_interface_name(name)?;
+            if !names.insert(name) {
+                return Err(
+                    HostConfigurationStaticValidationError::DuplicateNetworkInterfaceName {
+                        name: name.clone(),
+                    },
+                );
+            }
+        }
+
+        for vlan in &self.vlans {
+            if !(MIN_VLAN_ID..=MAX_VLAN_ID).contains(&vlan.id) {
+                return Err(HostConfigurationStaticValidationError::InvalidVlanId {
+                    name: vlan.name.clone(),
+                    id: vlan.id,
+                });
+            }
+            if !self
+                .interfaces
+                .iter()
+                .map(|interface| &interface.name)
+                .chain(self.bonds.iter().map(|bond| &bond.name))
+                .any(|name| *name == vlan.link)
+            {
+                return Err(HostConfigurationStaticValidationError::UnknownVlanLink {
+                    name: vlan.name.clone(),
+                    link: vlan.link.clone(),
+                });
+            }
+        }
+
+        self.interfaces
+            .iter()
+            .map(|interface| &interface.addressing)
+            .chain(self.bonds.iter().map(|bond| &bond.addressing))
+            .chain(self.vlans.iter().map(|vlan| &vlan.addressing))
+            .try_for_each(Addressing::validate)?;
+
+        if let Some(dns) = &self.dns {
+            if let Some(server) = dns
+                .servers
+                .iter()
+                .find(|server| server.parse::<IpAddr>().is_err())
+            {
+                return Err(HostConfigurationStaticValidationError::InvalidDnsServer {
+                    server: server.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Addressing {
+    fn validate(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        if let Some(address) = self.addresses.iter().find(|address| !is_prefix(address)) {
+            return Err(
+                HostConfigurationStaticValidationError::InvalidNetworkAddress {
+                    address: address.clone(),
+                },
+            );
+        }
+        if let Some(route) = self.routes.iter().find(|route| {
+            !(route.is_default() || is_prefix(&route.to)) || route.via.parse::<IpAddr>().is_err()
+        }) {
+            return Err(HostConfigurationStaticValidationError::InvalidRoute {
+                to: route.to.clone(),
+                via: route.via.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether `prefix` is an IP address followed by a valid prefix length, e.g.
+/// `192.168.0.10/24`.
+fn is_prefix(prefix: &str) -> bool {
+    let Some((address, length)) = prefix.split_once('/') else {
+        return false;
+    };
+    let Ok(length) = length.parse::<u8>() else {
+        return false;
+    };
+    match address.parse::<IpAddr>() {
+        Ok(IpAddr::V4(_)) => length <= 32,
+        Ok(IpAddr::V6(_)) => length <= 128,
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_network() -> Network {
+        serde_yaml::from_str(
+            "interfaces:
+- name: eth0
+  dhcp4: true
+bonds:
+- name: bond0
+  interfaces: [eth1, eth2]
+  mode: 802.3ad
+  addresses: [192.168.0.10/24]
+  routes:
+  - to: default
+    via: 192.168.0.1
+  mtu: 9000
+vlans:
+- name: vlan100
+  id: 100
+  link: bond0
+  addresses: [2001:db8::10/64]
+dns:
+  servers: [192.168.0.1]
+  searchDomains: [example.com]
+",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_network_serde() {
+        let network = sample_network();
+        assert_eq!(network.bonds[0].mode, BondMode::Ieee8023ad);
+        assert_eq!(
+            network.bonds[0].addressing,
+            Addressing {
+                dhcp4: false,
+                dhcp6: false,
+                addresses: vec!["192.168.0.10/24".into()],
+                routes: vec![Route {
+                    to: "default".into(),
+                    via: "192.168.0.1".into(),
+                    metric: None,
+                }],
+                mtu: Some(9000),
+            }
+        );
+        let serialized = serde_yaml::to_string(&network).unwrap();
+        assert_eq!(
+            serde_yaml::from_str::<Network>(&serialized).unwrap(),
+            network
+        );
+
+        serde_yaml::from_str::<Network>("interfaces:\n- name: eth0\n  dhcp: true\n").unwrap_err();
+    }
+
+    #[test]
+    fn test_validate() {
+        sample_network().validate().unwrap();
+
+        let mut network = sample_network();
+        network.interfaces[0].name = "eth1".into();
+        assert_eq!(
+            network.validate().unwrap_err(),
+            HostConfigurationStaticValidationError::DuplicateNetworkInterfaceName {
+                name: "eth1".into()
+            }
+        );
+
+        let mut network = sample_network();
+        network.vlans[0].id = 4095;
+        assert_eq!(
+            network.validate().unwrap_err(),
+            HostConfigurationStaticValidationError::InvalidVlanId {
+                name: "vlan100".into(),
+                id: 4095
+            }
+        );
+
+        let mut network = sample_network();
+        network.vlans[0].link = "eth1".into();
+        assert_eq!(
+            network.validate().unwrap_err(),
+            HostConfigurationStaticValidationError::UnknownVlanLink {
+                name: "vlan100".into(),
+                link: "eth1".into()
+            }
+        );
+
+        let mut network = sample_network();
+        network.interfaces[0].addressing.addresses = vec!["192.168.0.10".into()];
+        assert_eq!(
+            network.validate().unwrap_err(),
+            HostConfigurationStaticValidationError::InvalidNetworkAddress {
+                address: "192.168.0.10".into()
+            }
+        );
+
+        let mut network = sample_network();
+        network.bonds[0].addressing.routes[0].via = "gateway".into();
+        assert_eq!(
+            network.validate().unwrap_err(),
+            HostConfigurationStaticValidationError::InvalidRoute {
+                to: "default".into(),
+                via: "gateway".into()
+            }
+        );
+
+        let mut network = sample_network();
+        network.dns.as_mut().unwrap().servers = vec!["dns.example.com".into()];
+        assert_eq!(
+            network.validate().unwrap_err(),
+            HostConfigurationStaticValidationError::InvalidDnsServer {
+                server: "dns.example.com".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_is_prefix() {
+        assert!(is_prefix("192.168.0.10/24"));
+        assert!(is_prefix("0.0.0.0/0"));
+        assert!(is_prefix("2001:db8::10/64"));
+        assert!(!is_prefix("192.168.0.10"));
+        assert!(!is_prefix("192.168.0.10/33"));
+        assert!(!is_prefix("2001:db8::10/129"));
+        assert!(!is_prefix("host/24"));
+    }
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+
+use crate::config::HostConfigurationStaticValidationError;
+
+/// Time synchronization of the target OS, configured in chrony.
+///
+/// Trident replaces the chrony configuration of the image, `/etc/chrony.conf`, and enables
+/// `chronyd`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Time {
+    /// NTP servers to synchronize the clock with, as hostnames or IP addresses, e.g.
+    /// `time.example.com`. Servers are preferred over the fallback pools.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub servers: Vec<String>,
+
+    /// Pools of NTP servers to synchronize the clock with when the servers are unreachable, e.g.
+    /// `pool.ntp.org`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_pools: Vec<String>,
+
+    /// When the clock may be stepped, i.e. set at once, instead of being slewed gradually. By
+    /// default, the clock is stepped during the first three updates if it is off by more than one
+    /// second.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step: Option<ClockStep>,
+}
+
+/// Conditions under which chrony steps the clock.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ClockStep {
+    /// Offset of the clock, in milliseconds, above which the clock is stepped.
+    pub threshold_milliseconds: u64,
+
+    /// Number of clock updates, after chronyd starts, during which the clock may be stepped. By
+    /// default, the clock may be stepped at any time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+impl Time {
+    pub(super) fn validate(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        if self.servers.is_empty() && self.fallback_pools.is_empty() {
+            return Err(HostConfigurationStaticValidationError::NoNtpSources);
+        }
+
+        // Sources are written as is into the chrony configuration, one directive per line.
+        if let Some(address) = self
+            .servers
+            .iter()
+            .chain(&self.fallback_pools)
+            .find(|address| {
+                address.is_empty()
+                    || !address
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || ".-:[]".contains(c))
+            })
+        {
+            return Err(HostConfigurationStaticValidationError::InvalidNtpSource {
+                address: address.clone(),
+            });
+        }
+
+        if self
+            .step
+            .as_ref()
+            .is_some_and(|step| step.threshold_milliseconds == 0)
+        {
+            return Err(HostConfigurationStaticValidationError::InvalidClockStepThreshold);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let mut time = Time {
+            servers: vec!["time.example.com".into(), "fd00::123".into()],
+            fallback_pools: vec!["pool.ntp.org".into()],
+            step: Some(ClockStep {
+                threshold_milliseconds: 100,
+                limit: Some(3),
+            }),
+        };
+        time.validate().unwrap();
+
+        for address in ["", "time.example.com iburst", "time.example.com\nrtcsync"] {
+            time.fallback_pools = vec![address.into()];
+            assert_eq!(
+                time.validate().unwrap_err(),
+                HostConfigurationStaticValidationError::InvalidNtpSource {
+                    address: address.into()
+                }
+            );
+        }
+
+        time.fallback_pools.clear();
+        time.step = Some(ClockStep {
+            threshold_milliseconds: 0,
+            limit: None,
+        });
+        assert_eq!(
+            time.validate().unwrap_err(),
+            HostConfigurationStaticValidationError::InvalidClockStepThreshold
+        );
+
+        assert_eq!(
+            Time::default().validate().unwrap_err(),
+            HostConfigurationStaticValidationError::NoNtpSources
+        );
+    }
+}
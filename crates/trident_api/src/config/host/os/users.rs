@@ -3,7 +3,10 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "schemars")]
 use schemars::JsonSchema;
 
-use crate::is_default;
+use crate::{
+    config::{HostConfigurationStaticValidationError, Secret},
+    is_default,
+};
 
 /// Configuration for a specific user.
 #[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
@@ -33,6 +36,11 @@ pub struct User {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub ssh_public_keys: Vec<String>,
 
+    /// Sudo rules granted to the user, in the sudoers format without the user name, e.g.
+    /// `ALL=(ALL) NOPASSWD: ALL` or `ALL=(root) /usr/bin/systemctl`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sudo_rules: Vec<String>,
+
     /// SSH configuration for the user. **(IN DEVELOPMENT)**
     #[serde(default, skip_serializing_if = "is_default")]
     pub ssh_mode: SshMode,
@@ -51,6 +59,25 @@ pub struct User {
     pub home_directory: Option<String>,
 }
 
+impl User {
+    pub(super) fn validate(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        // Each rule must fit on a single line of the sudoers file, so that it cannot add rules for
+        // other users.
+        if let Some(rule) = self
+            .sudo_rules
+            .iter()
+            .find(|rule| rule.trim().is_empty() || rule.contains(['\n', '\r', '\\']))
+        {
+            return Err(HostConfigurationStaticValidationError::InvalidSudoRule {
+                username: self.name.clone(),
+                rule: rule.clone(),
+            });
+        }
+
+        self.password.validate()
+    }
+}
+
 /// Password configuration for a user.
 ///
 /// **NOTICE:**
@@ -78,15 +105,32 @@ pub enum Password {
 
     /// # Plaintext Password
     ///
-    /// Set the user's password to a plaintext value.
+    /// Set the user's password to a plaintext value, inline or read from a secret reference.
     #[cfg(feature = "dangerous-options")]
-    DangerousPlainText(String),
+    DangerousPlainText(Secret),
 
     /// # Hashed Password
     ///
-    /// Set the user's password to a hashed value.
+    /// Set the user's password to a hashed value, inline or read from a secret reference.
     #[cfg(feature = "dangerous-options")]
-    DangerousHashed(String),
+    DangerousHashed(Secret),
+}
+
+impl Password {
+    /// Returns the secret holding the password, if any.
+    pub fn secret(&self) -> Option<&Secret> {
+        match self {
+            Password::Locked => None,
+            #[cfg(feature = "dangerous-options")]
+            Password::DangerousPlainText(secret) | Password::DangerousHashed(secret) => {
+                Some(secret)
+            }
+        }
+    }
+
+    fn validate(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        self.secret().map_or(Ok(()), Secret::validate)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
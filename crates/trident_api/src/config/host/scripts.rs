@@ -5,9 +5,12 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "schemars")]
 use schemars::JsonSchema;
 
-use crate::status::ServicingType;
+use crate::{is_default, status::ServicingType};
 
-use super::error::HostConfigurationStaticValidationError;
+use super::{
+    error::HostConfigurationStaticValidationError,
+    health::{CheckRetry, CheckSeverity},
+};
 
 /// Scripts that can be run on the host during Trident stages.
 /// These scripts are run in the order they are defined.
@@ -91,27 +94,27 @@ pub struct Script {
     #[serde(default)]
     #[serde(skip_serializing_if = "HashMap::is_empty")]
     pub environment_variables: HashMap<String, String>,
+
+    /// Maximum time the script may run, in seconds. When it runs longer, the script and all the
+    /// processes it started are killed, and the script fails. Scripts of health checks time out
+    /// after 300 seconds by default; other scripts have no timeout by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<u64>,
+
+    /// Retries of the script when it fails. Only scripts of health checks are retried.
+    #[serde(flatten)]
+    pub retry: CheckRetry,
+
+    /// Whether a failure of the script fails the health checks. Only applies to scripts of health
+    /// checks. The default is `fail`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub severity: CheckSeverity,
 }
 
 impl Script {
     /// Returns true if servicing type is enabled for this script.
     pub fn should_run(&self, servicing_type: ServicingType) -> bool {
-        if self.run_on.contains(&ServicingTypeSelection::All) {
-            return true;
-        }
-        match servicing_type {
-            ServicingType::CleanInstall => {
-                self.run_on.contains(&ServicingTypeSelection::CleanInstall)
-            }
-            ServicingType::NormalUpdate => {
-                self.run_on.contains(&ServicingTypeSelection::NormalUpdate)
-            }
-            ServicingType::AbUpdate => self.run_on.contains(&ServicingTypeSelection::AbUpdate),
-            ServicingType::UpdateAndReboot => self
-                .run_on
-                .contains(&ServicingTypeSelection::UpdateAndReboot),
-            _ => false,
-        }
+        ServicingTypeSelection::any_selects(&self.run_on, servicing_type)
     }
 }
 
@@ -149,6 +152,31 @@ pub enum ServicingTypeSelection {
     All,
 }
 
+impl ServicingTypeSelection {
+    /// Returns true if any of `selections` includes `servicing_type`.
+    pub(crate) fn any_selects(
+        selections: &[ServicingTypeSelection],
+        servicing_type: ServicingType,
+    ) -> bool {
+        if selections.contains(&ServicingTypeSelection::All) {
+            return true;
+        }
+        match servicing_type {
+            ServicingType::CleanInstall => {
+                selections.contains(&ServicingTypeSelection::CleanInstall)
+            }
+            ServicingType::NormalUpdate => {
+                selections.contains(&ServicingTypeSelection::NormalUpdate)
+            }
+            ServicingType::AbUpdate => selections.contains(&ServicingTypeSelection::AbUpdate),
+            ServicingType::UpdateAndReboot => {
+                selections.contains(&ServicingTypeSelection::UpdateAndReboot)
+            }
+            _ => false,
+        }
+    }
+}
+
 impl Scripts {
     pub(crate) fn validate(&self) -> Result<(), HostConfigurationStaticValidationError> {
         self.post_provision
@@ -189,6 +217,9 @@ mod tests {
             source: ScriptSource::Content("echo test".into()),
             environment_variables: HashMap::new(),
             arguments: vec![],
+            timeout_seconds: None,
+            retry: Default::default(),
+            severity: Default::default(),
         };
         assert!(script.should_run(ServicingType::CleanInstall));
     }
@@ -202,6 +233,9 @@ mod tests {
             source: ScriptSource::Content("echo test".into()),
             environment_variables: HashMap::new(),
             arguments: vec![],
+            timeout_seconds: None,
+            retry: Default::default(),
+            severity: Default::default(),
         };
         assert!(!script.should_run(ServicingType::NormalUpdate));
     }
@@ -215,6 +249,9 @@ mod tests {
             source: ScriptSource::Content("echo test".into()),
             environment_variables: HashMap::new(),
             arguments: vec![],
+            timeout_seconds: None,
+            retry: Default::default(),
+            severity: Default::default(),
         };
         assert!(script.should_run(ServicingType::AbUpdate));
     }
@@ -228,6 +265,9 @@ mod tests {
             environment_variables: HashMap::new(),
             source: ScriptSource::Path("/path/to/script".into()),
             arguments: vec![],
+            timeout_seconds: None,
+            retry: Default::default(),
+            severity: Default::default(),
         };
         script.validate().unwrap();
     }
@@ -241,6 +281,9 @@ mod tests {
             environment_variables: HashMap::new(),
             source: ScriptSource::Content("echo".into()),
             arguments: vec!["test".into()],
+            timeout_seconds: None,
+            retry: Default::default(),
+            severity: Default::default(),
         };
         script.validate().unwrap();
     }
@@ -254,6 +297,9 @@ mod tests {
             environment_variables: HashMap::new(),
             source: ScriptSource::Path("path/to/script".into()),
             arguments: vec![],
+            timeout_seconds: None,
+            retry: Default::default(),
+            severity: Default::default(),
         };
         assert_eq!(
             script.validate().unwrap_err(),
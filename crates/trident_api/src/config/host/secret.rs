@@ -0,0 +1,136 @@
+use std::{fmt, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+
+use crate::config::HostConfigurationStaticValidationError;
+
+/// Secret value of the Host Configuration, either inline or referenced.
+///
+/// A referenced secret is read by Trident when it is needed, so only the reference is part of the
+/// Host Configuration stored on the host and in the datastore:
+///
+/// ```yaml
+/// valueFrom:
+///   file: /run/secrets/admin-password
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum Secret {
+    /// # Inline Secret
+    ///
+    /// Value of the secret, stored as is in the Host Configuration.
+    Inline(String),
+
+    /// # Secret Reference
+    ///
+    /// Reference to the source of the secret.
+    Reference {
+        /// Source to read the secret from.
+        #[serde(rename = "valueFrom")]
+        value_from: SecretSource,
+    },
+}
+
+/// Source of a referenced secret.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum SecretSource {
+    /// # File
+    ///
+    /// Absolute path, on the servicing OS, of a file containing the secret, e.g. a file of
+    /// `/run/secrets`. A single trailing newline is stripped.
+    File(PathBuf),
+
+    /// # Environment Variable
+    ///
+    /// Name of an environment variable of the Trident process containing the secret.
+    Env(String),
+}
+
+impl Secret {
+    pub(crate) fn validate(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        let Secret::Reference { value_from } = self else {
+            return Ok(());
+        };
+
+        let explanation = match value_from {
+            SecretSource::File(path) if !path.is_absolute() => "the path must be absolute",
+            SecretSource::Env(name) if name.is_empty() || name.contains(['=', '\0']) => {
+                "the name of the environment variable must be non-empty and not contain '='"
+            }
+            _ => return Ok(()),
+        };
+
+        Err(
+            HostConfigurationStaticValidationError::InvalidSecretReference {
+                reference: value_from.to_string(),
+                explanation: explanation.into(),
+            },
+        )
+    }
+}
+
+impl fmt::Display for SecretSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretSource::File(path) => write!(f, "file '{}'", path.display()),
+            SecretSource::Env(name) => write!(f, "environment variable '{name}'"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize() {
+        assert_eq!(
+            serde_yaml::from_str::<Secret>("hunter2").unwrap(),
+            Secret::Inline("hunter2".into())
+        );
+        assert_eq!(
+            serde_yaml::from_str::<Secret>("valueFrom:\n  file: /run/secrets/password").unwrap(),
+            Secret::Reference {
+                value_from: SecretSource::File("/run/secrets/password".into())
+            }
+        );
+        assert_eq!(
+            serde_yaml::from_str::<Secret>("valueFrom:\n  env: PASSWORD").unwrap(),
+            Secret::Reference {
+                value_from: SecretSource::Env("PASSWORD".into())
+            }
+        );
+        serde_yaml::from_str::<Secret>("valueFrom:\n  vault: password").unwrap_err();
+    }
+
+    #[test]
+    fn test_validate() {
+        Secret::Inline("".into()).validate().unwrap();
+        for valid in [
+            SecretSource::File("/run/secrets/password".into()),
+            SecretSource::Env("PASSWORD".into()),
+        ] {
+            Secret::Reference { value_from: valid }.validate().unwrap();
+        }
+
+        for invalid in [
+            SecretSource::File("secrets/password".into()),
+            SecretSource::Env("".into()),
+            SecretSource::Env("PASSWORD=x".into()),
+        ] {
+            assert!(matches!(
+                Secret::Reference {
+                    value_from: invalid
+                }
+                .validate(),
+                Err(HostConfigurationStaticValidationError::InvalidSecretReference { .. })
+            ));
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use std::path::PathBuf;
+use std::{
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+};
 
 use serde::{Deserialize, Serialize};
 
@@ -59,3 +62,63 @@ pub enum PartitionTableType {
     #[default]
     Gpt,
 }
+
+/// Class of a disk. Both volumes of an A/B volume pair are expected to be
+/// placed on disks of the same class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DiskClass {
+    /// NVMe drive.
+    Nvme,
+
+    /// eMMC or SD card.
+    Mmc,
+
+    /// Virtio block device.
+    Virtio,
+
+    /// Non-rotational drive that is not NVMe, e.g. a SATA SSD.
+    SolidState,
+
+    /// Rotational drive.
+    Rotational,
+}
+
+impl DiskClass {
+    /// Returns the class implied by the kernel name of a disk, e.g. `nvme0n1`.
+    /// Returns None when the name alone is not enough, e.g. for `sda`, which
+    /// can be either a solid state or a rotational drive.
+    pub fn from_kernel_name(name: &str) -> Option<Self> {
+        if name.starts_with("nvme") {
+            Some(Self::Nvme)
+        } else if name.starts_with("mmcblk") {
+            Some(Self::Mmc)
+        } else if name.starts_with("vd") {
+            Some(Self::Virtio)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the class implied by a disk device path when it is a kernel
+    /// device path such as `/dev/nvme0n1`. Stable paths, such as the ones
+    /// under `/dev/disk/by-path/`, need to be resolved on the target system.
+    pub fn from_device_path(path: &Path) -> Option<Self> {
+        if path.parent() != Some(Path::new("/dev")) {
+            return None;
+        }
+
+        Self::from_kernel_name(path.file_name()?.to_str()?)
+    }
+}
+
+impl Display for DiskClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Nvme => write!(f, "nvme"),
+            Self::Mmc => write!(f, "mmc"),
+            Self::Virtio => write!(f, "virtio"),
+            Self::SolidState => write!(f, "ssd"),
+            Self::Rotational => write!(f, "hdd"),
+        }
+    }
+}
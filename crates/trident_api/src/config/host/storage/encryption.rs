@@ -7,7 +7,9 @@ use url::Url;
 use schemars::JsonSchema;
 
 use crate::{
-    config::HostConfigurationStaticValidationError, constants::DEV_MAPPER_PATH, BlockDeviceId,
+    config::{HostConfigurationStaticValidationError, Secret},
+    constants::DEV_MAPPER_PATH,
+    BlockDeviceId,
 };
 use sysdefs::tpm2::Pcr;
 
@@ -74,6 +76,16 @@ pub struct Encryption {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub recovery_key_url: Option<Url>,
 
+    /// The recovery key, inline or read from a secret reference, as an alternative to
+    /// `recoveryKeyUrl`.
+    ///
+    /// The key is enrolled in every encrypted volume, like the contents of the file of
+    /// `recoveryKeyUrl`, and the same security considerations apply. Using a secret reference
+    /// keeps the key out of the Host Configuration stored on the host. Cannot be used together
+    /// with `recoveryKeyUrl`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery_key: Option<Secret>,
+
     /// The list of LUKS2-encrypted volumes to create.
     ///
     /// This parameter is required and must not be empty. Each item is an object that will contain
@@ -192,6 +204,15 @@ impl Encryption {
             }
         }
 
+        if let Some(recovery_key) = &self.recovery_key {
+            if self.recovery_key_url.is_some() {
+                return Err(
+                    HostConfigurationStaticValidationError::EncryptionRecoveryKeyConfiguredTwice,
+                );
+            }
+            recovery_key.validate()?;
+        }
+
         // The list of PCRs, if provided and not empty, must only contain currently supported PCRs.
         if !self.pcrs.is_empty() {
             let supported_pcrs = [Pcr::Pcr4, Pcr::Pcr7, Pcr::Pcr11];
@@ -229,6 +250,8 @@ impl EncryptedVolume {
 mod tests {
     use super::*;
 
+    use crate::config::SecretSource;
+
     #[test]
     fn test_validate_encryption() {
         let mut config = Encryption {
@@ -241,6 +264,33 @@ mod tests {
         config.validate().unwrap();
     }
 
+    #[test]
+    fn test_validate_encryption_recovery_key() {
+        let mut config = Encryption {
+            pcrs: vec![Pcr::Pcr7],
+            recovery_key: Some(Secret::Reference {
+                value_from: SecretSource::File("/run/secrets/recovery-key".into()),
+            }),
+            ..Default::default()
+        };
+        config.validate().unwrap();
+
+        config.recovery_key_url = Some(Url::parse("file:///path/to/recovery.key").unwrap());
+        assert_eq!(
+            config.validate().unwrap_err(),
+            HostConfigurationStaticValidationError::EncryptionRecoveryKeyConfiguredTwice
+        );
+
+        config.recovery_key_url = None;
+        config.recovery_key = Some(Secret::Reference {
+            value_from: SecretSource::File("recovery-key".into()),
+        });
+        assert!(matches!(
+            config.validate().unwrap_err(),
+            HostConfigurationStaticValidationError::InvalidSecretReference { .. }
+        ));
+    }
+
     #[test]
     fn test_validate_encryption_fail_invalid_recovery_key_url() {
         let config = Encryption {
@@ -0,0 +1,219 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+
+use crate::{config::PartitionSize, constants::DEV_PATH, BlockDeviceId};
+
+#[cfg(feature = "schemars")]
+use crate::{
+    primitives::bytes::ByteCount,
+    schema_helpers::{
+        block_device_id_list_schema, block_device_id_schema, unit_enum_with_untagged_variant,
+    },
+};
+
+/// LVM configuration for a host.
+///
+/// Volume groups are created on top of partitions, software RAID arrays or encrypted volumes,
+/// and logical volumes are carved out of them. Logical volumes can then be referenced by
+/// filesystems and swap devices like any other block device.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Lvm {
+    /// Volume groups to create or adopt.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub volume_groups: Vec<VolumeGroup>,
+
+    /// Logical volumes to create in the volume groups.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub logical_volumes: Vec<LogicalVolume>,
+}
+
+/// LVM volume group configuration.
+///
+/// During a clean install, the volume group is created on the given devices, which are
+/// initialized as physical volumes. When all devices are adopted partitions, the volume group is
+/// expected to already exist on them and is adopted instead: it is activated as is, and only the
+/// logical volumes that do not exist yet are created.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct VolumeGroup {
+    /// A unique identifier for the volume group.
+    ///
+    /// This is a user-defined string that links the logical volumes to the volume group. The
+    /// identifier must be unique across devices of all types in the Host Configuration.
+    #[cfg_attr(feature = "schemars", schemars(schema_with = "block_device_id_schema"))]
+    pub id: BlockDeviceId,
+
+    /// Name of the volume group on the system.
+    ///
+    /// For example, `data` will result in the logical volumes being available under `/dev/data`.
+    pub name: String,
+
+    /// IDs of the devices to use as physical volumes.
+    ///
+    /// Devices are partitions, adopted partitions, software RAID arrays or encrypted volumes.
+    /// All devices must be of the same kind.
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(schema_with = "block_device_id_list_schema")
+    )]
+    pub devices: Vec<BlockDeviceId>,
+
+    /// Thin pools to create in the volume group, from which thin logical volumes are allocated.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub thin_pools: Vec<ThinPool>,
+}
+
+/// LVM thin pool configuration.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ThinPool {
+    /// Name of the thin pool in the volume group.
+    pub name: String,
+
+    /// Size of the thin pool. `grow` uses all the space left in the volume group.
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(schema_with = "unit_enum_with_untagged_variant::<PartitionSize, ByteCount>")
+    )]
+    pub size: PartitionSize,
+}
+
+/// LVM logical volume configuration.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct LogicalVolume {
+    /// A unique identifier for the logical volume.
+    ///
+    /// This is a user-defined string that links the logical volume to the `filesystems` and
+    /// `swap` configs in the Host Configuration. The identifier must be unique across devices of
+    /// all types in the Host Configuration.
+    #[cfg_attr(feature = "schemars", schemars(schema_with = "block_device_id_schema"))]
+    pub id: BlockDeviceId,
+
+    /// Name of the logical volume in the volume group. It must be unique in the volume group.
+    pub name: String,
+
+    /// ID of the volume group to create the logical volume in.
+    #[cfg_attr(feature = "schemars", schemars(schema_with = "block_device_id_schema"))]
+    pub volume_group_id: BlockDeviceId,
+
+    /// Size of the logical volume. `grow` uses all the space left in the volume group.
+    ///
+    /// For thin logical volumes, this is the virtual size of the volume, which must be fixed and
+    /// may exceed the size of the thin pool.
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(schema_with = "unit_enum_with_untagged_variant::<PartitionSize, ByteCount>")
+    )]
+    pub size: PartitionSize,
+
+    /// Name of the thin pool of the volume group to allocate the logical volume from. If not
+    /// set, the logical volume is allocated directly from the volume group.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thin_pool: Option<String>,
+}
+
+impl Lvm {
+    /// Returns the volume group with the given ID, if it exists.
+    pub fn volume_group(&self, id: &BlockDeviceId) -> Option<&VolumeGroup> {
+        self.volume_groups.iter().find(|vg| &vg.id == id)
+    }
+
+    /// Returns the logical volumes of the given volume group.
+    pub fn logical_volumes_of<'a>(
+        &'a self,
+        volume_group: &'a VolumeGroup,
+    ) -> impl Iterator<Item = &'a LogicalVolume> {
+        self.logical_volumes
+            .iter()
+            .filter(move |lv| lv.volume_group_id == volume_group.id)
+    }
+
+    /// Returns the path of the logical volume with the given ID, if it exists.
+    pub fn logical_volume_path(&self, id: &BlockDeviceId) -> Option<PathBuf> {
+        let lv = self.logical_volumes.iter().find(|lv| &lv.id == id)?;
+        let vg = self.volume_group(&lv.volume_group_id)?;
+        Some(lv.device_path(vg))
+    }
+}
+
+impl LogicalVolume {
+    /// Returns the path of the logical volume, given its volume group.
+    pub fn device_path(&self, volume_group: &VolumeGroup) -> PathBuf {
+        Path::new(DEV_PATH)
+            .join(&volume_group.name)
+            .join(&self.name)
+    }
+}
+
+/// Returns whether `name` is a valid name for a volume group, a logical volume or a thin pool.
+///
+/// LVM names may only contain letters, digits and `+_.-`, and must not start with `-`.
+pub fn is_valid_lvm_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 127
+        && !name.starts_with('-')
+        && name != "."
+        && name != ".."
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+_.-".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_lvm_name() {
+        for name in ["data", "var_lib", "vg-01", "pool.thin", "a+b"] {
+            assert!(is_valid_lvm_name(name), "'{name}' should be valid");
+        }
+        let too_long = "a".repeat(128);
+        for name in [
+            "",
+            "-data",
+            ".",
+            "..",
+            "var/lib",
+            "data lv",
+            too_long.as_str(),
+        ] {
+            assert!(!is_valid_lvm_name(name), "'{name}' should be invalid");
+        }
+    }
+
+    #[test]
+    fn test_logical_volume_path() {
+        let lvm = Lvm {
+            volume_groups: vec![VolumeGroup {
+                id: "vg".into(),
+                name: "data".into(),
+                devices: vec!["part".into()],
+                thin_pools: vec![],
+            }],
+            logical_volumes: vec![LogicalVolume {
+                id: "lv".into(),
+                name: "var".into(),
+                volume_group_id: "vg".into(),
+                size: PartitionSize::Grow,
+                thin_pool: None,
+            }],
+        };
+
+        assert_eq!(
+            lvm.logical_volume_path(&"lv".into()).unwrap(),
+            PathBuf::from("/dev/data/var")
+        );
+        assert_eq!(lvm.logical_volume_path(&"vg".into()), None);
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
     collections::{BTreeMap, HashSet},
-    path::Path,
+    path::{Component, Path},
 };
 
 use log::trace;
@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "schemars")]
 use schemars::JsonSchema;
-use swap::Swap;
+use swap::{Swap, SwapFile, Zram};
 
 use crate::{
     constants::{
@@ -26,6 +26,7 @@ pub mod disks;
 pub mod encryption;
 pub mod filesystem;
 pub mod filesystem_types;
+pub mod lvm;
 pub mod partitions;
 pub mod raid;
 pub mod storage_graph;
@@ -37,7 +38,8 @@ use self::{
     disks::Disk,
     encryption::Encryption,
     filesystem::{FileSystem, MountPointInfo},
-    partitions::Partition,
+    lvm::Lvm,
+    partitions::{Partition, PartitionType},
     raid::Raid,
     storage_graph::{
         builder::StorageGraphBuilder,
@@ -66,6 +68,10 @@ pub struct Storage {
     #[serde(default, skip_serializing_if = "is_default")]
     pub raid: Raid,
 
+    /// LVM configuration.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub lvm: Lvm,
+
     /// A/B update configuration.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ab_update: Option<AbUpdate>,
@@ -74,6 +80,16 @@ pub struct Storage {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub filesystems: Vec<FileSystem>,
 
+    /// IDs of additional ESP partitions that mirror the ESP.
+    ///
+    /// Each mirror must be a partition of type `esp` on a different disk than the ESP and the
+    /// other mirrors, and must not be used by anything else. Trident formats the mirrors during
+    /// clean install, copies the contents of the ESP to them whenever it changes the ESP, and
+    /// creates a UEFI boot entry for each of them, so that the host can still boot when the disk
+    /// of the ESP fails.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub esp_mirrors: Vec<BlockDeviceId>,
+
     /// Verity device configuration.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub verity: Vec<VerityDevice>,
@@ -91,6 +107,38 @@ pub struct Storage {
         )
     )]
     pub swap: Vec<Swap>,
+
+    /// Swap files to create in the target OS.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub swap_files: Vec<SwapFile>,
+
+    /// Compressed swap device in RAM to set up in the target OS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zram: Option<Zram>,
+
+    /// What Trident does when devices on the disks it is about to partition are in use by the
+    /// running system: mounted, used as swap, or held by an LVM volume, an encrypted volume or a
+    /// RAID array.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub in_use_devices: InUseDevicePolicy,
+}
+
+/// Policy for devices on the target disks that are in use when a clean install starts.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum InUseDevicePolicy {
+    /// # Fail
+    ///
+    /// Fail before anything is written to the disks, listing the devices in use.
+    #[default]
+    Fail,
+
+    /// # Tear down
+    ///
+    /// Unmount filesystems, disable swap and stop the LVM volumes, encrypted volumes and RAID
+    /// arrays holding the devices, then proceed.
+    TearDown,
 }
 
 impl Storage {
@@ -134,6 +182,14 @@ impl Storage {
             builder.add_node(raid.into());
         }
 
+        // Add LVM volume groups and logical volumes
+        for volume_group in &self.lvm.volume_groups {
+            builder.add_node(volume_group.into());
+        }
+        for logical_volume in &self.lvm.logical_volumes {
+            builder.add_node(logical_volume.into());
+        }
+
         // Add A/B update volume pairs
         if let Some(ab_update) = &self.ab_update {
             for pair in &ab_update.volume_pairs {
@@ -201,9 +257,104 @@ impl Storage {
         // Validation of verity devices
         self.validate_verity_devices(&graph)?;
 
+        self.validate_esp_mirrors(&graph)?;
+        self.validate_swap_files()?;
+        if let Some(zram) = &self.zram {
+            if zram.size.bytes() == 0 {
+                return Err(HostConfigurationStaticValidationError::InvalidZramSize);
+            }
+        }
+
         Ok(graph)
     }
 
+    /// Checks that ESP mirrors are unused ESP partitions, each on a different disk than the ESP
+    /// and the other mirrors.
+    fn validate_esp_mirrors(
+        &self,
+        graph: &StorageGraph,
+    ) -> Result<(), HostConfigurationStaticValidationError> {
+        let disk_of = |device_id: &BlockDeviceId| {
+            self.disks
+                .iter()
+                .find(|disk| {
+                    disk.partitions.iter().any(|p| &p.id == device_id)
+                        || disk.adopted_partitions.iter().any(|p| &p.id == device_id)
+                })
+                .map(|disk| &disk.id)
+        };
+
+        let mut disks = HashSet::new();
+        if !self.esp_mirrors.is_empty() {
+            let esp_disk = self.esp_filesystem().and_then(|(id, _)| disk_of(id));
+            disks.extend(esp_disk);
+        }
+
+        for mirror in &self.esp_mirrors {
+            let invalid =
+                |explanation: &str| HostConfigurationStaticValidationError::InvalidEspMirror {
+                    device_id: mirror.clone(),
+                    explanation: explanation.into(),
+                };
+
+            if disks.is_empty() {
+                return Err(invalid("the ESP must be a partition to be mirrored"));
+            }
+            let Some(partition) = self.get_partition(mirror) else {
+                return Err(invalid("it must be a partition created by Trident"));
+            };
+            if partition.partition_type != PartitionType::Esp {
+                return Err(invalid("it must be a partition of type 'esp'"));
+            }
+            if graph.has_dependents(mirror).unwrap_or_default() {
+                return Err(invalid("it must not be used by other devices"));
+            }
+            if !disk_of(mirror).is_some_and(|disk| disks.insert(disk)) {
+                return Err(invalid(
+                    "it must be on a different disk than the ESP and the other ESP mirrors",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that swap files have unique absolute paths on read-write volumes, and a size.
+    fn validate_swap_files(&self) -> Result<(), HostConfigurationStaticValidationError> {
+        let mut paths = HashSet::new();
+        for swap_file in &self.swap_files {
+            let invalid =
+                |explanation: &str| HostConfigurationStaticValidationError::InvalidSwapFile {
+                    path: swap_file.path.to_string_lossy().to_string(),
+                    explanation: explanation.into(),
+                };
+
+            if !swap_file.path.is_absolute()
+                || swap_file.path.parent().is_none()
+                || swap_file
+                    .path
+                    .components()
+                    .any(|c| c == Component::ParentDir)
+            {
+                return Err(invalid("path must be an absolute path to a file"));
+            }
+            if !paths.insert(&swap_file.path) {
+                return Err(invalid("path is used by another swap file"));
+            }
+            if swap_file.size.bytes() == 0 {
+                return Err(invalid("size must not be zero"));
+            }
+            if self
+                .path_to_mount_point_info(&swap_file.path)
+                .is_some_and(|mpi| mpi.mount_point.options.contains(MOUNT_OPTION_READ_ONLY))
+            {
+                return Err(invalid("path is on a read-only volume"));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Checks that mountpoints that are expected to be writable are mounted as
     /// writable. Currently only check /var/tmp.
     fn validate_writable_mount_points(&self) -> Result<(), HostConfigurationStaticValidationError> {
@@ -548,6 +699,7 @@ mod tests {
     use crate::{
         config::HostConfiguration,
         constants::{BOOT_MOUNT_POINT_PATH, ROOT_MOUNT_POINT_PATH},
+        primitives::bytes::ByteCount,
     };
 
     use self::{
@@ -558,6 +710,7 @@ mod tests {
         filesystem_types::NewFileSystemType,
         partitions::{PartitionSize, PartitionType},
         raid::{RaidLevel, SoftwareRaidArray},
+        swap::ZramCompressionAlgorithm,
     };
 
     use super::*;
@@ -2301,6 +2454,114 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_validate_swap_files() {
+        let mut storage = get_verity_storage();
+        storage.swap_files = vec![SwapFile {
+            path: "/var/swapfile".into(),
+            size: ByteCount::from(1 << 30),
+        }];
+        storage.zram = Some(Zram {
+            size: ByteCount::from(1 << 30),
+            compression_algorithm: Some(ZramCompressionAlgorithm::Zstd),
+        });
+        storage.validate(true).unwrap();
+
+        let invalid = |path: &str, explanation: &str| {
+            HostConfigurationStaticValidationError::InvalidSwapFile {
+                path: path.into(),
+                explanation: explanation.into(),
+            }
+        };
+
+        // The root filesystem is read-only.
+        storage.swap_files[0].path = "/swapfile".into();
+        assert_eq!(
+            storage.validate(true).unwrap_err(),
+            invalid("/swapfile", "path is on a read-only volume")
+        );
+
+        storage.swap_files[0].path = "var/swapfile".into();
+        assert_eq!(
+            storage.validate(true).unwrap_err(),
+            invalid("var/swapfile", "path must be an absolute path to a file")
+        );
+
+        storage.swap_files[0].path = "/var/swapfile".into();
+        storage.swap_files.push(storage.swap_files[0].clone());
+        assert_eq!(
+            storage.validate(true).unwrap_err(),
+            invalid("/var/swapfile", "path is used by another swap file")
+        );
+
+        storage.swap_files.truncate(1);
+        storage.swap_files[0].size = ByteCount::from(0);
+        assert_eq!(
+            storage.validate(true).unwrap_err(),
+            invalid("/var/swapfile", "size must not be zero")
+        );
+
+        storage.swap_files.clear();
+        storage.zram.as_mut().unwrap().size = ByteCount::from(0);
+        assert_eq!(
+            storage.validate(true).unwrap_err(),
+            HostConfigurationStaticValidationError::InvalidZramSize
+        );
+    }
+
+    #[test]
+    fn test_validate_esp_mirrors() {
+        let mut storage = get_verity_storage();
+        storage.disks[0].partitions = vec![
+            Partition {
+                id: "esp-mirror".into(),
+                partition_type: PartitionType::Esp,
+                size: PartitionSize::from_str("1M").unwrap(),
+            },
+            Partition {
+                id: "data".into(),
+                partition_type: PartitionType::LinuxGeneric,
+                size: PartitionSize::from_str("1G").unwrap(),
+            },
+        ];
+        storage.esp_mirrors = vec!["esp-mirror".into()];
+        storage.validate(true).unwrap();
+
+        let invalid = |device_id: &str, explanation: &str| {
+            HostConfigurationStaticValidationError::InvalidEspMirror {
+                device_id: device_id.into(),
+                explanation: explanation.into(),
+            }
+        };
+
+        storage.esp_mirrors = vec!["data".into()];
+        assert_eq!(
+            storage.validate(true).unwrap_err(),
+            invalid("data", "it must be a partition of type 'esp'")
+        );
+
+        storage.esp_mirrors = vec!["srv".into()];
+        assert_eq!(
+            storage.validate(true).unwrap_err(),
+            invalid("srv", "it must be a partition created by Trident")
+        );
+
+        // The mirror is on the disk of the ESP.
+        storage.disks[1].partitions.push(Partition {
+            id: "esp-mirror-2".into(),
+            partition_type: PartitionType::Esp,
+            size: PartitionSize::from_str("1M").unwrap(),
+        });
+        storage.esp_mirrors = vec!["esp-mirror".into(), "esp-mirror-2".into()];
+        assert_eq!(
+            storage.validate(true).unwrap_err(),
+            invalid(
+                "esp-mirror-2",
+                "it must be on a different disk than the ESP and the other ESP mirrors"
+            )
+        );
+    }
+
     #[test]
     fn test_validate_host_configuration_esp_on_raid() {
         let mut storage = Storage {
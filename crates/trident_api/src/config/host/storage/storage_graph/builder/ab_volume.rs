@@ -0,0 +1,166 @@
+use petgraph::{visit::IntoNodeReferences, Direction};
+
+use crate::{
+    config::DiskClass,
+    storage_graph::{
+        error::StorageGraphBuildError,
+        graph::{underlying_disks, NodeIndex, StoragePetgraph},
+        node::{BlockDevice, StorageGraphNode},
+        types::HostConfigBlockDevice,
+    },
+    BlockDeviceId,
+};
+
+/// Checks that both volumes of every A/B volume pair have the same layout.
+///
+/// Partition sizes and types, as well as the kind of the volumes themselves,
+/// are checked separately. This check makes sure that the devices stacked
+/// underneath each volume are also the same, e.g. that both volumes are
+/// encrypted volumes on top of RAID arrays of the same level and with the same
+/// number of members.
+pub(super) fn check_ab_volume_symmetry(
+    graph: &StoragePetgraph,
+) -> Result<(), StorageGraphBuildError> {
+    for (node_idx, node) in graph.node_references() {
+        let StorageGraphNode::BlockDevice(BlockDevice {
+            host_config_ref: HostConfigBlockDevice::ABVolume(ab_volume),
+            ..
+        }) = node
+        else {
+            continue;
+        };
+
+        // Missing targets are reported by the reference checks.
+        let (Some(volume_a_layout), Some(volume_b_layout)) = (
+            find_target(graph, node_idx, &ab_volume.volume_a_id).map(|idx| layout(graph, idx)),
+            find_target(graph, node_idx, &ab_volume.volume_b_id).map(|idx| layout(graph, idx)),
+        ) else {
+            continue;
+        };
+
+        if volume_a_layout != volume_b_layout {
+            return Err(StorageGraphBuildError::AbVolumeLayoutMismatch {
+                node_identifier: node.identifier(),
+                volume_a_id: ab_volume.volume_a_id.clone(),
+                volume_a_layout,
+                volume_b_id: ab_volume.volume_b_id.clone(),
+                volume_b_layout,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that both volumes of every A/B volume pair are placed on disks of the
+/// same class.
+///
+/// Only disks with kernel device paths, such as `/dev/nvme0n1`, can be
+/// classified here. Volumes on other disks are checked during pre-flight
+/// validation, once the device paths can be resolved on the target system.
+pub(super) fn check_ab_volume_disk_classes(
+    graph: &StoragePetgraph,
+) -> Result<(), StorageGraphBuildError> {
+    for (node_idx, node) in graph.node_references() {
+        let StorageGraphNode::BlockDevice(BlockDevice {
+            host_config_ref: HostConfigBlockDevice::ABVolume(ab_volume),
+            ..
+        }) = node
+        else {
+            continue;
+        };
+
+        let (Some(volume_a_classes), Some(volume_b_classes)) = (
+            find_target(graph, node_idx, &ab_volume.volume_a_id)
+                .and_then(|idx| disk_classes(graph, idx)),
+            find_target(graph, node_idx, &ab_volume.volume_b_id)
+                .and_then(|idx| disk_classes(graph, idx)),
+        ) else {
+            continue;
+        };
+
+        if volume_a_classes != volume_b_classes {
+            return Err(StorageGraphBuildError::AbVolumeDiskClassMismatch {
+                node_identifier: node.identifier(),
+                volume_a_id: ab_volume.volume_a_id.clone(),
+                volume_a_classes: join_classes(&volume_a_classes),
+                volume_b_id: ab_volume.volume_b_id.clone(),
+                volume_b_classes: join_classes(&volume_b_classes),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the sorted, deduplicated classes of the disks underneath the given
+/// node, or None if the class of any of them cannot be told from its device
+/// path.
+fn disk_classes(graph: &StoragePetgraph, node_idx: NodeIndex) -> Option<Vec<DiskClass>> {
+    let disks = underlying_disks(graph, node_idx);
+    if disks.is_empty() {
+        return None;
+    }
+
+    let mut classes = disks
+        .iter()
+        .map(|disk| DiskClass::from_device_path(&disk.device))
+        .collect::<Option<Vec<_>>>()?;
+    classes.sort();
+    classes.dedup();
+    Some(classes)
+}
+
+/// Returns a comma-separated list of disk classes.
+fn join_classes(classes: &[DiskClass]) -> String {
+    classes
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns the index of the target of `node_idx` with the given ID.
+fn find_target(
+    graph: &StoragePetgraph,
+    node_idx: NodeIndex,
+    target_id: &BlockDeviceId,
+) -> Option<NodeIndex> {
+    graph
+        .neighbors_directed(node_idx, Direction::Outgoing)
+        .find(|idx| graph[*idx].id() == Some(target_id))
+}
+
+/// Returns a description of the stack of block devices starting at the given
+/// node, e.g. `encrypted-volume on raid-array (raid1, 2 members) on
+/// partition`.
+///
+/// Device IDs, names and sizes are not part of the description, so two stacks
+/// with the same structure have the same description.
+fn layout(graph: &StoragePetgraph, node_idx: NodeIndex) -> String {
+    let node = &graph[node_idx];
+    let mut description = match node {
+        StorageGraphNode::BlockDevice(BlockDevice {
+            host_config_ref: HostConfigBlockDevice::RaidArray(raid_array),
+            ..
+        }) => format!(
+            "{} ({}, {} members)",
+            node.device_kind(),
+            raid_array.level,
+            raid_array.devices.len()
+        ),
+        _ => node.device_kind().to_string(),
+    };
+
+    // Members of a RAID array are checked to be homogeneous separately, so
+    // following the first target is enough to describe the whole stack.
+    if let Some(target_idx) = graph
+        .neighbors_directed(node_idx, Direction::Outgoing)
+        .next()
+    {
+        description.push_str(" on ");
+        description.push_str(&layout(graph, target_idx));
+    }
+
+    description
+}
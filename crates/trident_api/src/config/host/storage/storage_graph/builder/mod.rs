@@ -19,6 +19,8 @@
 //!     the same block device twice)
 //! - Call the validation rules defined in the `rules` module to perform
 //!   per-kind validation.
+//! - Check that both volumes of every A/B volume pair have the same layout and
+//!   are placed on disks of the same class.
 //! - Validate that all mount points are unique and valid.
 //! - Validate that all filesystems are valid.
 //!   - Check that all filesystems have a valid block device ID if required.
@@ -44,6 +46,7 @@ use super::{
     references::ReferenceKind,
 };
 
+mod ab_volume;
 mod devices;
 mod filesystems;
 mod partition;
@@ -116,6 +119,15 @@ impl StorageGraphBuilder {
         trace!("Checking verity partition types");
         partition::check_verity_partition_types(&graph)?;
 
+        // Check that both volumes of A/B volume pairs have the same layout
+        trace!("Checking A/B volume symmetry");
+        ab_volume::check_ab_volume_symmetry(&graph)?;
+
+        // Check that both volumes of A/B volume pairs are on disks of the same
+        // class
+        trace!("Checking A/B volume disk classes");
+        ab_volume::check_ab_volume_disk_classes(&graph)?;
+
         // Check RAID levels
         trace!("Checking RAID levels");
         raid::check_raid_levels(&graph)?;
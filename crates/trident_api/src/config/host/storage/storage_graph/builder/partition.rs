@@ -12,7 +12,7 @@ use crate::{
             graph::{NodeIndex, StoragePetgraph},
             node::StorageGraphNode,
             references::{ReferenceKind, SpecialReferenceKind},
-            types::HostConfigBlockDevice,
+            types::{BlkDevKind, HostConfigBlockDevice},
         },
         Partition, PartitionSize, PartitionType,
     },
@@ -406,6 +406,10 @@ fn explore_tree_partitions<'a, T>(
                 return Ok(BlkDevAttrList::new(&part.id, extractor(part)));
             }
 
+            // Logical volumes do not expose the partitions of their volume group, these are
+            // checked starting from the volume group itself.
+            HostConfigBlockDevice::LogicalVolume(_) => return Ok(BlkDevAttrList::default()),
+
             // Ignore other block devices
             _ => (),
         }
@@ -465,10 +469,14 @@ fn explore_tree_partitions<'a, T>(
 ///
 /// These will generally be filesystems or block devices that are not referenced
 /// by any other device or filesystem (this includes disks!).
+///
+/// Volume groups are always top-level nodes, as the logical volumes referencing
+/// them do not expose their partitions.
 fn get_top_level_nodes(
     graph: &StoragePetgraph,
 ) -> impl Iterator<Item = (NodeIndex, &StorageGraphNode)> {
-    graph
-        .node_references()
-        .filter(|(idx, _)| graph.edges_directed(*idx, Direction::Incoming).count() == 0)
+    graph.node_references().filter(|(idx, node)| {
+        node.device_kind() == BlkDevKind::VolumeGroup
+            || graph.edges_directed(*idx, Direction::Incoming).count() == 0
+    })
 }
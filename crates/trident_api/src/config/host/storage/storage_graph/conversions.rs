@@ -1,8 +1,8 @@
 // //! Conversions from config types to BlkDevNode
 
 use crate::config::{
-    AbVolumePair, AdoptedPartition, Disk, EncryptedVolume, FileSystem, FileSystemSource, Partition,
-    SoftwareRaidArray, Swap, VerityDevice,
+    AbVolumePair, AdoptedPartition, Disk, EncryptedVolume, FileSystem, FileSystemSource,
+    LogicalVolume, Partition, SoftwareRaidArray, Swap, VerityDevice, VolumeGroup,
 };
 
 use super::{
@@ -88,6 +88,26 @@ impl From<&VerityDevice> for StorageGraphNode {
     }
 }
 
+/// Get a StorageGraphNode from a VolumeGroup reference.
+impl From<&VolumeGroup> for StorageGraphNode {
+    fn from(volume_group: &VolumeGroup) -> Self {
+        Self::new_block_device(
+            volume_group.id.clone(),
+            HostConfigBlockDevice::VolumeGroup(volume_group.clone()),
+        )
+    }
+}
+
+/// Get a StorageGraphNode from a LogicalVolume reference.
+impl From<&LogicalVolume> for StorageGraphNode {
+    fn from(logical_volume: &LogicalVolume) -> Self {
+        Self::new_block_device(
+            logical_volume.id.clone(),
+            HostConfigBlockDevice::LogicalVolume(logical_volume.clone()),
+        )
+    }
+}
+
 /// Get a StorageGraphNode from a Filesystem reference.
 impl From<&FileSystem> for StorageGraphNode {
     fn from(fs: &FileSystem) -> Self {
@@ -32,6 +32,8 @@ impl Display for BlkDevKind {
             Self::ABVolume => write!(f, "ab-volume"),
             Self::EncryptedVolume => write!(f, "encrypted-volume"),
             Self::VerityDevice => write!(f, "verity-device"),
+            Self::VolumeGroup => write!(f, "volume-group"),
+            Self::LogicalVolume => write!(f, "logical-volume"),
         }
     }
 }
@@ -44,6 +46,8 @@ impl Display for BlkDevReferrerKind {
             Self::ABVolume => write!(f, "ab-volume"),
             Self::EncryptedVolume => write!(f, "encrypted-volume"),
             Self::VerityDevice => write!(f, "verity-device"),
+            Self::VolumeGroup => write!(f, "volume-group"),
+            Self::LogicalVolume => write!(f, "logical-volume"),
             Self::Swap => write!(f, "swap-device"),
             Self::FileSystemNew => write!(f, "filesystem-new"),
             Self::FileSystemEsp => write!(f, "filesystem-esp"),
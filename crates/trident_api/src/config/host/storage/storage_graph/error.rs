@@ -38,6 +38,34 @@ fn pluralize(count: usize, singular: &str, plural: &str) -> String {
 #[derive(thiserror::Error, Serialize, Deserialize, Debug, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub enum StorageGraphBuildError {
+    #[error(
+        "A/B volume pair {} has volumes with different layouts: '{volume_a_id}' is \
+            {volume_a_layout}, while '{volume_b_id}' is {volume_b_layout}. Both volumes \
+            of an A/B volume pair must be built from the same kinds of block devices",
+        pretty_node_id(.node_identifier)
+    )]
+    AbVolumeLayoutMismatch {
+        node_identifier: NodeIdentifier,
+        volume_a_id: BlockDeviceId,
+        volume_a_layout: String,
+        volume_b_id: BlockDeviceId,
+        volume_b_layout: String,
+    },
+
+    #[error(
+        "A/B volume pair {} has volumes on disks of different classes: '{volume_a_id}' is \
+            on {volume_a_classes}, while '{volume_b_id}' is on {volume_b_classes}. Place both \
+            volumes of an A/B volume pair on disks of the same class",
+        pretty_node_id(.node_identifier)
+    )]
+    AbVolumeDiskClassMismatch {
+        node_identifier: NodeIdentifier,
+        volume_a_id: BlockDeviceId,
+        volume_a_classes: String,
+        volume_b_id: BlockDeviceId,
+        volume_b_classes: String,
+    },
+
     #[error("Block device '{node_id}' of kind '{kind}' is invalid: {body}")]
     BasicCheckFailed {
         node_id: String,
@@ -210,8 +238,8 @@ pub enum StorageGraphBuildError {
 
     #[error(
         "Referrer {} of kind '{kind}' references partitions of \
-            different sizes",
-            pretty_node_id(.node_identifier)
+            different sizes. Set all of these partitions to the same fixed size",
+        pretty_node_id(.node_identifier)
     )]
     PartitionSizeMismatch {
         node_identifier: NodeIdentifier,
@@ -219,8 +247,9 @@ pub enum StorageGraphBuildError {
     },
 
     #[error(
-        "Referrer {} of kind '{kind}' references partition
-            '{partition_id}' of non-fixed size",
+        "Referrer {} of kind '{kind}' references partition \
+            '{partition_id}' of non-fixed size. Set the size of the partition to a \
+            fixed value instead of 'grow'",
         pretty_node_id(.node_identifier)
     )]
     PartitionSizeNotFixed {
@@ -231,7 +260,7 @@ pub enum StorageGraphBuildError {
 
     #[error(
         "Referrer {} of kind '{kind}' references partitions \
-            of different types",
+            of different types. Set all of these partitions to the same type",
         pretty_node_id(.node_identifier)
     )]
     PartitionTypeMismatch {
@@ -251,7 +280,8 @@ pub enum StorageGraphBuildError {
     },
 
     #[error(
-        "Referrer {} of kind '{kind}' references block devices of different kinds", 
+        "Referrer {} of kind '{kind}' references block devices of different kinds. \
+            All of its targets must be of the same kind, e.g. all partitions",
         pretty_node_id(.node_identifier)
     )]
     ReferenceKindMismatch {
@@ -9,7 +9,7 @@ use petgraph::{
 };
 
 use crate::{
-    config::{FileSystem, FileSystemSource, RaidLevel, VerityDevice},
+    config::{Disk, FileSystem, FileSystemSource, RaidLevel, VerityDevice},
     constants::{LUKS_HEADER_SIZE_IN_MIB, ROOT_MOUNT_POINT_PATH, USR_MOUNT_POINT_PATH},
     storage_graph::references::SpecialReferenceKind,
     BlockDeviceId,
@@ -222,6 +222,92 @@ impl StorageGraph {
         let (fs_idx, _) = self.filesystem_node_by_mount_point(mount_path)?;
         self.backing_verity_device(fs_idx).map(|(_, dev)| dev)
     }
+
+    /// Returns the disks underneath both volumes of every A/B volume pair.
+    pub fn ab_volume_disks(&self) -> Vec<AbVolumeDisks<'_>> {
+        self.inner
+            .node_references()
+            .filter_map(|(idx, node)| {
+                let StorageGraphNode::BlockDevice(BlockDevice {
+                    id,
+                    host_config_ref: HostConfigBlockDevice::ABVolume(ab_volume),
+                }) = node
+                else {
+                    return None;
+                };
+
+                let target = |target_id: &BlockDeviceId| {
+                    self.inner
+                        .neighbors_directed(idx, Direction::Outgoing)
+                        .find(|idx| self.inner[*idx].id() == Some(target_id))
+                };
+                let volume_a_idx = target(&ab_volume.volume_a_id)?;
+                let volume_b_idx = target(&ab_volume.volume_b_id)?;
+
+                Some(AbVolumeDisks {
+                    id,
+                    volume_a_disks: underlying_disks(&self.inner, volume_a_idx),
+                    volume_b_disks: underlying_disks(&self.inner, volume_b_idx),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Disks underneath both volumes of an A/B volume pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbVolumeDisks<'a> {
+    /// ID of the A/B volume pair.
+    pub id: &'a BlockDeviceId,
+
+    /// Disks underneath volume A.
+    pub volume_a_disks: Vec<&'a Disk>,
+
+    /// Disks underneath volume B.
+    pub volume_b_disks: Vec<&'a Disk>,
+}
+
+/// Returns the disks that the block device at the given node index is placed
+/// on, sorted by ID.
+///
+/// Partitions do not reference their disks in the graph, so the disk of a
+/// partition is the disk node that lists it.
+pub(super) fn underlying_disks(graph: &StoragePetgraph, idx: NodeIndex) -> Vec<&Disk> {
+    let disk_nodes = graph
+        .node_weights()
+        .filter_map(|node| match node {
+            StorageGraphNode::BlockDevice(BlockDevice {
+                host_config_ref: HostConfigBlockDevice::Disk(disk),
+                ..
+            }) => Some(disk),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut disks = Dfs::new(graph, idx)
+        .iter(graph)
+        .filter_map(|idx| {
+            let StorageGraphNode::BlockDevice(dev) = &graph[idx] else {
+                return None;
+            };
+
+            match &dev.host_config_ref {
+                HostConfigBlockDevice::Disk(disk) => Some(disk),
+                HostConfigBlockDevice::Partition(_)
+                | HostConfigBlockDevice::AdoptedPartition(_) => {
+                    disk_nodes.iter().copied().find(|disk| {
+                        disk.partitions.iter().any(|part| part.id == dev.id)
+                            || disk.adopted_partitions.iter().any(|part| part.id == dev.id)
+                    })
+                }
+                _ => None,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    disks.sort_by(|a, b| a.id.cmp(&b.id));
+    disks.dedup_by(|a, b| a.id == b.id);
+    disks
 }
 
 /// For a given NodeIndex, find the first outgoing edge with the given
@@ -318,6 +404,13 @@ fn block_device_size(graph: &StoragePetgraph, idx: NodeIndex) -> Option<u64> {
             Some(backing_size - (LUKS_HEADER_SIZE_IN_MIB as u64 * 1024 * 1024))
         }
 
+        // For logical volumes we report the size, when available. The size of thin logical
+        // volumes is virtual.
+        HostConfigBlockDevice::LogicalVolume(lv) => lv.size.to_bytes(),
+
+        // For volume groups, we report None, as the space is shared by their logical volumes.
+        HostConfigBlockDevice::VolumeGroup(_) => None,
+
         // For disks, we report None, as we don't know the size.
         HostConfigBlockDevice::Disk(_) => None,
 
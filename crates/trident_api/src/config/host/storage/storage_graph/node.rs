@@ -143,6 +143,16 @@ impl StorageGraphNode {
                 HostConfigBlockDevice::EncryptedVolume(encrypted_volume) => {
                     vec![StorageReference::new_regular(&encrypted_volume.device_id)]
                 }
+                HostConfigBlockDevice::VolumeGroup(volume_group) => volume_group
+                    .devices
+                    .iter()
+                    .map(StorageReference::new_regular)
+                    .collect(),
+                HostConfigBlockDevice::LogicalVolume(logical_volume) => {
+                    vec![StorageReference::new_regular(
+                        &logical_volume.volume_group_id,
+                    )]
+                }
                 HostConfigBlockDevice::VerityDevice(verity_device) => {
                     vec![
                         StorageReference::new_special(
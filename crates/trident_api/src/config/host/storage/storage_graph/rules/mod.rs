@@ -11,16 +11,19 @@
 //! The rules are declared roughly in the order they are evaluated.
 
 use std::{
+    collections::HashSet,
     os::unix::ffi::OsStrExt,
     path::{Path, PathBuf},
 };
 
-use anyhow::{bail, ensure, Error};
+use anyhow::{bail, ensure, Context, Error};
+use petgraph::Direction;
 
 use crate::{
     config::{
-        FileSystemSource, FileSystemType, HostConfigurationStaticValidationError,
-        NewFileSystemType, Partition, PartitionSize, PartitionType, RaidLevel,
+        is_valid_lvm_name, FileSystemSource, FileSystemType,
+        HostConfigurationStaticValidationError, NewFileSystemType, Partition, PartitionSize,
+        PartitionType, RaidLevel,
     },
     constants::ESP_MOUNT_POINT_PATH,
 };
@@ -90,12 +93,56 @@ impl HostConfigBlockDevice {
             Self::ABVolume(_) => (),
             Self::EncryptedVolume(_) => (),
             Self::VerityDevice(_) => (),
+            Self::VolumeGroup(vg) => {
+                ensure!(
+                    is_valid_lvm_name(&vg.name),
+                    "Volume group name '{}' is not a valid LVM name.",
+                    vg.name
+                );
+                let mut pool_names = HashSet::new();
+                for pool in &vg.thin_pools {
+                    ensure!(
+                        is_valid_lvm_name(&pool.name),
+                        "Thin pool name '{}' is not a valid LVM name.",
+                        pool.name
+                    );
+                    ensure!(
+                        pool_names.insert(&pool.name),
+                        "Thin pool name '{}' is used more than once.",
+                        pool.name
+                    );
+                    check_lvm_size(pool.size)?;
+                }
+            }
+            Self::LogicalVolume(lv) => {
+                ensure!(
+                    is_valid_lvm_name(&lv.name),
+                    "Logical volume name '{}' is not a valid LVM name.",
+                    lv.name
+                );
+                check_lvm_size(lv.size)?;
+                ensure!(
+                    lv.thin_pool.is_none() || lv.size != PartitionSize::Grow,
+                    "Thin logical volumes must have a fixed size."
+                );
+            }
         }
 
         Ok(())
     }
 }
 
+/// Checks that a fixed size of a logical volume or thin pool is a non-zero multiple of 4096 bytes.
+fn check_lvm_size(size: PartitionSize) -> Result<(), Error> {
+    if let PartitionSize::Fixed(size) = size {
+        ensure!(
+            size.bytes() > 0 && size.bytes() % 4096 == 0,
+            "Logical volume and thin pool sizes must be non-zero multiples of 4096 bytes."
+        );
+    }
+    Ok(())
+}
+
 impl FileSystemType {
     /// Returns whether a filesystem type expects a block device ID.
     ///
@@ -183,6 +230,8 @@ impl BlkDevReferrerKind {
             Self::ABVolume => ValidCardinality::new_exact(2),
             Self::EncryptedVolume => ValidCardinality::new_exact(1),
             Self::VerityDevice => ValidCardinality::new_exact(2),
+            Self::VolumeGroup => ValidCardinality::new_at_least(1),
+            Self::LogicalVolume => ValidCardinality::new_exact(1),
             Self::Swap => ValidCardinality::new_exact(1),
 
             Self::FileSystemNew => ValidCardinality::new_at_most(1),
@@ -208,6 +257,7 @@ impl BlkDevReferrerKind {
                     | BlkDevKindFlag::RaidArray
                     | BlkDevKindFlag::EncryptedVolume
                     | BlkDevKindFlag::ABVolume
+                    | BlkDevKindFlag::LogicalVolume
             }
             Self::FileSystemImage => {
                 BlkDevKindFlag::Partition
@@ -221,11 +271,24 @@ impl BlkDevReferrerKind {
                     | BlkDevKindFlag::AdoptedPartition
                     | BlkDevKindFlag::RaidArray
             }
-            Self::FileSystemAdopted => BlkDevKindFlag::AdoptedPartition,
+            Self::FileSystemAdopted => {
+                BlkDevKindFlag::AdoptedPartition | BlkDevKindFlag::LogicalVolume
+            }
             Self::VerityDevice => {
                 BlkDevKindFlag::Partition | BlkDevKindFlag::RaidArray | BlkDevKindFlag::ABVolume
             }
-            Self::Swap => BlkDevKindFlag::Partition | BlkDevKindFlag::EncryptedVolume,
+            Self::VolumeGroup => {
+                BlkDevKindFlag::Partition
+                    | BlkDevKindFlag::AdoptedPartition
+                    | BlkDevKindFlag::RaidArray
+                    | BlkDevKindFlag::EncryptedVolume
+            }
+            Self::LogicalVolume => BlkDevKindFlag::VolumeGroup,
+            Self::Swap => {
+                BlkDevKindFlag::Partition
+                    | BlkDevKindFlag::EncryptedVolume
+                    | BlkDevKindFlag::LogicalVolume
+            }
         }
     }
 }
@@ -264,11 +327,15 @@ impl BlkDevReferrerKind {
             | Self::ABVolume
             | Self::EncryptedVolume
             | Self::VerityDevice
+            | Self::VolumeGroup
             | Self::Swap
             | Self::FileSystemNew
             | Self::FileSystemEsp
             | Self::FileSystemAdopted
             | Self::FileSystemImage => BlkDevReferrerKindFlag::empty(),
+
+            // All logical volumes of a volume group share it.
+            Self::LogicalVolume => BlkDevReferrerKindFlag::LogicalVolume,
         }
     }
 
@@ -287,10 +354,12 @@ impl BlkDevReferrerKind {
             | Self::ABVolume
             | Self::EncryptedVolume
             | Self::VerityDevice
+            | Self::VolumeGroup
             | Self::Swap => true,
 
             // These only have one target, so enforcing this is meaningless.
-            Self::FileSystemNew
+            Self::LogicalVolume
+            | Self::FileSystemNew
             | Self::FileSystemEsp
             | Self::FileSystemAdopted
             | Self::FileSystemImage => false,
@@ -373,6 +442,15 @@ impl BlkDevKind {
                     Ok(Some(blkdev.unwrap_verity_device()?.name.as_bytes()))
                 }),
             )]),
+            Self::VolumeGroup => Some(vec![(
+                "name",
+                Box::new(|blkdev: &HostConfigBlockDevice| {
+                    Ok(Some(blkdev.unwrap_volume_group()?.name.as_bytes()))
+                }),
+            )]),
+            // Names of logical volumes only need to be unique within their volume group, which
+            // is checked with the targets of logical volumes.
+            Self::LogicalVolume => None,
         }
     }
 }
@@ -396,6 +474,9 @@ impl BlkDevReferrerKind {
             // Verity allows for data and hash devices to have different sizes.
             Self::VerityDevice => false,
 
+            // Volume groups pool the space of their physical volumes, whatever their sizes.
+            Self::VolumeGroup | Self::LogicalVolume => false,
+
             // These don't really care about partition sizes.
             Self::EncryptedVolume
             | Self::Swap
@@ -421,6 +502,9 @@ impl BlkDevReferrerKind {
             // Verity devices *expect* heterogeneous partition types.
             Self::VerityDevice => false,
 
+            // Physical volumes may be of any type, and logical volumes do not expose them.
+            Self::VolumeGroup | Self::LogicalVolume => false,
+
             // These care about having all underlying partitions be of the same
             // type.
             Self::EncryptedVolume
@@ -483,6 +567,15 @@ impl BlkDevReferrerKind {
                 PartitionType::UsrVerity,
                 PartitionType::LinuxGeneric,
             ]),
+            Self::VolumeGroup => AllowBlockList::Block(vec![
+                PartitionType::Esp,
+                PartitionType::Root,
+                PartitionType::RootVerity,
+                PartitionType::Usr,
+                PartitionType::UsrVerity,
+                PartitionType::Swap,
+            ]),
+            Self::LogicalVolume => AllowBlockList::Any,
             Self::FileSystemImage => AllowBlockList::Any,
             Self::Swap => AllowBlockList::Allow(vec![PartitionType::Swap]),
         }
@@ -574,16 +667,20 @@ impl BlkDevReferrerKind {
     /// RAID array are the same.
     pub(super) fn check_targets(
         &self,
-        _node_idx: NodeIndex,
-        _node: &StorageGraphNode,
-        _graph: &StoragePetgraph,
+        node_idx: NodeIndex,
+        node: &StorageGraphNode,
+        graph: &StoragePetgraph,
     ) -> Result<(), Error> {
         match self {
+            // Logical volumes must fit in the layout of their volume group.
+            Self::LogicalVolume => check_logical_volume_allocation(node_idx, node, graph)?,
+
             Self::None
             | Self::RaidArray
             | Self::ABVolume
             | Self::EncryptedVolume
             | Self::VerityDevice
+            | Self::VolumeGroup
             | Self::Swap
             | Self::FileSystemNew
             | Self::FileSystemEsp
@@ -594,3 +691,77 @@ impl BlkDevReferrerKind {
         Ok(())
     }
 }
+
+/// Checks that a logical volume fits in the layout of its volume group:
+///
+/// - Its name is unique among the logical volumes and thin pools of the volume group.
+/// - Its thin pool, if any, is defined in the volume group.
+/// - At most one logical volume or thin pool of the volume group grows to use the space left.
+fn check_logical_volume_allocation(
+    node_idx: NodeIndex,
+    node: &StorageGraphNode,
+    graph: &StoragePetgraph,
+) -> Result<(), Error> {
+    let lv = node
+        .as_block_device()
+        .context("Logical volume is not a block device")?
+        .host_config_ref
+        .unwrap_logical_volume()?;
+
+    // Logical volumes have exactly one target: their volume group.
+    let vg_idx = graph
+        .neighbors_directed(node_idx, Direction::Outgoing)
+        .next()
+        .context("Logical volume does not reference a volume group")?;
+    let vg = graph[vg_idx]
+        .as_block_device()
+        .context("Volume group is not a block device")?
+        .host_config_ref
+        .unwrap_volume_group()?;
+
+    if let Some(thin_pool) = &lv.thin_pool {
+        ensure!(
+            vg.thin_pools.iter().any(|pool| &pool.name == thin_pool),
+            "Thin pool '{thin_pool}' is not defined in volume group '{}'.",
+            vg.name
+        );
+    }
+
+    ensure!(
+        vg.thin_pools.iter().all(|pool| pool.name != lv.name),
+        "Logical volume name '{}' is already used by a thin pool of volume group '{}'.",
+        lv.name,
+        vg.name
+    );
+
+    let mut growing = vg
+        .thin_pools
+        .iter()
+        .filter(|pool| pool.size == PartitionSize::Grow)
+        .count();
+    for sibling_idx in graph.neighbors_directed(vg_idx, Direction::Incoming) {
+        let sibling = graph[sibling_idx]
+            .as_block_device()
+            .context("Logical volume is not a block device")?
+            .host_config_ref
+            .unwrap_logical_volume()?;
+        if sibling_idx != node_idx {
+            ensure!(
+                sibling.name != lv.name,
+                "Logical volume name '{}' is used more than once in volume group '{}'.",
+                lv.name,
+                vg.name
+            );
+        }
+        if sibling.thin_pool.is_none() && sibling.size == PartitionSize::Grow {
+            growing += 1;
+        }
+    }
+    ensure!(
+        growing <= 1,
+        "At most one logical volume or thin pool of volume group '{}' can grow.",
+        vg.name
+    );
+
+    Ok(())
+}
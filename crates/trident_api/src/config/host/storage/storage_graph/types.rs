@@ -9,8 +9,8 @@ use anyhow::{bail, Error};
 use serde::{Deserialize, Serialize};
 
 use crate::config::{
-    AbVolumePair, AdoptedPartition, Disk, EncryptedVolume, Partition, SoftwareRaidArray,
-    VerityDevice,
+    AbVolumePair, AdoptedPartition, Disk, EncryptedVolume, LogicalVolume, Partition,
+    SoftwareRaidArray, VerityDevice, VolumeGroup,
 };
 
 /// Enum for supported block device types
@@ -44,6 +44,12 @@ pub enum BlkDevKind {
 
     /// A verity device
     VerityDevice,
+
+    /// An LVM volume group
+    VolumeGroup,
+
+    /// An LVM logical volume
+    LogicalVolume,
 }
 
 bitflags::bitflags! {
@@ -59,6 +65,8 @@ bitflags::bitflags! {
         const ABVolume = 1 << 4;
         const EncryptedVolume = 1 << 5;
         const VerityDevice = 1 << 6;
+        const VolumeGroup = 1 << 7;
+        const LogicalVolume = 1 << 8;
     }
 }
 
@@ -85,6 +93,12 @@ pub enum HostConfigBlockDevice {
 
     /// A verity device
     VerityDevice(VerityDevice),
+
+    /// An LVM volume group
+    VolumeGroup(VolumeGroup),
+
+    /// An LVM logical volume
+    LogicalVolume(LogicalVolume),
 }
 
 /// Enum for referrer kinds.
@@ -115,6 +129,12 @@ pub enum BlkDevReferrerKind {
     /// A verity device
     VerityDevice,
 
+    /// An LVM volume group
+    VolumeGroup,
+
+    /// An LVM logical volume
+    LogicalVolume,
+
     /// A swap mount
     Swap,
 
@@ -149,6 +169,9 @@ bitflags::bitflags! {
         const FileSystemEsp = 1 << 7;
         const FileSystemAdopted = 1 << 8;
 
+        const VolumeGroup = 1 << 9;
+        const LogicalVolume = 1 << 10;
+
         // Groups:
         // Example:
         // const AnyImage = Self::Image.bits() | Self::ImageSysupdate.bits();
@@ -179,6 +202,8 @@ impl HostConfigBlockDevice {
             Self::ABVolume(_) => BlkDevKind::ABVolume,
             Self::EncryptedVolume(_) => BlkDevKind::EncryptedVolume,
             Self::VerityDevice(_) => BlkDevKind::VerityDevice,
+            Self::VolumeGroup(_) => BlkDevKind::VolumeGroup,
+            Self::LogicalVolume(_) => BlkDevKind::LogicalVolume,
         }
     }
 
@@ -192,6 +217,8 @@ impl HostConfigBlockDevice {
             Self::ABVolume(_) => BlkDevReferrerKind::ABVolume,
             Self::EncryptedVolume(_) => BlkDevReferrerKind::EncryptedVolume,
             Self::VerityDevice(_) => BlkDevReferrerKind::VerityDevice,
+            Self::VolumeGroup(_) => BlkDevReferrerKind::VolumeGroup,
+            Self::LogicalVolume(_) => BlkDevReferrerKind::LogicalVolume,
         }
     }
 
@@ -255,6 +282,22 @@ impl HostConfigBlockDevice {
             bail!("Block device is not a verity device")
         }
     }
+
+    pub(super) fn unwrap_volume_group(&self) -> Result<&VolumeGroup, Error> {
+        if let HostConfigBlockDevice::VolumeGroup(volume_group) = self {
+            Ok(volume_group)
+        } else {
+            bail!("Block device is not a volume group")
+        }
+    }
+
+    pub(super) fn unwrap_logical_volume(&self) -> Result<&LogicalVolume, Error> {
+        if let HostConfigBlockDevice::LogicalVolume(logical_volume) = self {
+            Ok(logical_volume)
+        } else {
+            bail!("Block device is not a logical volume")
+        }
+    }
 }
 
 /// Conversion from BlkDevKind to BlkDevKindFlag
@@ -270,6 +313,8 @@ impl BlkDevKind {
             Self::ABVolume => BlkDevKindFlag::ABVolume,
             Self::EncryptedVolume => BlkDevKindFlag::EncryptedVolume,
             Self::VerityDevice => BlkDevKindFlag::VerityDevice,
+            Self::VolumeGroup => BlkDevKindFlag::VolumeGroup,
+            Self::LogicalVolume => BlkDevKindFlag::LogicalVolume,
         }
     }
 }
@@ -283,6 +328,8 @@ impl BlkDevReferrerKind {
             Self::ABVolume => BlkDevReferrerKindFlag::ABVolume,
             Self::EncryptedVolume => BlkDevReferrerKindFlag::EncryptedVolume,
             Self::VerityDevice => BlkDevReferrerKindFlag::VerityDevice,
+            Self::VolumeGroup => BlkDevReferrerKindFlag::VolumeGroup,
+            Self::LogicalVolume => BlkDevReferrerKindFlag::LogicalVolume,
             Self::Swap => BlkDevReferrerKindFlag::SwapDevice,
             Self::FileSystemNew => BlkDevReferrerKindFlag::FileSystemNew,
             Self::FileSystemEsp => BlkDevReferrerKindFlag::FileSystemEsp,
@@ -324,6 +371,8 @@ impl BitFlagsBackingEnumVec<BlkDevKind> for BlkDevKindFlag {
                 Self::ABVolume => BlkDevKind::ABVolume,
                 Self::EncryptedVolume => BlkDevKind::EncryptedVolume,
                 Self::VerityDevice => BlkDevKind::VerityDevice,
+                Self::VolumeGroup => BlkDevKind::VolumeGroup,
+                Self::LogicalVolume => BlkDevKind::LogicalVolume,
                 _ => unreachable!("Invalid block device kind flag: {:?}", kind),
             })
             .collect()
@@ -339,6 +388,8 @@ impl BitFlagsBackingEnumVec<BlkDevReferrerKind> for BlkDevReferrerKindFlag {
                 Self::RaidArray => BlkDevReferrerKind::RaidArray,
                 Self::ABVolume => BlkDevReferrerKind::ABVolume,
                 Self::VerityDevice => BlkDevReferrerKind::VerityDevice,
+                Self::VolumeGroup => BlkDevReferrerKind::VolumeGroup,
+                Self::LogicalVolume => BlkDevReferrerKind::LogicalVolume,
                 Self::SwapDevice => BlkDevReferrerKind::Swap,
                 Self::EncryptedVolume => BlkDevReferrerKind::EncryptedVolume,
                 Self::FileSystemNew => BlkDevReferrerKind::FileSystemNew,
@@ -1110,6 +1110,175 @@ mod ab {
         );
     }
 
+    #[test]
+    fn test_ab_volume_layout_mismatch() {
+        let mut builder = StorageGraphBuilder::default();
+
+        for id in ["part1", "part2", "part3"] {
+            let part = Partition {
+                id: id.into(),
+                partition_type: PartitionType::LinuxGeneric,
+                size: PartitionSize::Fixed(4096.into()),
+            };
+            builder.add_node((&part).into());
+        }
+
+        let raid = SoftwareRaidArray {
+            id: "raid".into(),
+            name: "raid".into(),
+            level: RaidLevel::Raid1,
+            devices: vec!["part2".into(), "part3".into()],
+        };
+        builder.add_node((&raid).into());
+
+        let enc_a = EncryptedVolume {
+            id: "enc-a".into(),
+            device_id: "part1".into(),
+            device_name: "encrypted-a".into(),
+        };
+        builder.add_node((&enc_a).into());
+
+        let enc_b = EncryptedVolume {
+            id: "enc-b".into(),
+            device_id: "raid".into(),
+            device_name: "encrypted-b".into(),
+        };
+        builder.add_node((&enc_b).into());
+
+        let ab = AbVolumePair {
+            id: "ab".into(),
+            volume_a_id: "enc-a".into(),
+            volume_b_id: "enc-b".into(),
+        };
+        builder.add_node((&ab).into());
+
+        // Both volumes are encrypted volumes, but only one of them is on RAID
+        assert_eq!(
+            builder.build().unwrap_err(),
+            StorageGraphBuildError::AbVolumeLayoutMismatch {
+                node_identifier: StorageGraphNode::from(&ab).identifier(),
+                volume_a_id: "enc-a".into(),
+                volume_a_layout: "encrypted-volume on partition".into(),
+                volume_b_id: "enc-b".into(),
+                volume_b_layout: "encrypted-volume on raid-array (raid1, 2 members) on partition"
+                    .into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_ab_volume_raid_layout_mismatch() {
+        let mut builder = StorageGraphBuilder::default();
+
+        for id in ["part1", "part2", "part3", "part4", "part5"] {
+            let part = Partition {
+                id: id.into(),
+                partition_type: PartitionType::LinuxGeneric,
+                size: PartitionSize::Fixed(4096.into()),
+            };
+            builder.add_node((&part).into());
+        }
+
+        let raid_a = SoftwareRaidArray {
+            id: "raid-a".into(),
+            name: "raid-a".into(),
+            level: RaidLevel::Raid1,
+            devices: vec!["part1".into(), "part2".into()],
+        };
+        builder.add_node((&raid_a).into());
+
+        let raid_b = SoftwareRaidArray {
+            id: "raid-b".into(),
+            name: "raid-b".into(),
+            level: RaidLevel::Raid1,
+            devices: vec!["part3".into(), "part4".into(), "part5".into()],
+        };
+        builder.add_node((&raid_b).into());
+
+        let ab = AbVolumePair {
+            id: "ab".into(),
+            volume_a_id: "raid-a".into(),
+            volume_b_id: "raid-b".into(),
+        };
+        builder.add_node((&ab).into());
+
+        assert_eq!(
+            builder.build().unwrap_err(),
+            StorageGraphBuildError::AbVolumeLayoutMismatch {
+                node_identifier: StorageGraphNode::from(&ab).identifier(),
+                volume_a_id: "raid-a".into(),
+                volume_a_layout: "raid-array (raid1, 2 members) on partition".into(),
+                volume_b_id: "raid-b".into(),
+                volume_b_layout: "raid-array (raid1, 3 members) on partition".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_ab_volume_disk_class_mismatch() {
+        let mut builder = StorageGraphBuilder::default();
+
+        let part_a = Partition {
+            id: "part-a".into(),
+            partition_type: PartitionType::LinuxGeneric,
+            size: PartitionSize::Fixed(4096.into()),
+        };
+        let part_b = Partition {
+            id: "part-b".into(),
+            ..part_a.clone()
+        };
+        builder.add_node((&part_a).into());
+        builder.add_node((&part_b).into());
+
+        let disk_a = Disk {
+            id: "disk-a".into(),
+            device: "/dev/nvme0n1".into(),
+            partition_table_type: PartitionTableType::Gpt,
+            partitions: vec![part_a],
+            ..Default::default()
+        };
+        let mut disk_b = Disk {
+            id: "disk-b".into(),
+            device: "/dev/disk/by-path/pci-0000:00:1f.2-ata-1".into(),
+            partition_table_type: PartitionTableType::Gpt,
+            partitions: vec![part_b],
+            ..Default::default()
+        };
+        builder.add_node((&disk_a).into());
+        builder.add_node((&disk_b).into());
+
+        let ab = AbVolumePair {
+            id: "ab".into(),
+            volume_a_id: "part-a".into(),
+            volume_b_id: "part-b".into(),
+        };
+        builder.add_node((&ab).into());
+
+        // The class of disk B cannot be told from its path, so the check is
+        // left to pre-flight validation
+        builder.build().unwrap();
+
+        let mut builder = StorageGraphBuilder::default();
+        disk_b.device = "/dev/vda".into();
+        for part in disk_a.partitions.iter().chain(&disk_b.partitions) {
+            builder.add_node(part.into());
+        }
+        builder.add_node((&disk_a).into());
+        builder.add_node((&disk_b).into());
+        builder.add_node((&ab).into());
+
+        assert_eq!(
+            builder.build().unwrap_err(),
+            StorageGraphBuildError::AbVolumeDiskClassMismatch {
+                node_identifier: StorageGraphNode::from(&ab).identifier(),
+                volume_a_id: "part-a".into(),
+                volume_a_classes: "nvme".into(),
+                volume_b_id: "part-b".into(),
+                volume_b_classes: "virtio".into(),
+            }
+        );
+    }
+
     #[test]
     fn test_ab_volume_nonexistent_ref() {
         let mut builder = StorageGraphBuilder::default();
@@ -1134,3 +1303,172 @@ mod ab {
         );
     }
 }
+
+mod lvm {
+    use super::*;
+
+    use crate::config::{LogicalVolume, Swap, ThinPool, VolumeGroup};
+
+    /// Returns a builder with two partitions, a volume group on top of them and a thin pool.
+    fn lvm_builder() -> StorageGraphBuilder {
+        let mut builder = StorageGraphBuilder::default();
+
+        for id in ["part1", "part2"] {
+            builder.add_node(
+                (&Partition {
+                    id: id.into(),
+                    ..generic_partition()
+                })
+                    .into(),
+            );
+        }
+
+        builder.add_node(
+            (&VolumeGroup {
+                id: "vg".into(),
+                name: "data".into(),
+                devices: vec!["part1".into(), "part2".into()],
+                thin_pools: vec![ThinPool {
+                    name: "pool".into(),
+                    size: PartitionSize::Fixed(4096.into()),
+                }],
+            })
+                .into(),
+        );
+
+        builder
+    }
+
+    fn logical_volume(id: &str, size: PartitionSize, thin_pool: Option<&str>) -> LogicalVolume {
+        LogicalVolume {
+            id: id.into(),
+            name: id.into(),
+            volume_group_id: "vg".into(),
+            size,
+            thin_pool: thin_pool.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn test_lvm() {
+        let mut builder = lvm_builder();
+
+        let var = logical_volume("var", PartitionSize::Grow, None);
+        builder.add_node((&var).into());
+        let swap = logical_volume("swap", PartitionSize::Fixed(4096.into()), None);
+        builder.add_node((&swap).into());
+        let thin = logical_volume("thin", PartitionSize::Fixed(8192.into()), Some("pool"));
+        builder.add_node((&thin).into());
+
+        builder.add_node(
+            (&FileSystem {
+                device_id: Some("var".into()),
+                source: FileSystemSource::New(NewFileSystemType::Ext4),
+                mount_point: Some(MountPoint {
+                    path: "/var".into(),
+                    options: MountOptions::empty(),
+                }),
+            })
+                .into(),
+        );
+        builder.add_node(
+            (&Swap {
+                device_id: "swap".into(),
+            })
+                .into(),
+        );
+
+        builder.build().unwrap();
+    }
+
+    #[test]
+    fn test_lvm_invalid_targets() {
+        let cases = [
+            (
+                vec![
+                    logical_volume("lv1", PartitionSize::Fixed(4096.into()), None),
+                    LogicalVolume {
+                        name: "lv1".into(),
+                        ..logical_volume("lv2", PartitionSize::Fixed(4096.into()), None)
+                    },
+                ],
+                "Logical volume name 'lv1' is used more than once in volume group 'data'.",
+            ),
+            (
+                vec![logical_volume(
+                    "lv1",
+                    PartitionSize::Fixed(4096.into()),
+                    Some("other"),
+                )],
+                "Thin pool 'other' is not defined in volume group 'data'.",
+            ),
+            (
+                vec![logical_volume(
+                    "pool",
+                    PartitionSize::Fixed(4096.into()),
+                    None,
+                )],
+                "Logical volume name 'pool' is already used by a thin pool of volume group 'data'.",
+            ),
+            (
+                vec![
+                    logical_volume("lv1", PartitionSize::Grow, None),
+                    logical_volume("lv2", PartitionSize::Grow, None),
+                ],
+                "At most one logical volume or thin pool of volume group 'data' can grow.",
+            ),
+        ];
+
+        for (logical_volumes, body) in cases {
+            let mut builder = lvm_builder();
+            logical_volumes
+                .iter()
+                .for_each(|lv| builder.add_node(lv.into()));
+
+            assert_eq!(
+                builder.build().unwrap_err(),
+                StorageGraphBuildError::InvalidTargets {
+                    node_identifier: StorageGraphNode::from(&logical_volumes[0]).identifier(),
+                    kind: BlkDevReferrerKind::LogicalVolume,
+                    body: body.into(),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_lvm_thin_logical_volume_grow() {
+        let mut builder = lvm_builder();
+        builder.add_node((&logical_volume("thin", PartitionSize::Grow, Some("pool"))).into());
+
+        assert_eq!(
+            builder.build().unwrap_err(),
+            StorageGraphBuildError::BasicCheckFailed {
+                node_id: "thin".into(),
+                kind: BlkDevKind::LogicalVolume,
+                body: "Thin logical volumes must have a fixed size.".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_lvm_logical_volume_on_partition() {
+        let mut builder = lvm_builder();
+        let lv = LogicalVolume {
+            volume_group_id: "part1".into(),
+            ..logical_volume("lv", PartitionSize::Grow, None)
+        };
+        builder.add_node((&lv).into());
+
+        assert_eq!(
+            builder.build().unwrap_err(),
+            StorageGraphBuildError::InvalidReferenceKind {
+                node_identifier: StorageGraphNode::from(&lv).identifier(),
+                kind: BlkDevReferrerKind::LogicalVolume,
+                target_id: "part1".into(),
+                target_kind: BlkDevKind::Partition,
+                valid_references: BlkDevReferrerKind::LogicalVolume.compatible_kinds()
+            }
+        );
+    }
+}
@@ -1,11 +1,11 @@
-use std::str::FromStr;
+use std::{path::PathBuf, str::FromStr};
 
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "schemars")]
 use schemars::JsonSchema;
 
-use crate::BlockDeviceId;
+use crate::{primitives::bytes::ByteCount, BlockDeviceId};
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
@@ -35,3 +35,66 @@ impl crate::primitives::shortcuts::StringOrStructMetadata for Swap {
         crate::schema_helpers::BLOCK_DEVICE_ID_FORMAT
     }
 }
+
+/// Swap file configuration.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct SwapFile {
+    /// Absolute path of the swap file in the target OS. It must be on a read-write filesystem.
+    pub path: PathBuf,
+
+    /// Size of the swap file. Accepts a number of bytes, or a number followed by a `K`, `M`, `G`,
+    /// or `T` suffix, e.g. `2G`.
+    pub size: ByteCount,
+}
+
+/// Compressed swap device in RAM, set up in the target OS by systemd's zram-generator, which must
+/// be installed in the OS image.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Zram {
+    /// Size of the zram device. As the data is compressed, the device uses less memory than its
+    /// size. Accepts a number of bytes, or a number followed by a `K`, `M`, `G`, or `T` suffix,
+    /// e.g. `4G`.
+    pub size: ByteCount,
+
+    /// Compression algorithm of the zram device. When not set, the default of the kernel is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression_algorithm: Option<ZramCompressionAlgorithm>,
+}
+
+/// Compression algorithm of a zram device.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum ZramCompressionAlgorithm {
+    /// # LZO
+    Lzo,
+
+    /// # LZO-RLE
+    LzoRle,
+
+    /// # LZ4
+    Lz4,
+
+    /// # LZ4HC
+    Lz4hc,
+
+    /// # Zstandard
+    Zstd,
+}
+
+impl ZramCompressionAlgorithm {
+    /// Returns the name of the algorithm, as known to the kernel.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Lzo => "lzo",
+            Self::LzoRle => "lzo-rle",
+            Self::Lz4 => "lz4",
+            Self::Lz4hc => "lz4hc",
+            Self::Zstd => "zstd",
+        }
+    }
+}
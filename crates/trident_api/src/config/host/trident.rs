@@ -47,6 +47,33 @@ pub struct Trident {
     /// Optional URL to stream logs to. TODO: document the interface.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub logstream: Option<String>,
+
+    /// Optional URL of a fleet endpoint to register the host with. When set, Trident generates a
+    /// stable identity for the host, made of a UUID and a key pair, the first time the target OS
+    /// boots, and sends the UUID and public key to this URL with a POST request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registration_url: Option<String>,
+
+    /// TLS settings used to connect to `phonehome`, and to the tracestream endpoint derived from
+    /// it. Defaults to trusting the system root certificates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phonehome_tls: Option<EndpointTls>,
+
+    /// TLS settings used to connect to `logstream`. Defaults to trusting the system root
+    /// certificates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logstream_tls: Option<EndpointTls>,
+
+    /// TLS settings used to connect to `registrationUrl`. Defaults to trusting the system root
+    /// certificates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registration_tls: Option<EndpointTls>,
+
+    /// When set to `true`, Trident records the files under /etc that were modified locally before
+    /// staging an A/B update, in `configDrift` of the Host Status, so that operators can see which
+    /// local changes may need to be migrated to the updated OS. Defaults to `false`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub capture_config_drift: bool,
 }
 
 impl Default for Trident {
@@ -57,7 +84,100 @@ impl Default for Trident {
             datastore_path: Trident::default_datastore_path(),
             phonehome: Default::default(),
             logstream: Default::default(),
+            registration_url: Default::default(),
+            phonehome_tls: Default::default(),
+            logstream_tls: Default::default(),
+            registration_tls: Default::default(),
+            capture_config_drift: Default::default(),
+        }
+    }
+}
+
+/// TLS settings of an HTTPS endpoint Trident reports to, for management planes using a private
+/// PKI.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct EndpointTls {
+    /// Absolute path of a PEM file holding the root certificates to trust for the endpoint. When
+    /// set, the system root certificates are not trusted for this endpoint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_certificate_path: Option<PathBuf>,
+
+    /// Pins of the public keys the endpoint may present, in the `sha256//<base64>` format used by
+    /// curl's `--pinnedpubkey`: the base64-encoded SHA256 hash of the DER-encoded
+    /// SubjectPublicKeyInfo of the server certificate. When set, connections to a server whose
+    /// certificate matches none of the pins are rejected during the TLS handshake, before any
+    /// request is sent.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pinned_public_keys: Vec<String>,
+
+    /// Absolute path of a PEM file holding the client certificate to authenticate with, followed
+    /// by any intermediate certificates. Requires `clientKeyPath`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_certificate_path: Option<PathBuf>,
+
+    /// Absolute path of a PEM file holding the PKCS#8 private key of the client certificate.
+    /// Requires `clientCertificatePath`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key_path: Option<PathBuf>,
+}
+
+/// Prefix of public key pins, naming the hash algorithm.
+pub const PINNED_PUBLIC_KEY_PREFIX: &str = "sha256//";
+
+impl EndpointTls {
+    /// Validates the TLS settings of the endpoint named `endpoint`.
+    fn validate(&self, endpoint: &str) -> Result<(), HostConfigurationStaticValidationError> {
+        let invalid =
+            |explanation: String| HostConfigurationStaticValidationError::InvalidEndpointTls {
+                endpoint: endpoint.into(),
+                explanation,
+            };
+
+        for path in [
+            &self.ca_certificate_path,
+            &self.client_certificate_path,
+            &self.client_key_path,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if !path.is_absolute() {
+                return Err(invalid(format!(
+                    "path '{}' is not absolute",
+                    path.display()
+                )));
+            }
+        }
+
+        if self.client_certificate_path.is_some() != self.client_key_path.is_some() {
+            return Err(invalid(
+                "client certificate and client key must be set together".into(),
+            ));
+        }
+
+        for pin in &self.pinned_public_keys {
+            // A SHA256 hash is 32 bytes, i.e. 44 base64 characters ending with one padding
+            // character.
+            let valid = pin
+                .strip_prefix(PINNED_PUBLIC_KEY_PREFIX)
+                .is_some_and(|hash| {
+                    hash.len() == 44
+                        && hash.ends_with('=')
+                        && hash[..43]
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '/')
+                });
+            if !valid {
+                return Err(invalid(format!(
+                    "public key pin '{pin}' must be a base64-encoded SHA256 hash prefixed with \
+                    '{PINNED_PUBLIC_KEY_PREFIX}'"
+                )));
+            }
         }
+
+        Ok(())
     }
 }
 
@@ -96,6 +216,16 @@ impl Trident {
             );
         }
 
+        for (endpoint, tls) in [
+            ("phonehome", &self.phonehome_tls),
+            ("logstream", &self.logstream_tls),
+            ("registration", &self.registration_tls),
+        ] {
+            if let Some(tls) = tls {
+                tls.validate(endpoint)?;
+            }
+        }
+
         Ok(())
     }
 }
@@ -130,4 +260,48 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_validate_endpoint_tls() {
+        let mut tls = EndpointTls {
+            ca_certificate_path: Some("/etc/pki/management-ca.pem".into()),
+            pinned_public_keys: vec!["sha256//47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=".into()],
+            client_certificate_path: Some("/etc/pki/client.pem".into()),
+            client_key_path: Some("/etc/pki/client.key".into()),
+        };
+        tls.validate("logstream").unwrap();
+
+        tls.client_key_path = None;
+        tls.validate("logstream").unwrap_err();
+
+        tls.client_certificate_path = None;
+        tls.validate("logstream").unwrap();
+
+        tls.ca_certificate_path = Some("management-ca.pem".into());
+        tls.validate("logstream").unwrap_err();
+        tls.ca_certificate_path = None;
+
+        for pin in [
+            "47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=",
+            "sha1//47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=",
+            "sha256//47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuF",
+            "sha256//47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hS-FU=",
+        ] {
+            tls.pinned_public_keys = vec![pin.into()];
+            assert!(
+                matches!(
+                    tls.validate("logstream"),
+                    Err(HostConfigurationStaticValidationError::InvalidEndpointTls { .. })
+                ),
+                "Pin '{pin}' should be rejected"
+            );
+        }
+
+        let trident = Trident {
+            registration_url: Some("https://fleet.example.com/register".into()),
+            registration_tls: Some(tls),
+            ..Default::default()
+        };
+        trident.validate().unwrap_err();
+    }
 }
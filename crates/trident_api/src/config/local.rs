@@ -13,6 +13,15 @@ use super::host::HostConfiguration;
 pub enum HostConfigurationSource {
     File(PathBuf),
     Embedded(Box<HostConfiguration>),
+    /// Host Configuration file tracked in a Git repository.
+    Git {
+        /// URL of the Git repository.
+        url: String,
+        /// Branch or tag to fetch the Host Configuration from.
+        reference: String,
+        /// Path of the Host Configuration file, relative to the root of the repository.
+        path: PathBuf,
+    },
 }
 
 impl std::fmt::Display for HostConfigurationSource {
@@ -20,6 +29,11 @@ impl std::fmt::Display for HostConfigurationSource {
         match self {
             HostConfigurationSource::File(path) => write!(f, "file: {}", path.display()),
             HostConfigurationSource::Embedded(_) => write!(f, "embedded"),
+            HostConfigurationSource::Git {
+                url,
+                reference,
+                path,
+            } => write!(f, "git: {url}@{reference}:{}", path.display()),
         }
     }
 }
@@ -72,3 +86,24 @@ pub enum Operation {
     Stage,
     Finalize,
 }
+
+/// Configuration domain that an update can be restricted to, so that a change to one domain can be
+/// rolled out without re-evaluating the rest of the Host Configuration.
+#[derive(
+    Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, EnumIter,
+)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub enum UpdateScope {
+    /// The `os.sysexts` and `os.confexts` sections.
+    Sysexts,
+    /// The `scripts` and `os.additionalFiles` sections.
+    Scripts,
+    /// The OS configuration applied by OS modifier: users, hostname, modules, services, kernel
+    /// command line and SELinux.
+    Osconfig,
+    /// The `storage` section. Changes are only validated against the current storage
+    /// configuration, never applied.
+    Storage,
+    /// The `os.netplan` section. Changes are hot-applied with `netplan apply`.
+    Network,
+}
@@ -3,31 +3,51 @@ pub(crate) mod local;
 
 pub use host::{
     error::{HostConfigurationDynamicValidationError, HostConfigurationStaticValidationError},
-    health::{Check, Health, SystemdCheck},
+    health::{
+        BootComplete, BurnIn, Check, CheckRetry, CheckSeverity, ContainerCheck,
+        ContainerCheckWorkloads, ContainerRuntime, DiskHealthCheck, DiskHealthCheckCriteria,
+        DiskUsageCheck, DiskUsageCheckCriteria, Health, HealthMonitor, HttpCheck, HttpCheckRequest,
+        HttpMethod, KernelCheck, KernelCheckCriteria, MountPointFreeSpace, NetworkCheck,
+        NetworkCheckCriteria, RollbackPolicy, ScriptFailureAction, SystemdCheck, TcpCheck,
+        TcpCheckTarget, TimeSyncCheck, TimeSyncCheckCriteria,
+    },
     image::{ImageSha384, OsImage},
     os::{
         additional_files::AdditionalFile,
-        extensions::Extension,
+        boot_menu::{BootMenu, ConsoleMode, MenuVisibility},
+        cloud_init::{CloudInit, CloudInitFile},
+        container_images::{ContainerImage, RegistryCredentials},
+        extensions::{
+            Extension, ExtensionDelta, ExtensionMutability, ExtensionScope, ExtensionSource,
+            SysextPolicy,
+        },
+        identity::Identity,
+        interfaces::PinnedInterface,
+        migration::{Migration, MigrationTransform},
         modules::{LoadMode, Module},
+        networking::{Addressing, Bond, BondMode, Dns, Network, NetworkInterface, Route, Vlan},
         services::Services,
+        time::{ClockStep, Time},
         users::{Password, SshMode, User},
         KernelCommandLine, ManagementOs, Os, Selinux, SelinuxMode, UefiFallbackMode,
     },
     scripts::{Script, ScriptSource, Scripts, ServicingTypeSelection},
+    secret::{Secret, SecretSource},
     storage::abupdate::{AbUpdate, AbVolumePair},
     storage::{
-        disks::{Disk, PartitionTableType},
+        disks::{Disk, DiskClass, PartitionTableType},
         encryption::{EncryptedVolume, Encryption},
         filesystem::{FileSystem, FileSystemSource, MountOptions, MountPoint, MountPointInfo},
         filesystem_types::{AdoptedFileSystemType, FileSystemType, NewFileSystemType},
+        lvm::{is_valid_lvm_name, LogicalVolume, Lvm, ThinPool, VolumeGroup},
         partitions::{AdoptedPartition, Partition, PartitionSize, PartitionType},
         raid::{Raid, RaidLevel, SoftwareRaidArray},
-        swap::Swap,
+        swap::{Swap, SwapFile, Zram, ZramCompressionAlgorithm},
         verity::{VerityCorruptionOption, VerityDevice},
-        Storage,
+        InUseDevicePolicy, Storage,
     },
-    trident::Trident,
+    trident::{EndpointTls, Trident, PINNED_PUBLIC_KEY_PREFIX},
     HostConfiguration,
 };
 
-pub use local::{GrpcConfiguration, HostConfigurationSource, Operation, Operations};
+pub use local::{GrpcConfiguration, HostConfigurationSource, Operation, Operations, UpdateScope};
@@ -32,6 +32,12 @@ pub const TRIDENT_DATASTORE_PATH_DEFAULT: &str = "/var/lib/trident/datastore.sql
 /// Path to load the agent config from.
 pub const AGENT_CONFIG_PATH: &str = "/etc/trident/trident.conf";
 
+/// Path to load the values of the `${values.*}` variables of the Host Configuration from.
+pub const TEMPLATE_VALUES_PATH: &str = "/etc/trident/values.yaml";
+
+/// Current API version of the Host Configuration.
+pub const HOST_CONFIGURATION_API_VERSION: u32 = 1;
+
 // Block of volume agnostic path constants
 
 /// Boot directory name.
@@ -120,6 +126,9 @@ pub const TRIDENT_OVERLAY_WORK_RELATIVE_PATH: &str = "etc/work";
 /// Upper directory relative path (upper).
 pub const TRIDENT_OVERLAY_UPPER_RELATIVE_PATH: &str = "etc/upper";
 
+/// Dev path
+pub const DEV_PATH: &str = "/dev";
+
 /// Dev Mapper path
 pub const DEV_MAPPER_PATH: &str = "/dev/mapper";
 
@@ -165,6 +174,9 @@ pub const MOUNT_OPTION_READ_ONLY: &str = "ro";
 /// Primary location for storing sysexts on the target OS.
 pub const DEFAULT_SYSEXT_DIRECTORY: &str = "/var/lib/extensions/";
 
+/// Location of the sysexts merged into the initrd, both on the target OS and in the initrd.
+pub const INITRD_SYSEXT_DIRECTORY: &str = "/.extra/sysext";
+
 /// Primary location for storing confexts on the target OS.
 pub const DEFAULT_CONFEXT_DIRECTORY: &str = "/var/lib/confexts/";
 
@@ -200,6 +212,9 @@ pub mod internal_params {
     /// Disable automatic ejection of installation media after clean install.
     pub const DISABLE_MEDIA_EJECTION: &str = "disableMediaEjection";
 
+    /// Disable the check that the servicing OS has enough free space to stage an update.
+    pub const DISABLE_SPACE_FORECAST: &str = "disableSpaceForecast";
+
     /// Run dracut in debug mode to capture more output.
     pub const DRACUT_DEBUG: &str = "dracutDebug";
 
@@ -209,6 +224,9 @@ pub mod internal_params {
     /// Experimental support for UKIs.
     pub const ENABLE_UKI_SUPPORT: &str = "uki";
 
+    /// Number of extension images downloaded and inspected concurrently. Defaults to 4.
+    pub const EXTENSION_CONCURRENCY: &str = "extensionConcurrency";
+
     /// Enable configuration of http connection timeout for file downloads.
     pub const HTTP_CONNECTION_TIMEOUT_SECONDS: &str = "httpConnectionTimeoutSeconds";
 
@@ -11,6 +11,7 @@ use url::Url;
 
 use crate::{
     config::{HostConfigurationDynamicValidationError, HostConfigurationStaticValidationError},
+    messages::{self, MessageKey},
     primitives::bytes::ByteCount,
     status::{ServicingState, ServicingType},
     storage_graph::error::StorageGraphBuildError,
@@ -53,6 +54,9 @@ pub enum InitializationError {
         inner: ContainerConfigurationError,
     },
 
+    #[error("Failed to inspect {section}")]
+    InspectHost { section: String },
+
     #[error("Failed to load local Trident Host Status")]
     LoadHostStatus,
 
@@ -62,8 +66,14 @@ pub enum InitializationError {
     #[error("Failed to query for updates with Harpoon: {0}")]
     QueryForUpdates(String),
 
+    #[error("Failed to query Git repository '{url}' for reference '{reference}'")]
+    QueryGitReference { url: String, reference: String },
+
     #[error("Failed to read '/proc/cmdline'")]
     ReadCmdline,
+
+    #[error("Failed to serve artifacts on '{address}'")]
+    ServeArtifacts { address: String },
 }
 
 /// Identifies errors that occur when the host is running from a docker container, but the system
@@ -180,6 +190,29 @@ pub enum InvalidInputError {
     #[error("Image contains invalid agent configuration")]
     ImageBadAgentConfiguration,
 
+    #[error(
+        "Not enough free space on the filesystem holding '{path}' for {purposes}. {} [{required} \
+        bytes] are required but only {} [{available} bytes] are available, {} [{} bytes] short.",
+        required.to_human_readable_approx(), available.to_human_readable_approx(),
+        ByteCount(required.0.saturating_sub(available.0)).to_human_readable_approx(),
+        required.0.saturating_sub(available.0)
+    )]
+    InsufficientSpace {
+        path: String,
+        purposes: String,
+        required: ByteCount,
+        available: ByteCount,
+    },
+
+    #[error("Invalid annotation '{annotation}': {explanation}")]
+    InvalidAnnotation {
+        annotation: String,
+        explanation: String,
+    },
+
+    #[error("Found {count} problem(s) in the health checks of the Host Configuration")]
+    InvalidHealthChecks { count: usize },
+
     #[error("Host Configuration failed dynamic validation: {inner}")]
     InvalidHostConfigurationDynamic {
         #[from]
@@ -201,12 +234,27 @@ pub enum InvalidInputError {
     #[error("Failed to load COSI file from '{url}'")]
     LoadCosi { url: Url },
 
+    #[error("Failed to load fleet manifest from '{path}'")]
+    LoadFleetManifest { path: String },
+
     #[error("Failed to load Host Configuration file from '{path}'")]
     LoadHostConfigurationFile { path: String },
 
+    #[error(
+        "Failed to load Host Configuration '{path}' from Git repository '{url}' at '{reference}'"
+    )]
+    LoadHostConfigurationGit {
+        url: String,
+        reference: String,
+        path: String,
+    },
+
     #[error("Failed to load kickstart file from '{path}'")]
     LoadKickstart { path: String },
 
+    #[error("Failed to load message catalog from '{path}': {explanation}")]
+    LoadMessageCatalog { path: String, explanation: String },
+
     #[error("Provided '{actual}' architecture OS image, but system is '{expected}'")]
     MismatchedArchitecture {
         expected: &'static str,
@@ -223,6 +271,9 @@ pub enum InvalidInputError {
         os_img_fs_type: String,
     },
 
+    #[error("Failed to migrate Host Configuration file '{path}' to the current API version")]
+    MigrateHostConfiguration { path: String },
+
     #[error("An OS image must be provided.")]
     MissingOsImage,
 
@@ -237,7 +288,13 @@ pub enum InvalidInputError {
     )]
     MultibootWithoutAdoptedPartitions,
 
-    #[error("Old style configuration not supported, 'hostConfiguration:' tag must be removed")]
+    #[error("No OS image or extension image of the Host Configuration is available locally")]
+    NoArtifactsToServe,
+
+    #[error(
+        "Old style configuration not supported, 'hostConfiguration:' tag must be removed, e.g. \
+        with 'trident migrate-config'"
+    )]
     OldStyleConfiguration,
 
     #[error("Failed to parse Host Configuration file from '{path}'")]
@@ -249,6 +306,12 @@ pub enum InvalidInputError {
     #[error("Failed to read input file '{path}'")]
     ReadInputFile { path: String },
 
+    #[error("Failed to substitute the variables of Host Configuration file '{path}'")]
+    RenderHostConfigurationTemplate { path: String },
+
+    #[error("Failed to merge the fragments included by Host Configuration file '{path}'")]
+    ResolveHostConfigurationIncludes { path: String },
+
     #[error(
         "Host configuration requires the following filesystems to be placed on verity devices: {}\
         but the OS image provides verity data for the following filesystems: {}",
@@ -268,6 +331,9 @@ pub enum InvalidInputError {
     #[error("Failed to translate kickstart")]
     TranslateKickstart,
 
+    #[error("Health check '{name}' is not defined in the Host Configuration")]
+    UnknownHealthCheck { name: String },
+
     #[error("Found verity hash on ESP image. ESP filesystem should never have verity enabled.")]
     UnexpectedVerityOnEsp,
 
@@ -312,12 +378,18 @@ pub enum ServicingError {
     )]
     AbUpdateHealthCheckCommitCheck { expected_device_path: String },
 
+    #[error("Failed to apply netplan config")]
+    ApplyNetplanConfig,
+
     #[error("Failed to bind encryption to pcrlock policy")]
     BindEncryptionToPcrlockPolicy,
 
     #[error("Failed to check if the boot entry '{boot_entry}' exists via efibootmgr")]
     BootEntryCheck { boot_entry: String },
 
+    #[error("Hardware burn-in failed, refusing to provision the host: {failures}")]
+    BurnInFailed { failures: String },
+
     #[error("Failed to canonicalize path '{path}'")]
     CanonicalizePath { path: String },
 
@@ -342,6 +414,9 @@ pub enum ServicingError {
     #[error("Failed to clean up pre-existing LUKS2-encrypted volumes")]
     CleanupEncryption,
 
+    #[error("Failed to deactivate pre-existing LVM volume groups")]
+    CleanupLvm,
+
     #[error("Failed to clean up pre-existing RAID arrays")]
     CleanupRaid,
 
@@ -357,6 +432,9 @@ pub enum ServicingError {
         explanation: String,
     },
 
+    #[error("Failed to confirm network configuration change")]
+    ConfirmNetworkChange,
+
     #[error("Failed to create extension image directories on target OS")]
     CreateExtensionImageDirectories,
 
@@ -374,6 +452,15 @@ pub enum ServicingError {
     #[error("Failed to copy Trident binary to target OS")]
     CopyTridentBinary,
 
+    #[error("Failed to set up timezone, locale and keymap")]
+    ConfigureIdentity,
+
+    #[error("Failed to configure time synchronization")]
+    ConfigureTimeSynchronization,
+
+    #[error("Failed to configure zram device")]
+    ConfigureZram,
+
     #[error("Failed to create boot entry '{boot_entry}' via efibootmgr")]
     CreateBootEntry { boot_entry: String },
 
@@ -389,6 +476,9 @@ pub enum ServicingError {
     #[error("Failed to create filesystems")]
     CreateFilesystems,
 
+    #[error("Failed to create LVM volume groups and logical volumes")]
+    CreateLvm,
+
     #[error("Failed to create machine ID for verity")]
     CreateMachineId,
 
@@ -407,6 +497,9 @@ pub enum ServicingError {
     #[error("Failed to create swap space")]
     CreateSwap,
 
+    #[error("Failed to create swap files")]
+    CreateSwapFiles,
+
     #[error("Failed to create verity devices")]
     CreateVerity,
 
@@ -445,6 +538,9 @@ pub enum ServicingError {
     #[error("Failed to exit chroot")]
     ExitChroot,
 
+    #[error("Extension images of the Host Configuration are not merged after a refresh:{images}")]
+    ExtensionImagesNotMerged { images: String },
+
     #[error(
         "Failed to find underlying block device with id '{device_id}' for encrypted volume \
         '{encrypted_volume}'"
@@ -457,9 +553,15 @@ pub enum ServicingError {
     #[error("Failed to find staged file at path '{staged_file}'")]
     FindStagedFile { staged_file: String },
 
+    #[error("Operation failed on hosts of the fleet:{hosts}")]
+    FleetHostsFailed { hosts: String },
+
     #[error("Failed to generate fstab at path '{fstab_path}'")]
     GenerateFstab { fstab_path: String },
 
+    #[error("Failed to generate host identity")]
+    GenerateHostIdentity,
+
     #[error("Failed to generate Netplan config")]
     GenerateNetplanConfig,
 
@@ -505,12 +607,24 @@ pub enum ServicingError {
     #[error("Failed to get block device path of root-verity data device")]
     GetRootVerityDataDevPath,
 
+    #[error("Failed to get root hash of verity device from OS image")]
+    GetVerityRootHash,
+
+    #[error("Failed to get status of verity device '{device_name}'")]
+    GetVerityStatus { device_name: String },
+
     #[error("Failed to get SELINUX")]
     GetSelinuxMode,
 
     #[error("Failed to get SELINUXTYPE")]
     GetSelinuxType,
 
+    #[error("Failed to install SELinux policy modules")]
+    InstallSelinuxPolicyModules,
+
+    #[error("Failed to install packages")]
+    InstallPackages,
+
     #[error("Failed health check(s) during '{servicing_type}': '{details}'")]
     HealthChecksFailed {
         details: String,
@@ -520,24 +634,54 @@ pub enum ServicingError {
     #[error("Failed to list boot entries via efibootmgr or parse them")]
     ListAndParseBootEntries,
 
+    #[error("Failed to list extension images via systemd-sysext and systemd-confext")]
+    ListExtensionImages,
+
+    #[error("Failed to lock the extension images against other Trident invocations")]
+    LockExtensionImages,
+
+    #[error("Failed to migrate files from the servicing OS into the updated OS")]
+    MigrateFiles,
+
     #[error("Failed to mount execroot binary")]
     MountExecrootBinary,
 
     #[error("Failed to mount newroot")]
     MountNewroot,
 
+    #[error("Failed to mount archive '{path}' of container image '{image}' into the target OS")]
+    MountContainerImageArchive { image: String, path: String },
+
     #[error("Failed to mount special directory '{dir}' in newroot")]
     MountNewrootSpecialDir { dir: String },
 
+    #[error("Failed to mount package repository '{path}' into the target OS")]
+    MountPackageRepository { path: String },
+
     #[error("Failed to mount overlay '{target}'")]
     MountOverlay { target: String },
 
+    #[error(
+        "Network configuration change was not confirmed within {timeout_seconds} seconds, \
+        reverted to the previous network configuration"
+    )]
+    NetworkChangeNotConfirmed { timeout_seconds: u64 },
+
     #[error("Failed to open firewall")]
     OpenFirewall,
 
     #[error("Failed to parse non-Unicode path '{path}'")]
     PathIsNotUnicode { path: String },
 
+    #[error("Failed to power off")]
+    PowerOff,
+
+    #[error("Failed to preload container images into the target OS")]
+    PreloadContainerImages,
+
+    #[error("Failed to prune unmanaged extension images")]
+    PruneExtensionImages,
+
     #[error("Failed to do a read operation with efibootmgr")]
     ReadEfibootmgr,
 
@@ -559,15 +703,33 @@ pub enum ServicingError {
     #[error("Failed to rebuild RAID arrays")]
     RebuildRaid,
 
+    #[error("Failed to refresh {extension_type} images: {inner}")]
+    RefreshExtensionImages {
+        extension_type: String,
+        inner: ExtensionRefreshError,
+    },
+
     #[error("Failed to regenerate initrd")]
     RegenerateInitrd,
 
+    #[error("Failed to register host with fleet endpoint '{url}'")]
+    RegisterHost { url: String },
+
+    #[error("Failed to remove extension images")]
+    RemoveExtensionImages,
+
     #[error("Failed to remove crypttab at path '{crypttab_path}'")]
     RemoveCrypttab { crypttab_path: String },
 
     #[error("Failed to remove the pre-existing pcrlock policy")]
     RemovePcrlockPolicy,
 
+    #[error("Failed to resolve hostname template")]
+    ResolveHostname,
+
+    #[error("Failed to revert to the previous network configuration")]
+    RevertNetworkConfig,
+
     #[error(
         "Failed to match current root device path '{root_device_path}' to either root volume A \
         path '{root_volume_a_path}' or B path '{root_volume_b_path}'"
@@ -605,6 +767,9 @@ pub enum ServicingError {
     #[error("Failed to start network")]
     StartNetwork,
 
+    #[error("Failed to synchronize the ESP mirrors")]
+    SyncEspMirrors,
+
     #[error("Service(s) '{services}' did not become active/running within {timeout_seconds} seconds: {last_error}")]
     SystemdCheckTimeout {
         services: String,
@@ -612,6 +777,9 @@ pub enum ServicingError {
         last_error: String,
     },
 
+    #[error("Target devices are in use")]
+    TargetDevicesInUse,
+
     #[error("Failed to update UKI")]
     UpdateUki,
 
@@ -627,6 +795,19 @@ pub enum ServicingError {
     #[error("Trident rebuild-raid validation failed")]
     ValidateRebuildRaid,
 
+    #[error(
+        "Verity device '{device_name}' was opened with root hash '{actual}' instead of the root \
+        hash '{expected}' of the deployed OS image"
+    )]
+    VerityRootHashMismatch {
+        device_name: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("Failed to verify the signature of extension image '{name}'")]
+    VerifyExtensionImageSignature { name: String },
+
     #[error("Failed to unmount newroot, unable to unmount '{dir}'")]
     UnmountNewroot { dir: String },
 
@@ -642,8 +823,17 @@ pub enum ServicingError {
     #[error("Failed to write an additional file '{file_name}'")]
     WriteAdditionalFile { file_name: String },
 
+    #[error("Failed to write the cloud-init NoCloud seed")]
+    WriteCloudInitSeed,
+
     #[error("Failed to write Netplan config")]
     WriteNetplanConfig,
+
+    #[error("Failed to write systemd-networkd config")]
+    WriteNetworkdConfig,
+
+    #[error("Failed to write link files for pinned network interfaces")]
+    WritePinnedInterfaceLinkFiles,
 }
 
 /// Identifies errors that occur when interacting with a misconfigured datastore.
@@ -669,6 +859,23 @@ pub enum DatastoreError {
     WriteToDatastore,
 }
 
+/// Identifies the cause of a failure to merge sysext or confext images into the running OS.
+#[derive(Debug, Eq, thiserror::Error, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExtensionRefreshError {
+    #[error("Extension image '{extension}' failed verification")]
+    ImageVerification { extension: String },
+
+    #[error("Extension image '{extension}' is not compatible with the host OS")]
+    IncompatibleExtension { extension: String },
+
+    #[error("Overlay on '{hierarchy}' is busy")]
+    OverlayBusy { hierarchy: String },
+
+    #[error("Unrecognized failure, see the logs for details")]
+    Unrecognized,
+}
+
 /// Identifies errors that occur when clean install or update fail due to the current configuration
 /// of the host.
 #[derive(Debug, Eq, thiserror::Error, Serialize, Deserialize, PartialEq)]
@@ -716,6 +923,24 @@ pub enum ErrorKind {
     UnsupportedConfiguration(#[from] UnsupportedConfigurationError),
 }
 
+impl ErrorKind {
+    /// Returns the key of the message summarizing errors of this category.
+    fn summary_key(&self) -> MessageKey {
+        match self {
+            ErrorKind::ExecutionEnvironmentMisconfiguration(_) => {
+                MessageKey::ErrorSummaryExecutionEnvironmentMisconfiguration
+            }
+            ErrorKind::Initialization(_) => MessageKey::ErrorSummaryInitialization,
+            ErrorKind::Internal(_) => MessageKey::ErrorSummaryInternal,
+            ErrorKind::InvalidInput(_) => MessageKey::ErrorSummaryInvalidInput,
+            ErrorKind::Servicing(_) => MessageKey::ErrorSummaryServicing,
+            ErrorKind::UnsupportedConfiguration(_) => {
+                MessageKey::ErrorSummaryUnsupportedConfiguration
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 struct TridentErrorInner {
     kind: ErrorKind,
@@ -762,6 +987,11 @@ impl TridentError {
     pub fn kind(&self) -> &ErrorKind {
         &self.0.kind
     }
+
+    /// Returns a one-line summary of the error for operators, using the installed message catalog.
+    pub fn summary(&self) -> String {
+        messages::render(self.0.kind.summary_key(), &[])
+    }
 }
 
 pub trait ReportError<T, K> {
@@ -856,19 +1086,7 @@ impl Serialize for TridentError {
 
 impl Debug for TridentError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.0.kind {
-            ErrorKind::ExecutionEnvironmentMisconfiguration(_) => writeln!(
-                f,
-                "Trident failed due to a misconfigured execution environment"
-            )?,
-            ErrorKind::Initialization(_) => writeln!(f, "Trident failed to initialize")?,
-            ErrorKind::Internal(_) => writeln!(f, "Trident failed due to an internal error")?,
-            ErrorKind::InvalidInput(_) => writeln!(f, "Trident failed due to invalid input")?,
-            ErrorKind::Servicing(_) => writeln!(f, "Trident failed due to a servicing error")?,
-            ErrorKind::UnsupportedConfiguration(_) => {
-                writeln!(f, "Trident failed due to an unsupported configuration")?
-            }
-        }
+        writeln!(f, "{}", messages::english(self.0.kind.summary_key(), &[]))?;
 
         writeln!(f, "\nContext:")?;
         writeln!(
@@ -1,6 +1,7 @@
 pub mod config;
 pub mod constants;
 pub mod error;
+pub mod messages;
 pub mod misc;
 pub mod primitives;
 pub mod status;
@@ -0,0 +1,344 @@
+//! Catalog of operator-facing messages.
+//!
+//! Messages shown to operators are looked up by key, so that vendors embedding Trident can replace
+//! them with translations. Templates may contain parameters in the form `{name}`, which are
+//! substituted when the message is rendered. Log messages are not part of the catalog and are
+//! always in English.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::OnceLock,
+};
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+/// Catalog installed for the whole process, if any.
+static CATALOG: OnceLock<MessageCatalog> = OnceLock::new();
+
+/// Key of an operator-facing message.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    EnumIter,
+    IntoStaticStr,
+)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+#[strum(serialize_all = "kebab-case")]
+pub enum MessageKey {
+    /// A command failed. Parameters: `command`.
+    CommandFailed,
+    /// Summary of an error caused by a misconfigured execution environment.
+    ErrorSummaryExecutionEnvironmentMisconfiguration,
+    /// Summary of an error that occurred while Trident was initializing.
+    ErrorSummaryInitialization,
+    /// Summary of an error caused by an internal failure in Trident.
+    ErrorSummaryInternal,
+    /// Summary of an error caused by invalid input.
+    ErrorSummaryInvalidInput,
+    /// Summary of an error that occurred during servicing.
+    ErrorSummaryServicing,
+    /// Summary of an error caused by the current configuration of the host.
+    ErrorSummaryUnsupportedConfiguration,
+    /// Result of a host on which a fleet operation failed.
+    FleetResultFailed,
+    /// Result of a host on which a fleet operation succeeded.
+    FleetResultOk,
+    /// Result of a host that finalized servicing but has not committed the new OS yet.
+    FleetResultPending,
+    /// Shown instead of the extension images of a hierarchy that has none.
+    HierarchyNoExtensions,
+    /// Heading of the extension images of a plan.
+    PlanExtensionImages,
+    /// Reason for keeping an extension image that is already installed.
+    PlanExtensionInstalled,
+    /// Reason for keeping an installed extension image that is moved. Parameters: `path`.
+    PlanExtensionMoved,
+    /// Reason for merging an extension image that is not installed yet.
+    PlanExtensionNotInstalled,
+    /// Reason for unmerging an extension image that was removed from the Host Configuration.
+    PlanExtensionRemoved,
+    /// First line of a plan. Parameters: `servicing_type`.
+    PlanServicingType,
+    /// Plan step selecting the inactive A/B volumes. Parameters: `count`.
+    PlanStepAbVolumes,
+    /// Plan step installing the bootloader.
+    PlanStepBootloader,
+    /// Plan step encrypting volumes. Parameters: `count`.
+    PlanStepEncryption,
+    /// Plan step installing extension images. Parameters: `sysexts`, `confexts`.
+    PlanStepExtensions,
+    /// Plan step finalizing the servicing.
+    PlanStepFinalize,
+    /// Plan step writing a filesystem from the OS image. Parameters: `target`.
+    PlanStepImage,
+    /// Plan step creating LVM volumes. Parameters: `volume_groups`, `logical_volumes`.
+    PlanStepLvm,
+    /// Plan step creating an empty filesystem. Parameters: `filesystem_type`, `target`.
+    PlanStepMkfs,
+    /// Plan step configuring the target OS.
+    PlanStepOsConfig,
+    /// Plan step partitioning a disk. Parameters: `disk`, `count`.
+    PlanStepPartition,
+    /// Plan step running post-configure scripts. Parameters: `count`.
+    PlanStepPostConfigureScripts,
+    /// Plan step running post-provision scripts. Parameters: `count`.
+    PlanStepPostProvisionScripts,
+    /// Plan step running pre-servicing scripts. Parameters: `count`.
+    PlanStepPreServicingScripts,
+    /// Plan step creating software RAID arrays. Parameters: `count`.
+    PlanStepRaid,
+    /// Plan step setting up verity devices. Parameters: `count`.
+    PlanStepVerity,
+    /// Action taken on an unmanaged extension image that is kept.
+    PruneActionKept,
+    /// Action taken on an unmanaged extension image that was pruned.
+    PruneActionPruned,
+    /// Action that would be taken on an unmanaged extension image in a dry run.
+    PruneActionWouldPrune,
+    /// Table column header.
+    TableAction,
+    /// Table column header.
+    TableError,
+    /// Table column header.
+    TableExtensions,
+    /// Table column header.
+    TableHierarchy,
+    /// Table column header.
+    TableHost,
+    /// Table column header.
+    TableId,
+    /// Table column header.
+    TableKind,
+    /// Table column header.
+    TableName,
+    /// Table column header.
+    TablePath,
+    /// Table column header.
+    TableResult,
+    /// Table column header.
+    TableScope,
+    /// Table column header.
+    TableState,
+    /// Table column header.
+    TableVersion,
+}
+
+impl MessageKey {
+    /// Returns the English template of the message.
+    pub fn english(&self) -> &'static str {
+        match self {
+            Self::CommandFailed => "Failed to execute '{command}' command",
+            Self::ErrorSummaryExecutionEnvironmentMisconfiguration => {
+                "Trident failed due to a misconfigured execution environment"
+            }
+            Self::ErrorSummaryInitialization => "Trident failed to initialize",
+            Self::ErrorSummaryInternal => "Trident failed due to an internal error",
+            Self::ErrorSummaryInvalidInput => "Trident failed due to invalid input",
+            Self::ErrorSummaryServicing => "Trident failed due to a servicing error",
+            Self::ErrorSummaryUnsupportedConfiguration => {
+                "Trident failed due to an unsupported configuration"
+            }
+            Self::FleetResultFailed => "failed",
+            Self::FleetResultOk => "ok",
+            Self::FleetResultPending => "pending",
+            Self::HierarchyNoExtensions => "none",
+            Self::PlanExtensionImages => "Extension images:",
+            Self::PlanExtensionInstalled => "Image with the same SHA384 is already installed",
+            Self::PlanExtensionMoved => "Image is already installed, and is moved to '{path}'",
+            Self::PlanExtensionNotInstalled => "Image is not installed yet",
+            Self::PlanExtensionRemoved => "Image is no longer in the Host Configuration",
+            Self::PlanServicingType => "Servicing type: {servicing_type}",
+            Self::PlanStepAbVolumes => "Select the inactive volume of {count} A/B volume pair(s)",
+            Self::PlanStepBootloader => "Install the bootloader and configure boot entries",
+            Self::PlanStepEncryption => "Encrypt {count} volume(s)",
+            Self::PlanStepExtensions => "Install {sysexts} sysext(s) and {confexts} confext(s)",
+            Self::PlanStepFinalize => "Update the boot order and reboot",
+            Self::PlanStepImage => "Write OS image filesystem for '{target}'",
+            Self::PlanStepLvm => "Create {volume_groups} LVM volume group(s) with {logical_volumes} logical volume(s)",
+            Self::PlanStepMkfs => "Create {filesystem_type} filesystem for '{target}'",
+            Self::PlanStepOsConfig => "Configure the target OS",
+            Self::PlanStepPartition => "Partition disk '{disk}' with {count} partition(s)",
+            Self::PlanStepPostConfigureScripts => "Run {count} post-configure script(s)",
+            Self::PlanStepPostProvisionScripts => "Run {count} post-provision script(s)",
+            Self::PlanStepPreServicingScripts => "Run {count} pre-servicing script(s)",
+            Self::PlanStepRaid => "Create {count} software RAID array(s)",
+            Self::PlanStepVerity => "Set up {count} verity device(s)",
+            Self::PruneActionKept => "kept",
+            Self::PruneActionPruned => "pruned",
+            Self::PruneActionWouldPrune => "would prune",
+            Self::TableAction => "ACTION",
+            Self::TableError => "ERROR",
+            Self::TableExtensions => "EXTENSIONS",
+            Self::TableHierarchy => "HIERARCHY",
+            Self::TableHost => "HOST",
+            Self::TableId => "ID",
+            Self::TableKind => "KIND",
+            Self::TableName => "NAME",
+            Self::TablePath => "PATH",
+            Self::TableResult => "RESULT",
+            Self::TableScope => "SCOPE",
+            Self::TableState => "STATE",
+            Self::TableVersion => "VERSION",
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum MessageCatalogError {
+    #[error("Failed to parse message catalog: {0}")]
+    Parse(String),
+
+    #[error("Message '{key}' uses unknown parameter '{parameter}'")]
+    UnknownParameter { key: String, parameter: String },
+}
+
+/// Set of message templates overriding the English ones.
+///
+/// Messages missing from the catalog fall back to English.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MessageCatalog {
+    templates: BTreeMap<MessageKey, String>,
+}
+
+impl MessageCatalog {
+    /// Parses a catalog from a YAML mapping of message keys to templates, e.g.
+    /// `command-failed: "Échec de la commande '{command}'"`.
+    ///
+    /// Templates may only use the parameters of the corresponding English template.
+    pub fn from_yaml(contents: &str) -> Result<Self, MessageCatalogError> {
+        let templates: BTreeMap<MessageKey, String> = serde_yaml::from_str(contents)
+            .map_err(|e| MessageCatalogError::Parse(e.to_string()))?;
+
+        for (key, template) in &templates {
+            let known = parameters(key.english());
+            if let Some(parameter) = parameters(template)
+                .into_iter()
+                .find(|parameter| !known.contains(parameter))
+            {
+                return Err(MessageCatalogError::UnknownParameter {
+                    key: <&str>::from(key).into(),
+                    parameter: parameter.into(),
+                });
+            }
+        }
+
+        Ok(Self { templates })
+    }
+
+    /// Renders a message, substituting the given parameters.
+    pub fn render(&self, key: MessageKey, params: &[(&str, &str)]) -> String {
+        let template = self
+            .templates
+            .get(&key)
+            .map(String::as_str)
+            .unwrap_or(key.english());
+        substitute(template, params)
+    }
+}
+
+/// Installs the catalog used by [`render`] for the rest of the process. Returns the catalog back if
+/// one was already installed.
+pub fn install(catalog: MessageCatalog) -> Result<(), MessageCatalog> {
+    CATALOG.set(catalog)
+}
+
+/// Renders a message using the installed catalog, or in English if none was installed.
+pub fn render(key: MessageKey, params: &[(&str, &str)]) -> String {
+    match CATALOG.get() {
+        Some(catalog) => catalog.render(key, params),
+        None => english(key, params),
+    }
+}
+
+/// Renders a message in English, regardless of the installed catalog. Used for logs.
+pub fn english(key: MessageKey, params: &[(&str, &str)]) -> String {
+    substitute(key.english(), params)
+}
+
+/// Replaces every `{name}` in the template with the value of the parameter `name`. Unknown
+/// parameters are left untouched.
+fn substitute(template: &str, params: &[(&str, &str)]) -> String {
+    params
+        .iter()
+        .fold(template.to_string(), |message, (name, value)| {
+            message.replace(&format!("{{{name}}}"), value)
+        })
+}
+
+/// Returns the names of the parameters used in a template.
+fn parameters(template: &str) -> BTreeSet<&str> {
+    template
+        .split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_render() {
+        let catalog = MessageCatalog::default();
+        assert_eq!(
+            catalog.render(MessageKey::CommandFailed, &[("command", "install")]),
+            "Failed to execute 'install' command"
+        );
+
+        let catalog =
+            MessageCatalog::from_yaml("command-failed: \"Échec de la commande « {command} »\"")
+                .unwrap();
+        assert_eq!(
+            catalog.render(MessageKey::CommandFailed, &[("command", "install")]),
+            "Échec de la commande « install »"
+        );
+        // Missing messages fall back to English
+        assert_eq!(
+            catalog.render(MessageKey::ErrorSummaryInternal, &[]),
+            "Trident failed due to an internal error"
+        );
+    }
+
+    #[test]
+    fn test_from_yaml_errors() {
+        assert_eq!(
+            MessageCatalog::from_yaml("command-failed: \"{cmd} failed\""),
+            Err(MessageCatalogError::UnknownParameter {
+                key: "command-failed".into(),
+                parameter: "cmd".into()
+            })
+        );
+        assert!(matches!(
+            MessageCatalog::from_yaml("no-such-message: \"text\""),
+            Err(MessageCatalogError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_english_templates_parameters() {
+        // Parameters must be plain identifiers, so that they can be validated in catalogs
+        for key in MessageKey::iter() {
+            for parameter in parameters(key.english()) {
+                assert!(
+                    !parameter.is_empty()
+                        && parameter
+                            .chars()
+                            .all(|c| c.is_ascii_lowercase() || c == '_'),
+                    "Invalid parameter '{parameter}' in message '{key:?}'"
+                );
+            }
+        }
+    }
+}
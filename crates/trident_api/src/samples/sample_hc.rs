@@ -12,11 +12,11 @@ use crate::{
     config::{
         host::os::{KernelCommandLine, Selinux, SelinuxMode},
         AbUpdate, AbVolumePair, AdditionalFile, Check, Disk, EncryptedVolume, Encryption,
-        FileSystem, FileSystemSource, Health, HostConfiguration, ImageSha384, MountOptions,
-        MountPoint, NewFileSystemType, Os, OsImage, Partition, PartitionTableType, PartitionType,
-        Raid, RaidLevel, Script, ScriptSource, Scripts, Services, ServicingTypeSelection,
-        SoftwareRaidArray, SshMode, Storage, Swap, SystemdCheck, UefiFallbackMode, User,
-        VerityDevice,
+        FileSystem, FileSystemSource, Health, HostConfiguration, ImageSha384, Migration,
+        MountOptions, MountPoint, NewFileSystemType, Os, OsImage, Partition, PartitionTableType,
+        PartitionType, Raid, RaidLevel, Script, ScriptSource, Scripts, Services,
+        ServicingTypeSelection, SoftwareRaidArray, SshMode, Storage, Swap, SysextPolicy,
+        SystemdCheck, UefiFallbackMode, User, VerityDevice,
     },
     constants::{self, MOUNT_OPTION_READ_ONLY, ROOT_MOUNT_POINT_PATH},
 };
@@ -199,11 +199,19 @@ pub fn sample_host_configuration(name: &str) -> Result<(&'static str, HostConfig
                         SystemdCheck {
                             name: "systemd-networkd".into(),
                             systemd_services: vec!["systemd-networkd".into()],
+                            system_running: false,
                             timeout_seconds: 10,
                             run_on: vec![ServicingTypeSelection::CleanInstall, ServicingTypeSelection::AbUpdate],
+                            ..Default::default()
                         }
                     )
                 ],
+                environment_variables: HashMap::new(),
+                timeout_seconds: None,
+                burn_in: None,
+                boot_complete: None,
+                monitor: None,
+                rollback_policy: None,
             },
             ..Default::default()
             }
@@ -414,11 +422,19 @@ pub fn sample_host_configuration(name: &str) -> Result<(&'static str, HostConfig
                         SystemdCheck {
                             name: "systemd-networkd".into(),
                             systemd_services: vec!["systemd-networkd".into()],
+                            system_running: false,
                             timeout_seconds: 10,
                             run_on: vec![ServicingTypeSelection::CleanInstall, ServicingTypeSelection::AbUpdate],
+                            ..Default::default()
                         }
                     )
                 ],
+                environment_variables: HashMap::new(),
+                timeout_seconds: None,
+                burn_in: None,
+                boot_complete: None,
+                monitor: None,
+                rollback_policy: None,
             },
             ..Default::default()
             }
@@ -961,7 +977,8 @@ pub fn sample_host_configuration(name: &str) -> Result<(&'static str, HostConfig
                     Swap {
                         device_id: "swap2".into(),
                     },
-                ]
+                ],
+                ..Default::default()
             },
             os: Os {
                 users: vec![User {
@@ -1107,6 +1124,7 @@ pub fn sample_host_configuration(name: &str) -> Result<(&'static str, HostConfig
                 os: Os {
                     selinux: Selinux {
                         mode: Some(SelinuxMode::Permissive),
+                        ..Default::default()
                     },
                     users: vec![User {
                         name: "my-custom-user".into(),
@@ -1135,8 +1153,16 @@ pub fn sample_host_configuration(name: &str) -> Result<(&'static str, HostConfig
                         )])),
                         ..Default::default()
                     }),
+                    pinned_interfaces: vec![],
+                    network: None,
                     additional_files: vec![],
+                    packages: vec![],
+                    package_repository: None,
+                    container_images: vec![],
+                    cloud_init: None,
                     hostname: None,
+                    identity: None,
+                    time: None,
                     modules: vec![],
                     services: Services {
                         enable: vec![],
@@ -1144,10 +1170,14 @@ pub fn sample_host_configuration(name: &str) -> Result<(&'static str, HostConfig
                     },
                     kernel_command_line: KernelCommandLine {
                         extra_command_line: vec![],
+                        removed_command_line: vec![],
                     },
                     sysexts: vec![],
                     confexts: vec![],
+                    sysext_policy: SysextPolicy::default(),
+                    migration: Migration::default(),
                     uefi_fallback: None,
+                    boot_menu: None,
                 },
                 scripts: Scripts {
                     post_configure: vec![Script {
@@ -1255,6 +1285,7 @@ pub fn sample_host_configuration(name: &str) -> Result<(&'static str, HostConfig
                 os: Os {
                     selinux: Selinux {
                         mode: Some(SelinuxMode::Permissive),
+                        ..Default::default()
                     },
                     users: vec![User {
                         name: "my-custom-user".into(),
@@ -1283,8 +1314,16 @@ pub fn sample_host_configuration(name: &str) -> Result<(&'static str, HostConfig
                         )])),
                         ..Default::default()
                     }),
+                    pinned_interfaces: vec![],
+                    network: None,
                     additional_files: vec![],
+                    packages: vec![],
+                    package_repository: None,
+                    container_images: vec![],
+                    cloud_init: None,
                     hostname: None,
+                    identity: None,
+                    time: None,
                     modules: vec![],
                     services: Services {
                         enable: vec![],
@@ -1292,10 +1331,14 @@ pub fn sample_host_configuration(name: &str) -> Result<(&'static str, HostConfig
                     },
                     kernel_command_line: KernelCommandLine {
                         extra_command_line: vec![],
+                        removed_command_line: vec![],
                     },
                     sysexts: vec![],
                     confexts: vec![],
+                    sysext_policy: SysextPolicy::default(),
+                    migration: Migration::default(),
                     uefi_fallback: None,
+                    boot_menu: None,
                 },
                 scripts: Scripts {
                     post_configure: vec![Script {
@@ -1422,6 +1465,7 @@ pub fn sample_host_configuration(name: &str) -> Result<(&'static str, HostConfig
                 os: Os {
                     selinux: Selinux {
                         mode: Some(SelinuxMode::Permissive),
+                        ..Default::default()
                     },
                     users: vec![User {
                         name: "my-custom-user".into(),
@@ -1450,8 +1494,16 @@ pub fn sample_host_configuration(name: &str) -> Result<(&'static str, HostConfig
                         )])),
                         ..Default::default()
                     }),
+                    pinned_interfaces: vec![],
+                    network: None,
                     additional_files: vec![],
+                    packages: vec![],
+                    package_repository: None,
+                    container_images: vec![],
+                    cloud_init: None,
                     hostname: None,
+                    identity: None,
+                    time: None,
                     modules: vec![],
                     services: Services {
                         enable: vec![],
@@ -1459,10 +1511,14 @@ pub fn sample_host_configuration(name: &str) -> Result<(&'static str, HostConfig
                     },
                     kernel_command_line: KernelCommandLine {
                         extra_command_line: vec![],
+                        removed_command_line: vec![],
                     },
                     sysexts: vec![],
                     confexts: vec![],
+                    sysext_policy: SysextPolicy::default(),
+                    migration: Migration::default(),
                     uefi_fallback: None,
+                    boot_menu: None,
                 },
                 scripts: Scripts {
                     post_configure: vec![Script {
@@ -5,6 +5,7 @@ use std::{
 };
 
 use anyhow::{bail, Context};
+use chrono::{DateTime, Utc};
 use log::{debug, info};
 use serde::{Deserialize, Serialize};
 use serde_yaml::Value;
@@ -58,6 +59,269 @@ pub struct HostStatus {
     /// Whether this HostStatus is stored on the management OS.
     #[serde(default, skip_serializing_if = "is_default")]
     pub is_management_os: bool,
+
+    /// When the Host Configuration was loaded from a Git repository, the SHA of the commit it was
+    /// taken from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_commit: Option<String>,
+
+    /// Where each artifact deployed on the host came from, and how its contents were verified.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<ArtifactProvenance>,
+
+    /// Boot ID of the running kernel when Trident initiated the last reboot. If Trident later finds
+    /// that the host is still running the same boot, the reboot never happened.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reboot_boot_id: Option<String>,
+
+    /// Identity of the host in the fleet, when `trident.registrationUrl` is configured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<HostIdentity>,
+
+    /// Files under /etc that were modified locally on the running OS before the last A/B update
+    /// was staged, when `trident.captureConfigDrift` is set. These changes may need to be migrated
+    /// to the updated OS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_drift: Option<BTreeMap<PathBuf, ConfigFileChange>>,
+
+    /// Key-value annotations attached by orchestrators and hook scripts with `trident annotate`,
+    /// e.g. `ticket: CHG-1234`. Trident keeps them across servicing operations, but does not
+    /// interpret them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
+
+    /// Extension images merged on the running OS, as found after the last servicing operation
+    /// completed, including the images that are not part of the Host Configuration.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<MergedExtension>,
+
+    /// Outcome of each health check of the last commit of a target OS, in the order in which the
+    /// checks were configured. When the checks failed, this tells which of them caused the
+    /// rollback.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub health_checks: Vec<HealthCheckOutcome>,
+
+    /// Outcome of the health checks in each of the last boots in which they ran, oldest first,
+    /// so that a host that flapped between healthy and unhealthy can be diagnosed after the fact.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub health_check_history: Vec<HealthCheckBoot>,
+
+    /// `VERSION_ID` of the OS that was running when the last A/B update was staged, i.e. the OS
+    /// the update rolls back to. Passed to health check scripts as `PREVIOUS_OS_VERSION`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_os_version: Option<String>,
+
+    /// Number of boots of the target OS of the A/B update in progress in which the health checks
+    /// failed. Trident reboots into the target OS until the `maxFailedBoots` of the rollback
+    /// policy is reached.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub failed_boots: u32,
+
+    /// Kernel command line the OS booted with, as read from `/proc/cmdline` when the last clean
+    /// install or A/B update was committed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kernel_command_line: Option<String>,
+
+    /// Root hash of the verity device protecting the root or usr filesystem of the target OS, as
+    /// found in the OS image. When the target OS boots, Trident checks that the verity device was
+    /// opened with this root hash before committing it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verity_root_hash: Option<String>,
+}
+
+/// Outcome of a health check run before committing a target OS.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct HealthCheckOutcome {
+    /// Name of the check.
+    pub name: String,
+
+    /// Kind of the check.
+    pub kind: HealthCheckKind,
+
+    /// Whether the check passed, or how its failure was handled.
+    pub verdict: HealthCheckVerdict,
+
+    /// Time the check took, including its retries, in milliseconds.
+    pub duration_millis: u64,
+
+    /// Exit code of the last run of a script check.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+
+    /// End of the combined stdout and stderr of the last run of a script check.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+
+    /// Why the check failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Outcome of the health checks in a boot of the host.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct HealthCheckBoot {
+    /// Boot ID of the kernel, as found in `/proc/sys/kernel/random/boot_id`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub boot_id: Option<String>,
+
+    /// Time of the last run of the checks in the boot.
+    pub last_run: DateTime<Utc>,
+
+    /// Number of runs of the checks in the boot, e.g. during the grace window of health
+    /// monitoring.
+    pub runs: u32,
+
+    /// Number of runs of the checks in the boot in which any check failed.
+    pub failed_runs: u32,
+
+    /// Outcome of each check in the last run of the checks in the boot.
+    pub checks: Vec<HealthCheckOutcome>,
+}
+
+/// Kind of a health check.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum HealthCheckKind {
+    Script,
+    SystemdCheck,
+    HttpCheck,
+    TcpCheck,
+    KernelCheck,
+    DiskUsageCheck,
+    ContainerCheck,
+    NetworkCheck,
+    DiskHealthCheck,
+    TimeSyncCheck,
+    Extensions,
+    BootComplete,
+}
+
+/// Verdict of a health check.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum HealthCheckVerdict {
+    /// The check succeeded.
+    Pass,
+
+    /// The check failed, but its severity is `warn`, so it did not prevent the commit.
+    Warn,
+
+    /// The check failed and prevented the commit.
+    Fail,
+}
+
+/// Extension image merged on the running OS.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct MergedExtension {
+    /// Kind of the image, either `sysext` or `confext`.
+    pub kind: ArtifactKind,
+
+    /// Name of the image, i.e. its file name without `.raw`.
+    pub name: String,
+
+    /// `SYSEXT_ID` or `CONFEXT_ID` of the extension-release file of the image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    /// `SYSEXT_VERSION_ID` or `CONFEXT_VERSION_ID` of the extension-release file of the image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+
+    /// Digest of the image, in the form `<algorithm>:<hex digest>`, when the image is part of
+    /// the Host Configuration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+
+    /// Path of the image on the running OS.
+    pub path: PathBuf,
+
+    /// When Trident first found the image merged.
+    pub merged_at: DateTime<Utc>,
+}
+
+/// Change made to a file under /etc, relative to the pristine copy shipped in the OS image.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConfigFileChange {
+    /// The file does not exist in the OS image.
+    Added,
+
+    /// The contents or metadata of the file differ from the OS image.
+    Modified,
+
+    /// The file exists in the OS image, but was removed.
+    Removed,
+}
+
+/// Stable identity of the host, generated the first time the target OS boots and kept across
+/// updates.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct HostIdentity {
+    /// Unique identifier of the host.
+    pub id: Uuid,
+
+    /// Public key of the host, in PEM format. The private key is stored next to the datastore.
+    pub public_key: String,
+
+    /// Whether the host was successfully registered with the fleet endpoint.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub registered: bool,
+}
+
+/// Provenance of an artifact deployed on the host, so that the exact bytes running on the host can
+/// be traced back to their source.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ArtifactProvenance {
+    /// Kind of artifact.
+    pub kind: ArtifactKind,
+
+    /// URL of the artifact, as given in the Host Configuration. For artifacts embedded in the OS
+    /// image, such as UKIs, this is the URL of the OS image.
+    pub url: String,
+
+    /// URL the artifact was actually downloaded from, when it differs from `url`, e.g. the
+    /// registry blob that an `oci://` URL resolved to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_url: Option<String>,
+
+    /// Digests of the artifact, in the form `<algorithm>:<hex digest>`.
+    pub digests: Vec<String>,
+
+    /// How the contents of the artifact were verified.
+    pub verification: VerificationMethod,
+
+    /// Path of the artifact in the target OS, if it is deployed as a file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+/// Kind of artifact deployed on the host.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub enum ArtifactKind {
+    OsImage,
+    Uki,
+    Sysext,
+    Confext,
+}
+
+/// Method used to verify the contents of an artifact.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub enum VerificationMethod {
+    /// The digests were checked against the ones in the Host Configuration.
+    HostConfigurationDigest,
+    /// The digest was checked against the one in the metadata of the OS image, which was itself
+    /// checked against the Host Configuration.
+    OsImageMetadata,
+    /// The digests were computed while deploying the artifact, but not checked against any
+    /// expected value.
+    Unverified,
 }
 
 /// Servicing type is the type of servicing that the Trident agent is executing on the host.
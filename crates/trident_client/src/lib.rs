@@ -0,0 +1,153 @@
+//! Client for the Trident gRPC API.
+//!
+//! This crate lets orchestration services written in Rust drive Trident over its gRPC API, using
+//! the same Host Configuration and Host Status types as Trident itself. The `trident_api` types
+//! are re-exported, so that callers do not need to depend on `trident_api` directly.
+//!
+//! The gRPC server is only available in Trident builds with the `grpc-dangerous` feature, and
+//! listens on the port configured in `grpc.listenPort` of the local Trident configuration.
+
+use tonic::{transport::Channel, Streaming};
+
+pub use trident_api::{
+    config::{self, HostConfiguration, Operation, Operations},
+    constants, error,
+    status::{self, HostStatus},
+    BlockDeviceId,
+};
+
+mod protobufs {
+    tonic::include_proto!("trident");
+}
+
+use protobufs::{
+    host_management_client::HostManagementClient, ConfirmNetworkRequest, GetHostStatusRequest,
+    HostStatusState, HostUpdateRequest,
+};
+
+/// Default port on which Trident listens for gRPC requests.
+pub const DEFAULT_PORT: u16 = 50051;
+
+/// Errors returned by the Trident client.
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("Failed to connect to Trident")]
+    Connect(#[from] tonic::transport::Error),
+
+    #[error("At least one operation must be allowed")]
+    NoAllowedOperations,
+
+    #[error("Failed to parse Host Status sent by Trident")]
+    ParseHostStatus(#[source] serde_yaml::Error),
+
+    #[error("Trident returned an error: {0}")]
+    Rpc(#[from] tonic::Status),
+
+    #[error("Failed to serialize Host Configuration")]
+    SerializeHostConfiguration(#[source] serde_yaml::Error),
+}
+
+/// Connection to the gRPC API of a Trident instance.
+pub struct TridentClient {
+    inner: HostManagementClient<Channel>,
+}
+
+impl TridentClient {
+    /// Connects to Trident at the given endpoint, e.g. `http://10.0.0.2:50051`.
+    pub async fn connect(endpoint: impl Into<String>) -> Result<Self, ClientError> {
+        Ok(Self {
+            inner: HostManagementClient::connect(endpoint.into()).await?,
+        })
+    }
+
+    /// Asks Trident to apply the given Host Configuration, performing only the allowed operations.
+    ///
+    /// Returns a stream of the Host Status reported by Trident as servicing progresses.
+    pub async fn update_host(
+        &mut self,
+        host_config: &HostConfiguration,
+        allowed_operations: &Operations,
+    ) -> Result<HostStatusStream, ClientError> {
+        // Trident treats an empty list of operations as allowing all of them.
+        if allowed_operations.0.is_empty() {
+            return Err(ClientError::NoAllowedOperations);
+        }
+
+        let request = HostUpdateRequest {
+            host_configuration: serde_yaml::to_string(host_config)
+                .map_err(ClientError::SerializeHostConfiguration)?,
+            allowed_operations: encode_operations(allowed_operations),
+        };
+
+        Ok(HostStatusStream {
+            inner: self.inner.update_host(request).await?.into_inner(),
+        })
+    }
+
+    /// Confirms a hot-applied network configuration change, so that Trident does not revert it.
+    ///
+    /// Must be called over the new network configuration, before the confirmation timeout given
+    /// to `trident update --only network --confirm-timeout` expires.
+    pub async fn confirm_network(&mut self) -> Result<(), ClientError> {
+        self.inner.confirm_network(ConfirmNetworkRequest {}).await?;
+        Ok(())
+    }
+
+    /// Returns the Host Status of the host, as it was when Trident started listening for
+    /// requests.
+    pub async fn get_host_status(&mut self) -> Result<HostStatus, ClientError> {
+        let state = self
+            .inner
+            .get_host_status(GetHostStatusRequest {})
+            .await?
+            .into_inner();
+        serde_yaml::from_str(&state.status).map_err(ClientError::ParseHostStatus)
+    }
+}
+
+/// Stream of Host Status updates sent by Trident while servicing a request.
+pub struct HostStatusStream {
+    inner: Streaming<HostStatusState>,
+}
+
+impl HostStatusStream {
+    /// Waits for the next Host Status update. Returns `None` once Trident has closed the stream.
+    pub async fn next(&mut self) -> Option<Result<HostStatus, ClientError>> {
+        match self.inner.message().await {
+            Ok(Some(state)) => {
+                Some(serde_yaml::from_str(&state.status).map_err(ClientError::ParseHostStatus))
+            }
+            Ok(None) => None,
+            Err(status) => Some(Err(status.into())),
+        }
+    }
+}
+
+/// Encodes operations in the comma-separated format of the `--allowed-operations` command line
+/// option, which is also what the gRPC API expects.
+fn encode_operations(operations: &Operations) -> String {
+    let mut operations = operations.0.iter().collect::<Vec<_>>();
+    operations.sort();
+    operations
+        .into_iter()
+        .map(|operation| match operation {
+            Operation::Stage => "stage",
+            Operation::Finalize => "finalize",
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_operations() {
+        assert_eq!(encode_operations(&Operations::all()), "stage,finalize");
+
+        let mut operations = Operations::empty();
+        operations.0.insert(Operation::Finalize);
+        assert_eq!(encode_operations(&operations), "finalize");
+    }
+}